@@ -1,15 +1,28 @@
 use std::sync::Arc;
 
-use common_database::create_db_pool;
-use common_restix::create_reqwest_client;
+use common_database::{create_database, Database};
+use common_di::singleton;
+use common_events::EventBus;
+use domain_buildings::Buildings;
 use domain_schedule::{
+    changes::repository::ScheduleChangeRepository,
     id::repository::ScheduleIdRepository,
-    mpei_api::MpeiApi,
+    mpei_api::MpeiUpstream,
+    overrides::repository::ScheduleOverrideRepository,
+    popularity::repository::SchedulePopularityRepository,
+    quality::repository::ScheduleQualityRepository,
     schedule::repository::ScheduleRepository,
     schedule_shift::repository::ScheduleShiftRepository,
-    search::repository::ScheduleSearchRepository,
+    search::{create_search_backend, repository::ScheduleSearchRepository},
+    status::repository::ServiceStatusRepository,
+    upstream_audit::repository::UpstreamAuditRepository,
     usecases::{
-        GetScheduleIdUseCase, GetScheduleUseCase, InitDomainScheduleUseCase, SearchScheduleUseCase,
+        AutocompleteScheduleUseCase, DiffScheduleUseCase, FindFreeRoomsUseCase,
+        GetScheduleChangesUseCase, GetScheduleIdUseCase, GetScheduleLatencyUseCase,
+        GetScheduleQualityUseCase, GetScheduleUseCase, GetServiceStatusUseCase,
+        GetShiftRulesUseCase, GetTrendingSchedulesUseCase, InitDomainScheduleUseCase,
+        PrefetchScheduleUseCase, ReloadConfigUseCase, ScheduleEventsUseCase, SearchScheduleUseCase,
+        SetScheduleOverridesUseCase, UpstreamAuditUseCase, VerifyScheduleUseCase,
     },
 };
 use domain_schedule_cooldown::ScheduleCooldownRepository;
@@ -17,45 +30,143 @@ use feature_schedule::v1::FeatureSchedule;
 
 use crate::AppSchedule;
 
+singleton! {
+    fn database() -> Database {
+        create_database().expect("DI error while creating database")
+    }
+
+    fn event_bus() -> EventBus {
+        EventBus::default()
+    }
+
+    fn upstream_audit_repository() -> UpstreamAuditRepository {
+        UpstreamAuditRepository::new(database())
+    }
+
+    fn mpei_upstream() -> MpeiUpstream {
+        MpeiUpstream::new(upstream_audit_repository())
+    }
+
+    fn schedule_id_repository() -> ScheduleIdRepository {
+        ScheduleIdRepository::new(mpei_upstream().as_ref().clone())
+    }
+
+    fn schedule_repository() -> ScheduleRepository {
+        ScheduleRepository::new(mpei_upstream().as_ref().clone())
+    }
+
+    fn schedule_shift_repository() -> ScheduleShiftRepository {
+        ScheduleShiftRepository::default()
+    }
+
+    fn schedule_search_repository() -> ScheduleSearchRepository {
+        ScheduleSearchRepository::new(
+            database(),
+            mpei_upstream().as_ref().clone(),
+            create_search_backend(database()),
+        )
+    }
+
+    fn schedule_popularity_repository() -> SchedulePopularityRepository {
+        SchedulePopularityRepository::new(database())
+    }
+
+    fn schedule_quality_repository() -> ScheduleQualityRepository {
+        ScheduleQualityRepository::new(database())
+    }
+
+    fn schedule_override_repository() -> ScheduleOverrideRepository {
+        ScheduleOverrideRepository::new(database())
+    }
+
+    fn schedule_change_repository() -> ScheduleChangeRepository {
+        ScheduleChangeRepository::new(database())
+    }
+
+    fn schedule_cooldown_repository() -> ScheduleCooldownRepository {
+        ScheduleCooldownRepository::default()
+    }
+
+    fn get_schedule_use_case() -> GetScheduleUseCase {
+        GetScheduleUseCase::new(
+            schedule_id_repository(),
+            schedule_repository(),
+            schedule_shift_repository(),
+            schedule_cooldown_repository(),
+            schedule_quality_repository(),
+            schedule_override_repository(),
+            event_bus(),
+        )
+    }
+}
+
 pub struct AppComponent;
 
 impl AppComponent {
     pub fn create_app() -> AppSchedule {
-        let db_pool = Arc::new(create_db_pool().expect("DI error while creating db pool"));
-        let api = MpeiApi::builder()
-            .client(create_reqwest_client())
-            .build()
-            .expect("DI error while creating MpeiApi");
-
-        // Repositories
-        let schedule_id_repository = Arc::new(ScheduleIdRepository::new(api.to_owned()));
-        let schedule_repository = Arc::new(ScheduleRepository::new(api.to_owned()));
-        let schedule_shift_repository = Arc::new(ScheduleShiftRepository::default());
-        let schedule_search_repository = Arc::new(ScheduleSearchRepository::new(db_pool, api));
-
-        // Use-cases
-        let get_schedule_id_use_case =
-            Arc::new(GetScheduleIdUseCase::new(schedule_id_repository.clone()));
-        let get_schedule_use_case = Arc::new(GetScheduleUseCase::new(
-            schedule_id_repository,
-            schedule_repository,
-            schedule_shift_repository,
-            Arc::new(ScheduleCooldownRepository::default()),
-        ));
-        let search_schedule_use_case = Arc::new(SearchScheduleUseCase::new(
-            schedule_search_repository.clone(),
-            Arc::new(ScheduleCooldownRepository::default()),
-        ));
-        let init_domain_schedule_use_case =
-            InitDomainScheduleUseCase::new(schedule_search_repository);
-
         AppSchedule {
             feature_schedule: FeatureSchedule::new(
-                get_schedule_id_use_case,
-                get_schedule_use_case,
-                search_schedule_use_case,
+                Arc::new(GetScheduleIdUseCase::new(schedule_id_repository())),
+                get_schedule_use_case(),
+                Arc::new(SearchScheduleUseCase::new(
+                    schedule_search_repository(),
+                    schedule_cooldown_repository(),
+                )),
+                Arc::new(Buildings::default()),
+                Arc::new(GetShiftRulesUseCase::new(schedule_shift_repository())),
+                Arc::new(AutocompleteScheduleUseCase::new(
+                    schedule_search_repository(),
+                )),
+            ),
+            init_domain_schedule_use_case: InitDomainScheduleUseCase::new(
+                schedule_search_repository(),
+                schedule_popularity_repository(),
+                schedule_quality_repository(),
+                schedule_override_repository(),
+                schedule_change_repository(),
+                upstream_audit_repository(),
+            ),
+            schedule_events_use_case: ScheduleEventsUseCase::new(
+                event_bus(),
+                schedule_popularity_repository(),
+                schedule_change_repository(),
+            ),
+            prefetch_schedule_use_case: PrefetchScheduleUseCase::new(
+                get_schedule_use_case(),
+                schedule_popularity_repository(),
+                schedule_cooldown_repository(),
+            ),
+            get_schedule_quality_use_case: GetScheduleQualityUseCase::new(
+                schedule_quality_repository(),
+            ),
+            set_schedule_overrides_use_case: SetScheduleOverridesUseCase::new(
+                schedule_override_repository(),
+            ),
+            verify_schedule_use_case: VerifyScheduleUseCase::new(get_schedule_use_case()),
+            get_schedule_changes_use_case: GetScheduleChangesUseCase::new(
+                schedule_change_repository(),
+            ),
+            diff_schedule_use_case: DiffScheduleUseCase::new(get_schedule_use_case()),
+            find_free_rooms_use_case: FindFreeRoomsUseCase::new(
+                schedule_search_repository(),
+                get_schedule_use_case(),
+            ),
+            get_trending_schedules_use_case: GetTrendingSchedulesUseCase::new(
+                schedule_popularity_repository(),
+            ),
+            get_schedule_latency_use_case: GetScheduleLatencyUseCase::new(get_schedule_use_case()),
+            upstream_audit_use_case: UpstreamAuditUseCase::new(upstream_audit_repository()),
+            get_service_status_use_case: GetServiceStatusUseCase::new(
+                Arc::new(ServiceStatusRepository::new(database())),
+                schedule_cooldown_repository(),
+            ),
+            reload_config_use_case: ReloadConfigUseCase::new(
+                schedule_id_repository(),
+                schedule_repository(),
+                schedule_search_repository(),
+                schedule_shift_repository(),
             ),
-            init_domain_schedule_use_case,
+            readiness: Default::default(),
         }
     }
 }
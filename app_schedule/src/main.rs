@@ -1,16 +1,44 @@
 mod di;
 mod routing;
 
+use std::{sync::Arc, time::Duration};
+
 use actix_web::{middleware, web::Data, App, HttpServer};
 use anyhow::Context;
-use common_actix::{define_app_error, get_address};
+use common_actix::{
+    define_app_error, get_address, get_shutdown_timeout, run_until_shutdown, ContentNegotiation,
+    CorsConfig, Readiness,
+};
+use common_logging::{access_logger, init_logger, RequestId};
+use common_metrics::HttpMetrics;
 use di::AppComponent;
-use domain_schedule::usecases::InitDomainScheduleUseCase;
+use domain_schedule::usecases::{
+    DiffScheduleUseCase, FindFreeRoomsUseCase, GetScheduleChangesUseCase,
+    GetScheduleLatencyUseCase, GetScheduleQualityUseCase, GetServiceStatusUseCase,
+    GetTrendingSchedulesUseCase, InitDomainScheduleUseCase, PrefetchScheduleUseCase,
+    ReloadConfigUseCase, ScheduleEventsUseCase, SetScheduleOverridesUseCase, UpstreamAuditUseCase,
+    VerifyScheduleUseCase,
+};
 use feature_schedule::v1::FeatureSchedule;
+use log::{error, info};
 
 pub struct AppSchedule {
     feature_schedule: FeatureSchedule,
     init_domain_schedule_use_case: InitDomainScheduleUseCase,
+    schedule_events_use_case: ScheduleEventsUseCase,
+    prefetch_schedule_use_case: PrefetchScheduleUseCase,
+    get_schedule_quality_use_case: GetScheduleQualityUseCase,
+    set_schedule_overrides_use_case: SetScheduleOverridesUseCase,
+    verify_schedule_use_case: VerifyScheduleUseCase,
+    get_schedule_changes_use_case: GetScheduleChangesUseCase,
+    diff_schedule_use_case: DiffScheduleUseCase,
+    find_free_rooms_use_case: FindFreeRoomsUseCase,
+    get_trending_schedules_use_case: GetTrendingSchedulesUseCase,
+    get_schedule_latency_use_case: GetScheduleLatencyUseCase,
+    upstream_audit_use_case: UpstreamAuditUseCase,
+    get_service_status_use_case: GetServiceStatusUseCase,
+    reload_config_use_case: ReloadConfigUseCase,
+    readiness: Arc<Readiness>,
 }
 
 define_app_error!(AppScheduleError);
@@ -19,25 +47,69 @@ define_app_error!(AppScheduleError);
 async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "debug");
     std::env::set_var("RUST_BACKTRACE", "1");
-    env_logger::init();
+    init_logger();
     let app = Data::new(AppComponent::create_app());
+    let cors_config = CorsConfig::from_env().unwrap();
+    let server_app = app.clone();
 
-    // we shall panic if init fails
-    init_app_components(&app).await.unwrap();
-
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
+            .wrap(ContentNegotiation)
+            .wrap(HttpMetrics)
+            .wrap(RequestId)
+            // outside of the request id middleware, so the access log sees its header
+            .wrap(access_logger())
             .wrap(middleware::Compress::default())
-            .app_data(app.clone())
+            // outermost, so preflight requests are answered before routing
+            .wrap(middleware::Condition::new(
+                cors_config.is_enabled(),
+                cors_config.to_cors(),
+            ))
+            .app_data(server_app.clone())
             .service(routing::health)
+            .service(routing::readiness)
+            .service(routing::metrics)
+            .service(routing::get_status)
+            .service(routing::get_loglevel_v1)
+            .service(routing::put_loglevel_v1)
+            .service(routing::reload_config_v1)
             .service(routing::get_id_v1)
             .service(routing::get_schedule_v1)
+            .service(routing::get_schedules_batch_v1)
+            .service(routing::get_session_schedule_v1)
+            .service(routing::get_schedule_changes_v1)
+            .service(routing::get_schedule_diff_v1)
+            .service(routing::get_schedule_cache_diff_v1)
             .service(routing::search_schedule_v1)
+            .service(routing::autocomplete_schedule_v1)
+            .service(routing::get_buildings_v1)
+            .service(routing::get_free_rooms_v1)
+            .service(routing::get_trending_v1)
+            .service(routing::get_shift_rules_v1)
+            .service(routing::get_quality_v1)
+            .service(routing::get_latency_v1)
+            .service(routing::get_upstream_audit_v1)
+            .service(routing::put_schedule_override_v1)
+            .service(routing::verify_schedule_v1)
     })
     .bind(get_address())?
-    .run()
-    .await
+    .disable_signals()
+    .shutdown_timeout(get_shutdown_timeout())
+    .run();
+
+    // the server answers the health probe during the init, but it is not ready until the init is complete
+    let init_app = app.clone();
+    actix_web::rt::spawn(async move {
+        // we shall exit if init fails
+        if let Err(e) = init_app_components(&init_app).await {
+            error!("{e:#}");
+            std::process::exit(1);
+        }
+        start_background_jobs(init_app.clone());
+        init_app.readiness.set_ready(true);
+        info!("App is ready");
+    });
+    run_until_shutdown(server, app.readiness.clone(), shutdown_app_components(&app)).await
 }
 
 async fn init_app_components(app: &AppSchedule) -> anyhow::Result<()> {
@@ -46,3 +118,33 @@ async fn init_app_components(app: &AppSchedule) -> anyhow::Result<()> {
         .await
         .with_context(|| "domain_schedule init error")
 }
+
+/// Save the state kept in memory, after the server has finished the requests in progress
+async fn shutdown_app_components(app: &AppSchedule) {
+    if let Err(e) = app.upstream_audit_use_case.flush().await {
+        error!("Upstream audit saving error: {e:#}");
+    }
+}
+
+fn start_background_jobs(app: Data<AppSchedule>) {
+    app.schedule_events_use_case.start_consumers();
+    let upstream_audit_app = app.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = upstream_audit_app.upstream_audit_use_case.flush().await {
+                error!("Upstream audit saving error: {e:#}");
+            }
+        }
+    });
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(10 * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = app.prefetch_schedule_use_case.prefetch_if_needed().await {
+                error!("Schedule prefetching error: {e}");
+            }
+        }
+    });
+}
@@ -1,12 +1,23 @@
+use std::collections::BTreeMap;
+
 use actix_web::{
     web::{Data, Json, Path, Query},
     HttpRequest, HttpResponse, Responder,
 };
 use anyhow::anyhow;
+use chrono::{Local, NaiveDate, NaiveTime};
+use common_actix::{check_admin_secret, ETagJson, Negotiated};
 use common_errors::errors::CommonError;
+use common_logging::{get_log_filter, set_log_filter};
+use common_sql::Page;
+use domain_buildings::Building;
 use domain_mobile::AppVersion;
 use domain_schedule_models::{
-    ParseScheduleTypeError, Schedule, ScheduleSearchResult, ScheduleType,
+    BatchScheduleResult, DayOverride, DaysRange, FreeRooms, ParseDaysRangeError,
+    ParseScheduleTypeError, Schedule, ScheduleAutocompleteItem, ScheduleCacheDiff,
+    ScheduleChangelog, ScheduleDiff, ScheduleFetchLatency, ScheduleId, ScheduleRef,
+    ScheduleSearchResult, ScheduleType, ScheduleVerification, ServiceStatus, SessionSchedule,
+    ShiftRules, TrendingSchedules, UpstreamCallStats,
 };
 use serde::{Deserialize, Serialize};
 
@@ -19,40 +30,196 @@ async fn health() -> impl Responder {
     HttpResponse::Ok().body("I'm alive :)")
 }
 
+/// Readiness probe method
+/// Returns `200 OK` only after the init (migrations) is complete and until the shutdown starts,
+/// otherwise `503 Service Unavailable`
+#[actix_web::get("v1/readiness")]
+async fn readiness(state: Data<AppSchedule>) -> impl Responder {
+    state.readiness.to_response()
+}
+
+/// Metrics in the Prometheus text format: requests, MPEI latency, cache hits and cooldowns
+#[actix_web::get("metrics")]
+async fn metrics() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(common_metrics::render())
+}
+
+/// Readiness of all components of the service for the public status page,
+/// `200 OK` even if some components are down
+#[actix_web::get("status")]
+async fn get_status(state: Data<AppSchedule>) -> Json<ServiceStatus> {
+    Json(state.get_service_status_use_case.get_status().await)
+}
+
 #[derive(Serialize)]
 struct GetIdResponse {
-    id: i64,
+    id: ScheduleId,
 }
 
 #[actix_web::get("v1/{type}/{name}/id")]
 async fn get_id_v1(
     path: Path<(String, String)>,
     state: Data<AppSchedule>,
-) -> Result<Json<GetIdResponse>, AppScheduleError> {
+) -> Result<Negotiated<GetIdResponse>, AppScheduleError> {
     let (r#type, name) = path.into_inner();
     let r#type = r#type.parse::<ScheduleType>()?;
-    Ok(Json(GetIdResponse {
+    Ok(Negotiated(GetIdResponse {
         id: state.feature_schedule.get_id(name, r#type).await?,
     }))
 }
 
+#[derive(Deserialize)]
+struct ScheduleQuery {
+    /// Range of days to keep in each week, e.g. `mon-sat`
+    days: Option<String>,
+    /// Skip the cache and fetch the schedule from MPEI, requires `X-Admin-Secret` header
+    refresh: Option<bool>,
+}
+
 #[actix_web::get("v1/{type}/{name}/schedule/{offset}")]
 async fn get_schedule_v1(
     path: Path<(String, String, i32)>,
+    query: Query<ScheduleQuery>,
     state: Data<AppSchedule>,
     req: HttpRequest,
-) -> Result<Json<Schedule>, AppScheduleError> {
+) -> Result<ETagJson<Schedule>, AppScheduleError> {
     let (r#type, name, offset) = path.into_inner();
     let r#type = r#type.parse::<ScheduleType>()?;
+    let days_range = match &query.days {
+        Some(days) => Some(days.to_lowercase().parse::<DaysRange>()?),
+        None => None,
+    };
+    let force_refresh = query.refresh.unwrap_or(false);
+    if force_refresh {
+        check_admin_header(&req)?;
+    }
     let app_version = get_app_version(&req);
-    Ok(Json(
+    Ok(ETagJson(
+        state
+            .feature_schedule
+            .get_schedule(name, r#type, offset, app_version, days_range, force_refresh)
+            .await?,
+    ))
+}
+
+/// Exams and consultations of the nearest exam session, or of the current one.
+/// Days are empty if MPEI has not published the session timetable yet.
+#[actix_web::get("v1/{type}/{name}/session")]
+async fn get_session_schedule_v1(
+    path: Path<(String, String)>,
+    state: Data<AppSchedule>,
+) -> Result<Negotiated<SessionSchedule>, AppScheduleError> {
+    let (r#type, name) = path.into_inner();
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Negotiated(
         state
             .feature_schedule
-            .get_schedule(name, r#type, offset, app_version)
+            .get_session_schedule(name, r#type)
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct ChangesQuery {
+    /// Changes detected during the last `days`, a week by default
+    days: Option<u32>,
+}
+
+/// Changes of the classes detected during the last days, in the order of detection.
+/// Changes are detected when the fresh schedule from MPEI replaces the cached one.
+#[actix_web::get("v1/{type}/{name}/changes")]
+async fn get_schedule_changes_v1(
+    path: Path<(String, String)>,
+    query: Query<ChangesQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ScheduleChangelog>, AppScheduleError> {
+    let (r#type, name) = path.into_inner();
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Json(
+        state
+            .get_schedule_changes_use_case
+            .get_changes(name, r#type, query.days.unwrap_or(7))
             .await?,
     ))
 }
 
+#[derive(Deserialize)]
+struct DiffQuery {
+    /// Offset of the week to compare with, the current week by default
+    from: Option<i32>,
+    /// Offset of the compared week, the next week by default
+    to: Option<i32>,
+}
+
+/// Changes of the classes of the `to` week relative to the `from` week,
+/// e.g. what changes next week compared to the current one.
+/// Days of the weeks are matched by the day of week.
+#[actix_web::get("v1/{type}/{name}/diff")]
+async fn get_schedule_diff_v1(
+    path: Path<(String, String)>,
+    query: Query<DiffQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ScheduleDiff>, AppScheduleError> {
+    let (r#type, name) = path.into_inner();
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Json(
+        state
+            .diff_schedule_use_case
+            .diff(name, r#type, query.from.unwrap_or(0), query.to.unwrap_or(1))
+            .await?,
+    ))
+}
+
+/// Changes of the week fetched from MPEI relative to its cached version.
+/// The fresh week replaces the cached one, unless the cached one is too fresh to compare.
+#[actix_web::get("v1/{type}/{name}/schedule/{offset}/diff")]
+async fn get_schedule_cache_diff_v1(
+    path: Path<(String, String, i32)>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ScheduleCacheDiff>, AppScheduleError> {
+    let (r#type, name, offset) = path.into_inner();
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Json(
+        state
+            .diff_schedule_use_case
+            .diff_with_cache(name, r#type, offset)
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct BatchScheduleRequest {
+    schedules: Vec<ScheduleRef>,
+    #[serde(default)]
+    offset: i32,
+}
+
+#[derive(Serialize)]
+struct BatchScheduleResponse {
+    items: BTreeMap<String, BatchScheduleResult>,
+}
+
+/// The same week of several schedules at once, keyed by schedule name.
+/// Each schedule has either `schedule` or `error` field, so one failed schedule
+/// does not fail the whole request.
+#[actix_web::post("v1/schedules/batch")]
+async fn get_schedules_batch_v1(
+    payload: Json<BatchScheduleRequest>,
+    state: Data<AppSchedule>,
+    req: HttpRequest,
+) -> Result<Negotiated<BatchScheduleResponse>, AppScheduleError> {
+    let BatchScheduleRequest { schedules, offset } = payload.into_inner();
+    let app_version = get_app_version(&req);
+    Ok(Negotiated(BatchScheduleResponse {
+        items: state
+            .feature_schedule
+            .get_schedules_batch(schedules, offset, app_version)
+            .await?,
+    }))
+}
+
 #[derive(Deserialize)]
 struct SearchQuery {
     #[serde(alias = "q")]
@@ -75,7 +242,7 @@ async fn search_schedule_v1(
         None => None,
     };
 
-    Ok(Json(SearchResponse {
+    Ok(Negotiated(SearchResponse {
         items: state
             .feature_schedule
             .search_schedule(query.query.clone(), r#type)
@@ -83,6 +250,140 @@ async fn search_schedule_v1(
     }))
 }
 
+#[derive(Deserialize)]
+struct AutocompleteQuery {
+    #[serde(alias = "q")]
+    query: String,
+    r#type: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct AutocompleteResponse {
+    items: Vec<ScheduleAutocompleteItem>,
+}
+
+/// Names and types of the known schedules starting with the query, without remote calls.
+/// Use `v1/search` to find the schedules, which were never searched before.
+#[actix_web::get("v1/autocomplete")]
+async fn autocomplete_schedule_v1(
+    query: Query<AutocompleteQuery>,
+    state: Data<AppSchedule>,
+) -> Result<impl Responder, AppScheduleError> {
+    let r#type = match &query.r#type {
+        Some(r#type) => Some(r#type.to_lowercase().parse::<ScheduleType>()?),
+        None => None,
+    };
+
+    Ok(Negotiated(AutocompleteResponse {
+        items: state
+            .feature_schedule
+            .autocomplete_schedule(&query.query, r#type, query.limit)?,
+    }))
+}
+
+#[derive(Deserialize)]
+struct FreeRoomsQuery {
+    /// Today by default
+    date: Option<NaiveDate>,
+    /// Time in `HH:MM` format, now by default
+    time: Option<String>,
+}
+
+/// Rooms without classes at the given date and time, with the time they stay free until.
+/// Rooms, which schedules could not be loaded, are listed separately as `unknown`.
+#[actix_web::get("v1/rooms/free")]
+async fn get_free_rooms_v1(
+    query: Query<FreeRoomsQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Json<FreeRooms>, AppScheduleError> {
+    let now = Local::now().naive_local();
+    let time = match &query.time {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| {
+            AppScheduleError(anyhow!(CommonError::user(
+                "Parameter 'time' must be in HH:MM format"
+            )))
+        })?,
+        None => now.time(),
+    };
+    Ok(Json(
+        state
+            .find_free_rooms_use_case
+            .find_free_rooms(query.date.unwrap_or(now.date()), time)
+            .await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct TrendingQuery {
+    /// Window of the last days like `7d`, a week by default
+    window: Option<String>,
+    r#type: Option<String>,
+    limit: Option<usize>,
+}
+
+/// Most requested schedules during the window, e.g. for the "popular groups" suggestions
+#[actix_web::get("v1/trending")]
+async fn get_trending_v1(
+    query: Query<TrendingQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Json<TrendingSchedules>, AppScheduleError> {
+    let r#type = match &query.r#type {
+        Some(r#type) => Some(r#type.to_lowercase().parse::<ScheduleType>()?),
+        None => None,
+    };
+    Ok(Json(
+        state
+            .get_trending_schedules_use_case
+            .get_trending(query.window.as_deref(), r#type, query.limit)
+            .await?,
+    ))
+}
+
+#[derive(Serialize)]
+struct BuildingsResponse {
+    items: Vec<Building>,
+}
+
+#[actix_web::get("v1/buildings")]
+async fn get_buildings_v1(state: Data<AppSchedule>) -> impl Responder {
+    Negotiated(BuildingsResponse {
+        items: state.feature_schedule.get_buildings(),
+    })
+}
+
+#[derive(Deserialize)]
+struct ShiftRulesQuery {
+    /// Date in `YYYY-MM-DD` format to explain the week number of
+    date: Option<NaiveDate>,
+}
+
+/// Rules of the non-standard academic weeks numbering and, if `date` is specified,
+/// the rule (or the default algorithm branch) which produced the week number of the date
+#[actix_web::get("v1/calendar/shift-rules")]
+async fn get_shift_rules_v1(
+    query: Query<ShiftRulesQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Negotiated<ShiftRules>, AppScheduleError> {
+    Ok(Negotiated(
+        state.feature_schedule.get_shift_rules(query.date).await?,
+    ))
+}
+
+/// Admin-only parameters of the public endpoints are gated with `X-Admin-Secret` header
+fn check_admin_header(req: &HttpRequest) -> Result<(), AppScheduleError> {
+    let secret = req
+        .headers()
+        .get("X-Admin-Secret")
+        .and_then(|it| it.to_str().ok())
+        .unwrap_or_default();
+    check_admin_secret(secret).map_err(|_| {
+        AppScheduleError(anyhow!(CommonError::user(
+            "Parameter 'refresh' requires valid 'X-Admin-Secret' header"
+        )))
+    })
+}
+
 fn get_app_version(req: &HttpRequest) -> Option<AppVersion> {
     req.headers()
         .get("X-App-Version")
@@ -95,3 +396,163 @@ impl From<ParseScheduleTypeError> for AppScheduleError {
         Self(anyhow!(CommonError::user(value)))
     }
 }
+
+impl From<ParseDaysRangeError> for AppScheduleError {
+    fn from(value: ParseDaysRangeError) -> Self {
+        Self(anyhow!(CommonError::user(value)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LogFilter {
+    filter: String,
+}
+
+/// Admin method: get current log filter in `RUST_LOG` format
+#[actix_web::get("v1/admin_{secret}/loglevel")]
+async fn get_loglevel_v1(path: Path<String>) -> Result<impl Responder, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(LogFilter {
+        filter: get_log_filter().unwrap_or_default(),
+    }))
+}
+
+/// Admin method: replace log filter without restart,
+/// e.g. `{"filter": "info,domain_schedule::schedule=debug"}`
+#[actix_web::put("v1/admin_{secret}/loglevel")]
+async fn put_loglevel_v1(
+    path: Path<String>,
+    payload: Json<LogFilter>,
+) -> Result<impl Responder, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    set_log_filter(&payload.filter)?;
+    Ok(payload)
+}
+
+#[derive(Serialize)]
+struct ConfigReload {
+    changed: Vec<String>,
+}
+
+/// Admin method: re-read the config file from `CONFIG_PATH` without restart,
+/// responds with the names of the changed variables
+#[actix_web::post("v1/admin_{secret}/reload")]
+async fn reload_config_v1(
+    path: Path<String>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ConfigReload>, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(ConfigReload {
+        changed: state.reload_config_use_case.reload().await?,
+    }))
+}
+
+#[derive(Deserialize)]
+struct QualityQuery {
+    days: Option<u32>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    sort: Option<String>,
+}
+
+/// Admin method: summary of validation warnings of schedules received
+/// from the MPEI backend for the last `days` days (default is 7).
+/// Paginated with `offset` and `limit`, sorted with `sort`, e.g. `-missing_rooms`
+#[actix_web::get("v1/admin_{secret}/quality")]
+async fn get_quality_v1(
+    path: Path<String>,
+    query: Query<QualityQuery>,
+    state: Data<AppSchedule>,
+) -> Result<impl Responder, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(
+        state
+            .get_schedule_quality_use_case
+            .get_quality_report(
+                query.days.unwrap_or(7),
+                Page::from_query(query.offset, query.limit, Page::MAX_LIMIT),
+                query.sort.as_deref(),
+            )
+            .await?,
+    ))
+}
+
+/// Admin method: latency of the current week schedule requests since the start
+/// of the service, by the source of the response (fresh cache, expired cache, remote or error).
+/// Used to compare `SCHEDULE_FETCH_RACE` strategy with the sequential one
+#[actix_web::get("v1/admin_{secret}/latency")]
+async fn get_latency_v1(
+    path: Path<String>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ScheduleFetchLatency>, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(state.get_schedule_latency_use_case.get_latency()))
+}
+
+#[derive(Deserialize)]
+struct UpstreamAuditQuery {
+    hours: Option<u32>,
+}
+
+/// Admin method: hourly stats of the calls to the MPEI backend for the last `hours` hours
+/// (default is 24) by endpoint and response status: number of calls, latency and payload size.
+/// The stats of the current minute may be not saved yet
+#[actix_web::get("v1/admin_{secret}/upstream_audit")]
+async fn get_upstream_audit_v1(
+    path: Path<String>,
+    query: Query<UpstreamAuditQuery>,
+    state: Data<AppSchedule>,
+) -> Result<Json<Vec<UpstreamCallStats>>, AppScheduleError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(
+        state
+            .upstream_audit_use_case
+            .get_hourly_stats(query.hours.unwrap_or(24))
+            .await?,
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScheduleOverrides {
+    days: Vec<DayOverride>,
+}
+
+/// Admin method: override classes of the schedule days until the expiry date,
+/// e.g. `{"days": [{"date": "2023-03-14", "expiresAt": "2023-03-14", "classes": [...]}]}`.
+/// Overridden days of the schedule are marked with `"source": "OVERRIDE"`.
+/// Repeated request for the same day replaces its override.
+#[actix_web::put("v1/admin_{secret}/{type}/{name}/override")]
+async fn put_schedule_override_v1(
+    path: Path<(String, String, String)>,
+    payload: Json<ScheduleOverrides>,
+    state: Data<AppSchedule>,
+) -> Result<impl Responder, AppScheduleError> {
+    let (secret, r#type, name) = path.into_inner();
+    check_admin_secret(&secret)?;
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Json(ScheduleOverrides {
+        days: state
+            .set_schedule_overrides_use_case
+            .set_overrides(name, r#type, payload.into_inner().days)
+            .await?,
+    }))
+}
+
+/// Admin method: fetch the week from MPEI bypassing the cache and compare it
+/// with the cached version. Returns cache metadata (age, TTL left) and the list
+/// of changed classes. The cache is not modified.
+#[actix_web::get("v1/admin_{secret}/{type}/{name}/schedule/{offset}/verify")]
+async fn verify_schedule_v1(
+    path: Path<(String, String, String, i32)>,
+    state: Data<AppSchedule>,
+) -> Result<Json<ScheduleVerification>, AppScheduleError> {
+    let (secret, r#type, name, offset) = path.into_inner();
+    check_admin_secret(&secret)?;
+    let r#type = r#type.parse::<ScheduleType>()?;
+    Ok(Json(
+        state
+            .verify_schedule_use_case
+            .verify(name, r#type, offset)
+            .await?,
+    ))
+}
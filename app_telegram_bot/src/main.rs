@@ -1,9 +1,19 @@
+use std::{sync::Arc, time::Duration};
+
 use actix_web::{middleware, web::Data, App, HttpServer};
 use anyhow::Context;
-use common_actix::{define_app_error, get_address};
+use common_actix::{
+    define_app_error, get_address, get_shutdown_timeout, init_webhook_ip_allowlist,
+    run_until_shutdown, Readiness,
+};
+use common_logging::{access_logger, init_logger, RequestId};
 use di::create_app;
-use domain_bot::usecases::InitDomainBotUseCase;
+use domain_bot::usecases::{
+    CommandKillSwitchUseCase, InitDomainBotUseCase, MigratePeersUseCase, PeerDeletionUseCase,
+    ReEngagementUseCase,
+};
 use feature_telegram_bot::FeatureTelegramBot;
+use log::{error, info};
 
 mod di;
 mod routing;
@@ -11,6 +21,11 @@ mod routing;
 pub struct AppTelegramBot {
     feature_telegram_bot: FeatureTelegramBot,
     init_domain_bot_use_case: InitDomainBotUseCase,
+    migrate_peers_use_case: MigratePeersUseCase,
+    command_kill_switch_use_case: Arc<CommandKillSwitchUseCase>,
+    reengagement_use_case: Arc<ReEngagementUseCase>,
+    peer_deletion_use_case: Arc<PeerDeletionUseCase>,
+    readiness: Arc<Readiness>,
 }
 
 define_app_error!(AppTelegramBotError);
@@ -20,23 +35,47 @@ define_app_error!(AppTelegramBotError);
 async fn main() -> std::io::Result<()> {
     std::env::set_var("RUST_LOG", "debug");
     std::env::set_var("RUST_BACKTRACE", "1");
-    env_logger::init();
+    init_logger();
     let app = Data::new(create_app());
+    init_webhook_ip_allowlist().unwrap();
+    let server_app = app.clone();
 
-    // we shall panic if init fails
-    init_app_components(&app).await.unwrap();
-
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
-            .wrap(middleware::Logger::default())
+            .wrap(RequestId)
+            // outside of the request id middleware, so the access log sees its header
+            .wrap(access_logger())
             .wrap(middleware::Compress::default())
-            .app_data(app.clone())
+            .app_data(server_app.clone())
             .service(routing::health)
+            .service(routing::readiness)
+            .service(routing::get_loglevel_v1)
+            .service(routing::put_loglevel_v1)
+            .service(routing::reload_config_v1)
+            .service(routing::migrate_peers_v1)
+            .service(routing::get_disabled_commands_v1)
+            .service(routing::put_disabled_commands_v1)
+            .service(routing::get_reengagement_stats_v1)
             .service(routing::telegram_webhook_v1)
     })
     .bind(get_address())?
-    .run()
-    .await
+    .disable_signals()
+    .shutdown_timeout(get_shutdown_timeout())
+    .run();
+
+    // the server answers the health probe during the init, but it is not ready until the init is complete
+    let init_app = app.clone();
+    actix_web::rt::spawn(async move {
+        // we shall exit if init fails
+        if let Err(e) = init_app_components(&init_app).await {
+            error!("{e:#}");
+            std::process::exit(1);
+        }
+        start_background_jobs(init_app.clone());
+        init_app.readiness.set_ready(true);
+        info!("App is ready");
+    });
+    run_until_shutdown(server, app.readiness.clone(), shutdown_app_components(&app)).await
 }
 
 async fn init_app_components(app: &AppTelegramBot) -> anyhow::Result<()> {
@@ -49,3 +88,41 @@ async fn init_app_components(app: &AppTelegramBot) -> anyhow::Result<()> {
         .await
         .with_context(|| "Set webhook error")
 }
+
+/// Last attempt to deliver the failed replies, after the server has finished the requests in progress.
+/// Unless the reply queue is persistent, the undelivered ones are lost.
+async fn shutdown_app_components(app: &AppTelegramBot) {
+    if let Err(e) = app.feature_telegram_bot.retry_failed_replies().await {
+        error!("Failed replies retry error: {e}");
+    }
+}
+
+fn start_background_jobs(app: Data<AppTelegramBot>) {
+    let retry_app = app.clone();
+    let purge_app = app.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            if let Err(e) = retry_app.feature_telegram_bot.retry_failed_replies().await {
+                error!("Failed replies retry error: {e}");
+            }
+        }
+    });
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(10 * 60));
+        loop {
+            interval.tick().await;
+            app.feature_telegram_bot.send_pushes().await;
+        }
+    });
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_app.peer_deletion_use_case.purge_deleted_peers().await {
+                error!("Deleted peers purge error: {e}");
+            }
+        }
+    });
+}
@@ -1,48 +1,201 @@
 use std::sync::Arc;
 
-use common_database::create_db_pool;
-use common_restix::create_reqwest_client;
-use common_rust::env;
+use common_database::{create_database, Database};
+use common_di::singleton;
 use domain_bot::{
-    mpeix_api::MpeixApi,
-    peer::repository::PeerRepository,
+    achievements::repository::PgAchievementsRepository,
+    activity::repository::PgPeerActivityRepository,
+    audit::repository::PgAuditRepository,
+    blocklist::repository::PgBlocklistRepository,
+    members::repository::PgMemberSchedulesRepository,
+    mpeix_api::MpeixUpstream,
+    notes::repository::PgNotesRepository,
+    peer::repository::PgPeerRepository,
+    queue::repository::ReplyQueueRepository,
+    recent::repository::PgRecentSchedulesRepository,
     schedule::repository::ScheduleRepository,
     search::repository::ScheduleSearchRepository,
     usecases::{
-        GenerateReplyUseCase, GetUpcomingEventsUseCase, InitDomainBotUseCase, TextToActionUseCase,
+        AbuseProtectionUseCase, AchievementsUseCase, AuditReplyUseCase, CommandKillSwitchUseCase,
+        EveningSummaryUseCase, ExportPeerDataUseCase, GenerateReplyUseCase,
+        GetMonthOverviewUseCase, GetUpcomingEventsUseCase, InitDomainBotUseCase,
+        MemberSchedulesUseCase, MigratePeersUseCase, NotesUseCase, PeerDeletionUseCase,
+        PingUseCase, ReEngagementUseCase, ReplyRetryUseCase, ScheduleChangeNotificationsUseCase,
+        TextToActionUseCase, VerifyScheduleUseCase, WeekAheadUseCase, WeeklyChangelogUseCase,
+        WeeklyDigestUseCase,
     },
+    warnings::repository::RoomChangeWarningsRepository,
 };
-use domain_vk_bot::usecases::ReplyToVkUseCase;
-use feature_vk_bot::FeatureVkBot;
+use domain_vk_bot::usecases::{GetConversationAdminsUseCase, ReplyToVkUseCase};
+use feature_vk_bot::{FeatureVkBot, VkChatAdminChecker};
 
 use crate::AppVkBot;
 
+singleton! {
+    fn database() -> Database {
+        create_database().expect("DI error while creating database")
+    }
+
+    fn mpeix_upstream() -> MpeixUpstream {
+        MpeixUpstream::default()
+    }
+
+    fn peer_repository() -> PgPeerRepository {
+        PgPeerRepository::new(database())
+    }
+
+    fn achievements_repository() -> PgAchievementsRepository {
+        PgAchievementsRepository::new(database())
+    }
+
+    fn audit_repository() -> PgAuditRepository {
+        PgAuditRepository::new(database())
+    }
+
+    fn recent_schedules_repository() -> PgRecentSchedulesRepository {
+        PgRecentSchedulesRepository::new(database())
+    }
+
+    fn notes_repository() -> PgNotesRepository {
+        PgNotesRepository::new(database())
+    }
+
+    fn member_schedules_repository() -> PgMemberSchedulesRepository {
+        PgMemberSchedulesRepository::new(database())
+    }
+
+    fn peer_activity_repository() -> PgPeerActivityRepository {
+        PgPeerActivityRepository::new(database())
+    }
+
+    fn blocklist_repository() -> PgBlocklistRepository {
+        PgBlocklistRepository::new(database())
+    }
+
+    fn reply_queue_repository() -> ReplyQueueRepository {
+        ReplyQueueRepository::new(database())
+    }
+
+    fn schedule_repository() -> ScheduleRepository {
+        ScheduleRepository::new(mpeix_upstream().as_ref().clone())
+    }
+
+    fn schedule_search_repository() -> ScheduleSearchRepository {
+        ScheduleSearchRepository::new(mpeix_upstream().as_ref().clone())
+    }
+
+    fn room_change_warnings_repository() -> RoomChangeWarningsRepository {
+        RoomChangeWarningsRepository::default()
+    }
+
+    fn command_kill_switch_use_case() -> CommandKillSwitchUseCase {
+        CommandKillSwitchUseCase::default()
+    }
+
+    fn reengagement_use_case() -> ReEngagementUseCase {
+        ReEngagementUseCase::new(peer_repository(), peer_activity_repository())
+    }
+
+    fn abuse_protection_use_case() -> AbuseProtectionUseCase {
+        AbuseProtectionUseCase::new(blocklist_repository())
+    }
+
+    fn peer_deletion_use_case() -> PeerDeletionUseCase {
+        PeerDeletionUseCase::new(peer_repository())
+    }
+}
+
 pub fn create_app() -> AppVkBot {
-    let db_pool = Arc::new(create_db_pool().expect("DI error while creating db pool"));
-    let api = MpeixApi::builder()
-        .base_url(env::required("APP_SCHEDULE_BASE_URL"))
-        .client(create_reqwest_client())
-        .build()
-        .expect("DI error while creating MpeixApi");
-
-    let peer_repository = Arc::new(PeerRepository::new(db_pool));
-    let schedule_repository = Arc::new(ScheduleRepository::new(api.to_owned()));
-    let schedule_search_repository = Arc::new(ScheduleSearchRepository::new(api));
-
-    let text_to_action_use_case = Arc::new(TextToActionUseCase);
-    let get_upcoming_events_use_case =
-        Arc::new(GetUpcomingEventsUseCase::new(schedule_repository.clone()));
+    let notes_use_case = Arc::new(NotesUseCase::new(
+        notes_repository(),
+        Arc::new(VkChatAdminChecker::new(Arc::new(
+            GetConversationAdminsUseCase::default(),
+        ))),
+    ));
     let generate_reply_use_case = Arc::new(GenerateReplyUseCase::new(
-        text_to_action_use_case,
-        peer_repository.clone(),
-        schedule_repository,
-        schedule_search_repository,
-        get_upcoming_events_use_case,
+        Arc::new(TextToActionUseCase),
+        peer_repository(),
+        schedule_repository(),
+        schedule_search_repository(),
+        Arc::new(GetUpcomingEventsUseCase::new(schedule_repository())),
+        Arc::new(AchievementsUseCase::new(achievements_repository())),
+        recent_schedules_repository(),
+        Arc::new(VerifyScheduleUseCase::new(
+            schedule_repository(),
+            schedule_search_repository(),
+        )),
+        notes_use_case,
+        command_kill_switch_use_case(),
+        Arc::new(ExportPeerDataUseCase::new(
+            recent_schedules_repository(),
+            achievements_repository(),
+            audit_repository(),
+            member_schedules_repository(),
+        )),
+        Arc::new(GetMonthOverviewUseCase::new(schedule_repository())),
+        Arc::new(PingUseCase::new(
+            schedule_repository(),
+            domain_bot::build_info!(),
+        )),
+        Arc::new(MemberSchedulesUseCase::new(
+            member_schedules_repository(),
+            schedule_search_repository(),
+        )),
+        reengagement_use_case(),
+        room_change_warnings_repository(),
+        abuse_protection_use_case(),
+        peer_deletion_use_case(),
     ));
-    let reply_to_vk_use_case = Arc::new(ReplyToVkUseCase::default());
 
     AppVkBot {
-        feature_vk_bot: FeatureVkBot::new(generate_reply_use_case, reply_to_vk_use_case),
-        init_domain_bot_use_case: InitDomainBotUseCase::new(peer_repository),
+        feature_vk_bot: FeatureVkBot::new(
+            generate_reply_use_case,
+            Arc::new(ReplyToVkUseCase::default()),
+            Arc::new(AuditReplyUseCase::new(audit_repository())),
+            Arc::new(ReplyRetryUseCase::new(reply_queue_repository())),
+            Arc::new(EveningSummaryUseCase::new(
+                peer_repository(),
+                schedule_repository(),
+            )),
+            Arc::new(WeeklyChangelogUseCase::new(
+                peer_repository(),
+                schedule_repository(),
+            )),
+            Arc::new(WeekAheadUseCase::new(
+                peer_repository(),
+                schedule_repository(),
+            )),
+            Arc::new(WeeklyDigestUseCase::new(
+                peer_repository(),
+                schedule_repository(),
+            )),
+            Arc::new(ScheduleChangeNotificationsUseCase::new(
+                peer_repository(),
+                schedule_repository(),
+                room_change_warnings_repository(),
+            )),
+            reengagement_use_case(),
+            abuse_protection_use_case(),
+        ),
+        init_domain_bot_use_case: InitDomainBotUseCase::new(
+            peer_repository(),
+            achievements_repository(),
+            audit_repository(),
+            recent_schedules_repository(),
+            notes_repository(),
+            reply_queue_repository(),
+            member_schedules_repository(),
+            peer_activity_repository(),
+            blocklist_repository(),
+        ),
+        migrate_peers_use_case: MigratePeersUseCase::new(
+            peer_repository(),
+            schedule_repository(),
+            schedule_search_repository(),
+        ),
+        command_kill_switch_use_case: command_kill_switch_use_case(),
+        reengagement_use_case: reengagement_use_case(),
+        peer_deletion_use_case: peer_deletion_use_case(),
+        readiness: Default::default(),
     }
 }
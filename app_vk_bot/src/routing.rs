@@ -1,8 +1,12 @@
 use actix_web::{
-    web::{Data, Json},
+    web::{Data, Json, Path, Query},
     HttpResponse, Responder,
 };
+use common_actix::check_admin_secret;
+use common_logging::{get_log_filter, set_log_filter};
+use common_rust::env;
 use domain_vk_bot::VkCallbackRequest;
+use serde::{Deserialize, Serialize};
 
 use crate::{AppVkBot, AppVkBotError};
 
@@ -13,7 +17,15 @@ async fn health() -> impl Responder {
     HttpResponse::Ok().body("I'm alive :)")
 }
 
-#[actix_web::post("v1/vk_callback")]
+/// Readiness probe method
+/// Returns `200 OK` only after the init (migrations) is complete and until the shutdown starts,
+/// otherwise `503 Service Unavailable`
+#[actix_web::get("v1/readiness")]
+async fn readiness(state: Data<AppVkBot>) -> impl Responder {
+    state.readiness.to_response()
+}
+
+#[actix_web::post("v1/vk_callback", guard = "common_actix::webhook_ip_guard")]
 async fn vk_callback_v1(
     payload: Json<VkCallbackRequest>,
     state: Data<AppVkBot>,
@@ -30,3 +42,109 @@ async fn vk_callback_v1(
             }
         })?)
 }
+
+#[derive(Deserialize)]
+struct MigratePeersQuery {
+    dry_run: Option<bool>,
+}
+
+/// Admin maintenance method: validate all peers' selected schedules and fix renamed ones.
+/// Works in dry-run mode by default, pass `?dry_run=false` to apply changes.
+#[actix_web::post("v1/admin_{secret}/peers/migrate")]
+async fn migrate_peers_v1(
+    path: Path<String>,
+    query: Query<MigratePeersQuery>,
+    state: Data<AppVkBot>,
+) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(
+        state
+            .migrate_peers_use_case
+            .migrate_peers(query.dry_run.unwrap_or(true))
+            .await?,
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct DisabledCommands {
+    commands: Vec<String>,
+}
+
+/// Admin method: get the commands disabled with the kill switch
+#[actix_web::get("v1/admin_{secret}/commands/disabled")]
+async fn get_disabled_commands_v1(
+    path: Path<String>,
+    state: Data<AppVkBot>,
+) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(DisabledCommands {
+        commands: state.command_kill_switch_use_case.get_disabled(),
+    }))
+}
+
+/// Admin method: replace the commands disabled with the kill switch without restart,
+/// e.g. `{"commands": ["WeekWithOffset", "UpcomingEvents"]}`, an empty list enables all commands
+#[actix_web::put("v1/admin_{secret}/commands/disabled")]
+async fn put_disabled_commands_v1(
+    path: Path<String>,
+    payload: Json<DisabledCommands>,
+    state: Data<AppVkBot>,
+) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(DisabledCommands {
+        commands: state
+            .command_kill_switch_use_case
+            .set_disabled(payload.into_inner().commands),
+    }))
+}
+
+/// Admin method: stats of the re-engagement campaign of the long inactive peers
+#[actix_web::get("v1/admin_{secret}/reengagement/stats")]
+async fn get_reengagement_stats_v1(
+    path: Path<String>,
+    state: Data<AppVkBot>,
+) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(state.reengagement_use_case.get_stats().await?))
+}
+
+#[derive(Serialize, Deserialize)]
+struct LogFilter {
+    filter: String,
+}
+
+/// Admin method: get current log filter in `RUST_LOG` format
+#[actix_web::get("v1/admin_{secret}/loglevel")]
+async fn get_loglevel_v1(path: Path<String>) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(LogFilter {
+        filter: get_log_filter().unwrap_or_default(),
+    }))
+}
+
+/// Admin method: replace log filter without restart,
+/// e.g. `{"filter": "info,domain_schedule::schedule=debug"}`
+#[actix_web::put("v1/admin_{secret}/loglevel")]
+async fn put_loglevel_v1(
+    path: Path<String>,
+    payload: Json<LogFilter>,
+) -> Result<impl Responder, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    set_log_filter(&payload.filter)?;
+    Ok(payload)
+}
+
+#[derive(Serialize)]
+struct ConfigReload {
+    changed: Vec<String>,
+}
+
+/// Admin method: re-read the config file from `CONFIG_PATH` without restart,
+/// responds with the names of the changed variables
+#[actix_web::post("v1/admin_{secret}/reload")]
+async fn reload_config_v1(path: Path<String>) -> Result<Json<ConfigReload>, AppVkBotError> {
+    check_admin_secret(&path.into_inner())?;
+    Ok(Json(ConfigReload {
+        changed: env::reload_config_file()?,
+    }))
+}
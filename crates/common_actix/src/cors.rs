@@ -0,0 +1,144 @@
+use actix_cors::Cors;
+use actix_web::http::{header::HeaderName, Method};
+use anyhow::Context;
+use common_rust::env;
+use log::info;
+
+/// Cross-origin access for the browser clients.
+///
+/// CORS is disabled if no allowed origins are specified, so browsers keep
+/// blocking cross-origin requests, and non-browser clients are not affected at all.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsConfig {
+    /// Allowed origins, e.g. `https://mpeix.ru`, or `*` for any origin
+    pub allowed_origins: Vec<String>,
+    /// Request headers allowed in addition to the CORS-safelisted ones
+    pub allowed_headers: Vec<HeaderName>,
+    /// How long the preflight response can be cached by the browser
+    pub max_age_secs: usize,
+}
+
+impl CorsConfig {
+    /// Read config from `CORS_ALLOWED_ORIGINS`, `CORS_ALLOWED_HEADERS`
+    /// and `CORS_MAX_AGE_SECS` environment variables.
+    pub fn from_env() -> anyhow::Result<Self> {
+        let config = Self {
+            allowed_origins: split_list(&env::get_or("CORS_ALLOWED_ORIGINS", ""))
+                .map(str::to_owned)
+                .collect(),
+            allowed_headers: split_list(&env::get_or("CORS_ALLOWED_HEADERS", ""))
+                .map(|it| {
+                    HeaderName::try_from(it).with_context(|| format!("Invalid CORS header '{it}'"))
+                })
+                .collect::<anyhow::Result<_>>()?,
+            max_age_secs: env::get_parsed_or("CORS_MAX_AGE_SECS", 3600),
+        };
+        match config.is_enabled() {
+            true => info!("CORS is enabled for {:?}", config.allowed_origins),
+            false => info!("CORS is disabled"),
+        }
+        Ok(config)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+
+    /// Create CORS middleware, which also answers preflight `OPTIONS` requests of all routes.
+    /// Should be wrapped with [actix_web::middleware::Condition] using [CorsConfig::is_enabled].
+    pub fn to_cors(&self) -> Cors {
+        let cors = Cors::default()
+            .allowed_methods([Method::GET, Method::POST, Method::PUT])
+            .max_age(self.max_age_secs);
+        let cors = match self.allowed_headers.is_empty() {
+            true => cors,
+            false => cors.allowed_headers(self.allowed_headers.to_owned()),
+        };
+        if self.allowed_origins.iter().any(|it| it == "*") {
+            return cors.allow_any_origin();
+        }
+        self.allowed_origins
+            .iter()
+            .fold(cors, |cors, origin| cors.allowed_origin(origin))
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|it| !it.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        http::{header, Method, StatusCode},
+        test::{call_service, init_service, TestRequest},
+        web, App, HttpResponse,
+    };
+
+    use super::CorsConfig;
+
+    fn config(allowed_origins: &[&str]) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: allowed_origins.iter().map(|it| it.to_string()).collect(),
+            allowed_headers: vec![header::CONTENT_TYPE],
+            max_age_secs: 600,
+        }
+    }
+
+    fn preflight(origin: &str) -> TestRequest {
+        TestRequest::default()
+            .method(Method::OPTIONS)
+            .uri("/v1/search?q=s-12")
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, "GET"))
+    }
+
+    #[actix_web::test]
+    async fn test_preflight_of_allowed_origin() {
+        let app = init_service(
+            App::new()
+                .wrap(config(&["https://mpeix.ru"]).to_cors())
+                .route("/v1/{tail:.*}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let response = call_service(&app, preflight("https://mpeix.ru").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let headers = response.headers();
+        assert_eq!(
+            headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+            "https://mpeix.ru"
+        );
+        assert_eq!(headers.get(header::ACCESS_CONTROL_MAX_AGE).unwrap(), "600");
+
+        let response = call_service(&app, preflight("https://evil.com").to_request()).await;
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_any_origin() {
+        let app = init_service(
+            App::new()
+                .wrap(config(&["*"]).to_cors())
+                .route("/v1/{tail:.*}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let request = TestRequest::get()
+            .uri("/v1/search?q=s-12")
+            .insert_header((header::ORIGIN, "https://example.com"))
+            .to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[test]
+    fn test_disabled_without_origins() {
+        assert!(!config(&[]).is_enabled());
+        assert!(config(&["*"]).is_enabled());
+    }
+}
@@ -0,0 +1,107 @@
+use actix_web::{body::BoxBody, http::header, HttpRequest, HttpResponse, Responder};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::ResponseFormat;
+
+/// JSON response with the `ETag` header, the hash of the serialized value.
+/// MessagePack is sent instead if the client prefers it, see [ResponseFormat],
+/// the tags of the formats differ, because they are the hashes of the different bodies.
+///
+/// If the `If-None-Match` header of the request contains the same tag, the client already
+/// has this value, so `304 Not Modified` is returned without the body.
+pub struct ETagJson<T>(pub T);
+
+impl<T: Serialize> Responder for ETagJson<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let format = ResponseFormat::of(req);
+        let body = match format.serialize(&self.0) {
+            Ok(body) => body,
+            Err(e) => return HttpResponse::from_error(e),
+        };
+        let etag = etag_of(&body);
+        let not_modified = req
+            .headers()
+            .get(header::IF_NONE_MATCH)
+            .and_then(|it| it.to_str().ok())
+            .is_some_and(|it| if_none_match(it, &etag));
+        if not_modified {
+            return HttpResponse::NotModified()
+                .insert_header((header::ETAG, etag))
+                .finish();
+        }
+        HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header((header::ETAG, etag))
+            .body(body)
+    }
+}
+
+/// Strong entity tag of the body: quoted first 16 bytes of its SHA-256 in hex
+fn etag_of(body: &[u8]) -> String {
+    let hash = Sha256::digest(body);
+    let mut etag = String::with_capacity(34);
+    etag.push('"');
+    for byte in &hash[..16] {
+        etag.push_str(&format!("{byte:02x}"));
+    }
+    etag.push('"');
+    etag
+}
+
+/// Whether the tag is listed in the `If-None-Match` header value.
+/// The header uses the weak comparison, so `W/"tag"` matches the strong `"tag"`.
+fn if_none_match(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(str::trim)
+        .any(|it| it == "*" || it.trim_start_matches("W/") == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::to_bytes,
+        http::{header, StatusCode},
+        test::TestRequest,
+        Responder,
+    };
+
+    use super::{if_none_match, ETagJson};
+
+    #[test]
+    fn test_if_none_match() {
+        assert!(if_none_match("\"abc\"", "\"abc\""));
+        assert!(if_none_match("\"xyz\", W/\"abc\"", "\"abc\""));
+        assert!(if_none_match("*", "\"abc\""));
+        assert!(!if_none_match("\"abcd\"", "\"abc\""));
+        assert!(!if_none_match("", "\"abc\""));
+    }
+
+    #[actix_web::test]
+    async fn test_not_modified_if_etag_matches() {
+        let value = vec!["А-08-19", "С-12-21"];
+        let response = ETagJson(&value).respond_to(&TestRequest::default().to_http_request());
+        assert_eq!(StatusCode::OK, response.status());
+        let etag = response.headers().get(header::ETAG).unwrap().to_owned();
+        assert_eq!(34, etag.len());
+        assert_eq!(
+            "[\"А-08-19\",\"С-12-21\"]",
+            to_bytes(response.into_body()).await.unwrap()
+        );
+
+        let req = TestRequest::default()
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_http_request();
+        let response = ETagJson(&value).respond_to(&req);
+        assert_eq!(StatusCode::NOT_MODIFIED, response.status());
+        assert_eq!(Some(&etag), response.headers().get(header::ETAG));
+        assert!(to_bytes(response.into_body()).await.unwrap().is_empty());
+
+        let response = ETagJson(vec!["А-08-19"]).respond_to(&req);
+        assert_eq!(StatusCode::OK, response.status());
+        assert_ne!(Some(&etag), response.headers().get(header::ETAG));
+    }
+}
@@ -0,0 +1,188 @@
+use std::{net::IpAddr, str::FromStr, sync::OnceLock};
+
+use actix_web::{guard::GuardContext, http::header::HeaderMap};
+use anyhow::Context;
+use common_rust::env;
+use ipnet::IpNet;
+use log::{info, warn};
+
+static WEBHOOK_IP_ALLOWLIST: OnceLock<Option<IpAllowlist>> = OnceLock::new();
+
+/// Source IP verification for the webhook routes.
+///
+/// The client IP is the peer address, unless the peer is one of the trusted reverse proxies.
+/// In this case the client IP is taken from `X-Forwarded-For` or `X-Real-IP` headers,
+/// so clients can not spoof their IP by sending these headers directly.
+#[derive(Debug)]
+pub struct IpAllowlist {
+    allowed: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl IpAllowlist {
+    /// Create allowlist from comma-separated CIDR lists, e.g. `"149.154.160.0/20, 91.108.4.0/22"`.
+    /// Single IP addresses are also accepted.
+    pub fn new(allowed: &str, trusted_proxies: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            allowed: parse_networks(allowed)?,
+            trusted_proxies: parse_networks(trusted_proxies)?,
+        })
+    }
+
+    /// Read allowlist from `WEBHOOK_ALLOWED_IPS` and `WEBHOOK_TRUSTED_PROXIES` environment variables.
+    /// Returns `None` if `WEBHOOK_ALLOWED_IPS` is not specified, so the verification is disabled.
+    pub fn from_env() -> anyhow::Result<Option<Self>> {
+        match env::get("WEBHOOK_ALLOWED_IPS").filter(|it| !it.trim().is_empty()) {
+            Some(allowed) => Ok(Some(
+                Self::new(&allowed, &env::get_or("WEBHOOK_TRUSTED_PROXIES", ""))
+                    .with_context(|| "Invalid webhook IP allowlist")?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub fn is_allowed(&self, peer_addr: Option<IpAddr>, headers: &HeaderMap) -> bool {
+        self.real_ip(peer_addr, headers)
+            .map(|ip| self.allowed.iter().any(|net| net.contains(&ip)))
+            .unwrap_or(false)
+    }
+
+    fn real_ip(&self, peer_addr: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        let peer_addr = peer_addr?;
+        if !self.is_trusted_proxy(&peer_addr) {
+            return Some(peer_addr);
+        }
+        if let Some(forwarded_for) = headers.get("X-Forwarded-For") {
+            // the rightmost address which is not our proxy is the real client,
+            // everything to the left of it could be sent by the client itself
+            return forwarded_for
+                .to_str()
+                .ok()?
+                .rsplit(',')
+                .map(|ip| IpAddr::from_str(ip.trim()).ok())
+                .find(|ip| !matches!(ip, Some(ip) if self.is_trusted_proxy(ip)))
+                .flatten();
+        }
+        match headers.get("X-Real-IP") {
+            Some(real_ip) => real_ip.to_str().ok()?.trim().parse().ok(),
+            None => Some(peer_addr),
+        }
+    }
+
+    fn is_trusted_proxy(&self, ip: &IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+}
+
+fn parse_networks(value: &str) -> anyhow::Result<Vec<IpNet>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .map(|it| {
+            IpNet::from_str(it)
+                .or_else(|_| IpAddr::from_str(it).map(IpNet::from))
+                .with_context(|| format!("Invalid IP network: '{it}'"))
+        })
+        .collect()
+}
+
+/// Validate webhook IP allowlist config. Should be called before the server starts,
+/// otherwise invalid config is detected only on the first webhook request.
+pub fn init_webhook_ip_allowlist() -> anyhow::Result<()> {
+    let allowlist = IpAllowlist::from_env()?;
+    match &allowlist {
+        Some(_) => info!("Webhook source IP verification is enabled"),
+        None => info!("Webhook source IP verification is disabled"),
+    }
+    let _ = WEBHOOK_IP_ALLOWLIST.set(allowlist);
+    Ok(())
+}
+
+/// Route guard for the webhook routes, rejects requests before the payload is parsed:
+/// `#[actix_web::post("v1/webhook", guard = "common_actix::webhook_ip_guard")]`.
+///
+/// Rejected requests get `404 Not Found`, as if the route does not exist.
+pub fn webhook_ip_guard(ctx: &GuardContext) -> bool {
+    let allowlist = WEBHOOK_IP_ALLOWLIST
+        .get_or_init(|| IpAllowlist::from_env().expect("Invalid webhook IP allowlist config"));
+    let Some(allowlist) = allowlist else {
+        return true;
+    };
+    let head = ctx.head();
+    let peer_addr = head.peer_addr.map(|it| it.ip());
+    let allowed = allowlist.is_allowed(peer_addr, &head.headers);
+    if !allowed {
+        warn!("Webhook request from {peer_addr:?} rejected by IP allowlist");
+    }
+    allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use actix_web::http::header::{HeaderMap, HeaderName, HeaderValue};
+
+    use super::IpAllowlist;
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(
+                HeaderName::from_static(name),
+                HeaderValue::from_static(value),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn test_direct_requests() {
+        let allowlist = IpAllowlist::new("149.154.160.0/20, 91.108.4.0/22, 10.0.0.1", "").unwrap();
+        assert!(allowlist.is_allowed(ip("149.154.167.220"), &headers(&[])));
+        assert!(allowlist.is_allowed(ip("10.0.0.1"), &headers(&[])));
+        assert!(!allowlist.is_allowed(ip("8.8.8.8"), &headers(&[])));
+        assert!(!allowlist.is_allowed(None, &headers(&[])));
+    }
+
+    #[test]
+    fn test_spoofed_headers_are_ignored_without_trusted_proxy() {
+        let allowlist = IpAllowlist::new("149.154.160.0/20", "").unwrap();
+        let spoofed = headers(&[
+            ("x-forwarded-for", "149.154.167.220"),
+            ("x-real-ip", "149.154.167.220"),
+        ]);
+        assert!(!allowlist.is_allowed(ip("8.8.8.8"), &spoofed));
+    }
+
+    #[test]
+    fn test_requests_behind_trusted_proxy() {
+        let allowlist = IpAllowlist::new("149.154.160.0/20", "172.16.0.0/12").unwrap();
+        let proxy = ip("172.18.0.2");
+        assert!(allowlist.is_allowed(proxy, &headers(&[("x-forwarded-for", "149.154.167.220")])));
+        assert!(allowlist.is_allowed(proxy, &headers(&[("x-real-ip", "149.154.167.220")])));
+        // client can prepend anything, only the address appended by our proxy matters
+        assert!(!allowlist.is_allowed(
+            proxy,
+            &headers(&[("x-forwarded-for", "149.154.167.220, 8.8.8.8")])
+        ));
+        assert!(allowlist.is_allowed(
+            proxy,
+            &headers(&[("x-forwarded-for", "8.8.8.8, 149.154.167.220, 172.18.0.3")])
+        ));
+        assert!(!allowlist.is_allowed(proxy, &headers(&[("x-forwarded-for", "garbage")])));
+        // proxy itself is not in the allowlist
+        assert!(!allowlist.is_allowed(proxy, &headers(&[])));
+    }
+
+    #[test]
+    fn test_invalid_networks() {
+        assert!(IpAllowlist::new("149.154.160.0/33", "").is_err());
+        assert!(IpAllowlist::new("149.154.160.0/20", "proxy").is_err());
+    }
+}
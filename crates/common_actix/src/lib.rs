@@ -1,6 +1,19 @@
+use anyhow::bail;
+use common_errors::errors::CommonError;
 use common_rust::env;
 use log::info;
 
+mod ip_allowlist;
+pub use ip_allowlist::*;
+mod cors;
+pub use cors::*;
+mod etag;
+pub use etag::*;
+mod lifecycle;
+pub use lifecycle::*;
+mod negotiation;
+pub use negotiation::*;
+
 /// Get address tuple (Host, Port) from environment variables `HOST` and `PORT`.
 /// Default host in prod builds is `0.0.0.0`, in debug builds is `127.0.0.1`.
 /// Default port is 8080 for all types of build.
@@ -18,6 +31,18 @@ pub fn get_address() -> (String, u16) {
     (host, port)
 }
 
+/// Check secret part of admin endpoints path against `ADMIN_SECRET` environment variable.
+/// If the variable is not specified, all admin endpoints are disabled.
+///
+/// Returns [CommonError::NotFoundError] if the secret does not match,
+/// so admin endpoints look like non-existent for outsiders.
+pub fn check_admin_secret(secret: &str) -> anyhow::Result<()> {
+    match env::get("ADMIN_SECRET") {
+        Some(expected) if !expected.is_empty() && expected == secret => Ok(()),
+        _ => bail!(CommonError::not_found("Admin endpoint not found")),
+    }
+}
+
 /// Create struct for app scope Error and implement all necessary standard
 /// and actix-web traits for further use as `Responder`.
 ///
@@ -60,6 +85,10 @@ macro_rules! define_app_error {
         impl actix_web::ResponseError for $name {
             fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
                 let status_code = self.status_code();
+                if status_code.is_server_error() {
+                    // logged inside the request, so the log has its request id
+                    log::error!("Request failed with {status_code}: {:#}", self.0);
+                }
                 HttpResponse::build(status_code)
                     .insert_header(ContentType::plaintext())
                     .body(format!("Error code: {}", status_code))
@@ -70,6 +99,7 @@ macro_rules! define_app_error {
                     Some(CommonError::GatewayError(_)) => StatusCode::BAD_GATEWAY,
                     Some(CommonError::InternalError(_)) => StatusCode::INTERNAL_SERVER_ERROR,
                     Some(CommonError::UserError(_)) => StatusCode::BAD_REQUEST,
+                    Some(CommonError::NotFoundError(_)) => StatusCode::NOT_FOUND,
                     None => StatusCode::INTERNAL_SERVER_ERROR,
                 }
             }
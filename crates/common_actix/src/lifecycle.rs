@@ -0,0 +1,119 @@
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use actix_web::{dev::Server, rt::signal::ctrl_c, HttpResponse};
+use common_rust::env;
+use log::{info, warn};
+
+/// Readiness of the app for the `v1/readiness` probe.
+///
+/// Unlike the `v1/health` probe, which answers as soon as the server is started,
+/// the app is ready only after its initialization (e.g. migrations) is complete
+/// and until the shutdown starts, so no traffic is routed to it in between.
+#[derive(Debug, Default)]
+pub struct Readiness(AtomicBool);
+
+impl Readiness {
+    pub fn set_ready(&self, ready: bool) {
+        self.0.store(ready, Ordering::SeqCst);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// `200 OK` if the app is ready, otherwise `503 Service Unavailable`
+    pub fn to_response(&self) -> HttpResponse {
+        match self.is_ready() {
+            true => HttpResponse::Ok().body("Ready"),
+            false => HttpResponse::ServiceUnavailable().body("Not ready"),
+        }
+    }
+}
+
+/// Time for the workers to finish the requests in progress after the shutdown signal,
+/// from `SHUTDOWN_TIMEOUT_SECONDS` environment variable. Default is 30 seconds.
+pub fn get_shutdown_timeout() -> u64 {
+    env::get_parsed_or("SHUTDOWN_TIMEOUT_SECONDS", 30)
+}
+
+/// Run the server until SIGTERM (sent by Docker and Kubernetes on stop) or Ctrl+C.
+///
+/// On the signal the app becomes not ready, the server stops accepting new connections
+/// and waits for the requests in progress. The server shall be built with the disabled
+/// signals, otherwise actix stops it on its own without the readiness change.
+///
+/// After the stop the `on_shutdown` hook saves the state kept in memory,
+/// it is dropped if it takes longer than [get_shutdown_timeout].
+pub async fn run_until_shutdown(
+    server: Server,
+    readiness: Arc<Readiness>,
+    on_shutdown: impl Future<Output = ()>,
+) -> std::io::Result<()> {
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        let signal = shutdown_signal().await;
+        info!("{signal} received, shutting down gracefully");
+        readiness.set_ready(false);
+        handle.stop(true).await;
+    });
+    server.await?;
+    info!("Server stopped");
+    let timeout = Duration::from_secs(get_shutdown_timeout());
+    if actix_web::rt::time::timeout(timeout, on_shutdown)
+        .await
+        .is_err()
+    {
+        warn!("Shutdown hook is not complete in {timeout:?}, the state kept in memory is lost");
+    }
+    Ok(())
+}
+
+/// Wait for the first of the shutdown signals, returns its name
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+        use futures_util::future::{select, Either};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                let sigterm = Box::pin(sigterm.recv());
+                let ctrl_c = Box::pin(ctrl_c());
+                return match select(sigterm, ctrl_c).await {
+                    Either::Left(_) => "SIGTERM",
+                    Either::Right(_) => "Ctrl+C",
+                };
+            }
+            Err(e) => warn!("Cannot listen to SIGTERM, only Ctrl+C stops the server: {e}"),
+        }
+    }
+    let _ = ctrl_c().await;
+    "Ctrl+C"
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::http::StatusCode;
+
+    use super::Readiness;
+
+    #[test]
+    fn test_readiness_response() {
+        let readiness = Readiness::default();
+        assert_eq!(
+            StatusCode::SERVICE_UNAVAILABLE,
+            readiness.to_response().status()
+        );
+        readiness.set_ready(true);
+        assert_eq!(StatusCode::OK, readiness.to_response().status());
+        readiness.set_ready(false);
+        assert!(!readiness.is_ready());
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    body::BoxBody,
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    error::{ErrorInternalServerError, JsonPayloadError},
+    http::header::{self, HeaderValue},
+    Error, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+use futures_util::future::LocalBoxFuture;
+use serde::Serialize;
+
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Format of the response body, chosen by the `Accept` header of the request.
+///
+/// JSON is the default, MessagePack is sent only if the client prefers it,
+/// e.g. `Accept: application/msgpack`. MessagePack payloads are the same DTOs
+/// with the field names, so they are decoded the same way as JSON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResponseFormat {
+    #[default]
+    Json,
+    MessagePack,
+}
+
+impl ResponseFormat {
+    /// Choose the format with the highest quality in the `Accept` header value,
+    /// the first listed one wins on equal quality. Wildcards mean JSON.
+    pub fn negotiate(accept: &str) -> Self {
+        let mut best: Option<(f32, Self)> = None;
+        for media_range in accept.split(',') {
+            let mut params = media_range.split(';').map(str::trim);
+            let format = match params.next().unwrap_or_default() {
+                "application/msgpack" | "application/x-msgpack" => Self::MessagePack,
+                "application/json" | "application/*" | "*/*" => Self::Json,
+                _ => continue,
+            };
+            let quality = params
+                .find_map(|it| it.strip_prefix("q="))
+                .and_then(|it| it.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality > 0.0 && best.map_or(true, |(best, _)| quality > best) {
+                best = Some((quality, format));
+            }
+        }
+        best.map(|(_, format)| format).unwrap_or_default()
+    }
+
+    /// Format negotiated by the [ContentNegotiation] middleware,
+    /// or negotiated right away if the route is not wrapped with it
+    pub fn of(req: &HttpRequest) -> Self {
+        if let Some(format) = req.extensions().get::<Self>() {
+            return *format;
+        }
+        req.headers()
+            .get(header::ACCEPT)
+            .and_then(|it| it.to_str().ok())
+            .map(Self::negotiate)
+            .unwrap_or_default()
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::MessagePack => MSGPACK_CONTENT_TYPE,
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, Error> {
+        match self {
+            Self::Json => {
+                serde_json::to_vec(value).map_err(|e| JsonPayloadError::Serialize(e).into())
+            }
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(ErrorInternalServerError),
+        }
+    }
+}
+
+/// Serializable response in the format negotiated with the client, see [ResponseFormat].
+/// Drop-in replacement of [actix_web::web::Json] for the endpoints used by the mobile apps.
+pub struct Negotiated<T>(pub T);
+
+impl<T: Serialize> Responder for Negotiated<T> {
+    type Body = BoxBody;
+
+    fn respond_to(self, req: &HttpRequest) -> HttpResponse<Self::Body> {
+        let format = ResponseFormat::of(req);
+        match format.serialize(&self.0) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(format.content_type())
+                .body(body),
+            Err(e) => HttpResponse::from_error(e),
+        }
+    }
+}
+
+/// Middleware, which negotiates [ResponseFormat] once per request for the responders
+/// and adds `Vary: Accept` to the responses, so the caches keep both formats apart.
+pub struct ContentNegotiation;
+
+impl<S, B> Transform<S, ServiceRequest> for ContentNegotiation
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ContentNegotiationMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ContentNegotiationMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct ContentNegotiationMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for ContentNegotiationMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let format = ResponseFormat::of(req.request());
+        req.extensions_mut().insert(format);
+        let response = self.service.call(req);
+        Box::pin(async move {
+            let mut response = response.await?;
+            response
+                .headers_mut()
+                .append(header::VARY, HeaderValue::from_static("accept"));
+            Ok(response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{
+        body::to_bytes,
+        http::header,
+        test::{self, TestRequest},
+        web, App, Responder,
+    };
+    use serde::{Deserialize, Serialize};
+
+    use super::{ContentNegotiation, Negotiated, ResponseFormat, MSGPACK_CONTENT_TYPE};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Classes {
+        name: String,
+        place: String,
+        teacher: Option<String>,
+        number: u8,
+    }
+
+    fn week() -> Vec<Classes> {
+        (0..30)
+            .map(|number| Classes {
+                name: "Математический анализ".to_owned(),
+                place: "А-300".to_owned(),
+                teacher: (number % 2 == 0).then(|| "Иванов Иван Иванович".to_owned()),
+                number: number % 7,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ResponseFormat::Json, ResponseFormat::negotiate(""));
+        assert_eq!(ResponseFormat::Json, ResponseFormat::negotiate("*/*"));
+        assert_eq!(
+            ResponseFormat::Json,
+            ResponseFormat::negotiate("application/json, application/msgpack")
+        );
+        assert_eq!(
+            ResponseFormat::MessagePack,
+            ResponseFormat::negotiate("application/msgpack")
+        );
+        assert_eq!(
+            ResponseFormat::MessagePack,
+            ResponseFormat::negotiate("application/json;q=0.5, application/x-msgpack")
+        );
+        assert_eq!(
+            ResponseFormat::Json,
+            ResponseFormat::negotiate("application/msgpack;q=0, */*")
+        );
+        assert_eq!(ResponseFormat::Json, ResponseFormat::negotiate("text/html"));
+    }
+
+    #[actix_web::test]
+    async fn test_msgpack_is_smaller_and_decoded_to_same_value() {
+        let req = TestRequest::default().to_http_request();
+        let json = to_bytes(Negotiated(week()).respond_to(&req).into_body())
+            .await
+            .unwrap();
+
+        let req = TestRequest::default()
+            .insert_header((header::ACCEPT, MSGPACK_CONTENT_TYPE))
+            .to_http_request();
+        let response = Negotiated(week()).respond_to(&req);
+        assert_eq!(
+            MSGPACK_CONTENT_TYPE,
+            response.headers().get(header::CONTENT_TYPE).unwrap()
+        );
+        let msgpack = to_bytes(response.into_body()).await.unwrap();
+
+        assert!(
+            msgpack.len() < json.len(),
+            "msgpack {} bytes, json {} bytes",
+            msgpack.len(),
+            json.len()
+        );
+        assert_eq!(
+            week(),
+            rmp_serde::from_slice::<Vec<Classes>>(&msgpack).unwrap()
+        );
+        assert_eq!(
+            week(),
+            serde_json::from_slice::<Vec<Classes>>(&json).unwrap()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_middleware_adds_vary_header() {
+        let app = test::init_service(
+            App::new()
+                .wrap(ContentNegotiation)
+                .route("/", web::get().to(|| async { Negotiated(week()) })),
+        )
+        .await;
+        let req = TestRequest::get()
+            .uri("/")
+            .insert_header((header::ACCEPT, "application/msgpack"))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(
+            MSGPACK_CONTENT_TYPE,
+            response.headers().get(header::CONTENT_TYPE).unwrap()
+        );
+        assert_eq!("accept", response.headers().get(header::VARY).unwrap());
+    }
+}
@@ -0,0 +1,162 @@
+use chrono::Duration;
+use common_rust::env;
+use log::warn;
+
+/// # CacheConfig
+///
+/// Capacity and expiration policies of the in-memory cache, so the memory usage
+/// can be tuned per deployment without rebuilding the app.
+///
+/// The defaults are overridden with the environment variables (or the config file)
+/// with the common prefix:
+/// - `{prefix}_CAPACITY` — max number of the entries, zero is ignored;
+/// - `{prefix}_MAX_HITS` — expiration by the number of hits;
+/// - `{prefix}_TTL_MIN` or `{prefix}_TTL_HOURS` — expiration by the creation time,
+///   the minutes win if both are set.
+///
+/// ### Example:
+/// ```rust
+/// use chrono::Duration;
+/// use common_config::CacheConfig;
+///
+/// // reads SCHEDULE_CACHE_CAPACITY, SCHEDULE_CACHE_MAX_HITS, SCHEDULE_CACHE_TTL_HOURS, ...
+/// let config = CacheConfig::new(500, Duration::hours(6))
+///     .max_hits(20)
+///     .with_env_prefix("SCHEDULE_CACHE");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub capacity: usize,
+    /// `None` if the entries do not expire by hits
+    pub max_hits: Option<u32>,
+    pub ttl: Duration,
+}
+
+impl CacheConfig {
+    /// Default config, which is used if the environment does not override it
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            max_hits: None,
+            ttl,
+        }
+    }
+
+    pub fn max_hits(mut self, max_hits: u32) -> Self {
+        self.max_hits = Some(max_hits);
+        self
+    }
+
+    /// Override the defaults with the environment variables with the `prefix`
+    pub fn with_env_prefix(self, prefix: &str) -> Self {
+        self.with_vars(prefix, env::get)
+    }
+
+    fn with_vars(self, prefix: &str, get: impl Fn(&str) -> Option<String>) -> Self {
+        let parsed = |suffix: &str| {
+            let key = format!("{prefix}_{suffix}");
+            let value = get(&key)?;
+            value
+                .trim()
+                .parse::<u32>()
+                .map_err(|_| warn!("Ignoring invalid {key}='{value}', it must be a number"))
+                .ok()
+        };
+        // the old names of the ttl variables are still read for the existing deployments
+        let ttl_minutes = parsed("TTL_MIN").or_else(|| parsed("LIFETIME_MINUTES"));
+        let ttl_hours = parsed("TTL_HOURS").or_else(|| parsed("LIFETIME_HOURS"));
+        Self {
+            capacity: match parsed("CAPACITY") {
+                Some(0) => {
+                    warn!("Ignoring zero {prefix}_CAPACITY, the cache cannot be empty");
+                    self.capacity
+                }
+                Some(capacity) => capacity as usize,
+                None => self.capacity,
+            },
+            max_hits: parsed("MAX_HITS").or(self.max_hits),
+            ttl: match (ttl_minutes, ttl_hours) {
+                (Some(minutes), _) => Duration::minutes(minutes as i64),
+                (None, Some(hours)) => Duration::hours(hours as i64),
+                (None, None) => self.ttl,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Duration;
+
+    use super::CacheConfig;
+
+    fn config(vars: &[(&str, &str)]) -> CacheConfig {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        CacheConfig::new(500, Duration::hours(6))
+            .max_hits(20)
+            .with_vars("SCHEDULE_CACHE", |key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn defaults_are_used_without_variables() {
+        assert_eq!(
+            CacheConfig {
+                capacity: 500,
+                max_hits: Some(20),
+                ttl: Duration::hours(6),
+            },
+            config(&[])
+        );
+    }
+
+    #[test]
+    fn variables_override_defaults() {
+        assert_eq!(
+            CacheConfig {
+                capacity: 100,
+                max_hits: Some(5),
+                ttl: Duration::hours(2),
+            },
+            config(&[
+                ("SCHEDULE_CACHE_CAPACITY", "100"),
+                ("SCHEDULE_CACHE_MAX_HITS", "5"),
+                ("SCHEDULE_CACHE_TTL_HOURS", "2"),
+                ("SEARCH_CACHE_CAPACITY", "1"),
+            ])
+        );
+    }
+
+    #[test]
+    fn minutes_win_over_hours() {
+        let both = config(&[
+            ("SCHEDULE_CACHE_TTL_HOURS", "2"),
+            ("SCHEDULE_CACHE_TTL_MIN", "30"),
+        ]);
+        assert_eq!(Duration::minutes(30), both.ttl);
+    }
+
+    #[test]
+    fn old_lifetime_variables_are_supported() {
+        let hours = config(&[("SCHEDULE_CACHE_LIFETIME_HOURS", "12")]);
+        assert_eq!(Duration::hours(12), hours.ttl);
+        let minutes = config(&[("SCHEDULE_CACHE_LIFETIME_MINUTES", "5")]);
+        assert_eq!(Duration::minutes(5), minutes.ttl);
+    }
+
+    #[test]
+    fn invalid_values_are_ignored() {
+        let invalid = config(&[
+            ("SCHEDULE_CACHE_CAPACITY", "0"),
+            ("SCHEDULE_CACHE_MAX_HITS", "many"),
+            ("SCHEDULE_CACHE_TTL_HOURS", "-1"),
+        ]);
+        assert_eq!(500, invalid.capacity);
+        assert_eq!(Some(20), invalid.max_hits);
+        assert_eq!(Duration::hours(6), invalid.ttl);
+    }
+}
@@ -0,0 +1,2 @@
+mod cache;
+pub use cache::*;
@@ -1,8 +1,31 @@
 use anyhow::Context;
 use common_rust::env;
 use deadpool_postgres::{Config, Pool};
+use log::info;
 
-/// Create Database Pool
+/// Database connection pools with simple query routing.
+///
+/// All queries which modify data shall be executed using [Database::write] pool.
+/// Read-only queries (`SELECT`s) shall be executed using [Database::read] pool,
+/// which points to the read replica (if it is configured) or to the primary database.
+pub struct Database {
+    primary: Pool,
+    replica: Option<Pool>,
+}
+
+impl Database {
+    /// Pool for queries which modify data (and for queries which must see the latest writes).
+    pub fn write(&self) -> &Pool {
+        &self.primary
+    }
+
+    /// Pool for read-only queries. Replication lag is possible here.
+    pub fn read(&self) -> &Pool {
+        self.replica.as_ref().unwrap_or(&self.primary)
+    }
+}
+
+/// Create Database with primary and optional read replica pools
 ///
 /// This function internally reads the following environment variables:
 /// - `POSTGRES_PASSWORD` (required)
@@ -10,20 +33,40 @@ use deadpool_postgres::{Config, Pool};
 /// - `POSTGRES_DB` (default is the same as `POSTGRES_USER`)
 /// - `POSTGRES_HOST` (default is `postgres`)
 /// - `POSTGRES_PORT` (default is `5432`)
+/// - `POSTGRES_REPLICA_HOST` (optional, read replica is disabled if not specified)
+/// - `POSTGRES_REPLICA_PORT` (default is the same as `POSTGRES_PORT`)
 ///
-/// You sholud create pool once and use it as a singleton in your application.
-pub fn create_db_pool() -> anyhow::Result<Pool> {
+/// You sholud create database once and use it as a singleton in your application.
+pub fn create_database() -> anyhow::Result<Database> {
+    let postgres_host = env::get_or("POSTGRES_HOST", "postgres");
+    let postgres_port = env::get_parsed_or::<u16>("POSTGRES_PORT", 5432);
+    let primary = create_db_pool(postgres_host, postgres_port)
+        .with_context(|| "Error during primary Postgres Pool creation")?;
+
+    let replica = match env::get("POSTGRES_REPLICA_HOST") {
+        Some(replica_host) => {
+            let replica_port = env::get_parsed_or::<u16>("POSTGRES_REPLICA_PORT", postgres_port);
+            info!("Read replica is enabled: {replica_host}:{replica_port}");
+            Some(
+                create_db_pool(replica_host, replica_port)
+                    .with_context(|| "Error during replica Postgres Pool creation")?,
+            )
+        }
+        None => None,
+    };
+    Ok(Database { primary, replica })
+}
+
+fn create_db_pool(host: String, port: u16) -> anyhow::Result<Pool> {
     let postgres_password =
         env::get("POSTGRES_PASSWORD").expect("Environment variable POSTGRES_PASSWORD not provided");
     let postgres_user = env::get_or("POSTGRES_USER", "postgres");
     let postgres_db = env::get_or("POSTGRES_DB", &postgres_user);
-    let postgres_host = env::get_or("POSTGRES_HOST", "postgres");
-    let postgres_port = env::get_parsed_or::<u16>("POSTGRES_PORT", 5432);
 
     let mut config = Config::new();
     config.dbname = Some(postgres_db);
-    config.host = Some(postgres_host);
-    config.port = Some(postgres_port);
+    config.host = Some(host);
+    config.port = Some(port);
     config.user = Some(postgres_user);
     config.password = Some(postgres_password);
 
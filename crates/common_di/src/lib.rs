@@ -1,7 +1,15 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
 #[macro_export]
 macro_rules! di_constructor {
     ($struct_name:tt { $( $dep_name:ident : $dep_type:ty ),* }) => {
         impl $struct_name {
+            // DI constructors take all dependencies of the component
+            #[allow(clippy::too_many_arguments)]
             pub fn new( $( $dep_name : $dep_type ),* ) -> $struct_name {
                 $struct_name { $( $dep_name ),* }
             }
@@ -9,9 +17,144 @@ macro_rules! di_constructor {
     };
     ($struct_name:tt ( $( $dep_name:ident : $dep_type:ty ),* )) => {
         impl $struct_name {
+            // DI constructors take all dependencies of the component
+            #[allow(clippy::too_many_arguments)]
             pub fn new( $( $dep_name : $dep_type ),* ) -> $struct_name {
                 $struct_name ( $( $dep_name ),* )
             }
         }
     };
 }
+
+/// Define the accessor of the component, which is created once per process on the first call.
+/// Heavyweight components (database pools, HTTP clients, caches) are shared this way
+/// by all app component modules, without passing the clones around:
+///
+/// ```ignore
+/// singleton! {
+///     pub fn schedule_repository() -> ScheduleRepository {
+///         ScheduleRepository::new(mpeix_upstream().as_ref().clone())
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! singleton {
+    ($( $(#[$meta:meta])* $vis:vis fn $name:ident() -> $type:ty $body:block )*) => {
+        $(
+            $(#[$meta])*
+            $vis fn $name() -> ::std::sync::Arc<$type> {
+                static INSTANCE: ::std::sync::OnceLock<::std::sync::Arc<$type>> =
+                    ::std::sync::OnceLock::new();
+                INSTANCE.get_or_init(|| ::std::sync::Arc::new($body)).clone()
+            }
+        )*
+    };
+}
+
+/// Define the accessor of the component, which is created once per [Scope]:
+///
+/// ```ignore
+/// scoped! {
+///     pub fn peer_repository(scope) -> PgPeerRepository {
+///         PgPeerRepository::new(database(scope))
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! scoped {
+    ($( $(#[$meta:meta])* $vis:vis fn $name:ident($scope:ident) -> $type:ty $body:block )*) => {
+        $(
+            $(#[$meta])*
+            $vis fn $name($scope: &$crate::Scope) -> ::std::sync::Arc<$type> {
+                $scope.get_or_init(|| $body)
+            }
+        )*
+    };
+}
+
+/// Components shared within the scope, e.g. within one app instance in tests,
+/// unlike [singleton!] components, which are shared by the whole process.
+///
+/// The scope holds one component of each type.
+#[derive(Default)]
+pub struct Scope {
+    components: Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Scope {
+    /// Get the component of type `T` created in this scope, or create it with `factory`.
+    ///
+    /// The factory may request other components of the scope, so it is called without the lock.
+    /// If two threads create the same component at once, the first created one is kept.
+    pub fn get_or_init<T, F>(&self, factory: F) -> Arc<T>
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> T,
+    {
+        if let Some(component) = self.get::<T>() {
+            return component;
+        }
+        let component = Arc::new(factory());
+        self.components
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<T>())
+            .or_insert(component)
+            .clone()
+            .downcast::<T>()
+            .expect("Components are stored by their TypeId")
+    }
+
+    fn get<T: Send + Sync + 'static>(&self) -> Option<Arc<T>> {
+        self.components
+            .lock()
+            .unwrap()
+            .get(&TypeId::of::<T>())
+            .cloned()
+            .and_then(|it| it.downcast::<T>().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use super::Scope;
+
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Client;
+    struct Repository(Arc<Client>);
+
+    singleton! {
+        fn client() -> Client {
+            CREATED.fetch_add(1, Ordering::SeqCst);
+            Client
+        }
+    }
+
+    scoped! {
+        fn scoped_client(scope) -> Client { Client }
+        fn repository(scope) -> Repository { Repository(scoped_client(scope)) }
+    }
+
+    #[test]
+    fn singleton_is_created_once() {
+        assert!(Arc::ptr_eq(&client(), &client()));
+        assert_eq!(CREATED.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn scoped_components_are_shared_within_scope() {
+        let scope = Scope::default();
+        let repository = repository(&scope);
+        assert!(Arc::ptr_eq(&repository, &self::repository(&scope)));
+        assert!(Arc::ptr_eq(&repository.0, &scoped_client(&scope)));
+
+        let other_scope = Scope::default();
+        assert!(!Arc::ptr_eq(&repository, &self::repository(&other_scope)));
+    }
+}
@@ -2,10 +2,11 @@ use std::fmt::Display;
 
 /// # CommonError
 ///
-/// All errors in this project should be divided into three categories:
+/// All errors in this project should be divided into four categories:
 /// - `InternalError` - errors that occur if the algorithms of this project do not work correctly.
 /// - `GatewayError` - errors that occur when MPEI backend is unavailable.
 /// - `UserError` - errors that occur due to the fact that the user sent incorrect data.
+/// - `NotFoundError` - errors that occur when the requested entity does not exist (anymore).
 ///
 /// All low-level project components should wrap their root/leaf errors with `CommonError`.
 #[derive(Debug, Clone)]
@@ -13,6 +14,7 @@ pub enum CommonError {
     InternalError(String),
     GatewayError(String),
     UserError(String),
+    NotFoundError(String),
 }
 
 impl CommonError {
@@ -30,6 +32,11 @@ impl CommonError {
     pub fn user<E: Display>(e: E) -> CommonError {
         CommonError::UserError(e.to_string())
     }
+
+    /// Alias for [CommonError::NotFoundError], immediately convert argument to string.
+    pub fn not_found<E: Display>(e: E) -> CommonError {
+        CommonError::NotFoundError(e.to_string())
+    }
 }
 
 impl Display for CommonError {
@@ -38,12 +45,26 @@ impl Display for CommonError {
             CommonError::InternalError(s) => writeln!(f, "Internal error: {s}"),
             CommonError::GatewayError(s) => writeln!(f, "Gateway error: {s}"),
             CommonError::UserError(s) => writeln!(f, "User error: {s}"),
+            CommonError::NotFoundError(s) => writeln!(f, "Not found error: {s}"),
         }
     }
 }
 
 impl std::error::Error for CommonError {}
 
+/// Errors of the restix Api methods declared with `#[api(error = "CommonError")]`:
+/// responses which cannot be decoded are internal errors, the rest ones are gateway errors.
+#[cfg(feature = "restix")]
+impl From<restix::Error> for CommonError {
+    fn from(err: restix::Error) -> Self {
+        if err.is_decode() {
+            CommonError::internal(err)
+        } else {
+            CommonError::gateway(err)
+        }
+    }
+}
+
 pub trait CommonErrorExt {
     fn as_common_error(&self) -> Option<CommonError>;
 }
@@ -88,6 +109,15 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_common_error_ext_not_found_error() {
+        let err = create_error(CommonError::not_found(""));
+        assert!(matches!(
+            err.unwrap_err().as_common_error(),
+            Some(CommonError::NotFoundError(_))
+        ));
+    }
+
     #[test]
     fn test_common_error_ext_unknown_error() {
         let err: Result<(), _> = Err(anyhow!("Unknown"))
@@ -0,0 +1,154 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    future::Future,
+    sync::Mutex,
+};
+
+use common_rust::env;
+use log::{error, warn};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
+
+/// Event of the [EventBus], each event type is a separate topic
+pub trait Event: Clone + Send + 'static {
+    /// Name of the topic in the logs
+    const TOPIC: &'static str;
+}
+
+/// Lightweight in-process event bus: typed topics over tokio broadcast channels.
+///
+/// Producers publish the events without knowing who consumes them, e.g. the schedule
+/// changes are published by the use case which fetches the schedules, and saved to the
+/// database by the separate consumer. Events are not persisted: the events published
+/// before the subscription, or without subscribers at all, are dropped.
+pub struct EventBus {
+    capacity: usize,
+    topics: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(env::get_parsed_or("EVENT_BUS_CAPACITY", 1024))
+    }
+}
+
+impl EventBus {
+    /// Bus with topics holding up to `capacity` events not received by the slowest subscriber
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            topics: Default::default(),
+        }
+    }
+
+    /// Publish the event to the current subscribers of its topic,
+    /// returns the number of subscribers which will receive it
+    pub fn publish<E: Event>(&self, event: E) -> usize {
+        self.sender::<E>().send(event).unwrap_or(0)
+    }
+
+    /// Receive all events of the topic published after this call
+    pub fn subscribe<E: Event>(&self) -> Receiver<E> {
+        self.sender::<E>().subscribe()
+    }
+
+    /// Subscribe to the topic and handle its events one by one in the background task.
+    /// Handler errors are only logged. If the handler is too slow, the oldest events
+    /// over the capacity of the topic are skipped.
+    pub fn consume<E, F, Fut>(&self, handler: F)
+    where
+        E: Event,
+        F: Fn(E) -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send,
+    {
+        let mut receiver = self.subscribe::<E>();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => {
+                        if let Err(e) = handler(event).await {
+                            error!("Error while handling '{}' event: {e:#}", E::TOPIC);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Consumer of '{}' events skipped {skipped} events", E::TOPIC)
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    fn sender<E: Event>(&self) -> Sender<E> {
+        self.topics
+            .lock()
+            .unwrap()
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(broadcast::channel::<E>(self.capacity).0))
+            .downcast_ref::<Sender<E>>()
+            .expect("Topics are keyed by the type of their events")
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{Event, EventBus};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Requested(&'static str);
+
+    impl Event for Requested {
+        const TOPIC: &'static str = "requested";
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Changed(u32);
+
+    impl Event for Changed {
+        const TOPIC: &'static str = "changed";
+    }
+
+    #[tokio::test]
+    async fn test_topics_are_separated_by_event_type() {
+        let bus = EventBus::new(16);
+        assert_eq!(0, bus.publish(Requested("dropped")));
+        let mut requested = bus.subscribe::<Requested>();
+        let mut changed = bus.subscribe::<Changed>();
+        assert_eq!(1, bus.publish(Requested("А-08-19")));
+        assert_eq!(1, bus.publish(Changed(2)));
+        assert_eq!(Requested("А-08-19"), requested.recv().await.unwrap());
+        assert_eq!(Changed(2), changed.recv().await.unwrap());
+        assert!(requested.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_consumer_handles_events_in_order() {
+        let bus = EventBus::new(16);
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let consumer_handled = handled.clone();
+        bus.consume(move |event: Changed| {
+            let handled = consumer_handled.clone();
+            async move {
+                anyhow::ensure!(event.0 != 2, "Unlucky number");
+                handled.lock().unwrap().push(event.0);
+                Ok(())
+            }
+        });
+        for n in 1..=3 {
+            bus.publish(Changed(n));
+        }
+        for _ in 0..100 {
+            if handled.lock().unwrap().len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(vec![1, 3], *handled.lock().unwrap());
+    }
+}
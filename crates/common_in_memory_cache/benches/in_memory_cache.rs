@@ -0,0 +1,83 @@
+use std::time::Instant;
+
+use chrono::Duration as ChronoDuration;
+use common_in_memory_cache::InMemoryCache;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::sync::Mutex;
+
+/// The same capacity as the cache of the schedule search results
+const CAPACITY: usize = 3000;
+const KEYS: usize = 10_000;
+
+fn cache() -> InMemoryCache<usize, String> {
+    let mut cache =
+        InMemoryCache::with_capacity(CAPACITY).expires_after_creation(ChronoDuration::hours(1));
+    for key in 0..CAPACITY {
+        cache.insert(key, format!("value {key}"));
+    }
+    cache
+}
+
+fn single_thread(c: &mut Criterion) {
+    let mut group = c.benchmark_group("in_memory_cache");
+    let mut hit_cache = cache();
+    group.bench_function("get_hit", |b| {
+        let mut key = 0;
+        b.iter(|| {
+            key = (key + 1) % CAPACITY;
+            black_box(hit_cache.get(&key).is_some())
+        })
+    });
+    let mut miss_cache = cache();
+    group.bench_function("get_miss", |b| {
+        b.iter(|| black_box(miss_cache.get(&black_box(KEYS)).is_some()))
+    });
+    let mut insert_cache = cache();
+    group.bench_function("insert_evict", |b| {
+        let mut key = CAPACITY;
+        b.iter(|| {
+            key += 1;
+            insert_cache.insert(key, String::new())
+        })
+    });
+    group.finish();
+}
+
+/// The caches are shared by the request handlers behind the mutex,
+/// so every thread does the mix of reads and writes like the search repository:
+/// mostly hits, sometimes the miss followed by the insert
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("in_memory_cache_contention");
+    for threads in [1, 4, 8] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(threads),
+            &threads,
+            |b, &threads| {
+                b.iter_custom(|iters| {
+                    let cache = Mutex::new(cache());
+                    let started_at = Instant::now();
+                    std::thread::scope(|scope| {
+                        for thread in 0..threads {
+                            let cache = &cache;
+                            scope.spawn(move || {
+                                for i in 0..iters as usize {
+                                    let key = (i * 7 + thread * 13) % KEYS;
+                                    let mut cache = cache.blocking_lock();
+                                    if cache.get(&key).is_none() {
+                                        cache.insert(key, String::new());
+                                    }
+                                }
+                            });
+                        }
+                    });
+                    // every thread does `iters` operations, so the contention shows up as the slower operation
+                    started_at.elapsed()
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, single_thread, contention);
+criterion_main!(benches);
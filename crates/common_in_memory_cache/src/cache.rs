@@ -2,6 +2,8 @@ use std::hash::Hash;
 use std::num::NonZeroUsize;
 
 use chrono::{DateTime, Duration, Local};
+use common_config::CacheConfig;
+use common_metrics::CacheAccess;
 use lru::LruCache;
 
 /// # InMemoryCache
@@ -25,6 +27,17 @@ use lru::LruCache;
 ///       .max_hits(10);
 ///   ```
 ///
+/// The capacity and the policies tunable per deployment are taken from [CacheConfig]:
+/// ```ignore
+/// let config = CacheConfig::new(500, Duration::hours(6)).with_env_prefix("SCHEDULE_CACHE");
+/// let mut cache = InMemoryCache::with_config(&config);
+/// ```
+///
+/// Reads of the named caches are counted in the `cache_accesses_total` metric:
+/// ```ignore
+/// let mut cache = InMemoryCache::with_capacity(1000).named("schedule");
+/// ```
+///
 /// ### Example:
 /// ```rust
 /// use common_in_memory_cache::InMemoryCache;
@@ -46,6 +59,7 @@ pub struct InMemoryCache<K: Eq + Hash, V> {
     expires_after_creation: Option<Duration>,
     expires_after_access: Option<Duration>,
     max_hits: Option<u32>,
+    name: Option<&'static str>,
 }
 
 /// # InMemoryCache.Entry
@@ -86,9 +100,26 @@ impl<K: Eq + Hash, V> InMemoryCache<K, V> {
             expires_after_creation: None,
             expires_after_access: None,
             max_hits: None,
+            name: None,
+        }
+    }
+
+    /// Create in-memory cache instance with the capacity, expiration by creation time
+    /// and by number of hits from the `config`.
+    pub fn with_config(config: &CacheConfig) -> Self {
+        let cache = Self::with_capacity(config.capacity).expires_after_creation(config.ttl);
+        match config.max_hits {
+            Some(max_hits) => cache.max_hits(max_hits),
+            None => cache,
         }
     }
 
+    /// Set the name of the cache for the hit/miss metrics, unnamed caches are not counted.
+    pub fn named(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
     /// Set expiration policy by creation time.
     ///
     /// Value stored in the cache will be considered as expired
@@ -116,6 +147,16 @@ impl<K: Eq + Hash, V> InMemoryCache<K, V> {
         self
     }
 
+    /// Replace capacity and expiration policies with the ones of the empty `policies` cache,
+    /// e.g. after the config reload. Entries are kept in the LRU order,
+    /// the least recently used ones are dropped if the new capacity is smaller.
+    pub fn replace_policies(&mut self, policies: Self) {
+        let mut previous = std::mem::replace(self, policies);
+        while let Some((key, entry)) = previous.entries.pop_lru() {
+            self.entries.push(key, entry);
+        }
+    }
+
     /// Insert value into the cache
     ///
     /// If an entry with key `k` already exists in the cache or another cache entry is removed
@@ -156,31 +197,50 @@ impl<K: Eq + Hash, V> InMemoryCache<K, V> {
             .map(|(entry, expired)| (&entry.value, expired))
     }
 
+    /// Get entry from the cache for diagnostics.
+    ///
+    /// Returns tuple of the entry and its expiration flag, like [InMemoryCache::peek].
+    /// Unlike other methods, does not modify last access date, hits number and LRU order.
+    pub fn inspect(&self, key: &K) -> Option<(&'_ Entry<V>, bool)> {
+        self.entries
+            .peek(key)
+            .map(|entry| (entry, self.is_entry_expired(entry)))
+    }
+
+    /// Time when the entry expires according to the time-based expiration policies.
+    ///
+    /// Returns `None` if no time-based policy is set.
+    pub fn expires_at(&self, entry: &Entry<V>) -> Option<DateTime<Local>> {
+        let by_creation = self
+            .expires_after_creation
+            .and_then(|duration| entry.created_at.checked_add_signed(duration));
+        let by_access = self
+            .expires_after_access
+            .and_then(|duration| entry.accessed_at.checked_add_signed(duration));
+        by_creation.into_iter().chain(by_access).min()
+    }
+
     /// For internal use only
     fn get_entry(&mut self, key: &K, keep_expired_value: bool) -> Option<(&'_ Entry<V>, bool)> {
-        let entry = self.entries.get(key);
-        // Check 'created_at' expiration policy
-        let expired = match (self.expires_after_creation, &entry) {
-            (Some(ref duration), Some(entry)) => is_expired(&Some(entry.created_at), duration),
-            (_, _) => false,
+        let Some(expired) = self
+            .entries
+            .peek(key)
+            .map(|entry| self.is_entry_expired(entry))
+        else {
+            self.record_access(CacheAccess::Miss);
+            return None;
         };
-        // Check 'accessed_at' expiration policy
-        let expired = expired
-            || match (self.expires_after_access, &entry) {
-                (Some(ref duration), Some(entry)) => is_expired(&Some(entry.accessed_at), duration),
-                (_, _) => false,
-            };
-        // Check 'max_hits' expiration policy
-        let expired = expired
-            || match (self.max_hits, &entry) {
-                (Some(max_hits), Some(entry)) => max_hits <= entry.hits,
-                (_, _) => false,
-            };
 
         if !keep_expired_value && expired {
             self.entries.pop(key);
+            self.record_access(CacheAccess::Miss);
             return None;
         }
+        self.record_access(if expired {
+            CacheAccess::Stale
+        } else {
+            CacheAccess::Hit
+        });
 
         // Modify last access date and hits number
         if let Some(entry) = self.entries.get_mut(key) {
@@ -192,6 +252,34 @@ impl<K: Eq + Hash, V> InMemoryCache<K, V> {
         self.entries.get(key).map(|entry| (entry, expired))
     }
 
+    /// For internal use only
+    fn record_access(&self, access: CacheAccess) {
+        if let Some(name) = self.name {
+            common_metrics::record_cache_access(name, access);
+        }
+    }
+
+    /// For internal use only
+    fn is_entry_expired(&self, entry: &Entry<V>) -> bool {
+        // Check 'created_at' expiration policy
+        let expired = match self.expires_after_creation {
+            Some(ref duration) => is_expired(&Some(entry.created_at), duration),
+            None => false,
+        };
+        // Check 'accessed_at' expiration policy
+        let expired = expired
+            || match self.expires_after_access {
+                Some(ref duration) => is_expired(&Some(entry.accessed_at), duration),
+                None => false,
+            };
+        // Check 'max_hits' expiration policy
+        expired
+            || match self.max_hits {
+                Some(max_hits) => max_hits <= entry.hits,
+                None => false,
+            }
+    }
+
     /// Returns a bool indicating whether the given key is in the cache.
     /// There are no any checks on expiration or cache modification
     /// during this call.
@@ -222,6 +310,43 @@ mod tests {
         assert_eq!(cache.get(&"World"), Some(&2));
     }
 
+    #[test]
+    fn test_named_cache_accesses_are_counted() {
+        let mut cache = InMemoryCache::with_capacity(10).named("test_named");
+        cache.insert("Hello", 1);
+        cache.get(&"Hello");
+        cache.get(&"World");
+        let metrics = common_metrics::render();
+        assert!(metrics.contains(r#"cache_accesses_total{cache="test_named",result="hit"} 1"#));
+        assert!(metrics.contains(r#"cache_accesses_total{cache="test_named",result="miss"} 1"#));
+    }
+
+    #[test]
+    fn test_replace_policies() {
+        let mut cache = InMemoryCache::with_capacity(3);
+        cache.insert_entry(
+            "Old",
+            Entry {
+                value: 1,
+                created_at: Local::now()
+                    .checked_sub_signed(Duration::minutes(10))
+                    .unwrap(),
+                accessed_at: Local::now(),
+                hits: 0,
+            },
+        );
+        cache.insert("Dropped", 2);
+        cache.insert("Recent", 3);
+        cache.get(&"Old");
+        cache.replace_policies(
+            InMemoryCache::with_capacity(2).expires_after_creation(Duration::minutes(5)),
+        );
+        assert_eq!(cache.get(&"Dropped"), None);
+        assert_eq!(cache.get(&"Recent"), Some(&3));
+        // the entry survives the capacity change, but expires by the new policy
+        assert_eq!(cache.get(&"Old"), None);
+    }
+
     #[test]
     fn test_insert_then_get_create_expired() {
         let mut cache =
@@ -297,6 +422,33 @@ mod tests {
         assert!(cache.get(&3).is_some());
     }
 
+    #[test]
+    fn test_inspect_does_not_count_hits() {
+        let mut cache = InMemoryCache::with_capacity(10)
+            .max_hits(1)
+            .expires_after_creation(Duration::minutes(5));
+        let created_at = Local::now();
+        cache.insert_entry(
+            1,
+            Entry {
+                value: "Lorem",
+                created_at,
+                accessed_at: created_at,
+                hits: 0,
+            },
+        );
+        let (entry, expired) = cache.inspect(&1).unwrap();
+        assert!(!expired);
+        assert_eq!(entry.hits, 0);
+        assert_eq!(
+            cache.expires_at(entry),
+            created_at.checked_add_signed(Duration::minutes(5))
+        );
+        assert_eq!(cache.get(&1), Some(&"Lorem"));
+        assert!(cache.inspect(&1).unwrap().1);
+        assert!(cache.inspect(&2).is_none());
+    }
+
     #[test]
     fn test_maximum_capacity() {
         let mut cache = InMemoryCache::with_capacity(3);
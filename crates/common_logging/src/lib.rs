@@ -0,0 +1,4 @@
+mod logger;
+pub use logger::*;
+mod request_id;
+pub use request_id::*;
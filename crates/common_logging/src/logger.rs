@@ -0,0 +1,176 @@
+use std::{
+    fmt::Display,
+    io::Write,
+    sync::{OnceLock, RwLock},
+};
+
+use anyhow::bail;
+use common_errors::errors::CommonError;
+use common_rust::env;
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::current_request_id;
+
+static LOGGER: OnceLock<ReloadableLogger> = OnceLock::new();
+
+/// Wrapper around [env_logger::Logger] which allows to replace its filter at runtime.
+struct ReloadableLogger {
+    inner: RwLock<FilteredLogger>,
+}
+
+struct FilteredLogger {
+    filter: String,
+    json: bool,
+    logger: env_logger::Logger,
+}
+
+impl FilteredLogger {
+    fn new(filter: &str, json: bool) -> Self {
+        let mut builder = env_logger::Builder::new();
+        builder.parse_filters(filter);
+        if json {
+            builder.format(|buf, record| {
+                let timestamp = buf.timestamp_millis();
+                writeln!(buf, "{}", json_line(timestamp, record))
+            });
+        }
+        let logger = builder.build();
+        log::set_max_level(logger.filter());
+        Self {
+            filter: filter.to_owned(),
+            json,
+            logger,
+        }
+    }
+}
+
+impl Log for ReloadableLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.read().unwrap().logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.inner.read().unwrap().logger.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.read().unwrap().logger.flush()
+    }
+}
+
+/// Initialize global logger with the filter from `RUST_LOG` environment variable.
+/// Works like `env_logger::init()`, but the filter can be changed later with [set_log_filter].
+///
+/// Logs are written as JSON lines with the request id of the current request,
+/// unless `LOG_FORMAT` environment variable is `text`.
+pub fn init_logger() {
+    let filter = std::env::var("RUST_LOG").unwrap_or_else(|_| "error".to_owned());
+    let json = env::get_or("LOG_FORMAT", "json") != "text";
+    let logger = LOGGER.get_or_init(|| ReloadableLogger {
+        inner: RwLock::new(FilteredLogger::new(&filter, json)),
+    });
+    log::set_logger(logger).expect("Logger should be initialized only once");
+}
+
+/// Current log filter in `RUST_LOG` format, e.g. `info,domain_schedule::schedule=debug`
+pub fn get_log_filter() -> Option<String> {
+    LOGGER
+        .get()
+        .map(|logger| logger.inner.read().unwrap().filter.to_owned())
+}
+
+/// Replace log filter of the global logger without restart.
+/// Filter has the same format as the `RUST_LOG` environment variable.
+pub fn set_log_filter(filter: &str) -> anyhow::Result<()> {
+    validate_log_filter(filter)?;
+    let Some(logger) = LOGGER.get() else {
+        bail!(CommonError::internal("Logger is not initialized"));
+    };
+    let mut inner = logger.inner.write().unwrap();
+    *inner = FilteredLogger::new(filter, inner.json);
+    Ok(())
+}
+
+/// One log record as JSON object, e.g.
+/// `{"level":"ERROR","message":"...","request_id":"3f2a9c0d1e4b5a67","target":"domain_schedule","timestamp":"..."}`
+fn json_line(timestamp: impl Display, record: &Record) -> String {
+    let mut line = serde_json::json!({
+        "timestamp": timestamp.to_string(),
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    });
+    if let Some(request_id) = current_request_id() {
+        line["request_id"] = request_id.into();
+    }
+    line.to_string()
+}
+
+/// `env_logger` silently ignores invalid directives, so we check them beforehand
+fn validate_log_filter(filter: &str) -> anyhow::Result<()> {
+    if filter.trim().is_empty() {
+        bail!(CommonError::user("Log filter should not be empty"));
+    }
+    // everything after `/` is a regex for log messages
+    let directives = filter.split('/').next().unwrap_or_default();
+    for directive in directives.split(',').map(str::trim) {
+        let (module, level) = match directive.split_once('=') {
+            Some((module, level)) => (module, Some(level)),
+            None if directive.parse::<LevelFilter>().is_ok() => ("", Some(directive)),
+            None => (directive, None),
+        };
+        let valid_module = module
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':');
+        let valid_level = level.map_or(true, |it| it.parse::<LevelFilter>().is_ok());
+        if directive.is_empty() || !valid_module || !valid_level {
+            bail!(CommonError::user(format!(
+                "Invalid log filter directive: `{directive}`"
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_log_filter() {
+        assert!(validate_log_filter("debug").is_ok());
+        assert!(validate_log_filter("info,domain_schedule::schedule=debug").is_ok());
+        assert!(validate_log_filter("domain_bot").is_ok());
+        assert!(validate_log_filter("warn,actix_web=off/timeout").is_ok());
+        assert!(validate_log_filter("").is_err());
+        assert!(validate_log_filter("info,").is_err());
+        assert!(validate_log_filter("domain_bot=loud").is_err());
+        assert!(validate_log_filter("domain bot=info").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_json_line() {
+        let line = |timestamp| {
+            json_line(
+                timestamp,
+                &Record::builder()
+                    .args(format_args!("Schedule \"А-08-19\" not found"))
+                    .level(log::Level::Warn)
+                    .target("domain_schedule")
+                    .build(),
+            )
+        };
+        assert_eq!(
+            r#"{"level":"WARN","message":"Schedule \"А-08-19\" not found","target":"domain_schedule","timestamp":"2023-03-08T12:00:00.000Z"}"#,
+            line("2023-03-08T12:00:00.000Z"),
+        );
+        let line = crate::with_request_id("3f2a9c0d1e4b5a67".to_owned(), async {
+            line("2023-03-08T12:00:00.000Z")
+        })
+        .await;
+        assert!(
+            line.contains(r#""request_id":"3f2a9c0d1e4b5a67""#),
+            "{line}"
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use std::{
+    future::{ready, Future, Ready},
+    rc::Rc,
+};
+
+use actix_web::{
+    dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Logger,
+    Error,
+};
+use futures_util::future::LocalBoxFuture;
+
+/// Header with the correlation id of the request, it is accepted from the clients
+/// (e.g. from the reverse proxy) and returned in every response
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+const REQUEST_ID_MAX_LENGTH: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Correlation id of the request processed by the current task, if any.
+/// Logs of use cases and repositories are marked with it, so errors of the MPEI calls
+/// can be traced to the original user request.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|it| it.to_owned()).ok()
+}
+
+/// Run the `future` with the `request_id`, so all its logs are marked with it.
+/// Requests to the actix apps get the request id from the [RequestId] middleware.
+pub async fn with_request_id<F: Future>(request_id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(request_id, future).await
+}
+
+/// Access log with the request id, should wrap [RequestId] middleware
+/// to see the response header
+pub fn access_logger() -> Logger {
+    Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T request_id=%{x-request-id}o"#)
+}
+
+/// Middleware, which generates the correlation id for each request
+/// (or takes the valid one from [REQUEST_ID_HEADER]) and processes the request with it.
+pub struct RequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let request_id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|it| it.to_str().ok())
+            .filter(|it| is_valid_request_id(it))
+            .map(str::to_owned)
+            .unwrap_or_else(generate_request_id);
+        // inner services may start the work right in `call`, not only in the returned future
+        let response = REQUEST_ID.sync_scope(request_id.clone(), || self.service.call(req));
+        Box::pin(REQUEST_ID.scope(request_id.clone(), async move {
+            let mut response = response.await?;
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            Ok(response)
+        }))
+    }
+}
+
+fn generate_request_id() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Request ids from the clients get into the logs and the headers, so only short
+/// ids without special characters are accepted
+fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= REQUEST_ID_MAX_LENGTH
+        && request_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_request_id() {
+        assert!(is_valid_request_id("3f2a9c0d1e4b5a67"));
+        assert!(is_valid_request_id("b7e1c2d4-0a9f-4c1e-9d2b-5e6f7a8b9c0d"));
+        assert!(is_valid_request_id(&generate_request_id()));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id("id\",\"level\":\"ERROR"));
+        assert!(!is_valid_request_id(&"a".repeat(65)));
+    }
+
+    #[tokio::test]
+    async fn test_request_id_scope() {
+        assert_eq!(current_request_id(), None);
+        let request_id = with_request_id("abc".to_owned(), async { current_request_id() }).await;
+        assert_eq!(request_id.as_deref(), Some("abc"));
+        assert_eq!(current_request_id(), None);
+    }
+}
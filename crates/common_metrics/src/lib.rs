@@ -0,0 +1,8 @@
+mod middleware;
+mod registry;
+
+pub use middleware::{HttpMetrics, HttpMetricsMiddleware};
+pub use registry::{
+    record_cache_access, record_cooldown_activation, record_http_request, record_mpei_request,
+    record_mpei_request_phases, render, CacheAccess,
+};
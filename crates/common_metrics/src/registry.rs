@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+/// Metrics are global, like the logger: they are recorded deep inside
+/// the repositories and the caches, which know nothing about the app state
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+static HTTP_REQUESTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("http_requests_total", "Requests to the service"),
+        &["method", "path", "status"],
+    ))
+});
+
+static MPEI_REQUEST_DURATION: Lazy<HistogramVec> = Lazy::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "mpei_request_duration_seconds",
+            "Latency of the requests to the MPEI backend",
+        ),
+        &["endpoint", "status"],
+    ))
+});
+
+static MPEI_REQUEST_PHASES: Lazy<HistogramVec> = Lazy::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "mpei_request_phase_seconds",
+            "Phases of the requests to the MPEI backend: DNS lookup and time to the first byte",
+        ),
+        &["phase", "connection"],
+    ))
+});
+
+static CACHE_ACCESSES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "cache_accesses_total",
+            "Reads of the in-memory caches by the result",
+        ),
+        &["cache", "result"],
+    ))
+});
+
+static COOLDOWN_ACTIVATIONS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new(
+        "schedule_cooldown_activations_total",
+        "Activations of the MPEI backend cooldown after its errors",
+    ))
+});
+
+fn register<M>(metric: prometheus::Result<M>) -> M
+where
+    M: prometheus::core::Collector + Clone + 'static,
+{
+    let metric = metric.expect("Metric options are static and valid");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("Each metric is registered once");
+    metric
+}
+
+/// Result of the in-memory cache read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheAccess {
+    Hit,
+    /// Expired value is returned, e.g. during the cooldown
+    Stale,
+    Miss,
+}
+
+impl CacheAccess {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CacheAccess::Hit => "hit",
+            CacheAccess::Stale => "stale",
+            CacheAccess::Miss => "miss",
+        }
+    }
+}
+
+/// `path` is the route pattern like `/v1/{type}/{name}/id`, not the actual path,
+/// otherwise every schedule name becomes a separate time series
+pub fn record_http_request(method: &str, path: &str, status: u16) {
+    HTTP_REQUESTS
+        .with_label_values(&[method, path, &status.to_string()])
+        .inc();
+}
+
+/// `status` is `0` if the request failed without a response, e.g. on timeout
+pub fn record_mpei_request(endpoint: &str, status: u16, latency: Duration) {
+    MPEI_REQUEST_DURATION
+        .with_label_values(&[endpoint, &status.to_string()])
+        .observe(latency.as_secs_f64());
+}
+
+/// `dns` is `None` if the pooled connection was reused
+pub fn record_mpei_request_phases(dns: Option<Duration>, first_byte: Duration) {
+    let connection = if dns.is_some() { "new" } else { "reused" };
+    if let Some(dns) = dns {
+        MPEI_REQUEST_PHASES
+            .with_label_values(&["dns", connection])
+            .observe(dns.as_secs_f64());
+    }
+    MPEI_REQUEST_PHASES
+        .with_label_values(&["first_byte", connection])
+        .observe(first_byte.as_secs_f64());
+}
+
+pub fn record_cache_access(cache: &str, access: CacheAccess) {
+    CACHE_ACCESSES
+        .with_label_values(&[cache, access.as_str()])
+        .inc();
+}
+
+pub fn record_cooldown_activation() {
+    COOLDOWN_ACTIVATIONS.inc();
+}
+
+/// All recorded metrics in the Prometheus text format
+pub fn render() -> String {
+    // metrics are registered lazily, so the never recorded ones are forced
+    // to be exported with zero values
+    Lazy::force(&HTTP_REQUESTS);
+    Lazy::force(&MPEI_REQUEST_DURATION);
+    Lazy::force(&MPEI_REQUEST_PHASES);
+    Lazy::force(&CACHE_ACCESSES);
+    Lazy::force(&COOLDOWN_ACTIVATIONS);
+    let mut buf = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buf)
+        .expect("Text encoding of the metrics does not fail");
+    String::from_utf8(buf).expect("Metrics are valid UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        record_http_request("GET", "/v1/{type}/{name}/id", 200);
+        record_mpei_request("/api/search", 200, Duration::from_millis(120));
+        record_cache_access("schedule", CacheAccess::Hit);
+        record_cache_access("schedule", CacheAccess::Miss);
+        record_cooldown_activation();
+        record_mpei_request_phases(Some(Duration::from_millis(5)), Duration::from_millis(80));
+
+        let metrics = render();
+        assert!(metrics.contains(
+            r#"http_requests_total{method="GET",path="/v1/{type}/{name}/id",status="200"} 1"#
+        ));
+        assert!(metrics.contains(
+            r#"mpei_request_duration_seconds_count{endpoint="/api/search",status="200"} 1"#
+        ));
+        assert!(metrics.contains(r#"cache_accesses_total{cache="schedule",result="hit"} 1"#));
+        assert!(metrics.contains(r#"cache_accesses_total{cache="schedule",result="miss"} 1"#));
+        assert!(metrics.contains("schedule_cooldown_activations_total 1"));
+        assert!(
+            metrics.contains(r#"mpei_request_phase_seconds_count{connection="new",phase="dns"} 1"#)
+        );
+    }
+}
@@ -2,6 +2,10 @@ use anyhow::anyhow;
 use common_errors::errors::CommonError;
 use common_rust::env;
 
+mod retry;
+
+pub use retry::{RetryConfig, RetryInterceptor};
+
 pub trait ResultExt<T>
 where
     Self: Sized,
@@ -11,13 +15,7 @@ where
 
 impl<T> ResultExt<T> for reqwest::Result<T> {
     fn with_common_error(self) -> anyhow::Result<T> {
-        self.map_err(|err| {
-            if err.is_decode() {
-                anyhow!(CommonError::internal(err))
-            } else {
-                anyhow!(CommonError::gateway(err))
-            }
-        })
+        self.map_err(|err| anyhow!(CommonError::from(err)))
     }
 }
 
@@ -30,6 +28,8 @@ pub fn create_reqwest_client() -> reqwest::Client {
         .timeout(std::time::Duration::from_secs(15))
         .connect_timeout(std::time::Duration::from_millis(connect_timeout))
         .pool_max_idle_per_host(3)
+        // DNS lookups for the call timings, see `on_timings` of the restix Api builders
+        .dns_resolver(std::sync::Arc::new(restix::TimingResolver))
         .build()
         .expect("Error while building reqwest::Client")
 }
@@ -0,0 +1,163 @@
+use std::{sync::Arc, time::Duration};
+
+use common_rust::{env, Reloadable};
+use log::warn;
+use reqwest::{Request, Response, StatusCode};
+use restix::{BoxFuture, Interceptor, Next};
+
+/// Configuration of [RetryInterceptor]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryConfig {
+    /// Max number of repeated requests, zero disables retries
+    pub retries: u32,
+    /// Delay before the first retry, it is doubled for each next one
+    pub backoff_base: Duration,
+    /// Max random delay added to the backoff, so the retries of the concurrent
+    /// requests do not hit the server at the same moment
+    pub jitter: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            retries: env::get_parsed_or("GATEWAY_RETRIES", 2),
+            backoff_base: Duration::from_millis(env::get_parsed_or(
+                "GATEWAY_RETRY_BACKOFF_MS",
+                200,
+            )),
+            jitter: Duration::from_millis(env::get_parsed_or("GATEWAY_RETRY_JITTER_MS", 100)),
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay before the retry with the zero-based number `attempt`, without the jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.backoff_base
+            .saturating_mul(2u32.saturating_pow(attempt.min(16)))
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        let jitter = match jitter_ms {
+            0 => Duration::ZERO,
+            _ => Duration::from_millis(rand::random::<u64>() % (jitter_ms + 1)),
+        };
+        self.backoff(attempt) + jitter
+    }
+}
+
+/// Retries the requests failed with the transient errors: connection errors and
+/// `500`, `502`, `503`, `504` responses. Timeouts are not retried, the server is too slow anyway.
+///
+/// Api instances have the only interceptor, so the other one (e.g. audit)
+/// can be wrapped with `inner`, then each attempt goes through it.
+pub struct RetryInterceptor {
+    config: Reloadable<RetryConfig>,
+    inner: Option<Arc<dyn Interceptor>>,
+}
+
+impl RetryInterceptor {
+    pub fn new(inner: Option<Arc<dyn Interceptor>>) -> Self {
+        Self {
+            config: Default::default(),
+            inner,
+        }
+    }
+
+    async fn run<'a>(&'a self, request: Request, next: Next<'a>) -> reqwest::Result<Response> {
+        match &self.inner {
+            Some(inner) => inner.intercept(request, next).await,
+            None => next.run(request).await,
+        }
+    }
+}
+
+impl Interceptor for RetryInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<Response>> {
+        Box::pin(async move {
+            let config = self.config.load();
+            let mut attempt = 0;
+            loop {
+                // requests with streaming bodies cannot be repeated
+                let retry_request = (attempt < config.retries)
+                    .then(|| request.try_clone())
+                    .flatten();
+                let Some(retry_request) = retry_request else {
+                    return self.run(request, next).await;
+                };
+                let response = self.run(retry_request, next).await;
+                if !is_transient(&response) {
+                    return response;
+                }
+                let delay = config.delay(attempt);
+                warn!(
+                    "Transient error of {} {}, retry {} of {} in {} ms",
+                    request.method(),
+                    request.url().path(),
+                    attempt + 1,
+                    config.retries,
+                    delay.as_millis(),
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+fn is_transient(response: &reqwest::Result<Response>) -> bool {
+    match response {
+        Ok(response) => is_transient_status(response.status()),
+        Err(e) => e.is_connect() || e.status().is_some_and(is_transient_status),
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+
+    use super::{is_transient_status, RetryConfig};
+
+    #[test]
+    fn test_backoff_is_doubled() {
+        let config = RetryConfig {
+            retries: 3,
+            backoff_base: Duration::from_millis(200),
+            jitter: Duration::from_millis(100),
+        };
+        assert_eq!(Duration::from_millis(200), config.backoff(0));
+        assert_eq!(Duration::from_millis(400), config.backoff(1));
+        assert_eq!(Duration::from_millis(800), config.backoff(2));
+        for attempt in 0..3 {
+            let delay = config.delay(attempt);
+            assert!(delay >= config.backoff(attempt));
+            assert!(delay <= config.backoff(attempt) + config.jitter);
+        }
+    }
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::OK));
+        assert!(!is_transient_status(StatusCode::NOT_IMPLEMENTED));
+    }
+}
@@ -1,44 +1,158 @@
+mod reloadable;
+pub use reloadable::*;
+
 pub mod env {
-    use std::str::FromStr;
+    use std::{
+        collections::{BTreeSet, HashMap},
+        str::FromStr,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, OnceLock,
+        },
+    };
+
+    use anyhow::{bail, Context};
+    use arc_swap::ArcSwap;
+
+    /// Variables of the config file from `CONFIG_PATH`, they override the process environment
+    static CONFIG_FILE: OnceLock<ArcSwap<HashMap<String, String>>> = OnceLock::new();
+    /// Number of config file reloads, see [crate::Reloadable]
+    static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
 
     /// Get environment variable value if exists.
     /// In case of errors return [Option::None].
     #[inline]
     pub fn get(key: &str) -> Option<String> {
-        std::env::var(key).ok()
+        config_file()
+            .load()
+            .get(key)
+            .cloned()
+            .or_else(|| std::env::var(key).ok())
     }
 
     /// Get environment variable value if exists.
     /// In case of errors return `default`.
     #[inline]
     pub fn get_or<S: AsRef<str>>(key: &str, default: S) -> String {
-        std::env::var(key).unwrap_or_else(|_| default.as_ref().to_owned())
+        get(key).unwrap_or_else(|| default.as_ref().to_owned())
     }
 
     /// Get environment variable value if exists, then parse it to necessary type.
     /// In case of errors return [Option::None].
     #[inline]
     pub fn get_parsed<T: FromStr>(key: &str) -> Option<T> {
-        match std::env::var(key) {
-            Ok(var) => var.parse::<T>().ok(),
-            Err(_) => None,
-        }
+        get(key).and_then(|var| var.parse::<T>().ok())
     }
 
     /// Get environment variable value if exists, then parse it to necessary type.
     /// In case of errors return `default`.
     #[inline]
     pub fn get_parsed_or<T: FromStr>(key: &str, default: T) -> T {
-        match std::env::var(key) {
-            Ok(var) => var.parse::<T>().unwrap_or(default),
-            Err(_) => default,
-        }
+        get_parsed(key).unwrap_or(default)
     }
 
     /// Get environment variable value if exists,
     /// or panic with readable description.
     #[inline]
     pub fn required(key: &str) -> String {
-        std::env::var(key).unwrap_or_else(|_| panic!("Environment variable {key} not provided"))
+        get(key).unwrap_or_else(|| panic!("Environment variable {key} not provided"))
+    }
+
+    /// Re-read the config file from `CONFIG_PATH` and atomically replace its variables.
+    /// Returns the names of the changed variables, in alphabetical order.
+    ///
+    /// Only the values, which are read after the reload, are affected:
+    /// components with config snapshots should use [crate::Reloadable].
+    pub fn reload_config_file() -> anyhow::Result<Vec<String>> {
+        let variables = Arc::new(read_config_file()?);
+        let previous = config_file().swap(variables.clone());
+        CONFIG_GENERATION.fetch_add(1, Ordering::SeqCst);
+        Ok(previous
+            .keys()
+            .chain(variables.keys())
+            .filter(|key| previous.get(*key) != variables.get(*key))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect())
+    }
+
+    /// Number of config file reloads since the start
+    pub fn config_generation() -> u64 {
+        CONFIG_GENERATION.load(Ordering::SeqCst)
+    }
+
+    fn config_file() -> &'static ArcSwap<HashMap<String, String>> {
+        CONFIG_FILE
+            .get_or_init(|| ArcSwap::from_pointee(read_config_file().expect("Invalid config file")))
+    }
+
+    fn read_config_file() -> anyhow::Result<HashMap<String, String>> {
+        let Some(path) = std::env::var("CONFIG_PATH")
+            .ok()
+            .filter(|it| !it.is_empty())
+        else {
+            return Ok(HashMap::new());
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Cannot read config file '{path}'"))?;
+        parse_config_file(&content).with_context(|| format!("Invalid config file '{path}'"))
+    }
+
+    /// Parse `KEY=VALUE` lines like in `.env` files, values may be quoted,
+    /// empty lines and lines starting with `#` are skipped
+    fn parse_config_file(content: &str) -> anyhow::Result<HashMap<String, String>> {
+        let mut variables = HashMap::new();
+        for (number, line) in content
+            .lines()
+            .enumerate()
+            .map(|(i, it)| (i + 1, it.trim()))
+        {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                bail!("Line {number} is not `KEY=VALUE`");
+            };
+            let key = key.trim();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                bail!("Line {number} has invalid variable name `{key}`");
+            }
+            let value = value.trim();
+            let value = ['"', '\'']
+                .into_iter()
+                .find_map(|quote| {
+                    value
+                        .strip_prefix(quote)
+                        .and_then(|it| it.strip_suffix(quote))
+                })
+                .unwrap_or(value);
+            variables.insert(key.to_owned(), value.to_owned());
+        }
+        Ok(variables)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse_config_file() {
+            let variables = parse_config_file(
+                "# cache tuning\n\
+                 SCHEDULE_CACHE_LIFETIME_HOURS=12\n\
+                 \n\
+                 SCHEDULE_PREFETCH_WEEKDAYS = \"sat,sun\"\n\
+                 MEILISEARCH_API_KEY='a=b'\n",
+            )
+            .unwrap();
+            assert_eq!(3, variables.len());
+            assert_eq!("12", variables["SCHEDULE_CACHE_LIFETIME_HOURS"]);
+            assert_eq!("sat,sun", variables["SCHEDULE_PREFETCH_WEEKDAYS"]);
+            assert_eq!("a=b", variables["MEILISEARCH_API_KEY"]);
+            assert!(parse_config_file("SCHEDULE_CACHE_LIFETIME_HOURS").is_err());
+            assert!(parse_config_file("=12").is_err());
+            assert!(parse_config_file("CACHE LIFETIME=12").is_err());
+        }
     }
 }
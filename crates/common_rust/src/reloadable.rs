@@ -0,0 +1,73 @@
+use std::{ops::Deref, sync::Arc};
+
+use arc_swap::ArcSwap;
+
+use crate::env;
+
+/// Config snapshot, which is read with [Default] (usually from the environment variables)
+/// and is read again after [env::reload_config_file].
+///
+/// The snapshot is replaced atomically, so the readers see either the old or the new config,
+/// and keep the loaded snapshot until they drop it:
+/// ```
+/// use common_rust::Reloadable;
+///
+/// #[derive(Default)]
+/// struct PrefetchConfig {
+///     budget: usize,
+/// }
+///
+/// let config = Reloadable::<PrefetchConfig>::default();
+/// assert_eq!(config.load().budget, 0);
+/// ```
+pub struct Reloadable<T> {
+    snapshot: ArcSwap<Snapshot<T>>,
+}
+
+pub struct Snapshot<T> {
+    generation: u64,
+    value: T,
+}
+
+impl<T: Default> Reloadable<T> {
+    /// Current config, read again if the config file was reloaded since the last read
+    pub fn load(&self) -> Arc<Snapshot<T>> {
+        let snapshot = self.snapshot.load_full();
+        let generation = env::config_generation();
+        if snapshot.generation == generation {
+            return snapshot;
+        }
+        let snapshot = Arc::new(Snapshot {
+            generation,
+            value: T::default(),
+        });
+        self.snapshot.store(snapshot.clone());
+        snapshot
+    }
+}
+
+impl<T: Default> Default for Reloadable<T> {
+    fn default() -> Self {
+        Self::from(T::default())
+    }
+}
+
+impl<T> From<T> for Reloadable<T> {
+    /// Snapshot of the specific config, e.g. in tests
+    fn from(value: T) -> Self {
+        Self {
+            snapshot: ArcSwap::from_pointee(Snapshot {
+                generation: env::config_generation(),
+                value,
+            }),
+        }
+    }
+}
+
+impl<T> Deref for Snapshot<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Window of rows to select: `LIMIT {limit} OFFSET {offset}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Page {
+    /// Max limit of pages requested by clients
+    pub const MAX_LIMIT: usize = 100;
+
+    pub fn new(offset: usize, limit: usize) -> Self {
+        Self { offset, limit }
+    }
+
+    /// First `limit` rows
+    pub fn first(limit: usize) -> Self {
+        Self::new(0, limit)
+    }
+
+    /// Page from the query string parameters. Limit is clamped to [Page::MAX_LIMIT],
+    /// so the client can not ask for the whole table in a single request.
+    pub fn from_query(offset: Option<usize>, limit: Option<usize>, default_limit: usize) -> Self {
+        Self::new(
+            offset.unwrap_or(0),
+            limit.unwrap_or(default_limit).min(Self::MAX_LIMIT),
+        )
+    }
+}
+
+impl Display for Page {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LIMIT {} OFFSET {}", self.limit, self.offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// Sort expression for the `ORDER BY` clause.
+///
+/// Column names can only come from the whitelist passed to [Sort::parse],
+/// so it is safe to put the sort into the statement text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    column: &'static str,
+    order: SortOrder,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseSortError(String);
+
+impl Sort {
+    pub const fn asc(column: &'static str) -> Self {
+        Self {
+            column,
+            order: SortOrder::Asc,
+        }
+    }
+
+    pub const fn desc(column: &'static str) -> Self {
+        Self {
+            column,
+            order: SortOrder::Desc,
+        }
+    }
+
+    /// Parse sort from the query string value: `column` for ascending order,
+    /// `-column` for descending order. Column should be one of the `allowed`.
+    pub fn parse(value: &str, allowed: &[&'static str]) -> Result<Self, ParseSortError> {
+        let (column, order) = match value.strip_prefix('-') {
+            Some(column) => (column, SortOrder::Desc),
+            None => (value, SortOrder::Asc),
+        };
+        allowed
+            .iter()
+            .find(|it| **it == column)
+            .map(|column| Self { column, order })
+            .ok_or_else(|| ParseSortError(column.to_owned()))
+    }
+}
+
+impl Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.order {
+            SortOrder::Asc => write!(f, "{} ASC", self.column),
+            SortOrder::Desc => write!(f, "{} DESC", self.column),
+        }
+    }
+}
+
+impl Display for ParseSortError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sorting by '{}' is not supported", self.0)
+    }
+}
+
+impl std::error::Error for ParseSortError {}
+
+/// Response envelope for paginated lists.
+/// `nextOffset` is absent when there are no more items.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Paged<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+impl<T> Paged<T> {
+    pub fn new(items: Vec<T>, total: usize, page: &Page) -> Self {
+        let next_offset = Some(page.offset + items.len())
+            .filter(|next_offset| !items.is_empty() && *next_offset < total);
+        Self {
+            items,
+            total,
+            next_offset,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_to_sql() {
+        assert_eq!("LIMIT 30 OFFSET 0", Page::first(30).to_string());
+        assert_eq!("LIMIT 10 OFFSET 20", Page::new(20, 10).to_string());
+        assert_eq!(Page::new(0, 50), Page::from_query(None, None, 50));
+        assert_eq!(
+            Page::new(5, Page::MAX_LIMIT),
+            Page::from_query(Some(5), Some(100_000), 50)
+        );
+    }
+
+    #[test]
+    fn test_sort_whitelist() {
+        let allowed = ["name", "requests"];
+        assert_eq!(Ok(Sort::asc("name")), Sort::parse("name", &allowed));
+        assert_eq!(
+            Ok(Sort::desc("requests")),
+            Sort::parse("-requests", &allowed)
+        );
+        assert!(Sort::parse("name; DROP TABLE peer", &allowed).is_err());
+        assert!(Sort::parse("--name", &allowed).is_err());
+        assert_eq!("requests DESC", Sort::desc("requests").to_string());
+    }
+
+    #[test]
+    fn test_paged_next_offset() {
+        let page = Page::new(10, 10);
+        assert_eq!(Some(20), Paged::new(vec![0; 10], 25, &page).next_offset);
+        assert_eq!(None, Paged::new(vec![0; 10], 20, &page).next_offset);
+        assert_eq!(None, Paged::<i32>::new(vec![], 5, &page).next_offset);
+    }
+}
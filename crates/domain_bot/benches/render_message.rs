@@ -0,0 +1,96 @@
+use chrono::{Days, NaiveDate, NaiveTime};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use domain_bot::{
+    models::{Locale, Reply},
+    renderer::{render_message, RenderTargetPlatform},
+    theme::ThemeKind,
+};
+use domain_schedule_models::{Classes, ClassesTime, ClassesType, Day, ScheduleType, Week};
+
+/// The busiest weeks of the evening programs: six days with seven classes each
+const DAYS: u64 = 6;
+const CLASSES_PER_DAY: u32 = 7;
+
+fn classes(number: u32) -> Classes {
+    let start = NaiveTime::from_hms_opt(9 + number * 2, 20, 0).unwrap();
+    Classes {
+        name: "Теоретические основы электротехники".to_owned(),
+        r#type: ClassesType::Lecture,
+        raw_type: "Лекция".to_owned(),
+        place: "Б-205".to_owned(),
+        groups: "А-08-19, А-12-19, А-13-19".to_owned(),
+        person: "Иванов Иван Иванович".to_owned(),
+        time: ClassesTime {
+            start,
+            end: start + chrono::Duration::minutes(95),
+        },
+        number: number as i8 + 1,
+        status: Default::default(),
+    }
+}
+
+fn large_week() -> Week {
+    let first_day = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+    Week {
+        week_of_year: 11,
+        week_of_semester: 6,
+        first_day_of_week: first_day,
+        days: (0..DAYS)
+            .map(|offset| Day {
+                day_of_week: offset as u8 + 1,
+                date: first_day.checked_add_days(Days::new(offset)).unwrap(),
+                classes: (0..CLASSES_PER_DAY).map(classes).collect(),
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: false,
+            })
+            .collect(),
+        period_type: Default::default(),
+        last_synced_at: None,
+    }
+}
+
+fn week_reply(full: bool) -> Reply {
+    Reply::Week {
+        schedule_name: "А-08-19".to_owned(),
+        week_offset: 0,
+        week: large_week(),
+        schedule_type: ScheduleType::Group,
+        map_provider: None,
+        notes: Vec::new(),
+        full,
+        rendered: None,
+    }
+}
+
+fn platform(name: &str) -> RenderTargetPlatform {
+    match name {
+        "telegram" => RenderTargetPlatform::Telegram,
+        _ => RenderTargetPlatform::Vk,
+    }
+}
+
+fn render_message_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_message");
+    for (name, reply) in [
+        ("week_summary", week_reply(false)),
+        ("week_full", week_reply(true)),
+    ] {
+        for platform_name in ["telegram", "vk"] {
+            group.bench_function(format!("{name}_{platform_name}"), |b| {
+                b.iter(|| {
+                    render_message(
+                        black_box(&reply),
+                        platform(platform_name),
+                        Locale::Ru,
+                        ThemeKind::Default,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render_message_bench);
+criterion_main!(benches);
@@ -0,0 +1,30 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use domain_bot::usecases::TextToActionUseCase;
+
+/// Typical messages of the busy group chats, from the cheapest to the most expensive ones
+const MESSAGES: &[(&str, &str)] = &[
+    ("command", "/week"),
+    ("mention", "[club123|@mpeixbot], эта неделя"),
+    ("day_of_week", "пары во вторник"),
+    ("rel_day_ptr", "пары послезавтра"),
+    ("commute", "/commute 45 мин"),
+    ("date", "15 марта"),
+    ("unknown", "а-08-19"),
+];
+
+fn text_to_action(c: &mut Criterion) {
+    let use_case = TextToActionUseCase;
+    // patterns are compiled lazily, do not count it
+    use_case.text_to_action("/start").unwrap();
+
+    let mut group = c.benchmark_group("text_to_action");
+    for (name, text) in MESSAGES {
+        group.bench_function(*name, |b| {
+            b.iter(|| use_case.text_to_action(black_box(text)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, text_to_action);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common_database::Database;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::models::AchievementStats;
+
+/// Repository for accessing table `peer_achievements` of the mpeix database
+#[async_trait]
+pub trait AchievementsRepository: Send + Sync {
+    async fn init_achievements_tables(&self) -> anyhow::Result<()>;
+
+    /// Get achievement stats of the peer, or default stats if peer has no stats yet.
+    async fn get_stats(&self, peer_id: i64) -> anyhow::Result<AchievementStats>;
+
+    async fn save_stats(&self, peer_id: i64, stats: &AchievementStats) -> anyhow::Result<()>;
+}
+
+/// [AchievementsRepository] backed by the mpeix database
+pub struct PgAchievementsRepository {
+    database: Arc<Database>,
+}
+
+impl PgAchievementsRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl AchievementsRepository for PgAchievementsRepository {
+    async fn init_achievements_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_achievements.pgsql");
+        client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error during table 'peer_achievements' creation")?;
+        info!("Table 'peer_achievements' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn get_stats(&self, peer_id: i64) -> anyhow::Result<AchievementStats> {
+        let client = self.database.read().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/select_peer_achievements.pgsql"),
+            peer_id = peer_id
+        );
+        Ok(client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error selecting peer achievements from db")?
+            .pop()
+            .and_then(map_from_db_model)
+            .unwrap_or_default())
+    }
+
+    async fn save_stats(&self, peer_id: i64, stats: &AchievementStats) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/upsert_peer_achievements.pgsql"),
+            peer_id = peer_id,
+            current_streak = stats.current_streak,
+            best_streak = stats.best_streak,
+            last_check_date = stats
+                .last_check_date
+                .context("Cannot save achievement stats without last check date")?,
+            early_bird_checks = stats.early_bird_checks,
+            total_checks = stats.total_checks,
+        );
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error updating peer achievements in db")?;
+        Ok(())
+    }
+}
+
+fn map_from_db_model(row: Row) -> Option<AchievementStats> {
+    Some(AchievementStats {
+        current_streak: row.try_get("current_streak").ok()?,
+        best_streak: row.try_get("best_streak").ok()?,
+        last_check_date: row
+            .try_get::<_, Option<String>>("last_check_date")
+            .ok()?
+            .and_then(|v| NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok()),
+        early_bird_checks: row.try_get("early_bird_checks").ok()?,
+        total_checks: row.try_get("total_checks").ok()?,
+    })
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common_database::Database;
+use log::info;
+
+use crate::models::ReEngagementStats;
+
+/// Repository for accessing table `peer_activity` of the mpeix database,
+/// the time of the last message of each peer and of the re-engagement message sent to it
+#[async_trait]
+pub trait PeerActivityRepository: Send + Sync {
+    async fn init_peer_activity_tables(&self) -> anyhow::Result<()>;
+
+    /// Remember that the peer sent a message right now
+    async fn record_activity(&self, peer_id: i64) -> anyhow::Result<()>;
+
+    /// Mark the peers as contacted by the re-engagement campaign, so they are never contacted again
+    async fn mark_reengaged(&self, peer_ids: &[i64]) -> anyhow::Result<u64>;
+
+    /// Stats of the re-engagement campaign, peers are inactive after `inactive_weeks`
+    async fn get_reengagement_stats(
+        &self,
+        inactive_weeks: u32,
+    ) -> anyhow::Result<ReEngagementStats>;
+}
+
+/// [PeerActivityRepository] backed by the mpeix database
+pub struct PgPeerActivityRepository {
+    database: Arc<Database>,
+}
+
+impl PgPeerActivityRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl PeerActivityRepository for PgPeerActivityRepository {
+    async fn init_peer_activity_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_activity.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'peer_activity' creation")?;
+        info!("Table 'peer_activity' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn record_activity(&self, peer_id: i64) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/upsert_peer_activity.pgsql");
+        client
+            .execute(stmt, &[&peer_id])
+            .await
+            .with_context(|| "Error inserting peer activity to db")?;
+        Ok(())
+    }
+
+    async fn mark_reengaged(&self, peer_ids: &[i64]) -> anyhow::Result<u64> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/update_peer_activity_reengaged.pgsql");
+        client
+            .execute(stmt, &[&peer_ids])
+            .await
+            .with_context(|| "Error updating peer activity in db")
+    }
+
+    async fn get_reengagement_stats(
+        &self,
+        inactive_weeks: u32,
+    ) -> anyhow::Result<ReEngagementStats> {
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_reengagement_stats.pgsql");
+        let row = client
+            .query_one(stmt, &[&(inactive_weeks as i32)])
+            .await
+            .with_context(|| "Error selecting re-engagement stats from db")?;
+        Ok(ReEngagementStats {
+            inactive_weeks,
+            inactive: row.try_get("inactive")?,
+            contacted: row.try_get("contacted")?,
+            returned: row.try_get("returned")?,
+        })
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use common_database::Database;
+use common_sql::Page;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::models::{AuditedReply, ReplyAuditEntry};
+
+/// Repository for accessing table `reply_audit` of the mpeix database.
+///
+/// Table contains redacted copies of the replies sent by the bot, for support purposes.
+#[async_trait]
+pub trait AuditRepository: Send + Sync {
+    async fn init_audit_tables(&self) -> anyhow::Result<()>;
+
+    async fn insert_entry(&self, entry: &ReplyAuditEntry) -> anyhow::Result<()>;
+
+    /// Get the last `limit` replies sent to the peer, the most recent first.
+    async fn get_recent_replies(
+        &self,
+        platform: &str,
+        platform_peer_id: i64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<AuditedReply>>;
+
+    /// Delete entries older than `retention_days`. Returns number of deleted entries.
+    async fn delete_expired(&self, retention_days: u32) -> anyhow::Result<u64>;
+}
+
+/// [AuditRepository] backed by the mpeix database
+pub struct PgAuditRepository {
+    database: Arc<Database>,
+}
+
+impl PgAuditRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl AuditRepository for PgAuditRepository {
+    async fn init_audit_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_reply_audit.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'reply_audit' creation")?;
+        info!("Table 'reply_audit' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn insert_entry(&self, entry: &ReplyAuditEntry) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        // reply text is arbitrary, so we pass values as statement parameters
+        let stmt = include_str!("../../sql/insert_reply_audit.pgsql");
+        client
+            .execute(
+                stmt,
+                &[
+                    &entry.platform,
+                    &entry.platform_peer_id,
+                    &entry.reply_variant,
+                    &entry.text,
+                    &entry.latency_ms,
+                    &entry.keyboard_version,
+                ],
+            )
+            .await
+            .with_context(|| "Error inserting reply audit entry to db")?;
+        Ok(())
+    }
+
+    async fn get_recent_replies(
+        &self,
+        platform: &str,
+        platform_peer_id: i64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<AuditedReply>> {
+        let client = self.database.read().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/select_peer_reply_audit.pgsql"),
+            page = Page::first(limit)
+        );
+        Ok(client
+            .query(&stmt, &[&platform, &platform_peer_id])
+            .await
+            .with_context(|| "Error selecting peer reply audit entries from db")?
+            .into_iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+
+    async fn delete_expired(&self, retention_days: u32) -> anyhow::Result<u64> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/delete_expired_reply_audit.pgsql"),
+            retention_days = retention_days
+        );
+        client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error deleting expired reply audit entries from db")
+    }
+}
+
+fn map_from_db_model(row: Row) -> Option<AuditedReply> {
+    Some(AuditedReply {
+        created_at: NaiveDateTime::parse_from_str(
+            row.try_get("created_at").ok()?,
+            "%Y-%m-%dT%H:%M:%S",
+        )
+        .ok()?,
+        reply_variant: row.try_get("reply_variant").ok()?,
+        text: row.try_get("text").ok()?,
+    })
+}
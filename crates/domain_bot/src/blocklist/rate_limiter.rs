@@ -0,0 +1,270 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use common_rust::env;
+use log::warn;
+
+use crate::peer::repository::PlatformId;
+
+/// Peers are forgotten when there are more tracked peers, so the spam from
+/// many different peers does not eat the memory
+const MAX_TRACKED_PEERS: usize = 10000;
+
+/// Configuration of [RateLimiter]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    /// Messages allowed per window, `0` disables the rate limiter
+    pub max_messages: u32,
+    pub window: Duration,
+    /// Number of windows with exceeded limit after which the peer is temporarily blocked
+    pub strikes_to_block: u32,
+    /// Duration of the temporary block, strikes are forgotten after it too
+    pub block_duration: Duration,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_messages: env::get_parsed_or("BOT_RATE_LIMIT_MESSAGES", 20),
+            window: Duration::from_secs(env::get_parsed_or("BOT_RATE_LIMIT_WINDOW_SECONDS", 60)),
+            strikes_to_block: env::get_parsed_or("BOT_RATE_LIMIT_STRIKES", 3),
+            block_duration: Duration::from_secs(
+                env::get_parsed_or("BOT_TEMPORARY_BLOCK_MINUTES", 60) * 60,
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    Allowed,
+    /// The limit of the current window is exceeded
+    Limited,
+    /// The peer is temporarily blocked for the repeated exceeding of the limit
+    Blocked,
+}
+
+/// Fixed window rate limiter of the incoming messages by peer.
+///
+/// The state is kept in memory, so it is reset on restart.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    peers: Mutex<HashMap<PlatformId, PeerRate>>,
+}
+
+#[derive(Debug)]
+struct PeerRate {
+    window_start: Instant,
+    messages: u32,
+    strikes: u32,
+    last_strike: Option<Instant>,
+    blocked_until: Option<Instant>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            peers: Default::default(),
+        }
+    }
+
+    /// Count the message of the peer and decide if it should be handled
+    pub fn check(&self, platform_id: &PlatformId, now: Instant) -> RateDecision {
+        if self.config.max_messages == 0 {
+            return RateDecision::Allowed;
+        }
+        let mut peers = self.peers.lock().unwrap();
+        if peers.len() >= MAX_TRACKED_PEERS && !peers.contains_key(platform_id) {
+            peers.retain(|_, rate| rate.is_relevant(&self.config, now));
+        }
+        let rate = peers.entry(platform_id.clone()).or_insert(PeerRate {
+            window_start: now,
+            messages: 0,
+            strikes: 0,
+            last_strike: None,
+            blocked_until: None,
+        });
+        if rate.blocked_until.is_some_and(|until| now < until) {
+            return RateDecision::Blocked;
+        }
+        if now.duration_since(rate.window_start) >= self.config.window {
+            rate.window_start = now;
+            rate.messages = 0;
+        }
+        if rate
+            .last_strike
+            .is_some_and(|it| now.duration_since(it) >= self.config.block_duration)
+        {
+            rate.strikes = 0;
+            rate.last_strike = None;
+        }
+        rate.messages += 1;
+        if rate.messages <= self.config.max_messages {
+            return RateDecision::Allowed;
+        }
+        // the strike is counted once per window
+        if rate.messages == self.config.max_messages + 1 {
+            rate.strikes += 1;
+            rate.last_strike = Some(now);
+            if rate.strikes >= self.config.strikes_to_block {
+                warn!(
+                    "Peer {platform_id:?} is temporarily blocked for {:?} after {} strikes",
+                    self.config.block_duration, rate.strikes,
+                );
+                rate.strikes = 0;
+                rate.last_strike = None;
+                rate.blocked_until = Some(now + self.config.block_duration);
+                return RateDecision::Blocked;
+            }
+        }
+        RateDecision::Limited
+    }
+
+    /// Temporarily blocked peers with the time left
+    pub fn temporarily_blocked(&self, now: Instant) -> Vec<(PlatformId, Duration)> {
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(platform_id, rate)| {
+                let until = rate.blocked_until.filter(|until| now < *until)?;
+                Some((platform_id.clone(), until.duration_since(now)))
+            })
+            .collect()
+    }
+
+    /// Lift the temporary block and forget the strikes of the peer,
+    /// returns `false` if the peer was not blocked
+    pub fn unblock(&self, platform_id: &PlatformId, now: Instant) -> bool {
+        self.peers
+            .lock()
+            .unwrap()
+            .remove(platform_id)
+            .and_then(|rate| rate.blocked_until)
+            .is_some_and(|until| now < until)
+    }
+}
+
+impl PeerRate {
+    fn is_relevant(&self, config: &RateLimitConfig, now: Instant) -> bool {
+        self.blocked_until.is_some_and(|until| now < until)
+            || self
+                .last_strike
+                .is_some_and(|it| now.duration_since(it) < config.block_duration)
+            || now.duration_since(self.window_start) < config.window
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{RateDecision, RateLimitConfig, RateLimiter};
+    use crate::peer::repository::PlatformId;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            max_messages: 2,
+            window: Duration::from_secs(60),
+            strikes_to_block: 2,
+            block_duration: Duration::from_secs(3600),
+        })
+    }
+
+    #[test]
+    fn messages_over_limit_are_limited_until_next_window() {
+        let limiter = limiter();
+        let peer = PlatformId::telegram(1);
+        let start = Instant::now();
+        assert_eq!(RateDecision::Allowed, limiter.check(&peer, start));
+        assert_eq!(RateDecision::Allowed, limiter.check(&peer, start));
+        assert_eq!(RateDecision::Limited, limiter.check(&peer, start));
+        assert_eq!(RateDecision::Limited, limiter.check(&peer, start));
+        // other peers are not affected
+        assert_eq!(
+            RateDecision::Allowed,
+            limiter.check(&PlatformId::vk(1), start)
+        );
+        let next_window = start + Duration::from_secs(60);
+        assert_eq!(RateDecision::Allowed, limiter.check(&peer, next_window));
+    }
+
+    #[test]
+    fn repeat_offender_is_temporarily_blocked() {
+        let limiter = limiter();
+        let peer = PlatformId::vk(2000000001);
+        let start = Instant::now();
+        for window in 0..2 {
+            let now = start + Duration::from_secs(60 * window);
+            for _ in 0..3 {
+                limiter.check(&peer, now);
+            }
+        }
+        let now = start + Duration::from_secs(120);
+        assert_eq!(RateDecision::Blocked, limiter.check(&peer, now));
+        assert_eq!(
+            vec![(peer.clone(), Duration::from_secs(3540))],
+            limiter.temporarily_blocked(now)
+        );
+        let after_block = start + Duration::from_secs(60 + 3600);
+        assert_eq!(RateDecision::Allowed, limiter.check(&peer, after_block));
+        assert!(limiter.temporarily_blocked(after_block).is_empty());
+    }
+
+    #[test]
+    fn strikes_are_forgotten_after_block_duration() {
+        let limiter = limiter();
+        let peer = PlatformId::telegram(1);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.check(&peer, start);
+        }
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..2 {
+            assert_eq!(RateDecision::Allowed, limiter.check(&peer, later));
+        }
+        assert_eq!(RateDecision::Limited, limiter.check(&peer, later));
+    }
+
+    #[test]
+    fn temporary_block_can_be_lifted() {
+        let limiter = limiter();
+        let peer = PlatformId::telegram(1);
+        let start = Instant::now();
+        for window in 0..2 {
+            let now = start + Duration::from_secs(60 * window);
+            for _ in 0..3 {
+                limiter.check(&peer, now);
+            }
+        }
+        let now = start + Duration::from_secs(60);
+        assert!(limiter.unblock(&peer, now));
+        assert!(!limiter.unblock(&peer, now));
+        assert_eq!(RateDecision::Allowed, limiter.check(&peer, now));
+    }
+
+    #[test]
+    fn zero_limit_disables_limiter() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            max_messages: 0,
+            ..limiter().config
+        });
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert_eq!(
+                RateDecision::Allowed,
+                limiter.check(&PlatformId::telegram(1), now)
+            );
+        }
+    }
+}
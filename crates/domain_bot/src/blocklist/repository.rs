@@ -0,0 +1,192 @@
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common_database::Database;
+use log::{info, warn};
+
+use crate::{models::BlockPattern, peer::repository::PlatformId};
+
+/// Repository for accessing table `peer_blocklist` of the mpeix database
+#[async_trait]
+pub trait BlocklistRepository: Send + Sync {
+    /// Create the table and load the patterns into memory
+    async fn init_blocklist_tables(&self) -> anyhow::Result<()>;
+
+    fn is_blocked(&self, platform_id: &PlatformId) -> bool;
+
+    fn get_patterns(&self) -> Vec<BlockPattern>;
+
+    async fn add_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<()>;
+
+    /// Remove the pattern, returns `false` if there was no such pattern
+    async fn remove_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<bool>;
+}
+
+/// [BlocklistRepository] backed by the mpeix database.
+///
+/// Blocklist is checked on every incoming message, so the patterns are kept in memory
+/// and loaded from the database only on the app start.
+pub struct PgBlocklistRepository {
+    database: Arc<Database>,
+    patterns: BlockPatterns,
+}
+
+/// Block patterns kept in memory, without duplicates
+#[derive(Default)]
+pub(crate) struct BlockPatterns(RwLock<Vec<BlockPattern>>);
+
+impl BlockPatterns {
+    fn replace(&self, patterns: Vec<BlockPattern>) {
+        *self.0.write().unwrap() = patterns;
+    }
+
+    pub(crate) fn is_blocked(&self, platform_id: &PlatformId) -> bool {
+        self.0
+            .read()
+            .unwrap()
+            .iter()
+            .any(|it| it.matches(platform_id))
+    }
+
+    pub(crate) fn get(&self) -> Vec<BlockPattern> {
+        self.0.read().unwrap().clone()
+    }
+
+    pub(crate) fn add(&self, pattern: &BlockPattern) {
+        let mut patterns = self.0.write().unwrap();
+        if !patterns.contains(pattern) {
+            patterns.push(pattern.to_owned());
+        }
+    }
+
+    /// Returns `false` if there was no such pattern
+    pub(crate) fn remove(&self, pattern: &BlockPattern) -> bool {
+        let mut patterns = self.0.write().unwrap();
+        let count = patterns.len();
+        patterns.retain(|it| it != pattern);
+        patterns.len() != count
+    }
+}
+
+impl PgBlocklistRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            patterns: Default::default(),
+        }
+    }
+
+    /// Execute the statement with the pattern as a parameter
+    async fn execute(&self, stmt: &str, pattern: &BlockPattern) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        client.execute(stmt, &[&pattern.to_string()]).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl BlocklistRepository for PgBlocklistRepository {
+    async fn init_blocklist_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_blocklist.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'peer_blocklist' creation")?;
+        info!("Table 'peer_blocklist' initialization passed successfully");
+
+        let stmt = include_str!("../../sql/select_peer_blocklist.pgsql");
+        let patterns = client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error selecting peer blocklist from db")?
+            .iter()
+            .filter_map(|row| {
+                let pattern: String = row.try_get("pattern").ok()?;
+                pattern
+                    .parse::<BlockPattern>()
+                    .map_err(|e| warn!("Skipping invalid block pattern: {e}"))
+                    .ok()
+            })
+            .collect::<Vec<_>>();
+        info!("Blocklist loaded, {} patterns", patterns.len());
+        self.patterns.replace(patterns);
+        Ok(())
+    }
+
+    fn is_blocked(&self, platform_id: &PlatformId) -> bool {
+        self.patterns.is_blocked(platform_id)
+    }
+
+    fn get_patterns(&self) -> Vec<BlockPattern> {
+        self.patterns.get()
+    }
+
+    async fn add_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<()> {
+        let stmt = include_str!("../../sql/insert_peer_blocklist.pgsql");
+        self.execute(stmt, pattern)
+            .await
+            .with_context(|| "Error inserting block pattern into db")?;
+        self.patterns.add(pattern);
+        Ok(())
+    }
+
+    async fn remove_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<bool> {
+        let stmt = include_str!("../../sql/delete_peer_blocklist.pgsql");
+        self.execute(stmt, pattern)
+            .await
+            .with_context(|| "Error deleting block pattern from db")?;
+        Ok(self.patterns.remove(pattern))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BlockPatterns;
+    use crate::{models::BlockPattern, peer::repository::PlatformId};
+
+    fn pattern(s: &str) -> BlockPattern {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn patterns_match_platform_and_id() {
+        let patterns = BlockPatterns::default();
+        patterns.add(&pattern("telegram:123"));
+        patterns.add(&pattern("vk:2000000*"));
+        patterns.add(&pattern("*:-100*"));
+        patterns.add(&pattern("telegram:123"));
+        assert_eq!(3, patterns.get().len());
+
+        assert!(patterns.is_blocked(&PlatformId::telegram(123)));
+        assert!(!patterns.is_blocked(&PlatformId::telegram(1234)));
+        assert!(!patterns.is_blocked(&PlatformId::vk(123)));
+        assert!(patterns.is_blocked(&PlatformId::vk(2000000001)));
+        assert!(!patterns.is_blocked(&PlatformId::telegram(2000000001)));
+        assert!(patterns.is_blocked(&PlatformId::telegram(-1001234)));
+        assert!(patterns.is_blocked(&PlatformId::vk(-1005)));
+
+        assert!(patterns.remove(&pattern("telegram:123")));
+        assert!(!patterns.remove(&pattern("telegram:123")));
+        assert!(!patterns.is_blocked(&PlatformId::telegram(123)));
+    }
+
+    #[test]
+    fn pattern_is_displayed_as_parsed() {
+        for s in ["telegram:123", "vk:2000000*", "*:-100*"] {
+            assert_eq!(s, pattern(s).to_string());
+        }
+        for s in [
+            "telegram:*",
+            "*:*",
+            "vk:",
+            "vk:12a",
+            "123",
+            "tg:123",
+            "vk:-",
+        ] {
+            assert!(s.parse::<BlockPattern>().is_err(), "{s}");
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use crate::models::Locale;
+
+/// Formatter of the dates in the replies, following the grammar of the peer's [Locale]:
+/// "вторник, 15 марта" in Russian and "Tuesday, March 15" in English.
+///
+/// The dates are formatted for the middle of the sentence,
+/// use [capitalize] if the date starts the sentence.
+#[derive(Clone, Copy)]
+pub(crate) struct DateFormatter(pub Locale);
+
+impl DateFormatter {
+    /// "вторник" or "Tuesday"
+    pub(crate) fn day_of_week(self, weekday: Weekday) -> &'static str {
+        match (self.0, weekday) {
+            (Locale::Ru, Weekday::Mon) => "понедельник",
+            (Locale::Ru, Weekday::Tue) => "вторник",
+            (Locale::Ru, Weekday::Wed) => "среда",
+            (Locale::Ru, Weekday::Thu) => "четверг",
+            (Locale::Ru, Weekday::Fri) => "пятница",
+            (Locale::Ru, Weekday::Sat) => "суббота",
+            (Locale::Ru, Weekday::Sun) => "воскресенье",
+            (Locale::En, Weekday::Mon) => "Monday",
+            (Locale::En, Weekday::Tue) => "Tuesday",
+            (Locale::En, Weekday::Wed) => "Wednesday",
+            (Locale::En, Weekday::Thu) => "Thursday",
+            (Locale::En, Weekday::Fri) => "Friday",
+            (Locale::En, Weekday::Sat) => "Saturday",
+            (Locale::En, Weekday::Sun) => "Sunday",
+        }
+    }
+
+    /// "во вторник" or "on Tuesday"
+    pub(crate) fn on_day_of_week(self, weekday: Weekday) -> String {
+        match self.0 {
+            Locale::Ru => {
+                let preposition = if weekday == Weekday::Tue {
+                    "во"
+                } else {
+                    "в"
+                };
+                // russian days of week are in the accusative case after the preposition
+                let day_of_week = match weekday {
+                    Weekday::Wed => "среду",
+                    Weekday::Fri => "пятницу",
+                    Weekday::Sat => "субботу",
+                    _ => self.day_of_week(weekday),
+                };
+                format!("{preposition} {day_of_week}")
+            }
+            Locale::En => format!("on {}", self.day_of_week(weekday)),
+        }
+    }
+
+    /// "15 марта" or "March 15"
+    pub(crate) fn day_and_month(self, date: NaiveDate) -> String {
+        match self.0 {
+            Locale::Ru => format!("{} {}", date.day(), self.month(date.month())),
+            Locale::En => format!("{} {}", self.month(date.month()), date.day()),
+        }
+    }
+
+    /// "вторник, 15 марта" or "Tuesday, March 15"
+    pub(crate) fn full_date(self, date: NaiveDate) -> String {
+        format!(
+            "{}, {}",
+            self.day_of_week(date.weekday()),
+            self.day_and_month(date)
+        )
+    }
+
+    /// "во вторник, 15 марта" or "on Tuesday, March 15"
+    pub(crate) fn on_full_date(self, date: NaiveDate) -> String {
+        format!(
+            "{}, {}",
+            self.on_day_of_week(date.weekday()),
+            self.day_and_month(date)
+        )
+    }
+
+    /// "март 2023" or "March 2023"
+    pub(crate) fn month_and_year(self, date: NaiveDate) -> String {
+        const RU: [&str; 12] = [
+            "январь",
+            "февраль",
+            "март",
+            "апрель",
+            "май",
+            "июнь",
+            "июль",
+            "август",
+            "сентябрь",
+            "октябрь",
+            "ноябрь",
+            "декабрь",
+        ];
+        let month = match self.0 {
+            Locale::Ru => RU.get(date.month0() as usize).copied().unwrap_or_default(),
+            Locale::En => self.month(date.month()),
+        };
+        format!("{month} {}", date.year())
+    }
+
+    /// Russian months are in the genitive case, as they always follow the day
+    fn month(self, month: u32) -> &'static str {
+        const RU: [&str; 12] = [
+            "января",
+            "февраля",
+            "марта",
+            "апреля",
+            "мая",
+            "июня",
+            "июля",
+            "августа",
+            "сентября",
+            "октября",
+            "ноября",
+            "декабря",
+        ];
+        const EN: [&str; 12] = [
+            "January",
+            "February",
+            "March",
+            "April",
+            "May",
+            "June",
+            "July",
+            "August",
+            "September",
+            "October",
+            "November",
+            "December",
+        ];
+        let months = match self.0 {
+            Locale::Ru => &RU,
+            Locale::En => &EN,
+        };
+        months.get(month as usize - 1).copied().unwrap_or_default()
+    }
+}
+
+/// Uppercase the first letter of the text, e.g. of the date at the sentence start
+pub(crate) fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{capitalize, DateFormatter};
+    use crate::models::Locale;
+
+    #[test]
+    fn test_date_formatter() {
+        let date = |day| NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+        let ru = DateFormatter(Locale::Ru);
+        let en = DateFormatter(Locale::En);
+
+        assert_eq!("15 марта", ru.day_and_month(date(15)));
+        assert_eq!("March 15", en.day_and_month(date(15)));
+        assert_eq!("среда, 15 марта", ru.full_date(date(15)));
+        assert_eq!("Wednesday, March 15", en.full_date(date(15)));
+        assert_eq!("во вторник, 14 марта", ru.on_full_date(date(14)));
+        assert_eq!("в среду, 15 марта", ru.on_full_date(date(15)));
+        assert_eq!("в понедельник, 13 марта", ru.on_full_date(date(13)));
+        assert_eq!("on Tuesday, March 14", en.on_full_date(date(14)));
+        assert_eq!("март 2023", ru.month_and_year(date(1)));
+        assert_eq!("March 2023", en.month_and_year(date(1)));
+    }
+
+    #[test]
+    fn test_capitalize() {
+        assert_eq!("Суббота, 18 марта", capitalize("суббота, 18 марта"));
+        assert_eq!("Saturday, March 18", capitalize("Saturday, March 18"));
+        assert_eq!("", capitalize(""));
+    }
+}
@@ -0,0 +1,339 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref PREFIX_PATTERN: Regex = Regex::new(r"^((пары|расписание)\s+)?(на\s+)?").unwrap();
+    static ref NUMERIC_DATE_PATTERN: Regex =
+        Regex::new(r"^(\d{1,2})\.(\d{1,2})(\.(\d{2}|\d{4}))?$").unwrap();
+    static ref TEXT_DATE_PATTERN: Regex =
+        Regex::new(r"^(\d{1,2})\s+([а-я]+)(\s+(\d{4})(\s+года?)?)?$").unwrap();
+    static ref RELATIVE_DATE_PATTERN: Regex = Regex::new(
+        r"^((во?\s+)?(?P<wd1>[а-я]+)\s+)?через\s+((?P<n>\d+|[а-я]+)\s+)?(?P<unit>день|дня|дней|неделю|недели|недель)(\s+во?\s+(?P<wd2>[а-я]+))?$"
+    )
+    .unwrap();
+    static ref NEXT_WEEKDAY_PATTERN: Regex = Regex::new(
+        r"^((в|во|on)\s+)?(следующ(ий|ую|ее|ая)|next)\s+(?P<wd>[a-zа-я]+)$"
+    )
+    .unwrap();
+    static ref RANGE_PATTERN: Regex = Regex::new(
+        r"^(с|со|from)\s+(?P<from>.+?)\s+(по|до|to|till|until)\s+(?P<to>.+)$"
+    )
+    .unwrap();
+    static ref DAYS_COUNT_PATTERN: Regex = Regex::new(
+        r"^((schedule\s+)?for\s+)?(?P<n>\d+|[а-я]+)\s+(дня|дней|days)$"
+    )
+    .unwrap();
+    static ref MONTHS: Vec<&'static str> = vec![
+        "января", "февраля", "марта", "апреля", "мая", "июня",
+        "июля", "августа", "сентября", "октября", "ноября", "декабря",
+    ];
+    static ref NUMBERS: Vec<(u64, Vec<&'static str>)> = vec![
+        (1, vec!["один", "одну"]),
+        (2, vec!["два", "две"]),
+        (3, vec!["три"]),
+        (4, vec!["четыре"]),
+        (5, vec!["пять"]),
+        (6, vec!["шесть"]),
+        (7, vec!["семь"]),
+    ];
+    static ref WEEKDAYS: Vec<(Weekday, Vec<&'static str>)> = vec![
+        (Weekday::Mon, vec!["пн", "понедельник"]),
+        (Weekday::Tue, vec!["вт", "вторник"]),
+        (Weekday::Wed, vec!["ср", "среду", "среда"]),
+        (Weekday::Thu, vec!["чт", "четверг"]),
+        (Weekday::Fri, vec!["пт", "пятницу", "пятница"]),
+        (Weekday::Sat, vec!["сб", "субботу", "суббота"]),
+        (Weekday::Sun, vec!["вс", "воскресенье"]),
+    ];
+    /// Weekdays in the forms used after "с" and "по", and in English
+    static ref RANGE_WEEKDAYS: Vec<(Weekday, Vec<&'static str>)> = vec![
+        (Weekday::Mon, vec!["понедельника", "mon", "monday"]),
+        (Weekday::Tue, vec!["вторника", "tue", "tuesday"]),
+        (Weekday::Wed, vec!["среды", "wed", "wednesday"]),
+        (Weekday::Thu, vec!["четверга", "thu", "thursday"]),
+        (Weekday::Fri, vec!["пятницы", "fri", "friday"]),
+        (Weekday::Sat, vec!["субботы", "sat", "saturday"]),
+        (Weekday::Sun, vec!["воскресенья", "sun", "sunday"]),
+    ];
+}
+
+/// Max number of days in the requested range, longer ranges do not fit into one message
+pub(crate) const MAX_DAY_RANGE_DAYS: i64 = 7;
+
+/// Parse date range expressions like `"с понедельника по среду"`, `"с 13 по 15 марта"`
+/// or `"на 3 дня"` relative to `today`. Both ends of the range are inclusive.
+///
+/// Weekdays are the nearest ones: the range starts on the next such weekday (or today)
+/// and ends on the first such weekday after the start.
+pub(crate) fn parse_date_range(text: &str, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+    let text = PREFIX_PATTERN.replace(text.trim(), "");
+    let (from, to) = if let Some(captures) = DAYS_COUNT_PATTERN.captures(&text) {
+        let count = parse_number(captures.name("n")?.as_str())?;
+        if count == 0 {
+            return None;
+        }
+        (today, today.checked_add_days(Days::new(count - 1))?)
+    } else if let Some(captures) = RANGE_PATTERN.captures(&text) {
+        let to = parse_range_end(captures.name("to")?.as_str(), today)?;
+        let from_text = captures.name("from")?.as_str();
+        let from = match from_text.parse::<u32>() {
+            // "с 13 по 15 марта", the month and the year are the same as in the end of the range
+            Ok(day) => to.with_day(day)?,
+            Err(_) => parse_range_end(from_text, today)?,
+        };
+        // the end weekday is counted from the start of the range
+        let to = match parse_range_weekday(captures.name("to")?.as_str()) {
+            Some(weekday) => next_weekday(from, weekday),
+            None => to,
+        };
+        (from, to)
+    } else {
+        return None;
+    };
+    let span = (to - from).num_days();
+    (0..MAX_DAY_RANGE_DAYS)
+        .contains(&span)
+        .then_some((from, to))
+}
+
+fn parse_range_end(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match text {
+        "сегодня" | "today" => Some(today),
+        "завтра" | "tomorrow" => today.checked_add_days(Days::new(1)),
+        text => match parse_range_weekday(text) {
+            Some(weekday) => Some(next_weekday(today, weekday)),
+            None => parse_date_expression(text, today),
+        },
+    }
+}
+
+fn parse_range_weekday(text: &str) -> Option<Weekday> {
+    RANGE_WEEKDAYS
+        .iter()
+        .find(|(_, v)| v.contains(&text))
+        .map(|(weekday, _)| *weekday)
+        .or_else(|| parse_weekday(text))
+}
+
+/// The nearest date with the `weekday`, starting from `since` inclusive
+fn next_weekday(since: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - since.weekday().num_days_from_monday()) % 7;
+    since + Days::new(days as u64)
+}
+
+/// Parse date expressions like `"15 марта"`, `"15.03"`, `"через 3 дня"`,
+/// `"через неделю в пятницу"` or `"next tuesday"` relative to `today`.
+///
+/// The next weekday is the nearest such weekday after today, today is never the next one.
+///
+/// Dates without year are considered to be in the current year, unless they are
+/// more than half a year ago, then they are considered to be in the next year.
+pub(crate) fn parse_date_expression(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = PREFIX_PATTERN.replace(text.trim(), "");
+    if let Some(captures) = NUMERIC_DATE_PATTERN.captures(&text) {
+        let day = captures.get(1)?.as_str().parse().ok()?;
+        let month = captures.get(2)?.as_str().parse().ok()?;
+        let year = captures
+            .get(4)
+            .and_then(|it| match it.as_str().parse::<i32>() {
+                Ok(year) if year < 100 => Some(2000 + year),
+                Ok(year) => Some(year),
+                Err(_) => None,
+            });
+        return date_of(day, month, year, today);
+    }
+    if let Some(captures) = TEXT_DATE_PATTERN.captures(&text) {
+        let day = captures.get(1)?.as_str().parse().ok()?;
+        let month_name = captures.get(2)?.as_str();
+        let month = MONTHS.iter().position(|it| *it == month_name)? as u32 + 1;
+        let year = captures.get(4).and_then(|it| it.as_str().parse().ok());
+        return date_of(day, month, year, today);
+    }
+    if let Some(captures) = RELATIVE_DATE_PATTERN.captures(&text) {
+        let amount = match captures.name("n") {
+            None => 1,
+            Some(n) => parse_number(n.as_str())?,
+        };
+        let weekday = match (captures.name("wd1"), captures.name("wd2")) {
+            (Some(_), Some(_)) => return None,
+            (Some(wd), None) | (None, Some(wd)) => Some(parse_weekday(wd.as_str())?),
+            (None, None) => None,
+        };
+        let unit = captures.name("unit")?.as_str();
+        return if unit.starts_with("д") {
+            // "в пятницу через 3 дня" makes no sense
+            if weekday.is_some() {
+                return None;
+            }
+            today.checked_add_days(Days::new(amount))
+        } else {
+            let date = today.checked_add_days(Days::new(amount * 7))?;
+            match weekday {
+                Some(weekday) => {
+                    let week_start = date.week(Weekday::Mon).first_day();
+                    week_start.checked_add_days(Days::new(weekday.num_days_from_monday() as u64))
+                }
+                None => Some(date),
+            }
+        };
+    }
+    if let Some(captures) = NEXT_WEEKDAY_PATTERN.captures(&text) {
+        let weekday = parse_range_weekday(captures.name("wd")?.as_str())?;
+        return Some(next_weekday(today.succ_opt()?, weekday));
+    }
+    None
+}
+
+fn date_of(day: u32, month: u32, year: Option<i32>, today: NaiveDate) -> Option<NaiveDate> {
+    if let Some(year) = year {
+        return NaiveDate::from_ymd_opt(year, month, day);
+    }
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today.checked_sub_months(Months::new(6))? {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
+fn parse_number(text: &str) -> Option<u64> {
+    if let Ok(number) = text.parse::<u64>() {
+        // limit the range to avoid overflows
+        return (number <= 365).then_some(number);
+    }
+    NUMBERS
+        .iter()
+        .find(|(_, v)| v.contains(&text))
+        .map(|(number, _)| *number)
+}
+
+fn parse_weekday(text: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(_, v)| v.contains(&text))
+        .map(|(weekday, _)| *weekday)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{parse_date_expression, parse_date_range};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    // Wednesday
+    fn today() -> NaiveDate {
+        date("2023-03-08")
+    }
+
+    #[test]
+    fn test_explicit_dates() {
+        for (text, expected) in [
+            ("15 марта", "2023-03-15"),
+            ("пары на 15 марта", "2023-03-15"),
+            ("1 января 2024 года", "2024-01-01"),
+            ("15.03", "2023-03-15"),
+            ("15.03.24", "2024-03-15"),
+            ("на 01.02.2023", "2023-02-01"),
+        ] {
+            assert_eq!(
+                parse_date_expression(text, today()),
+                Some(date(expected)),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse_date_expression("1 сентября", date("2023-12-20")),
+            Some(date("2023-09-01"))
+        );
+        // more than half a year ago, so it is the next year
+        assert_eq!(
+            parse_date_expression("15 января", date("2023-12-20")),
+            Some(date("2024-01-15"))
+        );
+    }
+
+    #[test]
+    fn test_relative_dates() {
+        for (text, expected) in [
+            ("через день", "2023-03-09"),
+            ("через 3 дня", "2023-03-11"),
+            ("через три дня", "2023-03-11"),
+            ("через неделю", "2023-03-15"),
+            ("через 2 недели", "2023-03-22"),
+            ("через неделю в пятницу", "2023-03-17"),
+            ("через неделю в понедельник", "2023-03-13"),
+            ("во вторник через две недели", "2023-03-21"),
+            ("next tuesday", "2023-03-14"),
+            ("on next friday", "2023-03-10"),
+            ("в следующий вторник", "2023-03-14"),
+            ("в следующую среду", "2023-03-15"),
+            ("пары на следующую пятницу", "2023-03-10"),
+        ] {
+            assert_eq!(
+                parse_date_expression(text, today()),
+                Some(date(expected)),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_dates() {
+        for text in [
+            "31.02",
+            "32 марта",
+            "15 мартобря",
+            "через",
+            "через 3 дня в пятницу",
+            "через 1000 дней",
+            "next month",
+            "следующий семестр",
+            "а-08-19",
+            "15",
+        ] {
+            assert_eq!(parse_date_expression(text, today()), None, "{text}");
+        }
+    }
+
+    #[test]
+    fn test_date_ranges() {
+        for (text, from, to) in [
+            ("пары с понедельника по среду", "2023-03-13", "2023-03-15"),
+            ("с пятницы по понедельник", "2023-03-10", "2023-03-13"),
+            ("со среды по пятницу", "2023-03-08", "2023-03-10"),
+            ("с сегодня по пятницу", "2023-03-08", "2023-03-10"),
+            ("расписание с 13 по 15 марта", "2023-03-13", "2023-03-15"),
+            ("с 10.03 по 12.03", "2023-03-10", "2023-03-12"),
+            ("расписание на 3 дня", "2023-03-08", "2023-03-10"),
+            ("на семь дней", "2023-03-08", "2023-03-14"),
+            ("from monday to wednesday", "2023-03-13", "2023-03-15"),
+            ("schedule for 3 days", "2023-03-08", "2023-03-10"),
+        ] {
+            assert_eq!(
+                parse_date_range(text, today()),
+                Some((date(from), date(to))),
+                "{text}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_date_ranges() {
+        for text in [
+            "на 8 дней",
+            "на 0 дней",
+            "с 15 по 13 марта",
+            "с 1 марта по 15 марта",
+            "с понедельника",
+            "через 3 дня",
+            "с утра по вечер",
+        ] {
+            assert_eq!(parse_date_range(text, today()), None, "{text}");
+        }
+    }
+}
@@ -1,27 +1,286 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use common_di::di_constructor;
+use common_restix::create_reqwest_client;
+use common_rust::env;
+use log::warn;
 
 use crate::{
-    mpeix_api::MpeixApi,
+    achievements::repository::AchievementsRepository,
+    activity::repository::PeerActivityRepository,
+    audit::repository::AuditRepository,
+    blocklist::{rate_limiter::RateLimiter, repository::BlocklistRepository},
+    members::repository::MemberSchedulesRepository,
+    mock::MockMpeixApi,
+    models::BuildInfo,
+    mpeix_api::{MpeixApi, MpeixUpstream},
+    notes::{repository::NotesRepository, ChatAdminChecker},
     peer::repository::PeerRepository,
+    queue::repository::ReplyQueueRepository,
+    recent::repository::RecentSchedulesRepository,
     schedule::repository::ScheduleRepository,
     search::repository::ScheduleSearchRepository,
     usecases::{
-        GenerateReplyUseCase, GetUpcomingEventsUseCase, InitDomainBotUseCase, TextToActionUseCase,
+        AbuseProtectionUseCase, AchievementsUseCase, AdminConfig, AuditReplyUseCase,
+        CommandKillSwitchUseCase, EveningSummaryUseCase, ExportPeerDataUseCase,
+        GenerateReplyUseCase, GetMonthOverviewUseCase, GetUpcomingEventsUseCase,
+        InitDomainBotUseCase, MemberSchedulesUseCase, MigratePeersUseCase, NotesUseCase,
+        PeerDeletionUseCase, PingUseCase, ReEngagementUseCase, ReplyRetryUseCase,
+        ScheduleChangeNotificationsUseCase, TextToActionUseCase, VerifyScheduleUseCase,
+        WeekAheadUseCase, WeeklyChangelogUseCase, WeeklyDigestUseCase,
     },
+    warnings::repository::RoomChangeWarningsRepository,
 };
 
-di_constructor! { ScheduleRepository(api: MpeixApi) }
-di_constructor! { ScheduleSearchRepository(api: MpeixApi) }
-di_constructor! { InitDomainBotUseCase(peer_repository: Arc<PeerRepository>) }
+di_constructor! { ScheduleSearchRepository(api: MpeixUpstream) }
+di_constructor! {
+    InitDomainBotUseCase(
+        peer_repository: Arc<dyn PeerRepository>,
+        achievements_repository: Arc<dyn AchievementsRepository>,
+        audit_repository: Arc<dyn AuditRepository>,
+        recent_schedules_repository: Arc<dyn RecentSchedulesRepository>,
+        notes_repository: Arc<dyn NotesRepository>,
+        reply_queue_repository: Arc<ReplyQueueRepository>,
+        member_schedules_repository: Arc<dyn MemberSchedulesRepository>,
+        peer_activity_repository: Arc<dyn PeerActivityRepository>,
+        blocklist_repository: Arc<dyn BlocklistRepository>
+    )
+}
+di_constructor! { AchievementsUseCase(achievements_repository: Arc<dyn AchievementsRepository>) }
 di_constructor! { GetUpcomingEventsUseCase(schedule_repository: Arc<ScheduleRepository>) }
 di_constructor! {
     GenerateReplyUseCase(
         text_to_action_use_case: Arc<TextToActionUseCase>,
-        peer_repository: Arc<PeerRepository>,
+        peer_repository: Arc<dyn PeerRepository>,
         schedule_repository: Arc<ScheduleRepository>,
         schedule_search_repository: Arc<ScheduleSearchRepository>,
-        get_upcoming_events_use_case: Arc<GetUpcomingEventsUseCase>
+        get_upcoming_events_use_case: Arc<GetUpcomingEventsUseCase>,
+        achievements_use_case: Arc<AchievementsUseCase>,
+        recent_schedules_repository: Arc<dyn RecentSchedulesRepository>,
+        verify_schedule_use_case: Arc<VerifyScheduleUseCase>,
+        notes_use_case: Arc<NotesUseCase>,
+        command_kill_switch_use_case: Arc<CommandKillSwitchUseCase>,
+        export_peer_data_use_case: Arc<ExportPeerDataUseCase>,
+        get_month_overview_use_case: Arc<GetMonthOverviewUseCase>,
+        ping_use_case: Arc<PingUseCase>,
+        member_schedules_use_case: Arc<MemberSchedulesUseCase>,
+        reengagement_use_case: Arc<ReEngagementUseCase>,
+        room_change_warnings_repository: Arc<RoomChangeWarningsRepository>,
+        abuse_protection_use_case: Arc<AbuseProtectionUseCase>,
+        peer_deletion_use_case: Arc<PeerDeletionUseCase>
+    )
+}
+di_constructor! {
+    ExportPeerDataUseCase {
+        recent_schedules_repository: Arc<dyn RecentSchedulesRepository>,
+        achievements_repository: Arc<dyn AchievementsRepository>,
+        audit_repository: Arc<dyn AuditRepository>,
+        member_schedules_repository: Arc<dyn MemberSchedulesRepository>
+    }
+}
+di_constructor! {
+    NotesUseCase {
+        notes_repository: Arc<dyn NotesRepository>,
+        chat_admin_checker: Arc<dyn ChatAdminChecker>
+    }
+}
+di_constructor! {
+    MemberSchedulesUseCase {
+        member_schedules_repository: Arc<dyn MemberSchedulesRepository>,
+        schedule_search_repository: Arc<ScheduleSearchRepository>
+    }
+}
+di_constructor! {
+    MigratePeersUseCase(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+        schedule_search_repository: Arc<ScheduleSearchRepository>
     )
 }
+
+impl Default for MpeixUpstream {
+    /// Api of the `app_schedule` microservice, or fixture-backed fake if `MOCK_UPSTREAM=true`
+    fn default() -> Self {
+        if env::get_parsed_or("MOCK_UPSTREAM", false) {
+            warn!("MOCK_UPSTREAM is enabled, app_schedule api is replaced with fixtures");
+            Self::Mock(MockMpeixApi)
+        } else {
+            Self::Remote(
+                MpeixApi::builder()
+                    .base_url(env::required("APP_SCHEDULE_BASE_URL"))
+                    .client(create_reqwest_client())
+                    .build()
+                    .expect("DI error while creating MpeixApi"),
+            )
+        }
+    }
+}
+
+impl AuditReplyUseCase {
+    pub fn new(audit_repository: Arc<dyn AuditRepository>) -> Self {
+        Self {
+            audit_repository,
+            config: Default::default(),
+            last_cleanup: Default::default(),
+        }
+    }
+}
+
+impl ReplyRetryUseCase {
+    pub fn new(reply_queue_repository: Arc<ReplyQueueRepository>) -> Self {
+        Self {
+            reply_queue_repository,
+            config: Default::default(),
+        }
+    }
+}
+
+impl ScheduleRepository {
+    pub fn new(api: MpeixUpstream) -> Self {
+        Self {
+            api,
+            schedule_requests: Default::default(),
+        }
+    }
+}
+
+impl GetMonthOverviewUseCase {
+    pub fn new(schedule_repository: Arc<ScheduleRepository>) -> Self {
+        Self {
+            schedule_repository,
+            cache: Default::default(),
+        }
+    }
+}
+
+impl EveningSummaryUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            schedule_repository,
+            config: Default::default(),
+            push: Default::default(),
+        }
+    }
+}
+
+impl WeeklyChangelogUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            schedule_repository,
+            config: Default::default(),
+            push: Default::default(),
+        }
+    }
+}
+
+impl WeekAheadUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            schedule_repository,
+            config: Default::default(),
+            push: Default::default(),
+        }
+    }
+}
+
+impl WeeklyDigestUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            schedule_repository,
+            config: Default::default(),
+            push: Default::default(),
+        }
+    }
+}
+
+impl ScheduleChangeNotificationsUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+        room_change_warnings_repository: Arc<RoomChangeWarningsRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            schedule_repository,
+            room_change_warnings_repository,
+            config: Default::default(),
+            last_poll: Default::default(),
+            seen_changes: Default::default(),
+        }
+    }
+}
+
+impl ReEngagementUseCase {
+    pub fn new(
+        peer_repository: Arc<dyn PeerRepository>,
+        peer_activity_repository: Arc<dyn PeerActivityRepository>,
+    ) -> Self {
+        Self {
+            peer_repository,
+            peer_activity_repository,
+            config: Default::default(),
+            push: Default::default(),
+        }
+    }
+}
+
+impl AbuseProtectionUseCase {
+    pub fn new(blocklist_repository: Arc<dyn BlocklistRepository>) -> Self {
+        Self {
+            blocklist_repository,
+            rate_limiter: RateLimiter::default(),
+            config: AdminConfig::default(),
+        }
+    }
+}
+
+impl PeerDeletionUseCase {
+    pub fn new(peer_repository: Arc<dyn PeerRepository>) -> Self {
+        Self {
+            peer_repository,
+            config: Default::default(),
+        }
+    }
+}
+
+impl PingUseCase {
+    /// Build info is passed by the app, use [crate::build_info] macro to get it
+    pub fn new(schedule_repository: Arc<ScheduleRepository>, build: BuildInfo) -> Self {
+        Self {
+            schedule_repository,
+            config: AdminConfig::default(),
+            build,
+            started_at: Instant::now(),
+            last_health_check: Default::default(),
+        }
+    }
+}
+
+impl VerifyScheduleUseCase {
+    pub fn new(
+        schedule_repository: Arc<ScheduleRepository>,
+        schedule_search_repository: Arc<ScheduleSearchRepository>,
+    ) -> Self {
+        Self {
+            schedule_repository,
+            schedule_search_repository,
+            config: AdminConfig::default(),
+        }
+    }
+}
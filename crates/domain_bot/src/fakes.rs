@@ -0,0 +1,472 @@
+//! In-memory fakes of the database repositories for the tests.
+//! Queries which are not needed by the tests are not supported.
+
+use std::sync::{Arc, Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use domain_schedule_models::ScheduleType;
+
+use crate::{
+    achievements::repository::AchievementsRepository,
+    activity::repository::PeerActivityRepository,
+    audit::repository::AuditRepository,
+    blocklist::repository::{BlockPatterns, BlocklistRepository},
+    members::repository::MemberSchedulesRepository,
+    models::{
+        AchievementStats, AuditedReply, BlockPattern, ChatMember, ChatNote, MemberMention, Peer,
+        ReEngagementStats, RecentSchedule, ReplyAuditEntry,
+    },
+    notes::repository::NotesRepository,
+    peer::repository::{PeerRepository, PlatformId, PushOptIn},
+    recent::repository::{RecentSchedulesRepository, MAX_RECENT_SCHEDULES},
+};
+
+/// Peers with their platform ids, new peers are created on the first request.
+/// The schedules linked by the members of the chats are stored along with the peers,
+/// because the purge of the peers deletes them, see [FakeMemberSchedulesRepository].
+pub(crate) struct FakePeerRepository {
+    peers: Mutex<Vec<(PlatformId, Peer)>>,
+    /// Peer id of the chat, its member and the linked schedule
+    member_schedules: Mutex<Vec<(i64, ChatMember, RecentSchedule)>>,
+}
+
+impl FakePeerRepository {
+    pub(crate) fn new(peers: Vec<(PlatformId, Peer)>) -> Self {
+        Self {
+            peers: Mutex::new(peers),
+            member_schedules: Default::default(),
+        }
+    }
+
+    /// Whether the member of the chat is a deleted peer on the platform of the chat
+    fn is_deleted_member(&self, chat_peer_id: i64, member_id: i64) -> bool {
+        let peers = self.peers.lock().unwrap();
+        let Some((chat, _)) = peers.iter().find(|(_, it)| it.id == chat_peer_id) else {
+            return false;
+        };
+        let (platform, _) = chat.split();
+        peers
+            .iter()
+            .any(|(id, peer)| id.split() == (platform, member_id) && peer.deleted_at.is_some())
+    }
+
+    /// Peers of the platform, which selected some schedule, not deleted and matching `filter`
+    fn peers_of(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        filter: impl Fn(&Peer) -> bool,
+    ) -> Vec<(PlatformId, Peer)> {
+        let (platform, _) = platform_id(0).split();
+        self.peers
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, peer)| {
+                id.split().0 == platform
+                    && !peer.selected_schedule.is_empty()
+                    && peer.deleted_at.is_none()
+                    && filter(peer)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl PeerRepository for FakePeerRepository {
+    async fn init_peer_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_peer_by_platform_id(&self, platform_id: PlatformId) -> anyhow::Result<Peer> {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some((_, peer)) = peers.iter().find(|(id, _)| id == &platform_id) {
+            return Ok(peer.clone());
+        }
+        let peer = Peer {
+            id: peers.len() as i64 + 1,
+            selected_schedule: String::new(),
+            selected_schedule_type: ScheduleType::Group,
+            selecting_schedule: false,
+            map_provider: None,
+            achievements_enabled: false,
+            days_range: Default::default(),
+            locale: None,
+            commute_minutes: None,
+            privacy_mode: false,
+            changelog_enabled: false,
+            week_ahead_enabled: false,
+            digest_enabled: false,
+            ics_enabled: false,
+            newcomer_mode: false,
+            theme: None,
+            subscribed: false,
+            deleted_at: None,
+        };
+        peers.push((platform_id, peer.clone()));
+        Ok(peer)
+    }
+
+    async fn save_peer(&self, peer: Peer) -> anyhow::Result<()> {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some((_, saved)) = peers.iter_mut().find(|(_, it)| it.id == peer.id) {
+            *saved = peer;
+        }
+        Ok(())
+    }
+
+    async fn mark_peer_deleted(&self, peer_id: i64) -> anyhow::Result<NaiveDateTime> {
+        let deleted_at = Local::now().naive_local();
+        let mut peers = self.peers.lock().unwrap();
+        if let Some((_, peer)) = peers.iter_mut().find(|(_, it)| it.id == peer_id) {
+            peer.deleted_at = Some(deleted_at);
+        }
+        Ok(deleted_at)
+    }
+
+    async fn restore_peer(&self, peer_id: i64) -> anyhow::Result<()> {
+        let mut peers = self.peers.lock().unwrap();
+        if let Some((_, peer)) = peers.iter_mut().find(|(_, it)| it.id == peer_id) {
+            peer.deleted_at = None;
+        }
+        Ok(())
+    }
+
+    async fn delete_purged_peers(&self, undo_days: u32) -> anyhow::Result<u64> {
+        let expired_at = Local::now().naive_local() - chrono::Duration::days(undo_days as i64);
+        let mut peers = self.peers.lock().unwrap();
+        let (kept, purged): (Vec<_>, Vec<_>) = peers
+            .drain(..)
+            .partition(|(_, peer)| peer.deleted_at.map_or(true, |it| it >= expired_at));
+        *peers = kept;
+        // the links of the purged chats and the links of the purged members in all chats
+        self.member_schedules
+            .lock()
+            .unwrap()
+            .retain(|(peer_id, member, _)| {
+                peers.iter().any(|(chat, it)| {
+                    it.id == *peer_id
+                        && !purged
+                            .iter()
+                            .any(|(id, _)| id.split() == (chat.split().0, member.id))
+                })
+            });
+        Ok(purged.len() as u64)
+    }
+
+    async fn get_peers_with_selected_schedule(&self) -> anyhow::Result<Vec<Peer>> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_peers_with_push(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        opt_in: PushOptIn,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>> {
+        Ok(self.peers_of(platform_id, |peer| opt_in.is_enabled(peer)))
+    }
+
+    async fn get_inactive_peers(
+        &self,
+        _: fn(i64) -> PlatformId,
+        _: u32,
+        _: usize,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn update_peers_selected_schedule(
+        &self,
+        _: &str,
+        _: &str,
+        _: &ScheduleType,
+    ) -> anyhow::Result<u64> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+}
+
+/// Achievement stats by peer id
+#[derive(Default)]
+pub(crate) struct FakeAchievementsRepository(Mutex<Vec<(i64, AchievementStats)>>);
+
+#[async_trait]
+impl AchievementsRepository for FakeAchievementsRepository {
+    async fn init_achievements_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_stats(&self, peer_id: i64) -> anyhow::Result<AchievementStats> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| *id == peer_id)
+            .map(|(_, stats)| stats.clone())
+            .unwrap_or_default())
+    }
+
+    async fn save_stats(&self, peer_id: i64, stats: &AchievementStats) -> anyhow::Result<()> {
+        let mut saved = self.0.lock().unwrap();
+        saved.retain(|(id, _)| *id != peer_id);
+        saved.push((peer_id, stats.clone()));
+        Ok(())
+    }
+}
+
+/// Ids of the peers with recorded activity
+#[derive(Default)]
+pub(crate) struct FakePeerActivityRepository(Mutex<Vec<i64>>);
+
+#[async_trait]
+impl PeerActivityRepository for FakePeerActivityRepository {
+    async fn init_peer_activity_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn record_activity(&self, peer_id: i64) -> anyhow::Result<()> {
+        let mut peer_ids = self.0.lock().unwrap();
+        if !peer_ids.contains(&peer_id) {
+            peer_ids.push(peer_id);
+        }
+        Ok(())
+    }
+
+    async fn mark_reengaged(&self, _: &[i64]) -> anyhow::Result<u64> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_reengagement_stats(&self, _: u32) -> anyhow::Result<ReEngagementStats> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+}
+
+/// Audited replies in the order of insertion
+#[derive(Default)]
+pub(crate) struct FakeAuditRepository(Mutex<Vec<(&'static str, i64, AuditedReply)>>);
+
+#[async_trait]
+impl AuditRepository for FakeAuditRepository {
+    async fn init_audit_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn insert_entry(&self, entry: &ReplyAuditEntry) -> anyhow::Result<()> {
+        let reply = AuditedReply {
+            created_at: Local::now().naive_local(),
+            reply_variant: entry.reply_variant.to_owned(),
+            text: entry.text.to_owned(),
+        };
+        self.0
+            .lock()
+            .unwrap()
+            .push((entry.platform, entry.platform_peer_id, reply));
+        Ok(())
+    }
+
+    async fn get_recent_replies(
+        &self,
+        platform: &str,
+        platform_peer_id: i64,
+        limit: usize,
+    ) -> anyhow::Result<Vec<AuditedReply>> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|(p, id, _)| *p == platform && *id == platform_peer_id)
+            .map(|(_, _, reply)| reply.clone())
+            .take(limit)
+            .collect())
+    }
+
+    async fn delete_expired(&self, _: u32) -> anyhow::Result<u64> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+}
+
+/// Block patterns are kept only in memory, like the cache of the real repository
+#[derive(Default)]
+pub(crate) struct FakeBlocklistRepository(BlockPatterns);
+
+#[async_trait]
+impl BlocklistRepository for FakeBlocklistRepository {
+    async fn init_blocklist_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    fn is_blocked(&self, platform_id: &PlatformId) -> bool {
+        self.0.is_blocked(platform_id)
+    }
+
+    fn get_patterns(&self) -> Vec<BlockPattern> {
+        self.0.get()
+    }
+
+    async fn add_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<()> {
+        self.0.add(pattern);
+        Ok(())
+    }
+
+    async fn remove_pattern(&self, pattern: &BlockPattern) -> anyhow::Result<bool> {
+        Ok(self.0.remove(pattern))
+    }
+}
+
+/// Schedules linked by the members of the chats, stored in [FakePeerRepository]
+pub(crate) struct FakeMemberSchedulesRepository(pub(crate) Arc<FakePeerRepository>);
+
+#[async_trait]
+impl MemberSchedulesRepository for FakeMemberSchedulesRepository {
+    async fn init_member_schedules_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_member_schedule(
+        &self,
+        peer_id: i64,
+        mention: &MemberMention,
+    ) -> anyhow::Result<Option<RecentSchedule>> {
+        let (member_id, username) = match mention {
+            MemberMention::Id(id) => (Some(*id), None),
+            MemberMention::Username(username) => (None, Some(username.to_lowercase())),
+        };
+        let link = self
+            .0
+            .member_schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, member, _)| {
+                *id == peer_id
+                    && (Some(member.id) == member_id
+                        || (username.is_some() && member.username == username))
+            })
+            .cloned();
+        Ok(link
+            .filter(|(id, member, _)| !self.0.is_deleted_member(*id, member.id))
+            .map(|(_, _, schedule)| schedule))
+    }
+
+    async fn link_member_schedule(
+        &self,
+        peer_id: i64,
+        member: &ChatMember,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()> {
+        let member = ChatMember {
+            id: member.id,
+            username: member.username.as_ref().map(|it| it.to_lowercase()),
+        };
+        let mut links = self.0.member_schedules.lock().unwrap();
+        links.retain(|(id, it, _)| *id != peer_id || it.id != member.id);
+        links.push((peer_id, member, schedule.clone()));
+        Ok(())
+    }
+
+    async fn unlink_member_schedule(&self, peer_id: i64, member_id: i64) -> anyhow::Result<bool> {
+        let mut links = self.0.member_schedules.lock().unwrap();
+        let count = links.len();
+        links.retain(|(id, member, _)| *id != peer_id || member.id != member_id);
+        Ok(count != links.len())
+    }
+
+    async fn get_member_schedules(
+        &self,
+        peer_id: i64,
+    ) -> anyhow::Result<Vec<(ChatMember, RecentSchedule)>> {
+        Ok(self
+            .0
+            .member_schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, _, _)| *id == peer_id)
+            .map(|(_, member, schedule)| (member.clone(), schedule.clone()))
+            .collect())
+    }
+}
+
+/// Notes by peer id
+#[derive(Default)]
+pub(crate) struct FakeNotesRepository(Mutex<Vec<(i64, ChatNote)>>);
+
+#[async_trait]
+impl NotesRepository for FakeNotesRepository {
+    async fn init_notes_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_notes(
+        &self,
+        peer_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<ChatNote>> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(id, note)| *id == peer_id && from <= note.date && note.date <= to)
+            .map(|(_, note)| note.clone())
+            .collect())
+    }
+
+    async fn add_note(&self, peer_id: i64, note: &ChatNote) -> anyhow::Result<()> {
+        self.0.lock().unwrap().push((peer_id, note.clone()));
+        Ok(())
+    }
+
+    async fn remove_notes(&self, peer_id: i64, date: NaiveDate) -> anyhow::Result<u64> {
+        let mut notes = self.0.lock().unwrap();
+        let count = notes.len();
+        notes.retain(|(id, note)| *id != peer_id || note.date != date);
+        Ok((count - notes.len()) as u64)
+    }
+}
+
+/// Peer id and its schedules from the most recent
+#[derive(Default)]
+pub(crate) struct FakeRecentSchedulesRepository(Mutex<Vec<(i64, Vec<RecentSchedule>)>>);
+
+#[async_trait]
+impl RecentSchedulesRepository for FakeRecentSchedulesRepository {
+    async fn init_recent_schedules_tables(&self) -> anyhow::Result<()> {
+        Err(anyhow!("Not supported by in-memory fake"))
+    }
+
+    async fn get_recent_schedules(&self, peer_id: i64) -> anyhow::Result<Vec<RecentSchedule>> {
+        Ok(self
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| *id == peer_id)
+            .map(|(_, schedules)| schedules.clone())
+            .unwrap_or_default())
+    }
+
+    async fn add_recent_schedule(
+        &self,
+        peer_id: i64,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()> {
+        let mut recent = self.0.lock().unwrap();
+        let schedules = match recent.iter_mut().find(|(id, _)| *id == peer_id) {
+            Some((_, schedules)) => schedules,
+            None => {
+                recent.push((peer_id, Vec::new()));
+                &mut recent.last_mut().unwrap().1
+            }
+        };
+        schedules.retain(|it| it != schedule);
+        schedules.insert(0, schedule.clone());
+        schedules.truncate(MAX_RECENT_SCHEDULES);
+        Ok(())
+    }
+}
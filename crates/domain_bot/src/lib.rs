@@ -1,8 +1,24 @@
+pub mod achievements;
+pub mod activity;
+pub mod audit;
+pub mod blocklist;
+pub(crate) mod date_format;
+pub(crate) mod dates;
 pub mod di;
+#[cfg(test)]
+pub(crate) mod fakes;
+pub(crate) mod locale;
+pub mod members;
+pub mod mock;
 pub mod models;
 pub mod mpeix_api;
+pub mod notes;
 pub mod peer;
+pub mod queue;
+pub mod recent;
 pub mod renderer;
 pub mod schedule;
 pub mod search;
+pub mod theme;
 pub mod usecases;
+pub mod warnings;
@@ -0,0 +1,55 @@
+use crate::models::Locale;
+
+/// Guess the language of the message by the prevailing alphabet.
+///
+/// Slash-commands are the same in every language, so they are ignored,
+/// as well as messages without letters (e.g. group numbers like `"08-19"`).
+pub(crate) fn detect_locale(text: &str) -> Option<Locale> {
+    if text.trim_start().starts_with('/') {
+        return None;
+    }
+    let (cyrillic, latin) = text.chars().fold((0, 0), |(cyrillic, latin), c| match c {
+        'а'..='я' | 'А'..='Я' | 'ё' | 'Ё' => (cyrillic + 1, latin),
+        'a'..='z' | 'A'..='Z' => (cyrillic, latin + 1),
+        _ => (cyrillic, latin),
+    });
+    match cyrillic.cmp(&latin) {
+        std::cmp::Ordering::Greater => Some(Locale::Ru),
+        std::cmp::Ordering::Less => Some(Locale::En),
+        std::cmp::Ordering::Equal => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::Locale;
+
+    use super::detect_locale;
+
+    #[test]
+    fn test_detect_russian() {
+        for text in [
+            "Начать",
+            "сегодня",
+            "А-08-19",
+            "Иванов И.И.",
+            "расписание на monday",
+        ] {
+            assert_eq!(Some(Locale::Ru), detect_locale(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_detect_english() {
+        for text in ["Start", "today", "next week", "help me with пары"] {
+            assert_eq!(Some(Locale::En), detect_locale(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn test_detect_nothing() {
+        for text in ["/start", "/today", "08-19", "", "👍"] {
+            assert_eq!(None, detect_locale(text), "{text}");
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common_database::Database;
+use domain_schedule_models::ScheduleType;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::models::{ChatMember, MemberMention, RecentSchedule};
+
+/// Repository for accessing table `peer_member_schedule` of the mpeix database,
+/// personal schedules linked by the members of the group chats
+#[async_trait]
+pub trait MemberSchedulesRepository: Send + Sync {
+    async fn init_member_schedules_tables(&self) -> anyhow::Result<()>;
+
+    /// Get the schedule linked by the mentioned member of the chat,
+    /// nothing if the chat or the member is deleted
+    async fn get_member_schedule(
+        &self,
+        peer_id: i64,
+        mention: &MemberMention,
+    ) -> anyhow::Result<Option<RecentSchedule>>;
+
+    /// Link the schedule to the member of the chat, the previous link is replaced
+    async fn link_member_schedule(
+        &self,
+        peer_id: i64,
+        member: &ChatMember,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()>;
+
+    /// Remove the link of the member, returns `false` if there was no link
+    async fn unlink_member_schedule(&self, peer_id: i64, member_id: i64) -> anyhow::Result<bool>;
+
+    /// Get all schedules linked by the members of the chat, in the order of linking
+    async fn get_member_schedules(
+        &self,
+        peer_id: i64,
+    ) -> anyhow::Result<Vec<(ChatMember, RecentSchedule)>>;
+}
+
+/// [MemberSchedulesRepository] backed by the mpeix database
+pub struct PgMemberSchedulesRepository {
+    database: Arc<Database>,
+}
+
+impl PgMemberSchedulesRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl MemberSchedulesRepository for PgMemberSchedulesRepository {
+    async fn init_member_schedules_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_member_schedule.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'peer_member_schedule' creation")?;
+        info!("Table 'peer_member_schedule' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn get_member_schedule(
+        &self,
+        peer_id: i64,
+        mention: &MemberMention,
+    ) -> anyhow::Result<Option<RecentSchedule>> {
+        let (member_id, username) = match mention {
+            MemberMention::Id(id) => (Some(*id), None),
+            MemberMention::Username(username) => (None, Some(username.to_lowercase())),
+        };
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_peer_member_schedule.pgsql");
+        Ok(client
+            .query(stmt, &[&peer_id, &member_id, &username])
+            .await
+            .with_context(|| "Error selecting peer member schedule from db")?
+            .into_iter()
+            .find_map(map_from_db_model))
+    }
+
+    async fn link_member_schedule(
+        &self,
+        peer_id: i64,
+        member: &ChatMember,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()> {
+        let member = ChatMember {
+            id: member.id,
+            username: member.username.as_ref().map(|it| it.to_lowercase()),
+        };
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/upsert_peer_member_schedule.pgsql");
+        client
+            .execute(
+                stmt,
+                &[
+                    &peer_id,
+                    &member.id,
+                    &member.username,
+                    &schedule.name,
+                    &schedule.r#type.to_string(),
+                ],
+            )
+            .await
+            .with_context(|| "Error inserting peer member schedule to db")?;
+        Ok(())
+    }
+
+    async fn unlink_member_schedule(&self, peer_id: i64, member_id: i64) -> anyhow::Result<bool> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/delete_peer_member_schedule.pgsql");
+        Ok(client
+            .execute(stmt, &[&peer_id, &member_id])
+            .await
+            .with_context(|| "Error deleting peer member schedule from db")?
+            > 0)
+    }
+
+    async fn get_member_schedules(
+        &self,
+        peer_id: i64,
+    ) -> anyhow::Result<Vec<(ChatMember, RecentSchedule)>> {
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_peer_member_schedules.pgsql");
+        Ok(client
+            .query(stmt, &[&peer_id])
+            .await
+            .with_context(|| "Error selecting peer member schedules from db")?
+            .into_iter()
+            .filter_map(|row| {
+                let member = ChatMember {
+                    id: row.try_get("member_id").ok()?,
+                    username: row.try_get("username").ok()?,
+                };
+                Some((member, map_from_db_model(row)?))
+            })
+            .collect())
+    }
+}
+
+fn map_from_db_model(row: Row) -> Option<RecentSchedule> {
+    Some(RecentSchedule {
+        name: row.try_get("schedule_name").ok()?,
+        r#type: row
+            .try_get::<_, String>("schedule_type")
+            .ok()?
+            .parse::<ScheduleType>()
+            .ok()?,
+    })
+}
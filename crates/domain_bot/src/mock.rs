@@ -0,0 +1,183 @@
+use chrono::{Datelike, Days, Local, NaiveDate, Weekday};
+use domain_schedule_models::{
+    ClassesType, Schedule, ScheduleChangelog, ScheduleDiff, ScheduleFetchLatency,
+    ScheduleSearchResult, ScheduleType, ScheduleVerification, SessionSchedule, TrendingSchedule,
+    TrendingSchedules,
+};
+
+/// Fixture-backed fake of [crate::mpeix_api::MpeixApi] for offline development.
+///
+/// Search results are filtered from `res/mock/search.json`, every schedule has
+/// the same classes from `res/mock/schedule.json`, moved to the requested week.
+#[derive(Clone, Default)]
+pub struct MockMpeixApi;
+
+impl MockMpeixApi {
+    pub fn schedule(&self, r#type: &ScheduleType, name: &str, offset: i32) -> Schedule {
+        let mut schedule =
+            serde_json::from_str::<Schedule>(include_str!("../res/mock/schedule.json"))
+                .expect("Invalid schedule fixture");
+        schedule.name = name.to_owned();
+        schedule.r#type = r#type.to_owned();
+        let week_start = Local::now().date_naive().week(Weekday::Mon).first_day();
+        let week_start = if offset >= 0 {
+            week_start.checked_add_days(Days::new(offset as u64 * 7))
+        } else {
+            week_start.checked_sub_days(Days::new(-offset as u64 * 7))
+        }
+        .unwrap_or(week_start);
+        for week in schedule.weeks.iter_mut() {
+            let days_offset = (week_start - week.first_day_of_week).num_days();
+            let move_date = |date: NaiveDate| {
+                if days_offset >= 0 {
+                    date.checked_add_days(Days::new(days_offset as u64))
+                } else {
+                    date.checked_sub_days(Days::new(-days_offset as u64))
+                }
+                .unwrap_or(date)
+            };
+            week.first_day_of_week = week_start;
+            week.week_of_year = week_start.iso_week().week() as u8;
+            week.days
+                .iter_mut()
+                .for_each(|day| day.date = move_date(day.date));
+        }
+        schedule
+    }
+
+    /// The fixture has no exams, so the first classes of each day of the next week
+    /// pretend to be exams
+    pub fn session(&self, r#type: &ScheduleType, name: &str) -> SessionSchedule {
+        let mut schedule = self.schedule(r#type, name, 1);
+        schedule
+            .weeks
+            .iter_mut()
+            .flat_map(|week| week.days.iter_mut())
+            .filter_map(|day| day.classes.first_mut())
+            .for_each(|classes| classes.r#type = ClassesType::Exam);
+        SessionSchedule::from_weeks(schedule.name, schedule.r#type, schedule.weeks)
+    }
+
+    /// Fixtures do not change, so the schedule always matches the (missing) cache
+    pub fn verify_schedule(&self, r#type: &ScheduleType, name: &str) -> ScheduleVerification {
+        ScheduleVerification {
+            name: name.to_owned(),
+            r#type: r#type.to_owned(),
+            week_start: Local::now().date_naive().week(Weekday::Mon).first_day(),
+            cache: None,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn changelog(&self, r#type: &ScheduleType, name: &str, days: u32) -> ScheduleChangelog {
+        ScheduleChangelog {
+            name: name.to_owned(),
+            r#type: r#type.to_owned(),
+            since: Local::now().naive_local() - chrono::Duration::days(days as i64),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Every week has the same classes, so the weeks never differ
+    pub fn diff(&self, r#type: &ScheduleType, name: &str, from: i32, to: i32) -> ScheduleDiff {
+        let week_start = |offset| {
+            self.schedule(r#type, name, offset)
+                .weeks
+                .first()
+                .map(|week| week.first_day_of_week)
+                .unwrap_or_default()
+        };
+        ScheduleDiff {
+            name: name.to_owned(),
+            r#type: r#type.to_owned(),
+            from_week_start: week_start(from),
+            to_week_start: week_start(to),
+            changes: Vec::new(),
+        }
+    }
+
+    /// Nothing is fetched from MPEI, so there are no stats
+    pub fn latency(&self) -> ScheduleFetchLatency {
+        ScheduleFetchLatency {
+            strategy: "mock".to_owned(),
+            since: Local::now().naive_local(),
+            sources: Default::default(),
+        }
+    }
+
+    pub fn search(&self, query: &str, r#type: Option<ScheduleType>) -> Vec<ScheduleSearchResult> {
+        let query = query.to_lowercase();
+        serde_json::from_str::<Vec<ScheduleSearchResult>>(include_str!("../res/mock/search.json"))
+            .expect("Invalid search results fixture")
+            .into_iter()
+            .filter(|it| r#type.as_ref().map_or(true, |t| &it.r#type == t))
+            .filter(|it| it.name.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    /// Search results fixture in its order, as if the first one is the most requested
+    pub fn trending(&self, r#type: Option<ScheduleType>, limit: usize) -> TrendingSchedules {
+        let items = self
+            .search("", r#type)
+            .into_iter()
+            .take(limit)
+            .collect::<Vec<_>>();
+        let count = items.len() as i64;
+        TrendingSchedules {
+            window_days: 7,
+            items: items
+                .into_iter()
+                .enumerate()
+                .map(|(i, it)| TrendingSchedule {
+                    name: it.name,
+                    r#type: it.r#type,
+                    requests: (count - i as i64) * 10,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, Local, Weekday};
+    use domain_schedule_models::ScheduleType;
+
+    use super::MockMpeixApi;
+
+    #[test]
+    fn test_mock_schedule_is_moved_to_requested_week() {
+        let schedule = MockMpeixApi.schedule(&ScheduleType::Group, "С-12-21", 1);
+        let next_week_start =
+            Local::now().date_naive().week(Weekday::Mon).first_day() + Days::new(7);
+        assert_eq!("С-12-21", schedule.name);
+        let week = &schedule.weeks[0];
+        assert_eq!(next_week_start, week.first_day_of_week);
+        assert!(week
+            .days
+            .iter()
+            .all(|day| day.date.week(Weekday::Mon).first_day() == next_week_start));
+    }
+
+    #[test]
+    fn test_mock_search() {
+        assert_eq!(1, MockMpeixApi.search("иванов", None).len());
+        assert!(MockMpeixApi
+            .search("иванов", Some(ScheduleType::Group))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_mock_trending() {
+        let trending = MockMpeixApi.trending(Some(ScheduleType::Group), 2);
+        assert_eq!(
+            vec!["А-08-19", "А-12-19"],
+            trending
+                .items
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(trending.items[0].requests > trending.items[1].requests);
+    }
+}
@@ -1,12 +1,108 @@
-use chrono::NaiveDate;
-use domain_schedule_models::{Classes, Day, ScheduleType, Week};
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::bail;
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use domain_buildings::MapProvider;
+use domain_schedule_models::{
+    Classes, ClassesChange, ClassesTime, Day, DaysRange, PeriodType, ScheduleDiff, ScheduleType,
+    ScheduleVerification, SessionSchedule, Week,
+};
+use serde::Serialize;
+
+use crate::{peer::repository::PlatformId, theme::ThemeKind};
 
 /// Representation of database row from table 'peer'
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Peer {
     pub id: i64,
     pub selected_schedule: String,
     pub selected_schedule_type: ScheduleType,
     pub selecting_schedule: bool,
+    /// Preferred map service for map links next to the rooms, links are disabled if `None`
+    pub map_provider: Option<MapProvider>,
+    /// Strictly opt-in: achievements are not tracked until the peer enables them
+    pub achievements_enabled: bool,
+    /// Days of the week which are shown in the week schedule
+    pub days_range: DaysRange,
+    /// Language of the replies, detected from the first message if `None`
+    pub locale: Option<Locale>,
+    /// Strictly opt-in: minutes to get to the university for the evening summary,
+    /// the summary is not sent if `None`
+    pub commute_minutes: Option<u16>,
+    /// Analytics and activity of the peer are not recorded,
+    /// and the peer is excluded from the experiments
+    pub privacy_mode: bool,
+    /// Strictly opt-in: weekly changelog of the selected schedule on Sunday evening
+    pub changelog_enabled: bool,
+    /// Strictly opt-in: summary of the upcoming week of the selected schedule on Sunday evening
+    pub week_ahead_enabled: bool,
+    /// Strictly opt-in: full schedule of the next week on Sunday evening
+    pub digest_enabled: bool,
+    /// Week replies come with the `.ics` file, if the platform supports files
+    pub ics_enabled: bool,
+    /// Day replies come with the directions to the room of the first class
+    pub newcomer_mode: bool,
+    /// Emojis of the replies, the default theme of the platform is used if `None`
+    pub theme: Option<ThemeKind>,
+    /// Strictly opt-in: push with the changes of the selected schedule as soon as they are detected
+    pub subscribed: bool,
+    /// Time of the data deletion request, the deleted peer is excluded from all features
+    /// and purged permanently after the undo window, unless it restores the data
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// Peer preferences which affect the delivery of the reply
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReplyContext {
+    pub locale: Locale,
+    pub privacy_mode: bool,
+    /// Attach the `.ics` file to the week replies
+    pub ics_enabled: bool,
+    pub theme: Option<ThemeKind>,
+}
+
+impl From<&Peer> for ReplyContext {
+    fn from(peer: &Peer) -> Self {
+        Self {
+            locale: peer.locale.unwrap_or_default(),
+            privacy_mode: peer.privacy_mode,
+            ics_enabled: peer.ics_enabled,
+            theme: peer.theme,
+        }
+    }
+}
+
+/// Language of the bot replies
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    #[default]
+    Ru,
+    En,
+}
+
+impl AsRef<str> for Locale {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Ru => "ru",
+            Self::En => "en",
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "ru" => Ok(Self::Ru),
+            "en" => Ok(Self::En),
+            _ => bail!("Unknown locale: '{s}'"),
+        }
+    }
 }
 
 /// Input actions for the bot
@@ -16,54 +112,426 @@ pub enum UserAction {
     Start,
     /// User requested the entire schedule for a certain week
     WeekWithOffset(i8),
+    /// User requested the week in full, after it was summarized because of the size
+    FullWeekWithOffset(i8),
     /// User requested the schedule for a certain day
     DayWithOffset(i8),
+    /// User requested the calendar overview of a certain month
+    MonthWithOffset(i8),
+    /// User asked what changes next week compared to the current one
+    NextWeekChanges,
+    /// User requested exams and consultations of the exam session
+    Exams,
+    /// User requested the schedule for a certain date, e.g. "15 марта" or "через неделю в пятницу"
+    SpecificDate(NaiveDate),
+    /// User requested the schedule for several days, e.g. "с понедельника по среду" or "на 3 дня",
+    /// both dates are inclusive
+    DayRange { from: NaiveDate, to: NaiveDate },
     /// User requested a schedule change
     ChangeScheduleIntent,
+    /// User requested the list of recently selected schedules
+    RecentSchedules,
     /// User requested an upcoming events (like as mpeix dashboard page)
     UpcomingEvents,
+    /// User asked how many classes are left today
+    RemainingToday,
     /// User requested help
     Help,
+    /// User enabled (or disabled, if `None`) map links next to the rooms
+    SetMapProvider(Option<MapProvider>),
+    /// User requested the list of achievements
+    Achievements,
+    /// User enabled or disabled achievements tracking
+    SetAchievementsEnabled(bool),
+    /// User changed days of the week which are shown in the week schedule
+    SetDaysRange(DaysRange),
+    /// User changed the language of the replies (or switched to the other one, if `None`)
+    SetLocale(Option<Locale>),
+    /// User enabled the evening summary with the commute time in minutes (or disabled, if `None`)
+    SetCommute(Option<u16>),
+    /// User enabled or disabled the privacy mode
+    SetPrivacyMode(bool),
+    /// User enabled or disabled the weekly changelog of the schedule
+    SetChangelogEnabled(bool),
+    /// User enabled or disabled the summary of the upcoming week on Sundays
+    SetWeekAheadEnabled(bool),
+    /// User enabled the weekly digest with the schedule of the next week
+    EnableDigest,
+    /// User disabled the weekly digest
+    DisableDigest,
+    /// User subscribed to the changes of the selected schedule
+    Subscribe,
+    /// User unsubscribed from the changes of the selected schedule
+    Unsubscribe,
+    /// User enabled or disabled the `.ics` files next to the week schedule
+    SetIcsEnabled(bool),
+    /// User requested the export of all data stored about the peer
+    ExportData,
+    /// User requested the deletion of all data stored about the peer
+    DeleteData,
+    /// User cancelled the deletion of the data before it is purged
+    RestoreData,
+    /// User asked how to get to the room, e.g. "как пройти в К-601"
+    Navigation(String),
+    /// User enabled or disabled the directions to the first class of the day
+    SetNewcomerMode(bool),
+    /// User changed the emojis of the replies
+    SetTheme(ThemeKind),
+    /// Admin requested the integrity check of the schedule: MPEI versus cache
+    VerifySchedule(String),
+    /// Admin requested the health self-test of the bot
+    Ping,
+    /// Admin blocked the peers matching the pattern, e.g. "/block telegram:123456"
+    Block(BlockPattern),
+    /// Admin removed the pattern from the blocklist or lifted the temporary block
+    Unblock(BlockPattern),
+    /// Admin requested the blocked patterns and the temporarily blocked peers
+    Blocklist,
+    /// Chat admin attached a note to the date, e.g. "контрольная по физике"
+    AddNote { date: NaiveDate, text: String },
+    /// Chat admin removed all notes of the date
+    RemoveNotes(NaiveDate),
+    /// Member of the group chat linked the personal group, e.g. "моя группа А-08-19"
+    LinkMemberSchedule(String),
+    /// Member of the group chat removed the link, so nobody can see the schedule by mention
+    UnlinkMemberSchedule,
+    /// User requested the schedule of the mentioned chat member, e.g. "пары @username завтра"
+    MemberSchedule {
+        member: MemberMention,
+        day_offset: i8,
+    },
     /// Maybe user types new chedule to change... who knows?
     Unknown(String),
 }
 
+impl UserAction {
+    /// Name of the action variant, which is used to disable the action with the kill switch
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            UserAction::Start => "Start",
+            UserAction::WeekWithOffset(_) => "WeekWithOffset",
+            UserAction::FullWeekWithOffset(_) => "FullWeekWithOffset",
+            UserAction::DayWithOffset(_) => "DayWithOffset",
+            UserAction::MonthWithOffset(_) => "MonthWithOffset",
+            UserAction::NextWeekChanges => "NextWeekChanges",
+            UserAction::Exams => "Exams",
+            UserAction::SpecificDate(_) => "SpecificDate",
+            UserAction::DayRange { .. } => "DayRange",
+            UserAction::ChangeScheduleIntent => "ChangeScheduleIntent",
+            UserAction::RecentSchedules => "RecentSchedules",
+            UserAction::UpcomingEvents => "UpcomingEvents",
+            UserAction::RemainingToday => "RemainingToday",
+            UserAction::Help => "Help",
+            UserAction::SetMapProvider(_) => "SetMapProvider",
+            UserAction::Achievements => "Achievements",
+            UserAction::SetAchievementsEnabled(_) => "SetAchievementsEnabled",
+            UserAction::SetDaysRange(_) => "SetDaysRange",
+            UserAction::SetLocale(_) => "SetLocale",
+            UserAction::SetCommute(_) => "SetCommute",
+            UserAction::SetPrivacyMode(_) => "SetPrivacyMode",
+            UserAction::SetChangelogEnabled(_) => "SetChangelogEnabled",
+            UserAction::SetWeekAheadEnabled(_) => "SetWeekAheadEnabled",
+            UserAction::EnableDigest => "EnableDigest",
+            UserAction::DisableDigest => "DisableDigest",
+            UserAction::Subscribe => "Subscribe",
+            UserAction::Unsubscribe => "Unsubscribe",
+            UserAction::SetIcsEnabled(_) => "SetIcsEnabled",
+            UserAction::ExportData => "ExportData",
+            UserAction::DeleteData => "DeleteData",
+            UserAction::RestoreData => "RestoreData",
+            UserAction::Navigation(_) => "Navigation",
+            UserAction::SetNewcomerMode(_) => "SetNewcomerMode",
+            UserAction::SetTheme(_) => "SetTheme",
+            UserAction::VerifySchedule(_) => "VerifySchedule",
+            UserAction::Ping => "Ping",
+            UserAction::Block(_) => "Block",
+            UserAction::Unblock(_) => "Unblock",
+            UserAction::Blocklist => "Blocklist",
+            UserAction::AddNote { .. } => "AddNote",
+            UserAction::RemoveNotes(_) => "RemoveNotes",
+            UserAction::LinkMemberSchedule(_) => "LinkMemberSchedule",
+            UserAction::UnlinkMemberSchedule => "UnlinkMemberSchedule",
+            UserAction::MemberSchedule { .. } => "MemberSchedule",
+            UserAction::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+/// Rendered texts of the weeks made of the same schedule request,
+/// see [crate::schedule::repository::ScheduleRepository::get_schedule_with_renders].
+///
+/// E.g. when the whole group chat taps "эта неделя" at once, the requests share
+/// not only the HTTP call, but also the rendered text. The texts live as long as the replies.
+#[derive(Clone, Default)]
+pub struct RenderedWeeks(Arc<Mutex<Vec<(RenderedWeekKey, String)>>>);
+
+/// Everything the text of the week depends on, except the schedule request itself
+#[derive(PartialEq)]
+pub(crate) struct RenderedWeekKey {
+    pub(crate) locale: Locale,
+    pub(crate) theme: ThemeKind,
+    pub(crate) summarized: bool,
+    pub(crate) map_provider: Option<MapProvider>,
+    pub(crate) days: Vec<NaiveDate>,
+    pub(crate) notes: Vec<ChatNote>,
+}
+
+impl RenderedWeeks {
+    /// Text rendered for the same `key` before, otherwise the text made by `render`.
+    /// The concurrent renders with the same key wait for the first one.
+    pub(crate) fn get_or_render(
+        &self,
+        key: RenderedWeekKey,
+        render: impl FnOnce() -> String,
+    ) -> String {
+        let mut rendered = self.0.lock().unwrap();
+        if let Some((_, text)) = rendered.iter().find(|(it, _)| *it == key) {
+            return text.to_owned();
+        }
+        let text = render();
+        rendered.push((key, text.clone()));
+        text
+    }
+}
+
 /// Rendered reply to answer
 pub enum Reply {
-    StartGreetings,
+    StartGreetings {
+        /// Most requested groups for the keyboard, empty if they are unavailable
+        suggestions: Vec<String>,
+    },
     AlreadyStarted {
         schedule_name: String,
     },
     Week {
+        schedule_name: String,
         week_offset: i8,
         week: Week,
         schedule_type: ScheduleType,
+        map_provider: Option<MapProvider>,
+        /// Notes of the chat for the days of the week
+        notes: Vec<ChatNote>,
+        /// The week is never summarized, even if it has too many classes
+        full: bool,
+        /// Texts of the week shared with the concurrent identical requests,
+        /// `None` if the week is not made of the shared schedule request
+        rendered: Option<RenderedWeeks>,
     },
     Day {
         day_offset: i8,
         day: Day,
         schedule_type: ScheduleType,
+        map_provider: Option<MapProvider>,
+        period_type: PeriodType,
+        /// Notes of the chat for the day
+        notes: Vec<ChatNote>,
+        /// Append the directions to the room of the first class
+        newcomer_mode: bool,
+        /// Classes of the day moved to other rooms, shown above the schedule
+        room_changes: Vec<RoomChangeWarning>,
+    },
+    /// Several days in a row, days out of the peer's days range are skipped
+    DayRange {
+        from: NaiveDate,
+        to: NaiveDate,
+        days: Vec<Day>,
+        schedule_type: ScheduleType,
+        map_provider: Option<MapProvider>,
+        /// Notes of the chat for the days of the range
+        notes: Vec<ChatNote>,
+    },
+    /// Calendar grid of the month with study days, exams and holidays
+    Month(MonthOverview),
+    /// Changes of the classes of the next week compared to the current one
+    NextWeekChanges(Box<ScheduleDiff>),
+    /// Exams and consultations of the nearest exam session
+    Exams {
+        session: Box<SessionSchedule>,
+        map_provider: Option<MapProvider>,
     },
     UpcomingEvents {
         prediction: UpcomingEventsPrediction,
         schedule_type: ScheduleType,
     },
+    /// Number of today's classes which are not finished yet
+    /// and the end time of the last one
+    RemainingToday {
+        count: usize,
+        last_class_end: Option<NaiveTime>,
+    },
     ScheduleChangedSuccessfully(String),
     ScheduleSearchResults {
         schedule_name: String,
         results: Vec<String>,
         results_contains_person: bool,
+        /// Lines like `Name — position, department` for persons with known metadata,
+        /// which help to choose between namesakes
+        person_details: Vec<String>,
     },
     CannotFindSchedule(String),
+    /// Selected schedule does not exist anymore (probably the group was renamed),
+    /// `results` contains the most similar schedules to choose from
+    ScheduleRenamed {
+        schedule_name: String,
+        results: Vec<String>,
+    },
     ReadyToChangeSchedule,
+    /// Names of recently selected schedules, the most recent first
+    RecentSchedules(Vec<String>),
     ShowHelp,
+    MapProviderChanged(Option<MapProvider>),
+    DaysRangeChanged(DaysRange),
+    LocaleChanged(Locale),
+    CommuteChanged(Option<u16>),
+    PrivacyModeChanged(bool),
+    ChangelogEnabledChanged(bool),
+    WeekAheadEnabledChanged(bool),
+    DigestEnabledChanged(bool),
+    SubscriptionChanged(bool),
+    IcsEnabledChanged(bool),
+    /// The platform cannot send files, e.g. `.ics` files or the data export
+    DocumentsNotSupported,
+    /// Data stored about the peer, sent as a JSON file
+    DataExport(Box<PeerDataExport>),
+    /// Peer is marked deleted and will be purged on `purge_date`
+    DataDeletionScheduled {
+        purge_date: NaiveDate,
+    },
+    /// Reply to any command of the deleted peer, except the restore
+    DataDeletionPending {
+        purge_date: NaiveDate,
+    },
+    DataRestored,
+    /// Restore is requested, but the peer is not deleted
+    NothingToRestore,
+    /// Directions to the room, e.g. `К-601`
+    Navigation {
+        room: String,
+        map_provider: Option<MapProvider>,
+    },
+    NewcomerModeChanged(bool),
+    ThemeChanged(ThemeKind),
+    /// Weekly push with the summary of the schedule changes
+    WeeklyChangelog(ChangelogDigest),
+    /// Sunday push with the summary of the upcoming week
+    WeekAhead(WeekAheadDigest),
+    /// The only message to the peer which has been inactive for a long time
+    ReEngagement {
+        schedule_name: String,
+    },
+    /// Push with the changes of the schedule detected since the previous poll
+    ScheduleChanged {
+        schedule_name: String,
+        changes: Vec<ClassesChange>,
+    },
+    /// Evening push with the first class of tomorrow and the time to leave home
+    EveningSummary {
+        first_class: Box<Classes>,
+        leave_at: NaiveTime,
+    },
+    /// Result of the admin integrity check of the schedule
+    ScheduleVerification(Box<ScheduleVerification>),
+    Pong(HealthReport),
+    /// Blocked patterns and the peers temporarily blocked by the rate limiter
+    /// with the minutes left, for the admin
+    Blocklist {
+        patterns: Vec<BlockPattern>,
+        temporarily_blocked: Vec<(PlatformId, u64)>,
+    },
+    NoteAdded(NaiveDate),
+    NotesRemoved {
+        date: NaiveDate,
+        count: u64,
+    },
+    NoteRejected(NoteRejection),
+    /// Name of the schedule linked by the member of the group chat
+    MemberScheduleLinked(String),
+    MemberScheduleUnlinked,
+    /// Mentioned member has not linked the schedule in this chat (or opted out)
+    MemberScheduleNotLinked,
+    /// The command makes sense only in the group chats
+    GroupChatOnly,
+    Achievements {
+        stats: AchievementStats,
+        badges: Vec<Badge>,
+    },
+    AchievementsDisabled,
+    AchievementsEnabledChanged(bool),
     UnknownCommand,
+    /// The action is disabled with the kill switch
+    CommandDisabled,
     /// Type for non-text messages
     UnknownMessageType,
     /// Type for default error message
     InternalError,
 }
 
+impl Reply {
+    /// Name of the reply variant for logs and audit
+    pub fn variant_name(&self) -> &'static str {
+        match self {
+            Reply::StartGreetings { .. } => "StartGreetings",
+            Reply::AlreadyStarted { .. } => "AlreadyStarted",
+            Reply::Week { .. } => "Week",
+            Reply::Day { .. } => "Day",
+            Reply::DayRange { .. } => "DayRange",
+            Reply::Month(_) => "Month",
+            Reply::NextWeekChanges(_) => "NextWeekChanges",
+            Reply::Exams { .. } => "Exams",
+            Reply::UpcomingEvents { .. } => "UpcomingEvents",
+            Reply::RemainingToday { .. } => "RemainingToday",
+            Reply::ScheduleChangedSuccessfully(_) => "ScheduleChangedSuccessfully",
+            Reply::ScheduleSearchResults { .. } => "ScheduleSearchResults",
+            Reply::CannotFindSchedule(_) => "CannotFindSchedule",
+            Reply::ScheduleRenamed { .. } => "ScheduleRenamed",
+            Reply::ReadyToChangeSchedule => "ReadyToChangeSchedule",
+            Reply::RecentSchedules(_) => "RecentSchedules",
+            Reply::ShowHelp => "ShowHelp",
+            Reply::MapProviderChanged(_) => "MapProviderChanged",
+            Reply::DaysRangeChanged(_) => "DaysRangeChanged",
+            Reply::LocaleChanged(_) => "LocaleChanged",
+            Reply::CommuteChanged(_) => "CommuteChanged",
+            Reply::PrivacyModeChanged(_) => "PrivacyModeChanged",
+            Reply::ChangelogEnabledChanged(_) => "ChangelogEnabledChanged",
+            Reply::WeekAheadEnabledChanged(_) => "WeekAheadEnabledChanged",
+            Reply::DigestEnabledChanged(_) => "DigestEnabledChanged",
+            Reply::SubscriptionChanged(_) => "SubscriptionChanged",
+            Reply::IcsEnabledChanged(_) => "IcsEnabledChanged",
+            Reply::DocumentsNotSupported => "DocumentsNotSupported",
+            Reply::DataExport(_) => "DataExport",
+            Reply::DataDeletionScheduled { .. } => "DataDeletionScheduled",
+            Reply::DataDeletionPending { .. } => "DataDeletionPending",
+            Reply::DataRestored => "DataRestored",
+            Reply::NothingToRestore => "NothingToRestore",
+            Reply::Navigation { .. } => "Navigation",
+            Reply::NewcomerModeChanged(_) => "NewcomerModeChanged",
+            Reply::ThemeChanged(_) => "ThemeChanged",
+            Reply::WeeklyChangelog(_) => "WeeklyChangelog",
+            Reply::WeekAhead(_) => "WeekAhead",
+            Reply::ReEngagement { .. } => "ReEngagement",
+            Reply::ScheduleChanged { .. } => "ScheduleChanged",
+            Reply::EveningSummary { .. } => "EveningSummary",
+            Reply::ScheduleVerification(_) => "ScheduleVerification",
+            Reply::Pong(_) => "Pong",
+            Reply::Blocklist { .. } => "Blocklist",
+            Reply::NoteAdded(_) => "NoteAdded",
+            Reply::NotesRemoved { .. } => "NotesRemoved",
+            Reply::NoteRejected(_) => "NoteRejected",
+            Reply::MemberScheduleLinked(_) => "MemberScheduleLinked",
+            Reply::MemberScheduleUnlinked => "MemberScheduleUnlinked",
+            Reply::MemberScheduleNotLinked => "MemberScheduleNotLinked",
+            Reply::GroupChatOnly => "GroupChatOnly",
+            Reply::Achievements { .. } => "Achievements",
+            Reply::AchievementsDisabled => "AchievementsDisabled",
+            Reply::AchievementsEnabledChanged(_) => "AchievementsEnabledChanged",
+            Reply::UnknownCommand => "UnknownCommand",
+            Reply::CommandDisabled => "CommandDisabled",
+            Reply::UnknownMessageType => "UnknownMessageType",
+            Reply::InternalError => "InternalError",
+        }
+    }
+}
+
 pub enum UpcomingEventsPrediction {
     NoClassesNextWeek,
     ClassesTodayNotStarted {
@@ -78,6 +546,12 @@ pub enum UpcomingEventsPrediction {
         time_prediction: TimePrediction,
         future_classes: Vec<Classes>,
     },
+    /// There are no classes because of the session or vacation,
+    /// `date` is the first day of the classes after it
+    ClassesResume {
+        period_type: PeriodType,
+        date: NaiveDate,
+    },
 }
 
 pub enum TimePrediction {
@@ -87,3 +561,380 @@ pub enum TimePrediction {
         duration: chrono::Duration,
     },
 }
+
+/// Kinds of the days of the month in the calendar overview
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonthOverview {
+    /// The first day of the month
+    pub first_day: NaiveDate,
+    /// Kind of each day of the month, starting from the first one
+    pub days: Vec<MonthDayKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonthDayKind {
+    /// Day with classes
+    Study,
+    /// Day with at least one exam
+    Exam,
+    /// Day without classes during the vacation
+    Holiday,
+    /// Day without classes during the study weeks or the session
+    DayOff,
+}
+
+/// Result of peers' selected schedules validation
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerMigrationReport {
+    pub dry_run: bool,
+    pub total_peers: usize,
+    pub total_schedules: usize,
+    pub valid_schedules: usize,
+    pub migrations: Vec<ScheduleMigration>,
+    pub unresolved: Vec<UnresolvedSchedule>,
+}
+
+/// Selected schedule which was (or will be, in dry-run mode) replaced with the new one
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleMigration {
+    pub schedule_type: ScheduleType,
+    pub old_name: String,
+    pub new_name: String,
+    pub peers: usize,
+}
+
+/// Selected schedule which cannot be validated or fixed automatically
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnresolvedSchedule {
+    pub schedule_type: ScheduleType,
+    pub name: String,
+    pub peers: usize,
+    pub reason: String,
+    pub candidates: Vec<String>,
+}
+
+/// Stats of the re-engagement campaign since the start of the activity tracking
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReEngagementStats {
+    pub inactive_weeks: u32,
+    /// Inactive peers which have not been contacted yet, including the ones in privacy mode
+    pub inactive: i64,
+    pub contacted: i64,
+    /// Contacted peers which sent a message after the re-engagement message
+    pub returned: i64,
+}
+
+/// Representation of database row from table 'peer_note':
+/// short note of the chat attached to the date, e.g. "контрольная по физике"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatNote {
+    pub date: NaiveDate,
+    pub text: String,
+}
+
+/// User who sent the message to the chat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMember {
+    /// Platform id of the user
+    pub id: i64,
+    /// Username for the mentions like `@username`, if the platform has them
+    pub username: Option<String>,
+}
+
+/// Mention of the chat member in the message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemberMention {
+    /// Mention by username like `@username` (Telegram), in lowercase without `@`
+    Username(String),
+    /// Mention by platform id like `[id123|Name]` (VK)
+    Id(i64),
+}
+
+/// Schedule changes detected during the week, aggregated by their kind
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangelogDigest {
+    pub schedule_name: String,
+    /// Classes which were removed from one time slot and added to another one
+    pub moved: usize,
+    pub added: usize,
+    pub removed: usize,
+    /// Classes with the changed room, teacher, etc.
+    pub changed: usize,
+}
+
+impl ChangelogDigest {
+    pub fn is_empty(&self) -> bool {
+        self.moved + self.added + self.removed + self.changed == 0
+    }
+}
+
+/// Upcoming week of the schedule, summarized day by day
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WeekAheadDigest {
+    pub schedule_name: String,
+    /// Days with classes, days off are omitted
+    pub days: Vec<WeekAheadDay>,
+}
+
+impl WeekAheadDigest {
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty()
+    }
+}
+
+/// Upcoming classes moved to another room, e.g. "Матан перенесён в Ж-200"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoomChangeWarning {
+    pub date: NaiveDate,
+    pub class_name: String,
+    pub time: ClassesTime,
+    /// The new room
+    pub place: String,
+}
+
+impl RoomChangeWarning {
+    pub(crate) fn is_about_same_classes(&self, other: &RoomChangeWarning) -> bool {
+        self.date == other.date && self.time == other.time && self.class_name == other.class_name
+    }
+}
+
+/// Day of the [WeekAheadDigest]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WeekAheadDay {
+    pub date: NaiveDate,
+    pub classes_count: usize,
+    /// Start of the first class of the day
+    pub earliest_start: NaiveTime,
+    /// Names of the labs, they usually require preparation
+    pub labs: Vec<String>,
+}
+
+/// Version of the bot and the commit it was built from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    pub commit: &'static str,
+}
+
+/// Build info of the crate where the macro is called: its version and `GIT_COMMIT`
+/// environment variable at the build time, which is passed to the docker build
+#[macro_export]
+macro_rules! build_info {
+    () => {
+        $crate::models::BuildInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: match option_env!("GIT_COMMIT") {
+                Some(commit) => commit,
+                None => "unknown",
+            },
+        }
+    };
+}
+
+/// Health self-test of the bot for the admins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthReport {
+    pub build: BuildInfo,
+    pub uptime_seconds: u64,
+    /// Latency of the `app_schedule` health check, `None` if it is unreachable
+    pub schedule_service_latency_ms: Option<u64>,
+    /// The result of the health check is cached, so it may be a bit outdated
+    pub checked_seconds_ago: u64,
+    /// Share of the schedule requests answered from the cache of `app_schedule`,
+    /// `None` if there were no requests since its start or the stats are unavailable
+    pub cache_hit_percent: Option<u8>,
+}
+
+/// Pattern of the blocked peers: the platform (`telegram`, `vk` or `*` for any)
+/// and the exact peer id or its prefix ending with `*`, e.g. `telegram:123456`,
+/// `vk:2000000*` or `*:-100*`. Patterns matching all peers of the platform are not allowed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPattern {
+    /// `None` for any platform
+    platform: Option<&'static str>,
+    /// Digits of the id without the trailing `*`, may start with `-`
+    id: String,
+    prefix: bool,
+}
+
+impl BlockPattern {
+    pub fn matches(&self, platform_id: &PlatformId) -> bool {
+        let (platform, id) = platform_id.split();
+        if self.platform.is_some_and(|it| it != platform) {
+            return false;
+        }
+        let id = id.to_string();
+        match self.prefix {
+            true => id.starts_with(&self.id),
+            false => id == self.id,
+        }
+    }
+}
+
+impl FromStr for BlockPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((platform, id)) = s.trim().split_once(':') else {
+            bail!("Block pattern '{s}' has no platform");
+        };
+        let platform = match platform {
+            "telegram" => Some("telegram"),
+            "vk" => Some("vk"),
+            "*" => None,
+            _ => bail!("Unknown platform '{platform}' of block pattern '{s}'"),
+        };
+        let (id, prefix) = match id.strip_suffix('*') {
+            Some(id) => (id, true),
+            None => (id, false),
+        };
+        let digits = id.strip_prefix('-').unwrap_or(id);
+        if id.is_empty() || !digits.chars().all(|it| it.is_ascii_digit()) {
+            bail!("Invalid peer id of block pattern '{s}'");
+        }
+        if !prefix && digits.is_empty() {
+            bail!("Invalid peer id of block pattern '{s}'");
+        }
+        Ok(Self {
+            platform,
+            id: id.to_owned(),
+            prefix,
+        })
+    }
+}
+
+impl Display for BlockPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let platform = self.platform.unwrap_or("*");
+        let star = if self.prefix { "*" } else { "" };
+        write!(f, "{platform}:{}{star}", self.id)
+    }
+}
+
+/// Reason why the note was not added or removed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteRejection {
+    /// Only chat admins can change the notes of group chats
+    NotChatAdmin,
+    TooLong,
+    TooManyNotes,
+    PastDate,
+}
+
+/// Representation of database row from table 'peer_achievements'
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct AchievementStats {
+    /// Number of consecutive days with schedule checks (including the last check date)
+    pub current_streak: i32,
+    pub best_streak: i32,
+    pub last_check_date: Option<NaiveDate>,
+    /// Number of schedule checks before 8:00
+    pub early_bird_checks: i32,
+    pub total_checks: i32,
+}
+
+/// Representation of database row from table 'peer_recent_schedule'
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecentSchedule {
+    pub name: String,
+    pub r#type: ScheduleType,
+}
+
+/// Badges which are awarded for achievements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Badge {
+    /// Schedule was checked before 8:00 at least once
+    EarlyBird,
+    /// Schedule was checked every day during a week
+    WeekStreak,
+    /// Schedule was checked every day during a month
+    MonthStreak,
+    /// Schedule was checked a hundred times
+    Centurion,
+}
+
+/// Reply from table 'reply_audit', as it is included into the data export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditedReply {
+    pub created_at: NaiveDateTime,
+    pub reply_variant: String,
+    pub text: String,
+}
+
+/// Everything the bot stores about the peer, requested by the user with "/export"
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeerDataExport {
+    pub exported_at: NaiveDateTime,
+    pub platform: &'static str,
+    pub platform_peer_id: i64,
+    pub selected_schedule: Option<RecentSchedule>,
+    pub preferences: PeerPreferences,
+    /// Pushes the peer subscribed to, e.g. `"weekly_changelog"`
+    pub subscriptions: Vec<&'static str>,
+    pub recent_schedules: Vec<RecentSchedule>,
+    /// Only if the peer enabled achievements
+    pub achievements: Option<AchievementStats>,
+    /// The latest replies from the audit log, the most recent first
+    pub recent_replies: Vec<AuditedReply>,
+    /// Schedules linked by the members of the group chat
+    pub member_schedules: Vec<MemberScheduleExport>,
+    /// Only if the peer requested the deletion of its data
+    pub deleted_at: Option<NaiveDateTime>,
+}
+
+/// Schedule linked by the member of the group chat in the data export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MemberScheduleExport {
+    pub member_id: i64,
+    pub username: Option<String>,
+    pub schedule: RecentSchedule,
+}
+
+/// Settings of the peer in the data export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerPreferences {
+    pub locale: Option<String>,
+    pub map_provider: Option<String>,
+    pub days_range: String,
+    pub commute_minutes: Option<u16>,
+    pub privacy_mode: bool,
+    pub achievements_enabled: bool,
+    pub ics_enabled: bool,
+    pub newcomer_mode: bool,
+    pub theme: Option<String>,
+}
+
+/// File attached to the reply, on the platforms which support files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub file_name: String,
+    pub content: Vec<u8>,
+}
+
+/// Reply which was not delivered because of the transient error, waiting for the next attempt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingReply {
+    pub id: i64,
+    pub platform_id: PlatformId,
+    pub text: String,
+    /// Keyboard already serialized to the JSON format of the platform
+    pub keyboard: Option<String>,
+    /// Number of the failed attempts, including the first one
+    pub attempts: i32,
+}
+
+/// Redacted copy of the reply sent by the bot, stored for support purposes
+#[derive(Debug, PartialEq)]
+pub struct ReplyAuditEntry {
+    pub platform: &'static str,
+    pub platform_peer_id: i64,
+    pub reply_variant: &'static str,
+    pub text: String,
+    /// Version of the keyboard layout shown with the reply, if the platform has A/B tested layouts
+    pub keyboard_version: Option<&'static str>,
+    pub latency_ms: i32,
+}
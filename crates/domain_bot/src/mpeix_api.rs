@@ -1,16 +1,57 @@
-use domain_schedule_models::{Schedule, ScheduleSearchResult, ScheduleType};
+use common_errors::errors::CommonError;
+use domain_schedule_models::{
+    ScheduleChangelog, ScheduleDiff, ScheduleFetchLatency, ScheduleSearchResult, ScheduleType,
+    ScheduleVerification, SessionSchedule, TrendingSchedules,
+};
 use restix::{api, get};
 use serde::Deserialize;
 
-#[api]
+use crate::mock::MockMpeixApi;
+
+#[api(error = "CommonError")]
 pub trait MpeixApi {
+    #[get("/v1/health")]
+    async fn health(&self);
+
     #[get("/v1/{type}/{name}/schedule/{offset}")]
     async fn schedule(
         &self,
         #[path] r#type: &ScheduleType,
         #[path] name: &str,
         #[path] offset: i32,
-    ) -> Schedule;
+    );
+
+    #[get("/v1/{type}/{name}/session")]
+    async fn session(&self, #[path] r#type: &ScheduleType, #[path] name: &str) -> SessionSchedule;
+
+    #[get("/v1/{type}/{name}/changes")]
+    async fn changes(
+        &self,
+        #[path] r#type: &ScheduleType,
+        #[path] name: &str,
+        #[query] days: u32,
+    ) -> ScheduleChangelog;
+
+    #[get("/v1/{type}/{name}/diff")]
+    async fn diff(
+        &self,
+        #[path] r#type: &ScheduleType,
+        #[path] name: &str,
+        #[query] from: i32,
+        #[query] to: i32,
+    ) -> ScheduleDiff;
+
+    #[get("/v1/admin_{secret}/{type}/{name}/schedule/{offset}/verify")]
+    async fn verify_schedule(
+        &self,
+        #[path] secret: &str,
+        #[path] r#type: &ScheduleType,
+        #[path] name: &str,
+        #[path] offset: i32,
+    ) -> ScheduleVerification;
+
+    #[get("/v1/admin_{secret}/latency")]
+    async fn latency(&self, #[path] secret: &str) -> ScheduleFetchLatency;
 
     #[get("/v1/search")]
     #[map_response_with(SearchResponse::items)]
@@ -19,6 +60,14 @@ pub trait MpeixApi {
         #[query("q")] query: &str,
         #[query] r#type: Option<ScheduleType>,
     ) -> Vec<ScheduleSearchResult>;
+
+    #[get("/v1/trending")]
+    async fn trending(
+        &self,
+        #[query] window: &str,
+        #[query] r#type: Option<ScheduleType>,
+        #[query] limit: usize,
+    ) -> TrendingSchedules;
 }
 
 #[derive(Deserialize)]
@@ -31,3 +80,12 @@ impl SearchResponse {
         self.items
     }
 }
+
+/// Source of the schedules: the `app_schedule` microservice
+/// or canned fixtures for offline development (`MOCK_UPSTREAM=true`).
+/// Check [crate::di] module for details.
+#[derive(Clone)]
+pub enum MpeixUpstream {
+    Remote(MpeixApi),
+    Mock(MockMpeixApi),
+}
@@ -0,0 +1,13 @@
+use async_trait::async_trait;
+
+use crate::peer::repository::PlatformId;
+
+pub mod repository;
+
+/// Platform-specific check of the admin rights of the group chat member,
+/// which is implemented with the api of the platform.
+#[async_trait]
+pub trait ChatAdminChecker: Send + Sync {
+    async fn is_chat_admin(&self, platform_id: &PlatformId, member_id: i64)
+        -> anyhow::Result<bool>;
+}
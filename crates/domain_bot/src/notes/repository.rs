@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common_database::Database;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::models::ChatNote;
+
+/// Notes are kept for a while after their date, just in case
+const KEEP_PAST_NOTES_DAYS: u32 = 30;
+
+/// Repository for accessing table `peer_note` of the mpeix database
+#[async_trait]
+pub trait NotesRepository: Send + Sync {
+    async fn init_notes_tables(&self) -> anyhow::Result<()>;
+
+    /// Get notes of the peer from `from` to `to` dates inclusive, sorted by date
+    async fn get_notes(
+        &self,
+        peer_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<ChatNote>>;
+
+    async fn add_note(&self, peer_id: i64, note: &ChatNote) -> anyhow::Result<()>;
+
+    /// Remove all notes of the peer for the date, returns the number of removed notes
+    async fn remove_notes(&self, peer_id: i64, date: NaiveDate) -> anyhow::Result<u64>;
+}
+
+/// [NotesRepository] backed by the mpeix database
+pub struct PgNotesRepository {
+    database: Arc<Database>,
+}
+
+impl PgNotesRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl NotesRepository for PgNotesRepository {
+    async fn init_notes_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_note.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'peer_note' creation")?;
+        info!("Table 'peer_note' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn get_notes(
+        &self,
+        peer_id: i64,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<ChatNote>> {
+        let client = self.database.read().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/select_peer_notes.pgsql"),
+            peer_id = peer_id,
+            from = from,
+            to = to,
+        );
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error selecting peer notes from db")?
+            .iter()
+            .map(map_from_db_model)
+            .collect()
+    }
+
+    async fn add_note(&self, peer_id: i64, note: &ChatNote) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/delete_old_peer_notes.pgsql"),
+            keep_days = KEEP_PAST_NOTES_DAYS,
+        );
+        client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error deleting old peer notes from db")?;
+        let stmt = format!(
+            include_str!("../../sql/insert_peer_note.pgsql"),
+            peer_id = peer_id,
+            date = note.date,
+        );
+        // the text is passed as a parameter, because it is written by users
+        client
+            .execute(&stmt, &[&note.text])
+            .await
+            .with_context(|| "Error inserting peer note into db")?;
+        Ok(())
+    }
+
+    async fn remove_notes(&self, peer_id: i64, date: NaiveDate) -> anyhow::Result<u64> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/delete_peer_notes.pgsql"),
+            peer_id = peer_id,
+            date = date,
+        );
+        client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error deleting peer notes from db")
+    }
+}
+
+fn map_from_db_model(row: &Row) -> anyhow::Result<ChatNote> {
+    Ok(ChatNote {
+        date: NaiveDate::parse_from_str(row.try_get("date")?, "%Y-%m-%d")?,
+        text: row.try_get("text")?,
+    })
+}
@@ -1,36 +1,210 @@
-use std::sync::Arc;
+use std::{fmt::Display, sync::Arc};
 
 use anyhow::{anyhow, Context};
-use deadpool_postgres::Pool;
-use domain_schedule_models::ScheduleType;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use common_database::Database;
+use domain_buildings::MapProvider;
+use domain_schedule_models::{DaysRange, ScheduleType};
 use log::info;
+use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
 
-use crate::models::Peer;
+use crate::{
+    models::{Locale, Peer},
+    theme::ThemeKind,
+};
 
 /// Repository for accessing tables `peer` and `peer_by_platform` of the mpeix database
-pub struct PeerRepository {
-    db_pool: Arc<Pool>,
+#[async_trait]
+pub trait PeerRepository: Send + Sync {
+    async fn init_peer_tables(&self) -> anyhow::Result<()>;
+
+    async fn get_peer_by_platform_id(&self, platform_id: PlatformId) -> anyhow::Result<Peer>;
+
+    async fn save_peer(&self, peer: Peer) -> anyhow::Result<()>;
+
+    /// Mark the peer deleted, so it is excluded from all features until it is restored
+    /// or purged with [PeerRepository::delete_purged_peers]. Returns the time of the deletion.
+    async fn mark_peer_deleted(&self, peer_id: i64) -> anyhow::Result<NaiveDateTime>;
+
+    /// Undo [PeerRepository::mark_peer_deleted]
+    async fn restore_peer(&self, peer_id: i64) -> anyhow::Result<()>;
+
+    /// Permanently delete the peers marked deleted more than `undo_days` ago, with all their data,
+    /// the reply audit entries, the queued replies and the schedules they linked as the members
+    /// of the group chats. Returns number of deleted peers.
+    async fn delete_purged_peers(&self, undo_days: u32) -> anyhow::Result<u64>;
+
+    /// Get all peers which have already selected some schedule, except the deleted ones.
+    async fn get_peers_with_selected_schedule(&self) -> anyhow::Result<Vec<Peer>>;
+
+    /// Get peers of the platform which enabled the push and selected some schedule.
+    /// `platform_id` is the constructor of the platform id, e.g. [PlatformId::telegram].
+    async fn get_peers_with_push(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        opt_in: PushOptIn,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>>;
+
+    /// Get at most `limit` peers of the platform, which sent nothing for `inactive_weeks`
+    /// and were never contacted by the re-engagement campaign, the most recently active first.
+    /// Peers in privacy mode and peers without selected schedule are skipped.
+    /// `platform_id` is the constructor of the platform id, e.g. [PlatformId::telegram].
+    async fn get_inactive_peers(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        inactive_weeks: u32,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>>;
+
+    /// Switch all peers with selected schedule `old_schedule` to the `new_schedule`.
+    /// Returns number of updated peers.
+    async fn update_peers_selected_schedule(
+        &self,
+        old_schedule: &str,
+        new_schedule: &str,
+        schedule_type: &ScheduleType,
+    ) -> anyhow::Result<u64>;
 }
 
-#[derive(Debug, Clone)]
+/// [PeerRepository] backed by the mpeix database
+pub struct PgPeerRepository {
+    database: Arc<Database>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum PlatformId {
-    Telegram(i64),
-    Vk(i64),
+    Telegram(TelegramChatId),
+    Vk(VkPeerId),
+}
+
+/// Id of the Telegram chat: the private chat with the user or the group chat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TelegramChatId(pub i64);
+
+/// Id of the VK peer: the user or the conversation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VkPeerId(pub i64);
+
+impl TelegramChatId {
+    /// Ids of the groups are negative, ids of the users are positive
+    pub fn is_group_chat(&self) -> bool {
+        self.0 < 0
+    }
+}
+
+impl VkPeerId {
+    /// Peer ids of the conversations start from 2000000000
+    pub fn is_group_chat(&self) -> bool {
+        self.0 >= 2_000_000_000
+    }
+}
+
+impl Display for TelegramChatId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for VkPeerId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl PlatformId {
+    /// Platform id from the raw id stored in the database, see [PlatformId::split]
+    pub const fn telegram(id: i64) -> Self {
+        PlatformId::Telegram(TelegramChatId(id))
+    }
+
+    /// Platform id from the raw id stored in the database, see [PlatformId::split]
+    pub const fn vk(id: i64) -> Self {
+        PlatformId::Vk(VkPeerId(id))
+    }
+
+    /// Platform name and raw peer id on this platform, as they are stored in the database
+    pub fn split(&self) -> (&'static str, i64) {
+        match self {
+            PlatformId::Telegram(id) => ("telegram", id.0),
+            PlatformId::Vk(id) => ("vk", id.0),
+        }
+    }
+
+    /// Whether the peer is a group chat, not a private chat with the user
+    pub fn is_group_chat(&self) -> bool {
+        match self {
+            PlatformId::Telegram(id) => id.is_group_chat(),
+            PlatformId::Vk(id) => id.is_group_chat(),
+        }
+    }
+
+    /// Whether the bot of the platform can send files, e.g. `.ics` files of the weeks
+    pub fn supports_documents(&self) -> bool {
+        match self {
+            PlatformId::Telegram(_) => true,
+            // uploading documents to VK requires the separate upload server flow
+            PlatformId::Vk(_) => false,
+        }
+    }
 }
 
-impl PeerRepository {
-    pub fn new(db_pool: Arc<Pool>) -> Self {
-        Self { db_pool }
+/// Pushes the peers opt in to, each one is enabled by its own column of the table `peer`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushOptIn {
+    EveningSummary,
+    WeeklyChangelog,
+    WeekAhead,
+    WeeklyDigest,
+    ScheduleChanges,
+}
+
+impl PushOptIn {
+    /// Condition of the peers with the push enabled, the same as [PushOptIn::is_enabled]
+    fn condition(&self) -> &'static str {
+        match self {
+            PushOptIn::EveningSummary => "peer.commute_minutes >= 0",
+            PushOptIn::WeeklyChangelog => "peer.changelog_enabled",
+            PushOptIn::WeekAhead => "peer.week_ahead_enabled",
+            PushOptIn::WeeklyDigest => "peer.digest_enabled",
+            PushOptIn::ScheduleChanges => "peer.subscribed",
+        }
+    }
+
+    pub fn is_enabled(&self, peer: &Peer) -> bool {
+        match self {
+            PushOptIn::EveningSummary => peer.commute_minutes.is_some(),
+            PushOptIn::WeeklyChangelog => peer.changelog_enabled,
+            PushOptIn::WeekAhead => peer.week_ahead_enabled,
+            PushOptIn::WeeklyDigest => peer.digest_enabled,
+            PushOptIn::ScheduleChanges => peer.subscribed,
+        }
+    }
+}
+
+impl PgPeerRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
     }
+}
 
-    pub async fn init_peer_tables(&self) -> anyhow::Result<()> {
-        let client = self.db_pool.get().await?;
+#[async_trait]
+impl PeerRepository for PgPeerRepository {
+    async fn init_peer_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
         let stmt = include_str!("../../sql/create_peer.pgsql");
         client
             .query(stmt, &[])
             .await
             .with_context(|| "Error during tables 'peer' creation")?;
+        let stmt = include_str!("../../sql/migrate_peer.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'peer' migration")?;
         let stmt = include_str!("../../sql/create_peer_by_platform.pgsql");
         client
             .query(stmt, &[])
@@ -40,12 +214,10 @@ impl PeerRepository {
         Ok(())
     }
 
-    pub async fn get_peer_by_platform_id(&self, platform_id: PlatformId) -> anyhow::Result<Peer> {
-        let client = self.db_pool.get().await?;
-        let (platform, id) = match platform_id {
-            PlatformId::Telegram(id) => ("telegram", id),
-            PlatformId::Vk(id) => ("vk", id),
-        };
+    async fn get_peer_by_platform_id(&self, platform_id: PlatformId) -> anyhow::Result<Peer> {
+        // peer is inserted if it does not exist yet, so we cannot use read replica here
+        let client = self.database.write().get().await?;
+        let (platform, id) = platform_id.split();
         let stmt = format!(
             include_str!("../../sql/select_or_insert_peer.pgsql"),
             platform = platform,
@@ -60,21 +232,157 @@ impl PeerRepository {
             .ok_or_else(|| anyhow!("Error mapping peer from db"))
     }
 
-    pub async fn save_peer(&self, peer: Peer) -> anyhow::Result<()> {
-        let client = self.db_pool.get().await?;
+    async fn save_peer(&self, peer: Peer) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
         let stmt = format!(
             include_str!("../../sql/update_peer.pgsql"),
             id = peer.id,
-            selected_schedule = peer.selected_schedule,
             selected_schedule_type = peer.selected_schedule_type,
             selecting_schedule = peer.selecting_schedule,
+            map_provider = peer.map_provider.as_ref().map_or("", |it| it.as_ref()),
+            achievements_enabled = peer.achievements_enabled,
+            days_range = peer.days_range,
+            locale = peer.locale.as_ref().map_or("", |it| it.as_ref()),
+            commute_minutes = peer.commute_minutes.map_or(-1, i32::from),
+            privacy_mode = peer.privacy_mode,
+            changelog_enabled = peer.changelog_enabled,
+            week_ahead_enabled = peer.week_ahead_enabled,
+            digest_enabled = peer.digest_enabled,
+            ics_enabled = peer.ics_enabled,
+            newcomer_mode = peer.newcomer_mode,
+            theme = peer.theme.as_ref().map_or("", |it| it.as_ref()),
+            subscribed = peer.subscribed,
         );
+        // schedule names come from MPEI, so they are passed as parameters
         client
-            .query(&stmt, &[])
+            .query(&stmt, &[&peer.selected_schedule])
             .await
             .with_context(|| "Error updating peer in db")?;
         Ok(())
     }
+
+    async fn mark_peer_deleted(&self, peer_id: i64) -> anyhow::Result<NaiveDateTime> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/update_peer_deleted_at.pgsql"),
+            id = peer_id,
+            deleted_at = "NOW()",
+        );
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error marking peer deleted in db")?
+            .pop()
+            .and_then(|row| row.try_get("deleted_at").ok())
+            .ok_or_else(|| anyhow!("Error mapping peer deletion time from db"))
+    }
+
+    async fn restore_peer(&self, peer_id: i64) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/update_peer_deleted_at.pgsql"),
+            id = peer_id,
+            deleted_at = "NULL",
+        );
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error restoring peer in db")?;
+        Ok(())
+    }
+
+    async fn delete_purged_peers(&self, undo_days: u32) -> anyhow::Result<u64> {
+        let client = self.database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/delete_purged_peers.pgsql"),
+            undo_days = undo_days
+        );
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error deleting purged peers from db")?
+            .pop()
+            .and_then(|row| row.try_get::<_, i64>("purged").ok())
+            .map(|it| it as u64)
+            .ok_or_else(|| anyhow!("Error mapping number of purged peers from db"))
+    }
+
+    async fn get_peers_with_selected_schedule(&self) -> anyhow::Result<Vec<Peer>> {
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_peers_with_selected_schedule.pgsql");
+        Ok(client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error selecting peers from db")?
+            .into_iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+
+    async fn get_peers_with_push(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        opt_in: PushOptIn,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>> {
+        let client = self.database.read().get().await?;
+        let (platform, _) = platform_id(0).split();
+        let stmt = format!(
+            include_str!("../../sql/select_peers_with_push.pgsql"),
+            platform = platform,
+            opt_in = opt_in.condition()
+        );
+        Ok(client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| format!("Error selecting peers with {opt_in:?} from db"))?
+            .into_iter()
+            .filter_map(|row| {
+                let id = row.try_get::<_, i64>("platform_id").ok()?;
+                Some((platform_id(id), map_from_db_model(row)?))
+            })
+            .collect())
+    }
+
+    async fn get_inactive_peers(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+        inactive_weeks: u32,
+        limit: usize,
+    ) -> anyhow::Result<Vec<(PlatformId, Peer)>> {
+        let client = self.database.read().get().await?;
+        let (platform, _) = platform_id(0).split();
+        let stmt = format!(
+            include_str!("../../sql/select_inactive_peers.pgsql"),
+            platform = platform
+        );
+        Ok(client
+            .query(&stmt, &[&(inactive_weeks as i32), &(limit as i64)])
+            .await
+            .with_context(|| "Error selecting inactive peers from db")?
+            .into_iter()
+            .filter_map(|row| {
+                let id = row.try_get::<_, i64>("platform_id").ok()?;
+                Some((platform_id(id), map_from_db_model(row)?))
+            })
+            .collect())
+    }
+
+    async fn update_peers_selected_schedule(
+        &self,
+        old_schedule: &str,
+        new_schedule: &str,
+        schedule_type: &ScheduleType,
+    ) -> anyhow::Result<u64> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/update_peers_selected_schedule.pgsql");
+        client
+            .execute(
+                stmt,
+                &[&old_schedule, &new_schedule, &schedule_type.to_string()],
+            )
+            .await
+            .with_context(|| "Error updating peers in db")
+    }
 }
 
 fn map_from_db_model(row: Row) -> Option<Peer> {
@@ -86,5 +394,35 @@ fn map_from_db_model(row: Row) -> Option<Peer> {
             .ok()
             .map(|v| v.parse::<ScheduleType>().unwrap_or(ScheduleType::Group))?,
         selecting_schedule: row.try_get("selecting_schedule").ok()?,
+        map_provider: row
+            .try_get::<_, String>("map_provider")
+            .ok()
+            .and_then(|v| v.parse::<MapProvider>().ok()),
+        achievements_enabled: row.try_get("achievements_enabled").ok()?,
+        days_range: row
+            .try_get::<_, String>("days_range")
+            .ok()
+            .and_then(|v| v.parse::<DaysRange>().ok())
+            .unwrap_or_default(),
+        locale: row
+            .try_get::<_, String>("locale")
+            .ok()
+            .and_then(|v| v.parse::<Locale>().ok()),
+        commute_minutes: row
+            .try_get::<_, i32>("commute_minutes")
+            .ok()
+            .and_then(|v| u16::try_from(v).ok()),
+        privacy_mode: row.try_get("privacy_mode").ok()?,
+        changelog_enabled: row.try_get("changelog_enabled").ok()?,
+        week_ahead_enabled: row.try_get("week_ahead_enabled").ok()?,
+        digest_enabled: row.try_get("digest_enabled").ok()?,
+        ics_enabled: row.try_get("ics_enabled").ok()?,
+        newcomer_mode: row.try_get("newcomer_mode").ok()?,
+        theme: row
+            .try_get::<_, String>("theme")
+            .ok()
+            .and_then(|v| v.parse::<ThemeKind>().ok()),
+        subscribed: row.try_get("subscribed").ok()?,
+        deleted_at: row.try_get("deleted_at").ok()?,
     })
 }
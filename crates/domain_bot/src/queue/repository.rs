@@ -0,0 +1,209 @@
+use std::{
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Context;
+use chrono::{Local, NaiveDateTime};
+use common_database::Database;
+use common_rust::env;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::{models::PendingReply, peer::repository::PlatformId};
+
+/// Repository for accessing the queue of the replies waiting for the next delivery attempt.
+///
+/// The queue is kept in memory, unless `BOT_REPLY_QUEUE_PERSISTENT` is enabled:
+/// then it is stored in table `reply_queue` of the mpeix database and survives restarts.
+pub struct ReplyQueueRepository {
+    storage: ReplyQueueStorage,
+}
+
+enum ReplyQueueStorage {
+    Database(Arc<Database>),
+    /// Pending replies with the time of the next attempt
+    InMemory {
+        last_id: AtomicI64,
+        replies: Mutex<Vec<(PendingReply, NaiveDateTime)>>,
+    },
+}
+
+impl ReplyQueueRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        if env::get_parsed_or("BOT_REPLY_QUEUE_PERSISTENT", false) {
+            Self {
+                storage: ReplyQueueStorage::Database(database),
+            }
+        } else {
+            Self::in_memory()
+        }
+    }
+
+    pub(crate) fn in_memory() -> Self {
+        Self {
+            storage: ReplyQueueStorage::InMemory {
+                last_id: AtomicI64::new(0),
+                replies: Default::default(),
+            },
+        }
+    }
+
+    pub async fn init_reply_queue_tables(&self) -> anyhow::Result<()> {
+        let ReplyQueueStorage::Database(database) = &self.storage else {
+            return Ok(());
+        };
+        let client = database.write().get().await?;
+        let stmt = include_str!("../../sql/create_reply_queue.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'reply_queue' creation")?;
+        info!("Table 'reply_queue' initialization passed successfully");
+        Ok(())
+    }
+
+    /// Queue the reply for the next attempt after `delay`
+    pub async fn push(
+        &self,
+        platform_id: &PlatformId,
+        text: &str,
+        keyboard: Option<&str>,
+        attempts: i32,
+        delay: Duration,
+    ) -> anyhow::Result<()> {
+        let database = match &self.storage {
+            ReplyQueueStorage::Database(database) => database,
+            ReplyQueueStorage::InMemory { last_id, replies } => {
+                let reply = PendingReply {
+                    id: last_id.fetch_add(1, Ordering::SeqCst) + 1,
+                    platform_id: platform_id.clone(),
+                    text: text.to_owned(),
+                    keyboard: keyboard.map(str::to_owned),
+                    attempts,
+                };
+                replies
+                    .lock()
+                    .unwrap()
+                    .push((reply, next_attempt_at(delay)));
+                return Ok(());
+            }
+        };
+        let client = database.write().get().await?;
+        let (platform, platform_peer_id) = platform_id.split();
+        let stmt = format!(
+            include_str!("../../sql/insert_reply_queue.pgsql"),
+            delay_ms = delay.as_millis()
+        );
+        // reply text is arbitrary, so we pass values as statement parameters
+        client
+            .execute(
+                &stmt,
+                &[&platform, &platform_peer_id, &text, &keyboard, &attempts],
+            )
+            .await
+            .with_context(|| "Error inserting reply into queue")?;
+        Ok(())
+    }
+
+    /// Get the replies of the platform, which are ready for the next attempt.
+    /// `platform_id` is the constructor of the platform id, e.g. [PlatformId::telegram].
+    pub async fn get_due(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<PendingReply>> {
+        let (platform, _) = platform_id(0).split();
+        let database = match &self.storage {
+            ReplyQueueStorage::Database(database) => database,
+            ReplyQueueStorage::InMemory { replies, .. } => {
+                let now = Local::now().naive_local();
+                return Ok(replies
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|(reply, at)| reply.platform_id.split().0 == platform && *at <= now)
+                    .map(|(reply, _)| reply.clone())
+                    .collect());
+            }
+        };
+        let client = database.read().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/select_due_reply_queue.pgsql"),
+            platform = platform
+        );
+        Ok(client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error selecting due replies from queue")?
+            .into_iter()
+            .filter_map(|row| map_from_db_model(row, platform_id))
+            .collect())
+    }
+
+    /// Schedule the next attempt of the reply after `delay`
+    pub async fn reschedule(&self, id: i64, attempts: i32, delay: Duration) -> anyhow::Result<()> {
+        let database = match &self.storage {
+            ReplyQueueStorage::Database(database) => database,
+            ReplyQueueStorage::InMemory { replies, .. } => {
+                if let Some((reply, at)) = replies
+                    .lock()
+                    .unwrap()
+                    .iter_mut()
+                    .find(|(reply, _)| reply.id == id)
+                {
+                    reply.attempts = attempts;
+                    *at = next_attempt_at(delay);
+                }
+                return Ok(());
+            }
+        };
+        let client = database.write().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/update_reply_queue.pgsql"),
+            id = id,
+            attempts = attempts,
+            delay_ms = delay.as_millis(),
+        );
+        client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error updating reply in queue")?;
+        Ok(())
+    }
+
+    /// Remove the reply from the queue: delivered or dead
+    pub async fn remove(&self, id: i64) -> anyhow::Result<()> {
+        let database = match &self.storage {
+            ReplyQueueStorage::Database(database) => database,
+            ReplyQueueStorage::InMemory { replies, .. } => {
+                replies.lock().unwrap().retain(|(reply, _)| reply.id != id);
+                return Ok(());
+            }
+        };
+        let client = database.write().get().await?;
+        let stmt = format!(include_str!("../../sql/delete_reply_queue.pgsql"), id = id);
+        client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error deleting reply from queue")?;
+        Ok(())
+    }
+}
+
+fn next_attempt_at(delay: Duration) -> NaiveDateTime {
+    Local::now().naive_local()
+        + chrono::Duration::from_std(delay).unwrap_or(chrono::Duration::zero())
+}
+
+fn map_from_db_model(row: Row, platform_id: fn(i64) -> PlatformId) -> Option<PendingReply> {
+    Some(PendingReply {
+        id: row.try_get("id").ok()?,
+        platform_id: platform_id(row.try_get("platform_peer_id").ok()?),
+        text: row.try_get("text").ok()?,
+        keyboard: row.try_get("keyboard").ok()?,
+        attempts: row.try_get("attempts").ok()?,
+    })
+}
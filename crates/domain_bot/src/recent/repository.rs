@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use common_database::Database;
+use common_sql::Page;
+use domain_schedule_models::ScheduleType;
+use log::info;
+use tokio_postgres::Row;
+
+use crate::models::RecentSchedule;
+
+/// How many recently selected schedules are remembered for each peer
+pub(crate) const MAX_RECENT_SCHEDULES: usize = 5;
+
+/// Repository for accessing table `peer_recent_schedule` of the mpeix database
+#[async_trait]
+pub trait RecentSchedulesRepository: Send + Sync {
+    async fn init_recent_schedules_tables(&self) -> anyhow::Result<()>;
+
+    /// Get recently selected schedules of the peer, the most recent first.
+    async fn get_recent_schedules(&self, peer_id: i64) -> anyhow::Result<Vec<RecentSchedule>>;
+
+    /// Remember the schedule selection and forget the oldest selections over the limit.
+    async fn add_recent_schedule(
+        &self,
+        peer_id: i64,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()>;
+}
+
+/// [RecentSchedulesRepository] backed by the mpeix database
+pub struct PgRecentSchedulesRepository {
+    database: Arc<Database>,
+}
+
+impl PgRecentSchedulesRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl RecentSchedulesRepository for PgRecentSchedulesRepository {
+    async fn init_recent_schedules_tables(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_peer_recent_schedule.pgsql");
+        client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error during table 'peer_recent_schedule' creation")?;
+        info!("Table 'peer_recent_schedule' initialization passed successfully");
+        Ok(())
+    }
+
+    async fn get_recent_schedules(&self, peer_id: i64) -> anyhow::Result<Vec<RecentSchedule>> {
+        let client = self.database.read().get().await?;
+        let stmt = format!(
+            include_str!("../../sql/select_peer_recent_schedules.pgsql"),
+            page = Page::first(MAX_RECENT_SCHEDULES)
+        );
+        Ok(client
+            .query(&stmt, &[&peer_id])
+            .await
+            .with_context(|| "Error selecting peer recent schedules from db")?
+            .into_iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+
+    async fn add_recent_schedule(
+        &self,
+        peer_id: i64,
+        schedule: &RecentSchedule,
+    ) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        // schedule names come from the search results, so we pass them as statement parameters
+        let stmt = include_str!("../../sql/upsert_peer_recent_schedule.pgsql");
+        client
+            .execute(
+                stmt,
+                &[&peer_id, &schedule.name, &schedule.r#type.to_string()],
+            )
+            .await
+            .with_context(|| "Error inserting peer recent schedule to db")?;
+        let stmt = format!(
+            include_str!("../../sql/delete_old_peer_recent_schedules.pgsql"),
+            page = Page::first(MAX_RECENT_SCHEDULES)
+        );
+        client
+            .execute(&stmt, &[&peer_id])
+            .await
+            .with_context(|| "Error deleting old peer recent schedules from db")?;
+        Ok(())
+    }
+}
+
+fn map_from_db_model(row: Row) -> Option<RecentSchedule> {
+    Some(RecentSchedule {
+        name: row.try_get("schedule_name").ok()?,
+        r#type: row
+            .try_get::<_, String>("schedule_type")
+            .ok()?
+            .parse::<ScheduleType>()
+            .ok()?,
+    })
+}
@@ -1,37 +1,213 @@
-use chrono::{Datelike, Weekday};
-use domain_schedule_models::{Classes, Day, ScheduleType, Week};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use common_rust::env;
+use domain_buildings::{room_floor, Buildings, MapProvider};
+use domain_schedule_models::{
+    ics::week_to_ics, Classes, ClassesChange, ClassesChangeKind, ClassesStatus, Day, DaysRange,
+    PeriodType, ScheduleDiff, ScheduleType, ScheduleVerification, SessionSchedule, Week,
+};
+use lazy_static::lazy_static;
 
-use crate::models::{Reply, TimePrediction, UpcomingEventsPrediction};
+use crate::{
+    date_format::{capitalize, DateFormatter},
+    models::{
+        AchievementStats, Badge, BlockPattern, ChangelogDigest, ChatNote, Document, HealthReport,
+        Locale, MonthDayKind, MonthOverview, NoteRejection, RenderedWeekKey, Reply, ReplyContext,
+        RoomChangeWarning, TimePrediction, UpcomingEventsPrediction, WeekAheadDigest,
+    },
+    peer::repository::PlatformId,
+    theme::{Theme, ThemeKind},
+    usecases::{MAX_NOTES_PER_DAY, NOTE_MAX_LENGTH},
+};
 use std::fmt::Write;
 
+lazy_static! {
+    static ref BUILDINGS: Buildings = Buildings::default();
+}
+
+/// Names of the classes are shortened to this number of chars in the week summary
+const SUMMARY_NAME_MAX_CHARS: usize = 32;
+
+/// Pick the template from the localization catalog of the `locale`:
+/// `res/` for Russian and `res/en/` for English. Arguments are passed to [format!].
+macro_rules! localized {
+    ($locale:expr, $name:literal) => {
+        match $locale {
+            Locale::Ru => include_str!(concat!("../res/", $name)).to_owned(),
+            Locale::En => include_str!(concat!("../res/en/", $name)).to_owned(),
+        }
+    };
+    ($locale:expr, $name:literal, $($args:tt)+) => {
+        match $locale {
+            Locale::Ru => format!(include_str!(concat!("../res/", $name)), $($args)+),
+            Locale::En => format!(include_str!(concat!("../res/en/", $name)), $($args)+),
+        }
+    };
+}
+
 pub enum RenderTargetPlatform {
     Vk,
     Telegram,
 }
 
+/// Render the file attached to the reply, if there is one.
+///
+/// Should be called only on the platforms which support files,
+/// the replies like [Reply::DataExport] make no sense without it.
+pub fn render_document(reply: &Reply, context: &ReplyContext) -> Option<Document> {
+    match reply {
+        Reply::Week {
+            schedule_name,
+            week,
+            ..
+        } if context.ics_enabled => {
+            if week.days.iter().all(|day| day.classes.is_empty()) {
+                return None;
+            }
+            Some(Document {
+                file_name: format!("{schedule_name} {}.ics", week.first_day_of_week),
+                content: week_to_ics(schedule_name, week, Utc::now().naive_utc()).into_bytes(),
+            })
+        }
+        Reply::DataExport(export) => Some(Document {
+            file_name: format!("mpeix-data-{}.json", export.exported_at.date()),
+            content: serde_json::to_vec_pretty(export).expect("Data export is always serializable"),
+        }),
+        _ => None,
+    }
+}
+
 /// Turn the [Reply] response model into the text of the message, for further sending to social networks.
-pub fn render_message(reply: &Reply, platform: RenderTargetPlatform) -> String {
+pub fn render_message(
+    reply: &Reply,
+    platform: RenderTargetPlatform,
+    locale: Locale,
+    theme: ThemeKind,
+) -> String {
+    let theme_kind = theme;
+    let theme = Theme::of(theme);
     match reply {
-        Reply::StartGreetings => include_str!("../res/msg_start_greetings.txt").to_owned(),
+        Reply::StartGreetings { suggestions } => {
+            let mut buf = localized!(locale, "msg_start_greetings.txt");
+            if !suggestions.is_empty() {
+                buf.push_str("\n\n");
+                buf.push_str(&localized!(locale, "msg_start_greetings_suggestions.txt"));
+            }
+            buf
+        }
         Reply::AlreadyStarted { schedule_name: _ } => {
-            include_str!("../res/msg_already_started.txt").to_owned()
+            localized!(locale, "msg_already_started.txt")
         }
         Reply::Week {
+            schedule_name: _,
             week_offset,
             week,
             schedule_type,
+            map_provider,
+            notes,
+            full: _,
+            rendered,
         } => {
-            let mut buf = String::with_capacity(4096);
-            render_week(*week_offset, week, schedule_type, &mut buf);
-            buf
+            let summarized = is_week_summarized(reply);
+            let render = || {
+                if summarized {
+                    let mut buf = String::with_capacity(2048);
+                    render_week_summary(*week_offset, week, notes, locale, theme, &mut buf);
+                    return buf;
+                }
+                let mut buf = String::with_capacity(4096);
+                render_week(
+                    *week_offset,
+                    week,
+                    schedule_type,
+                    *map_provider,
+                    notes,
+                    locale,
+                    theme,
+                    &mut buf,
+                );
+                buf
+            };
+            match rendered {
+                Some(rendered) => rendered.get_or_render(
+                    RenderedWeekKey {
+                        locale,
+                        theme: theme_kind,
+                        summarized,
+                        map_provider: *map_provider,
+                        days: week.days.iter().map(|day| day.date).collect(),
+                        notes: notes.to_owned(),
+                    },
+                    render,
+                ),
+                None => render(),
+            }
         }
         Reply::Day {
             day_offset,
             day,
             schedule_type,
+            map_provider,
+            period_type,
+            notes,
+            newcomer_mode,
+            room_changes,
         } => {
             let mut buf = String::with_capacity(2048);
-            render_day(*day_offset, day, schedule_type, &mut buf, false);
+            render_room_changes(room_changes, locale, theme, &mut buf);
+            render_period_warning(period_type, locale, theme, &mut buf);
+            render_day(
+                *day_offset,
+                day,
+                schedule_type,
+                *map_provider,
+                notes,
+                locale,
+                theme,
+                &mut buf,
+                false,
+            );
+            if *newcomer_mode {
+                render_first_class_directions(day, theme, &mut buf);
+            }
+            buf
+        }
+        Reply::DayRange {
+            from,
+            to,
+            days,
+            schedule_type,
+            map_provider,
+            notes,
+        } => {
+            let mut buf = String::with_capacity(4096);
+            render_day_range(
+                (*from, *to),
+                days,
+                schedule_type,
+                *map_provider,
+                notes,
+                locale,
+                theme,
+                &mut buf,
+            );
+            buf
+        }
+        Reply::Month(month) => {
+            let mut buf = String::with_capacity(1024);
+            render_month(month, locale, theme, &mut buf);
+            buf
+        }
+        Reply::NextWeekChanges(diff) => {
+            let mut buf = String::with_capacity(1024);
+            render_next_week_changes(diff, locale, theme, &mut buf);
+            buf
+        }
+        Reply::Exams {
+            session,
+            map_provider,
+        } => {
+            let mut buf = String::with_capacity(2048);
+            render_exams(session, *map_provider, locale, theme, &mut buf);
             buf
         }
         Reply::UpcomingEvents {
@@ -39,57 +215,269 @@ pub fn render_message(reply: &Reply, platform: RenderTargetPlatform) -> String {
             schedule_type,
         } => {
             let mut buf = String::with_capacity(2048);
-            render_upcoming_events(prediction, schedule_type, &mut buf);
+            render_upcoming_events(prediction, schedule_type, locale, theme, &mut buf);
             buf
         }
-        Reply::ScheduleChangedSuccessfully(schedule_name) => format!(
-            include_str!("../res/msg_schedule_changed_successfully.txt"),
+        Reply::RemainingToday {
+            count,
+            last_class_end,
+        } => {
+            let mut buf = String::with_capacity(128);
+            render_remaining_today(*count, last_class_end.as_ref(), &mut buf);
+            buf
+        }
+        Reply::ScheduleChangedSuccessfully(schedule_name) => localized!(
+            locale,
+            "msg_schedule_changed_successfully.txt",
             schedule_name = &schedule_name
         ),
         Reply::ScheduleSearchResults {
             schedule_name,
             results: _,
             results_contains_person: _,
-        } => format!(
-            include_str!("../res/msg_schedule_search_results.txt"),
-            schedule_name = &schedule_name
-        ),
+            person_details,
+        } => {
+            let mut buf = localized!(
+                locale,
+                "msg_schedule_search_results.txt",
+                schedule_name = &schedule_name
+            );
+            for line in person_details {
+                write!(buf, "\n{} {line}", theme.list_item).unwrap();
+            }
+            buf
+        }
         Reply::CannotFindSchedule(q) => {
-            format!(
-                include_str!("../res/msg_cannot_find_schedule.txt"),
-                schedule_name = q
-            )
+            localized!(locale, "msg_cannot_find_schedule.txt", schedule_name = q)
+        }
+        Reply::ScheduleRenamed {
+            schedule_name,
+            results,
+        } => {
+            if results.is_empty() {
+                localized!(
+                    locale,
+                    "msg_schedule_renamed_no_candidates.txt",
+                    schedule_name = schedule_name
+                )
+            } else {
+                localized!(
+                    locale,
+                    "msg_schedule_renamed.txt",
+                    schedule_name = schedule_name
+                )
+            }
         }
         Reply::ReadyToChangeSchedule => {
-            include_str!("../res/msg_ready_to_change_schedule.txt").to_owned()
+            localized!(locale, "msg_ready_to_change_schedule.txt")
+        }
+        Reply::RecentSchedules(results) if results.is_empty() => {
+            localized!(locale, "msg_recent_schedules_empty.txt")
+        }
+        Reply::RecentSchedules(_) => localized!(locale, "msg_recent_schedules.txt"),
+        Reply::MapProviderChanged(Some(map_provider)) => localized!(
+            locale,
+            "msg_map_links_enabled.txt",
+            map_provider = match (map_provider, locale) {
+                (MapProvider::Yandex, Locale::Ru) => "Яндекс Карты",
+                (MapProvider::Yandex, Locale::En) => "Yandex Maps",
+                (MapProvider::Google, _) => "Google Maps",
+            }
+        ),
+        Reply::MapProviderChanged(None) => {
+            localized!(locale, "msg_map_links_disabled.txt")
+        }
+        Reply::DaysRangeChanged(days_range) if *days_range == DaysRange::FULL_WEEK => {
+            localized!(locale, "msg_days_range_full_week.txt")
+        }
+        Reply::DaysRangeChanged(days_range) => localized!(
+            locale,
+            "msg_days_range_changed.txt",
+            first = DateFormatter(locale).day_of_week(days_range.first),
+            last = DateFormatter(locale).day_of_week(days_range.last),
+        ),
+        Reply::LocaleChanged(_) => localized!(locale, "msg_locale_changed.txt"),
+        Reply::CommuteChanged(Some(minutes)) => {
+            localized!(locale, "msg_commute_enabled.txt", minutes = minutes)
+        }
+        Reply::CommuteChanged(None) => localized!(locale, "msg_commute_disabled.txt"),
+        Reply::PrivacyModeChanged(true) => localized!(locale, "msg_privacy_enabled.txt"),
+        Reply::PrivacyModeChanged(false) => localized!(locale, "msg_privacy_disabled.txt"),
+        Reply::ChangelogEnabledChanged(true) => localized!(locale, "msg_changelog_enabled.txt"),
+        Reply::ChangelogEnabledChanged(false) => {
+            localized!(locale, "msg_changelog_disabled.txt")
+        }
+        Reply::WeekAheadEnabledChanged(true) => localized!(locale, "msg_week_ahead_enabled.txt"),
+        Reply::WeekAheadEnabledChanged(false) => {
+            localized!(locale, "msg_week_ahead_disabled.txt")
+        }
+        Reply::DigestEnabledChanged(true) => localized!(locale, "msg_digest_enabled.txt"),
+        Reply::DigestEnabledChanged(false) => localized!(locale, "msg_digest_disabled.txt"),
+        Reply::SubscriptionChanged(true) => localized!(locale, "msg_subscribed.txt"),
+        Reply::SubscriptionChanged(false) => localized!(locale, "msg_unsubscribed.txt"),
+        Reply::IcsEnabledChanged(true) => localized!(locale, "msg_ics_enabled.txt"),
+        Reply::IcsEnabledChanged(false) => localized!(locale, "msg_ics_disabled.txt"),
+        Reply::DocumentsNotSupported => localized!(locale, "msg_documents_not_supported.txt"),
+        Reply::DataExport(_) => localized!(locale, "msg_data_export.txt"),
+        Reply::DataDeletionScheduled { purge_date } => localized!(
+            locale,
+            "msg_data_deletion_scheduled.txt",
+            purge_date = DateFormatter(locale).day_and_month(*purge_date),
+        ),
+        Reply::DataDeletionPending { purge_date } => localized!(
+            locale,
+            "msg_data_deletion_pending.txt",
+            purge_date = DateFormatter(locale).day_and_month(*purge_date),
+        ),
+        Reply::DataRestored => localized!(locale, "msg_data_restored.txt"),
+        Reply::NothingToRestore => localized!(locale, "msg_nothing_to_restore.txt"),
+        Reply::Navigation { room, map_provider } => {
+            render_navigation(room, *map_provider, locale, theme)
+        }
+        Reply::NewcomerModeChanged(true) => localized!(locale, "msg_newcomer_mode_enabled.txt"),
+        Reply::NewcomerModeChanged(false) => {
+            localized!(locale, "msg_newcomer_mode_disabled.txt")
+        }
+        Reply::ThemeChanged(_) => localized!(locale, "msg_theme_changed.txt"),
+        Reply::WeeklyChangelog(digest) => {
+            let mut buf = String::with_capacity(256);
+            render_changelog_digest(digest, locale, &mut buf);
+            localized!(
+                locale,
+                "msg_weekly_changelog.txt",
+                name = digest.schedule_name,
+                changes = buf,
+            )
+        }
+        Reply::WeekAhead(digest) => {
+            let mut buf = String::with_capacity(512);
+            render_week_ahead_digest(digest, locale, theme, &mut buf);
+            localized!(
+                locale,
+                "msg_week_ahead.txt",
+                name = digest.schedule_name,
+                days = buf,
+            )
+        }
+        Reply::ReEngagement { schedule_name } => localized!(
+            locale,
+            "msg_reengagement.txt",
+            schedule_name = schedule_name
+        ),
+        Reply::ScheduleChanged {
+            schedule_name,
+            changes,
+        } => {
+            let mut buf = String::with_capacity(256);
+            render_schedule_changes(schedule_name, changes, locale, theme, &mut buf);
+            buf
+        }
+        Reply::EveningSummary {
+            first_class,
+            leave_at,
+        } => localized!(
+            locale,
+            "msg_evening_summary.txt",
+            name = first_class.name,
+            start = first_class.time.start.format("%H:%M"),
+            place = match (first_class.place.is_empty(), locale) {
+                (true, _) => String::new(),
+                (false, Locale::Ru) => format!(" в {}", first_class.place),
+                (false, Locale::En) => format!(" in {}", first_class.place),
+            },
+            leave_at = leave_at.format("%H:%M"),
+        ),
+        Reply::NoteAdded(date) => {
+            localized!(locale, "msg_note_added.txt", date = date.format("%d.%m"))
+        }
+        Reply::NotesRemoved { date, count } => localized!(
+            locale,
+            "msg_notes_removed.txt",
+            date = date.format("%d.%m"),
+            count = count,
+        ),
+        Reply::NoteRejected(NoteRejection::NotChatAdmin) => {
+            localized!(locale, "msg_note_not_chat_admin.txt")
+        }
+        Reply::NoteRejected(NoteRejection::TooLong) => {
+            localized!(locale, "msg_note_too_long.txt", max = NOTE_MAX_LENGTH)
+        }
+        Reply::NoteRejected(NoteRejection::TooManyNotes) => {
+            localized!(locale, "msg_note_too_many.txt", max = MAX_NOTES_PER_DAY)
+        }
+        Reply::NoteRejected(NoteRejection::PastDate) => {
+            localized!(locale, "msg_note_past_date.txt")
+        }
+        Reply::MemberScheduleLinked(schedule_name) => localized!(
+            locale,
+            "msg_member_schedule_linked.txt",
+            schedule_name = schedule_name
+        ),
+        Reply::MemberScheduleUnlinked => localized!(locale, "msg_member_schedule_unlinked.txt"),
+        Reply::MemberScheduleNotLinked => {
+            localized!(locale, "msg_member_schedule_not_linked.txt")
+        }
+        Reply::GroupChatOnly => localized!(locale, "msg_group_chat_only.txt"),
+        Reply::ScheduleVerification(verification) => {
+            let mut buf = String::with_capacity(1024);
+            render_schedule_verification(verification, theme, &mut buf);
+            buf
+        }
+        Reply::Pong(report) => {
+            let mut buf = String::with_capacity(256);
+            render_pong(report, theme, &mut buf);
+            buf
+        }
+        Reply::Blocklist {
+            patterns,
+            temporarily_blocked,
+        } => {
+            let mut buf = String::with_capacity(512);
+            render_blocklist(patterns, temporarily_blocked, &mut buf);
+            buf
+        }
+        Reply::Achievements { stats, badges } => {
+            let mut buf = String::with_capacity(1024);
+            render_achievements(stats, badges, theme, &mut buf);
+            buf
+        }
+        Reply::AchievementsDisabled => {
+            localized!(locale, "msg_achievements_disabled.txt")
+        }
+        Reply::AchievementsEnabledChanged(true) => {
+            localized!(locale, "msg_achievements_enabled.txt")
+        }
+        Reply::AchievementsEnabledChanged(false) => {
+            localized!(locale, "msg_achievements_turned_off.txt")
         }
         Reply::ShowHelp => match platform {
             RenderTargetPlatform::Telegram => {
-                include_str!("../res/msg_show_help_telegram.txt").to_owned()
+                localized!(locale, "msg_show_help_telegram.txt")
             }
-            RenderTargetPlatform::Vk => include_str!("../res/msg_show_help_vk.txt").to_owned(),
+            RenderTargetPlatform::Vk => localized!(locale, "msg_show_help_vk.txt"),
         },
         Reply::UnknownCommand => match platform {
             RenderTargetPlatform::Telegram => {
-                include_str!("../res/msg_unknown_command_telegram.txt").to_owned()
+                localized!(locale, "msg_unknown_command_telegram.txt")
             }
             RenderTargetPlatform::Vk => {
-                include_str!("../res/msg_unknown_command_vk.txt").to_owned()
+                localized!(locale, "msg_unknown_command_vk.txt")
             }
         },
+        Reply::CommandDisabled => localized!(locale, "msg_command_disabled.txt"),
         Reply::UnknownMessageType => match platform {
             RenderTargetPlatform::Telegram => {
-                include_str!("../res/msg_unknown_message_type_telegram.txt").to_owned()
+                localized!(locale, "msg_unknown_message_type_telegram.txt")
             }
             RenderTargetPlatform::Vk => {
-                include_str!("../res/msg_unknown_message_type_vk.txt").to_owned()
+                localized!(locale, "msg_unknown_message_type_vk.txt")
             }
         },
         Reply::InternalError => match platform {
             RenderTargetPlatform::Telegram => {
-                include_str!("../res/msg_internal_error_telegram.txt").to_owned()
+                localized!(locale, "msg_internal_error_telegram.txt")
             }
-            RenderTargetPlatform::Vk => include_str!("../res/msg_internal_error_vk.txt").to_owned(),
+            RenderTargetPlatform::Vk => localized!(locale, "msg_internal_error_vk.txt"),
         },
     }
 }
@@ -97,21 +485,47 @@ pub fn render_message(reply: &Reply, platform: RenderTargetPlatform) -> String {
 fn render_upcoming_events(
     prediction: &UpcomingEventsPrediction,
     schedule_type: &ScheduleType,
+    locale: Locale,
+    theme: &Theme,
     buf: &mut String,
 ) {
     use UpcomingEventsPrediction::*;
+    let dates = DateFormatter(locale);
     match prediction {
-        NoClassesNextWeek => buf.push_str("В ближайшие несколько дней нет пар"),
+        NoClassesNextWeek => buf.push_str(match locale {
+            Locale::Ru => "В ближайшие несколько дней нет пар",
+            Locale::En => "No classes in the next few days",
+        }),
+        ClassesResume { period_type, date } => {
+            let date = dates.day_and_month(*date);
+            match (period_type, locale) {
+                (PeriodType::Session, Locale::Ru) => {
+                    write!(buf, "Сейчас сессия, пары начнутся {date}")
+                }
+                (PeriodType::Vacation, Locale::Ru) => {
+                    write!(buf, "Сейчас каникулы, пары начнутся {date}")
+                }
+                (PeriodType::Study, Locale::Ru) => write!(buf, "Пары начнутся {date}"),
+                (PeriodType::Session, Locale::En) => {
+                    write!(buf, "It's exam session now, classes resume on {date}")
+                }
+                (PeriodType::Vacation, Locale::En) => {
+                    write!(buf, "It's vacation now, classes resume on {date}")
+                }
+                (PeriodType::Study, Locale::En) => write!(buf, "Classes resume on {date}"),
+            }
+            .unwrap();
+        }
         ClassesTodayNotStarted {
             time_prediction,
             future_classes,
         } => {
-            render_time_prediction(time_prediction, buf);
+            render_time_prediction(time_prediction, locale, buf);
             for (i, cls) in future_classes.iter().enumerate() {
                 if i > 0 {
                     buf.push_str("\n\n");
                 }
-                render_classes(cls, schedule_type, buf);
+                render_classes(cls, schedule_type, None, theme, buf);
             }
         }
         ClassesTodayStarted {
@@ -119,14 +533,14 @@ fn render_upcoming_events(
             future_classes,
         } => {
             buf.push_str("Пара уже началась:\n\n");
-            render_classes(in_progress, schedule_type, buf);
+            render_classes(in_progress, schedule_type, None, theme, buf);
             if let Some(classes) = future_classes {
                 buf.push_str("\n\nДалее:\n\n");
                 for (i, cls) in classes.iter().enumerate() {
                     if i > 0 {
                         buf.push_str("\n\n");
                     }
-                    render_classes(cls, schedule_type, buf);
+                    render_classes(cls, schedule_type, None, theme, buf);
                 }
             }
         }
@@ -134,18 +548,297 @@ fn render_upcoming_events(
             time_prediction,
             future_classes,
         } => {
-            render_time_prediction(time_prediction, buf);
+            render_time_prediction(time_prediction, locale, buf);
             for (i, cls) in future_classes.iter().enumerate() {
                 if i > 0 {
                     buf.push_str("\n\n");
                 }
-                render_classes(cls, schedule_type, buf);
+                render_classes(cls, schedule_type, None, theme, buf);
+            }
+        }
+    }
+}
+
+fn render_remaining_today(count: usize, last_class_end: Option<&NaiveTime>, buf: &mut String) {
+    match (count, last_class_end) {
+        (0, _) | (_, None) => buf.push_str(include_str!("../res/msg_no_classes_remaining.txt")),
+        (count, Some(end)) => {
+            if count % 10 == 1 && count % 100 != 11 {
+                buf.push_str("Сегодня осталась ");
+            } else {
+                buf.push_str("Сегодня осталось ");
             }
+            render_classes_count(count, buf);
+            write!(buf, ", закончишь в {}", end.format("%H:%M")).unwrap();
         }
     }
 }
 
-fn render_time_prediction(time_prediction: &TimePrediction, buf: &mut String) {
+fn render_schedule_verification(
+    verification: &ScheduleVerification,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    writeln!(
+        buf,
+        "{} {}, неделя с {}",
+        theme.verification,
+        verification.name,
+        verification.week_start.format("%d.%m")
+    )
+    .unwrap();
+    match &verification.cache {
+        Some(cache) => {
+            buf.push_str("Кэш: создан ");
+            render_seconds_short(cache.age_seconds, buf);
+            buf.push_str(" назад, ");
+            if cache.expired {
+                buf.push_str("истёк");
+            } else {
+                buf.push_str("истекает через ");
+                render_seconds_short(cache.ttl_seconds, buf);
+            }
+            writeln!(buf, ", обращений: {}", cache.hits).unwrap();
+        }
+        None => buf.push_str("Кэш: неделя не закэширована\n"),
+    }
+    if verification.cache.is_some() && verification.changes.is_empty() {
+        write!(buf, "{} Кэш совпадает с расписанием МЭИ", theme.ok).unwrap();
+        return;
+    }
+    if verification.changes.is_empty() {
+        buf.push_str("Сравнивать не с чем, следующий запрос расписания возьмёт его из МЭИ");
+        return;
+    }
+    write!(
+        buf,
+        "{} Изменения в МЭИ: {}",
+        theme.attention,
+        verification.changes.len()
+    )
+    .unwrap();
+    for change in &verification.changes {
+        render_classes_change(change, theme, buf);
+    }
+}
+
+/// Compact admin report, e.g. `🏓 mpeix 3.1.1 (4f2a9c1), аптайм 2 ч 5 мин`
+fn render_pong(report: &HealthReport, theme: &Theme, buf: &mut String) {
+    let commit = report.build.commit.get(..7).unwrap_or(report.build.commit);
+    write!(
+        buf,
+        "{} mpeix {} ({commit}), аптайм ",
+        theme.ping, report.build.version
+    )
+    .unwrap();
+    render_seconds_short(report.uptime_seconds as i64, buf);
+    match report.schedule_service_latency_ms {
+        Some(latency) => write!(buf, "\n{} app_schedule: {latency} мс", theme.ok),
+        None => write!(buf, "\n{} app_schedule недоступен", theme.failure),
+    }
+    .unwrap();
+    write!(buf, ", проверено {} с назад", report.checked_seconds_ago).unwrap();
+    match report.cache_hit_percent {
+        Some(percent) => write!(buf, "\nКэш: {percent}% попаданий").unwrap(),
+        None => buf.push_str("\nКэш: нет данных"),
+    }
+}
+
+/// Admin report, the platform ids are shown as patterns, so they can be copied to "/unblock"
+fn render_blocklist(
+    patterns: &[BlockPattern],
+    temporarily_blocked: &[(PlatformId, u64)],
+    buf: &mut String,
+) {
+    match patterns.is_empty() {
+        true => buf.push_str("Черный список пуст"),
+        false => buf.push_str("Черный список:"),
+    }
+    for pattern in patterns {
+        write!(buf, "\n{pattern}").unwrap();
+    }
+    if temporarily_blocked.is_empty() {
+        return;
+    }
+    buf.push_str("\n\nВременно заблокированы:");
+    for (platform_id, minutes) in temporarily_blocked {
+        let (platform, id) = platform_id.split();
+        write!(buf, "\n{platform}:{id}, еще {minutes} мин").unwrap();
+    }
+}
+
+fn render_next_week_changes(diff: &ScheduleDiff, locale: Locale, theme: &Theme, buf: &mut String) {
+    let week_start = DateFormatter(locale).day_and_month(diff.to_week_start);
+    let icon = theme.week_changes;
+    match locale {
+        Locale::Ru => write!(
+            buf,
+            "{icon} Что меняется на следующей неделе (с {week_start})"
+        ),
+        Locale::En => write!(buf, "{icon} What changes next week (from {week_start})"),
+    }
+    .unwrap();
+    if diff.changes.is_empty() {
+        let icon = theme.ok;
+        match locale {
+            Locale::Ru => write!(buf, "\n\n{icon} Пары те же, что и на этой неделе"),
+            Locale::En => write!(buf, "\n\n{icon} The classes are the same as this week"),
+        }
+        .unwrap();
+        return;
+    }
+    buf.push('\n');
+    for change in &diff.changes {
+        render_classes_change(change, theme, buf);
+    }
+}
+
+fn render_exams(
+    session: &SessionSchedule,
+    map_provider: Option<MapProvider>,
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    let icon = theme.exam_day;
+    match locale {
+        Locale::Ru => write!(buf, "{icon} Расписание сессии {}", session.name),
+        Locale::En => write!(buf, "{icon} Exam session schedule of {}", session.name),
+    }
+    .unwrap();
+    if session.days.is_empty() {
+        match locale {
+            Locale::Ru => buf.push_str("\n\nРасписание сессии пока не опубликовано"),
+            Locale::En => buf.push_str("\n\nThe exam session schedule is not published yet"),
+        }
+        return;
+    }
+    for day in &session.days {
+        buf.push_str("\n\n");
+        render_day(
+            0,
+            day,
+            &session.r#type,
+            map_provider,
+            &[],
+            locale,
+            theme,
+            buf,
+            true,
+        );
+    }
+}
+
+fn render_schedule_changes(
+    schedule_name: &str,
+    changes: &[ClassesChange],
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    let icon = theme.notification;
+    match locale {
+        Locale::Ru => write!(buf, "{icon} Расписание {schedule_name} изменилось:"),
+        Locale::En => write!(buf, "{icon} The schedule of {schedule_name} has changed:"),
+    }
+    .unwrap();
+    buf.push('\n');
+    for change in changes {
+        render_classes_change(change, theme, buf);
+    }
+}
+
+/// Line like `✏️ 15.03 09:20 Физика, Б-114 → Физика, К-601`, starting with a line break
+fn render_classes_change(change: &ClassesChange, theme: &Theme, buf: &mut String) {
+    let (emoji, cls) = match change.kind {
+        ClassesChangeKind::Added => (theme.added, change.after.as_ref()),
+        ClassesChangeKind::Removed => (theme.removed, change.before.as_ref()),
+        ClassesChangeKind::Changed => (theme.changed, change.before.as_ref()),
+    };
+    let Some(cls) = cls else {
+        return;
+    };
+    write!(
+        buf,
+        "\n{emoji} {} {} {}",
+        change.date.format("%d.%m"),
+        cls.time.start.format("%H:%M"),
+        render_classes_short(cls)
+    )
+    .unwrap();
+    if let (ClassesChangeKind::Changed, Some(after)) = (change.kind, &change.after) {
+        write!(buf, " → {}", render_classes_short(after)).unwrap();
+    }
+}
+
+fn render_classes_short(cls: &Classes) -> String {
+    if cls.place.is_empty() {
+        cls.name.to_owned()
+    } else {
+        format!("{}, {}", cls.name, cls.place)
+    }
+}
+
+fn render_seconds_short(seconds: i64, buf: &mut String) {
+    let (h, m) = (seconds / 3600, seconds % 3600 / 60);
+    if h > 0 {
+        write!(buf, "{h} ч {m} мин").unwrap();
+    } else {
+        write!(buf, "{m} мин").unwrap();
+    }
+}
+
+fn render_achievements(
+    stats: &AchievementStats,
+    badges: &[Badge],
+    theme: &Theme,
+    buf: &mut String,
+) {
+    write!(buf, "{} Твои достижения\n\n", theme.achievements).unwrap();
+    writeln!(
+        buf,
+        "{} Текущая серия: {} дн.",
+        theme.current_streak, stats.current_streak
+    )
+    .unwrap();
+    writeln!(
+        buf,
+        "{} Лучшая серия: {} дн.",
+        theme.best_streak, stats.best_streak
+    )
+    .unwrap();
+    write!(
+        buf,
+        "{} Всего проверок расписания: {}\n\n",
+        theme.total_checks, stats.total_checks
+    )
+    .unwrap();
+    if badges.is_empty() {
+        buf.push_str("Значков пока нет, но всё впереди!");
+        return;
+    }
+    buf.push_str("Значки:");
+    for badge in badges {
+        let (icon, text) = match badge {
+            Badge::EarlyBird => (
+                theme.early_bird,
+                "Ранняя пташка — расписание проверено до 8:00",
+            ),
+            Badge::WeekStreak => (
+                theme.week_streak,
+                "Неделя без пропусков — расписание проверялось 7 дней подряд",
+            ),
+            Badge::MonthStreak => (
+                theme.month_streak,
+                "Месяц без пропусков — расписание проверялось 30 дней подряд",
+            ),
+            Badge::Centurion => (theme.centurion, "Сотня — расписание проверено 100 раз"),
+        };
+        write!(buf, "\n{icon} {text}").unwrap();
+    }
+}
+
+fn render_time_prediction(time_prediction: &TimePrediction, locale: Locale, buf: &mut String) {
     match time_prediction {
         TimePrediction::WithinOneDay(duration) => {
             buf.push_str("Ближайшая пара начнется ");
@@ -156,81 +849,475 @@ fn render_time_prediction(time_prediction: &TimePrediction, buf: &mut String) {
                 buf.push_str("Ближайшая пара начнется ");
                 render_duration(duration, buf)
             } else {
-                buf.push_str("Ближайшие пары ");
-                buf.push_str(render_day_of_week_gen(date.weekday()));
-                buf.push_str(", ");
-                buf.push_str(&date.day().to_string());
-                buf.push(' ');
-                buf.push_str(render_month(date.month()));
+                let date = DateFormatter(locale).on_full_date(*date);
+                match locale {
+                    Locale::Ru => write!(buf, "Ближайшие пары {date}"),
+                    Locale::En => write!(buf, "The next classes are {date}"),
+                }
+                .unwrap();
             }
         }
     }
     buf.push_str(":\n\n");
 }
 
-fn render_week(_: i8, week: &Week, schedule_type: &ScheduleType, buf: &mut String) {
-    if let n @ 0..=17 = week.week_of_semester {
-        write!(buf, "Расписание на {n} учебную неделю\n\n").unwrap();
-    } else {
-        buf.push_str("Расписание на неделю\n\n")
-    }
+#[allow(clippy::too_many_arguments)]
+fn render_week(
+    _: i8,
+    week: &Week,
+    schedule_type: &ScheduleType,
+    map_provider: Option<MapProvider>,
+    notes: &[ChatNote],
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    render_period_warning(&week.period_type, locale, theme, buf);
+    render_week_title(week, locale, buf);
+    buf.push_str("\n\n");
 
     if week.days.is_empty() {
-        buf.push_str("Нет пар 🤷");
-        return;
+        render_no_classes(locale, theme, buf);
     }
-
     for (i, day) in week.days.iter().enumerate() {
         if i > 0 {
             buf.push_str("\n\n");
         }
-        render_day(0, day, schedule_type, buf, true);
+        let notes = notes
+            .iter()
+            .filter(|it| it.date == day.date)
+            .cloned()
+            .collect::<Vec<_>>();
+        render_day(
+            0,
+            day,
+            schedule_type,
+            map_provider,
+            &notes,
+            locale,
+            theme,
+            buf,
+            true,
+        );
+    }
+    if let Some(synced_at) = week.last_synced_at {
+        buf.push_str("\n\n");
+        render_sync_time(synced_at, Local::now().date_naive(), locale, theme, buf);
     }
 }
 
-fn render_day(
-    day_offset: i8,
-    day: &Day,
+#[allow(clippy::too_many_arguments)]
+fn render_day_range(
+    (from, to): (NaiveDate, NaiveDate),
+    days: &[Day],
     schedule_type: &ScheduleType,
+    map_provider: Option<MapProvider>,
+    notes: &[ChatNote],
+    locale: Locale,
+    theme: &Theme,
     buf: &mut String,
-    inside_week: bool,
 ) {
-    if !inside_week {
-        buf.push_str("Расписание ");
+    let dates = DateFormatter(locale);
+    let from_text = match locale {
+        // "с 13 по 15 марта", the month is written once
+        Locale::Ru if from.month() == to.month() => from.day().to_string(),
+        _ => dates.day_and_month(from),
+    };
+    match locale {
+        Locale::Ru => write!(
+            buf,
+            "Расписание с {from_text} по {}",
+            dates.day_and_month(to)
+        ),
+        Locale::En => write!(
+            buf,
+            "Schedule from {from_text} to {}",
+            dates.day_and_month(to)
+        ),
     }
+    .unwrap();
+    buf.push_str("\n\n");
 
-    if day_offset == 0 && !inside_week {
-        buf.push_str("сегодня\n\n")
-    } else {
-        if inside_week {
-            buf.push_str("📅 ");
-            buf.push_str(render_day_of_week(day.date.weekday()));
-        } else {
-            buf.push_str(render_day_of_week_gen(day.date.weekday()));
+    if days.is_empty() {
+        render_no_classes(locale, theme, buf);
+    }
+    for (i, day) in days.iter().enumerate() {
+        if i > 0 {
+            buf.push_str("\n\n");
         }
-        buf.push_str(", ");
-        buf.push_str(&day.date.day().to_string());
-        buf.push(' ');
-        buf.push_str(render_month(day.date.month()));
+        let notes = notes
+            .iter()
+            .filter(|it| it.date == day.date)
+            .cloned()
+            .collect::<Vec<_>>();
+        render_day(
+            0,
+            day,
+            schedule_type,
+            map_provider,
+            &notes,
+            locale,
+            theme,
+            buf,
+            true,
+        );
+    }
+}
+
+fn render_week_title(week: &Week, locale: Locale, buf: &mut String) {
+    match (week.week_of_semester, locale) {
+        (n @ 0..=17, Locale::Ru) => write!(buf, "Расписание на {n} учебную неделю").unwrap(),
+        (n @ 0..=17, Locale::En) => write!(buf, "Schedule for study week {n}").unwrap(),
+        (_, Locale::Ru) => buf.push_str("Расписание на неделю"),
+        (_, Locale::En) => buf.push_str("Schedule for the week"),
+    }
+}
+
+/// Whether the week of the reply has too many classes to be readable,
+/// so it is rendered as the summary with the [full_week_command] follow-up
+pub fn is_week_summarized(reply: &Reply) -> bool {
+    match reply {
+        Reply::Week {
+            week, full: false, ..
+        } => {
+            week.days.iter().map(|day| day.classes.len()).sum::<usize>() > max_week_render_classes()
+        }
+        _ => false,
+    }
+}
+
+/// Weeks with more classes are summarized, unless the week is requested in full.
+/// Read on each render, so it can be tuned with the config reload.
+fn max_week_render_classes() -> usize {
+    env::get_parsed_or("BOT_MAX_WEEK_RENDER_CLASSES", 40)
+}
+
+/// Text command, which shows the summarized week with the `week_offset` in full
+pub fn full_week_command(week_offset: i8, locale: Locale) -> &'static str {
+    match (week_offset, locale) {
+        (1, Locale::Ru) => "Показать полностью следующую неделю",
+        (1, Locale::En) => "Show next week in full",
+        (-1, Locale::Ru) => "Показать полностью прошлую неделю",
+        (-1, Locale::En) => "Show previous week in full",
+        (_, Locale::Ru) => "Показать полностью",
+        (_, Locale::En) => "Show in full",
+    }
+}
+
+/// Compact week for the schedules with too many classes: number of classes of each day
+/// and one line per class, identical classes in a row are grouped like `09:20 Физика ×2`
+fn render_week_summary(
+    week_offset: i8,
+    week: &Week,
+    notes: &[ChatNote],
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    let dates = DateFormatter(locale);
+    render_period_warning(&week.period_type, locale, theme, buf);
+    render_week_title(week, locale, buf);
+    buf.push_str(match locale {
+        Locale::Ru => " (кратко)",
+        Locale::En => " (in brief)",
+    });
+    for day in &week.days {
+        write!(
+            buf,
+            "\n\n{} {} — {}",
+            theme.day,
+            capitalize(&dates.full_date(day.date)),
+            match day.before_semester_start && day.classes.is_empty() {
+                true => before_semester_start(locale).to_owned(),
+                false => classes_count(day.classes.len(), locale),
+            }
+        )
+        .unwrap();
+        // consecutive classes with the same name, type and status are shown as one line
+        let mut groups = Vec::<(&Classes, usize)>::new();
+        for cls in &day.classes {
+            match groups.last_mut() {
+                Some((first, count))
+                    if first.name == cls.name
+                        && first.raw_type == cls.raw_type
+                        && first.status == cls.status =>
+                {
+                    *count += 1
+                }
+                _ => groups.push((cls, 1)),
+            }
+        }
+        for (cls, count) in groups {
+            write!(
+                buf,
+                "\n{} {} {}",
+                theme.list_item,
+                cls.time.start.format("%H:%M"),
+                ellipsize(&cls.name, SUMMARY_NAME_MAX_CHARS)
+            )
+            .unwrap();
+            if !cls.raw_type.is_empty() {
+                write!(buf, " ({})", cls.raw_type).unwrap();
+            }
+            if count > 1 {
+                write!(buf, " ×{count}").unwrap();
+            }
+            if cls.status == ClassesStatus::Cancelled {
+                write!(buf, " {}", theme.cancelled).unwrap();
+            }
+        }
+        for note in notes.iter().filter(|it| it.date == day.date) {
+            write!(buf, "\n{} {}", theme.note, note.text).unwrap();
+        }
+    }
+    buf.push_str("\n\n");
+    buf.push_str(&localized!(
+        locale,
+        "msg_week_summary.txt",
+        command = full_week_command(week_offset, locale)
+    ));
+    if let Some(synced_at) = week.last_synced_at {
         buf.push_str("\n\n");
+        render_sync_time(synced_at, Local::now().date_naive(), locale, theme, buf);
+    }
+}
+
+/// `5 пар` or `5 classes`
+fn classes_count(count: usize, locale: Locale) -> String {
+    match locale {
+        Locale::Ru => match (count % 10, count % 100) {
+            _ if count == 0 => "нет пар".to_owned(),
+            (1, n) if n != 11 => format!("{count} пара"),
+            (2..=4, n) if !(12..=14).contains(&n) => format!("{count} пары"),
+            _ => format!("{count} пар"),
+        },
+        Locale::En => match count {
+            0 => "no classes".to_owned(),
+            1 => "1 class".to_owned(),
+            _ => format!("{count} classes"),
+        },
+    }
+}
+
+/// Days of the first week before the first study day, if the semester starts mid-week
+fn before_semester_start(locale: Locale) -> &'static str {
+    match locale {
+        Locale::Ru => "семестр ещё не начался",
+        Locale::En => "the semester has not started yet",
+    }
+}
+
+/// Shorten the text to `max_chars` chars with the ellipsis at the end
+fn ellipsize(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_owned();
+    }
+    let mut text = text.chars().take(max_chars - 1).collect::<String>();
+    text.push('…');
+    text
+}
+
+/// Line like `🕖 обновлено сегодня в 14:02`, so the peers can tell
+/// how old the schedule is, if MPEI is unavailable for a long time
+fn render_sync_time(
+    synced_at: NaiveDateTime,
+    today: NaiveDate,
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    let time = synced_at.format("%H:%M");
+    let date = synced_at.date();
+    let day = match (today - date).num_days() {
+        0 => match locale {
+            Locale::Ru => "сегодня".to_owned(),
+            Locale::En => "today".to_owned(),
+        },
+        1 => match locale {
+            Locale::Ru => "вчера".to_owned(),
+            Locale::En => "yesterday".to_owned(),
+        },
+        _ => match locale {
+            Locale::Ru => DateFormatter(locale).day_and_month(date),
+            Locale::En => format!("on {}", DateFormatter(locale).day_and_month(date)),
+        },
     };
+    match locale {
+        Locale::Ru => write!(buf, "{} обновлено {day} в {time}", theme.time),
+        Locale::En => write!(buf, "{} updated {day} at {time}", theme.time),
+    }
+    .unwrap();
+}
+
+/// Calendar grid with a row of emoji squares per week and the day numbers next to it,
+/// squares are aligned in any font unlike the text
+fn render_month(month: &MonthOverview, locale: Locale, theme: &Theme, buf: &mut String) {
+    let date_formatter = DateFormatter(locale);
+    write!(
+        buf,
+        "{} {}\n\n",
+        theme.month,
+        capitalize(&date_formatter.month_and_year(month.first_day))
+    )
+    .unwrap();
+    // the first week is padded with the days of the previous month
+    let padding = month.first_day.weekday().num_days_from_monday() as usize;
+    let mut first_day_of_row = 1;
+    while first_day_of_row <= month.days.len() {
+        let row_padding = if first_day_of_row == 1 { padding } else { 0 };
+        let last_day_of_row = (first_day_of_row + 6 - row_padding).min(month.days.len());
+        buf.push_str(&theme.month_padding.repeat(row_padding));
+        for kind in &month.days[first_day_of_row - 1..last_day_of_row] {
+            buf.push_str(match kind {
+                MonthDayKind::Study => theme.study_day,
+                MonthDayKind::Exam => theme.exam_day,
+                MonthDayKind::Holiday => theme.holiday,
+                MonthDayKind::DayOff => theme.day_off,
+            });
+        }
+        if first_day_of_row == last_day_of_row {
+            writeln!(buf, " {first_day_of_row}").unwrap();
+        } else {
+            writeln!(buf, " {first_day_of_row}–{last_day_of_row}").unwrap();
+        }
+        first_day_of_row = last_day_of_row + 1;
+    }
+    let (study, exam, holiday, day_off) = (
+        theme.study_day,
+        theme.exam_day,
+        theme.holiday,
+        theme.day_off,
+    );
+    match locale {
+        Locale::Ru => write!(
+            buf,
+            "\n{study} пары  {exam} экзамены  {holiday} каникулы  {day_off} нет пар"
+        ),
+        Locale::En => write!(
+            buf,
+            "\n{study} classes  {exam} exams  {holiday} vacation  {day_off} no classes"
+        ),
+    }
+    .unwrap();
+}
+
+fn render_period_warning(
+    period_type: &PeriodType,
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    if let PeriodType::Session = period_type {
+        let icon = theme.session_warning;
+        match locale {
+            Locale::Ru => write!(buf, "{icon} Идёт сессия — расписание может меняться\n\n"),
+            Locale::En => write!(
+                buf,
+                "{icon} Exam session is on — the schedule may change\n\n"
+            ),
+        }
+        .unwrap();
+    }
+}
+
+/// Render the warnings about the moved classes, e.g. "⚠️ Матан (09:20) перенесён в Ж-200"
+fn render_room_changes(
+    room_changes: &[RoomChangeWarning],
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    if room_changes.is_empty() {
+        return;
+    }
+    let icon = theme.room_change;
+    for change in room_changes {
+        let (name, place) = (&change.class_name, &change.place);
+        let start = change.time.start.format("%H:%M");
+        match locale {
+            Locale::Ru => writeln!(buf, "{icon} {name} ({start}) перенесён в {place}"),
+            Locale::En => writeln!(buf, "{icon} {name} ({start}) moved to {place}"),
+        }
+        .unwrap();
+    }
+    buf.push('\n');
+}
+
+fn render_no_classes(locale: Locale, theme: &Theme, buf: &mut String) {
+    buf.push_str(match locale {
+        Locale::Ru => "Нет пар",
+        Locale::En => "No classes",
+    });
+    if !theme.no_classes.is_empty() {
+        buf.push(' ');
+        buf.push_str(theme.no_classes);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_day(
+    day_offset: i8,
+    day: &Day,
+    schedule_type: &ScheduleType,
+    map_provider: Option<MapProvider>,
+    notes: &[ChatNote],
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+    inside_week: bool,
+) {
+    let dates = DateFormatter(locale);
+    match (inside_week, day_offset, locale) {
+        (true, _, _) => write!(
+            buf,
+            "{} {}",
+            theme.day,
+            capitalize(&dates.full_date(day.date))
+        ),
+        (false, 0, Locale::Ru) => write!(buf, "Расписание сегодня"),
+        (false, 0, Locale::En) => write!(buf, "Schedule for today"),
+        (false, _, Locale::Ru) => write!(buf, "Расписание {}", dates.on_full_date(day.date)),
+        (false, _, Locale::En) => write!(buf, "Schedule for {}", dates.full_date(day.date)),
+    }
+    .unwrap();
+    buf.push_str("\n\n");
 
     if !day.classes.is_empty() {
         for (i, cls) in day.classes.iter().enumerate() {
             if i > 0 {
                 buf.push_str("\n\n");
+                if render_gap(&day.classes[i - 1], cls, buf) {
+                    buf.push_str("\n\n");
+                }
             }
-            render_classes(cls, schedule_type, buf);
+            render_classes(cls, schedule_type, map_provider, theme, buf);
         }
+    } else if day.before_semester_start {
+        buf.push_str(&capitalize(before_semester_start(locale)));
     } else {
-        buf.push_str("Нет пар 🤷")
+        render_no_classes(locale, theme, buf)
     };
+
+    for note in notes {
+        write!(buf, "\n\n{} {}", theme.note, note.text).unwrap();
+    }
 }
 
-fn render_classes(cls: &Classes, schedule_type: &ScheduleType, buf: &mut String) {
-    buf.push_str(render_emoji_number(cls.number));
+fn render_classes(
+    cls: &Classes,
+    schedule_type: &ScheduleType,
+    map_provider: Option<MapProvider>,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    buf.push_str(theme.number(cls.number));
     buf.push(' ');
     buf.push_str(&cls.name);
+    match cls.status {
+        ClassesStatus::Cancelled => write!(buf, " {} ОТМЕНЕНА", theme.cancelled).unwrap(),
+        ClassesStatus::Moved => write!(buf, " {} ПЕРЕНЕСЕНА", theme.moved).unwrap(),
+        ClassesStatus::Normal => (),
+    }
     if !cls.raw_type.is_empty() {
         buf.push_str(" (");
         buf.push_str(&cls.raw_type);
@@ -238,89 +1325,118 @@ fn render_classes(cls: &Classes, schedule_type: &ScheduleType, buf: &mut String)
     }
     match (schedule_type, cls.groups.is_empty(), cls.person.is_empty()) {
         (ScheduleType::Person, false, _) => {
-            buf.push_str("🎓 ");
-            buf.push_str(&cls.groups);
-            buf.push('\n');
+            writeln!(buf, "{} {}", theme.groups, cls.groups).unwrap();
+        }
+        // the room is known from the schedule, so both the groups and the person are useful
+        (ScheduleType::Room, groups_empty, person_empty) => {
+            if !groups_empty {
+                writeln!(buf, "{} {}", theme.groups, cls.groups).unwrap();
+            }
+            if !person_empty {
+                writeln!(buf, "{} {}", theme.person, cls.person).unwrap();
+            }
         }
         (_, _, false) => {
-            buf.push_str("👨‍🏫 ");
-            buf.push_str(&cls.person);
-            buf.push('\n');
+            writeln!(buf, "{} {}", theme.person, cls.person).unwrap();
         }
         _ => (),
     };
     if !cls.place.is_empty() {
-        buf.push_str("🚪 ");
-        buf.push_str(&cls.place);
-        buf.push('\n');
+        writeln!(buf, "{} {}", theme.place, cls.place).unwrap();
+        if let (Some(provider), Some(building)) = (map_provider, BUILDINGS.find_by_room(&cls.place))
+        {
+            writeln!(buf, "{} {}", theme.map, building.map_url(provider)).unwrap();
+        }
     }
-    buf.push_str("🕖 С ");
+    buf.push_str(theme.time);
+    buf.push_str(" С ");
     buf.push_str(&cls.time.start.format("%H:%M").to_string());
     buf.push_str(" до ");
     buf.push_str(&cls.time.end.format("%H:%M").to_string());
 }
 
-#[inline]
-fn render_emoji_number<'a>(num: i8) -> &'a str {
-    match num {
-        1 => "1️⃣",
-        2 => "2️⃣",
-        3 => "3️⃣",
-        4 => "4️⃣",
-        5 => "5️⃣",
-        6 => "6️⃣",
-        7 => "7️⃣",
-        8 => "8️⃣",
-        9 => "9️⃣",
-        _ => "🟢",
-    }
-}
-
-#[inline]
-fn render_day_of_week<'a>(weekday: Weekday) -> &'a str {
-    match weekday.number_from_monday() {
-        1 => "понедельник",
-        2 => "вторник",
-        3 => "среда",
-        4 => "четверг",
-        5 => "пятница",
-        6 => "суббота",
-        7 => "воскресенье",
-        _ => unreachable!(),
-    }
-}
-
-#[inline]
-fn render_day_of_week_gen<'a>(weekday: Weekday) -> &'a str {
-    match weekday.number_from_monday() {
-        1 => "в понедельник",
-        2 => "во вторник",
-        3 => "в среду",
-        4 => "в четверг",
-        5 => "в пятницу",
-        6 => "в субботу",
-        7 => "в воскресенье",
-        _ => unreachable!(),
-    }
-}
-
-#[inline]
-fn render_month<'a>(month: u32) -> &'a str {
-    match month {
-        1 => "января",
-        2 => "февраля",
-        3 => "марта",
-        4 => "апреля",
-        5 => "мая",
-        6 => "июня",
-        7 => "июля",
-        8 => "августа",
-        9 => "сентября",
-        10 => "октября",
-        11 => "ноября",
-        12 => "декабря",
-        _ => "",
+/// Render the directions to the building of the room, its floor and the map link
+fn render_navigation(
+    room: &str,
+    map_provider: Option<MapProvider>,
+    locale: Locale,
+    theme: &Theme,
+) -> String {
+    let Some(building) = BUILDINGS.find_by_room(room) else {
+        return localized!(locale, "msg_navigation_unknown_building.txt", room = room);
+    };
+    let mut buf = localized!(
+        locale,
+        "msg_navigation.txt",
+        room = room,
+        name = building.name,
+        address = building.address,
+    );
+    if let Some(navigation) = &building.navigation {
+        buf.push('\n');
+        buf.push_str(navigation);
     }
+    if let Some(floor) = room_floor(room) {
+        buf.push('\n');
+        buf.push_str(&localized!(
+            locale,
+            "msg_navigation_floor.txt",
+            floor = floor
+        ));
+    }
+    if let Some(provider) = map_provider {
+        write!(buf, "\n{} {}", theme.map, building.map_url(provider)).unwrap();
+    }
+    buf
+}
+
+/// Render the short directions to the room of the first class, which is not cancelled
+fn render_first_class_directions(day: &Day, theme: &Theme, buf: &mut String) {
+    let Some(cls) = day
+        .classes
+        .iter()
+        .find(|it| it.status != ClassesStatus::Cancelled && !it.place.is_empty())
+    else {
+        return;
+    };
+    let Some(navigation) = BUILDINGS
+        .find_by_room(&cls.place)
+        .and_then(|it| it.navigation.as_ref())
+    else {
+        return;
+    };
+    write!(
+        buf,
+        "\n\n{} Как пройти в {}: {navigation}",
+        theme.directions, cls.place
+    )
+    .unwrap();
+    if let Some(floor) = room_floor(&cls.place) {
+        write!(buf, " Аудитория на {floor} этаже.").unwrap();
+    }
+}
+
+/// Render "— окно (1 пара, 1ч 50м) —" if there are skipped classes numbers between
+/// `prev` and `next` classes. Returns `false` if there is no gap.
+fn render_gap(prev: &Classes, next: &Classes, buf: &mut String) -> bool {
+    // classes of different subgroups may have the same number
+    let skipped = next.number - prev.number - 1;
+    if prev.number <= 0 || skipped <= 0 {
+        return false;
+    }
+    buf.push_str("— окно (");
+    render_classes_count(skipped as usize, buf);
+    let duration = next.time.start - prev.time.end;
+    let (h, m) = (duration.num_hours(), duration.num_minutes() % 60);
+    if h > 0 {
+        write!(buf, ", {h}ч").unwrap();
+    }
+    if m > 0 {
+        buf.push_str(if h > 0 { " " } else { ", " });
+        write!(buf, "{m}м").unwrap();
+    }
+    buf.push_str(") —");
+    true
 }
 
 fn render_duration(duration: &chrono::Duration, buf: &mut String) {
@@ -358,6 +1474,91 @@ fn render_minutes(m: i8, buf: &mut String) {
     .unwrap()
 }
 
+fn render_classes_count(n: usize, buf: &mut String) {
+    if let n @ 11..=19 = n % 100 {
+        write!(buf, "{n} пар").unwrap();
+        return;
+    }
+    match n % 10 {
+        1 => write!(buf, "{n} пара"),
+        2..=4 => write!(buf, "{n} пары"),
+        _ => write!(buf, "{n} пар"),
+    }
+    .unwrap()
+}
+
+/// Comma-separated counts of the changes, e.g. "перенесены 2 пары, добавлена 1 пара"
+fn render_changelog_digest(digest: &ChangelogDigest, locale: Locale, buf: &mut String) {
+    let counts = [
+        (digest.moved, "перенесен", "moved"),
+        (digest.added, "добавлен", "added"),
+        (digest.removed, "отменен", "cancelled"),
+        (digest.changed, "изменен", "changed"),
+    ];
+    for (n, ru, en) in counts.into_iter().filter(|(n, _, _)| *n > 0) {
+        if !buf.is_empty() {
+            buf.push_str(", ");
+        }
+        match locale {
+            Locale::Ru => {
+                // the participle agrees with the number of classes
+                let ending = match (n % 100, n % 10) {
+                    (11..=19, _) => "о",
+                    (_, 1) => "а",
+                    (_, 2..=4) => "ы",
+                    _ => "о",
+                };
+                write!(buf, "{ru}{ending} ").unwrap();
+                render_classes_count(n, buf);
+            }
+            Locale::En => {
+                let classes = if n == 1 { "class" } else { "classes" };
+                write!(buf, "{en} {n} {classes}").unwrap();
+            }
+        }
+    }
+}
+
+/// One line per day, e.g. "Понедельник, 20 марта — 2 пары с 11:10, лабы: Физика"
+fn render_week_ahead_digest(
+    digest: &WeekAheadDigest,
+    locale: Locale,
+    theme: &Theme,
+    buf: &mut String,
+) {
+    let dates = DateFormatter(locale);
+    for day in &digest.days {
+        if !buf.is_empty() {
+            buf.push('\n');
+        }
+        write!(
+            buf,
+            "{} {} — {} {} {}",
+            theme.day,
+            capitalize(&dates.full_date(day.date)),
+            classes_count(day.classes_count, locale),
+            match locale {
+                Locale::Ru => "с",
+                Locale::En => "from",
+            },
+            day.earliest_start.format("%H:%M"),
+        )
+        .unwrap();
+        if !day.labs.is_empty() {
+            write!(
+                buf,
+                ", {}: {}",
+                match locale {
+                    Locale::Ru => "лабы",
+                    Locale::En => "labs",
+                },
+                day.labs.join(", ")
+            )
+            .unwrap();
+        }
+    }
+}
+
 fn render_hours(h: i8, buf: &mut String) {
     if let h @ 11..=19 = h {
         write!(buf, "{h} часов").unwrap();
@@ -370,3 +1571,791 @@ fn render_hours(h: i8, buf: &mut String) {
     }
     .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use chrono::{NaiveDate, NaiveDateTime};
+    use domain_buildings::MapProvider;
+    use domain_schedule_models::{
+        Classes, ClassesChange, ClassesChangeKind, ClassesStatus, ClassesTime, ClassesType, Day,
+        PeriodType, ScheduleDiff, ScheduleType, SessionSchedule, Week,
+    };
+
+    use crate::{
+        models::{
+            BuildInfo, ChangelogDigest, ChatNote, HealthReport, Locale, MonthDayKind,
+            MonthOverview, PeerDataExport, PeerPreferences, RenderedWeekKey, RenderedWeeks, Reply,
+            ReplyContext, RoomChangeWarning, UpcomingEventsPrediction, WeekAheadDay,
+            WeekAheadDigest,
+        },
+        theme::{Theme, ThemeKind},
+    };
+
+    use super::{
+        classes_count, ellipsize, is_week_summarized, render_changelog_digest, render_document,
+        render_exams, render_first_class_directions, render_gap, render_message, render_month,
+        render_navigation, render_next_week_changes, render_pong, render_schedule_changes,
+        render_sync_time, render_upcoming_events, render_week, RenderTargetPlatform,
+    };
+
+    fn classes(number: i8, start: &str, end: &str) -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: String::new(),
+            place: String::new(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: start.parse().unwrap(),
+                end: end.parse().unwrap(),
+            },
+            number,
+            status: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_week_with_sunday_classes() {
+        let day = |day: u32, classes| Day {
+            day_of_week: (day - 12) as u8,
+            date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            classes,
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        };
+        let week = Week {
+            week_of_year: 11,
+            week_of_semester: 6,
+            first_day_of_week: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            days: vec![
+                day(18, vec![classes(6, "18:55:00", "20:30:00")]),
+                day(19, vec![classes(1, "09:20:00", "10:55:00")]),
+            ],
+            period_type: Default::default(),
+            last_synced_at: None,
+        };
+        let mut buf = String::new();
+        let notes = vec![ChatNote {
+            date: NaiveDate::from_ymd_opt(2023, 3, 19).unwrap(),
+            text: "Контрольная по физике".to_owned(),
+        }];
+        render_week(
+            0,
+            &week,
+            &ScheduleType::Group,
+            None,
+            &notes,
+            Locale::Ru,
+            &Theme::DEFAULT,
+            &mut buf,
+        );
+        assert!(buf.starts_with("Расписание на 6 учебную неделю\n\n📅 Суббота, 18 марта"));
+        assert!(buf.contains("📅 Воскресенье, 19 марта"));
+        assert!(buf.ends_with("\n\n📌 Контрольная по физике"));
+        assert_eq!(buf.matches("📌").count(), 1);
+
+        let mut buf = String::new();
+        render_week(
+            0,
+            &week,
+            &ScheduleType::Group,
+            None,
+            &notes,
+            Locale::En,
+            &Theme::DEFAULT,
+            &mut buf,
+        );
+        assert!(buf.starts_with("Schedule for study week 6\n\n📅 Saturday, March 18"));
+        assert!(buf.contains("📅 Sunday, March 19"));
+    }
+
+    #[test]
+    fn test_big_week_summary() {
+        let long_name = "Теоретические основы электротехники и электроники";
+        let day = |day: u32| Day {
+            day_of_week: (day - 12) as u8,
+            date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            classes: (1..=8)
+                .map(|number| Classes {
+                    name: if number <= 2 {
+                        "Физика"
+                    } else {
+                        long_name
+                    }
+                    .to_owned(),
+                    raw_type: "Лекция".to_owned(),
+                    ..classes(number, "09:20:00", "10:55:00")
+                })
+                .collect(),
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        };
+        let reply = |full| Reply::Week {
+            schedule_name: "А-08-19".to_owned(),
+            week_offset: 1,
+            week: Week {
+                week_of_year: 11,
+                week_of_semester: 6,
+                first_day_of_week: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                days: (13..=18).map(day).collect(),
+                period_type: Default::default(),
+                last_synced_at: None,
+            },
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            notes: vec![],
+            full,
+            rendered: None,
+        };
+        let render = |reply| {
+            render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                Locale::Ru,
+                ThemeKind::Default,
+            )
+        };
+
+        assert!(is_week_summarized(&reply(false)));
+        let text = render(reply(false));
+        assert!(text.starts_with(
+            "Расписание на 6 учебную неделю (кратко)\n\n📅 Понедельник, 13 марта — 8 пар\n"
+        ));
+        assert!(text.contains(
+            "\n🔸 09:20 Физика (Лекция) ×2\n🔸 09:20 Теоретические основы электротех… (Лекция) ×6"
+        ));
+        assert!(text.ends_with("отправь \"Показать полностью следующую неделю\"."));
+
+        assert!(!is_week_summarized(&reply(true)));
+        let text = render(reply(true));
+        assert!(!text.contains("(кратко)"));
+        assert_eq!(text.matches(long_name).count(), 36);
+    }
+
+    #[test]
+    fn test_shared_week_is_rendered_once() {
+        let rendered = RenderedWeeks::default();
+        let key = |locale| RenderedWeekKey {
+            locale,
+            theme: ThemeKind::Default,
+            summarized: false,
+            map_provider: None,
+            days: vec![NaiveDate::from_ymd_opt(2023, 3, 13).unwrap()],
+            notes: vec![],
+        };
+        let renders = Cell::new(0);
+        let render = |text: &str| {
+            renders.set(renders.get() + 1);
+            text.to_owned()
+        };
+
+        assert_eq!(
+            "ru",
+            rendered.get_or_render(key(Locale::Ru), || render("ru"))
+        );
+        assert_eq!(
+            "ru",
+            rendered.get_or_render(key(Locale::Ru), || render("again"))
+        );
+        assert_eq!(
+            "en",
+            rendered.get_or_render(key(Locale::En), || render("en"))
+        );
+        assert_eq!(2, renders.get());
+    }
+
+    #[test]
+    fn test_classes_count() {
+        assert_eq!("нет пар", classes_count(0, Locale::Ru));
+        assert_eq!("1 пара", classes_count(1, Locale::Ru));
+        assert_eq!("3 пары", classes_count(3, Locale::Ru));
+        assert_eq!("11 пар", classes_count(11, Locale::Ru));
+        assert_eq!("21 пара", classes_count(21, Locale::Ru));
+        assert_eq!("1 class", classes_count(1, Locale::En));
+        assert_eq!("5 classes", classes_count(5, Locale::En));
+    }
+
+    #[test]
+    fn test_ellipsize() {
+        assert_eq!("Физика", ellipsize("Физика", 6));
+        assert_eq!("Физ…", ellipsize("Физика", 4));
+    }
+
+    #[test]
+    fn test_navigation() {
+        let text = render_navigation(
+            "К-601",
+            Some(MapProvider::Yandex),
+            Locale::Ru,
+            &Theme::DEFAULT,
+        );
+        assert!(text.starts_with("🧭 Как пройти в К-601\n\n🏢 Корпус К, "));
+        assert!(text.contains("\nАудитория на 6 этаже."));
+        assert!(text.contains("\n🗺 https://yandex.ru/maps/"));
+
+        let text = render_navigation("Я-100", None, Locale::En, &Theme::DEFAULT);
+        assert!(text.starts_with("I don't know where Я-100 is"));
+    }
+
+    #[test]
+    fn test_first_class_directions() {
+        let mut cancelled = classes(1, "09:20:00", "10:55:00");
+        cancelled.place = "М-611".to_owned();
+        cancelled.status = ClassesStatus::Cancelled;
+        let mut second = classes(2, "11:10:00", "12:45:00");
+        second.place = "К-601".to_owned();
+        let day = Day {
+            day_of_week: 1,
+            date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            classes: vec![cancelled, second],
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        };
+        let mut buf = String::new();
+        render_first_class_directions(&day, &Theme::DEFAULT, &mut buf);
+        assert!(buf.starts_with("\n\n🧭 Как пройти в К-601: "));
+        assert!(buf.ends_with(" Аудитория на 6 этаже."));
+    }
+
+    fn gap(prev: &Classes, next: &Classes) -> Option<String> {
+        let mut buf = String::new();
+        render_gap(prev, next, &mut buf).then_some(buf)
+    }
+
+    #[test]
+    fn test_single_slot_gap() {
+        let second = classes(2, "11:10:00", "12:45:00");
+        let fourth = classes(4, "14:35:00", "16:10:00");
+        assert_eq!(
+            Some("— окно (1 пара, 1ч 50м) —".to_owned()),
+            gap(&second, &fourth)
+        );
+    }
+
+    #[test]
+    fn test_multi_slot_gap() {
+        let first = classes(1, "09:20:00", "10:55:00");
+        let fourth = classes(4, "14:35:00", "16:10:00");
+        let seventh = classes(7, "20:50:00", "22:15:00");
+        assert_eq!(
+            Some("— окно (2 пары, 3ч 40м) —".to_owned()),
+            gap(&first, &fourth)
+        );
+        assert_eq!(
+            Some("— окно (2 пары, 4ч 40м) —".to_owned()),
+            gap(&fourth, &seventh)
+        );
+        assert_eq!(
+            Some("— окно (5 пар, 9ч 55м) —".to_owned()),
+            gap(&first, &seventh)
+        );
+    }
+
+    #[test]
+    fn test_gap_with_round_duration() {
+        let first = classes(1, "09:00:00", "10:00:00");
+        let third = classes(3, "12:00:00", "13:00:00");
+        let fifth = classes(5, "13:40:00", "15:00:00");
+        assert_eq!(
+            Some("— окно (1 пара, 2ч) —".to_owned()),
+            gap(&first, &third)
+        );
+        assert_eq!(
+            Some("— окно (1 пара, 40м) —".to_owned()),
+            gap(&third, &fifth)
+        );
+    }
+
+    #[test]
+    fn test_no_gap() {
+        let second = classes(2, "11:10:00", "12:45:00");
+        let third = classes(3, "13:45:00", "15:20:00");
+        let unknown = classes(0, "18:00:00", "19:00:00");
+        assert_eq!(None, gap(&second, &third));
+        // subgroups have classes with the same number
+        assert_eq!(None, gap(&second, &second.clone()));
+        assert_eq!(None, gap(&unknown, &third));
+    }
+
+    #[test]
+    fn test_classes_resume_after_vacation() {
+        let render = |locale| {
+            let mut buf = String::new();
+            render_upcoming_events(
+                &UpcomingEventsPrediction::ClassesResume {
+                    period_type: PeriodType::Vacation,
+                    date: NaiveDate::from_ymd_opt(2024, 2, 10).unwrap(),
+                },
+                &ScheduleType::Group,
+                locale,
+                &Theme::DEFAULT,
+                &mut buf,
+            );
+            buf
+        };
+        assert_eq!(
+            "Сейчас каникулы, пары начнутся 10 февраля",
+            render(Locale::Ru)
+        );
+        assert_eq!(
+            "It's vacation now, classes resume on February 10",
+            render(Locale::En)
+        );
+    }
+
+    #[test]
+    fn test_minimal_theme() {
+        let mut cancelled = classes(1, "09:20:00", "10:55:00");
+        cancelled.status = ClassesStatus::Cancelled;
+        cancelled.place = "К-601".to_owned();
+        cancelled.raw_type = "Лекция".to_owned();
+        let reply = |classes| Reply::Day {
+            day_offset: 0,
+            day: Day {
+                day_of_week: 1,
+                date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                classes,
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: false,
+            },
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            period_type: PeriodType::Session,
+            notes: vec![],
+            newcomer_mode: false,
+            room_changes: vec![],
+        };
+        let render = |reply: &Reply, theme| {
+            render_message(reply, RenderTargetPlatform::Telegram, Locale::Ru, theme)
+        };
+        assert_eq!(
+            "! Идёт сессия — расписание может меняться\n\n\
+             Расписание сегодня\n\n\
+             1. Физика × ОТМЕНЕНА (Лекция)\n• К-601\n\
+             • С 09:20 до 10:55",
+            render(&reply(vec![cancelled.clone()]), ThemeKind::Minimal)
+        );
+        assert!(render(&reply(vec![cancelled]), ThemeKind::Default).contains("1️⃣ Физика ❌"));
+        assert!(render(&reply(vec![]), ThemeKind::Minimal).ends_with("\n\nНет пар"));
+        assert!(render(&reply(vec![]), ThemeKind::HighContrast).starts_with("🚨 Идёт сессия"));
+    }
+
+    #[test]
+    fn test_day_before_semester_start() {
+        let reply = Reply::Day {
+            day_offset: 1,
+            day: Day {
+                day_of_week: 1,
+                date: NaiveDate::from_ymd_opt(2023, 8, 28).unwrap(),
+                classes: vec![],
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: true,
+            },
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            period_type: PeriodType::Study,
+            notes: vec![],
+            newcomer_mode: false,
+            room_changes: vec![],
+        };
+        let render = |locale| {
+            render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                locale,
+                ThemeKind::Minimal,
+            )
+        };
+        assert!(render(Locale::Ru).ends_with("\n\nСеместр ещё не начался"));
+        assert!(render(Locale::En).ends_with("\n\nThe semester has not started yet"));
+    }
+
+    #[test]
+    fn test_room_changes_go_first() {
+        let mut moved = classes(1, "09:20:00", "10:55:00");
+        moved.place = "Ж-200".to_owned();
+        let reply = Reply::Day {
+            day_offset: 0,
+            day: Day {
+                day_of_week: 1,
+                date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                classes: vec![moved.clone()],
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: false,
+            },
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            period_type: PeriodType::Study,
+            notes: vec![],
+            newcomer_mode: false,
+            room_changes: vec![RoomChangeWarning {
+                date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                class_name: moved.name,
+                time: moved.time,
+                place: moved.place,
+            }],
+        };
+        let render = |locale| {
+            render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                locale,
+                ThemeKind::Default,
+            )
+        };
+        assert!(render(Locale::Ru)
+            .starts_with("⚠️ Физика (09:20) перенесён в Ж-200\n\nРасписание сегодня"));
+        assert!(render(Locale::En).starts_with("⚠️ Физика (09:20) moved to Ж-200\n\n"));
+    }
+
+    #[test]
+    fn test_day_range() {
+        let day = |day_of_week: u8, classes| Day {
+            day_of_week,
+            date: NaiveDate::from_ymd_opt(2023, 3, 12 + day_of_week as u32).unwrap(),
+            classes,
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        };
+        let reply = Reply::DayRange {
+            from: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            to: NaiveDate::from_ymd_opt(2023, 3, 14).unwrap(),
+            days: vec![
+                day(1, vec![classes(1, "09:20:00", "10:55:00")]),
+                day(2, vec![]),
+            ],
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            notes: vec![],
+        };
+        let render = |locale| {
+            render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                locale,
+                ThemeKind::Minimal,
+            )
+        };
+        let text = render(Locale::Ru);
+        assert!(
+            text.starts_with("Расписание с 13 по 14 марта\n\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Понедельник, 13 марта\n\n1. Физика"),
+            "{text}"
+        );
+        assert!(text.contains("Вторник, 14 марта\n\nНет пар"), "{text}");
+        assert!(render(Locale::En).starts_with("Schedule from March 13 to March 14\n\n"));
+    }
+
+    #[test]
+    fn test_month() {
+        use MonthDayKind::*;
+        // February 2026 starts on Sunday
+        let month = MonthOverview {
+            first_day: NaiveDate::from_ymd_opt(2026, 2, 1).unwrap(),
+            days: [
+                vec![DayOff],
+                vec![Holiday; 7],
+                vec![Study, Study, Exam, Study, Study, Study, DayOff],
+                vec![Study, Study, Study, Study, Study, DayOff, DayOff],
+                vec![Study, Study, Study, Study, Study, Study],
+            ]
+            .concat(),
+        };
+        let render = |locale| {
+            let mut buf = String::new();
+            render_month(&month, locale, &Theme::DEFAULT, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🗓 Февраль 2026\n\n\
+             ➖➖➖➖➖➖⬜ 1\n\
+             🟩🟩🟩🟩🟩🟩🟩 2–8\n\
+             🟦🟦🟥🟦🟦🟦⬜ 9–15\n\
+             🟦🟦🟦🟦🟦⬜⬜ 16–22\n\
+             🟦🟦🟦🟦🟦🟦 23–28\n\
+             \n🟦 пары  🟥 экзамены  🟩 каникулы  ⬜ нет пар",
+            render(Locale::Ru)
+        );
+        assert!(render(Locale::En).starts_with("🗓 February 2026\n\n"));
+    }
+
+    #[test]
+    fn test_exams() {
+        let mut session = SessionSchedule {
+            name: "А-08-19".to_owned(),
+            r#type: ScheduleType::Group,
+            days: Vec::new(),
+        };
+        let render = |session: &SessionSchedule, locale| {
+            let mut buf = String::new();
+            render_exams(session, None, locale, &Theme::DEFAULT, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🟥 Расписание сессии А-08-19\n\nРасписание сессии пока не опубликовано",
+            render(&session, Locale::Ru)
+        );
+
+        let mut exam = classes(2, "11:10:00", "12:45:00");
+        exam.r#type = ClassesType::Exam;
+        exam.raw_type = "Экзамен".to_owned();
+        session.days.push(Day {
+            day_of_week: 2,
+            date: NaiveDate::from_ymd_opt(2023, 1, 10).unwrap(),
+            classes: vec![exam],
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        });
+        let rendered = render(&session, Locale::En);
+        assert!(rendered.starts_with("🟥 Exam session schedule of А-08-19\n\n"));
+        assert!(rendered.contains("Физика (Экзамен)"));
+        assert!(rendered.contains("11:10 до 12:45"));
+    }
+
+    #[test]
+    fn test_next_week_changes() {
+        let date = |day| NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+        let mut diff = ScheduleDiff {
+            name: "А-08-19".to_owned(),
+            r#type: ScheduleType::Group,
+            from_week_start: date(13),
+            to_week_start: date(20),
+            changes: Vec::new(),
+        };
+        let render = |diff: &ScheduleDiff, locale| {
+            let mut buf = String::new();
+            render_next_week_changes(diff, locale, &Theme::DEFAULT, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🔄 What changes next week (from March 20)\n\n✅ The classes are the same as this week",
+            render(&diff, Locale::En)
+        );
+
+        let mut moved = classes(1, "09:20:00", "10:55:00");
+        moved.place = "К-601".to_owned();
+        diff.changes = vec![
+            ClassesChange {
+                date: date(21),
+                kind: ClassesChangeKind::Changed,
+                before: Some(classes(1, "09:20:00", "10:55:00")),
+                after: Some(moved),
+            },
+            ClassesChange {
+                date: date(22),
+                kind: ClassesChangeKind::Removed,
+                before: Some(classes(2, "11:10:00", "12:45:00")),
+                after: None,
+            },
+        ];
+        assert_eq!(
+            "🔄 Что меняется на следующей неделе (с 20 марта)\n\
+             \n✏️ 21.03 09:20 Физика → Физика, К-601\
+             \n➖ 22.03 11:10 Физика",
+            render(&diff, Locale::Ru)
+        );
+    }
+
+    #[test]
+    fn test_sync_time() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 15).unwrap();
+        let render = |synced_at: &str, locale| {
+            let mut buf = String::new();
+            let synced_at = NaiveDateTime::parse_from_str(synced_at, "%Y-%m-%d %H:%M").unwrap();
+            render_sync_time(synced_at, today, locale, &Theme::DEFAULT, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🕖 обновлено сегодня в 14:02",
+            render("2023-03-15 14:02", Locale::Ru)
+        );
+        assert_eq!(
+            "🕖 обновлено вчера в 09:30",
+            render("2023-03-14 09:30", Locale::Ru)
+        );
+        assert_eq!(
+            "🕖 обновлено 10 марта в 23:59",
+            render("2023-03-10 23:59", Locale::Ru)
+        );
+        assert_eq!(
+            "🕖 updated on March 10 at 23:59",
+            render("2023-03-10 23:59", Locale::En)
+        );
+    }
+
+    #[test]
+    fn test_schedule_changes() {
+        let mut moved = classes(1, "09:20:00", "10:55:00");
+        moved.place = "К-601".to_owned();
+        let changes = vec![ClassesChange {
+            date: NaiveDate::from_ymd_opt(2023, 3, 15).unwrap(),
+            kind: ClassesChangeKind::Changed,
+            before: Some(classes(1, "09:20:00", "10:55:00")),
+            after: Some(moved),
+        }];
+        let render = |locale, theme| {
+            let mut buf = String::new();
+            render_schedule_changes("А-08-19", &changes, locale, theme, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🔔 Расписание А-08-19 изменилось:\n\n✏️ 15.03 09:20 Физика → Физика, К-601",
+            render(Locale::Ru, &Theme::DEFAULT)
+        );
+        assert_eq!(
+            "» The schedule of А-08-19 has changed:\n\n~ 15.03 09:20 Физика → Физика, К-601",
+            render(Locale::En, &Theme::MINIMAL)
+        );
+    }
+
+    #[test]
+    fn test_pong() {
+        let mut report = HealthReport {
+            build: BuildInfo {
+                version: "3.1.1",
+                commit: "4f2a9c1e8b7d6a5f4e3d2c1b0a9f8e7d6c5b4a39",
+            },
+            uptime_seconds: 7500,
+            schedule_service_latency_ms: Some(12),
+            checked_seconds_ago: 15,
+            cache_hit_percent: Some(87),
+        };
+        let render = |report: &HealthReport| {
+            let mut buf = String::new();
+            render_pong(report, &Theme::DEFAULT, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "🏓 mpeix 3.1.1 (4f2a9c1), аптайм 2 ч 5 мин\n\
+             ✅ app_schedule: 12 мс, проверено 15 с назад\n\
+             Кэш: 87% попаданий",
+            render(&report)
+        );
+
+        report.build.commit = "unknown";
+        report.schedule_service_latency_ms = None;
+        report.cache_hit_percent = None;
+        assert_eq!(
+            "🏓 mpeix 3.1.1 (unknown), аптайм 2 ч 5 мин\n\
+             ❌ app_schedule недоступен, проверено 15 с назад\n\
+             Кэш: нет данных",
+            render(&report)
+        );
+    }
+
+    #[test]
+    fn test_changelog_digest() {
+        let digest = ChangelogDigest {
+            schedule_name: "А-08-19".to_owned(),
+            moved: 2,
+            added: 1,
+            removed: 0,
+            changed: 5,
+        };
+        let render = |locale| {
+            let mut buf = String::new();
+            render_changelog_digest(&digest, locale, &mut buf);
+            buf
+        };
+        assert_eq!(
+            "перенесены 2 пары, добавлена 1 пара, изменено 5 пар",
+            render(Locale::Ru)
+        );
+        assert_eq!(
+            "moved 2 classes, added 1 class, changed 5 classes",
+            render(Locale::En)
+        );
+    }
+
+    #[test]
+    fn test_week_ahead() {
+        let reply = Reply::WeekAhead(WeekAheadDigest {
+            schedule_name: "А-08-19".to_owned(),
+            days: vec![
+                WeekAheadDay {
+                    date: NaiveDate::from_ymd_opt(2023, 3, 20).unwrap(),
+                    classes_count: 3,
+                    earliest_start: "11:10:00".parse().unwrap(),
+                    labs: vec!["Физика".to_owned(), "Химия".to_owned()],
+                },
+                WeekAheadDay {
+                    date: NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+                    classes_count: 1,
+                    earliest_start: "09:20:00".parse().unwrap(),
+                    labs: vec![],
+                },
+            ],
+        });
+        let render = |locale| {
+            render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                locale,
+                ThemeKind::Default,
+            )
+        };
+        assert_eq!(
+            "🗓 Неделя впереди для А-08-19:\n\n\
+            📅 Понедельник, 20 марта — 3 пары с 11:10, лабы: Физика, Химия\n\
+            📅 Четверг, 23 марта — 1 пара с 09:20",
+            render(Locale::Ru)
+        );
+        assert_eq!(
+            "🗓 The week ahead for А-08-19:\n\n\
+            📅 Monday, March 20 — 3 classes from 11:10, labs: Физика, Химия\n\
+            📅 Thursday, March 23 — 1 class from 09:20",
+            render(Locale::En)
+        );
+    }
+
+    #[test]
+    fn test_data_export_document() {
+        let export = PeerDataExport {
+            exported_at: NaiveDate::from_ymd_opt(2026, 10, 16)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            platform: "telegram",
+            platform_peer_id: 1,
+            selected_schedule: None,
+            preferences: PeerPreferences {
+                locale: Some("ru".to_owned()),
+                map_provider: None,
+                days_range: "пн-сб".to_owned(),
+                commute_minutes: None,
+                privacy_mode: false,
+                achievements_enabled: false,
+                ics_enabled: false,
+                newcomer_mode: false,
+                theme: None,
+            },
+            subscriptions: vec!["weekly_changelog"],
+            recent_schedules: vec![],
+            achievements: None,
+            recent_replies: vec![],
+            member_schedules: vec![],
+            deleted_at: None,
+        };
+        let reply = Reply::DataExport(Box::new(export));
+        let document = render_document(&reply, &ReplyContext::default()).unwrap();
+        assert_eq!(document.file_name, "mpeix-data-2026-10-16.json");
+        let json: serde_json::Value = serde_json::from_slice(&document.content).unwrap();
+        assert_eq!(json["platform"], "telegram");
+        assert_eq!(json["exported_at"], "2026-10-16T12:00:00");
+        assert_eq!(json["subscriptions"][0], "weekly_changelog");
+    }
+}
@@ -0,0 +1,117 @@
+use std::{collections::HashMap, future::Future, hash::Hash, sync::Mutex};
+
+use anyhow::anyhow;
+use common_errors::errors::{CommonError, CommonErrorExt};
+use futures_util::{
+    future::{BoxFuture, Shared},
+    FutureExt,
+};
+
+type SharedRequest<V> = Shared<BoxFuture<'static, Result<V, CommonError>>>;
+
+/// Concurrent requests with the same key share one execution and its result.
+///
+/// E.g. when the whole group chat taps "эта неделя" at once, only the first tap
+/// makes the HTTP call, the rest ones wait for it. Nothing is cached: the key
+/// is forgotten as soon as the request is completed.
+pub(crate) struct RequestCoalescer<K, V> {
+    in_flight: Mutex<HashMap<K, SharedRequest<V>>>,
+}
+
+impl<K, V> Default for RequestCoalescer<K, V> {
+    fn default() -> Self {
+        Self {
+            in_flight: Default::default(),
+        }
+    }
+}
+
+impl<K, V> RequestCoalescer<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    /// Join the request in flight with the same `key`, or start a new one with `request`.
+    ///
+    /// Errors are shared as [CommonError], so the callers can still tell
+    /// "not found" from "gateway" errors.
+    pub(crate) async fn run<F>(&self, key: K, request: impl FnOnce() -> F) -> anyhow::Result<V>
+    where
+        F: Future<Output = anyhow::Result<V>> + Send + 'static,
+    {
+        let shared = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_insert_with(|| {
+                let request = request();
+                async move {
+                    request.await.map_err(|e| {
+                        e.as_common_error()
+                            .unwrap_or_else(|| CommonError::internal(format!("{e:#}")))
+                    })
+                }
+                .boxed()
+                .shared()
+            })
+            .clone();
+        let result = shared.clone().await;
+        {
+            // the key may already belong to the next request, started after this one completed
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.get(&key).is_some_and(|it| it.ptr_eq(&shared)) {
+                in_flight.remove(&key);
+            }
+        }
+        result.map_err(|e| anyhow!(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use common_errors::errors::{CommonError, CommonErrorExt};
+    use futures_util::future::join3;
+    use tokio::sync::oneshot;
+
+    use super::RequestCoalescer;
+
+    #[test]
+    fn concurrent_requests_share_one_execution() {
+        let coalescer = RequestCoalescer::<&str, String>::default();
+        let executions = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = oneshot::channel::<()>();
+        let request = || {
+            let executions = executions.clone();
+            async move {
+                executions.fetch_add(1, Ordering::SeqCst);
+                rx.await?;
+                Ok("А-08-19".to_owned())
+            }
+        };
+        let (first, second, _) = tokio_test::block_on(join3(
+            coalescer.run("А-08-19", request),
+            coalescer.run("А-08-19", || async {
+                unreachable!("joins the first request")
+            }),
+            async { tx.send(()).unwrap() },
+        ));
+        assert_eq!(first.unwrap(), "А-08-19");
+        assert_eq!(second.unwrap(), "А-08-19");
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+
+        // completed requests are not cached
+        let third = tokio_test::block_on(coalescer.run("А-08-19", || async {
+            Err(anyhow::anyhow!(CommonError::not_found("А-08-19")))
+        }));
+        assert!(matches!(
+            third.unwrap_err().as_common_error(),
+            Some(CommonError::NotFoundError(_))
+        ));
+    }
+}
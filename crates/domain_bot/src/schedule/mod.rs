@@ -1 +1,2 @@
+pub(crate) mod coalescing;
 pub mod repository;
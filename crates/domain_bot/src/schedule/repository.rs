@@ -1,24 +1,169 @@
+use anyhow::bail;
+use common_errors::errors::CommonError;
 use common_restix::ResultExt;
-use domain_schedule_models::{Schedule, ScheduleType};
+use domain_schedule_models::{
+    Schedule, ScheduleChangelog, ScheduleDiff, ScheduleFetchLatency, ScheduleType,
+    ScheduleVerification, SessionSchedule,
+};
+use reqwest::StatusCode;
 
-use crate::mpeix_api::MpeixApi;
+use crate::{
+    models::RenderedWeeks,
+    mpeix_api::{MpeixApi, MpeixUpstream},
+};
+
+use super::coalescing::RequestCoalescer;
 
 /// Repository for accessing `app_schedule` microservice schedules.
 ///
 /// We do not need caching or other complex logic here, because it
 /// is implemented on the side of the `app_schedule` microservice.
-pub struct ScheduleRepository(pub(crate) MpeixApi);
+/// Only concurrent identical schedule requests are coalesced into one,
+/// together with the rendered texts of their weeks.
+pub struct ScheduleRepository {
+    pub(crate) api: MpeixUpstream,
+    pub(crate) schedule_requests:
+        RequestCoalescer<(String, ScheduleType, i8), (Schedule, RenderedWeeks)>,
+}
 
 impl ScheduleRepository {
+    /// Get schedule from `app_schedule` microservice.
+    ///
+    /// Returns [CommonError::NotFoundError] if the schedule does not exist anymore
+    /// (for example, if the group was renamed).
     pub async fn get_schedule(
         &self,
         name: &str,
         r#type: &ScheduleType,
         offset: i8,
     ) -> anyhow::Result<Schedule> {
-        self.0
-            .schedule(r#type, name, offset as i32)
+        let (schedule, _) = self.get_schedule_with_renders(name, r#type, offset).await?;
+        Ok(schedule)
+    }
+
+    /// Same as [ScheduleRepository::get_schedule], but also returns the rendered texts
+    /// of the week, which are shared by the concurrent identical requests.
+    pub async fn get_schedule_with_renders(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+        offset: i8,
+    ) -> anyhow::Result<(Schedule, RenderedWeeks)> {
+        let api = match &self.api {
+            MpeixUpstream::Remote(api) => api.clone(),
+            MpeixUpstream::Mock(mock) => {
+                let schedule = mock.schedule(r#type, name, offset as i32);
+                return Ok((schedule, RenderedWeeks::default()));
+            }
+        };
+        let key = (name.to_owned(), r#type.to_owned(), offset);
+        self.schedule_requests
+            .run(key.clone(), || async move {
+                let (name, r#type, offset) = key;
+                let schedule = fetch_schedule(&api, &name, &r#type, offset).await?;
+                Ok((schedule, RenderedWeeks::default()))
+            })
             .await
-            .with_common_error()
     }
+
+    /// Get exams and consultations of the nearest exam session from `app_schedule` microservice
+    pub async fn get_session(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<SessionSchedule> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => Ok(api.session(r#type, name).await?),
+            MpeixUpstream::Mock(mock) => Ok(mock.session(r#type, name)),
+        }
+    }
+
+    /// Get changes of the schedule detected by `app_schedule` microservice during the last `days`
+    pub async fn get_changelog(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+        days: u32,
+    ) -> anyhow::Result<ScheduleChangelog> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => Ok(api.changes(r#type, name, days).await?),
+            MpeixUpstream::Mock(mock) => Ok(mock.changelog(r#type, name, days)),
+        }
+    }
+
+    /// Get changes of the week with offset `to` relative to the week with offset `from`
+    pub async fn get_diff(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+        from: i8,
+        to: i8,
+    ) -> anyhow::Result<ScheduleDiff> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => {
+                Ok(api.diff(r#type, name, from as i32, to as i32).await?)
+            }
+            MpeixUpstream::Mock(mock) => Ok(mock.diff(r#type, name, from as i32, to as i32)),
+        }
+    }
+
+    /// Compare the current week of the schedule from MPEI with the cached one
+    /// using the admin endpoint of `app_schedule` microservice.
+    pub async fn verify_schedule(
+        &self,
+        admin_secret: &str,
+        name: &str,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<ScheduleVerification> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => {
+                Ok(api.verify_schedule(admin_secret, r#type, name, 0).await?)
+            }
+            MpeixUpstream::Mock(mock) => Ok(mock.verify_schedule(r#type, name)),
+        }
+    }
+
+    /// Check that `app_schedule` microservice is alive
+    pub async fn check_health(&self) -> anyhow::Result<()> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => {
+                api.health().await?.error_for_status().with_common_error()?;
+                Ok(())
+            }
+            MpeixUpstream::Mock(_) => Ok(()),
+        }
+    }
+
+    /// Latency of the schedule requests of `app_schedule` microservice by the source
+    /// of the response, using the admin endpoint
+    pub async fn get_fetch_latency(
+        &self,
+        admin_secret: &str,
+    ) -> anyhow::Result<ScheduleFetchLatency> {
+        match &self.api {
+            MpeixUpstream::Remote(api) => Ok(api.latency(admin_secret).await?),
+            MpeixUpstream::Mock(mock) => Ok(mock.latency()),
+        }
+    }
+}
+
+async fn fetch_schedule(
+    api: &MpeixApi,
+    name: &str,
+    r#type: &ScheduleType,
+    offset: i8,
+) -> anyhow::Result<Schedule> {
+    let response = api.schedule(r#type, name, offset as i32).await?;
+    if response.status() == StatusCode::NOT_FOUND {
+        bail!(CommonError::not_found(format!(
+            "Schedule with type '{:?}' and name '{}' not found",
+            r#type, name
+        )));
+    }
+    response
+        .error_for_status()
+        .with_common_error()?
+        .json::<Schedule>()
+        .await
+        .with_common_error()
 }
@@ -1 +1,2 @@
 pub mod repository;
+pub(crate) mod similarity;
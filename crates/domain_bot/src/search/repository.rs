@@ -1,20 +1,68 @@
-use common_restix::ResultExt;
+use anyhow::Context;
 use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
 
-use crate::mpeix_api::MpeixApi;
+use crate::{mpeix_api::MpeixUpstream, search::similarity};
 
 /// Repository for accessing app_schedule microservice search results.
 ///
 /// We do not need caching or other complex logic here, because it
 /// is implemented on the side of the `app_schedule` microservice.
-pub struct ScheduleSearchRepository(pub(crate) MpeixApi);
+pub struct ScheduleSearchRepository(pub(crate) MpeixUpstream);
+
+/// Schedule name with its similarity to the requested name in range `0.0..=1.0`
+pub struct SimilarSchedule {
+    pub name: String,
+    pub similarity: f64,
+}
 
 impl ScheduleSearchRepository {
+    /// Names of the most requested groups during the last week,
+    /// suggested to the new users instead of typing the group name.
+    pub async fn get_trending_groups(&self, limit: usize) -> anyhow::Result<Vec<String>> {
+        let trending = match &self.0 {
+            MpeixUpstream::Remote(api) => {
+                api.trending("7d", Some(ScheduleType::Group), limit).await?
+            }
+            MpeixUpstream::Mock(mock) => mock.trending(Some(ScheduleType::Group), limit),
+        };
+        Ok(trending.items.into_iter().map(|it| it.name).collect())
+    }
+
     pub async fn search_schedule(
         &self,
         query: &str,
         r#type: Option<ScheduleType>,
     ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
-        self.0.search(query, r#type).await.with_common_error()
+        match &self.0 {
+            MpeixUpstream::Remote(api) => Ok(api.search(query, r#type).await?),
+            MpeixUpstream::Mock(mock) => Ok(mock.search(query, r#type)),
+        }
+    }
+
+    /// Search schedules which look like the renamed version of the schedule `name`.
+    ///
+    /// Results are sorted by similarity in descending order
+    /// and do not contain the schedule `name` itself.
+    pub async fn search_similar_schedules(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<Vec<SimilarSchedule>> {
+        let mut results = self
+            .search_schedule(
+                similarity::stable_name_prefix(name),
+                Some(r#type.to_owned()),
+            )
+            .await
+            .with_context(|| "Error while searching for similar schedules")?
+            .into_iter()
+            .filter(|it| it.name.to_lowercase() != name.to_lowercase())
+            .map(|it| SimilarSchedule {
+                similarity: similarity::similarity(name, &it.name),
+                name: it.name,
+            })
+            .collect::<Vec<SimilarSchedule>>();
+        results.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        Ok(results)
     }
 }
@@ -0,0 +1,69 @@
+/// Extract the part of the schedule name, which most likely
+/// survives the renaming of the schedule.
+///
+/// For persons it is the last name (`Иванов Иван Иванович` -> `Иванов`),
+/// for groups it is the name without the year of admission (`А-08-19` -> `А-08`).
+pub(crate) fn stable_name_prefix(name: &str) -> &str {
+    let name = name.trim();
+    if let Some((first_word, _)) = name.split_once(char::is_whitespace) {
+        first_word
+    } else if let Some((prefix, _)) = name.rsplit_once('-') {
+        prefix
+    } else {
+        name
+    }
+}
+
+/// Case-insensitive similarity of two strings in range `0.0..=1.0`,
+/// based on the Levenshtein distance.
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    let a = a.to_lowercase().chars().collect::<Vec<char>>();
+    let b = b.to_lowercase().chars().collect::<Vec<char>>();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein_distance(&a, &b) as f64 / max_len as f64
+}
+
+fn levenshtein_distance(a: &[char], b: &[char]) -> usize {
+    let mut prev_row = (0..=b.len()).collect::<Vec<usize>>();
+    let mut curr_row = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        curr_row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution_cost = if ca == cb { 0 } else { 1 };
+            curr_row[j + 1] = (prev_row[j] + substitution_cost)
+                .min(prev_row[j + 1] + 1)
+                .min(curr_row[j] + 1);
+        }
+        std::mem::swap(&mut prev_row, &mut curr_row);
+    }
+    prev_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{similarity, stable_name_prefix};
+
+    #[test]
+    fn test_stable_name_prefix() {
+        assert_eq!(stable_name_prefix("А-08-19"), "А-08");
+        assert_eq!(stable_name_prefix("Иванов Иван Иванович"), "Иванов");
+        assert_eq!(stable_name_prefix("Иванов"), "Иванов");
+    }
+
+    #[test]
+    fn test_similarity_bounds() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("А-08-19", "а-08-19"), 1.0);
+        assert_eq!(similarity("abc", "xyz"), 0.0);
+    }
+
+    #[test]
+    fn test_similarity_ordering() {
+        let renamed = similarity("А-08-19", "А-08м-19");
+        let other = similarity("А-08-19", "С-12-21");
+        assert!(renamed > other);
+    }
+}
@@ -0,0 +1,205 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// Set of emojis of the replies, chosen by the peer or by the bot config of the platform
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeKind {
+    #[default]
+    Default,
+    /// Plain text symbols for the clients which render emojis poorly
+    Minimal,
+    /// Bright emojis which are distinguishable without color perception
+    HighContrast,
+}
+
+impl AsRef<str> for ThemeKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Default => "default",
+            Self::Minimal => "minimal",
+            Self::HighContrast => "high_contrast",
+        }
+    }
+}
+
+impl FromStr for ThemeKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "default" => Ok(Self::Default),
+            "minimal" => Ok(Self::Minimal),
+            "high_contrast" => Ok(Self::HighContrast),
+            _ => bail!("Unknown theme: '{s}'"),
+        }
+    }
+}
+
+/// Icons of the rendered replies, so the replies can be restyled without touching the renderer
+pub(crate) struct Theme {
+    pub list_item: &'static str,
+    pub verification: &'static str,
+    pub ok: &'static str,
+    pub failure: &'static str,
+    pub attention: &'static str,
+    pub week_changes: &'static str,
+    pub notification: &'static str,
+    pub added: &'static str,
+    pub removed: &'static str,
+    pub changed: &'static str,
+    pub achievements: &'static str,
+    pub current_streak: &'static str,
+    pub best_streak: &'static str,
+    pub total_checks: &'static str,
+    pub ping: &'static str,
+    pub early_bird: &'static str,
+    pub week_streak: &'static str,
+    pub month_streak: &'static str,
+    pub centurion: &'static str,
+    pub month: &'static str,
+    /// Days of the previous month in the first row of the month grid
+    pub month_padding: &'static str,
+    pub study_day: &'static str,
+    pub exam_day: &'static str,
+    pub holiday: &'static str,
+    pub day_off: &'static str,
+    pub session_warning: &'static str,
+    pub room_change: &'static str,
+    /// Goes after the text, so it may be empty
+    pub no_classes: &'static str,
+    pub day: &'static str,
+    pub note: &'static str,
+    pub cancelled: &'static str,
+    pub moved: &'static str,
+    pub groups: &'static str,
+    pub person: &'static str,
+    pub place: &'static str,
+    pub map: &'static str,
+    pub time: &'static str,
+    pub directions: &'static str,
+    /// Numbers of the classes from 1 to 9
+    pub numbers: [&'static str; 9],
+    pub unknown_number: &'static str,
+}
+
+impl Theme {
+    pub(crate) const DEFAULT: Theme = Theme {
+        list_item: "🔸",
+        verification: "🔍",
+        ok: "✅",
+        failure: "❌",
+        attention: "❗",
+        week_changes: "🔄",
+        notification: "🔔",
+        added: "➕",
+        removed: "➖",
+        changed: "✏️",
+        achievements: "🏆",
+        current_streak: "🔥",
+        best_streak: "⭐",
+        total_checks: "👀",
+        ping: "🏓",
+        early_bird: "🐦",
+        week_streak: "📅",
+        month_streak: "🗓",
+        centurion: "💯",
+        month: "🗓",
+        month_padding: "➖",
+        study_day: "🟦",
+        exam_day: "🟥",
+        holiday: "🟩",
+        day_off: "⬜",
+        session_warning: "⚠️",
+        room_change: "⚠️",
+        no_classes: "🤷",
+        day: "📅",
+        note: "📌",
+        cancelled: "❌",
+        moved: "🔀",
+        groups: "🎓",
+        person: "👨‍🏫",
+        place: "🚪",
+        map: "🗺",
+        time: "🕖",
+        directions: "🧭",
+        numbers: ["1️⃣", "2️⃣", "3️⃣", "4️⃣", "5️⃣", "6️⃣", "7️⃣", "8️⃣", "9️⃣"],
+        unknown_number: "🟢",
+    };
+
+    pub(crate) const MINIMAL: Theme = Theme {
+        list_item: "•",
+        verification: "»",
+        ok: "✓",
+        failure: "×",
+        attention: "!",
+        week_changes: "»",
+        notification: "»",
+        added: "+",
+        removed: "−",
+        changed: "~",
+        achievements: "»",
+        current_streak: "•",
+        best_streak: "•",
+        total_checks: "•",
+        ping: "»",
+        early_bird: "•",
+        week_streak: "•",
+        month_streak: "•",
+        centurion: "•",
+        month: "»",
+        month_padding: "·",
+        study_day: "●",
+        exam_day: "▲",
+        holiday: "○",
+        day_off: "◦",
+        session_warning: "!",
+        room_change: "!",
+        no_classes: "",
+        day: "»",
+        note: "*",
+        cancelled: "×",
+        moved: "→",
+        groups: "•",
+        person: "•",
+        place: "•",
+        map: "•",
+        time: "•",
+        directions: "»",
+        numbers: ["1.", "2.", "3.", "4.", "5.", "6.", "7.", "8.", "9."],
+        unknown_number: "•",
+    };
+
+    pub(crate) const HIGH_CONTRAST: Theme = Theme {
+        list_item: "▶️",
+        attention: "‼️",
+        ok: "✔️",
+        session_warning: "🚨",
+        room_change: "🚨",
+        study_day: "⬛",
+        exam_day: "🟥",
+        holiday: "🟨",
+        day_off: "⬜",
+        month_padding: "▫️",
+        cancelled: "⛔",
+        moved: "↪️",
+        unknown_number: "⏺",
+        ..Theme::DEFAULT
+    };
+
+    pub(crate) fn of(kind: ThemeKind) -> &'static Theme {
+        match kind {
+            ThemeKind::Default => &Theme::DEFAULT,
+            ThemeKind::Minimal => &Theme::MINIMAL,
+            ThemeKind::HighContrast => &Theme::HIGH_CONTRAST,
+        }
+    }
+
+    /// Icon of the classes number, e.g. `1️⃣`
+    pub(crate) fn number(&self, num: i8) -> &'static str {
+        match num {
+            1..=9 => self.numbers[num as usize - 1],
+            _ => self.unknown_number,
+        }
+    }
+}
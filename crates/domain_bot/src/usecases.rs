@@ -1,34 +1,119 @@
-use std::{cmp::Ordering, sync::Arc};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap},
+    future::Future,
+    marker::PhantomData,
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, Context};
-use chrono::{Datelike, Days, Local};
-use common_errors::errors::CommonError;
-use domain_schedule_models::{Classes, Day, ScheduleType};
+use async_trait::async_trait;
+use chrono::{
+    Datelike, Days, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
+use common_errors::errors::{CommonError, CommonErrorExt};
+use common_rust::{env, Reloadable};
+use domain_buildings::MapProvider;
+use domain_schedule_models::{
+    Classes, ClassesChange, ClassesChangeKind, ClassesStatus, ClassesType, Day, DaysRange,
+    PeriodType, Schedule, ScheduleFetchLatency, ScheduleSearchResult, ScheduleType, Week,
+};
+use futures_util::future::try_join_all;
 use lazy_static::lazy_static;
-use regex::Regex;
+use log::{error, info, warn};
+use regex::{Regex, RegexSet};
 
 use crate::{
-    models::{Peer, Reply, TimePrediction, UpcomingEventsPrediction, UserAction},
-    peer::repository::{PeerRepository, PlatformId},
+    achievements::repository::AchievementsRepository,
+    activity::repository::PeerActivityRepository,
+    audit::repository::AuditRepository,
+    blocklist::{
+        rate_limiter::{RateDecision, RateLimiter},
+        repository::BlocklistRepository,
+    },
+    dates::{parse_date_expression, parse_date_range},
+    locale::detect_locale,
+    members::repository::MemberSchedulesRepository,
+    models::{
+        AchievementStats, Badge, BlockPattern, BuildInfo, ChangelogDigest, ChatMember, ChatNote,
+        HealthReport, Locale, MemberMention, MemberScheduleExport, MonthDayKind, MonthOverview,
+        NoteRejection, Peer, PeerDataExport, PeerMigrationReport, PeerPreferences, PendingReply,
+        ReEngagementStats, RecentSchedule, RenderedWeeks, Reply, ReplyAuditEntry, ReplyContext,
+        RoomChangeWarning, ScheduleMigration, TimePrediction, UnresolvedSchedule,
+        UpcomingEventsPrediction, UserAction, WeekAheadDay, WeekAheadDigest,
+    },
+    notes::{repository::NotesRepository, ChatAdminChecker},
+    peer::repository::{PeerRepository, PlatformId, PushOptIn},
+    queue::repository::ReplyQueueRepository,
+    recent::repository::RecentSchedulesRepository,
     schedule::repository::ScheduleRepository,
     search::repository::ScheduleSearchRepository,
+    theme::ThemeKind,
+    warnings::repository::RoomChangeWarningsRepository,
 };
 
 /// Create databases if needed and run migrations.
-/// This use case must be started **STRICTLY** before the server starts.
-pub struct InitDomainBotUseCase(pub(crate) Arc<PeerRepository>);
+/// This use case must be complete **STRICTLY** before the app becomes ready.
+pub struct InitDomainBotUseCase(
+    pub(crate) Arc<dyn PeerRepository>,
+    pub(crate) Arc<dyn AchievementsRepository>,
+    pub(crate) Arc<dyn AuditRepository>,
+    pub(crate) Arc<dyn RecentSchedulesRepository>,
+    pub(crate) Arc<dyn NotesRepository>,
+    pub(crate) Arc<ReplyQueueRepository>,
+    pub(crate) Arc<dyn MemberSchedulesRepository>,
+    pub(crate) Arc<dyn PeerActivityRepository>,
+    pub(crate) Arc<dyn BlocklistRepository>,
+);
 
 impl InitDomainBotUseCase {
     pub async fn init(&self) -> anyhow::Result<()> {
-        self.0.init_peer_tables().await
+        self.0.init_peer_tables().await?;
+        self.1.init_achievements_tables().await?;
+        self.2.init_audit_tables().await?;
+        self.3.init_recent_schedules_tables().await?;
+        self.4.init_notes_tables().await?;
+        self.5.init_reply_queue_tables().await?;
+        self.6.init_member_schedules_tables().await?;
+        self.7.init_peer_activity_tables().await?;
+        self.8.init_blocklist_tables().await
     }
 }
 
 /// Determine [UserAction] from text sent by user
 pub struct TextToActionUseCase;
 
+/// Max commute time for the evening summary, longer values are most likely typos
+const MAX_COMMUTE_MINUTES: u16 = 180;
+/// Search results beyond this number are too irrelevant for the keyboard
+const MAX_SCHEDULE_SEARCH_RESULTS: usize = 6;
+
 lazy_static! {
     static ref MENTIONS_PATTERN: Regex = Regex::new(r"(\[.*\],?)|(@\w+,?)").unwrap();
+    static ref VERIFY_PATTERN: Regex = Regex::new(r"^/verify[\s_]+(\S.*)$").unwrap();
+    static ref NOTE_PATTERN: Regex = Regex::new(r"(?is)^(?:/note|note|заметка)\s+(\S.*)$").unwrap();
+    static ref REMOVE_NOTES_PATTERN: Regex =
+        Regex::new(r"^(?:/note_remove|remove notes|удалить заметки)\s+(\S.*)$").unwrap();
+    static ref MY_GROUP_PATTERN: Regex =
+        Regex::new(r"^(?:/mygroup|my group|моя группа)\s+(\S.*)$").unwrap();
+    static ref BLOCK_PATTERN: Regex = Regex::new(r"^/(block|unblock)[\s_]+(\S+)$").unwrap();
+    /// Mentions are matched in the original text, because they are cleared before the commands.
+    /// Mentions of the bot itself (VK) may precede the command.
+    static ref MEMBER_SCHEDULE_PATTERN: Regex = Regex::new(
+        r"(?i)^(?:\[club\d+\|[^\]]*\],?\s*)*(?:пары|расписание|schedule|/schedule(?:@\w+)?)\s+(?:@(\w+)|\[id(\d+)\|[^\]]*\]),?(?:\s+(сегодня|завтра|today|tomorrow))?$"
+    )
+    .unwrap();
+    static ref COMMUTE_PATTERN: Regex =
+        Regex::new(r"^(?:/commute|commute|дорога)[\s_]+(\d{1,3})(?:\s*(?:мин|min))?$").unwrap();
+    /// Search filter of the room schedules, like "ауд. а-300"
+    static ref ROOM_SEARCH_PATTERN: Regex =
+        Regex::new(r"^(?:ауд(?:итория)?(?:\.\s*|\s+)|room\s+)(\S.*)$").unwrap();
+    static ref NAVIGATION_PATTERN: Regex = Regex::new(
+        r"^(?:/where|where is|how to get to|как пройти|как добраться|как найти|где находится|где)\s+(?:(?:в|до|к|to)\s+)?([a-zа-яё]{1,2}-\S+?)\??$"
+    )
+    .unwrap();
     static ref DAY_OF_WEEK_MAP: Vec<(i8, Vec<&'static str>)> = vec![
         (1, vec!["пн", "понедельник", "mon", "monday"]),
         (2, vec!["вт", "вторник", "tue", "tuesday"]),
@@ -36,6 +121,7 @@ lazy_static! {
         (4, vec!["чт", "четверг", "thu", "thursday"]),
         (5, vec!["пт", "пятница", "fri", "friday"]),
         (6, vec!["сб", "суббота", "sat", "saturday"]),
+        (7, vec!["вс", "воскресенье", "sun", "sunday"]),
     ];
     static ref DAY_OF_WEEK_PATTERN: Regex = create_multipattern(
         r"(пар[ыау]\s+)?((в|во)\s+)?",
@@ -44,50 +130,290 @@ lazy_static! {
             .flat_map(|(_, v)| v)
             .map(|x| x.to_lowercase())
             .collect::<Vec<String>>(),
-        |a, b| format!("^({a}{b})$")
+        |a, b| format!("^({a}({b}))$")
     );
     static ref REL_DAY_PTR_MAP: Vec<(i8, Vec<&'static str>)> = vec![
         (2, vec!["послезавтра", "послезавтрашние", "послезавтрашний"]),
         (-2, vec!["позавчера", "позавчерашние", "позавчерашний"]),
-        (0, vec!["сегодня", "сегодняшние", "сегодняшний", "/today"]),
-        (-1, vec!["вчера", "вчерашние", "вчерашний", "/yesterday"]),
-        (1, vec!["завтра", "завтрашние", "завтрашний", "/tomorrow"]),
+        (
+            0,
+            vec!["сегодня", "сегодняшние", "сегодняшний", "today", "/today"]
+        ),
+        (
+            -1,
+            vec!["вчера", "вчерашние", "вчерашний", "yesterday", "/yesterday"]
+        ),
+        (
+            1,
+            vec![
+                "завтра",
+                "завтрашние",
+                "завтрашний",
+                "tomorrow",
+                "/tomorrow"
+            ]
+        ),
     ];
-    static ref REL_DAY_PTR_PATTERN: Regex = create_multipattern(
-        r#"(пар[ыау])?(день)?"#,
-        &REL_DAY_PTR_MAP
-            .iter()
-            .flat_map(|(_, v)| v)
-            .map(|x| x.to_lowercase())
-            .collect::<Vec<String>>(),
-        |a, b| format!(r#"(({a}\s+)?{b})|({b}(\s+{a})?)"#)
-    );
+    static ref DAY_OF_WEEK_KEYWORDS: HashMap<&'static str, i8> = DAY_OF_WEEK_MAP
+        .iter()
+        .flat_map(|(day, v)| v.iter().map(|it| (*it, *day)))
+        .collect();
+    /// All patterns of the commands with arguments, matched in one pass over the message.
+    /// Relative day pointers may be anywhere in the message, so each of them is a pattern too,
+    /// in the order of [REL_DAY_PTR_MAP].
+    static ref ARGUMENT_PATTERNS: RegexSet = RegexSet::new(
+        [
+            VERIFY_PATTERN.as_str(),
+            REMOVE_NOTES_PATTERN.as_str(),
+            COMMUTE_PATTERN.as_str(),
+            NAVIGATION_PATTERN.as_str(),
+            MY_GROUP_PATTERN.as_str(),
+            BLOCK_PATTERN.as_str(),
+            DAY_OF_WEEK_PATTERN.as_str(),
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .chain(
+            REL_DAY_PTR_MAP
+                .iter()
+                .flat_map(|(_, v)| v)
+                .map(|it| regex::escape(it))
+        )
+    )
+    .unwrap();
+    /// Offsets of the relative day pointers, by the index of the pattern in [ARGUMENT_PATTERNS]
+    static ref REL_DAY_PTR_OFFSETS: Vec<i8> = REL_DAY_PTR_MAP
+        .iter()
+        .flat_map(|(offset, v)| v.iter().map(|_| *offset))
+        .collect();
 }
 
+/// Indices of the patterns in [ARGUMENT_PATTERNS]
+const VERIFY_INDEX: usize = 0;
+const REMOVE_NOTES_INDEX: usize = 1;
+const COMMUTE_INDEX: usize = 2;
+const NAVIGATION_INDEX: usize = 3;
+const MY_GROUP_INDEX: usize = 4;
+const BLOCK_INDEX: usize = 5;
+const DAY_OF_WEEK_INDEX: usize = 6;
+const REL_DAY_PTR_FIRST_INDEX: usize = 7;
+
 impl TextToActionUseCase {
     pub fn text_to_action(&self, text: &str) -> anyhow::Result<UserAction> {
-        let cleared_text = MENTIONS_PATTERN.replace_all(text, "").trim().to_lowercase();
+        if let Some(action) = parse_member_schedule(text.trim()) {
+            return Ok(action);
+        }
+        let cleared_text = MENTIONS_PATTERN.replace_all(text, "").trim().to_owned();
+        // notes are the only user input, which is saved as is
+        if let Some((date, text)) = NOTE_PATTERN
+            .captures(&cleared_text)
+            .and_then(|it| parse_note(&it[1], Local::now().date_naive()))
+        {
+            return Ok(UserAction::AddNote { date, text });
+        }
+        let cleared_text = cleared_text.to_lowercase();
         match cleared_text.as_str() {
             "старт" | "начать" | "start" | "/start" => Ok(UserAction::Start),
             "статус" | "ближайшие пары" | "ближайшие" | "status" | "/status" => {
                 Ok(UserAction::UpcomingEvents)
             }
+            "сколько пар осталось"
+            | "сколько пар осталось сегодня"
+            | "сколько осталось пар"
+            | "сколько осталось"
+            | "remaining"
+            | "/remaining" => Ok(UserAction::RemainingToday),
             "помощь" | "справка" | "помоги" | "help" | "/help" => {
                 Ok(UserAction::Help)
             }
             "сменить" | "сменить группу" | "сменить расписание" | "change" | "/change" => {
                 Ok(UserAction::ChangeScheduleIntent)
             }
-            "неделя" | "эта неделя" | "/thisweek" => {
+            "недавние" | "недавние расписания" | "последние расписания" | "recent" | "/recent" => {
+                Ok(UserAction::RecentSchedules)
+            }
+            "неделя" | "эта неделя" | "this week" | "/thisweek" => {
                 Ok(UserAction::WeekWithOffset(0))
             }
-            "следующая неделя" | "/nextweek" => Ok(UserAction::WeekWithOffset(1)),
-            "прошлая неделя" | "/prevweek" => Ok(UserAction::WeekWithOffset(-1)),
+            "следующая неделя" | "next week" | "/nextweek" => {
+                Ok(UserAction::WeekWithOffset(1))
+            }
+            "прошлая неделя" | "previous week" | "/prevweek" => {
+                Ok(UserAction::WeekWithOffset(-1))
+            }
+            "показать полностью" | "show in full" | "/thisweek_full" => {
+                Ok(UserAction::FullWeekWithOffset(0))
+            }
+            "показать полностью следующую неделю" | "show next week in full" | "/nextweek_full" => {
+                Ok(UserAction::FullWeekWithOffset(1))
+            }
+            "показать полностью прошлую неделю"
+            | "show previous week in full"
+            | "/prevweek_full" => Ok(UserAction::FullWeekWithOffset(-1)),
+            "месяц" | "этот месяц" | "month" | "this month" | "/month" => {
+                Ok(UserAction::MonthWithOffset(0))
+            }
+            "следующий месяц" | "next month" | "/nextmonth" => {
+                Ok(UserAction::MonthWithOffset(1))
+            }
+            "что меняется на следующей неделе"
+            | "изменения на следующей неделе"
+            | "next week changes"
+            | "/nextweek_changes" => Ok(UserAction::NextWeekChanges),
+            "экзамены" | "сессия" | "расписание сессии" | "exams" | "/exams" => {
+                Ok(UserAction::Exams)
+            }
+            "карты яндекс" | "/maps_yandex" => {
+                Ok(UserAction::SetMapProvider(Some(MapProvider::Yandex)))
+            }
+            "карты гугл" | "карты google" | "/maps_google" => {
+                Ok(UserAction::SetMapProvider(Some(MapProvider::Google)))
+            }
+            "карты выкл" | "/maps_off" => Ok(UserAction::SetMapProvider(None)),
+            "дни пн-сб" | "без воскресенья" | "/days_mon_sat" => {
+                Ok(UserAction::SetDaysRange(DaysRange {
+                    first: Weekday::Mon,
+                    last: Weekday::Sat,
+                }))
+            }
+            "дни пн-вс" | "все дни" | "/days_all" => {
+                Ok(UserAction::SetDaysRange(DaysRange::FULL_WEEK))
+            }
+            "достижения" | "ачивки" | "/achievements" => {
+                Ok(UserAction::Achievements)
+            }
+            "достижения вкл" | "/achievements_on" => {
+                Ok(UserAction::SetAchievementsEnabled(true))
+            }
+            "достижения выкл" | "/achievements_off" => {
+                Ok(UserAction::SetAchievementsEnabled(false))
+            }
+            "дорога выкл" | "commute off" | "/commute_off" => {
+                Ok(UserAction::SetCommute(None))
+            }
+            "приватность" | "privacy" | "/privacy" | "/privacy_on" => {
+                Ok(UserAction::SetPrivacyMode(true))
+            }
+            "приватность выкл" | "privacy off" | "/privacy_off" => {
+                Ok(UserAction::SetPrivacyMode(false))
+            }
+            "изменения вкл" | "changelog on" | "/changelog_on" => {
+                Ok(UserAction::SetChangelogEnabled(true))
+            }
+            "изменения выкл" | "changelog off" | "/changelog_off" => {
+                Ok(UserAction::SetChangelogEnabled(false))
+            }
+            "неделя вперед вкл" | "неделя вперёд вкл" | "week ahead on" | "/week_ahead_on" => {
+                Ok(UserAction::SetWeekAheadEnabled(true))
+            }
+            "неделя вперед выкл" | "неделя вперёд выкл" | "week ahead off" | "/week_ahead_off" => {
+                Ok(UserAction::SetWeekAheadEnabled(false))
+            }
+            "дайджест вкл" | "digest on" | "/digest_on" => Ok(UserAction::EnableDigest),
+            "дайджест выкл" | "digest off" | "/digest_off" => {
+                Ok(UserAction::DisableDigest)
+            }
+            "подписаться" | "subscribe" | "/subscribe" => Ok(UserAction::Subscribe),
+            "отписаться" | "unsubscribe" | "/unsubscribe" => Ok(UserAction::Unsubscribe),
+            "моя группа выкл" | "my group off" | "/mygroup_off" => {
+                Ok(UserAction::UnlinkMemberSchedule)
+            }
+            "календарь вкл" | "ics on" | "/ics_on" => {
+                Ok(UserAction::SetIcsEnabled(true))
+            }
+            "календарь выкл" | "ics off" | "/ics_off" => {
+                Ok(UserAction::SetIcsEnabled(false))
+            }
+            "экспорт" | "экспорт данных" | "export" | "export my data" | "/export" => {
+                Ok(UserAction::ExportData)
+            }
+            "удалить мои данные" | "delete my data" | "/deletemydata" => {
+                Ok(UserAction::DeleteData)
+            }
+            "восстановить данные" | "restore my data" | "/restore" => {
+                Ok(UserAction::RestoreData)
+            }
+            "новичок вкл" | "newcomer on" | "/newcomer_on" => {
+                Ok(UserAction::SetNewcomerMode(true))
+            }
+            "новичок выкл" | "newcomer off" | "/newcomer_off" => {
+                Ok(UserAction::SetNewcomerMode(false))
+            }
+            "тема стандартная" | "theme default" | "/theme_default" => {
+                Ok(UserAction::SetTheme(ThemeKind::Default))
+            }
+            "тема минимальная" | "theme minimal" | "/theme_minimal" => {
+                Ok(UserAction::SetTheme(ThemeKind::Minimal))
+            }
+            "тема контрастная" | "theme high contrast" | "/theme_contrast" => {
+                Ok(UserAction::SetTheme(ThemeKind::HighContrast))
+            }
+            "пинг" | "ping" | "/ping" => Ok(UserAction::Ping),
+            "/blocklist" => Ok(UserAction::Blocklist),
+            "язык" | "language" | "/language" => Ok(UserAction::SetLocale(None)),
+            "язык русский"
+            | "language russian"
+            | "language ru"
+            | "/language ru"
+            | "/language_ru" => Ok(UserAction::SetLocale(Some(Locale::Ru))),
+            "язык английский"
+            | "language english"
+            | "language en"
+            | "/language en"
+            | "/language_en" => Ok(UserAction::SetLocale(Some(Locale::En))),
             cleared_text => {
-                if DAY_OF_WEEK_PATTERN.is_match(cleared_text) {
-                    let (requested_day_of_week, _) = DAY_OF_WEEK_MAP
-                        .iter()
-                        .find(|(_, v)| v.iter().any(|it| cleared_text.contains(it)))
+                // the captures are extracted only from the patterns which are known to match
+                let matches = ARGUMENT_PATTERNS.matches(cleared_text);
+                if matches.matched(VERIFY_INDEX) {
+                    let captures = VERIFY_PATTERN.captures(cleared_text).ok_or_else(|| {
+                        CommonError::internal("Error: text matches pattern set but not pattern")
+                    })?;
+                    Ok(UserAction::VerifySchedule(captures[1].to_owned()))
+                } else if let Some(date) = matches
+                    .matched(REMOVE_NOTES_INDEX)
+                    .then(|| REMOVE_NOTES_PATTERN.captures(cleared_text))
+                    .flatten()
+                    .and_then(|it| parse_note_date(&it[1], Local::now().date_naive()))
+                {
+                    Ok(UserAction::RemoveNotes(date))
+                } else if let Some(minutes) = matches
+                    .matched(COMMUTE_INDEX)
+                    .then(|| COMMUTE_PATTERN.captures(cleared_text))
+                    .flatten()
+                    .and_then(|it| it[1].parse::<u16>().ok())
+                    .filter(|it| *it <= MAX_COMMUTE_MINUTES)
+                {
+                    Ok(UserAction::SetCommute(Some(minutes)))
+                } else if let Some(captures) = matches
+                    .matched(NAVIGATION_INDEX)
+                    .then(|| NAVIGATION_PATTERN.captures(cleared_text))
+                    .flatten()
+                {
+                    Ok(UserAction::Navigation(normalize_room(&captures[1])))
+                } else if let Some(captures) = matches
+                    .matched(MY_GROUP_INDEX)
+                    .then(|| MY_GROUP_PATTERN.captures(cleared_text))
+                    .flatten()
+                {
+                    Ok(UserAction::LinkMemberSchedule(
+                        captures[1].trim().to_owned(),
+                    ))
+                } else if let Some((command, pattern)) = matches
+                    .matched(BLOCK_INDEX)
+                    .then(|| BLOCK_PATTERN.captures(cleared_text))
+                    .flatten()
+                    .and_then(|it| Some((it[1].to_owned(), it[2].parse::<BlockPattern>().ok()?)))
+                {
+                    match command.as_str() {
+                        "block" => Ok(UserAction::Block(pattern)),
+                        _ => Ok(UserAction::Unblock(pattern)),
+                    }
+                } else if matches.matched(DAY_OF_WEEK_INDEX) {
+                    // the pattern is anchored, so the day of week is always the last word
+                    let requested_day_of_week = cleared_text
+                        .split_whitespace()
+                        .last()
+                        .and_then(|it| DAY_OF_WEEK_KEYWORDS.get(it))
                         .ok_or_else(|| {
                             CommonError::internal(
                                 "Error: text present in pattern but absent in map (day of week)",
@@ -103,16 +429,21 @@ impl TextToActionUseCase {
                         }
                     };
                     Ok(UserAction::DayWithOffset(day_offset))
-                } else if REL_DAY_PTR_PATTERN.is_match(cleared_text) {
-                    let (requested_day_offset, _) = REL_DAY_PTR_MAP
-                        .iter()
-                        .find(|(_, v)| v.iter().any(|it| cleared_text.contains(it)))
-                        .ok_or_else(|| {
-                            CommonError::internal(
-                                "Error: text present in pattern but absent in map (rel day ptr)",
-                            )
-                        })?;
-                    Ok(UserAction::DayWithOffset(*requested_day_offset))
+                } else if let Some((from, to)) =
+                    // before the day pointers, the range may contain them, like "с сегодня по пятницу"
+                    parse_date_range(cleared_text, Local::now().date_naive())
+                {
+                    Ok(UserAction::DayRange { from, to })
+                } else if let Some(index) = matches.iter().find(|it| *it >= REL_DAY_PTR_FIRST_INDEX)
+                {
+                    // the first pointer in the order of the map wins, like "послезавтра" over "завтра"
+                    Ok(UserAction::DayWithOffset(
+                        REL_DAY_PTR_OFFSETS[index - REL_DAY_PTR_FIRST_INDEX],
+                    ))
+                } else if let Some(date) =
+                    parse_date_expression(cleared_text, Local::now().date_naive())
+                {
+                    Ok(UserAction::SpecificDate(date))
                 } else {
                     Ok(UserAction::Unknown(cleared_text.to_owned()))
                 }
@@ -121,6 +452,68 @@ impl TextToActionUseCase {
     }
 }
 
+/// Parse the request of the chat member schedule like `"пары @username завтра"`
+fn parse_member_schedule(text: &str) -> Option<UserAction> {
+    let captures = MEMBER_SCHEDULE_PATTERN.captures(text)?;
+    let member = match (captures.get(1), captures.get(2)) {
+        (Some(username), _) => MemberMention::Username(username.as_str().to_lowercase()),
+        (None, Some(id)) => MemberMention::Id(id.as_str().parse().ok()?),
+        (None, None) => return None,
+    };
+    let day_offset = match captures.get(3).map(|it| it.as_str().to_lowercase()) {
+        Some(day) if day == "завтра" || day == "tomorrow" => 1,
+        _ => 0,
+    };
+    Some(UserAction::MemberSchedule { member, day_offset })
+}
+
+/// Render `Name — position, каф. department` line if the person metadata is known
+fn render_person_details(result: &ScheduleSearchResult) -> Option<String> {
+    let details = match (&result.position, &result.department) {
+        (Some(position), Some(department)) => format!("{position}, каф. {department}"),
+        (Some(position), None) => position.to_owned(),
+        (None, Some(department)) => format!("каф. {department}"),
+        (None, None) => return None,
+    };
+    Some(format!("{} — {details}", result.name))
+}
+
+/// Text of the search result button, which is sent back as is when the button is tapped
+fn search_result_label(result: &ScheduleSearchResult) -> String {
+    match result.r#type {
+        ScheduleType::Room => format!("ауд. {}", result.name),
+        _ => result.name.to_owned(),
+    }
+}
+
+/// Restore the case of the building code after the lowercasing: `к-120а` becomes `К-120а`
+fn normalize_room(room: &str) -> String {
+    match room.split_once('-') {
+        Some((code, number)) => format!("{}-{number}", code.to_uppercase()),
+        None => room.to_owned(),
+    }
+}
+
+/// Split the note like `"15.03 контрольная по физике"` into the date and the text.
+/// The date is the longest prefix of the note, which can be parsed as a date.
+fn parse_note(note: &str, today: NaiveDate) -> Option<(NaiveDate, String)> {
+    let words = note.split_whitespace().collect::<Vec<_>>();
+    (1..words.len().min(5)).rev().find_map(|n| {
+        let date = parse_note_date(&words[..n].join(" "), today)?;
+        Some((date, words[n..].join(" ")))
+    })
+}
+
+fn parse_note_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+    match text.as_str() {
+        "сегодня" | "today" => Some(today),
+        "завтра" | "tomorrow" => today.checked_add_days(Days::new(1)),
+        "послезавтра" => today.checked_add_days(Days::new(2)),
+        text => parse_date_expression(text, today),
+    }
+}
+
 fn create_multipattern<F: FnOnce(&str, &str) -> String>(
     prefix_pattern: &str,
     variants: &[String],
@@ -138,23 +531,151 @@ fn create_multipattern<F: FnOnce(&str, &str) -> String>(
 /// side of the `app_schedule` microservice.
 pub struct GenerateReplyUseCase(
     pub(crate) Arc<TextToActionUseCase>,
-    pub(crate) Arc<PeerRepository>,
+    pub(crate) Arc<dyn PeerRepository>,
     pub(crate) Arc<ScheduleRepository>,
     pub(crate) Arc<ScheduleSearchRepository>,
     pub(crate) Arc<GetUpcomingEventsUseCase>,
+    pub(crate) Arc<AchievementsUseCase>,
+    pub(crate) Arc<dyn RecentSchedulesRepository>,
+    pub(crate) Arc<VerifyScheduleUseCase>,
+    pub(crate) Arc<NotesUseCase>,
+    pub(crate) Arc<CommandKillSwitchUseCase>,
+    pub(crate) Arc<ExportPeerDataUseCase>,
+    pub(crate) Arc<GetMonthOverviewUseCase>,
+    pub(crate) Arc<PingUseCase>,
+    pub(crate) Arc<MemberSchedulesUseCase>,
+    pub(crate) Arc<ReEngagementUseCase>,
+    pub(crate) Arc<RoomChangeWarningsRepository>,
+    pub(crate) Arc<AbuseProtectionUseCase>,
+    pub(crate) Arc<PeerDeletionUseCase>,
 );
 
 impl GenerateReplyUseCase {
-    /// Generate [Reply] model from user request for further text reply rendering.
+    /// Generate [Reply] model from user request for further text reply rendering
+    /// in the peer's [Locale]. The [ReplyContext] also tells if the reply can be audited.
+    ///
+    /// `sender` is the user, who sent the message to the chat.
     pub async fn generate_reply(
         &self,
         platform_id: PlatformId,
+        sender: Option<ChatMember>,
         text: &str,
-    ) -> anyhow::Result<Reply> {
+    ) -> anyhow::Result<(Reply, ReplyContext)> {
         let action = self.0.text_to_action(text)?;
-        let peer = self.1.get_peer_by_platform_id(platform_id).await?;
+        let mut peer = self.1.get_peer_by_platform_id(platform_id.clone()).await?;
+        // deleted peers are excluded from all features until they restore the data,
+        // nothing about them is recorded or audited
+        if let Some(deleted_at) = peer.deleted_at {
+            let context = ReplyContext {
+                privacy_mode: true,
+                ..ReplyContext::from(&peer)
+            };
+            let reply = match action {
+                UserAction::RestoreData => self.17.restore(&peer).await?,
+                UserAction::DeleteData => self.17.delete(&peer).await?,
+                _ => self.17.pending_deletion(deleted_at),
+            };
+            return Ok((reply, context));
+        }
+        self.14.record_activity_if_needed(&peer).await;
+        // the language is detected only once, after that it can be changed with "/language"
+        if peer.locale.is_none() {
+            if let Some(locale) = detect_locale(text) {
+                peer.locale = Some(locale);
+                self.1.save_peer(peer.clone()).await?;
+            }
+        }
+        let mut context = ReplyContext::from(&peer);
+        let sender_id = sender.as_ref().map(|it| it.id);
+        if self.9.is_disabled(&action) {
+            return Ok((Reply::CommandDisabled, context));
+        }
+        let reply = match action {
+            // admin commands do not depend on the peer state
+            UserAction::VerifySchedule(q) => self.7.verify(&platform_id, &q).await?,
+            UserAction::Ping => self.12.ping(&platform_id).await,
+            UserAction::Block(pattern) => self.16.block(&platform_id, &pattern).await?,
+            UserAction::Unblock(pattern) => self.16.unblock(&platform_id, &pattern).await?,
+            UserAction::Blocklist => self.16.get_blocklist(&platform_id),
+            // notes belong to the chat, so they do not depend on the peer state too
+            UserAction::AddNote { date, text } => {
+                let note = ChatNote { date, text };
+                self.8
+                    .add_note(&platform_id, sender_id, &peer, note)
+                    .await?
+            }
+            UserAction::RemoveNotes(date) => {
+                self.8
+                    .remove_notes(&platform_id, sender_id, &peer, date)
+                    .await?
+            }
+            // member links belong to the chat too
+            UserAction::LinkMemberSchedule(_)
+            | UserAction::UnlinkMemberSchedule
+            | UserAction::MemberSchedule { .. }
+                if !platform_id.is_group_chat() =>
+            {
+                Reply::GroupChatOnly
+            }
+            UserAction::LinkMemberSchedule(q) => match &sender {
+                Some(sender) => self.13.link(&peer, sender, &q).await?,
+                None => Reply::UnknownCommand,
+            },
+            UserAction::UnlinkMemberSchedule => match &sender {
+                Some(sender) => self.13.unlink(&peer, sender).await?,
+                None => Reply::UnknownCommand,
+            },
+            UserAction::MemberSchedule { member, day_offset } => {
+                match self.13.get_member_schedule(&peer, &member).await? {
+                    // the schedule of the member is only shown, the chat peer is not changed
+                    Some(schedule) => {
+                        let member_peer = Peer {
+                            selected_schedule: schedule.name,
+                            selected_schedule_type: schedule.r#type,
+                            selecting_schedule: false,
+                            ..peer
+                        };
+                        self.handle_day_with_offset(member_peer, day_offset).await?
+                    }
+                    None => Reply::MemberScheduleNotLinked,
+                }
+            }
+            UserAction::SetIcsEnabled(_) | UserAction::ExportData
+                if !platform_id.supports_documents() =>
+            {
+                Reply::DocumentsNotSupported
+            }
+            // export does not depend on the peer state, even new peers can request it
+            UserAction::ExportData => self.10.export(&platform_id, &peer).await?,
+            // the same for the deletion of the data
+            UserAction::DeleteData => self.17.delete(&peer).await?,
+            UserAction::RestoreData => self.17.restore(&peer).await?,
+            // the building is known from the room, so the directions do not need the schedule
+            UserAction::Navigation(room) => Reply::Navigation {
+                room,
+                map_provider: peer.map_provider,
+            },
+            action => self.handle_action(peer, action).await?,
+        };
+        match &reply {
+            Reply::LocaleChanged(locale) => context.locale = *locale,
+            Reply::PrivacyModeChanged(privacy_mode) => context.privacy_mode = *privacy_mode,
+            Reply::IcsEnabledChanged(ics_enabled) => context.ics_enabled = *ics_enabled,
+            Reply::ThemeChanged(theme) => context.theme = Some(*theme),
+            _ => (),
+        };
+        context.ics_enabled &= platform_id.supports_documents();
+        Ok((reply, context))
+    }
+
+    async fn handle_action(&self, peer: Peer, action: UserAction) -> anyhow::Result<Reply> {
         // handle initial state
-        if peer.selected_schedule.is_empty() && !matches!(&action, UserAction::Unknown(_)) {
+        if peer.selected_schedule.is_empty()
+            && !matches!(
+                &action,
+                UserAction::Unknown(_) | UserAction::SetLocale(_) | UserAction::SetPrivacyMode(_)
+            )
+        {
             return if peer.selecting_schedule {
                 Ok(Reply::ReadyToChangeSchedule)
             } else {
@@ -163,9 +684,49 @@ impl GenerateReplyUseCase {
         }
         match action {
             UserAction::Start => self.handle_start(peer).await,
-            UserAction::WeekWithOffset(offset) => self.handle_week_with_offset(peer, offset).await,
-            UserAction::DayWithOffset(offset) => self.handle_day_with_offset(peer, offset).await,
+            UserAction::WeekWithOffset(offset) | UserAction::FullWeekWithOffset(offset) => {
+                let full = matches!(action, UserAction::FullWeekWithOffset(_));
+                let reply = self
+                    .handle_week_with_offset(peer.clone(), offset, full)
+                    .await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::DayWithOffset(offset) => {
+                let reply = self.handle_day_with_offset(peer.clone(), offset).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::MonthWithOffset(offset) => {
+                let reply = self.handle_month_with_offset(peer.clone(), offset).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::NextWeekChanges => {
+                let reply = self.handle_next_week_changes(peer.clone()).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::Exams => {
+                let reply = self.handle_exams(peer.clone()).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::SpecificDate(date) => {
+                let reply = self.handle_day(peer.clone(), date).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::DayRange { from, to } => {
+                let reply = self.handle_day_range(peer.clone(), from, to).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
             UserAction::Unknown(q) => {
+                // one-tap switching to the recent schedule, bypassing search
+                if let Some(schedule) = self.find_recent_schedule(&peer, &q).await {
+                    return self.select_schedule(peer, schedule).await;
+                }
                 if peer.selecting_schedule || peer.selected_schedule.is_empty() {
                     self.handle_schedule_search(peer, &q).await
                 } else {
@@ -181,11 +742,219 @@ impl GenerateReplyUseCase {
                     .await?;
                 Ok(Reply::ReadyToChangeSchedule)
             }
+            UserAction::RecentSchedules => {
+                let recent_schedules = self.6.get_recent_schedules(peer.id).await?;
+                self.reset_schedule_selection_if_needed(peer).await?;
+                Ok(Reply::RecentSchedules(
+                    recent_schedules.into_iter().map(|it| it.name).collect(),
+                ))
+            }
             UserAction::Help => Ok(Reply::ShowHelp),
-            UserAction::UpcomingEvents => self.4.handle_upcoming_events(peer).await,
+            UserAction::SetMapProvider(map_provider) => {
+                self.1
+                    .save_peer(Peer {
+                        map_provider,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::MapProviderChanged(map_provider))
+            }
+            UserAction::SetDaysRange(days_range) => {
+                self.1
+                    .save_peer(Peer {
+                        days_range,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::DaysRangeChanged(days_range))
+            }
+            UserAction::SetLocale(locale) => {
+                let locale = locale.unwrap_or(match peer.locale.unwrap_or_default() {
+                    Locale::Ru => Locale::En,
+                    Locale::En => Locale::Ru,
+                });
+                self.1
+                    .save_peer(Peer {
+                        locale: Some(locale),
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::LocaleChanged(locale))
+            }
+            UserAction::SetCommute(commute_minutes) => {
+                self.1
+                    .save_peer(Peer {
+                        commute_minutes,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::CommuteChanged(commute_minutes))
+            }
+            UserAction::SetPrivacyMode(privacy_mode) => {
+                self.1
+                    .save_peer(Peer {
+                        privacy_mode,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::PrivacyModeChanged(privacy_mode))
+            }
+            UserAction::SetChangelogEnabled(changelog_enabled) => {
+                self.1
+                    .save_peer(Peer {
+                        changelog_enabled,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::ChangelogEnabledChanged(changelog_enabled))
+            }
+            UserAction::SetWeekAheadEnabled(week_ahead_enabled) => {
+                self.1
+                    .save_peer(Peer {
+                        week_ahead_enabled,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::WeekAheadEnabledChanged(week_ahead_enabled))
+            }
+            UserAction::EnableDigest | UserAction::DisableDigest => {
+                let digest_enabled = matches!(action, UserAction::EnableDigest);
+                self.1
+                    .save_peer(Peer {
+                        digest_enabled,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::DigestEnabledChanged(digest_enabled))
+            }
+            UserAction::Subscribe | UserAction::Unsubscribe => {
+                let subscribed = matches!(action, UserAction::Subscribe);
+                self.1
+                    .save_peer(Peer {
+                        subscribed,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::SubscriptionChanged(subscribed))
+            }
+            UserAction::SetIcsEnabled(ics_enabled) => {
+                self.1
+                    .save_peer(Peer {
+                        ics_enabled,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::IcsEnabledChanged(ics_enabled))
+            }
+            UserAction::SetNewcomerMode(newcomer_mode) => {
+                self.1
+                    .save_peer(Peer {
+                        newcomer_mode,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::NewcomerModeChanged(newcomer_mode))
+            }
+            UserAction::SetTheme(theme) => {
+                self.1
+                    .save_peer(Peer {
+                        theme: Some(theme),
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::ThemeChanged(theme))
+            }
+            UserAction::Achievements => self.5.handle_achievements(&peer).await,
+            // admin, notes, member links, export, deletion and navigation commands
+            // are handled before the peer state checks
+            UserAction::VerifySchedule(_)
+            | UserAction::Ping
+            | UserAction::Block(_)
+            | UserAction::Unblock(_)
+            | UserAction::Blocklist
+            | UserAction::AddNote { .. }
+            | UserAction::RemoveNotes(_)
+            | UserAction::LinkMemberSchedule(_)
+            | UserAction::UnlinkMemberSchedule
+            | UserAction::MemberSchedule { .. }
+            | UserAction::ExportData
+            | UserAction::DeleteData
+            | UserAction::RestoreData
+            | UserAction::Navigation(_) => Ok(Reply::UnknownCommand),
+            UserAction::SetAchievementsEnabled(achievements_enabled) => {
+                self.1
+                    .save_peer(Peer {
+                        achievements_enabled,
+                        selecting_schedule: false,
+                        ..peer
+                    })
+                    .await?;
+                Ok(Reply::AchievementsEnabledChanged(achievements_enabled))
+            }
+            UserAction::UpcomingEvents => {
+                let reply = self.4.handle_upcoming_events(peer.clone()).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+            UserAction::RemainingToday => {
+                let reply = self.4.handle_remaining_today(&peer).await;
+                self.5.record_schedule_check_if_needed(&peer, &reply).await;
+                self.handle_schedule_not_found_if_needed(peer, reply).await
+            }
+        }
+    }
+
+    /// If the peer's selected schedule does not exist anymore, suggest similar schedules
+    /// instead of the error. Otherwise, return `reply` as is.
+    async fn handle_schedule_not_found_if_needed(
+        &self,
+        peer: Peer,
+        reply: anyhow::Result<Reply>,
+    ) -> anyhow::Result<Reply> {
+        match reply {
+            Err(e) if matches!(e.as_common_error(), Some(CommonError::NotFoundError(_))) => {
+                self.handle_schedule_renamed(peer).await
+            }
+            _ => reply,
         }
     }
 
+    /// Process the case when the peer's selected schedule disappeared from MPEI.
+    ///
+    /// Usually this means that the group was renamed, so we are looking for
+    /// the most similar schedules and let the user choose one of them.
+    /// The peer is switched to the schedule selection mode, so tapping
+    /// any of the suggested schedules will select it.
+    async fn handle_schedule_renamed(&self, peer: Peer) -> anyhow::Result<Reply> {
+        let schedule_name = peer.selected_schedule.to_owned();
+        let candidates = self
+            .3
+            .search_similar_schedules(&schedule_name, &peer.selected_schedule_type)
+            .await?;
+        self.1
+            .save_peer(Peer {
+                selecting_schedule: true,
+                ..peer
+            })
+            .await?;
+        Ok(Reply::ScheduleRenamed {
+            schedule_name,
+            results: candidates.into_iter().take(3).map(|it| it.name).collect(),
+        })
+    }
+
     /// Process `/start` command.
     /// This command can usually be sent by new bot users.
     async fn handle_start(&self, peer: Peer) -> anyhow::Result<Reply> {
@@ -196,7 +965,16 @@ impl GenerateReplyUseCase {
                     ..peer
                 })
                 .await?;
-            Ok(Reply::StartGreetings)
+            // suggestions are optional, the greetings are sent without them
+            let suggestions = self
+                .3
+                .get_trending_groups(START_SUGGESTIONS_LIMIT)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("Cannot get trending groups for the start suggestions: {e:#}");
+                    vec![]
+                });
+            Ok(Reply::StartGreetings { suggestions })
         } else {
             let schedule_name = peer.selected_schedule.to_owned();
             self.reset_schedule_selection_if_needed(peer).await?;
@@ -206,24 +984,84 @@ impl GenerateReplyUseCase {
 
     /// Process `/thisweek` and `/nextweek` commands
     /// with `offset` equals 0 and 1 respectively.
-    async fn handle_week_with_offset(&self, peer: Peer, offset: i8) -> anyhow::Result<Reply> {
-        let schedule = self
+    ///
+    /// Big weeks are summarized by the renderer, unless the week is requested in `full`.
+    async fn handle_week_with_offset(
+        &self,
+        peer: Peer,
+        offset: i8,
+        full: bool,
+    ) -> anyhow::Result<Reply> {
+        let peer_id = peer.id;
+        let map_provider = peer.map_provider;
+        let days_range = peer.days_range;
+        let (schedule, rendered) = self
             .2
-            .get_schedule(
+            .get_schedule_with_renders(
                 &peer.selected_schedule,
                 &peer.selected_schedule_type,
                 offset,
             )
             .await?;
         self.reset_schedule_selection_if_needed(peer).await?;
+        let mut week = schedule
+            .weeks
+            .first()
+            .ok_or_else(|| anyhow!(CommonError::internal("Schedule does not have week")))?
+            .clone();
+        week.retain_days(&days_range);
+        let week_end = week.first_day_of_week + chrono::Duration::days(6);
+        let notes = self
+            .8
+            .get_notes(peer_id, week.first_day_of_week, week_end)
+            .await;
         Ok(Reply::Week {
+            schedule_name: schedule.name,
             week_offset: offset,
-            week: schedule
-                .weeks
-                .first()
-                .ok_or_else(|| anyhow!(CommonError::internal("Schedule does not have week")))?
-                .clone(),
+            week,
             schedule_type: schedule.r#type,
+            map_provider,
+            notes,
+            full,
+            rendered: Some(rendered),
+        })
+    }
+
+    /// Process `/month` and `/nextmonth` commands
+    async fn handle_month_with_offset(&self, peer: Peer, offset: i8) -> anyhow::Result<Reply> {
+        let month = self
+            .11
+            .get_month_overview(
+                &peer.selected_schedule,
+                &peer.selected_schedule_type,
+                offset,
+            )
+            .await?;
+        self.reset_schedule_selection_if_needed(peer).await?;
+        Ok(Reply::Month(month))
+    }
+
+    /// Process `/nextweek_changes` command
+    async fn handle_next_week_changes(&self, peer: Peer) -> anyhow::Result<Reply> {
+        let diff = self
+            .2
+            .get_diff(&peer.selected_schedule, &peer.selected_schedule_type, 0, 1)
+            .await?;
+        self.reset_schedule_selection_if_needed(peer).await?;
+        Ok(Reply::NextWeekChanges(Box::new(diff)))
+    }
+
+    /// Process `/exams` command
+    async fn handle_exams(&self, peer: Peer) -> anyhow::Result<Reply> {
+        let session = self
+            .2
+            .get_session(&peer.selected_schedule, &peer.selected_schedule_type)
+            .await?;
+        let map_provider = peer.map_provider;
+        self.reset_schedule_selection_if_needed(peer).await?;
+        Ok(Reply::Exams {
+            session: Box::new(session),
+            map_provider,
         })
     }
 
@@ -236,8 +1074,20 @@ impl GenerateReplyUseCase {
             Ordering::Less => current_date.checked_sub_days(Days::new(-offset as u64)),
         }
         .ok_or_else(|| anyhow!(CommonError::user("Invalid day offset")))?;
+        self.handle_day(peer, selected_date).await
+    }
+
+    /// Process requests of the schedule for the specific date, e.g. "15 марта".
+    async fn handle_day(&self, peer: Peer, selected_date: NaiveDate) -> anyhow::Result<Reply> {
+        let current_date = Local::now().date_naive();
+        let week_offset = (selected_date.week(Weekday::Mon).first_day()
+            - current_date.week(Weekday::Mon).first_day())
+        .num_weeks();
         let week_offset =
-            selected_date.iso_week().week() as i8 - current_date.iso_week().week() as i8;
+            i8::try_from(week_offset).map_err(|_| anyhow!(CommonError::user("Too far date")))?;
+        let day_offset = (selected_date - current_date)
+            .num_days()
+            .clamp(i8::MIN as i64, i8::MAX as i64) as i8;
         let schedule = self
             .2
             .get_schedule(
@@ -246,47 +1096,133 @@ impl GenerateReplyUseCase {
                 week_offset,
             )
             .await?;
+        let period_type = schedule
+            .weeks
+            .first()
+            .map(|week| week.period_type)
+            .unwrap_or_default();
         let day = schedule
             .weeks
             .iter()
             .flat_map(|week| &week.days)
             .find(|day| day.date == selected_date)
-            .map(Clone::clone)
+            .cloned()
             // mock day without classes
             .unwrap_or_else(|| Day {
                 day_of_week: selected_date.weekday().number_from_monday() as u8,
                 date: selected_date,
                 classes: Vec::with_capacity(0),
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: false,
             });
+        let map_provider = peer.map_provider;
+        let newcomer_mode = peer.newcomer_mode;
+        let notes = self
+            .8
+            .get_notes(peer.id, selected_date, selected_date)
+            .await;
+        let room_changes = self.15.take_warnings(peer.id, selected_date, current_date);
         self.reset_schedule_selection_if_needed(peer).await?;
         Ok(Reply::Day {
-            day_offset: offset,
+            day_offset,
             day,
             schedule_type: schedule.r#type,
+            map_provider,
+            period_type,
+            notes,
+            newcomer_mode,
+            room_changes,
+        })
+    }
+
+    /// Process requests of the schedule for several days, e.g. "с понедельника по среду".
+    async fn handle_day_range(
+        &self,
+        peer: Peer,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Reply> {
+        let current_week = Local::now().date_naive().week(Weekday::Mon).first_day();
+        let week_offset = |date: NaiveDate| {
+            i8::try_from((date.week(Weekday::Mon).first_day() - current_week).num_weeks())
+                .map_err(|_| anyhow!(CommonError::user("Too far date")))
+        };
+        let mut schedule_type = peer.selected_schedule_type.to_owned();
+        let mut found_days = Vec::new();
+        for week_offset in week_offset(from)?..=week_offset(to)? {
+            let schedule = self
+                .2
+                .get_schedule(
+                    &peer.selected_schedule,
+                    &peer.selected_schedule_type,
+                    week_offset,
+                )
+                .await?;
+            schedule_type = schedule.r#type;
+            found_days.extend(
+                schedule
+                    .weeks
+                    .into_iter()
+                    .flat_map(|week| week.days)
+                    .filter(|day| from <= day.date && day.date <= to),
+            );
+        }
+        let days = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| peer.days_range.contains(date.weekday()))
+            .map(|date| {
+                found_days
+                    .iter()
+                    .find(|day| day.date == date)
+                    .cloned()
+                    // mock day without classes
+                    .unwrap_or_else(|| Day {
+                        day_of_week: date.weekday().number_from_monday() as u8,
+                        date,
+                        classes: Vec::with_capacity(0),
+                        source: Default::default(),
+                        expires_at: None,
+                        before_semester_start: false,
+                    })
+            })
+            .collect();
+        let map_provider = peer.map_provider;
+        let notes = self.8.get_notes(peer.id, from, to).await;
+        self.reset_schedule_selection_if_needed(peer).await?;
+        Ok(Reply::DayRange {
+            from,
+            to,
+            days,
+            schedule_type,
+            map_provider,
+            notes,
         })
     }
 
     /// Process uncnown commands which may be a schedule change request commands.
     ///
     /// We suggest search results if it is not possible to switch to the specified schedule.
-    async fn handle_schedule_search(&self, peer: Peer, q: &str) -> anyhow::Result<Reply> {
+    ///
+    /// Rooms are searched only with the filter like "ауд. а-300", so the room search results
+    /// are shown with the filter too, and the tap on the result button selects the room.
+    async fn handle_schedule_search(&self, peer: Peer, query: &str) -> anyhow::Result<Reply> {
+        let (q, r#type) = match ROOM_SEARCH_PATTERN.captures(query) {
+            Some(captures) => (captures.get(1).unwrap().as_str(), Some(ScheduleType::Room)),
+            None => (query, None),
+        };
         let search_results = self
             .3
-            .search_schedule(q, None)
+            .search_schedule(q, r#type)
             .await
             .with_context(|| "Error while processing schedule change")?;
         if let Some(candidate) = search_results.iter().find(|it| it.name.to_lowercase() == q) {
-            self.1
-                .save_peer(Peer {
-                    selected_schedule: candidate.name.to_owned(),
-                    selected_schedule_type: candidate.r#type.to_owned(),
-                    selecting_schedule: false,
-                    ..peer
-                })
-                .await?;
-            Ok(Reply::ScheduleChangedSuccessfully(
-                candidate.name.to_owned(),
-            ))
+            let schedule = RecentSchedule {
+                name: candidate.name.to_owned(),
+                r#type: candidate.r#type.to_owned(),
+            };
+            self.select_schedule(peer, schedule).await
         } else if !search_results.is_empty() {
             let mut results = search_results;
             let max_idx = results.len();
@@ -298,27 +1234,56 @@ impl GenerateReplyUseCase {
             let results_contains_person = results
                 .iter()
                 .any(|it| matches!(it.r#type, ScheduleType::Person));
+            results.truncate(MAX_SCHEDULE_SEARCH_RESULTS);
 
             Ok(Reply::ScheduleSearchResults {
-                schedule_name: q.to_owned(),
+                schedule_name: query.to_owned(),
                 results_contains_person,
-                results: if results_contains_person {
-                    results.into_iter().take(3).map(|it| it.name).collect()
-                } else {
-                    results.into_iter().take(6).map(|it| it.name).collect()
-                },
+                person_details: results.iter().filter_map(render_person_details).collect(),
+                results: results.iter().map(search_result_label).collect(),
             })
         } else {
-            Ok(Reply::CannotFindSchedule(q.to_owned()))
+            Ok(Reply::CannotFindSchedule(query.to_owned()))
         }
     }
 
-    async fn reset_schedule_selection_if_needed(&self, peer: Peer) -> anyhow::Result<()> {
-        if peer.selecting_schedule {
-            self.1
-                .save_peer(Peer {
-                    selecting_schedule: false,
-                    ..peer
+    /// Switch the peer to the `schedule` and remember it in the recent schedules.
+    async fn select_schedule(&self, peer: Peer, schedule: RecentSchedule) -> anyhow::Result<Reply> {
+        let peer_id = peer.id;
+        self.1
+            .save_peer(Peer {
+                selected_schedule: schedule.name.to_owned(),
+                selected_schedule_type: schedule.r#type.to_owned(),
+                selecting_schedule: false,
+                ..peer
+            })
+            .await?;
+        if let Err(e) = self.6.add_recent_schedule(peer_id, &schedule).await {
+            error!("Error while saving recent schedule: {e}");
+        }
+        Ok(Reply::ScheduleChangedSuccessfully(schedule.name))
+    }
+
+    /// Find the recently selected schedule of the peer with the name `q` (in lowercase).
+    /// Errors are only logged, because the regular search can be used instead.
+    async fn find_recent_schedule(&self, peer: &Peer, q: &str) -> Option<RecentSchedule> {
+        match self.6.get_recent_schedules(peer.id).await {
+            Ok(recent_schedules) => recent_schedules
+                .into_iter()
+                .find(|it| it.name.to_lowercase() == q),
+            Err(e) => {
+                error!("Error while getting recent schedules: {e}");
+                None
+            }
+        }
+    }
+
+    async fn reset_schedule_selection_if_needed(&self, peer: Peer) -> anyhow::Result<()> {
+        if peer.selecting_schedule {
+            self.1
+                .save_peer(Peer {
+                    selecting_schedule: false,
+                    ..peer
                 })
                 .await?;
         }
@@ -333,22 +1298,38 @@ impl GenerateReplyUseCase {
 /// shows which pair has already started and is running.
 pub struct GetUpcomingEventsUseCase(pub(crate) Arc<ScheduleRepository>);
 
+/// During the session or vacation upcoming events are searched
+/// further than the next week, but not further than this number of weeks
+const UPCOMING_EVENTS_MAX_WEEKS: i8 = 10;
+/// Trending groups suggested to the new users, two rows of the keyboard
+const START_SUGGESTIONS_LIMIT: usize = 4;
+
 impl GetUpcomingEventsUseCase {
     pub async fn handle_upcoming_events(&self, peer: Peer) -> anyhow::Result<Reply> {
         // load all days for current and next week
         let mut days: Vec<Day> = Vec::with_capacity(14);
-        self.0
-            .get_schedule(&peer.selected_schedule, &peer.selected_schedule_type, 0)
-            .await?
-            .weeks
-            .iter_mut()
-            .for_each(|week| days.append(&mut week.days));
-        self.0
-            .get_schedule(&peer.selected_schedule, &peer.selected_schedule_type, 1)
-            .await?
-            .weeks
-            .iter_mut()
-            .for_each(|week| days.append(&mut week.days));
+        let mut period_types = Vec::with_capacity(2);
+        for offset in 0..=1 {
+            self.0
+                .get_schedule(
+                    &peer.selected_schedule,
+                    &peer.selected_schedule_type,
+                    offset,
+                )
+                .await?
+                .weeks
+                .iter_mut()
+                .for_each(|week| {
+                    period_types.push(week.period_type);
+                    days.append(&mut week.days);
+                });
+        }
+        // cancelled classes are shown in schedules, but they are not upcoming events
+        days.iter_mut().for_each(|day| {
+            day.classes
+                .retain(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+        });
+        days.retain(|day| !day.classes.is_empty());
         // remove all past days, (and also current day if it has only past classes)
         let local_datetime = Local::now();
         let current_date = local_datetime.date_naive();
@@ -365,8 +1346,21 @@ impl GetUpcomingEventsUseCase {
         // early return if there are no actual days
         use UpcomingEventsPrediction::*;
         if days.is_empty() {
+            let current_period_type = period_types.first().copied().unwrap_or_default();
+            let next_period_type = period_types.last().copied().unwrap_or_default();
+            // an empty study week is not a break, there is just no schedule
+            let prediction = match next_period_type {
+                PeriodType::Study => NoClassesNextWeek,
+                _ => match self.find_classes_resume_date(&peer).await? {
+                    Some(date) => ClassesResume {
+                        period_type: current_period_type,
+                        date,
+                    },
+                    None => NoClassesNextWeek,
+                },
+            };
             return Ok(Reply::UpcomingEvents {
-                prediction: NoClassesNextWeek,
+                prediction,
                 schedule_type: peer.selected_schedule_type,
             });
         }
@@ -449,142 +1443,4260 @@ impl GetUpcomingEventsUseCase {
     }
 }
 
-#[cfg(test)]
-mod t2a_tests {
-    use crate::models::UserAction;
+impl GetUpcomingEventsUseCase {
+    /// Scan the weeks after the next one until the end of the session or vacation,
+    /// returns `None` if the classes do not resume within [UPCOMING_EVENTS_MAX_WEEKS].
+    async fn find_classes_resume_date(&self, peer: &Peer) -> anyhow::Result<Option<NaiveDate>> {
+        for offset in 2..UPCOMING_EVENTS_MAX_WEEKS {
+            let schedule = self
+                .0
+                .get_schedule(
+                    &peer.selected_schedule,
+                    &peer.selected_schedule_type,
+                    offset,
+                )
+                .await?;
+            if let Some(date) = classes_resume_date(&schedule.weeks) {
+                return Ok(Some(date));
+            }
+        }
+        Ok(None)
+    }
+}
 
-    use super::TextToActionUseCase;
+/// The first day with classes, or the first day of the study week
+/// if its classes are not published yet
+fn classes_resume_date(weeks: &[Week]) -> Option<NaiveDate> {
+    weeks.iter().find_map(|week| {
+        week.days
+            .iter()
+            .find(|day| {
+                day.classes
+                    .iter()
+                    .any(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+            })
+            .map(|day| day.date)
+            .or_else(|| (week.period_type == PeriodType::Study).then_some(week.first_day_of_week))
+    })
+}
 
-    macro_rules! test_t2a {
-        ($name:tt, $exp:expr, $inputs:expr) => {
-            #[test]
-            fn $name() {
-                let use_case = TextToActionUseCase;
-                for text in $inputs {
-                    assert_eq!(use_case.text_to_action(text).unwrap(), $exp);
-                }
+/// How long the month overviews are reused without requests to `app_schedule`
+const MONTH_OVERVIEW_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Calendar overview of the month for the `/month` command.
+///
+/// The month takes up to six weekly schedules, so the overviews are cached
+/// for [MONTH_OVERVIEW_CACHE_TTL] instead of requesting all of them on every tap.
+pub struct GetMonthOverviewUseCase {
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) cache: Mutex<HashMap<MonthKey, (Instant, MonthOverview)>>,
+}
+
+/// Schedule name, type and the first day of the month
+type MonthKey = (String, ScheduleType, NaiveDate);
+
+impl GetMonthOverviewUseCase {
+    /// Overview of the month with `offset` relative to the current one
+    pub async fn get_month_overview(
+        &self,
+        name: &str,
+        r#type: &ScheduleType,
+        offset: i8,
+    ) -> anyhow::Result<MonthOverview> {
+        let today = Local::now().date_naive();
+        let first_day = first_day_of_month(today, offset)
+            .ok_or_else(|| anyhow!(CommonError::user("Invalid month offset")))?;
+        let key = (name.to_owned(), r#type.to_owned(), first_day);
+        if let Some((cached_at, month)) = self.cache.lock().unwrap().get(&key) {
+            if cached_at.elapsed() < MONTH_OVERVIEW_CACHE_TTL {
+                return Ok(month.clone());
             }
-        };
+        }
+        let week_offsets = week_offsets_of_month(today, first_day)
+            .ok_or_else(|| anyhow!(CommonError::user("Invalid month offset")))?;
+        let weeks = try_join_all(
+            week_offsets.map(|offset| self.schedule_repository.get_schedule(name, r#type, offset)),
+        )
+        .await?
+        .into_iter()
+        .flat_map(|schedule| schedule.weeks)
+        .collect::<Vec<_>>();
+        let month = month_overview(first_day, &weeks);
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (cached_at, _)| cached_at.elapsed() < MONTH_OVERVIEW_CACHE_TTL);
+        cache.insert(key, (Instant::now(), month.clone()));
+        Ok(month)
     }
+}
 
-    test_t2a!(
-        action_start,
-        UserAction::Start,
-        ["старт", "начать", "start", "/start"]
-    );
+/// The first day of the month with `offset` relative to the month of `today`
+fn first_day_of_month(today: NaiveDate, offset: i8) -> Option<NaiveDate> {
+    let first_day = today.with_day(1)?;
+    let months = Months::new(offset.unsigned_abs() as u32);
+    if offset >= 0 {
+        first_day.checked_add_months(months)
+    } else {
+        first_day.checked_sub_months(months)
+    }
+}
 
-    test_t2a!(
-        action_status,
-        UserAction::UpcomingEvents,
-        ["статус", "ближайшие пары", "ближайшие", "status", "/status"]
-    );
+/// Offsets of the weeks which contain the days of the month, relative to the week of `today`
+fn week_offsets_of_month(today: NaiveDate, first_day: NaiveDate) -> Option<RangeInclusive<i8>> {
+    let last_day = first_day.checked_add_months(Months::new(1))?.pred_opt()?;
+    let current_week = today.week(Weekday::Mon).first_day();
+    let offset = |date: NaiveDate| {
+        i8::try_from((date.week(Weekday::Mon).first_day() - current_week).num_weeks()).ok()
+    };
+    Some(offset(first_day)?..=offset(last_day)?)
+}
 
-    test_t2a!(
-        action_help,
-        UserAction::Help,
-        ["помощь", "справка", "помоги", "help", "/help"]
-    );
+/// Classify the days of the month starting with `first_day` by the `weeks` of its schedule.
+/// The days are looked up by date, so the weeks may start in the previous month.
+fn month_overview(first_day: NaiveDate, weeks: &[Week]) -> MonthOverview {
+    let days = first_day
+        .iter_days()
+        .take_while(|date| date.month() == first_day.month())
+        .map(|date| {
+            let Some(week) = weeks.iter().find(|week| {
+                week.first_day_of_week <= date
+                    && date < week.first_day_of_week + chrono::Duration::days(7)
+            }) else {
+                return MonthDayKind::DayOff;
+            };
+            let classes = week
+                .days
+                .iter()
+                .filter(|day| day.date == date)
+                .flat_map(|day| &day.classes)
+                .filter(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+                .collect::<Vec<_>>();
+            if classes.iter().any(|cls| cls.r#type == ClassesType::Exam) {
+                MonthDayKind::Exam
+            } else if !classes.is_empty() {
+                MonthDayKind::Study
+            } else if week.period_type == PeriodType::Vacation {
+                MonthDayKind::Holiday
+            } else {
+                MonthDayKind::DayOff
+            }
+        })
+        .collect();
+    MonthOverview { first_day, days }
+}
 
-    test_t2a!(
-        action_change,
-        UserAction::ChangeScheduleIntent,
-        [
-            "сменить",
-            "сменить группу",
-            "сменить расписание",
-            "change",
-            "/change",
-        ]
-    );
+impl GetUpcomingEventsUseCase {
+    /// Lightweight variant of upcoming events: only the number of classes left today.
+    pub async fn handle_remaining_today(&self, peer: &Peer) -> anyhow::Result<Reply> {
+        let local_datetime = Local::now();
+        let current_date = local_datetime.date_naive();
+        let schedule = self
+            .0
+            .get_schedule(&peer.selected_schedule, &peer.selected_schedule_type, 0)
+            .await?;
+        let today = schedule
+            .weeks
+            .iter()
+            .flat_map(|week| week.days.iter())
+            .find(|day| day.date == current_date);
+        let (count, last_class_end) = remaining_classes(today, local_datetime.time());
+        Ok(Reply::RemainingToday {
+            count,
+            last_class_end,
+        })
+    }
+}
 
-    test_t2a!(
-        action_week_offset_0,
-        UserAction::WeekWithOffset(0),
-        ["неделя", "эта неделя", "/thisweek"]
-    );
+/// Number of classes which end after `current_time` and the end time of the last of them
+fn remaining_classes(day: Option<&Day>, current_time: NaiveTime) -> (usize, Option<NaiveTime>) {
+    let remaining = day
+        .map(|day| {
+            day.classes
+                .iter()
+                .filter(|cls| cls.time.end > current_time)
+                .filter(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let last_class_end = remaining.iter().map(|cls| cls.time.end).max();
+    (remaining.len(), last_class_end)
+}
 
-    test_t2a!(
-        action_week_offset_1,
-        UserAction::WeekWithOffset(1),
-        ["следующая неделя", "/nextweek"]
-    );
+/// Lightweight gamification: streaks of schedule checks and badges.
+///
+/// Achievements are strictly opt-in, nothing is tracked
+/// until the peer enables achievements in preferences.
+pub struct AchievementsUseCase(pub(crate) Arc<dyn AchievementsRepository>);
 
-    test_t2a!(
-        action_week_offset_m1,
-        UserAction::WeekWithOffset(-1),
-        ["прошлая неделя", "/prevweek"]
-    );
+impl AchievementsUseCase {
+    /// Count successful schedule check for the peer, if the peer enabled achievements.
+    /// Errors are only logged, because achievements must not break schedule replies.
+    pub async fn record_schedule_check_if_needed(
+        &self,
+        peer: &Peer,
+        reply: &anyhow::Result<Reply>,
+    ) {
+        // activity of the peers in privacy mode is not recorded
+        if !peer.achievements_enabled || peer.privacy_mode || reply.is_err() {
+            return;
+        }
+        if let Err(e) = self.record_schedule_check(peer.id).await {
+            error!("Error while recording schedule check for achievements: {e}");
+        }
+    }
 
-    test_t2a!(
-        action_today,
-        UserAction::DayWithOffset(0),
-        [
-            "сегодня",
-            "сегодняшние",
-            "сегодняшний",
-            "/today",
-            "пары сегодня",
-            "сегодняшние пары"
-        ]
-    );
+    async fn record_schedule_check(&self, peer_id: i64) -> anyhow::Result<()> {
+        let stats = self.0.get_stats(peer_id).await?;
+        let stats = apply_schedule_check(stats, Local::now().naive_local());
+        self.0.save_stats(peer_id, &stats).await
+    }
 
-    test_t2a!(
-        action_yesterday,
-        UserAction::DayWithOffset(-1),
-        [
-            "вчера",
-            "вчерашние",
-            "вчерашний",
-            "/yesterday",
-            "пары вчера",
-            "вчерашние пары"
-        ]
-    );
+    pub async fn handle_achievements(&self, peer: &Peer) -> anyhow::Result<Reply> {
+        if !peer.achievements_enabled {
+            return Ok(Reply::AchievementsDisabled);
+        }
+        let mut stats = self.0.get_stats(peer.id).await?;
+        // streak is broken if the schedule was checked neither yesterday nor today
+        let today = Local::now().date_naive();
+        let streak_is_alive = stats
+            .last_check_date
+            .is_some_and(|date| today.signed_duration_since(date).num_days() <= 1);
+        if !streak_is_alive {
+            stats.current_streak = 0;
+        }
+        let badges = earned_badges(&stats);
+        Ok(Reply::Achievements { stats, badges })
+    }
+}
 
-    test_t2a!(
-        action_tomorrow,
-        UserAction::DayWithOffset(1),
-        [
-            "завтра",
-            "завтрашние",
-            "завтрашний",
-            "/tomorrow",
-            "пары завтра",
-            "завтрашние пары"
-        ]
-    );
+/// Update achievement stats with a new schedule check at `now`.
+fn apply_schedule_check(stats: AchievementStats, now: NaiveDateTime) -> AchievementStats {
+    let today = now.date();
+    let current_streak = match stats.last_check_date {
+        Some(date) if date == today => stats.current_streak,
+        Some(date) if today.signed_duration_since(date).num_days() == 1 => stats.current_streak + 1,
+        _ => 1,
+    };
+    let early_bird_time = NaiveTime::from_hms_opt(8, 0, 0).expect("Valid time");
+    AchievementStats {
+        current_streak,
+        best_streak: stats.best_streak.max(current_streak),
+        last_check_date: Some(today),
+        early_bird_checks: stats.early_bird_checks + (now.time() < early_bird_time) as i32,
+        total_checks: stats.total_checks + 1,
+    }
+}
 
-    test_t2a!(
-        action_day_after_tomorrow,
-        UserAction::DayWithOffset(2),
-        ["послезавтра", "послезавтрашние", "послезавтрашний"]
-    );
+fn earned_badges(stats: &AchievementStats) -> Vec<Badge> {
+    let mut badges = Vec::with_capacity(4);
+    if stats.early_bird_checks > 0 {
+        badges.push(Badge::EarlyBird);
+    }
+    if stats.best_streak >= 7 {
+        badges.push(Badge::WeekStreak);
+    }
+    if stats.best_streak >= 30 {
+        badges.push(Badge::MonthStreak);
+    }
+    if stats.total_checks >= 100 {
+        badges.push(Badge::Centurion);
+    }
+    badges
+}
 
-    test_t2a!(
-        action_day_before_yesterday,
-        UserAction::DayWithOffset(-2),
-        ["позавчера", "позавчерашние", "позавчерашний"]
-    );
+/// Admin maintenance use case, which validates all peers' selected schedules
+/// against the `app_schedule` microservice and fixes renamed/merged groups.
+///
+/// Schedule is considered renamed if it is not found anymore, and there is
+/// the only one most similar schedule of the same type, which is similar enough.
+/// All other cases are reported as unresolved and should be fixed manually.
+pub struct MigratePeersUseCase(
+    pub(crate) Arc<dyn PeerRepository>,
+    pub(crate) Arc<ScheduleRepository>,
+    pub(crate) Arc<ScheduleSearchRepository>,
+);
 
-    #[test]
-    fn action_day_of_week() {
-        let use_case = TextToActionUseCase;
-        for text in [
-            "понедельник",
-            "вторник",
-            "среда",
-            "четверг",
-            "пятница",
-            "суббота",
-            "пн",
-            "вт",
-            "ср",
-            "чт",
-            "пт",
-            "сб",
-        ] {
-            let result = use_case.text_to_action(text).unwrap();
-            assert!(matches!(result, UserAction::DayWithOffset(_)));
+/// Minimal similarity of the old and the new schedule names for automatic migration
+const MIGRATION_MIN_SIMILARITY: f64 = 0.7;
+
+impl MigratePeersUseCase {
+    /// Validate all selected schedules and migrate peers to the renamed ones.
+    /// In `dry_run` mode nothing is changed in the database, only the report is generated.
+    pub async fn migrate_peers(&self, dry_run: bool) -> anyhow::Result<PeerMigrationReport> {
+        let peers = self.0.get_peers_with_selected_schedule().await?;
+        let mut peers_by_schedule: HashMap<(String, ScheduleType), usize> = HashMap::new();
+        for peer in &peers {
+            *peers_by_schedule
+                .entry((
+                    peer.selected_schedule.to_owned(),
+                    peer.selected_schedule_type.to_owned(),
+                ))
+                .or_default() += 1;
+        }
+
+        let mut report = PeerMigrationReport {
+            dry_run,
+            total_peers: peers.len(),
+            total_schedules: peers_by_schedule.len(),
+            valid_schedules: 0,
+            migrations: vec![],
+            unresolved: vec![],
+        };
+        for ((name, r#type), peers_count) in peers_by_schedule {
+            let error = match self.1.get_schedule(&name, &r#type, 0).await {
+                Ok(_) => {
+                    report.valid_schedules += 1;
+                    continue;
+                }
+                Err(e) => e,
+            };
+            if !matches!(error.as_common_error(), Some(CommonError::NotFoundError(_))) {
+                report.unresolved.push(UnresolvedSchedule {
+                    schedule_type: r#type,
+                    name,
+                    peers: peers_count,
+                    reason: format!("Cannot validate schedule: {error}"),
+                    candidates: vec![],
+                });
+                continue;
+            }
+
+            let candidates = self.2.search_similar_schedules(&name, &r#type).await?;
+            let best_candidate = match candidates.as_slice() {
+                [first] => Some(first),
+                [first, second, ..] if first.similarity > second.similarity => Some(first),
+                _ => None,
+            }
+            .filter(|it| it.similarity >= MIGRATION_MIN_SIMILARITY);
+
+            if let Some(new_schedule) = best_candidate {
+                if !dry_run {
+                    self.0
+                        .update_peers_selected_schedule(&name, &new_schedule.name, &r#type)
+                        .await?;
+                    info!(
+                        "Peers migrated from schedule '{}' to '{}'",
+                        name, new_schedule.name
+                    );
+                }
+                report.migrations.push(ScheduleMigration {
+                    schedule_type: r#type,
+                    old_name: name,
+                    new_name: new_schedule.name.to_owned(),
+                    peers: peers_count,
+                });
+            } else {
+                report.unresolved.push(UnresolvedSchedule {
+                    schedule_type: r#type,
+                    name,
+                    peers: peers_count,
+                    reason: "Schedule not found and there is no unambiguous replacement".to_owned(),
+                    candidates: candidates.into_iter().take(3).map(|it| it.name).collect(),
+                });
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Admin integrity check of the schedule, e.g. "/verify А-08-19", to quickly answer
+/// "is the bot lying or is MPEI lying" support questions: the current week is fetched
+/// from MPEI bypassing the cache of `app_schedule` and compared with the cached one.
+///
+/// For other peers the command does not exist, so they get [Reply::UnknownCommand].
+pub struct VerifyScheduleUseCase {
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) schedule_search_repository: Arc<ScheduleSearchRepository>,
+    pub(crate) config: AdminConfig,
+}
+
+/// Configuration of the bot admin commands
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminConfig {
+    /// Chat ids (Telegram) or peer ids (VK) of the admins
+    pub admin_ids: Vec<i64>,
+    /// Secret part of `app_schedule` admin endpoints, admin commands are disabled without it
+    pub app_schedule_admin_secret: Option<String>,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            admin_ids: env::get_or("BOT_ADMIN_IDS", "")
+                .split(',')
+                .filter_map(|it| it.trim().parse::<i64>().ok())
+                .collect(),
+            app_schedule_admin_secret: env::get("APP_SCHEDULE_ADMIN_SECRET"),
+        }
+    }
+}
+
+/// Runtime switch to disable misbehaving commands without redeploying.
+///
+/// Commands are identified by the [UserAction] variant names, e.g. `WeekWithOffset` or `UpcomingEvents`.
+/// The initial list is taken from `BOT_DISABLED_COMMANDS` environment variable,
+/// and can be replaced with the admin endpoint.
+pub struct CommandKillSwitchUseCase {
+    disabled: RwLock<BTreeSet<String>>,
+}
+
+impl Default for CommandKillSwitchUseCase {
+    fn default() -> Self {
+        let disabled = env::get_or("BOT_DISABLED_COMMANDS", "")
+            .split(',')
+            .map(str::trim)
+            .filter(|it| !it.is_empty())
+            .map(str::to_owned)
+            .collect::<BTreeSet<_>>();
+        if !disabled.is_empty() {
+            warn!("Disabled commands: {disabled:?}");
+        }
+        Self {
+            disabled: RwLock::new(disabled),
+        }
+    }
+}
+
+impl CommandKillSwitchUseCase {
+    pub fn is_disabled(&self, action: &UserAction) -> bool {
+        self.disabled
+            .read()
+            .unwrap()
+            .contains(action.variant_name())
+    }
+
+    pub fn get_disabled(&self) -> Vec<String> {
+        self.disabled.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Replace the whole list of disabled commands, an empty list enables everything
+    pub fn set_disabled(&self, commands: Vec<String>) -> Vec<String> {
+        let commands = commands.into_iter().collect::<BTreeSet<_>>();
+        warn!("Disabled commands changed: {commands:?}");
+        *self.disabled.write().unwrap() = commands;
+        self.get_disabled()
+    }
+}
+
+impl VerifyScheduleUseCase {
+    pub async fn verify(&self, platform_id: &PlatformId, q: &str) -> anyhow::Result<Reply> {
+        let (_, id) = platform_id.split();
+        let Some(secret) = &self.config.app_schedule_admin_secret else {
+            return Ok(Reply::UnknownCommand);
+        };
+        if !self.config.admin_ids.contains(&id) {
+            return Ok(Reply::UnknownCommand);
         }
+        let search_results = self
+            .schedule_search_repository
+            .search_schedule(q, None)
+            .await
+            .with_context(|| "Error while searching schedule to verify")?;
+        let Some(schedule) = search_results
+            .into_iter()
+            .find(|it| it.name.to_lowercase() == q)
+        else {
+            return Ok(Reply::CannotFindSchedule(q.to_owned()));
+        };
+        info!("Verifying {} '{}'", schedule.r#type, schedule.name);
+        let verification = self
+            .schedule_repository
+            .verify_schedule(secret, &schedule.name, &schedule.r#type)
+            .await
+            .with_context(|| "Error while verifying schedule")?;
+        Ok(Reply::ScheduleVerification(Box::new(verification)))
+    }
+}
+
+/// For how long the result of the `app_schedule` health check is reused
+const HEALTH_CHECK_TTL: Duration = Duration::from_secs(30);
+
+/// Protection of the bots from the abusive peers, checked before the message is handled:
+/// peers matching the admin-managed blocklist and the peers temporarily blocked
+/// by the rate limiter are dropped silently, without any reply.
+///
+/// The admins manage the blocklist with "/block telegram:123", "/unblock telegram:123"
+/// and "/blocklist" commands. For other peers the commands do not exist,
+/// so they get [Reply::UnknownCommand].
+pub struct AbuseProtectionUseCase {
+    pub(crate) blocklist_repository: Arc<dyn BlocklistRepository>,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) config: AdminConfig,
+}
+
+impl AbuseProtectionUseCase {
+    /// `true` if the message of the peer must be dropped without reply.
+    /// The admins are never dropped, so they can always lift the blocks.
+    pub fn should_drop(&self, platform_id: &PlatformId) -> bool {
+        let (_, id) = platform_id.split();
+        if self.config.admin_ids.contains(&id) {
+            return false;
+        }
+        if self.blocklist_repository.is_blocked(platform_id) {
+            return true;
+        }
+        match self.rate_limiter.check(platform_id, Instant::now()) {
+            RateDecision::Allowed => false,
+            RateDecision::Limited | RateDecision::Blocked => true,
+        }
+    }
+
+    pub async fn block(
+        &self,
+        platform_id: &PlatformId,
+        pattern: &BlockPattern,
+    ) -> anyhow::Result<Reply> {
+        if !self.is_admin(platform_id) {
+            return Ok(Reply::UnknownCommand);
+        }
+        warn!("Block pattern '{pattern}' added by {platform_id:?}");
+        self.blocklist_repository.add_pattern(pattern).await?;
+        Ok(self.blocklist())
+    }
+
+    /// Remove the pattern from the blocklist, the temporary block of the peer
+    /// with the exact id is lifted too
+    pub async fn unblock(
+        &self,
+        platform_id: &PlatformId,
+        pattern: &BlockPattern,
+    ) -> anyhow::Result<Reply> {
+        if !self.is_admin(platform_id) {
+            return Ok(Reply::UnknownCommand);
+        }
+        warn!("Block pattern '{pattern}' removed by {platform_id:?}");
+        self.blocklist_repository.remove_pattern(pattern).await?;
+        let now = Instant::now();
+        for (blocked, _) in self.rate_limiter.temporarily_blocked(now) {
+            if pattern.matches(&blocked) {
+                self.rate_limiter.unblock(&blocked, now);
+            }
+        }
+        Ok(self.blocklist())
+    }
+
+    pub fn get_blocklist(&self, platform_id: &PlatformId) -> Reply {
+        match self.is_admin(platform_id) {
+            true => self.blocklist(),
+            false => Reply::UnknownCommand,
+        }
+    }
+
+    fn blocklist(&self) -> Reply {
+        let mut temporarily_blocked = self
+            .rate_limiter
+            .temporarily_blocked(Instant::now())
+            .into_iter()
+            .map(|(platform_id, left)| (platform_id, (left.as_secs() + 59) / 60))
+            .collect::<Vec<_>>();
+        temporarily_blocked.sort_by_key(|(_, minutes)| *minutes);
+        Reply::Blocklist {
+            patterns: self.blocklist_repository.get_patterns(),
+            temporarily_blocked,
+        }
+    }
+
+    fn is_admin(&self, platform_id: &PlatformId) -> bool {
+        let (_, id) = platform_id.split();
+        self.config.admin_ids.contains(&id)
+    }
+}
+
+/// Deletion of all data stored about the peer, e.g. "/deletemydata".
+///
+/// The deletion is soft: the peer is marked deleted and excluded from all features right away,
+/// but it is purged permanently only after the undo window, so the peer can change its mind
+/// and send "/restore". The purge is done by [PeerDeletionUseCase::purge_deleted_peers],
+/// which the bots run every hour.
+pub struct PeerDeletionUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) config: Reloadable<PeerDeletionConfig>,
+}
+
+/// Configuration of [PeerDeletionUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerDeletionConfig {
+    /// Deleted peers can restore the data for this number of days
+    pub undo_days: u32,
+}
+
+impl Default for PeerDeletionConfig {
+    fn default() -> Self {
+        Self {
+            undo_days: env::get_parsed_or("BOT_DELETION_UNDO_DAYS", 7),
+        }
+    }
+}
+
+impl PeerDeletionUseCase {
+    pub async fn delete(&self, peer: &Peer) -> anyhow::Result<Reply> {
+        let deleted_at = match peer.deleted_at {
+            Some(deleted_at) => deleted_at,
+            None => self.peer_repository.mark_peer_deleted(peer.id).await?,
+        };
+        info!("Peer {} is marked deleted", peer.id);
+        Ok(Reply::DataDeletionScheduled {
+            purge_date: self.purge_date(deleted_at),
+        })
+    }
+
+    pub async fn restore(&self, peer: &Peer) -> anyhow::Result<Reply> {
+        if peer.deleted_at.is_none() {
+            return Ok(Reply::NothingToRestore);
+        }
+        self.peer_repository.restore_peer(peer.id).await?;
+        info!("Peer {} is restored", peer.id);
+        Ok(Reply::DataRestored)
+    }
+
+    /// Reply to the deleted peer, which sent anything but "/restore"
+    pub fn pending_deletion(&self, deleted_at: NaiveDateTime) -> Reply {
+        Reply::DataDeletionPending {
+            purge_date: self.purge_date(deleted_at),
+        }
+    }
+
+    /// Permanently delete the peers, whose undo window has passed
+    pub async fn purge_deleted_peers(&self) -> anyhow::Result<()> {
+        let undo_days = self.config.load().undo_days;
+        let purged = self.peer_repository.delete_purged_peers(undo_days).await?;
+        if purged > 0 {
+            info!("Purged {purged} peers deleted more than {undo_days} days ago");
+        }
+        Ok(())
+    }
+
+    fn purge_date(&self, deleted_at: NaiveDateTime) -> NaiveDate {
+        (deleted_at + chrono::Duration::days(self.config.load().undo_days as i64)).date()
+    }
+}
+
+/// Admin health self-test, e.g. "/ping", to triage issues right from the chat:
+/// version of the bot, its uptime, reachability of `app_schedule` and its cache hit ratio.
+///
+/// The health check result is cached, so the command does not load `app_schedule`
+/// during incidents. For other peers the command does not exist, so they get [Reply::UnknownCommand].
+pub struct PingUseCase {
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) config: AdminConfig,
+    pub(crate) build: BuildInfo,
+    pub(crate) started_at: Instant,
+    /// Time of the last health check and its latency in ms, `None` if `app_schedule` was unreachable
+    pub(crate) last_health_check: Mutex<Option<(Instant, Option<u64>)>>,
+}
+
+impl PingUseCase {
+    pub async fn ping(&self, platform_id: &PlatformId) -> Reply {
+        let (_, id) = platform_id.split();
+        if !self.config.admin_ids.contains(&id) {
+            return Reply::UnknownCommand;
+        }
+        let (checked_at, schedule_service_latency_ms) = self.check_schedule_service().await;
+        // the stats endpoint is admin-only, so the ratio is unknown without the secret
+        let cache_hit_percent = match &self.config.app_schedule_admin_secret {
+            Some(secret) if schedule_service_latency_ms.is_some() => self
+                .schedule_repository
+                .get_fetch_latency(secret)
+                .await
+                .map_err(|e| warn!("Error while getting app_schedule latency stats: {e}"))
+                .ok()
+                .and_then(|it| cache_hit_percent(&it)),
+            _ => None,
+        };
+        Reply::Pong(HealthReport {
+            build: self.build,
+            uptime_seconds: self.started_at.elapsed().as_secs(),
+            schedule_service_latency_ms,
+            checked_seconds_ago: checked_at.elapsed().as_secs(),
+            cache_hit_percent,
+        })
+    }
+
+    async fn check_schedule_service(&self) -> (Instant, Option<u64>) {
+        if let Some((checked_at, latency)) = *self.last_health_check.lock().unwrap() {
+            if checked_at.elapsed() < HEALTH_CHECK_TTL {
+                return (checked_at, latency);
+            }
+        }
+        let started_at = Instant::now();
+        let latency = match self.schedule_repository.check_health().await {
+            Ok(_) => Some(started_at.elapsed().as_millis() as u64),
+            Err(e) => {
+                warn!("app_schedule health check failed: {e}");
+                None
+            }
+        };
+        let checked_at = Instant::now();
+        *self.last_health_check.lock().unwrap() = Some((checked_at, latency));
+        (checked_at, latency)
+    }
+}
+
+/// Share of the requests answered from the cache, including the expired one
+fn cache_hit_percent(latency: &ScheduleFetchLatency) -> Option<u8> {
+    let total = latency.sources.values().map(|it| it.count).sum::<u64>();
+    let hits = ["cache", "staleCache"]
+        .iter()
+        .filter_map(|source| latency.sources.get(*source))
+        .map(|it| it.count)
+        .sum::<u64>();
+    (total > 0).then(|| (hits * 100 / total) as u8)
+}
+
+/// Max length of the note in characters
+pub(crate) const NOTE_MAX_LENGTH: usize = 200;
+/// Max number of notes of the chat for one day
+pub(crate) const MAX_NOTES_PER_DAY: usize = 5;
+
+/// Shared board of the chat: short notes attached to the dates, e.g. "контрольная по физике",
+/// which are shown beneath the classes of the day to everyone in the chat.
+///
+/// Everyone can change the notes of the private chats, but only chat admins
+/// can change the notes of the group chats.
+pub struct NotesUseCase {
+    pub(crate) notes_repository: Arc<dyn NotesRepository>,
+    pub(crate) chat_admin_checker: Arc<dyn ChatAdminChecker>,
+}
+
+impl NotesUseCase {
+    pub async fn add_note(
+        &self,
+        platform_id: &PlatformId,
+        sender_id: Option<i64>,
+        peer: &Peer,
+        note: ChatNote,
+    ) -> anyhow::Result<Reply> {
+        if !self.is_chat_admin(platform_id, sender_id).await? {
+            return Ok(Reply::NoteRejected(NoteRejection::NotChatAdmin));
+        }
+        if note.text.chars().count() > NOTE_MAX_LENGTH {
+            return Ok(Reply::NoteRejected(NoteRejection::TooLong));
+        }
+        if note.date < Local::now().date_naive() {
+            return Ok(Reply::NoteRejected(NoteRejection::PastDate));
+        }
+        let notes_of_day = self
+            .notes_repository
+            .get_notes(peer.id, note.date, note.date)
+            .await?;
+        if notes_of_day.len() >= MAX_NOTES_PER_DAY {
+            return Ok(Reply::NoteRejected(NoteRejection::TooManyNotes));
+        }
+        self.notes_repository.add_note(peer.id, &note).await?;
+        Ok(Reply::NoteAdded(note.date))
+    }
+
+    pub async fn remove_notes(
+        &self,
+        platform_id: &PlatformId,
+        sender_id: Option<i64>,
+        peer: &Peer,
+        date: NaiveDate,
+    ) -> anyhow::Result<Reply> {
+        if !self.is_chat_admin(platform_id, sender_id).await? {
+            return Ok(Reply::NoteRejected(NoteRejection::NotChatAdmin));
+        }
+        let count = self.notes_repository.remove_notes(peer.id, date).await?;
+        Ok(Reply::NotesRemoved { date, count })
+    }
+
+    /// Notes of the chat from `from` to `to` dates inclusive.
+    /// Errors are only logged, because the notes must not break the schedule replies.
+    pub async fn get_notes(&self, peer_id: i64, from: NaiveDate, to: NaiveDate) -> Vec<ChatNote> {
+        self.notes_repository
+            .get_notes(peer_id, from, to)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Cannot get notes of the peer: {e:#}");
+                Vec::new()
+            })
+    }
+
+    async fn is_chat_admin(
+        &self,
+        platform_id: &PlatformId,
+        sender_id: Option<i64>,
+    ) -> anyhow::Result<bool> {
+        if !platform_id.is_group_chat() {
+            return Ok(true);
+        }
+        match sender_id {
+            Some(sender_id) => self
+                .chat_admin_checker
+                .is_chat_admin(platform_id, sender_id)
+                .await
+                .with_context(|| "Error while checking chat admin rights"),
+            None => Ok(false),
+        }
+    }
+}
+
+/// Personal schedules of the group chat members, which can be requested by mention,
+/// e.g. "пары @username".
+///
+/// Members link their groups themselves, so the schedule of the member is never shown
+/// without consent, and the link can be removed with "моя группа выкл" anytime.
+pub struct MemberSchedulesUseCase {
+    pub(crate) member_schedules_repository: Arc<dyn MemberSchedulesRepository>,
+    pub(crate) schedule_search_repository: Arc<ScheduleSearchRepository>,
+}
+
+impl MemberSchedulesUseCase {
+    /// Link the group `q` to the `member` of the chat, only existing groups can be linked
+    pub async fn link(&self, peer: &Peer, member: &ChatMember, q: &str) -> anyhow::Result<Reply> {
+        let q = q.to_lowercase();
+        let candidate = self
+            .schedule_search_repository
+            .search_schedule(&q, Some(ScheduleType::Group))
+            .await
+            .with_context(|| "Error while searching for member schedule")?
+            .into_iter()
+            .find(|it| it.name.to_lowercase() == q);
+        let Some(candidate) = candidate else {
+            return Ok(Reply::CannotFindSchedule(q));
+        };
+        let schedule = RecentSchedule {
+            name: candidate.name,
+            r#type: candidate.r#type,
+        };
+        self.member_schedules_repository
+            .link_member_schedule(peer.id, member, &schedule)
+            .await?;
+        Ok(Reply::MemberScheduleLinked(schedule.name))
+    }
+
+    pub async fn unlink(&self, peer: &Peer, member: &ChatMember) -> anyhow::Result<Reply> {
+        self.member_schedules_repository
+            .unlink_member_schedule(peer.id, member.id)
+            .await?;
+        Ok(Reply::MemberScheduleUnlinked)
+    }
+
+    pub async fn get_member_schedule(
+        &self,
+        peer: &Peer,
+        member: &MemberMention,
+    ) -> anyhow::Result<Option<RecentSchedule>> {
+        self.member_schedules_repository
+            .get_member_schedule(peer.id, member)
+            .await
+    }
+}
+
+/// Push prepared by the domain, sending it is up to the platform-specific bots
+#[async_trait]
+pub trait PushUseCase: Send + Sync {
+    /// Kind of the push for the logs, e.g. "evening summary"
+    fn name(&self) -> &'static str;
+
+    /// Prepare the push for the peers of the platform if it is time to do it,
+    /// otherwise return nothing. This method is supposed to be called periodically.
+    ///
+    /// `platform_id` is the constructor of the platform id, e.g. [PlatformId::telegram].
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>>;
+}
+
+/// Guard of the push, which is sent once a day when the gate of the push allows it,
+/// e.g. on Sundays after the configured hour.
+///
+/// The day is counted as done only when the peers of the push are loaded, so a failed query
+/// is retried by the next call. The date of the last push is kept in memory only,
+/// so the bot restarted after the hour sends the push of the day once again.
+#[derive(Default)]
+pub(crate) struct ScheduledPush {
+    last_date: Mutex<Option<NaiveDate>>,
+}
+
+impl ScheduledPush {
+    /// Load the peers with `load_peers` if `is_due` allows the push at the current time,
+    /// given the date of the last push. Returns the current time and the peers,
+    /// or `None` if it is not time to send the push.
+    async fn peers_if_due<T>(
+        &self,
+        is_due: impl FnOnce(NaiveDateTime, Option<NaiveDate>) -> bool,
+        load_peers: impl Future<Output = anyhow::Result<T>>,
+    ) -> anyhow::Result<Option<(NaiveDateTime, T)>> {
+        let now = Local::now().naive_local();
+        let last_date = *self.last_date.lock().unwrap();
+        if !is_due(now, last_date) {
+            return Ok(None);
+        }
+        let peers = load_peers.await?;
+        *self.last_date.lock().unwrap() = Some(now.date());
+        Ok(Some((now, peers)))
+    }
+}
+
+/// Replies of the push for the `peers`. The data of each schedule is loaded once with `load`,
+/// which logs its own errors, and the peers get nothing if there is no data or no `reply`.
+async fn replies_by_schedule<T, F>(
+    peers: Vec<(PlatformId, Peer)>,
+    load: impl Fn(String, ScheduleType) -> F,
+    reply: impl Fn(&T, &Peer) -> Option<Reply>,
+) -> Vec<(PlatformId, Reply, ReplyContext)>
+where
+    F: Future<Output = Option<T>>,
+{
+    let mut loaded = HashMap::<(String, ScheduleType), Option<T>>::new();
+    let mut replies = Vec::with_capacity(peers.len());
+    for (platform_id, peer) in peers {
+        let key = (
+            peer.selected_schedule.to_owned(),
+            peer.selected_schedule_type.to_owned(),
+        );
+        if !loaded.contains_key(&key) {
+            let data = load(key.0.clone(), key.1.clone()).await;
+            loaded.insert(key.clone(), data);
+        }
+        if let Some(reply) = loaded[&key].as_ref().and_then(|data| reply(data, &peer)) {
+            replies.push((platform_id, reply, ReplyContext::from(&peer)));
+        }
+    }
+    replies
+}
+
+/// Opt-in evening push with the first class of tomorrow and the time to leave home,
+/// e.g. "Завтра первая пара в 09:20 в К-601, выходи к 08:40".
+///
+/// Summaries are prepared once a day after the configured hour for all peers with
+/// the commute time preference, sending them is up to the platform-specific bots.
+pub struct EveningSummaryUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) config: Reloadable<EveningSummaryConfig>,
+    pub(crate) push: ScheduledPush,
+}
+
+/// Configuration of [EveningSummaryUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct EveningSummaryConfig {
+    /// Summaries are sent not earlier than this hour
+    pub hour: u32,
+}
+
+impl Default for EveningSummaryConfig {
+    fn default() -> Self {
+        Self {
+            hour: env::get_parsed_or("BOT_EVENING_SUMMARY_HOUR", 20),
+        }
+    }
+}
+
+impl EveningSummaryConfig {
+    fn should_summarize(&self, now: NaiveDateTime, last_summary_date: Option<NaiveDate>) -> bool {
+        now.hour() >= self.hour && last_summary_date != Some(now.date())
+    }
+}
+
+#[async_trait]
+impl PushUseCase for EveningSummaryUseCase {
+    fn name(&self) -> &'static str {
+        "evening summary"
+    }
+
+    /// Peers without classes tomorrow get nothing.
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        let config = self.config.load();
+        let Some((now, peers)) = self
+            .push
+            .peers_if_due(
+                |now, last_date| config.should_summarize(now, last_date),
+                self.peer_repository
+                    .get_peers_with_push(platform_id, PushOptIn::EveningSummary),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        let tomorrow = now.date() + chrono::Duration::days(1);
+        // tomorrow is in the next week on Sundays
+        let week_offset = (tomorrow.week(Weekday::Mon).first_day()
+            - now.date().week(Weekday::Mon).first_day())
+        .num_weeks() as i8;
+
+        info!("Preparing evening summaries for {} peers", peers.len());
+        let schedule_repository = &self.schedule_repository;
+        Ok(replies_by_schedule(
+            peers,
+            |name, r#type| async move {
+                match schedule_repository
+                    .get_schedule(&name, &r#type, week_offset)
+                    .await
+                {
+                    Ok(schedule) => schedule
+                        .weeks
+                        .into_iter()
+                        .flat_map(|week| week.days)
+                        .find(|day| day.date == tomorrow),
+                    Err(e) => {
+                        error!("Cannot get schedule for evening summary: {e}");
+                        None
+                    }
+                }
+            },
+            |day, peer| evening_summary(day, peer.commute_minutes?),
+        )
+        .await)
+    }
+}
+
+/// Summary of the `day` for the peer, `None` if there are no classes
+fn evening_summary(day: &Day, commute_minutes: u16) -> Option<Reply> {
+    let first_class = day
+        .classes
+        .iter()
+        .filter(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+        .min_by_key(|cls| cls.time.start)?;
+    Some(Reply::EveningSummary {
+        first_class: Box::new(first_class.to_owned()),
+        leave_at: first_class.time.start - chrono::Duration::minutes(commute_minutes as i64),
+    })
+}
+
+/// Opt-in push, which is sent on Sunday evenings after the hour configured
+/// with its own environment variable, see [SundayPushConfig]
+pub trait SundayPush {
+    /// Name of the environment variable with the hour
+    const HOUR_KEY: &'static str;
+    const DEFAULT_HOUR: u32;
+}
+
+/// Configuration of the [SundayPush] use cases
+pub struct SundayPushConfig<P> {
+    /// Pushes are sent on Sundays not earlier than this hour
+    pub hour: u32,
+    push: PhantomData<fn() -> P>,
+}
+
+impl<P> SundayPushConfig<P> {
+    pub fn new(hour: u32) -> Self {
+        Self {
+            hour,
+            push: PhantomData,
+        }
+    }
+
+    fn should_send(&self, now: NaiveDateTime, last_push_date: Option<NaiveDate>) -> bool {
+        now.weekday() == Weekday::Sun
+            && now.hour() >= self.hour
+            && last_push_date != Some(now.date())
+    }
+}
+
+impl<P: SundayPush> Default for SundayPushConfig<P> {
+    fn default() -> Self {
+        Self::new(env::get_parsed_or(P::HOUR_KEY, P::DEFAULT_HOUR))
+    }
+}
+
+/// Opt-in Sunday evening push with the summary of the schedule changes detected
+/// during the past week, e.g. "перенесены 2 пары, добавлена 1 пара".
+///
+/// Changelogs are prepared once a week after the configured hour for all peers which
+/// enabled them, sending them is up to the platform-specific bots.
+pub struct WeeklyChangelogUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) config: Reloadable<SundayPushConfig<Self>>,
+    pub(crate) push: ScheduledPush,
+}
+
+impl SundayPush for WeeklyChangelogUseCase {
+    const HOUR_KEY: &'static str = "BOT_WEEKLY_CHANGELOG_HOUR";
+    const DEFAULT_HOUR: u32 = 19;
+}
+
+/// Changes of the past week are included into the changelog
+const CHANGELOG_DAYS: u32 = 7;
+
+#[async_trait]
+impl PushUseCase for WeeklyChangelogUseCase {
+    fn name(&self) -> &'static str {
+        "weekly changelog"
+    }
+
+    /// Peers without changes of their schedules get nothing.
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        let config = self.config.load();
+        let Some((_, peers)) = self
+            .push
+            .peers_if_due(
+                |now, last_date| config.should_send(now, last_date),
+                self.peer_repository
+                    .get_peers_with_push(platform_id, PushOptIn::WeeklyChangelog),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        info!("Preparing weekly changelogs for {} peers", peers.len());
+        let schedule_repository = &self.schedule_repository;
+        Ok(replies_by_schedule(
+            peers,
+            |name, r#type| async move {
+                match schedule_repository
+                    .get_changelog(&name, &r#type, CHANGELOG_DAYS)
+                    .await
+                {
+                    Ok(changelog) => Some(aggregate_changes(&name, &changelog.changes)),
+                    Err(e) => {
+                        error!("Cannot get schedule changelog: {e}");
+                        None
+                    }
+                }
+            },
+            |digest, _| (!digest.is_empty()).then(|| Reply::WeeklyChangelog(digest.to_owned())),
+        )
+        .await)
+    }
+}
+
+/// Count the changes by their kind. Removed and added classes with the same name
+/// are paired as moved ones, because this is how a transfer of the classes looks in the diff.
+fn aggregate_changes(schedule_name: &str, changes: &[ClassesChange]) -> ChangelogDigest {
+    let mut digest = ChangelogDigest {
+        schedule_name: schedule_name.to_owned(),
+        ..Default::default()
+    };
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for change in changes {
+        match (&change.kind, &change.before, &change.after) {
+            (ClassesChangeKind::Added, _, Some(after)) => added.push(&after.name),
+            (ClassesChangeKind::Removed, Some(before), _) => removed.push(&before.name),
+            _ => digest.changed += 1,
+        }
+    }
+    for name in removed {
+        match added.iter().position(|it| *it == name) {
+            Some(index) => {
+                added.remove(index);
+                digest.moved += 1;
+            }
+            None => digest.removed += 1,
+        }
+    }
+    digest.added = added.len();
+    digest
+}
+
+/// Opt-in Sunday evening push with the summary of the upcoming week: number of classes
+/// of each day, the start of the first class and the labs, which usually require preparation.
+///
+/// Summaries are prepared once a week after the configured hour for all peers which
+/// enabled them, sending them is up to the platform-specific bots.
+pub struct WeekAheadUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) config: Reloadable<SundayPushConfig<Self>>,
+    pub(crate) push: ScheduledPush,
+}
+
+impl SundayPush for WeekAheadUseCase {
+    const HOUR_KEY: &'static str = "BOT_WEEK_AHEAD_HOUR";
+    const DEFAULT_HOUR: u32 = 18;
+}
+
+#[async_trait]
+impl PushUseCase for WeekAheadUseCase {
+    fn name(&self) -> &'static str {
+        "week ahead summary"
+    }
+
+    /// Peers without classes next week get nothing.
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        let config = self.config.load();
+        let Some((_, peers)) = self
+            .push
+            .peers_if_due(
+                |now, last_date| config.should_send(now, last_date),
+                self.peer_repository
+                    .get_peers_with_push(platform_id, PushOptIn::WeekAhead),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        info!("Preparing week ahead summaries for {} peers", peers.len());
+        let schedule_repository = &self.schedule_repository;
+        Ok(replies_by_schedule(
+            peers,
+            |name, r#type| async move {
+                match schedule_repository.get_schedule(&name, &r#type, 1).await {
+                    Ok(schedule) => schedule
+                        .weeks
+                        .first()
+                        .map(|week| week_ahead_digest(&name, week)),
+                    Err(e) => {
+                        error!("Cannot get schedule for week ahead summary: {e}");
+                        None
+                    }
+                }
+            },
+            |digest, _| (!digest.is_empty()).then(|| Reply::WeekAhead(digest.to_owned())),
+        )
+        .await)
+    }
+}
+
+/// Summary of the days of the week, cancelled classes are not counted
+fn week_ahead_digest(schedule_name: &str, week: &Week) -> WeekAheadDigest {
+    let days = week
+        .days
+        .iter()
+        .filter_map(|day| {
+            let classes = day
+                .classes
+                .iter()
+                .filter(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+                .collect::<Vec<_>>();
+            let earliest_start = classes.iter().map(|cls| cls.time.start).min()?;
+            let mut labs = Vec::<String>::new();
+            for cls in classes.iter().filter(|cls| cls.r#type == ClassesType::Lab) {
+                if !labs.contains(&cls.name) {
+                    labs.push(cls.name.to_owned());
+                }
+            }
+            Some(WeekAheadDay {
+                date: day.date,
+                classes_count: classes.len(),
+                earliest_start,
+                labs,
+            })
+        })
+        .collect();
+    WeekAheadDigest {
+        schedule_name: schedule_name.to_owned(),
+        days,
+    }
+}
+
+/// Opt-in Sunday evening push with the full schedule of the next week,
+/// rendered the same way as the reply to `/nextweek`.
+///
+/// Digests are prepared once a week after the configured hour for all peers which
+/// enabled them, sending them is up to the platform-specific bots.
+pub struct WeeklyDigestUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) config: Reloadable<SundayPushConfig<Self>>,
+    pub(crate) push: ScheduledPush,
+}
+
+impl SundayPush for WeeklyDigestUseCase {
+    const HOUR_KEY: &'static str = "BOT_WEEKLY_DIGEST_HOUR";
+    const DEFAULT_HOUR: u32 = 19;
+}
+
+#[async_trait]
+impl PushUseCase for WeeklyDigestUseCase {
+    fn name(&self) -> &'static str {
+        "weekly digest"
+    }
+
+    /// Peers without classes next week get nothing.
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        let config = self.config.load();
+        let Some((_, peers)) = self
+            .push
+            .peers_if_due(
+                |now, last_date| config.should_send(now, last_date),
+                self.peer_repository
+                    .get_peers_with_push(platform_id, PushOptIn::WeeklyDigest),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+
+        info!("Preparing weekly digests for {} peers", peers.len());
+        let schedule_repository = &self.schedule_repository;
+        Ok(replies_by_schedule(
+            peers,
+            |name, r#type| async move {
+                schedule_repository
+                    .get_schedule_with_renders(&name, &r#type, 1)
+                    .await
+                    .map_err(|e| error!("Cannot get schedule for weekly digest: {e}"))
+                    .ok()
+            },
+            |(schedule, rendered), peer| weekly_digest(schedule, rendered, peer),
+        )
+        .await)
+    }
+}
+
+/// Reply with the next week of the schedule, `None` if there are no classes in the days of the peer.
+/// The digests of the same schedule share the `rendered` texts.
+fn weekly_digest(schedule: &Schedule, rendered: &RenderedWeeks, peer: &Peer) -> Option<Reply> {
+    let mut week = schedule.weeks.first()?.clone();
+    week.retain_days(&peer.days_range);
+    if week.days.iter().all(|day| day.classes.is_empty()) {
+        return None;
+    }
+    Some(Reply::Week {
+        schedule_name: schedule.name.to_owned(),
+        week_offset: 1,
+        week,
+        schedule_type: schedule.r#type.to_owned(),
+        map_provider: peer.map_provider.to_owned(),
+        notes: Vec::new(),
+        full: false,
+        rendered: Some(rendered.to_owned()),
+    })
+}
+
+/// Opt-in push with the changes of the selected schedule, sent as soon as `app_schedule`
+/// detects them, e.g. "Физика перенесена в К-601".
+///
+/// The changelogs are polled once per interval for all schedules of the subscribed peers.
+/// Changelog entries have no detection time, so the changes of the previous poll are kept
+/// in memory and only the unseen ones are pushed. The first poll of the schedule after
+/// the start of the bot pushes nothing and only remembers the changes.
+///
+/// New room changes of today's and tomorrow's classes are also kept as warnings
+/// for the next day schedule reply of the peer, see [RoomChangeWarningsRepository].
+pub struct ScheduleChangeNotificationsUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) schedule_repository: Arc<ScheduleRepository>,
+    pub(crate) room_change_warnings_repository: Arc<RoomChangeWarningsRepository>,
+    pub(crate) config: Reloadable<ScheduleChangeNotificationsConfig>,
+    pub(crate) last_poll: Mutex<Option<Instant>>,
+    pub(crate) seen_changes: Mutex<HashMap<(String, ScheduleType), Vec<ClassesChange>>>,
+}
+
+/// Configuration of [ScheduleChangeNotificationsUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleChangeNotificationsConfig {
+    pub poll_interval: Duration,
+}
+
+impl Default for ScheduleChangeNotificationsConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(
+                env::get_parsed_or("BOT_SCHEDULE_CHANGES_POLL_MINUTES", 30) * 60,
+            ),
+        }
+    }
+}
+
+/// Changes of the last day are enough, as long as the poll interval is shorter
+const SCHEDULE_CHANGES_DAYS: u32 = 1;
+
+#[async_trait]
+impl PushUseCase for ScheduleChangeNotificationsUseCase {
+    fn name(&self) -> &'static str {
+        "schedule changes"
+    }
+
+    /// It is time to poll the changelogs once per interval.
+    /// Peers without new changes of their schedules get nothing.
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        {
+            let mut last_poll = self.last_poll.lock().unwrap();
+            if last_poll.is_some_and(|it| it.elapsed() < self.config.load().poll_interval) {
+                return Ok(Vec::new());
+            }
+            *last_poll = Some(Instant::now());
+        }
+
+        let peers = self
+            .peer_repository
+            .get_peers_with_push(platform_id, PushOptIn::ScheduleChanges)
+            .await?;
+        let today = Local::now().date_naive();
+        let mut new_changes = HashMap::<(String, ScheduleType), Vec<ClassesChange>>::new();
+        let mut notifications = Vec::new();
+        for (platform_id, peer) in peers {
+            let key = (
+                peer.selected_schedule.to_owned(),
+                peer.selected_schedule_type.to_owned(),
+            );
+            if !new_changes.contains_key(&key) {
+                let changes = match self
+                    .schedule_repository
+                    .get_changelog(&key.0, &key.1, SCHEDULE_CHANGES_DAYS)
+                    .await
+                {
+                    Ok(changelog) => {
+                        let previous = self
+                            .seen_changes
+                            .lock()
+                            .unwrap()
+                            .insert(key.clone(), changelog.changes.clone());
+                        detect_new_changes(previous.as_deref(), &changelog.changes, today)
+                    }
+                    Err(e) => {
+                        error!("Cannot get schedule changelog: {e}");
+                        Vec::new()
+                    }
+                };
+                new_changes.insert(key.clone(), changes);
+            }
+            let changes = &new_changes[&key];
+            self.room_change_warnings_repository
+                .add_warnings(peer.id, &room_change_warnings(changes, today));
+            if !changes.is_empty() {
+                notifications.push((
+                    platform_id,
+                    Reply::ScheduleChanged {
+                        schedule_name: key.0.clone(),
+                        changes: changes.to_owned(),
+                    },
+                    ReplyContext::from(&peer),
+                ));
+            }
+        }
+        if !notifications.is_empty() {
+            info!(
+                "Prepared {} schedule change notifications",
+                notifications.len()
+            );
+        }
+        Ok(notifications)
+    }
+}
+
+/// Changes which were not seen during the previous poll, except the changes of the past days.
+/// Nothing is new without the previous poll, otherwise the whole changelog would be pushed.
+fn detect_new_changes(
+    previous: Option<&[ClassesChange]>,
+    changes: &[ClassesChange],
+    today: NaiveDate,
+) -> Vec<ClassesChange> {
+    let Some(previous) = previous else {
+        return Vec::new();
+    };
+    changes
+        .iter()
+        .filter(|it| it.date >= today && !previous.contains(it))
+        .cloned()
+        .collect()
+}
+
+/// Classes of today and tomorrow, which are moved to another room without other changes
+fn room_change_warnings(changes: &[ClassesChange], today: NaiveDate) -> Vec<RoomChangeWarning> {
+    changes
+        .iter()
+        .filter(|it| it.kind == ClassesChangeKind::Changed)
+        .filter(|it| it.date >= today && (it.date - today).num_days() <= 1)
+        .filter_map(|it| {
+            let (before, after) = (it.before.as_ref()?, it.after.as_ref()?);
+            let is_moved = before.name == after.name
+                && before.time == after.time
+                && before.place != after.place
+                && !after.place.trim().is_empty();
+            is_moved.then(|| RoomChangeWarning {
+                date: it.date,
+                class_name: after.name.to_owned(),
+                time: after.time.to_owned(),
+                place: after.place.to_owned(),
+            })
+        })
+        .collect()
+}
+
+lazy_static! {
+    static ref EMAIL_PATTERN: Regex = Regex::new(r"[\w.+-]+@[\w-]+\.[\w.-]+").unwrap();
+    static ref PHONE_PATTERN: Regex = Regex::new(r"\+?\d[\d\s()-]{8,}\d").unwrap();
+}
+
+/// Single message to the peers which have been inactive for a long time,
+/// reminding that their schedule is still available.
+///
+/// Activity is the time of the last message of the peer, peers in privacy mode are not tracked
+/// and never contacted. The campaign runs once a day during the safe hours, each peer is marked
+/// as contacted before sending, so nobody gets the message twice, even if sending fails.
+/// The day is done once the inactive peers are loaded. Its date is kept in memory only,
+/// but a restart does not repeat the campaign for the contacted peers, they are marked already.
+pub struct ReEngagementUseCase {
+    pub(crate) peer_repository: Arc<dyn PeerRepository>,
+    pub(crate) peer_activity_repository: Arc<dyn PeerActivityRepository>,
+    pub(crate) config: Reloadable<ReEngagementConfig>,
+    pub(crate) push: ScheduledPush,
+}
+
+/// Configuration of [ReEngagementUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReEngagementConfig {
+    pub enabled: bool,
+    /// Peers are inactive if they sent nothing for this number of weeks
+    pub inactive_weeks: u32,
+    /// Messages are sent not earlier than this hour and earlier than [REENGAGEMENT_LATEST_HOUR]
+    pub hour: u32,
+    /// Max number of peers contacted a day
+    pub batch_size: usize,
+}
+
+impl Default for ReEngagementConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env::get_parsed_or("BOT_REENGAGEMENT_ENABLED", false),
+            inactive_weeks: env::get_parsed_or("BOT_REENGAGEMENT_INACTIVE_WEEKS", 6),
+            hour: env::get_parsed_or("BOT_REENGAGEMENT_HOUR", 12),
+            batch_size: env::get_parsed_or("BOT_REENGAGEMENT_BATCH_SIZE", 200),
+        }
+    }
+}
+
+/// Nobody wants to get a message from the bot late in the evening
+const REENGAGEMENT_LATEST_HOUR: u32 = 20;
+
+impl ReEngagementConfig {
+    fn should_run(&self, now: NaiveDateTime, last_campaign_date: Option<NaiveDate>) -> bool {
+        self.enabled
+            && (self.hour..REENGAGEMENT_LATEST_HOUR).contains(&now.hour())
+            && last_campaign_date != Some(now.date())
+    }
+}
+
+impl ReEngagementUseCase {
+    /// Remember the time of the message of the peer, unless the peer is in privacy mode.
+    /// Errors are only logged, because the activity tracking must not break replies.
+    pub async fn record_activity_if_needed(&self, peer: &Peer) {
+        if peer.privacy_mode {
+            return;
+        }
+        if let Err(e) = self.peer_activity_repository.record_activity(peer.id).await {
+            error!("Error while recording peer activity: {e}");
+        }
+    }
+
+    /// Stats of the campaign for the admin endpoint
+    pub async fn get_stats(&self) -> anyhow::Result<ReEngagementStats> {
+        self.peer_activity_repository
+            .get_reengagement_stats(self.config.load().inactive_weeks)
+            .await
+    }
+}
+
+#[async_trait]
+impl PushUseCase for ReEngagementUseCase {
+    fn name(&self) -> &'static str {
+        "re-engagement message"
+    }
+
+    async fn get_pushes_if_needed(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<(PlatformId, Reply, ReplyContext)>> {
+        let config = self.config.load();
+        let Some((_, peers)) = self
+            .push
+            .peers_if_due(
+                |now, last_date| config.should_run(now, last_date),
+                self.peer_repository.get_inactive_peers(
+                    platform_id,
+                    config.inactive_weeks,
+                    config.batch_size,
+                ),
+            )
+            .await?
+        else {
+            return Ok(Vec::new());
+        };
+        if peers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let peer_ids = peers.iter().map(|(_, peer)| peer.id).collect::<Vec<_>>();
+        self.peer_activity_repository
+            .mark_reengaged(&peer_ids)
+            .await?;
+        info!("Preparing re-engagement messages for {} peers", peers.len());
+        Ok(peers
+            .into_iter()
+            .map(|(platform_id, peer)| {
+                let context = ReplyContext::from(&peer);
+                let reply = Reply::ReEngagement {
+                    schedule_name: peer.selected_schedule,
+                };
+                (platform_id, reply, context)
+            })
+            .collect())
+    }
+}
+
+/// How many of the latest replies are included into the data export
+const EXPORT_RECENT_REPLIES: usize = 50;
+
+/// Collect everything the bot stores about the peer, so the user can see it ("/export")
+pub struct ExportPeerDataUseCase {
+    pub(crate) recent_schedules_repository: Arc<dyn RecentSchedulesRepository>,
+    pub(crate) achievements_repository: Arc<dyn AchievementsRepository>,
+    pub(crate) audit_repository: Arc<dyn AuditRepository>,
+    pub(crate) member_schedules_repository: Arc<dyn MemberSchedulesRepository>,
+}
+
+impl ExportPeerDataUseCase {
+    pub async fn export(&self, platform_id: &PlatformId, peer: &Peer) -> anyhow::Result<Reply> {
+        let (platform, platform_peer_id) = platform_id.split();
+        let achievements = if peer.achievements_enabled {
+            Some(self.achievements_repository.get_stats(peer.id).await?)
+        } else {
+            None
+        };
+        let mut subscriptions = Vec::new();
+        if peer.commute_minutes.is_some() {
+            subscriptions.push("evening_summary");
+        }
+        if peer.changelog_enabled {
+            subscriptions.push("weekly_changelog");
+        }
+        if peer.week_ahead_enabled {
+            subscriptions.push("week_ahead");
+        }
+        if peer.digest_enabled {
+            subscriptions.push("weekly_digest");
+        }
+        if peer.subscribed {
+            subscriptions.push("schedule_changes");
+        }
+        Ok(Reply::DataExport(Box::new(PeerDataExport {
+            exported_at: Local::now().naive_local(),
+            platform,
+            platform_peer_id,
+            selected_schedule: (!peer.selected_schedule.is_empty()).then(|| RecentSchedule {
+                name: peer.selected_schedule.to_owned(),
+                r#type: peer.selected_schedule_type.to_owned(),
+            }),
+            preferences: PeerPreferences {
+                locale: peer.locale.as_ref().map(|it| it.as_ref().to_owned()),
+                map_provider: peer.map_provider.as_ref().map(|it| it.as_ref().to_owned()),
+                days_range: peer.days_range.to_string(),
+                commute_minutes: peer.commute_minutes,
+                privacy_mode: peer.privacy_mode,
+                achievements_enabled: peer.achievements_enabled,
+                ics_enabled: peer.ics_enabled,
+                newcomer_mode: peer.newcomer_mode,
+                theme: peer.theme.as_ref().map(|it| it.as_ref().to_owned()),
+            },
+            subscriptions,
+            recent_schedules: self
+                .recent_schedules_repository
+                .get_recent_schedules(peer.id)
+                .await?,
+            achievements,
+            recent_replies: self
+                .audit_repository
+                .get_recent_replies(platform, platform_peer_id, EXPORT_RECENT_REPLIES)
+                .await?,
+            member_schedules: self
+                .member_schedules_repository
+                .get_member_schedules(peer.id)
+                .await?
+                .into_iter()
+                .map(|(member, schedule)| MemberScheduleExport {
+                    member_id: member.id,
+                    username: member.username,
+                    schedule,
+                })
+                .collect(),
+            deleted_at: peer.deleted_at,
+        })))
+    }
+}
+
+/// Save redacted copies of outgoing replies to the audit table, so support
+/// can see exactly what the bot sent. Disabled by default.
+pub struct AuditReplyUseCase {
+    pub(crate) audit_repository: Arc<dyn AuditRepository>,
+    pub(crate) config: Reloadable<AuditConfig>,
+    pub(crate) last_cleanup: Mutex<Option<Instant>>,
+}
+
+/// Configuration of [AuditReplyUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditConfig {
+    pub enabled: bool,
+    /// Entries older than this number of days are deleted
+    pub retention_days: u32,
+    /// Longer reply texts are truncated
+    pub max_text_length: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: env::get_parsed_or("BOT_AUDIT_LOG_ENABLED", false),
+            retention_days: env::get_parsed_or("BOT_AUDIT_LOG_RETENTION_DAYS", 30),
+            max_text_length: env::get_parsed_or("BOT_AUDIT_LOG_MAX_TEXT_LENGTH", 512),
+        }
+    }
+}
+
+const AUDIT_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+impl AuditReplyUseCase {
+    /// Save the reply to the audit table if the audit log is enabled
+    /// and the peer is not in privacy mode.
+    /// Errors are only logged, because the audit must not break replies.
+    pub async fn audit_reply(
+        &self,
+        platform_id: &PlatformId,
+        context: &ReplyContext,
+        reply: &Reply,
+        text: &str,
+        keyboard_version: Option<&'static str>,
+        latency: Duration,
+    ) {
+        let config = self.config.load();
+        if !config.enabled || context.privacy_mode {
+            return;
+        }
+        let (platform, platform_peer_id) = platform_id.split();
+        let entry = ReplyAuditEntry {
+            platform,
+            platform_peer_id,
+            reply_variant: reply.variant_name(),
+            text: redact_text(text, config.max_text_length),
+            keyboard_version,
+            latency_ms: latency.as_millis().try_into().unwrap_or(i32::MAX),
+        };
+        if let Err(e) = self.audit_repository.insert_entry(&entry).await {
+            error!("Error while saving reply audit entry: {e}");
+        }
+        if self.is_cleanup_needed() {
+            match self
+                .audit_repository
+                .delete_expired(config.retention_days)
+                .await
+            {
+                Ok(deleted) => info!("Deleted {deleted} expired reply audit entries"),
+                Err(e) => error!("Error while deleting expired reply audit entries: {e}"),
+            }
+        }
+    }
+
+    fn is_cleanup_needed(&self) -> bool {
+        let mut last_cleanup = self.last_cleanup.lock().unwrap();
+        match *last_cleanup {
+            Some(instant) if instant.elapsed() < AUDIT_CLEANUP_INTERVAL => false,
+            _ => {
+                *last_cleanup = Some(Instant::now());
+                true
+            }
+        }
+    }
+}
+
+/// Hide emails and phone numbers, and truncate the text to `max_length` chars
+fn redact_text(text: &str, max_length: usize) -> String {
+    let text = EMAIL_PATTERN.replace_all(text, "[email]");
+    let text = PHONE_PATTERN.replace_all(&text, "[phone]");
+    if text.chars().count() > max_length {
+        let mut truncated = text.chars().take(max_length).collect::<String>();
+        truncated.push('…');
+        truncated
+    } else {
+        text.into_owned()
+    }
+}
+
+/// At-least-once delivery of the replies: the replies failed because of the transient
+/// errors are queued and sent again with exponential backoff, the ones which failed
+/// permanently are logged as dead letters.
+pub struct ReplyRetryUseCase {
+    pub(crate) reply_queue_repository: Arc<ReplyQueueRepository>,
+    pub(crate) config: Reloadable<ReplyRetryConfig>,
+}
+
+/// Configuration of [ReplyRetryUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyRetryConfig {
+    /// Delivery attempts of each reply, including the first one
+    pub max_attempts: i32,
+    /// Delay before the second attempt, it doubles after each next failed attempt
+    pub backoff: Duration,
+}
+
+impl Default for ReplyRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: env::get_parsed_or("BOT_REPLY_RETRY_MAX_ATTEMPTS", 5),
+            backoff: Duration::from_secs(env::get_parsed_or("BOT_REPLY_RETRY_BACKOFF_SECS", 10)),
+        }
+    }
+}
+
+/// How many chars of the lost reply are logged with the dead letter
+const DEAD_LETTER_TEXT_LENGTH: usize = 64;
+
+impl ReplyRetryUseCase {
+    /// Queue the reply failed with `error` for the next attempt, if the error is transient.
+    ///
+    /// Returns `true` if the reply is queued, so its delivery is not the caller's concern anymore.
+    pub async fn enqueue(
+        &self,
+        platform_id: &PlatformId,
+        text: &str,
+        keyboard: Option<&str>,
+        error: &anyhow::Error,
+    ) -> bool {
+        let config = self.config.load();
+        if !is_transient_error(error) || config.max_attempts <= 1 {
+            log_dead_letter(platform_id, 1, text, error);
+            return false;
+        }
+        match self
+            .reply_queue_repository
+            .push(platform_id, text, keyboard, 1, config.backoff)
+            .await
+        {
+            Ok(()) => {
+                warn!("Reply to {platform_id:?} is queued for the next attempt: {error:#}");
+                true
+            }
+            Err(e) => {
+                error!("Error while queueing reply: {e}");
+                log_dead_letter(platform_id, 1, text, error);
+                false
+            }
+        }
+    }
+
+    /// Get the queued replies of the platform, which are ready for the next attempt.
+    /// `platform_id` is the constructor of the platform id, e.g. [PlatformId::telegram].
+    pub async fn get_due_replies(
+        &self,
+        platform_id: fn(i64) -> PlatformId,
+    ) -> anyhow::Result<Vec<PendingReply>> {
+        self.reply_queue_repository.get_due(platform_id).await
+    }
+
+    /// Remove the delivered reply from the queue, or schedule its next attempt
+    pub async fn report_attempt(
+        &self,
+        reply: &PendingReply,
+        result: anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let Err(error) = result else {
+            info!("Queued reply to {:?} is delivered", reply.platform_id);
+            return self.reply_queue_repository.remove(reply.id).await;
+        };
+        let attempts = reply.attempts + 1;
+        let config = self.config.load();
+        if is_transient_error(&error) && attempts < config.max_attempts {
+            let delay = config.backoff * 2u32.pow(attempts as u32 - 1);
+            self.reply_queue_repository
+                .reschedule(reply.id, attempts, delay)
+                .await
+        } else {
+            log_dead_letter(&reply.platform_id, attempts, &reply.text, &error);
+            self.reply_queue_repository.remove(reply.id).await
+        }
+    }
+}
+
+/// Only network and server failures may disappear by themselves,
+/// the replies rejected by the platform api will be rejected again
+fn is_transient_error(error: &anyhow::Error) -> bool {
+    matches!(error.as_common_error(), Some(CommonError::GatewayError(_)))
+}
+
+fn log_dead_letter(platform_id: &PlatformId, attempts: i32, text: &str, error: &anyhow::Error) {
+    error!(
+        "Dead letter: reply to {platform_id:?} is not delivered after {attempts} attempt(s), \
+        text: \"{}\", last error: {error:#}",
+        redact_text(text, DEAD_LETTER_TEXT_LENGTH),
+    );
+}
+
+#[cfg(test)]
+mod t2a_tests {
+    use chrono::{NaiveDate, Weekday};
+    use domain_buildings::MapProvider;
+    use domain_schedule_models::DaysRange;
+
+    use crate::{
+        models::{Locale, MemberMention, UserAction},
+        theme::ThemeKind,
+    };
+
+    use super::{parse_note, TextToActionUseCase};
+
+    macro_rules! test_t2a {
+        ($name:tt, $exp:expr, $inputs:expr) => {
+            #[test]
+            fn $name() {
+                let use_case = TextToActionUseCase;
+                for text in $inputs {
+                    assert_eq!(use_case.text_to_action(text).unwrap(), $exp);
+                }
+            }
+        };
+    }
+
+    test_t2a!(
+        action_start,
+        UserAction::Start,
+        ["старт", "начать", "start", "/start"]
+    );
+
+    test_t2a!(
+        action_status,
+        UserAction::UpcomingEvents,
+        ["статус", "ближайшие пары", "ближайшие", "status", "/status"]
+    );
+
+    test_t2a!(
+        action_remaining_today,
+        UserAction::RemainingToday,
+        [
+            "сколько пар осталось",
+            "Сколько пар осталось сегодня",
+            "сколько осталось пар",
+            "сколько осталось",
+            "remaining",
+            "/remaining",
+        ]
+    );
+
+    test_t2a!(
+        action_help,
+        UserAction::Help,
+        ["помощь", "справка", "помоги", "help", "/help"]
+    );
+
+    test_t2a!(
+        action_change,
+        UserAction::ChangeScheduleIntent,
+        [
+            "сменить",
+            "сменить группу",
+            "сменить расписание",
+            "change",
+            "/change",
+        ]
+    );
+
+    test_t2a!(
+        action_recent,
+        UserAction::RecentSchedules,
+        [
+            "недавние",
+            "недавние расписания",
+            "последние расписания",
+            "recent",
+            "/recent",
+        ]
+    );
+
+    test_t2a!(
+        action_week_offset_0,
+        UserAction::WeekWithOffset(0),
+        ["неделя", "эта неделя", "this week", "/thisweek"]
+    );
+
+    test_t2a!(
+        action_week_offset_1,
+        UserAction::WeekWithOffset(1),
+        ["следующая неделя", "next week", "/nextweek"]
+    );
+
+    test_t2a!(
+        action_week_offset_m1,
+        UserAction::WeekWithOffset(-1),
+        ["прошлая неделя", "previous week", "/prevweek"]
+    );
+
+    test_t2a!(
+        action_month_offset_0,
+        UserAction::MonthWithOffset(0),
+        ["месяц", "этот месяц", "month", "this month", "/month"]
+    );
+
+    test_t2a!(
+        action_month_offset_1,
+        UserAction::MonthWithOffset(1),
+        ["следующий месяц", "next month", "/nextmonth"]
+    );
+
+    test_t2a!(
+        action_next_week_changes,
+        UserAction::NextWeekChanges,
+        [
+            "Что меняется на следующей неделе",
+            "изменения на следующей неделе",
+            "next week changes",
+            "/nextweek_changes"
+        ]
+    );
+
+    test_t2a!(
+        action_exams,
+        UserAction::Exams,
+        ["Экзамены", "сессия", "расписание сессии", "exams", "/exams"]
+    );
+
+    test_t2a!(
+        action_maps_yandex,
+        UserAction::SetMapProvider(Some(MapProvider::Yandex)),
+        ["карты яндекс", "/maps_yandex"]
+    );
+
+    test_t2a!(
+        action_maps_google,
+        UserAction::SetMapProvider(Some(MapProvider::Google)),
+        ["карты гугл", "карты google", "/maps_google"]
+    );
+
+    test_t2a!(
+        action_maps_off,
+        UserAction::SetMapProvider(None),
+        ["карты выкл", "/maps_off"]
+    );
+
+    test_t2a!(
+        action_days_mon_sat,
+        UserAction::SetDaysRange(DaysRange {
+            first: Weekday::Mon,
+            last: Weekday::Sat
+        }),
+        ["дни пн-сб", "без воскресенья", "/days_mon_sat"]
+    );
+
+    test_t2a!(
+        action_days_all,
+        UserAction::SetDaysRange(DaysRange::FULL_WEEK),
+        ["дни пн-вс", "все дни", "/days_all"]
+    );
+
+    test_t2a!(
+        action_achievements,
+        UserAction::Achievements,
+        ["достижения", "ачивки", "/achievements"]
+    );
+
+    test_t2a!(
+        action_achievements_on,
+        UserAction::SetAchievementsEnabled(true),
+        ["достижения вкл", "/achievements_on"]
+    );
+
+    test_t2a!(
+        action_achievements_off,
+        UserAction::SetAchievementsEnabled(false),
+        ["достижения выкл", "/achievements_off"]
+    );
+
+    test_t2a!(
+        action_locale_toggle,
+        UserAction::SetLocale(None),
+        ["язык", "language", "/language", "Language"]
+    );
+
+    test_t2a!(
+        action_locale_ru,
+        UserAction::SetLocale(Some(Locale::Ru)),
+        [
+            "язык русский",
+            "language russian",
+            "Language RU",
+            "/language ru",
+            "/language_ru",
+        ]
+    );
+
+    test_t2a!(
+        action_locale_en,
+        UserAction::SetLocale(Some(Locale::En)),
+        [
+            "Язык английский",
+            "language english",
+            "language en",
+            "/language en",
+            "/language_en",
+        ]
+    );
+
+    test_t2a!(
+        action_today,
+        UserAction::DayWithOffset(0),
+        [
+            "сегодня",
+            "сегодняшние",
+            "сегодняшний",
+            "/today",
+            "today",
+            "Today",
+            "пары сегодня",
+            "сегодняшние пары"
+        ]
+    );
+
+    test_t2a!(
+        action_yesterday,
+        UserAction::DayWithOffset(-1),
+        [
+            "вчера",
+            "вчерашние",
+            "вчерашний",
+            "/yesterday",
+            "yesterday",
+            "пары вчера",
+            "вчерашние пары"
+        ]
+    );
+
+    test_t2a!(
+        action_tomorrow,
+        UserAction::DayWithOffset(1),
+        [
+            "завтра",
+            "завтрашние",
+            "завтрашний",
+            "/tomorrow",
+            "tomorrow",
+            "пары завтра",
+            "завтрашние пары"
+        ]
+    );
+
+    test_t2a!(
+        action_day_after_tomorrow,
+        UserAction::DayWithOffset(2),
+        ["послезавтра", "послезавтрашние", "послезавтрашний"]
+    );
+
+    test_t2a!(
+        action_day_before_yesterday,
+        UserAction::DayWithOffset(-2),
+        ["позавчера", "позавчерашние", "позавчерашний"]
+    );
+
+    #[test]
+    fn action_specific_date() {
+        let use_case = TextToActionUseCase;
+        for text in [
+            "15 марта",
+            "пары на 15.03",
+            "через 3 дня",
+            "через неделю в пятницу",
+            "Next Tuesday",
+            "в следующий вторник",
+        ] {
+            let result = use_case.text_to_action(text).unwrap();
+            assert!(matches!(result, UserAction::SpecificDate(_)), "{text}");
+        }
+    }
+
+    #[test]
+    fn action_day_range() {
+        let use_case = TextToActionUseCase;
+        for text in [
+            "пары с понедельника по среду",
+            "с сегодня по пятницу",
+            "расписание на 3 дня",
+            "Schedule for 3 days",
+        ] {
+            let result = use_case.text_to_action(text).unwrap();
+            assert!(matches!(result, UserAction::DayRange { .. }), "{text}");
+        }
+    }
+
+    test_t2a!(
+        action_set_commute,
+        UserAction::SetCommute(Some(40)),
+        [
+            "/commute 40",
+            "/commute_40",
+            "дорога 40",
+            "дорога 40 мин",
+            "commute 40 min"
+        ]
+    );
+
+    test_t2a!(
+        action_commute_off,
+        UserAction::SetCommute(None),
+        ["/commute_off", "дорога выкл", "commute off"]
+    );
+
+    test_t2a!(
+        action_changelog_on,
+        UserAction::SetChangelogEnabled(true),
+        ["/changelog_on", "изменения вкл", "changelog on"]
+    );
+
+    test_t2a!(
+        action_changelog_off,
+        UserAction::SetChangelogEnabled(false),
+        ["/changelog_off", "изменения выкл", "changelog off"]
+    );
+
+    test_t2a!(
+        action_week_ahead_on,
+        UserAction::SetWeekAheadEnabled(true),
+        [
+            "/week_ahead_on",
+            "неделя вперёд вкл",
+            "Неделя вперед вкл",
+            "week ahead on"
+        ]
+    );
+
+    test_t2a!(
+        action_week_ahead_off,
+        UserAction::SetWeekAheadEnabled(false),
+        ["/week_ahead_off", "неделя вперёд выкл", "week ahead off"]
+    );
+
+    test_t2a!(
+        action_digest_on,
+        UserAction::EnableDigest,
+        ["/digest_on", "Дайджест вкл", "digest on"]
+    );
+
+    test_t2a!(
+        action_digest_off,
+        UserAction::DisableDigest,
+        ["/digest_off", "дайджест выкл", "digest off"]
+    );
+
+    test_t2a!(
+        action_subscribe,
+        UserAction::Subscribe,
+        ["/subscribe", "подписаться", "Subscribe"]
+    );
+
+    test_t2a!(
+        action_unsubscribe,
+        UserAction::Unsubscribe,
+        ["/unsubscribe", "Отписаться", "unsubscribe"]
+    );
+
+    test_t2a!(
+        action_ics_on,
+        UserAction::SetIcsEnabled(true),
+        ["/ics_on", "календарь вкл", "ics on"]
+    );
+
+    test_t2a!(
+        action_ics_off,
+        UserAction::SetIcsEnabled(false),
+        ["/ics_off", "календарь выкл", "ics off"]
+    );
+
+    test_t2a!(
+        action_export,
+        UserAction::ExportData,
+        ["/export", "экспорт", "Экспорт данных", "export my data"]
+    );
+
+    test_t2a!(
+        action_delete_data,
+        UserAction::DeleteData,
+        ["/deletemydata", "удалить мои данные", "Delete my data"]
+    );
+
+    test_t2a!(
+        action_restore_data,
+        UserAction::RestoreData,
+        ["/restore", "Восстановить данные", "restore my data"]
+    );
+
+    test_t2a!(
+        action_privacy_on,
+        UserAction::SetPrivacyMode(true),
+        ["/privacy", "/privacy_on", "Приватность", "privacy"]
+    );
+
+    test_t2a!(
+        action_privacy_off,
+        UserAction::SetPrivacyMode(false),
+        ["/privacy_off", "приватность выкл", "Privacy off"]
+    );
+
+    test_t2a!(
+        action_newcomer_on,
+        UserAction::SetNewcomerMode(true),
+        ["/newcomer_on", "новичок вкл", "Newcomer on"]
+    );
+
+    test_t2a!(
+        action_newcomer_off,
+        UserAction::SetNewcomerMode(false),
+        ["/newcomer_off", "новичок выкл", "Newcomer off"]
+    );
+
+    test_t2a!(
+        action_theme_minimal,
+        UserAction::SetTheme(ThemeKind::Minimal),
+        ["/theme_minimal", "тема минимальная", "Theme minimal"]
+    );
+
+    test_t2a!(
+        action_theme_high_contrast,
+        UserAction::SetTheme(ThemeKind::HighContrast),
+        ["/theme_contrast", "Тема контрастная", "theme high contrast"]
+    );
+
+    test_t2a!(action_ping, UserAction::Ping, ["/ping", "Пинг", "ping"]);
+
+    test_t2a!(
+        action_navigation,
+        UserAction::Navigation("К-601".to_owned()),
+        [
+            "Как пройти в К-601",
+            "как пройти в к-601?",
+            "Где находится К-601",
+            "Как добраться до к-601",
+            "how to get to К-601",
+            "/where к-601"
+        ]
+    );
+
+    test_t2a!(
+        action_full_week,
+        UserAction::FullWeekWithOffset(1),
+        [
+            "/nextweek_full",
+            "Показать полностью следующую неделю",
+            "show next week in full"
+        ]
+    );
+
+    test_t2a!(
+        action_link_member_schedule,
+        UserAction::LinkMemberSchedule("а-08-19".to_owned()),
+        ["/mygroup А-08-19", "Моя группа А-08-19", "my group а-08-19"]
+    );
+
+    test_t2a!(
+        action_unlink_member_schedule,
+        UserAction::UnlinkMemberSchedule,
+        ["/mygroup_off", "Моя группа выкл", "My group off"]
+    );
+
+    test_t2a!(
+        action_member_schedule_by_username,
+        UserAction::MemberSchedule {
+            member: MemberMention::Username("ivanov_ii".to_owned()),
+            day_offset: 0,
+        },
+        [
+            "пары @Ivanov_II",
+            "Пары @ivanov_ii сегодня",
+            "/schedule@mpeix_bot @ivanov_ii",
+            "schedule @ivanov_ii today"
+        ]
+    );
+
+    test_t2a!(
+        action_member_schedule_by_id,
+        UserAction::MemberSchedule {
+            member: MemberMention::Id(123),
+            day_offset: 1,
+        },
+        [
+            "Пары [id123|Иван] завтра",
+            "[club1|@mpeix] пары [id123|@ivanov], завтра",
+            "schedule [id123|Ivan] tomorrow"
+        ]
+    );
+
+    test_t2a!(
+        action_verify_schedule,
+        UserAction::VerifySchedule("а-08-19".to_owned()),
+        ["/verify а-08-19", "/verify_а-08-19"]
+    );
+
+    test_t2a!(
+        action_block,
+        UserAction::Block("telegram:123456".parse().unwrap()),
+        ["/block telegram:123456", "/block_telegram:123456"]
+    );
+
+    test_t2a!(
+        action_unblock,
+        UserAction::Unblock("vk:2000000*".parse().unwrap()),
+        ["/unblock vk:2000000*", "/UNBLOCK vk:2000000*"]
+    );
+
+    test_t2a!(action_blocklist, UserAction::Blocklist, ["/blocklist"]);
+
+    #[test]
+    fn action_block_invalid_pattern() {
+        let use_case = TextToActionUseCase;
+        for text in [
+            "/block telegram:*",
+            "/block *:",
+            "/block 123",
+            "/block tg:123",
+        ] {
+            let result = use_case.text_to_action(text).unwrap();
+            assert!(!matches!(result, UserAction::Block(_)), "{text}");
+        }
+    }
+
+    #[test]
+    fn action_commute_too_long() {
+        let use_case = TextToActionUseCase;
+        let result = use_case.text_to_action("/commute 999").unwrap();
+        assert!(!matches!(result, UserAction::SetCommute(_)));
+    }
+
+    #[test]
+    fn action_notes() {
+        let use_case = TextToActionUseCase;
+        let result = use_case
+            .text_to_action("/note завтра Контрольная по физике")
+            .unwrap();
+        assert!(
+            matches!(result, UserAction::AddNote { text, .. } if text == "Контрольная по физике")
+        );
+        let result = use_case.text_to_action("Удалить заметки 15.03").unwrap();
+        assert!(matches!(result, UserAction::RemoveNotes(_)));
+        // the note without the text is not a note
+        let result = use_case.text_to_action("/note завтра").unwrap();
+        assert!(!matches!(result, UserAction::AddNote { .. }));
+    }
+
+    #[test]
+    fn test_parse_note() {
+        // 2023-03-13 is Monday
+        let today = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2023, 3, day).unwrap();
+        assert_eq!(
+            parse_note("15 марта Контрольная по физике", today),
+            Some((date(15), "Контрольная по физике".to_owned()))
+        );
+        assert_eq!(
+            parse_note("15.03 Сдать  лабу", today),
+            Some((date(15), "Сдать лабу".to_owned()))
+        );
+        assert_eq!(
+            parse_note("через неделю в пятницу экзамен", today),
+            Some((date(24), "экзамен".to_owned()))
+        );
+        assert_eq!(parse_note("Контрольная по физике", today), None);
+    }
+
+    #[test]
+    fn action_day_of_week() {
+        let use_case = TextToActionUseCase;
+        for text in [
+            "понедельник",
+            "вторник",
+            "среда",
+            "четверг",
+            "пятница",
+            "суббота",
+            "воскресенье",
+            "пн",
+            "вт",
+            "ср",
+            "чт",
+            "пт",
+            "сб",
+            "вс",
+            "пары в понедельник",
+            "пары в воскресенье",
+            "sunday",
+        ] {
+            let result = use_case.text_to_action(text).unwrap();
+            assert!(matches!(result, UserAction::DayWithOffset(_)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod remaining_today_tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::{Classes, ClassesStatus, ClassesTime, ClassesType, Day};
+
+    use super::remaining_classes;
+
+    fn classes(start: &str, end: &str) -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: String::new(),
+            place: String::new(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: start.parse().unwrap(),
+                end: end.parse().unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    fn day() -> Day {
+        Day {
+            day_of_week: 1,
+            date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            classes: vec![
+                classes("09:20:00", "10:55:00"),
+                classes("11:10:00", "12:45:00"),
+                classes("13:45:00", "15:20:00"),
+            ],
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        }
+    }
+
+    #[test]
+    fn test_remaining_classes() {
+        let time = |t: &str| t.parse::<NaiveTime>().unwrap();
+        let day = day();
+        assert_eq!(
+            (3, Some(time("15:20:00"))),
+            remaining_classes(Some(&day), time("08:00:00"))
+        );
+        // class in progress is counted too
+        assert_eq!(
+            (2, Some(time("15:20:00"))),
+            remaining_classes(Some(&day), time("11:30:00"))
+        );
+        assert_eq!((0, None), remaining_classes(Some(&day), time("16:00:00")));
+        assert_eq!((0, None), remaining_classes(None, time("08:00:00")));
+        // cancelled classes are not counted
+        let mut day = day;
+        day.classes[2].status = ClassesStatus::Cancelled;
+        assert_eq!(
+            (2, Some(time("12:45:00"))),
+            remaining_classes(Some(&day), time("08:00:00"))
+        );
+    }
+}
+
+#[cfg(test)]
+mod classes_resume_tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::{
+        Classes, ClassesStatus, ClassesTime, ClassesType, Day, PeriodType, Week,
+    };
+
+    use super::classes_resume_date;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 2, day).unwrap()
+    }
+
+    fn week(first_day: u32, period_type: PeriodType, classes: Vec<ClassesStatus>) -> Week {
+        Week {
+            week_of_year: 6,
+            week_of_semester: 1,
+            first_day_of_week: date(first_day),
+            days: classes
+                .into_iter()
+                .enumerate()
+                .map(|(i, status)| Day {
+                    day_of_week: i as u8 + 1,
+                    date: date(first_day + i as u32),
+                    classes: vec![Classes {
+                        name: "Физика".to_owned(),
+                        r#type: ClassesType::Lecture,
+                        raw_type: String::new(),
+                        place: String::new(),
+                        groups: String::new(),
+                        person: String::new(),
+                        time: ClassesTime {
+                            start: "09:20:00".parse().unwrap(),
+                            end: "10:55:00".parse().unwrap(),
+                        },
+                        number: 1,
+                        status,
+                    }],
+                    source: Default::default(),
+                    expires_at: None,
+                    before_semester_start: false,
+                })
+                .collect(),
+            period_type,
+            last_synced_at: None,
+        }
+    }
+
+    #[test]
+    fn test_classes_resume_date() {
+        assert_eq!(
+            None,
+            classes_resume_date(&[week(5, PeriodType::Vacation, vec![])])
+        );
+        // cancelled classes do not count
+        assert_eq!(
+            Some(date(13)),
+            classes_resume_date(&[week(
+                12,
+                PeriodType::Study,
+                vec![ClassesStatus::Cancelled, ClassesStatus::Normal]
+            )])
+        );
+        // the study week without classes yet
+        assert_eq!(
+            Some(date(12)),
+            classes_resume_date(&[week(12, PeriodType::Study, vec![])])
+        );
+    }
+}
+
+#[cfg(test)]
+mod month_overview_tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::{
+        Classes, ClassesStatus, ClassesTime, ClassesType, Day, PeriodType, Week,
+    };
+
+    use crate::models::MonthDayKind::{self, *};
+
+    use super::{first_day_of_month, month_overview, week_offsets_of_month};
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    /// Week with the classes of `types` from Monday, one class per day
+    fn week(first_day: &str, period_type: PeriodType, types: Vec<ClassesType>) -> Week {
+        let first_day = date(first_day);
+        Week {
+            week_of_year: 0,
+            week_of_semester: 0,
+            first_day_of_week: first_day,
+            days: types
+                .into_iter()
+                .enumerate()
+                .map(|(i, r#type)| Day {
+                    day_of_week: i as u8 + 1,
+                    date: first_day + chrono::Duration::days(i as i64),
+                    classes: vec![Classes {
+                        name: "Физика".to_owned(),
+                        r#type,
+                        raw_type: String::new(),
+                        place: String::new(),
+                        groups: String::new(),
+                        person: String::new(),
+                        time: ClassesTime {
+                            start: "09:20:00".parse().unwrap(),
+                            end: "10:55:00".parse().unwrap(),
+                        },
+                        number: 1,
+                        status: ClassesStatus::Normal,
+                    }],
+                    source: Default::default(),
+                    expires_at: None,
+                    before_semester_start: false,
+                })
+                .collect(),
+            period_type,
+            last_synced_at: None,
+        }
+    }
+
+    #[test]
+    fn test_first_day_of_month() {
+        let today = date("2026-10-16");
+        assert_eq!(Some(date("2026-10-01")), first_day_of_month(today, 0));
+        assert_eq!(Some(date("2026-09-01")), first_day_of_month(today, -1));
+        assert_eq!(Some(date("2027-01-01")), first_day_of_month(today, 3));
+    }
+
+    #[test]
+    fn test_week_offsets_of_month() {
+        let today = date("2026-10-16");
+        // October 2026 is from Thursday to Saturday
+        assert_eq!(
+            Some(-2..=2),
+            week_offsets_of_month(today, date("2026-10-01"))
+        );
+        // November 2026 starts on Sunday, so it takes six weeks
+        assert_eq!(
+            Some(2..=7),
+            week_offsets_of_month(today, date("2026-11-01"))
+        );
+        // February 2027 is exactly four weeks from Monday to Sunday
+        assert_eq!(
+            Some(16..=19),
+            week_offsets_of_month(today, date("2027-02-01"))
+        );
+    }
+
+    #[test]
+    fn test_month_overview_boundaries() {
+        use ClassesType::*;
+        // March 2026 starts on Sunday and ends on Tuesday
+        let weeks = [
+            week("2026-02-23", PeriodType::Study, vec![Lecture; 7]),
+            week("2026-03-30", PeriodType::Study, vec![Lecture, Practice]),
+        ];
+        let month = month_overview(date("2026-03-01"), &weeks);
+        assert_eq!(date("2026-03-01"), month.first_day);
+        assert_eq!(31, month.days.len());
+        assert_eq!(Study, month.days[0]);
+        // the weeks in the middle of the month are missing
+        assert_eq!(DayOff, month.days[1]);
+        assert_eq!(&[Study, Study], &month.days[29..]);
+    }
+
+    #[test]
+    fn test_month_overview_shifted_semester() {
+        // the semester starts a week later, on February 9,
+        // after the session in the first week and the vacation in the second one
+        let weeks = [
+            week(
+                "2026-01-26",
+                PeriodType::Session,
+                vec![ClassesType::Consultation, ClassesType::Exam],
+            ),
+            week("2026-02-02", PeriodType::Vacation, vec![]),
+            week(
+                "2026-02-09",
+                PeriodType::Study,
+                vec![ClassesType::Lecture, ClassesType::Lab],
+            ),
+            week("2026-02-16", PeriodType::Study, vec![]),
+            week("2026-02-23", PeriodType::Study, vec![ClassesType::Lecture]),
+        ];
+        let month = month_overview(date("2026-02-01"), &weeks);
+        let expected: Vec<MonthDayKind> = [
+            vec![DayOff],
+            vec![Holiday; 7],
+            vec![Study, Study],
+            vec![DayOff; 5],
+            vec![DayOff; 7],
+            vec![Study],
+            vec![DayOff; 5],
+        ]
+        .concat();
+        assert_eq!(expected, month.days);
+
+        let month = month_overview(date("2026-01-01"), &weeks);
+        assert_eq!(&[Study, Exam, DayOff], &month.days[25..28]);
+    }
+}
+
+#[cfg(test)]
+mod ping_tests {
+    use chrono::Local;
+    use domain_schedule_models::{LatencySummary, ScheduleFetchLatency};
+
+    use super::cache_hit_percent;
+
+    #[test]
+    fn test_cache_hit_percent() {
+        let summary = |count| LatencySummary {
+            count,
+            avg_ms: 10,
+            max_ms: 100,
+        };
+        let mut latency = ScheduleFetchLatency {
+            strategy: "sequential".to_owned(),
+            since: Local::now().naive_local(),
+            sources: Default::default(),
+        };
+        assert_eq!(None, cache_hit_percent(&latency));
+
+        latency.sources.insert("cache".to_owned(), summary(80));
+        latency.sources.insert("staleCache".to_owned(), summary(7));
+        latency.sources.insert("remote".to_owned(), summary(10));
+        latency.sources.insert("error".to_owned(), summary(3));
+        assert_eq!(Some(87), cache_hit_percent(&latency));
+    }
+}
+
+#[cfg(test)]
+mod scheduled_push_tests {
+    use anyhow::anyhow;
+
+    use super::ScheduledPush;
+
+    #[test]
+    fn push_is_done_only_after_peers_are_loaded() {
+        let push = ScheduledPush::default();
+        let once_a_day = |_, last_date: Option<_>| last_date.is_none();
+        let failed = tokio_test::block_on(
+            push.peers_if_due(once_a_day, async { Err::<(), _>(anyhow!("db is down")) }),
+        );
+        assert!(failed.is_err());
+        let loaded = tokio_test::block_on(push.peers_if_due(once_a_day, async { Ok(()) }));
+        assert!(loaded.unwrap().is_some());
+        let repeated = tokio_test::block_on(push.peers_if_due(once_a_day, async { Ok(()) }));
+        assert!(repeated.unwrap().is_none());
+    }
+}
+
+#[cfg(test)]
+mod evening_summary_tests {
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+    use domain_schedule_models::{Classes, ClassesStatus, ClassesTime, ClassesType, Day};
+
+    use crate::models::Reply;
+
+    use super::{evening_summary, EveningSummaryConfig};
+
+    fn classes(start: &str, place: &str) -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: String::new(),
+            place: place.to_owned(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: start.parse().unwrap(),
+                end: start.parse().unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    fn day(classes: Vec<Classes>) -> Day {
+        Day {
+            day_of_week: 1,
+            date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            classes,
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        }
+    }
+
+    fn leave_at(reply: Option<Reply>) -> Option<(String, NaiveTime)> {
+        match reply {
+            Some(Reply::EveningSummary {
+                first_class,
+                leave_at,
+            }) => Some((first_class.place, leave_at)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn summary_of_first_class() {
+        let time = |t: &str| t.parse::<NaiveTime>().unwrap();
+        let day = day(vec![
+            classes("11:10:00", "Б-205"),
+            classes("09:20:00", "К-601"),
+        ]);
+        assert_eq!(
+            Some(("К-601".to_owned(), time("08:40:00"))),
+            leave_at(evening_summary(&day, 40))
+        );
+    }
+
+    #[test]
+    fn summary_skips_cancelled_classes() {
+        let time = |t: &str| t.parse::<NaiveTime>().unwrap();
+        let mut first = classes("09:20:00", "К-601");
+        first.status = ClassesStatus::Cancelled;
+        let day = day(vec![first, classes("11:10:00", "Б-205")]);
+        assert_eq!(
+            Some(("Б-205".to_owned(), time("10:10:00"))),
+            leave_at(evening_summary(&day, 60))
+        );
+    }
+
+    #[test]
+    fn no_summary_without_classes() {
+        assert_eq!(None, leave_at(evening_summary(&day(vec![]), 40)));
+    }
+
+    #[test]
+    fn summarize_once_a_day_after_configured_hour() {
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let config = EveningSummaryConfig { hour: 20 };
+        assert!(config.should_summarize(datetime("2023-03-13 20:00"), None));
+        assert!(!config.should_summarize(datetime("2023-03-13 19:59"), None));
+        let today = Some(NaiveDate::from_ymd_opt(2023, 3, 13).unwrap());
+        assert!(!config.should_summarize(datetime("2023-03-13 21:00"), today));
+        assert!(config.should_summarize(datetime("2023-03-14 21:00"), today));
+    }
+}
+
+#[cfg(test)]
+mod weekly_changelog_tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::{
+        Classes, ClassesChange, ClassesChangeKind, ClassesTime, ClassesType,
+    };
+
+    use crate::models::ChangelogDigest;
+
+    use super::aggregate_changes;
+
+    fn change(kind: ClassesChangeKind, name: &str) -> ClassesChange {
+        let classes = Classes {
+            name: name.to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: String::new(),
+            place: String::new(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: "09:20:00".parse().unwrap(),
+                end: "10:55:00".parse().unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        };
+        ClassesChange {
+            date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+            before: match kind {
+                ClassesChangeKind::Added => None,
+                _ => Some(classes.clone()),
+            },
+            after: match kind {
+                ClassesChangeKind::Removed => None,
+                _ => Some(classes),
+            },
+            kind,
+        }
+    }
+
+    #[test]
+    fn removed_and_added_classes_of_the_same_name_are_moved() {
+        let changes = vec![
+            change(ClassesChangeKind::Removed, "Физика"),
+            change(ClassesChangeKind::Removed, "Химия"),
+            change(ClassesChangeKind::Added, "Физика"),
+            change(ClassesChangeKind::Added, "Физика"),
+            change(ClassesChangeKind::Changed, "Физика"),
+        ];
+        assert_eq!(
+            ChangelogDigest {
+                schedule_name: "А-08-19".to_owned(),
+                moved: 1,
+                added: 1,
+                removed: 1,
+                changed: 1,
+            },
+            aggregate_changes("А-08-19", &changes)
+        );
+        assert!(aggregate_changes("А-08-19", &[]).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod week_ahead_tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::{
+        Classes, ClassesStatus, ClassesTime, ClassesType, Day, PeriodType, Week,
+    };
+
+    use crate::models::{WeekAheadDay, WeekAheadDigest};
+
+    use super::week_ahead_digest;
+
+    fn classes(name: &str, r#type: ClassesType, start: &str, status: ClassesStatus) -> Classes {
+        Classes {
+            name: name.to_owned(),
+            r#type,
+            raw_type: String::new(),
+            place: String::new(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: start.parse().unwrap(),
+                end: start.parse().unwrap(),
+            },
+            number: 1,
+            status,
+        }
+    }
+
+    fn day(day: u32, classes: Vec<Classes>) -> Day {
+        Day {
+            day_of_week: (day - 19) as u8,
+            date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            classes,
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        }
+    }
+
+    #[test]
+    fn digest_counts_classes_and_labs_of_each_day() {
+        let week = Week {
+            week_of_year: 12,
+            week_of_semester: 6,
+            first_day_of_week: NaiveDate::from_ymd_opt(2023, 3, 20).unwrap(),
+            days: vec![
+                day(
+                    20,
+                    vec![
+                        classes("Физика", ClassesType::Lab, "11:10:00", Default::default()),
+                        classes("Физика", ClassesType::Lab, "12:45:00", Default::default()),
+                        classes(
+                            "Химия",
+                            ClassesType::Lecture,
+                            "09:20:00",
+                            ClassesStatus::Cancelled,
+                        ),
+                    ],
+                ),
+                day(21, vec![]),
+                day(
+                    22,
+                    vec![classes(
+                        "Химия",
+                        ClassesType::Lecture,
+                        "09:20:00",
+                        ClassesStatus::Cancelled,
+                    )],
+                ),
+                day(
+                    23,
+                    vec![classes(
+                        "Химия",
+                        ClassesType::Lecture,
+                        "13:45:00",
+                        Default::default(),
+                    )],
+                ),
+            ],
+            period_type: PeriodType::Study,
+            last_synced_at: None,
+        };
+        let time = |t: &str| t.parse().unwrap();
+        assert_eq!(
+            WeekAheadDigest {
+                schedule_name: "А-08-19".to_owned(),
+                days: vec![
+                    WeekAheadDay {
+                        date: NaiveDate::from_ymd_opt(2023, 3, 20).unwrap(),
+                        classes_count: 2,
+                        earliest_start: time("11:10:00"),
+                        labs: vec!["Физика".to_owned()],
+                    },
+                    WeekAheadDay {
+                        date: NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+                        classes_count: 1,
+                        earliest_start: time("13:45:00"),
+                        labs: vec![],
+                    },
+                ],
+            },
+            week_ahead_digest("А-08-19", &week)
+        );
+    }
+}
+
+#[cfg(test)]
+mod sunday_push_tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{SundayPushConfig, WeeklyDigestUseCase};
+
+    #[test]
+    fn send_once_on_sunday_after_configured_hour() {
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let config = SundayPushConfig::<WeeklyDigestUseCase>::new(19);
+        assert!(config.should_send(datetime("2023-03-19 19:00"), None));
+        assert!(!config.should_send(datetime("2023-03-19 18:59"), None));
+        assert!(!config.should_send(datetime("2023-03-18 20:00"), None));
+        let today = Some(NaiveDate::from_ymd_opt(2023, 3, 19).unwrap());
+        assert!(!config.should_send(datetime("2023-03-19 21:00"), today));
+        assert!(config.should_send(datetime("2023-03-26 21:00"), today));
+    }
+}
+
+#[cfg(test)]
+mod reengagement_tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::ReEngagementConfig;
+
+    #[test]
+    fn run_once_a_day_during_safe_hours() {
+        let datetime = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap();
+        let config = ReEngagementConfig {
+            enabled: true,
+            inactive_weeks: 6,
+            hour: 12,
+            batch_size: 200,
+        };
+        assert!(config.should_run(datetime("2023-03-13 12:00"), None));
+        assert!(!config.should_run(datetime("2023-03-13 11:59"), None));
+        assert!(!config.should_run(datetime("2023-03-13 20:00"), None));
+        let today = Some(NaiveDate::from_ymd_opt(2023, 3, 13).unwrap());
+        assert!(!config.should_run(datetime("2023-03-13 15:00"), today));
+        assert!(config.should_run(datetime("2023-03-14 15:00"), today));
+        let disabled = ReEngagementConfig {
+            enabled: false,
+            ..config
+        };
+        assert!(!disabled.should_run(datetime("2023-03-14 15:00"), None));
+    }
+}
+
+#[cfg(test)]
+mod schedule_change_notifications_tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::{
+        Classes, ClassesChange, ClassesChangeKind, ClassesTime, ClassesType,
+    };
+
+    use super::{detect_new_changes, room_change_warnings};
+    use crate::models::RoomChangeWarning;
+
+    fn change(day: u32, name: &str) -> ClassesChange {
+        ClassesChange {
+            date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            kind: ClassesChangeKind::Added,
+            before: None,
+            after: Some(Classes {
+                name: name.to_owned(),
+                r#type: ClassesType::Lecture,
+                raw_type: String::new(),
+                place: String::new(),
+                groups: String::new(),
+                person: String::new(),
+                time: ClassesTime {
+                    start: "09:20:00".parse().unwrap(),
+                    end: "10:55:00".parse().unwrap(),
+                },
+                number: 1,
+                status: Default::default(),
+            }),
+        }
+    }
+
+    #[test]
+    fn only_unseen_upcoming_changes_are_new() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        let previous = vec![change(15, "Физика")];
+        let changes = vec![
+            change(15, "Физика"),
+            change(13, "Химия"),
+            change(14, "Химия"),
+            change(16, "Физика"),
+        ];
+        assert_eq!(
+            vec![change(14, "Химия"), change(16, "Физика")],
+            detect_new_changes(Some(&previous), &changes, today)
+        );
+    }
+
+    #[test]
+    fn first_poll_only_remembers_changes() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        assert!(detect_new_changes(None, &[change(15, "Физика")], today).is_empty());
+    }
+
+    fn moved(day: u32, name: &str, before: &str, after: &str) -> ClassesChange {
+        let classes = |place: &str| {
+            change(day, name).after.map(|it| Classes {
+                place: place.to_owned(),
+                ..it
+            })
+        };
+        ClassesChange {
+            kind: ClassesChangeKind::Changed,
+            before: classes(before),
+            after: classes(after),
+            ..change(day, name)
+        }
+    }
+
+    #[test]
+    fn room_changes_of_today_and_tomorrow_are_warnings() {
+        let today = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        let mut renamed = moved(14, "Физика", "К-601", "Ж-200");
+        renamed.after.as_mut().unwrap().name = "Химия".to_owned();
+        let changes = vec![
+            moved(13, "Физика", "К-601", "Ж-200"),
+            moved(14, "Матан", "К-601", "Ж-200"),
+            moved(15, "Физика", "К-601", "Б-100"),
+            moved(16, "Физика", "К-601", "Ж-200"),
+            moved(14, "Химия", "К-601", ""),
+            renamed,
+            change(14, "Химия"),
+        ];
+        let warning = |day, name: &str, place: &str| RoomChangeWarning {
+            date: NaiveDate::from_ymd_opt(2023, 3, day).unwrap(),
+            class_name: name.to_owned(),
+            time: ClassesTime {
+                start: "09:20:00".parse().unwrap(),
+                end: "10:55:00".parse().unwrap(),
+            },
+            place: place.to_owned(),
+        };
+        assert_eq!(
+            vec![
+                warning(14, "Матан", "Ж-200"),
+                warning(15, "Физика", "Б-100")
+            ],
+            room_change_warnings(&changes, today)
+        );
+    }
+}
+
+#[cfg(test)]
+mod achievements_tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use crate::models::{AchievementStats, Badge};
+
+    use super::{apply_schedule_check, earned_badges};
+
+    fn apply_checks(checks: &[&str]) -> AchievementStats {
+        checks
+            .iter()
+            .map(|it| NaiveDateTime::parse_from_str(it, "%Y-%m-%d %H:%M").unwrap())
+            .fold(AchievementStats::default(), apply_schedule_check)
+    }
+
+    #[test]
+    fn first_check_starts_streak() {
+        let stats = apply_checks(&["2023-03-01 10:00"]);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.best_streak, 1);
+        assert_eq!(stats.total_checks, 1);
+        assert_eq!(stats.early_bird_checks, 0);
+        assert_eq!(stats.last_check_date, NaiveDate::from_ymd_opt(2023, 3, 1));
+    }
+
+    #[test]
+    fn checks_on_consecutive_days_increase_streak() {
+        let stats = apply_checks(&[
+            "2023-03-01 10:00",
+            "2023-03-01 12:00",
+            "2023-03-02 10:00",
+            "2023-03-03 07:45",
+        ]);
+        assert_eq!(stats.current_streak, 3);
+        assert_eq!(stats.best_streak, 3);
+        assert_eq!(stats.total_checks, 4);
+        assert_eq!(stats.early_bird_checks, 1);
+    }
+
+    #[test]
+    fn missed_day_resets_streak_but_keeps_best() {
+        let stats = apply_checks(&["2023-03-01 10:00", "2023-03-02 10:00", "2023-03-04 10:00"]);
+        assert_eq!(stats.current_streak, 1);
+        assert_eq!(stats.best_streak, 2);
+    }
+
+    #[test]
+    fn badges_are_awarded() {
+        assert!(earned_badges(&AchievementStats::default()).is_empty());
+        let stats = AchievementStats {
+            current_streak: 1,
+            best_streak: 7,
+            last_check_date: None,
+            early_bird_checks: 1,
+            total_checks: 10,
+        };
+        assert_eq!(
+            earned_badges(&stats),
+            vec![Badge::EarlyBird, Badge::WeekStreak]
+        );
+    }
+}
+
+#[cfg(test)]
+mod audit_tests {
+    use super::redact_text;
+
+    #[test]
+    fn test_redact_personal_data() {
+        assert_eq!(
+            redact_text("Пишите на help@mpei.ru или звоните +7 (495) 362-75-60", 100),
+            "Пишите на [email] или звоните [phone]"
+        );
+    }
+
+    #[test]
+    fn test_schedule_is_not_redacted() {
+        let text = "Расписание А-08-19 на 15.03: 09:20 - 10:55, Б-303";
+        assert_eq!(redact_text(text, 100), text);
+    }
+
+    #[test]
+    fn test_truncate_text() {
+        assert_eq!(redact_text("Сегодня пар нет", 7), "Сегодня…");
+    }
+}
+
+#[cfg(test)]
+mod reply_retry_tests {
+    use std::{sync::Arc, time::Duration};
+
+    use anyhow::anyhow;
+    use common_errors::errors::CommonError;
+
+    use crate::{peer::repository::PlatformId, queue::repository::ReplyQueueRepository};
+
+    use super::{ReplyRetryConfig, ReplyRetryUseCase};
+
+    fn use_case() -> ReplyRetryUseCase {
+        ReplyRetryUseCase {
+            reply_queue_repository: Arc::new(ReplyQueueRepository::in_memory()),
+            config: ReplyRetryConfig {
+                max_attempts: 3,
+                backoff: Duration::ZERO,
+            }
+            .into(),
+        }
+    }
+
+    #[test]
+    fn test_transient_failures_are_retried_until_max_attempts() {
+        let use_case = use_case();
+        let platform_id = PlatformId::telegram(1);
+        let gateway_error = || anyhow!(CommonError::gateway("Connection reset"));
+        assert!(tokio_test::block_on(use_case.enqueue(
+            &platform_id,
+            "Пары завтра",
+            Some("{}"),
+            &gateway_error()
+        )));
+
+        let due = || tokio_test::block_on(use_case.get_due_replies(PlatformId::telegram)).unwrap();
+        assert!(
+            tokio_test::block_on(use_case.get_due_replies(PlatformId::vk))
+                .unwrap()
+                .is_empty()
+        );
+        let reply = due().remove(0);
+        assert_eq!((reply.attempts, reply.keyboard.as_deref()), (1, Some("{}")));
+
+        tokio_test::block_on(use_case.report_attempt(&reply, Err(gateway_error()))).unwrap();
+        let reply = due().remove(0);
+        assert_eq!(reply.attempts, 2);
+
+        // the third failed attempt is the last one, the reply is dead
+        tokio_test::block_on(use_case.report_attempt(&reply, Err(gateway_error()))).unwrap();
+        assert!(due().is_empty());
+    }
+
+    #[test]
+    fn test_delivered_and_rejected_replies_are_not_retried() {
+        let use_case = use_case();
+        let platform_id = PlatformId::vk(1);
+        let rejected = anyhow!(CommonError::internal("Chat not found"));
+        assert!(!tokio_test::block_on(use_case.enqueue(
+            &platform_id,
+            "Пары завтра",
+            None,
+            &rejected
+        )));
+
+        let gateway_error = anyhow!(CommonError::gateway("Timeout"));
+        assert!(tokio_test::block_on(use_case.enqueue(
+            &platform_id,
+            "Пары завтра",
+            None,
+            &gateway_error
+        )));
+        let due = || tokio_test::block_on(use_case.get_due_replies(PlatformId::vk)).unwrap();
+        let reply = due().remove(0);
+        tokio_test::block_on(use_case.report_attempt(&reply, Ok(()))).unwrap();
+        assert!(due().is_empty());
+    }
+}
+
+/// Executable description of the [GenerateReplyUseCase] state machine.
+///
+/// Peer state is a pair of `selected_schedule` (empty or not) and `selecting_schedule` flag:
+/// - new peer: no schedule, not selecting;
+/// - selecting peer: no schedule, selecting (after `/start`);
+/// - selected peer: has schedule, not selecting;
+/// - changing peer: has schedule, selecting (after `/change`).
+///
+/// Each case is `initial peer × message => expected reply variant, persisted peer`.
+/// Schedules and search results come from [crate::mock::MockMpeixApi] fixtures,
+/// peers and recent schedules are kept in memory.
+#[cfg(test)]
+mod state_machine_tests {
+    use std::{sync::Arc, time::Instant};
+
+    use async_trait::async_trait;
+    use chrono::{Duration, Local};
+    use domain_buildings::MapProvider;
+    use domain_schedule_models::{ClassesType, ScheduleType};
+
+    use crate::{
+        audit::repository::AuditRepository,
+        blocklist::rate_limiter::RateLimiter,
+        fakes::{
+            FakeAchievementsRepository, FakeAuditRepository, FakeBlocklistRepository,
+            FakeMemberSchedulesRepository, FakeNotesRepository, FakePeerActivityRepository,
+            FakePeerRepository, FakeRecentSchedulesRepository,
+        },
+        members::repository::MemberSchedulesRepository,
+        mock::MockMpeixApi,
+        models::{
+            ChatMember, ChatNote, Locale, MemberScheduleExport, NoteRejection, Peer,
+            RecentSchedule, Reply, ReplyAuditEntry,
+        },
+        mpeix_api::MpeixUpstream,
+        notes::ChatAdminChecker,
+        peer::repository::{PeerRepository, PlatformId, PushOptIn},
+        recent::repository::RecentSchedulesRepository,
+        schedule::repository::ScheduleRepository,
+        search::repository::ScheduleSearchRepository,
+        theme::ThemeKind,
+        warnings::repository::RoomChangeWarningsRepository,
+    };
+
+    use super::{
+        AbuseProtectionUseCase, AchievementsUseCase, AdminConfig, CommandKillSwitchUseCase,
+        ExportPeerDataUseCase, GenerateReplyUseCase, GetMonthOverviewUseCase,
+        GetUpcomingEventsUseCase, MemberSchedulesUseCase, NotesUseCase, PeerDeletionUseCase,
+        PingUseCase, ReEngagementUseCase, TextToActionUseCase, VerifyScheduleUseCase,
+    };
+
+    const PLATFORM_ID: PlatformId = PlatformId::vk(1);
+    const GROUP: &str = "А-08-19";
+
+    fn peer(selected_schedule: &str, selecting_schedule: bool) -> Peer {
+        Peer {
+            id: 1,
+            selected_schedule: selected_schedule.to_owned(),
+            selected_schedule_type: ScheduleType::Group,
+            selecting_schedule,
+            map_provider: None,
+            achievements_enabled: false,
+            days_range: Default::default(),
+            locale: Some(Locale::Ru),
+            commute_minutes: None,
+            privacy_mode: false,
+            changelog_enabled: false,
+            week_ahead_enabled: false,
+            digest_enabled: false,
+            ics_enabled: false,
+            newcomer_mode: false,
+            theme: None,
+            subscribed: false,
+            deleted_at: None,
+        }
+    }
+
+    fn new_peer() -> Peer {
+        peer("", false)
+    }
+
+    fn selecting_peer() -> Peer {
+        peer("", true)
+    }
+
+    fn selected_peer() -> Peer {
+        peer(GROUP, false)
+    }
+
+    fn changing_peer() -> Peer {
+        peer(GROUP, true)
+    }
+
+    /// Only the user with id `1` is an admin of any group chat
+    struct FakeChatAdminChecker;
+
+    #[async_trait]
+    impl ChatAdminChecker for FakeChatAdminChecker {
+        async fn is_chat_admin(&self, _: &PlatformId, member_id: i64) -> anyhow::Result<bool> {
+            Ok(member_id == 1)
+        }
+    }
+
+    fn notes_use_case() -> NotesUseCase {
+        NotesUseCase {
+            notes_repository: Arc::new(FakeNotesRepository::default()),
+            chat_admin_checker: Arc::new(FakeChatAdminChecker),
+        }
+    }
+
+    fn use_case(initial: Peer, recent_schedules: &[&str]) -> GenerateReplyUseCase {
+        admin_use_case(initial, recent_schedules, vec![])
+    }
+
+    fn admin_use_case(
+        initial: Peer,
+        recent_schedules: &[&str],
+        admin_ids: Vec<i64>,
+    ) -> GenerateReplyUseCase {
+        let schedule_repository =
+            Arc::new(ScheduleRepository::new(MpeixUpstream::Mock(MockMpeixApi)));
+        let schedule_search_repository =
+            Arc::new(ScheduleSearchRepository(MpeixUpstream::Mock(MockMpeixApi)));
+        let recent_schedules_repository = Arc::new(FakeRecentSchedulesRepository::default());
+        for name in recent_schedules.iter().rev() {
+            let schedule = RecentSchedule {
+                name: name.to_string(),
+                r#type: ScheduleType::Group,
+            };
+            tokio_test::block_on(recent_schedules_repository.add_recent_schedule(1, &schedule))
+                .unwrap();
+        }
+        let peer_repository = Arc::new(FakePeerRepository::new(vec![(PLATFORM_ID, initial)]));
+        let member_schedules_repository =
+            Arc::new(FakeMemberSchedulesRepository(peer_repository.clone()));
+        GenerateReplyUseCase(
+            Arc::new(TextToActionUseCase),
+            peer_repository.clone(),
+            schedule_repository.clone(),
+            schedule_search_repository.clone(),
+            Arc::new(GetUpcomingEventsUseCase(schedule_repository.clone())),
+            Arc::new(AchievementsUseCase(Arc::new(
+                FakeAchievementsRepository::default(),
+            ))),
+            recent_schedules_repository.clone(),
+            Arc::new(VerifyScheduleUseCase {
+                schedule_repository: schedule_repository.clone(),
+                schedule_search_repository: schedule_search_repository.clone(),
+                config: AdminConfig {
+                    admin_ids: admin_ids.clone(),
+                    app_schedule_admin_secret: Some("secret".to_owned()),
+                },
+            }),
+            Arc::new(notes_use_case()),
+            Arc::new(CommandKillSwitchUseCase {
+                disabled: Default::default(),
+            }),
+            Arc::new(ExportPeerDataUseCase {
+                recent_schedules_repository,
+                achievements_repository: Arc::new(FakeAchievementsRepository::default()),
+                audit_repository: Arc::new(FakeAuditRepository::default()),
+                member_schedules_repository: member_schedules_repository.clone(),
+            }),
+            Arc::new(GetMonthOverviewUseCase::new(schedule_repository.clone())),
+            Arc::new(PingUseCase {
+                schedule_repository,
+                config: AdminConfig {
+                    admin_ids: admin_ids.clone(),
+                    app_schedule_admin_secret: Some("secret".to_owned()),
+                },
+                build: crate::build_info!(),
+                started_at: Instant::now(),
+                last_health_check: Default::default(),
+            }),
+            Arc::new(MemberSchedulesUseCase {
+                member_schedules_repository,
+                schedule_search_repository,
+            }),
+            Arc::new(ReEngagementUseCase::new(
+                peer_repository.clone(),
+                Arc::new(FakePeerActivityRepository::default()),
+            )),
+            Arc::new(RoomChangeWarningsRepository::default()),
+            Arc::new(AbuseProtectionUseCase {
+                blocklist_repository: Arc::new(FakeBlocklistRepository::default()),
+                rate_limiter: RateLimiter::default(),
+                config: AdminConfig {
+                    admin_ids,
+                    app_schedule_admin_secret: Some("secret".to_owned()),
+                },
+            }),
+            Arc::new(PeerDeletionUseCase::new(peer_repository)),
+        )
+    }
+
+    fn sender(id: i64) -> Option<ChatMember> {
+        Some(ChatMember {
+            id,
+            username: Some(format!("user{id}")),
+        })
+    }
+
+    /// Send `text` from the peer in the `initial` state,
+    /// return the reply variant and the persisted peer
+    fn transition(use_case: GenerateReplyUseCase, text: &str) -> (&'static str, Peer) {
+        let (reply, _) =
+            tokio_test::block_on(use_case.generate_reply(PLATFORM_ID, sender(1), text)).unwrap();
+        let peer = tokio_test::block_on(use_case.1.get_peer_by_platform_id(PLATFORM_ID)).unwrap();
+        (reply.variant_name(), peer)
+    }
+
+    macro_rules! test_transition {
+        ($name:tt, $initial:expr, $text:expr => $reply:expr, $expected:expr) => {
+            test_transition!($name, $initial, [], $text => $reply, $expected);
+        };
+        ($name:tt, $initial:expr, $recent:expr, $text:expr => $reply:expr, $expected:expr) => {
+            #[test]
+            fn $name() {
+                let (reply, peer) = transition(use_case($initial, &$recent), $text);
+                assert_eq!(reply, $reply);
+                assert_eq!(peer, $expected);
+            }
+        };
+    }
+
+    // new peer: any command starts the schedule selection
+    test_transition!(new_start, new_peer(), "/start" => "StartGreetings", selecting_peer());
+    test_transition!(new_week, new_peer(), "/thisweek" => "StartGreetings", selecting_peer());
+    test_transition!(new_help, new_peer(), "/help" => "StartGreetings", selecting_peer());
+    // ...except schedule names, which are searched right away
+    test_transition!(new_exact_name, new_peer(), "а-08-19" => "ScheduleChangedSuccessfully", selected_peer());
+    test_transition!(new_unknown_name, new_peer(), "ъ-00-00" => "CannotFindSchedule", new_peer());
+    // ...and the language and privacy mode, which can be changed before anything else
+    test_transition!(
+        new_privacy,
+        new_peer(),
+        "/privacy" => "PrivacyModeChanged",
+        Peer { privacy_mode: true, ..new_peer() }
+    );
+    // directions do not need the schedule
+    test_transition!(new_navigation, new_peer(), "Как пройти в К-601" => "Navigation", new_peer());
+    test_transition!(
+        new_language,
+        new_peer(),
+        "/language_en" => "LocaleChanged",
+        Peer { locale: Some(Locale::En), ..new_peer() }
+    );
+
+    // selecting peer: only schedule names make sense
+    test_transition!(selecting_start, selecting_peer(), "/start" => "ReadyToChangeSchedule", selecting_peer());
+    test_transition!(selecting_day, selecting_peer(), "/today" => "ReadyToChangeSchedule", selecting_peer());
+    test_transition!(selecting_help, selecting_peer(), "/help" => "ReadyToChangeSchedule", selecting_peer());
+    test_transition!(selecting_exact_name, selecting_peer(), "а-08-19" => "ScheduleChangedSuccessfully", selected_peer());
+    test_transition!(selecting_partial_name, selecting_peer(), "а-" => "ScheduleSearchResults", selecting_peer());
+    test_transition!(selecting_unknown_name, selecting_peer(), "ъ-00-00" => "CannotFindSchedule", selecting_peer());
+    test_transition!(
+        selecting_room_name,
+        selecting_peer(),
+        "ауд. к-205" => "ScheduleChangedSuccessfully",
+        Peer { selected_schedule_type: ScheduleType::Room, ..peer("К-205", false) }
+    );
+    test_transition!(selecting_partial_room_name, selecting_peer(), "room к-2" => "ScheduleSearchResults", selecting_peer());
+
+    // selected peer: regular commands
+    test_transition!(selected_start, selected_peer(), "/start" => "AlreadyStarted", selected_peer());
+    test_transition!(selected_week, selected_peer(), "/thisweek" => "Week", selected_peer());
+    test_transition!(selected_day, selected_peer(), "/today" => "Day", selected_peer());
+    test_transition!(selected_month, selected_peer(), "/month" => "Month", selected_peer());
+    test_transition!(selected_next_week_changes, selected_peer(), "/nextweek_changes" => "NextWeekChanges", selected_peer());
+    test_transition!(selected_exams, selected_peer(), "/exams" => "Exams", selected_peer());
+    test_transition!(selected_help, selected_peer(), "/help" => "ShowHelp", selected_peer());
+    test_transition!(selected_recent, selected_peer(), "/recent" => "RecentSchedules", selected_peer());
+    test_transition!(selected_change, selected_peer(), "/change" => "ReadyToChangeSchedule", changing_peer());
+    test_transition!(
+        selected_commute,
+        selected_peer(),
+        "/commute 40" => "CommuteChanged",
+        Peer { commute_minutes: Some(40), ..selected_peer() }
+    );
+    // schedule names are not searched without `/change`...
+    test_transition!(selected_name, selected_peer(), "с-12-21" => "UnknownCommand", selected_peer());
+    // ...unless the schedule was selected recently
+    test_transition!(
+        selected_recent_name,
+        selected_peer(),
+        ["С-12-21"],
+        "с-12-21" => "ScheduleChangedSuccessfully",
+        peer("С-12-21", false)
+    );
+    test_transition!(
+        selected_maps,
+        selected_peer(),
+        "/maps_yandex" => "MapProviderChanged",
+        Peer { map_provider: Some(MapProvider::Yandex), ..selected_peer() }
+    );
+    test_transition!(selected_achievements, selected_peer(), "/achievements" => "AchievementsDisabled", selected_peer());
+    test_transition!(
+        selected_privacy,
+        selected_peer(),
+        "/privacy" => "PrivacyModeChanged",
+        Peer { privacy_mode: true, ..selected_peer() }
+    );
+    test_transition!(
+        selected_changelog,
+        selected_peer(),
+        "/changelog_on" => "ChangelogEnabledChanged",
+        Peer { changelog_enabled: true, ..selected_peer() }
+    );
+    test_transition!(
+        selected_week_ahead,
+        selected_peer(),
+        "/week_ahead_on" => "WeekAheadEnabledChanged",
+        Peer { week_ahead_enabled: true, ..selected_peer() }
+    );
+    test_transition!(
+        selected_digest,
+        selected_peer(),
+        "/digest_on" => "DigestEnabledChanged",
+        Peer { digest_enabled: true, ..selected_peer() }
+    );
+    test_transition!(
+        selected_subscribe,
+        selected_peer(),
+        "/subscribe" => "SubscriptionChanged",
+        Peer { subscribed: true, ..selected_peer() }
+    );
+    // VK bot cannot send files
+    test_transition!(selected_ics, selected_peer(), "/ics_on" => "DocumentsNotSupported", selected_peer());
+    test_transition!(selected_export, selected_peer(), "/export" => "DocumentsNotSupported", selected_peer());
+    test_transition!(
+        selected_newcomer,
+        selected_peer(),
+        "/newcomer_on" => "NewcomerModeChanged",
+        Peer { newcomer_mode: true, ..selected_peer() }
+    );
+    test_transition!(
+        selected_theme,
+        selected_peer(),
+        "/theme_minimal" => "ThemeChanged",
+        Peer { theme: Some(ThemeKind::Minimal), ..selected_peer() }
+    );
+    test_transition!(selected_navigation, selected_peer(), "Как пройти в К-601" => "Navigation", selected_peer());
+    // admin commands do not exist for other peers
+    test_transition!(selected_verify, selected_peer(), "/verify а-08-19" => "UnknownCommand", selected_peer());
+    test_transition!(selected_ping, selected_peer(), "/ping" => "UnknownCommand", selected_peer());
+
+    // changing peer: schedule names are searched, other commands cancel the selection
+    test_transition!(changing_exact_name, changing_peer(), "с-12-21" => "ScheduleChangedSuccessfully", peer("С-12-21", false));
+    test_transition!(changing_partial_name, changing_peer(), "иванов" => "ScheduleSearchResults", changing_peer());
+    test_transition!(changing_unknown_name, changing_peer(), "ъ-00-00" => "CannotFindSchedule", changing_peer());
+    test_transition!(changing_start, changing_peer(), "/start" => "AlreadyStarted", selected_peer());
+    test_transition!(changing_week, changing_peer(), "/thisweek" => "Week", selected_peer());
+    test_transition!(changing_day, changing_peer(), "/today" => "Day", selected_peer());
+    test_transition!(changing_recent, changing_peer(), "/recent" => "RecentSchedules", selected_peer());
+    test_transition!(
+        changing_achievements_on,
+        changing_peer(),
+        "/achievements_on" => "AchievementsEnabledChanged",
+        Peer { achievements_enabled: true, ..selected_peer() }
+    );
+    // help does not cancel the selection, so the peer can read it and send the name
+    test_transition!(changing_help, changing_peer(), "/help" => "ShowHelp", changing_peer());
+
+    #[test]
+    fn test_admin_verifies_schedule_in_any_state() {
+        for initial in [
+            new_peer(),
+            selecting_peer(),
+            selected_peer(),
+            changing_peer(),
+        ] {
+            let generate_reply_use_case = admin_use_case(initial.clone(), &[], vec![1]);
+            let (reply, peer) = transition(generate_reply_use_case, "/verify а-08-19");
+            assert_eq!(reply, "ScheduleVerification");
+            assert_eq!(peer, initial);
+        }
+        let generate_reply_use_case = admin_use_case(selected_peer(), &[], vec![1]);
+        let (reply, _) = transition(generate_reply_use_case, "/verify ъ-00-00");
+        assert_eq!(reply, "CannotFindSchedule");
+    }
+
+    #[test]
+    fn test_ping() {
+        let generate_reply_use_case = admin_use_case(new_peer(), &[], vec![1]);
+        let (reply, peer) = transition(generate_reply_use_case, "/ping");
+        assert_eq!(reply, "Pong");
+        assert_eq!(peer, new_peer());
+    }
+
+    #[test]
+    fn test_blocked_peers_are_dropped() {
+        let generate_reply_use_case = admin_use_case(selected_peer(), &[], vec![1]);
+        let spammer = PlatformId::vk(2000000005);
+        assert!(!generate_reply_use_case.16.should_drop(&spammer));
+        for (text, expected) in [
+            ("/block vk:2000000*", "Blocklist"),
+            // the admins are never dropped, even if they match the pattern
+            ("/block vk:1", "Blocklist"),
+            ("/blocklist", "Blocklist"),
+        ] {
+            let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+                PLATFORM_ID,
+                sender(1),
+                text,
+            ))
+            .unwrap();
+            assert_eq!(expected, reply.variant_name());
+        }
+        assert!(generate_reply_use_case.16.should_drop(&spammer));
+        assert!(!generate_reply_use_case
+            .16
+            .should_drop(&PlatformId::telegram(2000000005)));
+        assert!(!generate_reply_use_case.16.should_drop(&PLATFORM_ID));
+
+        let (reply, _) = transition(generate_reply_use_case, "/unblock vk:2000000*");
+        assert_eq!(reply, "Blocklist");
+    }
+
+    #[test]
+    fn test_block_is_unknown_for_not_admins() {
+        for text in ["/block vk:2000000*", "/unblock vk:2000000*", "/blocklist"] {
+            let (reply, _) = transition(use_case(selected_peer(), &[]), text);
+            assert_eq!(reply, "UnknownCommand");
+        }
+    }
+
+    #[test]
+    fn test_room_search_results_select_the_room() {
+        let generate_reply_use_case = use_case(selecting_peer(), &[]);
+        let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "ауд. к-2",
+        ))
+        .unwrap();
+        let Reply::ScheduleSearchResults { results, .. } = reply else {
+            panic!("Unexpected reply {}", reply.variant_name());
+        };
+        assert_eq!(vec!["ауд. К-205".to_owned()], results);
+
+        let (reply, peer) = transition(generate_reply_use_case, &results[0]);
+        assert_eq!(reply, "ScheduleChangedSuccessfully");
+        assert_eq!(peer.selected_schedule_type, ScheduleType::Room);
+    }
+
+    #[test]
+    fn test_trending_groups_are_suggested_to_new_peers() {
+        let generate_reply_use_case = use_case(new_peer(), &[]);
+        let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "/start",
+        ))
+        .unwrap();
+        let Reply::StartGreetings { suggestions } = reply else {
+            panic!("Unexpected reply {}", reply.variant_name());
+        };
+        assert_eq!(vec!["А-08-19", "А-12-19", "С-12-21"], suggestions);
+    }
+
+    #[test]
+    fn test_exams_of_the_selected_schedule() {
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "экзамены",
+        ))
+        .unwrap();
+        let Reply::Exams { session, .. } = reply else {
+            panic!("Unexpected reply {}", reply.variant_name());
+        };
+        assert!(!session.days.is_empty());
+        assert!(session
+            .days
+            .iter()
+            .flat_map(|day| day.classes.iter())
+            .all(|classes| classes.r#type == ClassesType::Exam));
+    }
+
+    #[test]
+    fn test_notes_are_shown_in_the_day_reply() {
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "/note завтра Контрольная по физике",
+        ))
+        .unwrap();
+        assert_eq!(reply.variant_name(), "NoteAdded");
+
+        let (reply, _) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "/tomorrow",
+        ))
+        .unwrap();
+        let Reply::Day { notes, .. } = reply else {
+            panic!("Unexpected reply: {}", reply.variant_name());
+        };
+        assert_eq!(
+            notes.into_iter().map(|it| it.text).collect::<Vec<_>>(),
+            vec!["Контрольная по физике"]
+        );
+    }
+
+    #[test]
+    fn test_disabled_commands_are_not_handled() {
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        generate_reply_use_case
+            .9
+            .set_disabled(vec!["WeekWithOffset".to_owned()]);
+        let (reply, peer) = transition(generate_reply_use_case, "/thisweek");
+        assert_eq!(reply, "CommandDisabled");
+        assert_eq!(peer, selected_peer());
+
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        generate_reply_use_case
+            .9
+            .set_disabled(vec!["WeekWithOffset".to_owned()]);
+        let (reply, _) = transition(generate_reply_use_case, "/tomorrow");
+        assert_eq!(reply, "Day");
+    }
+
+    #[test]
+    fn test_ics_is_attached_only_if_platform_supports_documents() {
+        let telegram = PlatformId::telegram(1);
+        let mut generate_reply_use_case = use_case(selected_peer(), &[]);
+        generate_reply_use_case.1 = Arc::new(FakePeerRepository::new(vec![(
+            telegram.clone(),
+            selected_peer(),
+        )]));
+        let generate_reply = |text| {
+            tokio_test::block_on(generate_reply_use_case.generate_reply(
+                telegram.clone(),
+                sender(1),
+                text,
+            ))
+            .unwrap()
+        };
+        assert!(!generate_reply("/thisweek").1.ics_enabled);
+        let (reply, context) = generate_reply("/ics_on");
+        assert!(matches!(reply, Reply::IcsEnabledChanged(true)));
+        assert!(context.ics_enabled);
+        let (reply, context) = generate_reply("/thisweek");
+        assert!(matches!(reply, Reply::Week { .. }));
+        assert!(context.ics_enabled);
+
+        // the preference is ignored by the platforms without documents
+        let generate_reply_use_case = use_case(
+            Peer {
+                ics_enabled: true,
+                ..selected_peer()
+            },
+            &[],
+        );
+        let (_, context) = tokio_test::block_on(generate_reply_use_case.generate_reply(
+            PLATFORM_ID,
+            sender(1),
+            "/thisweek",
+        ))
+        .unwrap();
+        assert!(!context.ics_enabled);
+    }
+
+    #[test]
+    fn test_member_schedule_is_shown_by_mention_after_linking() {
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        let group_chat = PlatformId::telegram(-1);
+        let generate_reply = |platform_id: &PlatformId, sender_id, text| {
+            tokio_test::block_on(generate_reply_use_case.generate_reply(
+                platform_id.clone(),
+                sender(sender_id),
+                text,
+            ))
+            .unwrap()
+            .0
+            .variant_name()
+        };
+
+        assert_eq!(
+            "MemberScheduleNotLinked",
+            generate_reply(&group_chat, 1, "пары @user2")
+        );
+        assert_eq!(
+            "CannotFindSchedule",
+            generate_reply(&group_chat, 2, "/mygroup ъ-00-00")
+        );
+        assert_eq!(
+            "MemberScheduleLinked",
+            generate_reply(&group_chat, 2, "/mygroup А-08-19")
+        );
+        assert_eq!("Day", generate_reply(&group_chat, 1, "пары @User2 завтра"));
+        // links belong to the chat
+        assert_eq!(
+            "GroupChatOnly",
+            generate_reply(&PLATFORM_ID, 1, "пары @user2")
+        );
+        assert_eq!(
+            "MemberScheduleNotLinked",
+            generate_reply(&PlatformId::telegram(-2), 1, "пары @user2")
+        );
+        // opt-out
+        assert_eq!(
+            "MemberScheduleUnlinked",
+            generate_reply(&group_chat, 2, "/mygroup_off")
+        );
+        assert_eq!(
+            "MemberScheduleNotLinked",
+            generate_reply(&group_chat, 1, "пары @user2")
+        );
+    }
+
+    #[test]
+    fn test_member_schedule_of_deleted_member_is_hidden_and_purged() {
+        let generate_reply_use_case = use_case(selected_peer(), &[]);
+        let group_chat = PlatformId::telegram(-1);
+        let member = PlatformId::telegram(2);
+        let generate_reply = |platform_id: &PlatformId, sender_id, text| {
+            tokio_test::block_on(generate_reply_use_case.generate_reply(
+                platform_id.clone(),
+                sender(sender_id),
+                text,
+            ))
+            .unwrap()
+            .0
+            .variant_name()
+        };
+        let peer_repository = &generate_reply_use_case.1;
+
+        assert_eq!(
+            "MemberScheduleLinked",
+            generate_reply(&group_chat, 2, "/mygroup А-08-19")
+        );
+        assert_eq!(
+            "DataDeletionScheduled",
+            generate_reply(&member, 2, "/deletemydata")
+        );
+        assert_eq!(
+            "MemberScheduleNotLinked",
+            generate_reply(&group_chat, 1, "пары @user2")
+        );
+
+        let deleted =
+            tokio_test::block_on(peer_repository.get_peer_by_platform_id(member.clone())).unwrap();
+        tokio_test::block_on(peer_repository.save_peer(Peer {
+            deleted_at: deleted.deleted_at.map(|it| it - Duration::days(8)),
+            ..deleted
+        }))
+        .unwrap();
+        tokio_test::block_on(generate_reply_use_case.17.purge_deleted_peers()).unwrap();
+        // the member starts from scratch, without the links in the chats
+        let purged = tokio_test::block_on(peer_repository.get_peer_by_platform_id(member)).unwrap();
+        assert_eq!(None, purged.deleted_at);
+        assert_eq!(
+            "MemberScheduleNotLinked",
+            generate_reply(&group_chat, 1, "пары @user2")
+        );
+    }
+
+    #[test]
+    fn test_only_chat_admins_add_notes_in_group_chats() {
+        let notes_use_case = notes_use_case();
+        let group_chat = PlatformId::vk(2_000_000_001);
+        let note = ChatNote {
+            date: chrono::Local::now().date_naive(),
+            text: "Контрольная по физике".to_owned(),
+        };
+        let add_note = |sender_id| {
+            tokio_test::block_on(notes_use_case.add_note(
+                &group_chat,
+                sender_id,
+                &selected_peer(),
+                note.clone(),
+            ))
+            .unwrap()
+        };
+        assert!(matches!(
+            add_note(Some(2)),
+            Reply::NoteRejected(NoteRejection::NotChatAdmin)
+        ));
+        assert!(matches!(
+            add_note(None),
+            Reply::NoteRejected(NoteRejection::NotChatAdmin)
+        ));
+        assert!(matches!(add_note(Some(1)), Reply::NoteAdded(date) if date == note.date));
+    }
+
+    #[test]
+    fn test_locale_is_detected_from_the_first_message() {
+        let generate_reply_use_case = use_case(
+            Peer {
+                locale: None,
+                ..new_peer()
+            },
+            &[],
+        );
+        let (_, peer) = transition(generate_reply_use_case, "а-08-19");
+        assert_eq!(peer.locale, Some(Locale::Ru));
+
+        let generate_reply_use_case = use_case(
+            Peer {
+                locale: None,
+                ..new_peer()
+            },
+            &[],
+        );
+        let (_, peer) = transition(generate_reply_use_case, "/start");
+        assert_eq!(peer.locale, None);
+    }
+
+    #[test]
+    fn test_export_contains_subscriptions_and_recent_replies() {
+        let audit_repository = Arc::new(FakeAuditRepository::default());
+        for (platform_peer_id, text) in [(1, "first"), (2, "another chat"), (1, "second")] {
+            let entry = ReplyAuditEntry {
+                platform: "telegram",
+                platform_peer_id,
+                reply_variant: "Week",
+                text: text.to_owned(),
+                keyboard_version: None,
+                latency_ms: 0,
+            };
+            tokio_test::block_on(audit_repository.insert_entry(&entry)).unwrap();
+        }
+        let peer = Peer {
+            commute_minutes: Some(40),
+            ..selected_peer()
+        };
+        let member_schedules_repository = Arc::new(FakeMemberSchedulesRepository(Arc::new(
+            FakePeerRepository::new(vec![(PlatformId::telegram(1), peer.clone())]),
+        )));
+        let schedule = RecentSchedule {
+            name: GROUP.to_owned(),
+            r#type: ScheduleType::Group,
+        };
+        for (peer_id, member_id) in [(1, 2), (2, 3)] {
+            let member = ChatMember {
+                id: member_id,
+                username: Some(format!("User{member_id}")),
+            };
+            tokio_test::block_on(
+                member_schedules_repository.link_member_schedule(peer_id, &member, &schedule),
+            )
+            .unwrap();
+        }
+        let use_case = ExportPeerDataUseCase {
+            recent_schedules_repository: Arc::new(FakeRecentSchedulesRepository::default()),
+            achievements_repository: Arc::new(FakeAchievementsRepository::default()),
+            audit_repository,
+            member_schedules_repository,
+        };
+        let reply = tokio_test::block_on(use_case.export(&PlatformId::telegram(1), &peer)).unwrap();
+        let Reply::DataExport(export) = reply else {
+            panic!("Unexpected reply: {}", reply.variant_name());
+        };
+        assert_eq!(export.platform, "telegram");
+        assert_eq!(export.subscriptions, vec!["evening_summary"]);
+        assert_eq!(export.selected_schedule.unwrap().name, GROUP);
+        assert_eq!(export.achievements, None);
+        let texts = export
+            .recent_replies
+            .iter()
+            .map(|it| it.text.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["second", "first"]);
+        assert_eq!(
+            export.member_schedules,
+            vec![MemberScheduleExport {
+                member_id: 2,
+                username: Some("user2".to_owned()),
+                schedule,
+            }]
+        );
+        assert_eq!(export.deleted_at, None);
+    }
+
+    fn reply(use_case: &GenerateReplyUseCase, text: &str) -> (&'static str, bool) {
+        let (reply, context) =
+            tokio_test::block_on(use_case.generate_reply(PLATFORM_ID, sender(1), text)).unwrap();
+        (reply.variant_name(), context.privacy_mode)
+    }
+
+    #[test]
+    fn test_deleted_peer_is_excluded_until_restored() {
+        let generate_reply_use_case = use_case(
+            Peer {
+                subscribed: true,
+                ..selected_peer()
+            },
+            &[],
+        );
+        assert_eq!(
+            ("DataDeletionScheduled", false),
+            reply(&generate_reply_use_case, "/deletemydata")
+        );
+        let peer = tokio_test::block_on(
+            generate_reply_use_case
+                .1
+                .get_peer_by_platform_id(PLATFORM_ID),
+        )
+        .unwrap();
+        assert!(peer.deleted_at.is_some());
+        // deleted peers get no pushes and no replies, and nothing about them is audited
+        let subscribed = tokio_test::block_on(
+            generate_reply_use_case
+                .1
+                .get_peers_with_push(PlatformId::vk, PushOptIn::ScheduleChanges),
+        )
+        .unwrap();
+        assert!(subscribed.is_empty());
+        for text in ["/today", "/start", "/export", "/unsubscribe"] {
+            assert_eq!(
+                ("DataDeletionPending", true),
+                reply(&generate_reply_use_case, text),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            ("DataDeletionScheduled", true),
+            reply(&generate_reply_use_case, "/deletemydata")
+        );
+
+        assert_eq!(
+            ("DataRestored", true),
+            reply(&generate_reply_use_case, "/restore")
+        );
+        let (_, peer) = transition(generate_reply_use_case, "/restore");
+        assert_eq!(
+            Peer {
+                subscribed: true,
+                ..selected_peer()
+            },
+            peer
+        );
+    }
+
+    #[test]
+    fn test_restore_without_deletion() {
+        let (reply, peer) = transition(use_case(new_peer(), &[]), "/restore");
+        assert_eq!("NothingToRestore", reply);
+        assert_eq!(new_peer(), peer);
+    }
+
+    #[test]
+    fn test_deleted_peers_are_purged_after_undo_window() {
+        let now = Local::now().naive_local();
+        let peer_repository = Arc::new(FakePeerRepository::new(vec![
+            (PlatformId::telegram(1), selected_peer()),
+            (
+                PlatformId::telegram(2),
+                Peer {
+                    id: 2,
+                    deleted_at: Some(now - Duration::days(6)),
+                    ..selected_peer()
+                },
+            ),
+            (
+                PlatformId::telegram(3),
+                Peer {
+                    id: 3,
+                    deleted_at: Some(now - Duration::days(8)),
+                    ..selected_peer()
+                },
+            ),
+        ]));
+        let use_case = PeerDeletionUseCase::new(peer_repository.clone());
+        let Reply::DataDeletionPending { purge_date } =
+            use_case.pending_deletion(now - Duration::days(6))
+        else {
+            panic!("Unexpected reply");
+        };
+        assert_eq!((now + Duration::days(1)).date(), purge_date);
+
+        tokio_test::block_on(use_case.purge_deleted_peers()).unwrap();
+        let get_peer = |id| {
+            tokio_test::block_on(peer_repository.get_peer_by_platform_id(PlatformId::telegram(id)))
+                .unwrap()
+        };
+        assert_eq!(selected_peer(), get_peer(1));
+        assert!(get_peer(2).deleted_at.is_some());
+        // the purged peer starts from scratch
+        let purged = get_peer(3);
+        assert!(purged.selected_schedule.is_empty());
+        assert_eq!(None, purged.deleted_at);
     }
 }
@@ -0,0 +1,123 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use chrono::NaiveDate;
+
+use crate::models::RoomChangeWarning;
+
+/// Pending warnings about the moved classes by peer id, kept in memory until the peer
+/// sees the schedule of the day. Warnings are lost on restart, it is fine,
+/// because the subscribed peers also get the change notifications.
+#[derive(Default)]
+pub struct RoomChangeWarningsRepository {
+    warnings: Mutex<HashMap<i64, Vec<RoomChangeWarning>>>,
+}
+
+impl RoomChangeWarningsRepository {
+    /// Remember the warnings of the peer. The warning about the same classes
+    /// is replaced, so only the latest room is shown.
+    pub fn add_warnings(&self, peer_id: i64, new_warnings: &[RoomChangeWarning]) {
+        if new_warnings.is_empty() {
+            return;
+        }
+        let mut warnings = self.warnings.lock().unwrap();
+        let peer_warnings = warnings.entry(peer_id).or_default();
+        for warning in new_warnings {
+            peer_warnings.retain(|it| !it.is_about_same_classes(warning));
+            peer_warnings.push(warning.to_owned());
+        }
+    }
+
+    /// Take the warnings of the peer about the date, they are shown only once.
+    /// Warnings about the days before `today` are dropped.
+    pub fn take_warnings(
+        &self,
+        peer_id: i64,
+        date: NaiveDate,
+        today: NaiveDate,
+    ) -> Vec<RoomChangeWarning> {
+        let mut warnings = self.warnings.lock().unwrap();
+        let Some(peer_warnings) = warnings.get_mut(&peer_id) else {
+            return Vec::new();
+        };
+        let (taken, rest) = peer_warnings
+            .drain(..)
+            .filter(|it| it.date >= today)
+            .partition(|it| it.date == date);
+        *peer_warnings = rest;
+        if peer_warnings.is_empty() {
+            warnings.remove(&peer_id);
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::ClassesTime;
+
+    use super::RoomChangeWarningsRepository;
+    use crate::models::RoomChangeWarning;
+
+    fn warning(date: &str, place: &str) -> RoomChangeWarning {
+        RoomChangeWarning {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            class_name: "Матан".to_owned(),
+            time: ClassesTime {
+                start: NaiveTime::from_hms_opt(9, 20, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(10, 55, 0).unwrap(),
+            },
+            place: place.to_owned(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn warnings_are_taken_once_by_date() {
+        let repository = RoomChangeWarningsRepository::default();
+        repository.add_warnings(
+            1,
+            &[
+                warning("2023-03-13", "Ж-200"),
+                warning("2023-03-14", "Б-100"),
+            ],
+        );
+        let today = date("2023-03-13");
+        assert_eq!(
+            vec![warning("2023-03-13", "Ж-200")],
+            repository.take_warnings(1, today, today)
+        );
+        assert!(repository.take_warnings(1, today, today).is_empty());
+        assert!(repository.take_warnings(2, today, today).is_empty());
+        assert_eq!(
+            vec![warning("2023-03-14", "Б-100")],
+            repository.take_warnings(1, date("2023-03-14"), today)
+        );
+    }
+
+    #[test]
+    fn latest_room_replaces_previous_warning() {
+        let repository = RoomChangeWarningsRepository::default();
+        repository.add_warnings(1, &[warning("2023-03-13", "Ж-200")]);
+        repository.add_warnings(1, &[warning("2023-03-13", "К-601")]);
+        let today = date("2023-03-13");
+        assert_eq!(
+            vec![warning("2023-03-13", "К-601")],
+            repository.take_warnings(1, today, today)
+        );
+    }
+
+    #[test]
+    fn past_warnings_are_dropped() {
+        let repository = RoomChangeWarningsRepository::default();
+        repository.add_warnings(1, &[warning("2023-03-13", "Ж-200")]);
+        let today = date("2023-03-14");
+        assert!(repository.take_warnings(1, today, today).is_empty());
+        assert!(repository
+            .take_warnings(1, date("2023-03-13"), today)
+            .is_empty());
+    }
+}
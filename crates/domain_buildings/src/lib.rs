@@ -0,0 +1,177 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+/// MPEI campus building, rooms of which can be found in schedules.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Building {
+    /// Building code, which is used as a prefix of room names (e.g. `К` for room `К-205`)
+    #[serde(default)]
+    pub code: String,
+    pub name: String,
+    pub address: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Short directions for the first-time visitors: entrance, stairs, landmarks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub navigation: Option<String>,
+}
+
+/// Map service used for map deep links rendering
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapProvider {
+    Yandex,
+    Google,
+}
+
+impl AsRef<str> for MapProvider {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Yandex => "yandex",
+            Self::Google => "google",
+        }
+    }
+}
+
+impl FromStr for MapProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "yandex" => Ok(Self::Yandex),
+            "google" => Ok(Self::Google),
+            _ => bail!("Unknown map provider: '{s}'"),
+        }
+    }
+}
+
+impl Building {
+    /// Deep link to the building location on the map of specified provider.
+    pub fn map_url(&self, provider: MapProvider) -> String {
+        let (lat, lon) = (self.latitude, self.longitude);
+        match provider {
+            MapProvider::Yandex => format!("https://yandex.ru/maps/?pt={lon},{lat}&z=18&l=map"),
+            MapProvider::Google => {
+                format!("https://www.google.com/maps/search/?api=1&query={lat},{lon}")
+            }
+        }
+    }
+}
+
+/// Typed configuration of MPEI campus buildings.
+/// Shared between `app_schedule` (`/v1/buildings` endpoint) and bots (map links rendering).
+#[derive(Debug, PartialEq)]
+pub struct Buildings(BTreeMap<String, Building>);
+
+impl Buildings {
+    /// Get all buildings ordered by code.
+    pub fn all(&self) -> Vec<Building> {
+        self.0.values().cloned().collect()
+    }
+
+    /// Get building by its code (case-insensitive).
+    pub fn get(&self, code: &str) -> Option<&Building> {
+        self.0.get(&code.trim().to_uppercase())
+    }
+
+    /// Find the building in which the room is located.
+    /// Room names look like `К-205`, `Ж-120а` or `М-611`.
+    pub fn find_by_room(&self, room: &str) -> Option<&Building> {
+        let (code, _) = room.trim().split_once('-')?;
+        self.get(code)
+    }
+}
+
+/// Floor of the room, which is the first digit of the three-digit room number:
+/// `К-601` is on the 6th floor, `Ж-120а` is on the 1st one.
+pub fn room_floor(room: &str) -> Option<u8> {
+    let (_, number) = room.trim().split_once('-')?;
+    let digits = number
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<Vec<_>>();
+    match digits.as_slice() {
+        [floor, _, _] => floor.to_digit(10).map(|it| it as u8),
+        _ => None,
+    }
+}
+
+impl Default for Buildings {
+    fn default() -> Self {
+        include_str!("../res/default_buildings.toml")
+            .parse()
+            .expect("Default buildings config must be valid")
+    }
+}
+
+impl FromStr for Buildings {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let mut buildings = toml::from_str::<BTreeMap<String, Building>>(s)?;
+        for (code, building) in buildings.iter_mut() {
+            building.code = code.to_owned();
+        }
+        Ok(Self(buildings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{room_floor, Buildings, MapProvider};
+
+    #[test]
+    fn test_default_buildings_are_valid() {
+        let buildings = Buildings::default();
+        assert!(!buildings.all().is_empty());
+        assert!(buildings.all().iter().all(|it| !it.code.is_empty()));
+    }
+
+    #[test]
+    fn test_find_by_room() {
+        let buildings = Buildings::default();
+        assert_eq!(buildings.find_by_room("К-205").unwrap().code, "К");
+        assert_eq!(buildings.find_by_room("ж-120а").unwrap().code, "Ж");
+        assert!(buildings.find_by_room("Спортзал").is_none());
+        assert!(buildings.find_by_room("Я-100").is_none());
+    }
+
+    #[test]
+    fn test_room_floor() {
+        assert_eq!(room_floor("К-601"), Some(6));
+        assert_eq!(room_floor("Ж-120а"), Some(1));
+        assert_eq!(room_floor("М-V"), None);
+        assert_eq!(room_floor("А-10"), None);
+        assert_eq!(room_floor("Спортзал"), None);
+    }
+
+    #[test]
+    fn test_default_buildings_have_navigation() {
+        let buildings = Buildings::default();
+        assert!(buildings.all().iter().all(|it| it.navigation.is_some()));
+    }
+
+    #[test]
+    fn test_map_url() {
+        let buildings: Buildings = r#"
+            ["К"]
+            name = "Корпус К"
+            address = "Красноказарменная ул., 13"
+            latitude = 55.75
+            longitude = 37.7
+        "#
+        .parse()
+        .unwrap();
+        let building = buildings.get("к").unwrap();
+        assert_eq!(
+            building.map_url(MapProvider::Yandex),
+            "https://yandex.ru/maps/?pt=37.7,55.75&z=18&l=map"
+        );
+        assert_eq!(
+            building.map_url(MapProvider::Google),
+            "https://www.google.com/maps/search/?api=1&query=55.75,37.7"
+        );
+    }
+}
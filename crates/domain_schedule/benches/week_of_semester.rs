@@ -0,0 +1,53 @@
+use std::str::FromStr;
+
+use chrono::{Days, NaiveDate};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use domain_schedule::time::NaiveDateExt;
+use domain_schedule_shift::ScheduleShift;
+
+/// Shifts of several years, like in the production config
+const SHIFTS: &str = r#"
+[2021]
+spring = { first-day = "2021-02-15", week-number = 0 }
+fall = { first-day = "2021-09-06", week-number = 2 }
+
+[2022]
+spring = { first-day = "2022-02-16" }
+fall = { first-day = "2022-09-16" }
+
+[2023]
+spring = { first-day = "2023-02-08", week-number = 0 }
+"#;
+
+/// Every day of the year, so both semesters, the summer and the January weekend are covered
+fn dates() -> Vec<NaiveDate> {
+    let first_day = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+    (0..365)
+        .map(|offset| first_day.checked_add_days(Days::new(offset)).unwrap())
+        .collect()
+}
+
+fn week_of_semester(c: &mut Criterion) {
+    let shifts = ScheduleShift::from_str(SHIFTS).unwrap();
+    let dates = dates();
+
+    let mut group = c.benchmark_group("week_of_semester");
+    group.bench_function("without_shifts", |b| {
+        b.iter(|| {
+            for date in &dates {
+                black_box(date.week_of_semester(None));
+            }
+        })
+    });
+    group.bench_function("with_shifts", |b| {
+        b.iter(|| {
+            for date in &dates {
+                black_box(date.week_of_semester(Some(&shifts)));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, week_of_semester);
+criterion_main!(benches);
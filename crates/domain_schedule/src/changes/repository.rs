@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::NaiveDateTime;
+use common_database::Database;
+use domain_schedule_models::{ClassesChange, ClassesChangeKind, ScheduleType};
+use log::{info, warn};
+use tokio_postgres::Row;
+
+use crate::dto::mpeix::ScheduleName;
+
+/// Changes older than this are deleted, weekly digests do not need them
+const KEEP_CHANGES_DAYS: u32 = 30;
+
+/// Repository for accessing table `schedule_change` of the mpeix database.
+///
+/// Table contains changes of the classes detected when a fresh schedule from the MPEI backend
+/// replaces the cached one, so clients can find out what has changed during the last days.
+pub struct ScheduleChangeRepository {
+    database: Arc<Database>,
+}
+
+impl ScheduleChangeRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn init_schedule_change_db(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        client
+            .batch_execute(include_str!("../../sql/create_schedule_change.pgsql"))
+            .await
+            .with_context(|| "Error during table 'schedule_change' creation")?;
+        let stmt = format!(
+            include_str!("../../sql/delete_old_schedule_changes.pgsql"),
+            keep_days = KEEP_CHANGES_DAYS,
+        );
+        let deleted = client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error while deleting old schedule changes")?;
+        info!("Table 'schedule_change' initialization passed successfully, {deleted} old changes deleted");
+        Ok(())
+    }
+
+    pub async fn save_changes(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        changes: &[ClassesChange],
+    ) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/insert_schedule_change.pgsql");
+        for change in changes {
+            let before = change
+                .before
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            let after = change
+                .after
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?;
+            client
+                .execute(
+                    stmt,
+                    &[
+                        &name.as_ref(),
+                        &r#type.to_string(),
+                        &change.date.to_string(),
+                        &kind_to_db(change.kind),
+                        &before,
+                        &after,
+                    ],
+                )
+                .await
+                .with_context(|| "Error while saving schedule change to db")?;
+        }
+        Ok(())
+    }
+
+    /// Get changes detected since `since`, in the order of detection
+    pub async fn get_changes(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        since: NaiveDateTime,
+    ) -> anyhow::Result<Vec<ClassesChange>> {
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_schedule_changes.pgsql");
+        Ok(client
+            .query(
+                stmt,
+                &[&name.as_ref(), &r#type.to_string(), &since.to_string()],
+            )
+            .await
+            .with_context(|| "Error while getting schedule changes from db")?
+            .iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+}
+
+fn kind_to_db(kind: ClassesChangeKind) -> &'static str {
+    match kind {
+        ClassesChangeKind::Added => "added",
+        ClassesChangeKind::Removed => "removed",
+        ClassesChangeKind::Changed => "changed",
+    }
+}
+
+fn map_from_db_model(row: &Row) -> Option<ClassesChange> {
+    let date = row.try_get::<_, String>("date").ok()?.parse().ok()?;
+    let kind = match row.try_get::<_, &str>("kind").ok()? {
+        "added" => ClassesChangeKind::Added,
+        "removed" => ClassesChangeKind::Removed,
+        "changed" => ClassesChangeKind::Changed,
+        other => {
+            warn!("Database contains invalid schedule change kind: '{other}'");
+            return None;
+        }
+    };
+    let classes = |column: &str| {
+        row.try_get::<_, Option<&str>>(column)
+            .ok()
+            .flatten()
+            .and_then(|it| {
+                serde_json::from_str(it)
+                    .map_err(|e| warn!("Cannot deserialize changed classes of {date}: {e}"))
+                    .ok()
+            })
+    };
+    Some(ClassesChange {
+        date,
+        kind,
+        before: classes("before"),
+        after: classes("after"),
+    })
+}
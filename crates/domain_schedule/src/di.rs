@@ -1,26 +1,40 @@
 use std::sync::Arc;
 
 use common_di::di_constructor;
+use common_events::EventBus;
+use common_restix::{create_reqwest_client, RetryInterceptor};
+use common_rust::env;
 use domain_schedule_cooldown::ScheduleCooldownRepository;
+use log::warn;
 
 use crate::{
+    changes::repository::ScheduleChangeRepository,
     id::repository::ScheduleIdRepository,
+    mock::MockMpeiApi,
+    mpei_api::{MpeiApi, MpeiUpstream},
+    overrides::repository::ScheduleOverrideRepository,
+    popularity::repository::SchedulePopularityRepository,
+    quality::repository::ScheduleQualityRepository,
     schedule::repository::ScheduleRepository,
     schedule_shift::repository::ScheduleShiftRepository,
     search::repository::ScheduleSearchRepository,
+    status::repository::ServiceStatusRepository,
+    upstream_audit::{
+        record_call_timings, repository::UpstreamAuditRepository, UpstreamAuditInterceptor,
+    },
     usecases::{
-        GetScheduleIdUseCase, GetScheduleUseCase, InitDomainScheduleUseCase, SearchScheduleUseCase,
+        AutocompleteScheduleUseCase, DiffScheduleUseCase, FindFreeRoomsUseCase,
+        GetScheduleChangesUseCase, GetScheduleIdUseCase, GetScheduleLatencyUseCase,
+        GetScheduleQualityUseCase, GetScheduleUseCase, GetServiceStatusUseCase,
+        GetShiftRulesUseCase, GetTrendingSchedulesUseCase, InitDomainScheduleUseCase,
+        PrefetchScheduleUseCase, ReloadConfigUseCase, ScheduleEventsUseCase, SearchScheduleUseCase,
+        SetScheduleOverridesUseCase, UpstreamAuditUseCase, VerifyScheduleUseCase,
     },
 };
 
 di_constructor! { GetScheduleIdUseCase(schedule_id_repository: Arc<ScheduleIdRepository>) }
 di_constructor! {
-    GetScheduleUseCase {
-        schedule_id_repository: Arc<ScheduleIdRepository>,
-        schedule_repository: Arc<ScheduleRepository>,
-        schedule_shift_repository: Arc<ScheduleShiftRepository>,
-        schedule_cooldown_repository: Arc<ScheduleCooldownRepository>
-    }
+    AutocompleteScheduleUseCase(schedule_search_repository: Arc<ScheduleSearchRepository>)
 }
 di_constructor! {
     SearchScheduleUseCase {
@@ -29,5 +43,131 @@ di_constructor! {
     }
 }
 di_constructor! {
-    InitDomainScheduleUseCase(schedule_search_repository: Arc<ScheduleSearchRepository>)
+    InitDomainScheduleUseCase(
+        schedule_search_repository: Arc<ScheduleSearchRepository>,
+        schedule_popularity_repository: Arc<SchedulePopularityRepository>,
+        schedule_quality_repository: Arc<ScheduleQualityRepository>,
+        schedule_override_repository: Arc<ScheduleOverrideRepository>,
+        schedule_change_repository: Arc<ScheduleChangeRepository>,
+        upstream_audit_repository: Arc<UpstreamAuditRepository>
+    )
+}
+di_constructor! {
+    ScheduleEventsUseCase {
+        event_bus: Arc<EventBus>,
+        schedule_popularity_repository: Arc<SchedulePopularityRepository>,
+        schedule_change_repository: Arc<ScheduleChangeRepository>
+    }
+}
+di_constructor! { GetScheduleChangesUseCase(schedule_change_repository: Arc<ScheduleChangeRepository>) }
+di_constructor! {
+    GetTrendingSchedulesUseCase(schedule_popularity_repository: Arc<SchedulePopularityRepository>)
+}
+di_constructor! { GetShiftRulesUseCase(schedule_shift_repository: Arc<ScheduleShiftRepository>) }
+di_constructor! {
+    GetScheduleQualityUseCase(schedule_quality_repository: Arc<ScheduleQualityRepository>)
+}
+di_constructor! {
+    SetScheduleOverridesUseCase(schedule_override_repository: Arc<ScheduleOverrideRepository>)
+}
+di_constructor! { VerifyScheduleUseCase(get_schedule_use_case: Arc<GetScheduleUseCase>) }
+di_constructor! { DiffScheduleUseCase(get_schedule_use_case: Arc<GetScheduleUseCase>) }
+di_constructor! { GetScheduleLatencyUseCase(get_schedule_use_case: Arc<GetScheduleUseCase>) }
+di_constructor! { UpstreamAuditUseCase(upstream_audit_repository: Arc<UpstreamAuditRepository>) }
+di_constructor! {
+    GetServiceStatusUseCase(
+        service_status_repository: Arc<ServiceStatusRepository>,
+        schedule_cooldown_repository: Arc<ScheduleCooldownRepository>
+    )
+}
+di_constructor! {
+    ReloadConfigUseCase(
+        schedule_id_repository: Arc<ScheduleIdRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+        schedule_search_repository: Arc<ScheduleSearchRepository>,
+        schedule_shift_repository: Arc<ScheduleShiftRepository>
+    )
+}
+
+impl MpeiUpstream {
+    /// Real MPEI api with all calls audited and transient errors retried,
+    /// or fixture-backed fake if `MOCK_UPSTREAM=true`
+    pub fn new(upstream_audit_repository: Arc<UpstreamAuditRepository>) -> Self {
+        if env::get_parsed_or("MOCK_UPSTREAM", false) {
+            warn!("MOCK_UPSTREAM is enabled, MPEI api is replaced with fixtures");
+            Self::Mock(MockMpeiApi)
+        } else {
+            #[allow(unused_mut)]
+            let mut inner: Option<Arc<dyn restix::Interceptor>> = None;
+            #[cfg(feature = "record-replay")]
+            if let Some(interceptor) = crate::record_replay::RecordReplayInterceptor::from_env() {
+                inner = Some(Arc::new(interceptor));
+            }
+            let mut builder = MpeiApi::builder()
+                .client(create_reqwest_client())
+                // each retry is audited separately
+                .interceptor(Arc::new(RetryInterceptor::new(Some(Arc::new(
+                    UpstreamAuditInterceptor::new(upstream_audit_repository, inner),
+                )))));
+            if env::get_parsed_or("GATEWAY_CALL_TIMINGS", false) {
+                builder = builder.on_timings(Arc::new(record_call_timings));
+            }
+            let api = builder.build().expect("DI error while creating MpeiApi");
+            Self::Remote(api)
+        }
+    }
+}
+
+impl GetScheduleUseCase {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        schedule_id_repository: Arc<ScheduleIdRepository>,
+        schedule_repository: Arc<ScheduleRepository>,
+        schedule_shift_repository: Arc<ScheduleShiftRepository>,
+        schedule_cooldown_repository: Arc<ScheduleCooldownRepository>,
+        schedule_quality_repository: Arc<ScheduleQualityRepository>,
+        schedule_override_repository: Arc<ScheduleOverrideRepository>,
+        event_bus: Arc<EventBus>,
+    ) -> Self {
+        Self {
+            schedule_id_repository,
+            schedule_repository,
+            schedule_shift_repository,
+            schedule_cooldown_repository,
+            schedule_quality_repository,
+            schedule_override_repository,
+            event_bus,
+            fetch_strategy: Default::default(),
+            fetch_latency: Default::default(),
+        }
+    }
+}
+
+impl FindFreeRoomsUseCase {
+    pub fn new(
+        schedule_search_repository: Arc<ScheduleSearchRepository>,
+        get_schedule_use_case: Arc<GetScheduleUseCase>,
+    ) -> Self {
+        Self {
+            schedule_search_repository,
+            get_schedule_use_case,
+            config: Default::default(),
+        }
+    }
+}
+
+impl PrefetchScheduleUseCase {
+    pub fn new(
+        get_schedule_use_case: Arc<GetScheduleUseCase>,
+        schedule_popularity_repository: Arc<SchedulePopularityRepository>,
+        schedule_cooldown_repository: Arc<ScheduleCooldownRepository>,
+    ) -> Self {
+        Self {
+            get_schedule_use_case,
+            schedule_popularity_repository,
+            schedule_cooldown_repository,
+            config: Default::default(),
+            last_prefetch_date: Default::default(),
+        }
+    }
 }
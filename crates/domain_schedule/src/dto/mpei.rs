@@ -1,4 +1,5 @@
 use chrono::{NaiveDate, NaiveTime};
+use domain_schedule_models::ScheduleId;
 use serde::{
     de::{self, Visitor},
     Deserialize,
@@ -6,7 +7,7 @@ use serde::{
 
 #[derive(Debug, Deserialize)]
 pub struct MpeiSearchResult {
-    pub id: i64,
+    pub id: ScheduleId,
     /// Group name
     pub label: String,
     /// Faculty + description
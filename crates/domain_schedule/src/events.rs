@@ -0,0 +1,27 @@
+use common_events::Event;
+use domain_schedule_models::{ClassesChange, ScheduleType};
+
+use crate::dto::mpeix::ScheduleName;
+
+/// Schedule is requested by the user directly, e.g. not aggregated in the free rooms search
+#[derive(Debug, Clone)]
+pub struct ScheduleRequested {
+    pub name: ScheduleName,
+    pub r#type: ScheduleType,
+}
+
+impl Event for ScheduleRequested {
+    const TOPIC: &'static str = "schedule_requested";
+}
+
+/// Fresh schedule from the MPEI backend differs from the cached one, which it replaces
+#[derive(Debug, Clone)]
+pub struct ScheduleChanged {
+    pub name: ScheduleName,
+    pub r#type: ScheduleType,
+    pub changes: Vec<ClassesChange>,
+}
+
+impl Event for ScheduleChanged {
+    const TOPIC: &'static str = "schedule_changed";
+}
@@ -1,9 +1,8 @@
 use anyhow::bail;
+use common_config::CacheConfig;
 use common_errors::errors::CommonError;
 use common_in_memory_cache::InMemoryCache;
-use common_restix::ResultExt;
-use common_rust::env;
-use domain_schedule_models::ScheduleType;
+use domain_schedule_models::{ScheduleId, ScheduleType};
 use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
@@ -11,7 +10,7 @@ use tokio::sync::Mutex;
 
 use crate::{
     dto::{mpei::MpeiSearchResult, mpeix::ScheduleName as ValidScheduleName},
-    mpei_api::MpeiApi,
+    mpei_api::MpeiUpstream,
 };
 
 lazy_static! {
@@ -19,7 +18,7 @@ lazy_static! {
 }
 
 pub struct ScheduleIdRepository {
-    api: MpeiApi,
+    api: MpeiUpstream,
     cache: Mutex<InMemoryCache<ScheduleName, ScheduleId>>,
 }
 
@@ -31,25 +30,28 @@ struct ScheduleName {
     r#type: ScheduleType,
 }
 
-/// Helper struct for [ScheduleIdRepository]:
-/// Value for in-memory cache
-struct ScheduleId(i64);
-
 impl ScheduleIdRepository {
-    pub fn new(api: MpeiApi) -> Self {
-        let cache_capacity = env::get_parsed_or("SCHEDULE_ID_CACHE_CAPACITY", 3000);
-        let cache_max_hits = env::get_parsed_or("SCHEDULE_ID_CACHE_MAX_HITS", 10);
-        let cache_lifetife = env::get_parsed_or("SCHEDULE_ID_CACHE_LIFETIME_HOURS", 12);
-
+    pub fn new(api: MpeiUpstream) -> Self {
         Self {
             api,
-            cache: Mutex::new(
-                InMemoryCache::with_capacity(cache_capacity)
-                    .max_hits(cache_max_hits)
-                    .expires_after_creation(chrono::Duration::hours(cache_lifetife)),
-            ),
+            cache: Mutex::new(create_in_memory_cache()),
         }
     }
+
+    /// Apply the reloaded in-memory cache policies, cached ids are kept
+    pub async fn reload_config(&self) {
+        self.cache
+            .lock()
+            .await
+            .replace_policies(create_in_memory_cache());
+    }
+}
+
+fn create_in_memory_cache() -> InMemoryCache<ScheduleName, ScheduleId> {
+    let config = CacheConfig::new(3000, chrono::Duration::hours(12))
+        .max_hits(10)
+        .with_env_prefix("SCHEDULE_ID_CACHE");
+    InMemoryCache::with_config(&config).named("schedule_id")
 }
 
 impl ScheduleIdRepository {
@@ -57,7 +59,7 @@ impl ScheduleIdRepository {
         &self,
         name: ValidScheduleName,
         r#type: ScheduleType,
-    ) -> anyhow::Result<i64> {
+    ) -> anyhow::Result<ScheduleId> {
         debug!("Trying to get schedule id from cache...");
         let cache_key = ScheduleName {
             r#type: r#type.to_owned(),
@@ -65,7 +67,7 @@ impl ScheduleIdRepository {
         };
         if let Some(value) = self.cache.lock().await.get(&cache_key) {
             debug!("Got schedule id from cache");
-            return Ok(value.0);
+            return Ok(*value);
         };
 
         debug!("Getting schedule id from remote...");
@@ -76,13 +78,10 @@ impl ScheduleIdRepository {
             Some(search_result) => {
                 debug!("Got schedule id from remote");
                 // Put value to cache
-                self.cache
-                    .lock()
-                    .await
-                    .insert(cache_key, ScheduleId(search_result.id));
+                self.cache.lock().await.insert(cache_key, search_result.id);
                 Ok(search_result.id)
             }
-            _ => bail!(CommonError::user(format!(
+            _ => bail!(CommonError::not_found(format!(
                 "Schedule with type '{:?}' and name '{}' not found",
                 r#type, cache_key.name
             ))),
@@ -94,11 +93,7 @@ impl ScheduleIdRepository {
         name: ValidScheduleName,
         r#type: ScheduleType,
     ) -> anyhow::Result<Option<MpeiSearchResult>> {
-        let search_results = self
-            .api
-            .search(name.as_ref(), &r#type)
-            .await
-            .with_common_error()?;
+        let search_results = self.api.search(name.as_ref(), &r#type).await?;
         Ok(search_results
             .into_iter()
             .find(|result| self.fuzzy_equals(name.as_ref(), &result.label)))
@@ -1,9 +1,19 @@
+pub mod changes;
 pub mod di;
 pub mod dto;
+pub mod events;
 pub mod id;
+pub mod mock;
 pub mod mpei_api;
+pub mod overrides;
+pub mod popularity;
+pub mod quality;
+#[cfg(feature = "record-replay")]
+pub mod record_replay;
 pub mod schedule;
 pub mod schedule_shift;
 pub mod search;
-pub(crate) mod time;
+pub mod status;
+pub mod time;
+pub mod upstream_audit;
 pub mod usecases;
@@ -0,0 +1,79 @@
+use chrono::{Days, NaiveDate};
+use domain_schedule_models::ScheduleType;
+
+use crate::dto::mpei::{MpeiClasses, MpeiSearchResult};
+
+/// Monday of the week, for which classes in `res/mock/schedule.json` are described
+const FIXTURE_WEEK_START: (i32, u32, u32) = (2023, 2, 6);
+
+/// Fixture-backed fake of [crate::mpei_api::MpeiApi] for offline development.
+///
+/// Search results are filtered from `res/mock/search.json`, every schedule has
+/// the same classes from `res/mock/schedule.json`, moved to the requested week.
+#[derive(Clone, Default)]
+pub struct MockMpeiApi;
+
+impl MockMpeiApi {
+    pub fn search(&self, query: &str, r#type: &ScheduleType) -> Vec<MpeiSearchResult> {
+        let query = query.to_lowercase();
+        serde_json::from_str::<Vec<MpeiSearchResult>>(include_str!("../res/mock/search.json"))
+            .expect("Invalid search results fixture")
+            .into_iter()
+            .filter(|it| it.r#type == r#type.as_ref())
+            .filter(|it| it.label.to_lowercase().contains(&query))
+            .collect()
+    }
+
+    pub fn schedule(&self, start: &str) -> Vec<MpeiClasses> {
+        let fixture_week_start = {
+            let (y, m, d) = FIXTURE_WEEK_START;
+            NaiveDate::from_ymd_opt(y, m, d).expect("Valid fixture date")
+        };
+        let Ok(week_start) = NaiveDate::parse_from_str(start, "%Y.%m.%d") else {
+            return Vec::new();
+        };
+        let days_offset = (week_start - fixture_week_start).num_days();
+        serde_json::from_str::<Vec<MpeiClasses>>(include_str!("../res/mock/schedule.json"))
+            .expect("Invalid schedule fixture")
+            .into_iter()
+            .filter_map(|mut cls| {
+                cls.date = if days_offset >= 0 {
+                    cls.date.checked_add_days(Days::new(days_offset as u64))
+                } else {
+                    cls.date.checked_sub_days(Days::new(-days_offset as u64))
+                }?;
+                Some(cls)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use domain_schedule_models::ScheduleType;
+
+    use super::MockMpeiApi;
+
+    #[test]
+    fn test_mock_search() {
+        let results = MockMpeiApi.search("а-08", &ScheduleType::Group);
+        assert_eq!(1, results.len());
+        assert_eq!("А-08-19", results[0].label);
+        assert!(MockMpeiApi.search("а-08", &ScheduleType::Person).is_empty());
+    }
+
+    #[test]
+    fn test_mock_schedule_is_moved_to_requested_week() {
+        let classes = MockMpeiApi.schedule("2024.03.04");
+        assert!(!classes.is_empty());
+        assert_eq!(
+            NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+            classes[0].date
+        );
+        assert!(MockMpeiApi.schedule("2022.10.03").iter().all(|cls| {
+            cls.date >= NaiveDate::from_ymd_opt(2022, 10, 3).unwrap()
+                && cls.date < NaiveDate::from_ymd_opt(2022, 10, 10).unwrap()
+        }));
+    }
+}
@@ -1,9 +1,13 @@
-use domain_schedule_models::ScheduleType;
+use common_errors::errors::CommonError;
+use domain_schedule_models::{ScheduleId, ScheduleType};
 use restix::{api, get};
 
-use crate::dto::mpei::{MpeiClasses, MpeiSearchResult};
+use crate::{
+    dto::mpei::{MpeiClasses, MpeiSearchResult},
+    mock::MockMpeiApi,
+};
 
-#[api(base_url = "http://ts.mpei.ru/api")]
+#[api(base_url = "http://ts.mpei.ru/api", error = "CommonError")]
 pub trait MpeiApi {
     #[get("/search")]
     async fn search(
@@ -16,9 +20,45 @@ pub trait MpeiApi {
     async fn schedule(
         &self,
         #[path] r#type: &ScheduleType,
-        #[path] id: i64,
+        #[path] id: ScheduleId,
         #[query] start: &str,
         #[query] finish: &str,
         #[query] lng: u8,
     ) -> Vec<MpeiClasses>;
 }
+
+/// Source of the MPEI data: the real MPEI backend
+/// or canned fixtures for offline development (`MOCK_UPSTREAM=true`).
+/// Check [crate::di] module for details.
+#[derive(Clone)]
+pub enum MpeiUpstream {
+    Remote(MpeiApi),
+    Mock(MockMpeiApi),
+}
+
+impl MpeiUpstream {
+    pub async fn search(
+        &self,
+        query: &str,
+        r#type: &ScheduleType,
+    ) -> Result<Vec<MpeiSearchResult>, CommonError> {
+        match self {
+            Self::Remote(api) => api.search(query, r#type).await,
+            Self::Mock(mock) => Ok(mock.search(query, r#type)),
+        }
+    }
+
+    pub async fn schedule(
+        &self,
+        r#type: &ScheduleType,
+        id: ScheduleId,
+        start: &str,
+        finish: &str,
+        lng: u8,
+    ) -> Result<Vec<MpeiClasses>, CommonError> {
+        match self {
+            Self::Remote(api) => api.schedule(r#type, id, start, finish, lng).await,
+            Self::Mock(mock) => Ok(mock.schedule(start)),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use chrono::{Datelike, Duration};
+use domain_schedule_models::{Day, DayOverride, DaySource, Schedule};
+
+/// Replace classes of the overridden days of the schedule.
+/// Overridden days missing in the schedule (e.g. MPEI has no classes there yet) are added.
+pub(crate) fn apply_overrides(schedule: &mut Schedule, overrides: Vec<DayOverride>) {
+    for day_override in overrides {
+        let Some(week) = schedule.weeks.iter_mut().find(|week| {
+            week.first_day_of_week <= day_override.date
+                && day_override.date < week.first_day_of_week + Duration::days(7)
+        }) else {
+            continue;
+        };
+        let day = Day {
+            day_of_week: day_override.date.weekday().number_from_monday() as u8,
+            date: day_override.date,
+            classes: day_override.classes,
+            source: DaySource::Override,
+            expires_at: Some(day_override.expires_at),
+            before_semester_start: false,
+        };
+        match week.days.iter_mut().find(|it| it.date == day.date) {
+            Some(existing) => *existing = day,
+            None => {
+                week.days.push(day);
+                week.days.sort_by_key(|day| day.date);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::{
+        Classes, ClassesTime, ClassesType, Day, DayOverride, DaySource, Schedule, ScheduleType,
+        Week,
+    };
+
+    use super::apply_overrides;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 3, day).unwrap()
+    }
+
+    fn classes(name: &str) -> Classes {
+        Classes {
+            name: name.to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: "Лекция".to_owned(),
+            place: "Б-114".to_owned(),
+            groups: "А-08-19".to_owned(),
+            person: "Иванов И.И.".to_owned(),
+            time: ClassesTime {
+                start: NaiveTime::from_hms_opt(9, 20, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(10, 55, 0).unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    fn schedule(days: Vec<Day>) -> Schedule {
+        Schedule {
+            id: "1".to_owned(),
+            name: "А-08-19".to_owned(),
+            r#type: ScheduleType::Group,
+            weeks: vec![Week {
+                week_of_year: 11,
+                week_of_semester: 6,
+                first_day_of_week: date(13),
+                days,
+                period_type: Default::default(),
+                last_synced_at: None,
+            }],
+        }
+    }
+
+    fn day(date: NaiveDate, name: &str) -> Day {
+        Day {
+            day_of_week: 1,
+            date,
+            classes: vec![classes(name)],
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
+        }
+    }
+
+    fn day_override(date: NaiveDate, name: &str) -> DayOverride {
+        DayOverride {
+            date,
+            classes: vec![classes(name)],
+            expires_at: date,
+        }
+    }
+
+    #[test]
+    fn test_existing_day_is_replaced() {
+        let mut schedule = schedule(vec![day(date(13), "Физика"), day(date(14), "Химия")]);
+        apply_overrides(&mut schedule, vec![day_override(date(14), "Математика")]);
+
+        let days = &schedule.weeks[0].days;
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].source, DaySource::Mpei);
+        assert_eq!(days[1].source, DaySource::Override);
+        assert_eq!(days[1].classes[0].name, "Математика");
+        assert_eq!(days[1].expires_at, Some(date(14)));
+    }
+
+    #[test]
+    fn test_missing_day_is_added_in_order() {
+        let mut schedule = schedule(vec![day(date(13), "Физика"), day(date(15), "Химия")]);
+        apply_overrides(&mut schedule, vec![day_override(date(14), "Математика")]);
+
+        let days = &schedule.weeks[0].days;
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[1].date, date(14));
+        assert_eq!(days[1].day_of_week, 2);
+        assert_eq!(days[1].source, DaySource::Override);
+    }
+
+    #[test]
+    fn test_days_out_of_weeks_are_ignored() {
+        let mut schedule = schedule(vec![day(date(13), "Физика")]);
+        apply_overrides(
+            &mut schedule,
+            vec![
+                day_override(date(12), "Химия"),
+                day_override(date(20), "Химия"),
+            ],
+        );
+
+        let days = &schedule.weeks[0].days;
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].source, DaySource::Mpei);
+    }
+}
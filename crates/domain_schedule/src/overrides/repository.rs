@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use common_database::Database;
+use domain_schedule_models::{DayOverride, ScheduleType};
+use log::{info, warn};
+use tokio_postgres::Row;
+
+use crate::dto::mpeix::ScheduleName;
+
+/// Repository for accessing table `schedule_override` of the mpeix database.
+///
+/// Table contains manual corrections of schedule days, which are merged
+/// over the schedules received from the MPEI backend until they expire.
+pub struct ScheduleOverrideRepository {
+    database: Arc<Database>,
+}
+
+impl ScheduleOverrideRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn init_schedule_override_db(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_schedule_override.pgsql");
+        client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error during table 'schedule_override' creation")?;
+        info!("Table 'schedule_override' initialization passed successfully");
+        Ok(())
+    }
+
+    /// Insert or replace overrides of the days with the same dates.
+    pub async fn save_overrides(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        overrides: &[DayOverride],
+    ) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/upsert_schedule_override.pgsql");
+        for day in overrides {
+            let classes = serde_json::to_string(&day.classes)
+                .with_context(|| "Error while serializing overridden classes")?;
+            client
+                .execute(
+                    stmt,
+                    &[
+                        &name.as_ref(),
+                        &r#type.to_string(),
+                        &day.date.to_string(),
+                        &classes,
+                        &day.expires_at.to_string(),
+                    ],
+                )
+                .await
+                .with_context(|| "Error while saving schedule override to db")?;
+        }
+        Ok(())
+    }
+
+    /// Get not expired overrides of the days from `from` to `to` (inclusive).
+    pub async fn get_active_overrides(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<DayOverride>> {
+        let client = self.database.read().get().await?;
+        let stmt = include_str!("../../sql/select_active_schedule_overrides.pgsql");
+        Ok(client
+            .query(
+                stmt,
+                &[
+                    &name.as_ref(),
+                    &r#type.to_string(),
+                    &from.to_string(),
+                    &to.to_string(),
+                ],
+            )
+            .await
+            .with_context(|| "Error while getting schedule overrides from db")?
+            .iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+}
+
+fn map_from_db_model(row: &Row) -> Option<DayOverride> {
+    let date = row.try_get::<_, String>("date").ok()?.parse().ok()?;
+    let classes = serde_json::from_str(row.try_get("classes").ok()?)
+        .map_err(|e| warn!("Cannot deserialize overridden classes of {date}: {e}"))
+        .ok()?;
+    Some(DayOverride {
+        date,
+        classes,
+        expires_at: row.try_get::<_, String>("expires_at").ok()?.parse().ok()?,
+    })
+}
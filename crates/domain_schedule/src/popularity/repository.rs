@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use common_database::Database;
+use common_sql::Page;
+use domain_schedule_models::{ScheduleType, TrendingSchedule};
+use log::info;
+use tokio_postgres::Row;
+
+use crate::dto::mpeix::ScheduleName;
+
+/// Daily counters older than this are deleted, trending window can not be longer
+pub(crate) const KEEP_DAILY_POPULARITY_DAYS: u32 = 30;
+
+/// Repository for accessing tables `schedule_popularity` and `schedule_popularity_daily`
+/// of the mpeix database.
+///
+/// Tables contain the number of requests of each schedule in total and by days,
+/// which is used to find out the most active and the trending schedules.
+pub struct SchedulePopularityRepository {
+    database: Arc<Database>,
+}
+
+impl SchedulePopularityRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn init_schedule_popularity_db(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_schedule_popularity.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'schedule_popularity' creation")?;
+        let stmt = format!(
+            include_str!("../../sql/delete_old_schedule_popularity_daily.pgsql"),
+            keep_days = KEEP_DAILY_POPULARITY_DAYS,
+        );
+        let deleted = client
+            .execute(&stmt, &[])
+            .await
+            .with_context(|| "Error while deleting old daily schedule popularity")?;
+        info!("Table 'schedule_popularity' initialization passed successfully, {deleted} old daily counters deleted");
+        Ok(())
+    }
+
+    pub async fn record_request(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<()> {
+        let stmt = include_str!("../../sql/update_schedule_popularity.pgsql")
+            .replace("$name", name.as_ref())
+            .replace("$type", r#type.as_ref());
+        let client = self.database.write().get().await?;
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while updating schedule popularity in db")?;
+        client
+            .execute(
+                include_str!("../../sql/update_schedule_popularity_daily.pgsql"),
+                &[&name.as_ref(), &r#type.to_string()],
+            )
+            .await
+            .with_context(|| "Error while updating daily schedule popularity in db")?;
+        Ok(())
+    }
+
+    /// Get the most requested schedules for the last two weeks.
+    pub async fn get_most_popular(
+        &self,
+        page: Page,
+    ) -> anyhow::Result<Vec<(ScheduleName, ScheduleType)>> {
+        let stmt = include_str!("../../sql/select_most_popular_schedules.pgsql")
+            .replace("$page", &page.to_string());
+        let client = self.database.read().get().await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while getting most popular schedules from db")?
+            .iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+
+    /// Get the most requested schedules for the last `days`, including today,
+    /// with the number of requests during this window.
+    pub async fn get_trending(
+        &self,
+        days: u32,
+        r#type: Option<ScheduleType>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<TrendingSchedule>> {
+        let stmt = include_str!("../../sql/select_trending_schedules.pgsql");
+        let client = self.database.read().get().await?;
+        Ok(client
+            .query(
+                stmt,
+                &[
+                    &(days as i32),
+                    &r#type.map(|it| it.to_string()),
+                    &(limit as i64),
+                ],
+            )
+            .await
+            .with_context(|| "Error while getting trending schedules from db")?
+            .iter()
+            .filter_map(map_trending_from_db_model)
+            .collect())
+    }
+}
+
+fn map_from_db_model(row: &Row) -> Option<(ScheduleName, ScheduleType)> {
+    let r#type = row
+        .try_get::<_, String>("type")
+        .ok()?
+        .parse::<ScheduleType>()
+        .ok()?;
+    let name = ScheduleName::new(row.try_get("name").ok()?, r#type.clone()).ok()?;
+    Some((name, r#type))
+}
+
+fn map_trending_from_db_model(row: &Row) -> Option<TrendingSchedule> {
+    Some(TrendingSchedule {
+        name: row.try_get("name").ok()?,
+        r#type: row
+            .try_get::<_, String>("type")
+            .ok()?
+            .parse::<ScheduleType>()
+            .ok()?,
+        requests: row.try_get("requests").ok()?,
+    })
+}
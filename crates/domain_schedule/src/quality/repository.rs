@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use anyhow::Context;
+use chrono::NaiveDate;
+use common_database::Database;
+use common_sql::{Page, Paged, Sort};
+use domain_schedule_models::{ScheduleQuality, ScheduleType};
+use log::info;
+use tokio_postgres::Row;
+
+use crate::{dto::mpeix::ScheduleName, quality::validation::ScheduleWarnings};
+
+/// Columns of the quality report which can be used for sorting
+pub const QUALITY_SORT_COLUMNS: &[&str] = &[
+    "name",
+    "overlapping_classes",
+    "missing_rooms",
+    "unknown_class_types",
+    "total_warnings",
+];
+
+/// Repository for accessing table `schedule_quality` of the mpeix database.
+///
+/// Table contains the latest validation warnings of each schedule week
+/// received from the MPEI backend, which helps to prioritize upstream data fixes.
+pub struct ScheduleQualityRepository {
+    database: Arc<Database>,
+}
+
+impl ScheduleQualityRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+
+    pub async fn init_schedule_quality_db(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_schedule_quality.pgsql");
+        client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error during table 'schedule_quality' creation")?;
+        info!("Table 'schedule_quality' initialization passed successfully");
+        Ok(())
+    }
+
+    pub async fn save_warnings(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        warnings: &ScheduleWarnings,
+    ) -> anyhow::Result<()> {
+        let stmt = include_str!("../../sql/upsert_schedule_quality.pgsql")
+            .replace("$name", name.as_ref())
+            .replace("$type", r#type.as_ref())
+            .replace("$week_start", &week_start.to_string())
+            .replace(
+                "$overlapping_classes",
+                &warnings.overlapping_classes.to_string(),
+            )
+            .replace("$missing_rooms", &warnings.missing_rooms.to_string())
+            .replace(
+                "$unknown_class_types",
+                &warnings.unknown_class_types.to_string(),
+            );
+        let client = self.database.write().get().await?;
+        client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while saving schedule quality to db")?;
+        Ok(())
+    }
+
+    /// Get schedules with warnings checked during the last `days` days.
+    pub async fn get_quality_report(
+        &self,
+        days: u32,
+        page: Page,
+        sort: Sort,
+    ) -> anyhow::Result<Paged<ScheduleQuality>> {
+        let stmt = include_str!("../../sql/select_schedule_quality.pgsql")
+            .replace("$days", &days.to_string())
+            .replace("$sort", &sort.to_string())
+            .replace("$page", &page.to_string());
+        let client = self.database.read().get().await?;
+        let rows = client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while getting schedule quality from db")?;
+        // every row contains the total number of rows before LIMIT/OFFSET
+        let total = rows
+            .first()
+            .and_then(|row| row.try_get::<_, i64>("total").ok())
+            .unwrap_or_default();
+        let items = rows.iter().filter_map(map_from_db_model).collect();
+        Ok(Paged::new(items, total as usize, &page))
+    }
+}
+
+fn map_from_db_model(row: &Row) -> Option<ScheduleQuality> {
+    Some(ScheduleQuality {
+        name: row.try_get("name").ok()?,
+        r#type: row.try_get::<_, String>("type").ok()?.parse().ok()?,
+        overlapping_classes: row.try_get("overlapping_classes").ok()?,
+        missing_rooms: row.try_get("missing_rooms").ok()?,
+        unknown_class_types: row.try_get("unknown_class_types").ok()?,
+    })
+}
@@ -0,0 +1,133 @@
+use domain_schedule_models::{Classes, ClassesType, Schedule};
+
+/// Counts of suspicious places in the schedule received from the MPEI backend.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScheduleWarnings {
+    /// Classes of the same day which intersect in time.
+    /// Parallel classes of different subgroups in the same time slot are not counted.
+    pub overlapping_classes: i32,
+    /// Classes without room
+    pub missing_rooms: i32,
+    /// Classes whose type is not recognized by [ClassesType] mapping
+    pub unknown_class_types: i32,
+}
+
+impl ScheduleWarnings {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Validate schedule and count warnings of each kind
+pub fn validate_schedule(schedule: &Schedule) -> ScheduleWarnings {
+    let mut warnings = ScheduleWarnings::default();
+    for day in schedule.weeks.iter().flat_map(|week| week.days.iter()) {
+        warnings.overlapping_classes += count_overlapping_classes(&day.classes);
+        for classes in &day.classes {
+            if is_room_missing(&classes.place) {
+                warnings.missing_rooms += 1;
+            }
+            if let ClassesType::Undefined = classes.r#type {
+                warnings.unknown_class_types += 1;
+            }
+        }
+    }
+    warnings
+}
+
+fn count_overlapping_classes(classes: &[Classes]) -> i32 {
+    let mut count = 0;
+    for (i, a) in classes.iter().enumerate() {
+        for b in classes.iter().skip(i + 1) {
+            let same_slot = a.time.start == b.time.start && a.time.end == b.time.end;
+            let intersects = a.time.start < b.time.end && b.time.start < a.time.end;
+            if intersects && !same_slot {
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn is_room_missing(place: &str) -> bool {
+    place
+        .trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '—')
+        .is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::{ClassesTime, Day, ScheduleType, Week};
+
+    use super::*;
+
+    fn classes(start: &str, end: &str, place: &str, r#type: ClassesType) -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type,
+            raw_type: String::new(),
+            place: place.to_owned(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: NaiveTime::parse_from_str(start, "%H:%M").unwrap(),
+                end: NaiveTime::parse_from_str(end, "%H:%M").unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    fn schedule(classes: Vec<Classes>) -> Schedule {
+        let date = NaiveDate::from_ymd_opt(2023, 2, 6).unwrap();
+        Schedule {
+            id: "1".to_owned(),
+            name: "А-08-19".to_owned(),
+            r#type: ScheduleType::Group,
+            weeks: vec![Week {
+                week_of_year: 6,
+                week_of_semester: 1,
+                first_day_of_week: date,
+                period_type: Default::default(),
+                last_synced_at: None,
+                days: vec![Day {
+                    day_of_week: 1,
+                    date,
+                    classes,
+                    source: Default::default(),
+                    expires_at: None,
+                    before_semester_start: false,
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_valid_schedule() {
+        let warnings = validate_schedule(&schedule(vec![
+            classes("09:20", "10:55", "Б-303", ClassesType::Lecture),
+            classes("11:10", "12:45", "Б-303", ClassesType::Practice),
+            // parallel classes of different subgroups
+            classes("11:10", "12:45", "Б-304", ClassesType::Practice),
+        ]));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn test_schedule_with_warnings() {
+        let warnings = validate_schedule(&schedule(vec![
+            classes("09:20", "10:55", "", ClassesType::Lecture),
+            classes("10:00", "11:30", "-", ClassesType::Undefined),
+            classes("11:10", "12:45", "Б-303", ClassesType::Undefined),
+        ]));
+        assert_eq!(
+            warnings,
+            ScheduleWarnings {
+                overlapping_classes: 2,
+                missing_rooms: 2,
+                unknown_class_types: 2,
+            }
+        );
+    }
+}
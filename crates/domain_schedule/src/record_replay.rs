@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common_rust::env;
+use log::{info, warn};
+use reqwest::{header, Request, Response};
+use restix::{BoxFuture, Interceptor, Next};
+
+/// Mode of the [RecordReplayInterceptor]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordReplayMode {
+    /// Send requests to the MPEI backend and save raw responses to the fixtures
+    Record,
+    /// Answer requests with the saved fixtures without any network calls
+    Replay,
+}
+
+/// Record/replay layer of [crate::mpei_api::MpeiApi] for deterministic integration tests.
+///
+/// Fixtures are raw response bodies, one file per request, keyed by the request URL,
+/// e.g. `GET_ts.mpei.ru_api_search_term_%D0%90-08-19_type_group.json`.
+pub struct RecordReplayInterceptor {
+    mode: RecordReplayMode,
+    fixtures_dir: PathBuf,
+}
+
+impl RecordReplayInterceptor {
+    pub fn new(mode: RecordReplayMode, fixtures_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            mode,
+            fixtures_dir: fixtures_dir.into(),
+        }
+    }
+
+    /// Read config from `MPEI_API_RECORD_REPLAY` (`record` or `replay`)
+    /// and `MPEI_API_FIXTURES_DIR` environment variables.
+    /// Returns `None` if the mode is not specified.
+    pub fn from_env() -> Option<Self> {
+        let mode = match env::get("MPEI_API_RECORD_REPLAY")?.as_str() {
+            "record" => RecordReplayMode::Record,
+            "replay" => RecordReplayMode::Replay,
+            other => panic!("Unknown MPEI_API_RECORD_REPLAY mode '{other}'"),
+        };
+        let fixtures_dir = env::get_or("MPEI_API_FIXTURES_DIR", "res/fixtures");
+        info!("MPEI api {mode:?} mode is enabled, fixtures dir: {fixtures_dir}");
+        Some(Self::new(mode, fixtures_dir))
+    }
+
+    fn fixture_path(&self, request: &Request) -> PathBuf {
+        self.fixtures_dir.join(fixture_name(request))
+    }
+}
+
+impl Interceptor for RecordReplayInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<Response>> {
+        let path = self.fixture_path(&request);
+        Box::pin(async move {
+            match self.mode {
+                RecordReplayMode::Replay => Ok(replay(&path)),
+                RecordReplayMode::Record => {
+                    let response = next.run(request).await?;
+                    let status = response.status();
+                    let body = response.bytes().await?;
+                    if status.is_success() {
+                        record(&path, &body);
+                    } else {
+                        warn!("Response with status {status} is not recorded: {path:?}");
+                    }
+                    let response = http::Response::builder()
+                        .status(status)
+                        .body(body)
+                        .expect("Valid recorded response");
+                    Ok(Response::from(response))
+                }
+            }
+        })
+    }
+}
+
+/// Fixture file name of the request: method and URL without scheme,
+/// all characters except letters, digits, `-`, `.` and `%` are replaced with `_`
+fn fixture_name(request: &Request) -> String {
+    let url = request.url();
+    let key = format!(
+        "{} {}{}?{}",
+        request.method(),
+        url.host_str().unwrap_or_default(),
+        url.path(),
+        url.query().unwrap_or_default(),
+    );
+    let key = key
+        .trim_end_matches('?')
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' | '%' => c,
+            _ => '_',
+        })
+        .collect::<String>();
+    format!("{key}.json")
+}
+
+fn record(path: &Path, body: &[u8]) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).expect("Cannot create fixtures dir");
+    }
+    fs::write(path, body).unwrap_or_else(|e| panic!("Cannot write fixture {path:?}: {e}"));
+    info!("Recorded fixture {path:?}");
+}
+
+/// Replay mode is meant for tests, so missing fixtures are reported loudly
+fn replay(path: &Path) -> Response {
+    let body = fs::read(path).unwrap_or_else(|e| {
+        panic!("Cannot read fixture {path:?}: {e}. Record it with MPEI_API_RECORD_REPLAY=record")
+    });
+    let response = http::Response::builder()
+        .status(200)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .expect("Valid replayed response");
+    Response::from(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture_name;
+
+    #[test]
+    fn test_fixture_name() {
+        let request = reqwest::Client::new()
+            .get("http://ts.mpei.ru/api/schedule/group/1001")
+            .query(&[("start", "2023.02.06"), ("lng", "1")])
+            .build()
+            .unwrap();
+        assert_eq!(
+            fixture_name(&request),
+            "GET_ts.mpei.ru_api_schedule_group_1001_start_2023.02.06_lng_1.json"
+        );
+
+        let request = reqwest::Client::new()
+            .get("http://ts.mpei.ru/api/search")
+            .build()
+            .unwrap();
+        assert_eq!(fixture_name(&request), "GET_ts.mpei.ru_api_search.json");
+    }
+}
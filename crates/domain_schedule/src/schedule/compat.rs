@@ -1,13 +1,14 @@
 use chrono::{DateTime, Local};
 use common_in_memory_cache::Entry;
-use domain_schedule_models::Schedule;
+use domain_schedule_models::{Lenient, Schedule};
 use serde::{Deserialize, Serialize};
 
 /// Low-cost wrapper for Entry<Schedule> for safe reading from old cache entries,
-/// generated by Kotlin backend.
+/// generated by Kotlin backend or by the older versions of this one.
 #[derive(Debug, Deserialize)]
 pub struct ReadingPersistentEntry {
-    value: Schedule,
+    /// Unknown enum values of the old entries must not make the whole entry unreadable
+    value: Lenient<Schedule>,
     #[serde(alias = "created", with = "datetime_serde")]
     created_at: Option<DateTime<Local>>,
     #[serde(alias = "accessed", with = "datetime_serde")]
@@ -29,8 +30,17 @@ pub struct WritingPersistentEntry<'a> {
 
 impl From<ReadingPersistentEntry> for Entry<Schedule> {
     fn from(value: ReadingPersistentEntry) -> Self {
+        let mut schedule = value.value.0;
+        // older entries do not track the sync time, but they were synced when they were created
+        if let Some(created_at) = value.created_at {
+            schedule
+                .weeks
+                .iter_mut()
+                .filter(|week| week.last_synced_at.is_none())
+                .for_each(|week| week.last_synced_at = Some(created_at.naive_local()));
+        }
         Self {
-            value: value.value,
+            value: schedule,
             created_at: value.created_at.unwrap_or_else(Local::now),
             accessed_at: value.accessed_at.unwrap_or_else(Local::now),
             hits: value.hits,
@@ -72,3 +82,39 @@ mod datetime_serde {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use common_in_memory_cache::Entry;
+    use domain_schedule_models::Schedule;
+
+    use super::ReadingPersistentEntry;
+
+    #[test]
+    fn test_old_entries_are_synced_at_creation() {
+        let json = r#"{
+            "value": {
+                "id": "1",
+                "name": "А-08-19",
+                "type": "GROUP",
+                "weeks": [{
+                    "weekOfYear": 11,
+                    "weekOfSemester": 6,
+                    "firstDayOfWeek": "2023-03-13",
+                    "days": []
+                }]
+            },
+            "created_at": "2023-03-13T14:02:00.000+0300",
+            "accessed_at": null,
+            "hits": 3
+        }"#;
+        let entry: Entry<Schedule> = serde_json::from_str::<ReadingPersistentEntry>(json)
+            .unwrap()
+            .into();
+        assert_eq!(
+            Some(NaiveDateTime::parse_from_str("2023-03-13 14:02", "%Y-%m-%d %H:%M").unwrap()),
+            entry.value.weeks[0].last_synced_at
+        );
+    }
+}
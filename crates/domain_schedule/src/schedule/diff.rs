@@ -0,0 +1,215 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveTime};
+use domain_schedule_models::{Classes, ClassesChange, ClassesChangeKind, Schedule};
+
+/// Find changes of the classes of `after` schedule relative to `before` schedule.
+///
+/// Days are matched by date, classes are matched by the time slot (start time).
+/// Identical classes are skipped, the rest ones of the same slot are paired as changed,
+/// and extra classes of the slot are reported as added or removed.
+pub(crate) fn diff_schedules(before: &Schedule, after: &Schedule) -> Vec<ClassesChange> {
+    let mut slots = BTreeMap::<(NaiveDate, NaiveTime), (Vec<&Classes>, Vec<&Classes>)>::new();
+    for (schedule, is_before) in [(before, true), (after, false)] {
+        for day in schedule.weeks.iter().flat_map(|week| week.days.iter()) {
+            for cls in &day.classes {
+                let slot = slots.entry((day.date, cls.time.start)).or_default();
+                if is_before {
+                    slot.0.push(cls);
+                } else {
+                    slot.1.push(cls);
+                }
+            }
+        }
+    }
+
+    let mut changes = Vec::new();
+    for ((date, _), (mut before, mut after)) in slots {
+        before.retain(|cls| match after.iter().position(|it| it == cls) {
+            Some(index) => {
+                after.remove(index);
+                false
+            }
+            None => true,
+        });
+        let mut before = before.into_iter();
+        let mut after = after.into_iter();
+        loop {
+            let (before, after) = (before.next(), after.next());
+            let kind = match (before, after) {
+                (Some(_), Some(_)) => ClassesChangeKind::Changed,
+                (Some(_), None) => ClassesChangeKind::Removed,
+                (None, Some(_)) => ClassesChangeKind::Added,
+                (None, None) => break,
+            };
+            changes.push(ClassesChange {
+                date,
+                kind,
+                before: before.cloned(),
+                after: after.cloned(),
+            });
+        }
+    }
+    changes
+}
+
+/// Find changes of the classes of `to` week relative to `from` week of the same schedule,
+/// e.g. what changes next week compared to the current one.
+///
+/// Days of `from` are moved to the `to` week, so the days are matched by the day of week,
+/// and the dates of the changes are the dates of the `to` week.
+pub(crate) fn diff_weeks(from: &Schedule, to: &Schedule) -> Vec<ClassesChange> {
+    let (Some(from_week), Some(to_week)) = (from.weeks.first(), to.weeks.first()) else {
+        return diff_schedules(from, to);
+    };
+    let shift = to_week.first_day_of_week - from_week.first_day_of_week;
+    let mut shifted = from.clone();
+    for week in shifted.weeks.iter_mut() {
+        week.first_day_of_week += shift;
+        week.days.iter_mut().for_each(|day| day.date += shift);
+    }
+    diff_schedules(&shifted, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::{
+        Classes, ClassesChangeKind, ClassesTime, ClassesType, Day, Schedule, ScheduleType, Week,
+    };
+
+    use super::{diff_schedules, diff_weeks};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 3, day).unwrap()
+    }
+
+    fn classes(name: &str, place: &str, start_hour: u32) -> Classes {
+        Classes {
+            name: name.to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: "Лекция".to_owned(),
+            place: place.to_owned(),
+            groups: "А-08-19".to_owned(),
+            person: "Иванов И.И.".to_owned(),
+            time: ClassesTime {
+                start: NaiveTime::from_hms_opt(start_hour, 20, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(start_hour + 1, 55, 0).unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    fn schedule(days: Vec<(u32, Vec<Classes>)>) -> Schedule {
+        week_schedule(13, days)
+    }
+
+    fn week_schedule(first_day: u32, days: Vec<(u32, Vec<Classes>)>) -> Schedule {
+        Schedule {
+            id: "1".to_owned(),
+            name: "А-08-19".to_owned(),
+            r#type: ScheduleType::Group,
+            weeks: vec![Week {
+                week_of_year: 11,
+                week_of_semester: 6,
+                first_day_of_week: date(first_day),
+                days: days
+                    .into_iter()
+                    .map(|(day, classes)| Day {
+                        day_of_week: (day - first_day + 1) as u8,
+                        date: date(day),
+                        classes,
+                        source: Default::default(),
+                        expires_at: None,
+                        before_semester_start: false,
+                    })
+                    .collect(),
+                period_type: Default::default(),
+                last_synced_at: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn same_schedules_have_no_changes() {
+        let schedule = schedule(vec![(13, vec![classes("Физика", "Б-114", 9)])]);
+        assert!(diff_schedules(&schedule, &schedule).is_empty());
+    }
+
+    #[test]
+    fn classes_at_the_same_slot_are_changed() {
+        let before = schedule(vec![(13, vec![classes("Физика", "Б-114", 9)])]);
+        let after = schedule(vec![(13, vec![classes("Физика", "К-601", 9)])]);
+        let changes = diff_schedules(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].date, date(13));
+        assert_eq!(changes[0].kind, ClassesChangeKind::Changed);
+        assert_eq!(changes[0].before.as_ref().unwrap().place, "Б-114");
+        assert_eq!(changes[0].after.as_ref().unwrap().place, "К-601");
+    }
+
+    #[test]
+    fn classes_at_other_slots_are_added_and_removed() {
+        let before = schedule(vec![
+            (13, vec![classes("Физика", "Б-114", 9)]),
+            (14, vec![classes("Химия", "Б-114", 9)]),
+        ]);
+        let after = schedule(vec![(13, vec![classes("Физика", "Б-114", 11)])]);
+        let kinds = diff_schedules(&before, &after)
+            .into_iter()
+            .map(|it| (it.date, it.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (date(13), ClassesChangeKind::Removed),
+                (date(13), ClassesChangeKind::Added),
+                (date(14), ClassesChangeKind::Removed),
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_classes_are_matched_exactly() {
+        let before = schedule(vec![(
+            13,
+            vec![classes("Физика", "Б-114", 9), classes("Химия", "Б-205", 9)],
+        )]);
+        let after = schedule(vec![(13, vec![classes("Химия", "Б-205", 9)])]);
+        let changes = diff_schedules(&before, &after);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ClassesChangeKind::Removed);
+        assert_eq!(changes[0].before.as_ref().unwrap().name, "Физика");
+    }
+
+    #[test]
+    fn weeks_are_matched_by_day_of_week() {
+        let this_week = week_schedule(
+            13,
+            vec![
+                (13, vec![classes("Физика", "Б-114", 9)]),
+                (14, vec![classes("Химия", "Б-114", 9)]),
+            ],
+        );
+        let next_week = week_schedule(
+            20,
+            vec![
+                (20, vec![classes("Физика", "Б-114", 9)]),
+                (21, vec![classes("Химия", "К-601", 9)]),
+                (22, vec![classes("Физкультура", "", 11)]),
+            ],
+        );
+        let changes = diff_weeks(&this_week, &next_week)
+            .into_iter()
+            .map(|it| (it.date, it.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            vec![
+                (date(21), ClassesChangeKind::Changed),
+                (date(22), ClassesChangeKind::Added),
+            ]
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+use chrono::{Local, NaiveDateTime};
+use domain_schedule_models::{LatencySummary, ScheduleFetchLatency};
+
+/// Where the schedule of the response came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FetchSource {
+    Cache,
+    /// Expired cache entry, because remote is unavailable, too slow or returned nothing
+    StaleCache,
+    Remote,
+    Error,
+}
+
+impl FetchSource {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Cache => "cache",
+            Self::StaleCache => "staleCache",
+            Self::Remote => "remote",
+            Self::Error => "error",
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Accumulator {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// In-memory latency statistics of the schedule requests, reset on restart
+pub(crate) struct FetchLatencyStats {
+    since: NaiveDateTime,
+    sources: Mutex<BTreeMap<&'static str, Accumulator>>,
+}
+
+impl Default for FetchLatencyStats {
+    fn default() -> Self {
+        Self {
+            since: Local::now().naive_local(),
+            sources: Default::default(),
+        }
+    }
+}
+
+impl FetchLatencyStats {
+    pub(crate) fn record(&self, source: FetchSource, elapsed: Duration) {
+        let mut sources = self.sources.lock().unwrap();
+        let accumulator = sources.entry(source.name()).or_default();
+        accumulator.count += 1;
+        accumulator.total += elapsed;
+        accumulator.max = accumulator.max.max(elapsed);
+    }
+
+    pub(crate) fn report(&self, strategy: &str) -> ScheduleFetchLatency {
+        let sources = self
+            .sources
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(source, it)| {
+                let summary = LatencySummary {
+                    count: it.count,
+                    avg_ms: (it.total.as_millis() / it.count.max(1) as u128) as u64,
+                    max_ms: it.max.as_millis() as u64,
+                };
+                (source.to_string(), summary)
+            })
+            .collect();
+        ScheduleFetchLatency {
+            strategy: strategy.to_owned(),
+            since: self.since,
+            sources,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{FetchLatencyStats, FetchSource};
+
+    #[test]
+    fn test_latency_is_summarized_by_source() {
+        let stats = FetchLatencyStats::default();
+        stats.record(FetchSource::Cache, Duration::from_millis(2));
+        stats.record(FetchSource::Cache, Duration::from_millis(4));
+        stats.record(FetchSource::Remote, Duration::from_millis(900));
+
+        let report = stats.report("race");
+        assert_eq!(report.strategy, "race");
+        assert_eq!(report.sources.len(), 2);
+        let cache = &report.sources["cache"];
+        assert_eq!((cache.count, cache.avg_ms, cache.max_ms), (2, 3, 4));
+        let remote = &report.sources["remote"];
+        assert_eq!((remote.count, remote.avg_ms, remote.max_ms), (1, 900, 900));
+    }
+}
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
-use chrono::{Datelike, NaiveDate, Timelike};
+use chrono::{Datelike, Local, NaiveDate, Timelike};
 use domain_schedule_models::{
-    Classes, ClassesTime, ClassesType, Day, Schedule, ScheduleType, Week,
+    Classes, ClassesStatus, ClassesTime, ClassesType, Day, Schedule, ScheduleId, ScheduleType, Week,
 };
 
 use crate::{
@@ -13,7 +13,7 @@ use crate::{
 pub(crate) fn map_schedule_models(
     name: ScheduleName,
     week_start: NaiveDate,
-    schedule_id: i64,
+    schedule_id: ScheduleId,
     r#type: ScheduleType,
     mpei_classes: Vec<MpeiClasses>,
     week_of_semester: WeekOfSemester,
@@ -38,6 +38,7 @@ pub(crate) fn map_schedule_models(
             person: check_is_not_empty(&cls.lecturer),
             number: get_number(&time),
             time,
+            status: get_classes_status(&cls.discipline, &cls.kind_of_work),
         };
         if !map_of_days.contains_key(&cls.date) {
             map_of_days.insert(cls.date.to_owned(), vec![]);
@@ -52,9 +53,12 @@ pub(crate) fn map_schedule_models(
             day_of_week: day_of_week.weekday().number_from_monday() as u8,
             date: day_of_week,
             classes,
+            source: Default::default(),
+            expires_at: None,
+            before_semester_start: false,
         });
     }
-    days.sort_by(|a, b| a.date.cmp(&b.date));
+    days.sort_by_key(|day| day.date);
     Schedule {
         id: schedule_id.to_string(),
         name: name.as_string(),
@@ -67,10 +71,35 @@ pub(crate) fn map_schedule_models(
             week_of_year: week_start.week_of_year(),
             first_day_of_week: week_start.to_owned(),
             days,
+            period_type: Default::default(),
+            last_synced_at: Some(Local::now().naive_local()),
         }],
     }
 }
 
+/// Add the empty days of the week before the first study day of the semester,
+/// so they are not confused with the days without classes. Existing days are only marked.
+pub(crate) fn mark_days_before_semester_start(week: &mut Week, first_day: NaiveDate) {
+    for date in week
+        .first_day_of_week
+        .iter_days()
+        .take_while(|it| *it < first_day)
+    {
+        match week.days.iter_mut().find(|day| day.date == date) {
+            Some(day) => day.before_semester_start = true,
+            None => week.days.push(Day {
+                day_of_week: date.weekday().number_from_monday() as u8,
+                date,
+                classes: Vec::with_capacity(0),
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: true,
+            }),
+        }
+    }
+    week.days.sort_by_key(|day| day.date);
+}
+
 fn get_classes_type(raw_type: &str) -> ClassesType {
     let raw_type = raw_type.to_lowercase();
     if raw_type.contains("лек") {
@@ -90,6 +119,19 @@ fn get_classes_type(raw_type: &str) -> ClassesType {
     }
 }
 
+/// MPEI does not have a separate field for the classes status,
+/// but sometimes annotates cancelled or moved classes in their name or type.
+fn get_classes_status(name: &str, raw_type: &str) -> ClassesStatus {
+    let text = format!("{name} {raw_type}").to_lowercase();
+    if text.contains("отмен") {
+        ClassesStatus::Cancelled
+    } else if text.contains("перенос") || text.contains("перенес") {
+        ClassesStatus::Moved
+    } else {
+        ClassesStatus::Normal
+    }
+}
+
 fn check_is_not_empty(lecturer: &str) -> String {
     if lecturer.to_lowercase().contains("вакансия") {
         return String::new();
@@ -109,3 +151,105 @@ fn get_number(time: &ClassesTime) -> i8 {
         _ => -1,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_schedule_models::{ClassesStatus, ScheduleId, ScheduleType};
+
+    use crate::{
+        dto::{mpei::MpeiClasses, mpeix::ScheduleName},
+        time::WeekOfSemester,
+    };
+
+    use super::{get_classes_status, map_schedule_models, mark_days_before_semester_start};
+
+    fn mpei_classes(date: NaiveDate, begin_hour: u32) -> MpeiClasses {
+        MpeiClasses {
+            auditorium: "Б-114".to_owned(),
+            begin_lesson: NaiveTime::from_hms_opt(begin_hour, 20, 0).unwrap(),
+            end_lesson: NaiveTime::from_hms_opt(begin_hour + 1, 55, 0).unwrap(),
+            date,
+            discipline: "Физика".to_owned(),
+            kind_of_work: "Лекция".to_owned(),
+            lecturer: "Иванов И.И.".to_owned(),
+            stream: None,
+            group: Some("ИЭвд-01-22".to_owned()),
+            sub_group: None,
+        }
+    }
+
+    #[test]
+    fn test_sunday_classes_are_mapped() {
+        // 2023-03-13 is Monday, 2023-03-19 is Sunday
+        let monday = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        let sunday = NaiveDate::from_ymd_opt(2023, 3, 19).unwrap();
+        let schedule = map_schedule_models(
+            ScheduleName::new("ИЭвд-01-22".to_owned(), ScheduleType::Group).unwrap(),
+            monday,
+            ScheduleId(1),
+            ScheduleType::Group,
+            vec![mpei_classes(sunday, 9), mpei_classes(monday, 17)],
+            WeekOfSemester::Studying(6),
+        );
+        let days = &schedule.weeks[0].days;
+        assert_eq!(days.len(), 2);
+        assert_eq!((days[0].date, days[0].day_of_week), (monday, 1));
+        assert_eq!((days[1].date, days[1].day_of_week), (sunday, 7));
+        assert_eq!(days[1].classes[0].number, 1);
+    }
+
+    #[test]
+    fn test_days_before_semester_start() {
+        // the semester starts on Wednesday, 2023-09-06
+        let monday = NaiveDate::from_ymd_opt(2023, 9, 4).unwrap();
+        let wednesday = NaiveDate::from_ymd_opt(2023, 9, 6).unwrap();
+        let mut week = map_schedule_models(
+            ScheduleName::new("ИЭвд-01-22".to_owned(), ScheduleType::Group).unwrap(),
+            monday,
+            ScheduleId(1),
+            ScheduleType::Group,
+            vec![mpei_classes(wednesday, 9)],
+            WeekOfSemester::Studying(1),
+        )
+        .weeks
+        .remove(0);
+        mark_days_before_semester_start(&mut week, wednesday);
+        let days = week
+            .days
+            .iter()
+            .map(|day| {
+                (
+                    day.day_of_week,
+                    day.classes.len(),
+                    day.before_semester_start,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(1, 0, true), (2, 0, true), (3, 1, false)], days);
+    }
+
+    #[test]
+    fn test_classes_status() {
+        assert_eq!(
+            ClassesStatus::Normal,
+            get_classes_status("Физика", "Лекция")
+        );
+        assert_eq!(
+            ClassesStatus::Cancelled,
+            get_classes_status("Физика (ОТМЕНА)", "Лекция")
+        );
+        assert_eq!(
+            ClassesStatus::Cancelled,
+            get_classes_status("Физика", "Лекция отменена")
+        );
+        assert_eq!(
+            ClassesStatus::Moved,
+            get_classes_status("Физика (перенос на 15.03)", "Лекция")
+        );
+        assert_eq!(
+            ClassesStatus::Moved,
+            get_classes_status("Физика", "Перенесено")
+        );
+    }
+}
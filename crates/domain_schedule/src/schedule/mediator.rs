@@ -1,11 +1,11 @@
-use std::hash::Hash;
+use std::{fmt::Display, hash::Hash};
 
 use anyhow::{anyhow, Ok};
-use chrono::{Datelike, NaiveDate};
+use chrono::{Datelike, Local, NaiveDate};
 use common_errors::errors::CommonError;
 use common_in_memory_cache::{Entry, InMemoryCache};
 use common_persistent_cache::PersistentCache;
-use domain_schedule_models::Schedule;
+use domain_schedule_models::{CacheMetadata, Schedule};
 
 use super::compat::{writing, ReadingPersistentEntry, WritingPersistentEntry};
 
@@ -40,6 +40,32 @@ impl CacheMediator {
         Ok(None)
     }
 
+    /// Get value with its cache metadata regardless of expiration policy.
+    /// Does not count as the cache hit.
+    pub async fn inspect(
+        &mut self,
+        key: &InMemoryCacheKey,
+    ) -> anyhow::Result<Option<(Schedule, CacheMetadata)>> {
+        if !self.in_memory_cache.contains(key) {
+            self.restore_from_persistent(key).await?;
+        }
+        let now = Local::now();
+        Ok(self.in_memory_cache.inspect(key).map(|(entry, expired)| {
+            let ttl_seconds = match self.in_memory_cache.expires_at(entry) {
+                Some(expires_at) if !expired => (expires_at - now).num_seconds().max(0),
+                _ => 0,
+            };
+            let metadata = CacheMetadata {
+                created_at: entry.created_at.naive_local(),
+                age_seconds: (now - entry.created_at).num_seconds(),
+                ttl_seconds,
+                expired,
+                hits: entry.hits,
+            };
+            (entry.value.to_owned(), metadata)
+        }))
+    }
+
     async fn restore_from_persistent(&mut self, key: &InMemoryCacheKey) -> anyhow::Result<()> {
         if let Some(entry) = self
             .persistent_cache
@@ -83,13 +109,14 @@ impl CacheMediator {
     }
 }
 
-impl ToString for InMemoryCacheKey {
-    fn to_string(&self) -> String {
+impl Display for InMemoryCacheKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let year = &self.week_start.year();
         let r#type = &self.r#type.to_lowercase();
         let name = &self.name.to_uppercase();
 
-        format!(
+        write!(
+            f,
             "{}/{} {} [{}].cache",
             year,
             r#type,
@@ -1,4 +1,6 @@
 pub(crate) mod compat;
+pub(crate) mod diff;
+pub(crate) mod latency;
 pub(crate) mod mapping;
 pub(crate) mod mediator;
 pub mod repository;
@@ -1,14 +1,14 @@
 use anyhow::Context;
 use chrono::{Days, NaiveDate};
+use common_config::CacheConfig;
 use common_in_memory_cache::InMemoryCache;
 use common_persistent_cache::PersistentCache;
-use common_restix::ResultExt;
 use common_rust::env;
-use domain_schedule_models::{Schedule, ScheduleType};
+use domain_schedule_models::{CacheMetadata, Schedule, ScheduleId, ScheduleType};
 use log::debug;
 use tokio::sync::Mutex;
 
-use crate::{dto::mpeix::ScheduleName, mpei_api::MpeiApi, time::WeekOfSemester};
+use crate::{dto::mpeix::ScheduleName, mpei_api::MpeiUpstream, time::WeekOfSemester};
 
 use super::{
     mapping::map_schedule_models,
@@ -16,27 +16,38 @@ use super::{
 };
 
 pub struct ScheduleRepository {
-    api: MpeiApi,
+    api: MpeiUpstream,
     mediator: Mutex<CacheMediator>,
 }
 
 impl ScheduleRepository {
-    pub fn new(api: MpeiApi) -> Self {
-        let cache_capacity = env::get_parsed_or("SCHEDULE_CACHE_CAPACITY", 500);
-        let cache_max_hits = env::get_parsed_or("SCHEDULE_CACHE_MAX_HITS", 20);
-        let cache_lifetife = env::get_parsed_or("SCHEDULE_CACHE_LIFETIME_HOURS", 6);
+    pub fn new(api: MpeiUpstream) -> Self {
         let cache_dir = env::get_or("SCHEDULE_CACHE_DIR", "./cache");
 
         Self {
             api,
             mediator: Mutex::new(CacheMediator {
-                in_memory_cache: InMemoryCache::with_capacity(cache_capacity)
-                    .max_hits(cache_max_hits)
-                    .expires_after_creation(chrono::Duration::hours(cache_lifetife)),
+                in_memory_cache: create_in_memory_cache(),
                 persistent_cache: PersistentCache::new(cache_dir.into()),
             }),
         }
     }
+
+    /// Apply the reloaded in-memory cache policies, cached schedules are kept
+    pub async fn reload_config(&self) {
+        self.mediator
+            .lock()
+            .await
+            .in_memory_cache
+            .replace_policies(create_in_memory_cache());
+    }
+}
+
+fn create_in_memory_cache() -> InMemoryCache<InMemoryCacheKey, Schedule> {
+    let config = CacheConfig::new(500, chrono::Duration::hours(6))
+        .max_hits(20)
+        .with_env_prefix("SCHEDULE_CACHE");
+    InMemoryCache::with_config(&config).named("schedule")
 }
 
 impl ScheduleRepository {
@@ -62,6 +73,60 @@ impl ScheduleRepository {
             .with_context(|| "Error while getting schedule from cache via CacheMediator")
     }
 
+    /// Get schedule from cache with the cache entry metadata, even if it is expired
+    pub async fn inspect_cached_schedule(
+        &self,
+        name: ScheduleName,
+        r#type: ScheduleType,
+        week_start: NaiveDate,
+    ) -> anyhow::Result<Option<(Schedule, CacheMetadata)>> {
+        let key = InMemoryCacheKey {
+            name: name.as_string(),
+            r#type: r#type.to_string(),
+            week_start,
+        };
+
+        self.mediator
+            .lock()
+            .await
+            .inspect(&key)
+            .await
+            .with_context(|| "Error while inspecting schedule cache via CacheMediator")
+    }
+
+    /// Look for any cached schedule, even expired, which contains the `date`.
+    ///
+    /// Cached schedules are keyed by the first day of the fetched range, so the day
+    /// may be present in the fetch started up to 6 days before it.
+    pub async fn find_cached_schedule_with_date(
+        &self,
+        name: ScheduleName,
+        r#type: ScheduleType,
+        date: NaiveDate,
+    ) -> anyhow::Result<Option<Schedule>> {
+        debug!("Looking for cached schedules containing {date}...");
+        let (name, r#type) = (name.as_string(), r#type.to_string());
+        let mut mediator = self.mediator.lock().await;
+        for days_before in 0..=6 {
+            let Some(week_start) = date.checked_sub_days(Days::new(days_before)) else {
+                break;
+            };
+            let key = InMemoryCacheKey {
+                name: name.clone(),
+                r#type: r#type.clone(),
+                week_start,
+            };
+            let schedule = mediator
+                .get(&key, true)
+                .await
+                .with_context(|| "Error while getting schedule from cache via CacheMediator")?;
+            if let Some(schedule) = schedule.filter(|s| contains_date(s, date)) {
+                return Ok(Some(schedule));
+            }
+        }
+        Ok(None)
+    }
+
     pub async fn insert_schedule_to_cache(
         &self,
         name: ScheduleName,
@@ -86,7 +151,7 @@ impl ScheduleRepository {
 
     pub async fn get_schedule_from_remote(
         &self,
-        schedule_id: i64,
+        schedule_id: ScheduleId,
         name: ScheduleName,
         r#type: ScheduleType,
         week_start: NaiveDate,
@@ -106,8 +171,7 @@ impl ScheduleRepository {
                 &week_end.format("%Y.%m.%d").to_string(),
                 1, // default language
             )
-            .await
-            .with_common_error()?;
+            .await?;
 
         Ok(map_schedule_models(
             name,
@@ -119,3 +183,11 @@ impl ScheduleRepository {
         ))
     }
 }
+
+fn contains_date(schedule: &Schedule, date: NaiveDate) -> bool {
+    schedule
+        .weeks
+        .iter()
+        .flat_map(|week| week.days.iter())
+        .any(|day| day.date == date)
+}
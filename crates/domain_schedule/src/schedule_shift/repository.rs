@@ -3,7 +3,8 @@ use std::{path::PathBuf, str::FromStr};
 use anyhow::{anyhow, Context};
 use chrono::{Duration, NaiveDate};
 use common_in_memory_cache::InMemoryCache;
-use common_rust::env;
+use common_rust::{env, Reloadable};
+use domain_schedule_models::{PeriodType, ShiftRuleDescription, ShiftRules};
 use domain_schedule_shift::ScheduleShift;
 use log::debug;
 use tokio::sync::Mutex;
@@ -12,33 +13,104 @@ use crate::time::{NaiveDateExt, WeekOfSemester};
 
 pub struct ScheduleShiftRepository {
     cache: Mutex<InMemoryCache<(), ScheduleShift>>,
+    config: Reloadable<ScheduleShiftConfig>,
+}
+
+struct ScheduleShiftConfig {
     config_path: PathBuf,
+    session_weeks: u8,
 }
 
-impl Default for ScheduleShiftRepository {
+impl Default for ScheduleShiftConfig {
     fn default() -> Self {
         let config_path = env::get_or("SCHEDULE_SHIFT_CONFIG_PATH", "./schedule_shift.toml");
         Self {
-            cache: Mutex::new(
-                InMemoryCache::with_capacity(1).expires_after_creation(Duration::minutes(1)),
-            ),
             config_path: config_path.into(),
+            session_weeks: env::get_parsed_or("SCHEDULE_SESSION_WEEKS", 4),
+        }
+    }
+}
+
+impl Default for ScheduleShiftRepository {
+    fn default() -> Self {
+        Self {
+            cache: Mutex::new(create_in_memory_cache()),
+            config: Default::default(),
         }
     }
 }
 
+fn create_in_memory_cache() -> InMemoryCache<(), ScheduleShift> {
+    InMemoryCache::with_capacity(1).expires_after_creation(Duration::minutes(1))
+}
+
 impl ScheduleShiftRepository {
     pub async fn get_week_of_semester(
         &self,
         week_start: &NaiveDate,
     ) -> anyhow::Result<WeekOfSemester> {
+        self.with_schedule_shift(|shift| week_start.week_of_semester(shift))
+            .await?
+            .ok_or_else(|| anyhow!("Cannot calculate week of semester for '{week_start}'"))
+    }
+
+    pub async fn get_period_type(&self, week_start: &NaiveDate) -> anyhow::Result<PeriodType> {
+        let session_weeks = self.config.load().session_weeks;
+        self.with_schedule_shift(|shift| week_start.period_type(shift, session_weeks))
+            .await
+    }
+
+    /// The first study day of the semester, if the semester starts in the middle of the week
+    pub async fn get_semester_start_within_week(
+        &self,
+        week_start: &NaiveDate,
+    ) -> anyhow::Result<Option<NaiveDate>> {
+        self.with_schedule_shift(|shift| {
+            week_start
+                .week_number_provenance(shift)
+                .first_day
+                .filter(|it| week_start < it && *it < *week_start + Duration::days(7))
+        })
+        .await
+    }
+
+    /// Get all shift rules and, if `date` is specified,
+    /// the rule which produced the week number of this date.
+    pub async fn get_shift_rules(&self, date: Option<NaiveDate>) -> anyhow::Result<ShiftRules> {
+        self.with_schedule_shift(|shift| ShiftRules {
+            rules: shift
+                .map(|it| it.rules())
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(year, semester, rule)| ShiftRuleDescription {
+                    year: year.value(),
+                    semester: semester.to_string(),
+                    first_day: rule.first_day,
+                    week_number: rule.week_number,
+                })
+                .collect(),
+            provenance: date.map(|date| date.week_number_provenance(shift)),
+        })
+        .await
+    }
+
+    /// Drop the cached shift rules, so the rules file is read again right away
+    pub async fn reload_config(&self) {
+        *self.cache.lock().await = create_in_memory_cache();
+    }
+
+    async fn with_schedule_shift<T>(
+        &self,
+        f: impl FnOnce(Option<&ScheduleShift>) -> T,
+    ) -> anyhow::Result<T> {
         debug!("Getting schedule shift...");
         let mut cache = self.cache.lock().await;
         if cache.get(&()).is_none() {
-            if self.config_path.exists() {
+            let config_path = &self.config.load().config_path;
+            if config_path.exists() {
                 cache.insert(
                     (),
-                    ScheduleShift::from_file(&self.config_path)
+                    ScheduleShift::from_file(config_path)
                         .await
                         .with_context(|| "Cannot access shift config file")?,
                 );
@@ -52,8 +124,6 @@ impl ScheduleShiftRepository {
             }
         }
 
-        week_start
-            .week_of_semester(cache.get(&()))
-            .ok_or_else(|| anyhow!("Cannot calculate week of semester for '{week_start}'"))
+        Ok(f(cache.get(&())))
     }
 }
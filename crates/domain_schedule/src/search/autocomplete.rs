@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use domain_schedule_models::{ScheduleAutocompleteItem, ScheduleType};
+
+/// In-memory index of the schedule names for the autocomplete.
+///
+/// Names are stored sorted by their normalized form, so all names with the same prefix
+/// are the neighbours in the map and the lookup does not depend on the index size.
+#[derive(Default)]
+pub(crate) struct PrefixIndex(BTreeMap<String, ScheduleAutocompleteItem>);
+
+impl PrefixIndex {
+    pub(crate) fn insert(&mut self, name: &str, r#type: ScheduleType) {
+        self.0.insert(
+            normalize(name),
+            ScheduleAutocompleteItem {
+                name: name.to_owned(),
+                r#type,
+            },
+        );
+    }
+
+    /// Names starting with the `prefix`, in alphabetical order
+    pub(crate) fn lookup(
+        &self,
+        prefix: &str,
+        r#type: Option<&ScheduleType>,
+        limit: usize,
+    ) -> Vec<ScheduleAutocompleteItem> {
+        let prefix = normalize(prefix);
+        self.0
+            .range(prefix.to_owned()..)
+            .take_while(|(key, _)| key.starts_with(&prefix))
+            .map(|(_, item)| item)
+            .filter(|item| r#type.map_or(true, |t| &item.r#type == t))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Lowercase the text and replace the latin letters, which look like the cyrillic ones,
+/// because mobile keyboards often stay in the latin layout, e.g. `A-08` instead of `А-08`
+fn normalize(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a' => 'а',
+            'b' => 'в',
+            'c' => 'с',
+            'e' | 'ё' => 'е',
+            'h' => 'н',
+            'k' => 'к',
+            'm' => 'м',
+            'o' => 'о',
+            'p' => 'р',
+            't' => 'т',
+            'x' => 'х',
+            'y' => 'у',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_schedule_models::ScheduleType;
+
+    use super::PrefixIndex;
+
+    fn index() -> PrefixIndex {
+        let mut index = PrefixIndex::default();
+        index.insert("А-08-19", ScheduleType::Group);
+        index.insert("А-01-20", ScheduleType::Group);
+        index.insert("Аб-08-21", ScheduleType::Group);
+        index.insert("Анисимов Сергей Петрович", ScheduleType::Person);
+        index.insert("С-12-21", ScheduleType::Group);
+        index
+    }
+
+    fn names(index: &PrefixIndex, prefix: &str, r#type: Option<&ScheduleType>) -> Vec<String> {
+        index
+            .lookup(prefix, r#type, 8)
+            .into_iter()
+            .map(|it| it.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_lookup_by_prefix() {
+        let index = index();
+        assert_eq!(vec!["А-01-20", "А-08-19"], names(&index, "а-0", None));
+        assert_eq!(vec!["А-08-19"], names(&index, "А-08", None));
+        assert!(names(&index, "Б-", None).is_empty());
+        assert_eq!(1, index.lookup("а", None, 1).len());
+    }
+
+    #[test]
+    fn test_lookup_in_latin_layout() {
+        assert_eq!(vec!["А-08-19"], names(&index(), "A-08", None));
+        assert_eq!(vec!["С-12-21"], names(&index(), "c-12", None));
+    }
+
+    #[test]
+    fn test_lookup_by_type() {
+        let index = index();
+        assert_eq!(
+            vec!["Анисимов Сергей Петрович"],
+            names(&index, "ан", Some(&ScheduleType::Person))
+        );
+        assert_eq!(
+            vec!["А-01-20", "А-08-19", "Аб-08-21"],
+            names(&index, "а", Some(&ScheduleType::Group))
+        );
+    }
+}
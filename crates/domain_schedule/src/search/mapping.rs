@@ -14,15 +14,78 @@ pub(crate) fn map_search_models(
 ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
     let mut output = Vec::with_capacity(mpei_results.len());
     for res in mpei_results {
+        let r#type = res
+            .r#type
+            .parse::<ScheduleType>()
+            .map_err(CommonError::internal)?;
+        let (position, department) = match r#type {
+            ScheduleType::Person => parse_person_description(&res.description),
+            _ => (None, None),
+        };
         output.push(ScheduleSearchResult {
             name: SPACES_PATTERN.replace_all(&res.label, " ").to_string(),
             description: res.description.trim().to_owned(),
             id: res.id.to_string(),
-            r#type: res
-                .r#type
-                .parse::<ScheduleType>()
-                .map_err(CommonError::internal)?,
+            r#type,
+            position,
+            department,
         })
     }
     Ok(output)
 }
+
+/// Parse person description like `"доц., каф. ВМСС"` into position and department
+fn parse_person_description(description: &str) -> (Option<String>, Option<String>) {
+    let mut position = None;
+    let mut department = None;
+    for part in description
+        .split(',')
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+    {
+        if let Some(name) = part.strip_prefix("каф.") {
+            department = Some(name.trim().to_owned()).filter(|it| !it.is_empty());
+        } else if position.is_none() {
+            position = Some(expand_position(part));
+        }
+    }
+    (position, department)
+}
+
+fn expand_position(position: &str) -> String {
+    match position.replace(' ', "").as_str() {
+        "доц." => "доцент",
+        "проф." => "профессор",
+        "ст.преп." => "старший преподаватель",
+        "преп." => "преподаватель",
+        "асс." => "ассистент",
+        "зав.каф." => "заведующий кафедрой",
+        _ => position,
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_person_description;
+
+    #[test]
+    fn test_parse_person_description() {
+        assert_eq!(
+            parse_person_description("доц., каф. ВМСС"),
+            (Some("доцент".to_owned()), Some("ВМСС".to_owned()))
+        );
+        assert_eq!(
+            parse_person_description("ст. преп., каф. ТОЭ"),
+            (
+                Some("старший преподаватель".to_owned()),
+                Some("ТОЭ".to_owned())
+            )
+        );
+        assert_eq!(
+            parse_person_description("инженер"),
+            (Some("инженер".to_owned()), None)
+        );
+        assert_eq!(parse_person_description(""), (None, None));
+    }
+}
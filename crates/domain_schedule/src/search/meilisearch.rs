@@ -0,0 +1,214 @@
+use anyhow::Context;
+use async_trait::async_trait;
+use common_errors::errors::CommonError;
+use common_rust::env;
+use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
+use log::info;
+use reqwest::header::{self, HeaderMap, HeaderValue};
+use restix::{api, post};
+use serde::{Deserialize, Serialize};
+
+use crate::dto::mpeix::ScheduleSearchQuery;
+
+use super::{SearchBackend, SEARCH_RESULTS_LIMIT};
+
+const SCHEDULE_TYPES: [ScheduleType; 3] = [
+    ScheduleType::Group,
+    ScheduleType::Person,
+    ScheduleType::Room,
+];
+
+#[api(error = "CommonError")]
+pub trait MeilisearchApi {
+    #[post("/indexes/{index}/search")]
+    async fn search(&self, #[path] index: &str, #[raw_body] request: String)
+        -> MeilisearchResponse;
+
+    #[post("/indexes/{index}/documents")]
+    async fn add_documents(
+        &self,
+        #[path] index: &str,
+        #[query("primaryKey")] primary_key: &str,
+        #[raw_body] documents: String,
+    ) -> MeilisearchTask;
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MeilisearchRequest<'a> {
+    q: &'a str,
+    limit: usize,
+    show_ranking_score: bool,
+}
+
+#[derive(Deserialize)]
+pub struct MeilisearchResponse {
+    hits: Vec<MeilisearchHit>,
+}
+
+#[derive(Deserialize)]
+struct MeilisearchHit {
+    #[serde(flatten)]
+    result: ScheduleSearchResult,
+    #[serde(rename = "_rankingScore", default)]
+    ranking_score: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MeilisearchTask {
+    task_uid: u64,
+}
+
+/// Search with Meilisearch, which has typo tolerance and relevancy ranking.
+///
+/// Each schedule type has its own index, e.g. `schedule_search_results_group`,
+/// so typed search does not need any index settings. Indexes are created by Meilisearch
+/// on the first insertion of the documents.
+pub struct MeilisearchSearchBackend {
+    api: MeilisearchApi,
+    index: String,
+}
+
+impl MeilisearchSearchBackend {
+    /// Read config from `MEILISEARCH_URL`, `MEILISEARCH_API_KEY`
+    /// and `MEILISEARCH_INDEX` environment variables.
+    pub fn from_env() -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        if let Some(api_key) = env::get("MEILISEARCH_API_KEY") {
+            let mut value = HeaderValue::from_str(&format!("Bearer {api_key}"))
+                .expect("Invalid MEILISEARCH_API_KEY");
+            value.set_sensitive(true);
+            headers.insert(header::AUTHORIZATION, value);
+        }
+        let api = MeilisearchApi::builder()
+            .base_url(env::required("MEILISEARCH_URL"))
+            .client(
+                reqwest::ClientBuilder::new()
+                    .default_headers(headers)
+                    .timeout(std::time::Duration::from_secs(5))
+                    .build()
+                    .expect("Error while building reqwest::Client"),
+            )
+            .build()
+            .expect("DI error while creating MeilisearchApi");
+        Self {
+            api,
+            index: env::get_or("MEILISEARCH_INDEX", "schedule_search_results"),
+        }
+    }
+
+    fn index_of(&self, r#type: &ScheduleType) -> String {
+        format!("{}_{}", self.index, r#type.as_ref())
+    }
+
+    async fn search_typed(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<Vec<MeilisearchHit>> {
+        let request = serde_json::to_string(&MeilisearchRequest {
+            q: query.as_ref(),
+            limit: SEARCH_RESULTS_LIMIT,
+            show_ranking_score: true,
+        })?;
+        let response = self
+            .api
+            .search(&self.index_of(r#type), request)
+            .await
+            .with_context(|| format!("Error while searching in Meilisearch index of '{type}'"))?;
+        Ok(response.hits)
+    }
+}
+
+#[async_trait]
+impl SearchBackend for MeilisearchSearchBackend {
+    async fn search(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: Option<ScheduleType>,
+    ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
+        let hits = match r#type {
+            Some(r#type) => self.search_typed(query, &r#type).await?,
+            None => {
+                let (groups, persons, rooms) = tokio::try_join!(
+                    self.search_typed(query, &SCHEDULE_TYPES[0]),
+                    self.search_typed(query, &SCHEDULE_TYPES[1]),
+                    self.search_typed(query, &SCHEDULE_TYPES[2]),
+                )?;
+                merge_by_ranking_score(vec![groups, persons, rooms])
+            }
+        };
+        Ok(hits.into_iter().map(|it| it.result).collect())
+    }
+
+    async fn index(&self, results: &[ScheduleSearchResult]) -> anyhow::Result<()> {
+        for r#type in &SCHEDULE_TYPES {
+            let documents = results
+                .iter()
+                .filter(|it| &it.r#type == r#type)
+                .collect::<Vec<_>>();
+            if documents.is_empty() {
+                continue;
+            }
+            let task = self
+                .api
+                .add_documents(
+                    &self.index_of(r#type),
+                    "id",
+                    serde_json::to_string(&documents)?,
+                )
+                .await
+                .with_context(|| format!("Error while indexing '{type}' in Meilisearch"))?;
+            info!(
+                "Meilisearch task {} enqueued for {} documents of '{type}'",
+                task.task_uid,
+                documents.len()
+            );
+        }
+        Ok(())
+    }
+
+    fn has_own_index(&self) -> bool {
+        true
+    }
+}
+
+/// Hits of different indexes are merged into one list, sorted by the relevancy
+fn merge_by_ranking_score(hits: Vec<Vec<MeilisearchHit>>) -> Vec<MeilisearchHit> {
+    let mut hits = hits.into_iter().flatten().collect::<Vec<_>>();
+    hits.sort_by(|a, b| b.ranking_score.total_cmp(&a.ranking_score));
+    hits.truncate(SEARCH_RESULTS_LIMIT);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_by_ranking_score, MeilisearchResponse};
+
+    #[test]
+    fn test_hits_are_merged_by_ranking_score() {
+        let groups: MeilisearchResponse = serde_json::from_str(
+            r#"{"hits": [
+                {"name": "А-08-19", "description": "ИВТИ", "id": "1", "type": "GROUP", "_rankingScore": 0.9},
+                {"name": "А-08-20", "description": "ИВТИ", "id": "2", "type": "GROUP", "_rankingScore": 0.5}
+            ]}"#,
+        )
+        .unwrap();
+        let persons: MeilisearchResponse = serde_json::from_str(
+            r#"{"hits": [
+                {"name": "Адамов А.А.", "description": "", "id": "3", "type": "PERSON", "_rankingScore": 0.7}
+            ]}"#,
+        )
+        .unwrap();
+        let names = merge_by_ranking_score(vec![groups.hits, persons.hits])
+            .into_iter()
+            .map(|it| it.result.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["А-08-19", "Адамов А.А.", "А-08-20"]);
+    }
+}
@@ -1,2 +1,60 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use common_database::Database;
+use common_rust::env;
+use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
+use log::info;
+
+use crate::dto::mpeix::ScheduleSearchQuery;
+
+use self::{meilisearch::MeilisearchSearchBackend, postgres::PostgresSearchBackend};
+
+pub(crate) mod autocomplete;
 pub(crate) mod mapping;
+pub mod meilisearch;
+pub mod postgres;
 pub mod repository;
+
+/// Max number of search results returned by the backends
+const SEARCH_RESULTS_LIMIT: usize = 30;
+
+/// Full-text search over the known schedules.
+///
+/// The `schedule_search_results` table is always the source of truth: results found
+/// on the MPEI backend are saved there first, and then passed to [SearchBackend::index].
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Find the best matches of the query, sorted by relevance.
+    async fn search(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: Option<ScheduleType>,
+    ) -> anyhow::Result<Vec<ScheduleSearchResult>>;
+
+    /// Add new and update existing results of the backend's own index, if it has one.
+    async fn index(&self, _results: &[ScheduleSearchResult]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Whether the backend has its own index, which should be synced
+    /// with the `schedule_search_results` table on startup.
+    fn has_own_index(&self) -> bool {
+        false
+    }
+}
+
+/// Postgres `LIKE` search, or Meilisearch if `SCHEDULE_SEARCH_BACKEND=meilisearch`
+pub fn create_search_backend(database: Arc<Database>) -> Arc<dyn SearchBackend> {
+    match env::get_or("SCHEDULE_SEARCH_BACKEND", "postgres").as_str() {
+        "meilisearch" => {
+            info!("Schedule search backend: Meilisearch");
+            Arc::new(MeilisearchSearchBackend::from_env())
+        }
+        "postgres" => {
+            info!("Schedule search backend: Postgres");
+            Arc::new(PostgresSearchBackend::new(database))
+        }
+        other => panic!("Unknown schedule search backend '{other}'"),
+    }
+}
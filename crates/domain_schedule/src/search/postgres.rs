@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use common_database::Database;
+use common_sql::Page;
+use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
+use tokio_postgres::Row;
+
+use crate::dto::mpeix::ScheduleSearchQuery;
+
+use super::{SearchBackend, SEARCH_RESULTS_LIMIT};
+
+/// Search with `LIKE` right in the `schedule_search_results` table
+pub struct PostgresSearchBackend {
+    database: Arc<Database>,
+}
+
+impl PostgresSearchBackend {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self { database }
+    }
+}
+
+#[async_trait]
+impl SearchBackend for PostgresSearchBackend {
+    async fn search(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: Option<ScheduleType>,
+    ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
+        let stmt = if let Some(r#type) = r#type {
+            include_str!("../../sql/select_all_schedule_search_results_typed.pgsql")
+                .replace("$2", r#type.as_ref())
+        } else {
+            include_str!("../../sql/select_all_schedule_search_results.pgsql").to_string()
+        }
+        .replace("$1", query.as_ref())
+        .replace("$page", &Page::first(SEARCH_RESULTS_LIMIT).to_string());
+
+        let client = self.database.read().get().await?;
+        let mut results = client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while getting schedule search results from db")?
+            .iter()
+            .map(map_from_db_model)
+            .collect::<anyhow::Result<Vec<ScheduleSearchResult>>>()
+            .with_context(|| "Error while mapping schedule search results from db")?;
+        rank_by_match_position(&mut results, query.as_ref());
+        Ok(results)
+    }
+}
+
+/// Results with the query closer to the beginning of the name go first
+fn rank_by_match_position(results: &mut [ScheduleSearchResult], query: &str) {
+    let max_idx = results.len();
+    results.sort_by_key(|it| it.name.to_lowercase().find(query).unwrap_or(max_idx));
+}
+
+pub(super) fn map_from_db_model(row: &Row) -> anyhow::Result<ScheduleSearchResult> {
+    let db_type = row.get("type");
+    Ok(ScheduleSearchResult {
+        name: row.get("name"),
+        description: row.get("description"),
+        id: row.get("remote_id"),
+        r#type: match db_type {
+            "group" => ScheduleType::Group,
+            "person" => ScheduleType::Person,
+            "room" => ScheduleType::Room,
+            _ => bail!("Database contains invalid schedule type value: '{db_type}'"),
+        },
+        position: row.get("position"),
+        department: row.get("department"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
+
+    use super::rank_by_match_position;
+
+    fn result(name: &str) -> ScheduleSearchResult {
+        ScheduleSearchResult {
+            name: name.to_owned(),
+            description: String::new(),
+            id: "1".to_owned(),
+            r#type: ScheduleType::Group,
+            position: None,
+            department: None,
+        }
+    }
+
+    #[test]
+    fn test_rank_by_match_position() {
+        let mut results = vec![result("ИЭ-08-19"), result("А-08-19"), result("А-08-20")];
+        rank_by_match_position(&mut results, "а-08");
+        let names = results
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["А-08-19", "А-08-20", "ИЭ-08-19"]);
+    }
+}
@@ -1,23 +1,34 @@
-use std::sync::Arc;
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
 
-use anyhow::{bail, Context};
+use anyhow::Context;
+use common_config::CacheConfig;
+use common_database::Database;
 use common_in_memory_cache::InMemoryCache;
-use common_restix::ResultExt;
-use common_rust::env;
-use deadpool_postgres::Pool;
-use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
-use log::info;
+use common_rust::{env, Reloadable};
+use domain_schedule_models::{ScheduleAutocompleteItem, ScheduleSearchResult, ScheduleType};
+use log::{info, warn};
 use tokio::sync::Mutex;
-use tokio_postgres::Row;
 
-use crate::{dto::mpeix::ScheduleSearchQuery, mpei_api::MpeiApi};
+use crate::{dto::mpeix::ScheduleSearchQuery, mpei_api::MpeiUpstream};
 
-use super::mapping::map_search_models;
+use super::{
+    autocomplete::PrefixIndex, mapping::map_search_models, postgres::map_from_db_model,
+    SearchBackend,
+};
+
+/// Batch size of the search index sync
+const INDEX_SYNC_BATCH_SIZE: usize = 1000;
 
 pub struct ScheduleSearchRepository {
-    api: MpeiApi,
-    db_pool: Arc<Pool>,
+    api: MpeiUpstream,
+    database: Arc<Database>,
+    search_backend: Arc<dyn SearchBackend>,
+    recency_policy: Reloadable<RecencyPolicy>,
     in_memory_cache: Mutex<InMemoryCache<TypedSearchQuery, Vec<ScheduleSearchResult>>>,
+    autocomplete_index: RwLock<PrefixIndex>,
 }
 
 /// Helper struct for [ScheduleSearchRepository]:
@@ -25,21 +36,64 @@ pub struct ScheduleSearchRepository {
 #[derive(Hash, PartialEq, Eq)]
 struct TypedSearchQuery(ScheduleSearchQuery, Option<ScheduleType>);
 
-impl ScheduleSearchRepository {
-    pub fn new(db_pool: Arc<Pool>, api: MpeiApi) -> Self {
-        let cache_capacity = env::get_parsed_or("SCHEDULE_SEARCH_CACHE_CAPACITY", 3000);
-        let cache_lifetife = env::get_parsed_or("SCHEDULE_SEARCH_CACHE_LIFETIME_MINUTES", 5);
+/// Helper struct for [ScheduleSearchRepository]:
+/// Results not seen on the MPEI backend for `archive_after_days` are archived,
+/// e.g. the groups graduated years ago. Archived results are excluded from the search,
+/// or moved to the end of the results if `include_archived` is set.
+struct RecencyPolicy {
+    archive_after_days: i32,
+    include_archived: bool,
+}
 
+impl Default for RecencyPolicy {
+    fn default() -> Self {
+        Self {
+            archive_after_days: env::get_parsed_or("SCHEDULE_SEARCH_ARCHIVE_AFTER_DAYS", 365),
+            include_archived: env::get_parsed_or("SCHEDULE_SEARCH_INCLUDE_ARCHIVED", false),
+        }
+    }
+}
+
+impl RecencyPolicy {
+    fn apply(
+        &self,
+        results: Vec<ScheduleSearchResult>,
+        archived: &HashSet<String>,
+    ) -> Vec<ScheduleSearchResult> {
+        let (mut actual, stale): (Vec<_>, Vec<_>) = results
+            .into_iter()
+            .partition(|it| !archived.contains(&it.name));
+        if self.include_archived {
+            actual.extend(stale);
+        }
+        actual
+    }
+}
+
+impl ScheduleSearchRepository {
+    pub fn new(
+        database: Arc<Database>,
+        api: MpeiUpstream,
+        search_backend: Arc<dyn SearchBackend>,
+    ) -> Self {
         Self {
             api,
-            db_pool,
-            in_memory_cache: Mutex::new(
-                InMemoryCache::with_capacity(cache_capacity)
-                    .expires_after_creation(chrono::Duration::hours(cache_lifetife)),
-            ),
+            database,
+            search_backend,
+            recency_policy: Default::default(),
+            in_memory_cache: Mutex::new(create_in_memory_cache()),
+            autocomplete_index: Default::default(),
         }
     }
 
+    /// Apply the reloaded in-memory cache policies, cached results are kept
+    pub async fn reload_config(&self) {
+        self.in_memory_cache
+            .lock()
+            .await
+            .replace_policies(create_in_memory_cache());
+    }
+
     pub async fn get_results_from_cache(
         &self,
         query: ScheduleSearchQuery,
@@ -69,61 +123,154 @@ impl ScheduleSearchRepository {
         query: &ScheduleSearchQuery,
         r#type: &ScheduleType,
     ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
-        map_search_models(
-            self.api
-                .search(query.as_ref(), r#type)
-                .await
-                .with_common_error()?,
-        )
-        .with_context(|| "Error while mapping response from MPEI backend")
+        map_search_models(self.api.search(query.as_ref(), r#type).await?)
+            .with_context(|| "Error while mapping response from MPEI backend")
     }
 
     pub async fn init_schedule_search_results_db(&self) -> anyhow::Result<()> {
-        let client = self.db_pool.get().await?;
+        let client = self.database.write().get().await?;
         let stmt = include_str!("../../sql/create_schedule_search_results.pgsql");
         client
             .query(stmt, &[])
             .await
             .with_context(|| "Error during table 'schedule_search_results' creation")?;
+        let stmt = include_str!("../../sql/migrate_schedule_search_results.pgsql");
+        client
+            .batch_execute(stmt)
+            .await
+            .with_context(|| "Error during table 'schedule_search_results' migration")?;
         info!("Table 'schedule_search_results' initialization passed successfully");
         Ok(())
     }
 
-    pub async fn get_results_from_db(
-        &self,
-        query: &ScheduleSearchQuery,
-        r#type: Option<ScheduleType>,
-    ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
-        let stmt = if let Some(r#type) = r#type {
-            include_str!("../../sql/select_all_schedule_search_results_typed.pgsql")
-                .replace("$2", r#type.as_ref())
-        } else {
-            include_str!("../../sql/select_all_schedule_search_results.pgsql").to_string()
+    /// Copy all results of the `schedule_search_results` table to the index of the search backend.
+    /// Does nothing if the backend searches right in the table.
+    pub async fn sync_search_index(&self) -> anyhow::Result<()> {
+        if !self.search_backend.has_own_index() {
+            return Ok(());
+        }
+        let stmt = include_str!("../../sql/select_every_schedule_search_result.pgsql");
+        let client = self.database.read().get().await?;
+        let results = client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error while getting schedule search results from db")?
+            .iter()
+            .map(map_from_db_model)
+            .collect::<anyhow::Result<Vec<ScheduleSearchResult>>>()
+            .with_context(|| "Error while mapping schedule search results from db")?;
+        for batch in results.chunks(INDEX_SYNC_BATCH_SIZE) {
+            self.search_backend.index(batch).await?;
         }
-        .replace("$1", query.as_ref());
+        info!(
+            "Search index sync of {} results passed successfully",
+            results.len()
+        );
+        Ok(())
+    }
 
-        let client = self.db_pool.get().await?;
+    /// Fill the autocomplete index with all results of the `schedule_search_results` table.
+    /// Later the index is updated with the new results, see [Self::insert_results_to_db].
+    pub async fn load_autocomplete_index(&self) -> anyhow::Result<()> {
+        let stmt = include_str!("../../sql/select_every_schedule_search_result.pgsql");
+        let client = self.database.read().get().await?;
         let results = client
-            .query(&stmt, &[])
+            .query(stmt, &[])
             .await
             .with_context(|| "Error while getting schedule search results from db")?
             .iter()
             .map(map_from_db_model)
             .collect::<anyhow::Result<Vec<ScheduleSearchResult>>>()
             .with_context(|| "Error while mapping schedule search results from db")?;
-        Ok(results)
+        let mut index = self.autocomplete_index.write().unwrap();
+        for result in results {
+            index.insert(&result.name, result.r#type);
+        }
+        info!("Autocomplete index of {} names loaded", index.len());
+        Ok(())
+    }
+
+    /// Names starting with the `prefix`, straight from the memory without any I/O
+    pub fn get_autocomplete(
+        &self,
+        prefix: &str,
+        r#type: Option<&ScheduleType>,
+        limit: usize,
+    ) -> Vec<ScheduleAutocompleteItem> {
+        self.autocomplete_index
+            .read()
+            .unwrap()
+            .lookup(prefix, r#type, limit)
     }
 
+    /// Names of all rooms which were ever found on the MPEI backend
+    pub async fn get_known_rooms(&self) -> anyhow::Result<Vec<String>> {
+        let stmt = include_str!("../../sql/select_known_rooms.pgsql");
+        let client = self.database.read().get().await?;
+        Ok(client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error while getting known rooms from db")?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect())
+    }
+
+    /// Search with the search backend, applying the recency policy to the found results
+    pub async fn get_results_from_search_backend(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: Option<ScheduleType>,
+    ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
+        let results = self.search_backend.search(query, r#type).await?;
+        if results.is_empty() {
+            return Ok(results);
+        }
+        let archived = self.get_archived_names(&results).await?;
+        Ok(self.recency_policy.load().apply(results, &archived))
+    }
+
+    /// Names of the `results`, which were not seen on the MPEI backend for a long time
+    async fn get_archived_names(
+        &self,
+        results: &[ScheduleSearchResult],
+    ) -> anyhow::Result<HashSet<String>> {
+        let names = results
+            .iter()
+            .map(|it| it.name.as_str())
+            .collect::<Vec<_>>();
+        let stmt = include_str!("../../sql/select_archived_schedule_search_results.pgsql");
+        let client = self.database.read().get().await?;
+        Ok(client
+            .query(
+                stmt,
+                &[&names, &self.recency_policy.load().archive_after_days],
+            )
+            .await
+            .with_context(|| "Error while getting archived schedule search results from db")?
+            .iter()
+            .map(|row| row.get("name"))
+            .collect())
+    }
+
+    /// Save results to the `schedule_search_results` table and to the index of the search backend.
+    /// The table is the source of truth, so indexing errors are only logged.
+    /// Saved results are marked as seen on the MPEI backend just now.
     pub async fn insert_results_to_db(
         &self,
         results: Vec<ScheduleSearchResult>,
     ) -> anyhow::Result<()> {
         let values = results
-            .into_iter()
+            .iter()
             .map(|it| {
                 format!(
-                    "('{}', '{}', '{}', '{}')",
-                    it.id, it.name, it.description, it.r#type,
+                    "('{}', '{}', '{}', '{}', {}, {})",
+                    it.id,
+                    it.name,
+                    it.description,
+                    it.r#type,
+                    sql_nullable(&it.position),
+                    sql_nullable(&it.department),
                 )
             })
             .collect::<Vec<String>>()
@@ -132,26 +279,71 @@ impl ScheduleSearchRepository {
         let stmt = include_str!("../../sql/update_schedule_search_results.pgsql")
             .replace("$values", &values);
 
-        let client = self.db_pool.get().await?;
+        let client = self.database.write().get().await?;
         client
             .query(&stmt, &[])
             .await
             .with_context(|| "Error while inserting schedule search results into db")?;
+        {
+            let mut index = self.autocomplete_index.write().unwrap();
+            for result in &results {
+                index.insert(&result.name, result.r#type.to_owned());
+            }
+        }
+        if let Err(e) = self.search_backend.index(&results).await {
+            warn!("{e:#}");
+        }
         Ok(())
     }
 }
 
-fn map_from_db_model(row: &Row) -> anyhow::Result<ScheduleSearchResult> {
-    let db_type = row.get("type");
-    Ok(ScheduleSearchResult {
-        name: row.get("name"),
-        description: row.get("description"),
-        id: row.get("remote_id"),
-        r#type: match db_type {
-            "group" => ScheduleType::Group,
-            "person" => ScheduleType::Person,
-            "room" => ScheduleType::Room,
-            _ => bail!("Database contains invalid schedule type value: '{db_type}'"),
-        },
-    })
+fn sql_nullable(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("'{value}'"),
+        None => "NULL".to_owned(),
+    }
+}
+
+fn create_in_memory_cache() -> InMemoryCache<TypedSearchQuery, Vec<ScheduleSearchResult>> {
+    let config = CacheConfig::new(3000, chrono::Duration::minutes(5))
+        .with_env_prefix("SCHEDULE_SEARCH_CACHE");
+    InMemoryCache::with_config(&config).named("schedule_search")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use domain_schedule_models::{ScheduleSearchResult, ScheduleType};
+
+    use super::RecencyPolicy;
+
+    fn result(name: &str) -> ScheduleSearchResult {
+        ScheduleSearchResult {
+            name: name.to_owned(),
+            description: String::new(),
+            id: "1".to_owned(),
+            r#type: ScheduleType::Group,
+            position: None,
+            department: None,
+        }
+    }
+
+    #[test]
+    fn test_recency_policy() {
+        let results = vec![result("А-08-14"), result("А-08-19"), result("А-08-20")];
+        let archived = HashSet::from(["А-08-14".to_owned()]);
+        let names = |include_archived| {
+            RecencyPolicy {
+                archive_after_days: 365,
+                include_archived,
+            }
+            .apply(results.clone(), &archived)
+            .into_iter()
+            .map(|it| it.name)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(names(false), vec!["А-08-19", "А-08-20"]);
+        assert_eq!(names(true), vec!["А-08-19", "А-08-20", "А-08-14"]);
+    }
 }
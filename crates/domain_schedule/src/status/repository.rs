@@ -0,0 +1,179 @@
+use std::{collections::HashMap, sync::Arc, sync::Mutex, time::Duration};
+
+use chrono::{Local, NaiveDateTime};
+use common_database::Database;
+use common_rust::env;
+use deadpool_postgres::Pool;
+use domain_schedule_models::ComponentHealth;
+
+/// Checks the components of the service for the public status page
+/// and remembers when each component changed its status.
+pub struct ServiceStatusRepository {
+    database: Arc<Database>,
+    client: reqwest::Client,
+    /// Health endpoints of the other services by component name, e.g. the bots
+    health_urls: Vec<(String, String)>,
+    timeout: Duration,
+    history: Mutex<StatusHistory>,
+}
+
+impl ServiceStatusRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            client: reqwest::Client::new(),
+            health_urls: parse_health_urls(&env::get_or("STATUS_HEALTH_URLS", "")),
+            timeout: Duration::from_millis(env::get_parsed_or("STATUS_CHECK_TIMEOUT_MS", 2000)),
+            history: Mutex::new(StatusHistory::new(Local::now().naive_local())),
+        }
+    }
+
+    pub fn health_urls(&self) -> &[(String, String)] {
+        &self.health_urls
+    }
+
+    /// `Down` if the primary database does not answer,
+    /// `Degraded` if only the read replica does not answer
+    pub async fn check_database(&self) -> (ComponentHealth, Option<String>) {
+        if let Err(e) = self.ping_pool(self.database.write()).await {
+            return (ComponentHealth::Down, Some(e));
+        }
+        match self.ping_pool(self.database.read()).await {
+            Ok(_) => (ComponentHealth::Ok, None),
+            Err(e) => (
+                ComponentHealth::Degraded,
+                Some(format!("Read replica: {e}")),
+            ),
+        }
+    }
+
+    async fn ping_pool(&self, pool: &Pool) -> Result<(), String> {
+        let ping = async {
+            let client = pool.get().await.map_err(|e| e.to_string())?;
+            client
+                .query_one("SELECT 1", &[])
+                .await
+                .map_err(|e| e.to_string())
+        };
+        match tokio::time::timeout(self.timeout, ping).await {
+            Ok(result) => result.map(|_| ()),
+            Err(_) => Err(format!("No answer in {:?}", self.timeout)),
+        }
+    }
+
+    /// `Ok` if the health endpoint answers with success status in time, otherwise `Down`
+    pub async fn check_health_url(&self, url: &str) -> (ComponentHealth, Option<String>) {
+        let response = self.client.get(url).timeout(self.timeout).send().await;
+        match response {
+            Ok(response) if response.status().is_success() => (ComponentHealth::Ok, None),
+            Ok(response) => (
+                ComponentHealth::Down,
+                Some(format!("Health check status {}", response.status())),
+            ),
+            Err(e) => (ComponentHealth::Down, Some(e.to_string())),
+        }
+    }
+
+    /// Remember the status of the component and get the time of its last change
+    pub fn record(&self, name: &str, health: ComponentHealth, now: NaiveDateTime) -> NaiveDateTime {
+        self.history.lock().unwrap().record(name, health, now)
+    }
+}
+
+/// Statuses of the components with the time of the last change
+struct StatusHistory {
+    started_at: NaiveDateTime,
+    statuses: HashMap<String, (ComponentHealth, NaiveDateTime)>,
+}
+
+impl StatusHistory {
+    fn new(started_at: NaiveDateTime) -> Self {
+        Self {
+            started_at,
+            statuses: HashMap::new(),
+        }
+    }
+
+    /// The first status of the component is considered to be set at the service start
+    fn record(&mut self, name: &str, health: ComponentHealth, now: NaiveDateTime) -> NaiveDateTime {
+        let started_at = self.started_at;
+        let (status, last_change) = self
+            .statuses
+            .entry(name.to_owned())
+            .or_insert((health, started_at));
+        if *status != health {
+            *status = health;
+            *last_change = now;
+        }
+        *last_change
+    }
+}
+
+/// Parse `name=url` pairs separated by commas, invalid pairs are skipped
+fn parse_health_urls(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let (name, url) = pair.split_once('=')?;
+            let (name, url) = (name.trim(), url.trim());
+            (!name.is_empty() && !url.is_empty()).then(|| (name.to_owned(), url.to_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+    use domain_schedule_models::ComponentHealth;
+
+    use super::{parse_health_urls, StatusHistory};
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn last_change_is_updated_only_on_status_change() {
+        let started_at = datetime("2023-03-13 09:00");
+        let mut history = StatusHistory::new(started_at);
+        let record = |history: &mut StatusHistory, health, now| {
+            history.record("database", health, datetime(now))
+        };
+        assert_eq!(
+            started_at,
+            record(&mut history, ComponentHealth::Ok, "2023-03-13 10:00")
+        );
+        assert_eq!(
+            datetime("2023-03-13 11:00"),
+            record(&mut history, ComponentHealth::Down, "2023-03-13 11:00")
+        );
+        assert_eq!(
+            datetime("2023-03-13 11:00"),
+            record(&mut history, ComponentHealth::Down, "2023-03-13 11:05")
+        );
+        assert_eq!(
+            datetime("2023-03-13 11:10"),
+            record(&mut history, ComponentHealth::Ok, "2023-03-13 11:10")
+        );
+    }
+
+    #[test]
+    fn health_urls() {
+        assert_eq!(
+            vec![
+                (
+                    "telegram_bot".to_owned(),
+                    "http://app_telegram_bot:8080/v1/health".to_owned()
+                ),
+                (
+                    "vk_bot".to_owned(),
+                    "http://app_vk_bot:8080/v1/health".to_owned()
+                ),
+            ],
+            parse_health_urls(
+                "telegram_bot=http://app_telegram_bot:8080/v1/health, vk_bot=http://app_vk_bot:8080/v1/health,broken"
+            )
+        );
+        assert!(parse_health_urls("").is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::{DateTime, Datelike, Days, Local, Month, NaiveDate, TimeZone, Weekday};
+use domain_schedule_models::{PeriodType, WeekNumberProvenance, WeekNumberSource};
 use domain_schedule_shift::{ScheduleShift, ShiftRule, ShiftedSemester, Year};
 use std::cmp::Ordering;
 
@@ -43,6 +44,21 @@ pub trait NaiveDateExt {
     where
         Self: Sized;
 
+    /// Same as [NaiveDateExt::week_of_semester], but also tells
+    /// which shift rule or default algorithm branch produced the week number.
+    fn week_number_provenance(self, shifts: Option<&ScheduleShift>) -> WeekNumberProvenance
+    where
+        Self: Sized;
+
+    /// Get period of the academic year.
+    ///
+    /// Study weeks are the same as in [NaiveDateExt::week_of_semester],
+    /// the session lasts `session_weeks` weeks right after the 17th week of the semester,
+    /// all other days (including the January weekend) are vacation.
+    fn period_type(self, shifts: Option<&ScheduleShift>, session_weeks: u8) -> PeriodType
+    where
+        Self: Sized;
+
     fn is_past_week(&self) -> bool
     where
         Self: Sized;
@@ -54,26 +70,81 @@ impl NaiveDateExt for NaiveDate {
     }
 
     fn week_of_semester(self, shifts: Option<&ScheduleShift>) -> Option<WeekOfSemester> {
+        match self.week_number_provenance(shifts).week_of_semester {
+            Some(week) => Some(WeekOfSemester::Studying(week)),
+            None => Some(WeekOfSemester::NonStudying),
+        }
+    }
+
+    fn week_number_provenance(self, shifts: Option<&ScheduleShift>) -> WeekNumberProvenance {
+        let provenance = WeekNumberProvenance {
+            date: self,
+            week_of_semester: None,
+            source: WeekNumberSource::OutOfSemester,
+            semester: None,
+            first_day: None,
+            first_week_number: None,
+        };
         if let (1, 1..=7) = (self.month(), self.day()) {
             // January weekend
-            return Some(WeekOfSemester::NonStudying);
+            return WeekNumberProvenance {
+                source: WeekNumberSource::JanuaryWeekend,
+                ..provenance
+            };
         }
-        vec![ShiftedSemester::Spring, ShiftedSemester::Fall]
+        let studying_week = vec![ShiftedSemester::Spring, ShiftedSemester::Fall]
             .into_iter()
-            .filter_map(|semester| get_first_day_and_week_number(&self, shifts, semester))
-            .filter_map(|(first_day, week_number)| {
-                let has_zero_week = week_number == 0;
-                let result =
-                    self.week_of_year() as i8 - first_day.week_of_year() as i8 + week_number;
+            .filter_map(|semester| get_semester_start(self.year(), shifts, semester))
+            .filter_map(|start| {
+                let has_zero_week = start.week_number == 0;
+                let result = self.week_of_year() as i8 - start.first_day.week_of_year() as i8
+                    + start.week_number;
                 match (result, has_zero_week) {
-                    (0..=17, true) => Some(result as u8),
-                    (1..=17, false) => Some(result as u8),
+                    (0..=17, true) => Some((result as u8, start)),
+                    (1..=17, false) => Some((result as u8, start)),
                     _ => None,
                 }
             })
-            .min()
-            .map(WeekOfSemester::Studying)
-            .or(Some(WeekOfSemester::NonStudying))
+            .min_by_key(|(week, _)| *week);
+        match studying_week {
+            Some((week, start)) => WeekNumberProvenance {
+                week_of_semester: Some(week),
+                source: start.source,
+                semester: Some(start.semester.to_string()),
+                first_day: Some(start.first_day),
+                first_week_number: Some(start.week_number),
+                ..provenance
+            },
+            None => provenance,
+        }
+    }
+
+    fn period_type(self, shifts: Option<&ScheduleShift>, session_weeks: u8) -> PeriodType {
+        match self.week_of_semester(shifts) {
+            Some(WeekOfSemester::Studying(_)) => return PeriodType::Study,
+            _ if matches!((self.month(), self.day()), (1, 1..=7)) => return PeriodType::Vacation,
+            _ => (),
+        }
+        let week_start = self.week(Weekday::Mon).first_day();
+        // the fall semester session usually takes place in January of the next year
+        let semesters = [
+            (self.year() - 1, ShiftedSemester::Fall),
+            (self.year(), ShiftedSemester::Spring),
+            (self.year(), ShiftedSemester::Fall),
+        ];
+        let is_session = semesters
+            .into_iter()
+            .filter_map(|(year, semester)| get_semester_start(year, shifts, semester))
+            .any(|start| {
+                let first_week_start = start.first_day.week(Weekday::Mon).first_day();
+                let week = (week_start - first_week_start).num_weeks() + start.week_number as i64;
+                (18..18 + session_weeks as i64).contains(&week)
+            });
+        if is_session {
+            PeriodType::Session
+        } else {
+            PeriodType::Vacation
+        }
     }
 
     fn is_past_week(&self) -> bool {
@@ -83,15 +154,22 @@ impl NaiveDateExt for NaiveDate {
     }
 }
 
-fn get_first_day_and_week_number(
-    now: &NaiveDate,
+/// The first study day of the semester and the rule it was determined by
+struct SemesterStart {
+    semester: ShiftedSemester,
+    first_day: NaiveDate,
+    week_number: i8,
+    source: WeekNumberSource,
+}
+
+fn get_semester_start(
+    year: i32,
     shifts: Option<&ScheduleShift>,
     semester: ShiftedSemester,
-) -> Option<(NaiveDate, i8)> {
+) -> Option<SemesterStart> {
     // look for 'shift' rule for this semester
     // in case the first study day is determined by non-standard rules
-    let shift_rule_for_semester =
-        shifts.and_then(|it| it.get(Year::new(now.year()), semester.clone()));
+    let shift_rule_for_semester = shifts.and_then(|it| it.get(Year::new(year), semester.clone()));
 
     if let Some(ShiftRule {
         first_day,
@@ -99,29 +177,45 @@ fn get_first_day_and_week_number(
     }) = shift_rule_for_semester
     {
         // default number for first study week is 1, but we can provide any
-        Some((*first_day, week_number.unwrap_or(1)))
+        Some(SemesterStart {
+            semester,
+            first_day: *first_day,
+            week_number: week_number.unwrap_or(1),
+            source: WeekNumberSource::ShiftRule,
+        })
     } else {
-        let first_day = match semester {
+        let (first_day, source) = match semester {
             // first of September if it is not Sunday, either 2nd of September
             ShiftedSemester::Fall => {
                 let first_of_september =
-                    NaiveDate::from_ymd_opt(now.year(), Month::September.number_from_month(), 1)?;
+                    NaiveDate::from_ymd_opt(year, Month::September.number_from_month(), 1)?;
                 if matches!(first_of_september.weekday(), Weekday::Sun) {
                     // return 2nd of September (Monday)
-                    NaiveDate::from_ymd_opt(now.year(), Month::September.number_from_month(), 2)?
+                    (
+                        NaiveDate::from_ymd_opt(year, Month::September.number_from_month(), 2)?,
+                        WeekNumberSource::SecondOfSeptember,
+                    )
                 } else {
-                    first_of_september
+                    (first_of_september, WeekNumberSource::FirstOfSeptember)
                 }
             }
             // first monday of February
-            ShiftedSemester::Spring => NaiveDate::from_weekday_of_month_opt(
-                now.year(),
-                Month::February.number_from_month(),
-                Weekday::Mon,
-                1,
-            )?,
+            ShiftedSemester::Spring => (
+                NaiveDate::from_weekday_of_month_opt(
+                    year,
+                    Month::February.number_from_month(),
+                    Weekday::Mon,
+                    1,
+                )?,
+                WeekNumberSource::FirstMondayOfFebruary,
+            ),
         };
-        Some((first_day, 1))
+        Some(SemesterStart {
+            semester,
+            first_day,
+            week_number: 1,
+            source,
+        })
     }
 }
 
@@ -130,6 +224,7 @@ mod tests {
     use std::{fmt::Display, str::FromStr};
 
     use chrono::{Days, Month, NaiveDate};
+    use domain_schedule_models::{PeriodType, WeekNumberSource};
     use domain_schedule_shift::ScheduleShift;
     use lazy_static::lazy_static;
 
@@ -288,6 +383,142 @@ mod tests {
         result = WeekOfSemester::Studying(1)
     );
 
+    macro_rules! test_period_type {
+        ($name:tt, date = ($y:expr, $m:expr, $d:expr), shift = $sh:expr, result = $res:expr) => {
+            #[test]
+            fn $name() {
+                let date = NaiveDate::from_ymd_opt($y, $m.number_from_month(), $d).unwrap();
+                assert_eq!(date.period_type($sh, 4), $res);
+            }
+        };
+    }
+
+    test_period_type!(
+        december_21st_2020_study_without_shifts,
+        date = (2020, Month::December, 21),
+        shift = None,
+        result = PeriodType::Study
+    );
+
+    test_period_type!(
+        december_28th_2020_session_without_shifts,
+        date = (2020, Month::December, 28),
+        shift = None,
+        result = PeriodType::Session
+    );
+
+    test_period_type!(
+        january_5th_2021_vacation_without_shifts,
+        date = (2021, Month::January, 5),
+        shift = None,
+        result = PeriodType::Vacation
+    );
+
+    test_period_type!(
+        january_11th_2021_session_without_shifts,
+        date = (2021, Month::January, 11),
+        shift = None,
+        result = PeriodType::Session
+    );
+
+    test_period_type!(
+        june_1st_2020_session_without_shifts,
+        date = (2020, Month::June, 1),
+        shift = None,
+        result = PeriodType::Session
+    );
+
+    test_period_type!(
+        july_20th_2020_vacation_without_shifts,
+        date = (2020, Month::July, 20),
+        shift = None,
+        result = PeriodType::Vacation
+    );
+
+    test_period_type!(
+        june_14th_2021_study_with_shift,
+        date = (2021, Month::June, 14),
+        shift = Some(&TEST_SHIFTS),
+        result = PeriodType::Study
+    );
+
+    test_period_type!(
+        june_21st_2021_session_with_shift,
+        date = (2021, Month::June, 21),
+        shift = Some(&TEST_SHIFTS),
+        result = PeriodType::Session
+    );
+
+    test_period_type!(
+        january_17th_2022_session_with_shift,
+        date = (2022, Month::January, 17),
+        shift = Some(&TEST_SHIFTS),
+        result = PeriodType::Session
+    );
+
+    test_period_type!(
+        january_31st_2022_vacation_with_shift,
+        date = (2022, Month::January, 31),
+        shift = Some(&TEST_SHIFTS),
+        result = PeriodType::Vacation
+    );
+
+    macro_rules! test_week_number_source {
+        ($name:tt, date = ($y:expr, $m:expr, $d:expr), shift = $sh:expr, result = ($week:expr, $src:expr)) => {
+            #[test]
+            fn $name() {
+                let date = NaiveDate::from_ymd_opt($y, $m.number_from_month(), $d).unwrap();
+                let provenance = date.week_number_provenance($sh);
+                assert_eq!(
+                    (provenance.week_of_semester, provenance.source),
+                    ($week, $src)
+                );
+            }
+        };
+    }
+
+    test_week_number_source!(
+        september_2nd_2019_second_of_september,
+        date = (2019, Month::September, 2),
+        shift = None,
+        result = (Some(1), WeekNumberSource::SecondOfSeptember)
+    );
+
+    test_week_number_source!(
+        september_9th_2020_first_of_september,
+        date = (2020, Month::September, 9),
+        shift = None,
+        result = (Some(2), WeekNumberSource::FirstOfSeptember)
+    );
+
+    test_week_number_source!(
+        february_10th_2020_first_monday_of_february,
+        date = (2020, Month::February, 10),
+        shift = None,
+        result = (Some(2), WeekNumberSource::FirstMondayOfFebruary)
+    );
+
+    test_week_number_source!(
+        february_22nd_2021_shift_rule,
+        date = (2021, Month::February, 22),
+        shift = Some(&TEST_SHIFTS),
+        result = (Some(1), WeekNumberSource::ShiftRule)
+    );
+
+    test_week_number_source!(
+        january_5th_2021_january_weekend,
+        date = (2021, Month::January, 5),
+        shift = Some(&TEST_SHIFTS),
+        result = (None, WeekNumberSource::JanuaryWeekend)
+    );
+
+    test_week_number_source!(
+        july_20th_2020_out_of_semester,
+        date = (2020, Month::July, 20),
+        shift = None,
+        result = (None, WeekNumberSource::OutOfSemester)
+    );
+
     #[test]
     fn test_all_days_from_2019_to_2025() {
         let mut date =
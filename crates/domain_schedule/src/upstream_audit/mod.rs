@@ -0,0 +1,125 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, NaiveDateTime};
+use log::debug;
+use reqwest::{Request, Response, Url};
+use restix::{BoxFuture, CallTimings, Interceptor, Next};
+
+use self::repository::UpstreamAuditRepository;
+
+pub mod repository;
+
+/// Single call to the MPEI backend
+pub struct UpstreamCall {
+    pub at: NaiveDateTime,
+    pub endpoint: String,
+    /// `0` if the call failed without a response
+    pub status: u16,
+    pub latency: Duration,
+    /// By `Content-Length` header, `0` if it is missing
+    pub bytes: u64,
+}
+
+/// Audit hook of [crate::mpei_api::MpeiApi]: records every call to the [UpstreamAuditRepository].
+///
+/// Api instances have the only interceptor, so the other one (e.g. record/replay)
+/// can be wrapped with `inner`.
+pub struct UpstreamAuditInterceptor {
+    repository: Arc<UpstreamAuditRepository>,
+    inner: Option<Arc<dyn Interceptor>>,
+}
+
+impl UpstreamAuditInterceptor {
+    pub fn new(
+        repository: Arc<UpstreamAuditRepository>,
+        inner: Option<Arc<dyn Interceptor>>,
+    ) -> Self {
+        Self { repository, inner }
+    }
+}
+
+impl Interceptor for UpstreamAuditInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<Response>> {
+        let endpoint = endpoint_of(request.url());
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let response = match &self.inner {
+                Some(inner) => inner.intercept(request, next).await,
+                None => next.run(request).await,
+            };
+            let (status, bytes) = match &response {
+                Ok(response) => (
+                    response.status().as_u16(),
+                    response.content_length().unwrap_or_default(),
+                ),
+                Err(e) => (e.status().map(|it| it.as_u16()).unwrap_or_default(), 0),
+            };
+            let latency = started_at.elapsed();
+            common_metrics::record_mpei_request(&endpoint, status, latency);
+            self.repository.record(UpstreamCall {
+                at: Local::now().naive_local(),
+                endpoint,
+                status,
+                latency,
+                bytes,
+            });
+            response
+        })
+    }
+}
+
+/// Timings of the calls to the MPEI backend (`GATEWAY_CALL_TIMINGS=true`), including the retries,
+/// to tell the network issues (slow DNS, new connections) from the slow MPEI server
+pub fn record_call_timings(timings: &CallTimings) {
+    debug!(
+        "MPEI call {} {}: status {:?}, dns {:?}, first byte {:?}",
+        timings.method,
+        endpoint_of(&timings.url),
+        timings.status.map(|it| it.as_u16()),
+        timings.dns,
+        timings.first_byte,
+    );
+    common_metrics::record_mpei_request_phases(timings.dns, timings.first_byte);
+}
+
+/// Path of the request without ids and query, e.g. `/api/schedule/group/{id}`,
+/// so the calls for the different schedules have the same endpoint
+fn endpoint_of(url: &Url) -> String {
+    url.path()
+        .split('/')
+        .map(|segment| match segment.parse::<i64>() {
+            Ok(_) => "{id}",
+            Err(_) => segment,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Url;
+
+    use super::endpoint_of;
+
+    #[test]
+    fn test_endpoint_of() {
+        let endpoint = |url: &str| endpoint_of(&Url::parse(url).unwrap());
+        assert_eq!(
+            "/api/schedule/group/{id}",
+            endpoint(
+                "http://ts.mpei.ru/api/schedule/group/12345?start=2023.03.13&finish=2023.03.19"
+            )
+        );
+        assert_eq!(
+            "/api/search",
+            endpoint("http://ts.mpei.ru/api/search?term=%D0%90-08-19&type=group")
+        );
+    }
+}
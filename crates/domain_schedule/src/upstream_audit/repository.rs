@@ -0,0 +1,202 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Context;
+use chrono::{NaiveDateTime, Timelike};
+use common_database::Database;
+use domain_schedule_models::UpstreamCallStats;
+use log::info;
+use tokio_postgres::Row;
+
+use super::UpstreamCall;
+
+/// Repository for accessing table `upstream_audit` of the mpeix database.
+///
+/// Table contains hourly stats of the calls to the MPEI backend by endpoint and status,
+/// which are needed to negotiate the rate limits with the university IT department.
+/// Calls are aggregated in memory and saved to the table with [Self::flush].
+pub struct UpstreamAuditRepository {
+    database: Arc<Database>,
+    pending: Mutex<HashMap<UpstreamCallKey, PendingStats>>,
+}
+
+/// Helper struct for [UpstreamAuditRepository]:
+/// Hour, endpoint and status of the calls
+#[derive(Hash, PartialEq, Eq)]
+struct UpstreamCallKey(NaiveDateTime, String, u16);
+
+/// Helper struct for [UpstreamAuditRepository]:
+/// Stats of the calls not saved yet
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PendingStats {
+    count: i64,
+    total_latency_ms: i64,
+    max_latency_ms: i64,
+    total_bytes: i64,
+}
+
+impl PendingStats {
+    fn merge(&mut self, other: PendingStats) {
+        self.count += other.count;
+        self.total_latency_ms += other.total_latency_ms;
+        self.max_latency_ms = self.max_latency_ms.max(other.max_latency_ms);
+        self.total_bytes += other.total_bytes;
+    }
+}
+
+impl UpstreamAuditRepository {
+    pub fn new(database: Arc<Database>) -> Self {
+        Self {
+            database,
+            pending: Default::default(),
+        }
+    }
+
+    pub async fn init_upstream_audit_db(&self) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        let stmt = include_str!("../../sql/create_upstream_audit.pgsql");
+        client
+            .query(stmt, &[])
+            .await
+            .with_context(|| "Error during table 'upstream_audit' creation")?;
+        info!("Table 'upstream_audit' initialization passed successfully");
+        Ok(())
+    }
+
+    pub fn record(&self, call: UpstreamCall) {
+        let hour = hour_of(call.at);
+        let latency_ms = call.latency.as_millis() as i64;
+        self.pending
+            .lock()
+            .unwrap()
+            .entry(UpstreamCallKey(hour, call.endpoint, call.status))
+            .or_default()
+            .merge(PendingStats {
+                count: 1,
+                total_latency_ms: latency_ms,
+                max_latency_ms: latency_ms,
+                total_bytes: call.bytes as i64,
+            });
+    }
+
+    /// Add the stats aggregated in memory to the table.
+    /// If saving fails, unsaved stats are kept for the next attempt.
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let mut entries = pending.into_iter();
+        let result = self.save(&mut entries).await;
+        let mut pending = self.pending.lock().unwrap();
+        for (key, stats) in entries {
+            pending.entry(key).or_default().merge(stats);
+        }
+        result
+    }
+
+    /// Save entries one by one, leaving unsaved ones in the iterator on error
+    async fn save(
+        &self,
+        entries: &mut impl Iterator<Item = (UpstreamCallKey, PendingStats)>,
+    ) -> anyhow::Result<()> {
+        let client = self.database.write().get().await?;
+        for (UpstreamCallKey(hour, endpoint, status), stats) in entries.by_ref() {
+            let stmt = include_str!("../../sql/upsert_upstream_audit.pgsql")
+                .replace("$hour", &hour.to_string())
+                .replace("$endpoint", &endpoint)
+                .replace("$status", &status.to_string())
+                .replace("$count", &stats.count.to_string())
+                .replace("$total_latency_ms", &stats.total_latency_ms.to_string())
+                .replace("$max_latency_ms", &stats.max_latency_ms.to_string())
+                .replace("$total_bytes", &stats.total_bytes.to_string());
+            if let Err(e) = client.query(&stmt, &[]).await {
+                // the failed entry is already taken from the iterator
+                self.pending
+                    .lock()
+                    .unwrap()
+                    .entry(UpstreamCallKey(hour, endpoint, status))
+                    .or_default()
+                    .merge(stats);
+                return Err(e).with_context(|| "Error while saving upstream audit to db");
+            }
+        }
+        Ok(())
+    }
+
+    /// Get hourly stats of the last `hours` hours, the latest hours go first
+    pub async fn get_hourly_stats(&self, hours: u32) -> anyhow::Result<Vec<UpstreamCallStats>> {
+        let stmt = include_str!("../../sql/select_upstream_audit.pgsql")
+            .replace("$hours", &hours.to_string());
+        let client = self.database.read().get().await?;
+        Ok(client
+            .query(&stmt, &[])
+            .await
+            .with_context(|| "Error while getting upstream audit from db")?
+            .iter()
+            .filter_map(map_from_db_model)
+            .collect())
+    }
+}
+
+/// Start of the hour of the call
+fn hour_of(at: NaiveDateTime) -> NaiveDateTime {
+    at.with_minute(0)
+        .and_then(|it| it.with_second(0))
+        .and_then(|it| it.with_nanosecond(0))
+        .unwrap_or(at)
+}
+
+fn map_from_db_model(row: &Row) -> Option<UpstreamCallStats> {
+    let count: i64 = row.try_get("count").ok()?;
+    let total_latency_ms: i64 = row.try_get("total_latency_ms").ok()?;
+    Some(UpstreamCallStats {
+        hour: NaiveDateTime::parse_from_str(row.try_get("hour").ok()?, "%Y-%m-%dT%H:%M:%S").ok()?,
+        endpoint: row.try_get("endpoint").ok()?,
+        status: row.try_get::<_, i32>("status").ok()? as u16,
+        count,
+        avg_latency_ms: total_latency_ms.checked_div(count).unwrap_or_default(),
+        max_latency_ms: row.try_get("max_latency_ms").ok()?,
+        total_bytes: row.try_get("total_bytes").ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::{hour_of, PendingStats};
+
+    #[test]
+    fn test_hour_of() {
+        let datetime = |s| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(
+            datetime("2026-10-16 12:00:00"),
+            hour_of(datetime("2026-10-16 12:59:31"))
+        );
+    }
+
+    #[test]
+    fn test_merge_pending_stats() {
+        let mut stats = PendingStats::default();
+        for (latency_ms, bytes) in [(120, 1000), (480, 3000), (300, 0)] {
+            stats.merge(PendingStats {
+                count: 1,
+                total_latency_ms: latency_ms,
+                max_latency_ms: latency_ms,
+                total_bytes: bytes,
+            });
+        }
+        assert_eq!(
+            PendingStats {
+                count: 3,
+                total_latency_ms: 900,
+                max_latency_ms: 480,
+                total_bytes: 4000,
+            },
+            stats
+        );
+    }
+}
@@ -1,20 +1,50 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use anyhow::{anyhow, ensure, Context};
-use chrono::{Local, NaiveDate, Weekday};
+use chrono::{
+    Datelike, Duration as ChronoDuration, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike,
+    Weekday,
+};
 use common_errors::errors::{CommonError, CommonErrorExt};
+use common_events::EventBus;
+use common_rust::{env, Reloadable};
+use common_sql::{Page, Paged, Sort};
 use domain_schedule_cooldown::ScheduleCooldownRepository;
-use domain_schedule_models::{Schedule, ScheduleSearchResult, ScheduleType};
+use domain_schedule_models::{
+    ClassesStatus, ComponentHealth, ComponentStatus, DayOverride, FreeRoom, FreeRooms, PeriodType,
+    Schedule, ScheduleAutocompleteItem, ScheduleCacheDiff, ScheduleChangelog, ScheduleDiff,
+    ScheduleFetchLatency, ScheduleId, ScheduleQuality, ScheduleSearchResult, ScheduleType,
+    ScheduleVerification, ServiceStatus, SessionSchedule, ShiftRules, TrendingSchedules,
+    UpstreamCallStats,
+};
+use futures_util::{stream, StreamExt};
 use lazy_static::lazy_static;
 use log::{debug, info, warn};
+use tokio::sync::Mutex;
 
 use crate::{
+    changes::repository::ScheduleChangeRepository,
     dto::mpeix::{ScheduleName, ScheduleSearchQuery},
+    events::{ScheduleChanged, ScheduleRequested},
     id::repository::ScheduleIdRepository,
-    schedule::repository::ScheduleRepository,
+    overrides::{merge::apply_overrides, repository::ScheduleOverrideRepository},
+    popularity::repository::{SchedulePopularityRepository, KEEP_DAILY_POPULARITY_DAYS},
+    quality::repository::QUALITY_SORT_COLUMNS,
+    quality::{repository::ScheduleQualityRepository, validation::validate_schedule},
+    schedule::{
+        diff::{diff_schedules, diff_weeks},
+        latency::{FetchLatencyStats, FetchSource},
+        mapping::mark_days_before_semester_start,
+        repository::ScheduleRepository,
+    },
     schedule_shift::repository::ScheduleShiftRepository,
     search::repository::ScheduleSearchRepository,
+    status::repository::ServiceStatusRepository,
     time::{DateTimeExt, NaiveDateExt, WeekOfSemester},
+    upstream_audit::repository::UpstreamAuditRepository,
 };
 
 /// Get numeric `ID` of schedule by its `name` and `type`.
@@ -27,7 +57,7 @@ pub struct GetScheduleIdUseCase(pub(crate) Arc<ScheduleIdRepository>);
 impl GetScheduleIdUseCase {
     /// Get numeric `ID` of schedule by its `name` and `type`.
     /// See [GetScheduleIdUseCase] description.
-    pub async fn get_id(&self, name: String, r#type: ScheduleType) -> anyhow::Result<i64> {
+    pub async fn get_id(&self, name: String, r#type: ScheduleType) -> anyhow::Result<ScheduleId> {
         let name = ScheduleName::new(name, r#type.clone())?;
         self.0.get_id(name, r#type).await
     }
@@ -36,6 +66,55 @@ impl GetScheduleIdUseCase {
 lazy_static! {
     static ref MAX_OFFSET: i32 = i32::MAX / 7;
     static ref MIN_OFFSET: i32 = i32::MIN / 7;
+    /// Cached weeks younger than this are not compared with MPEI, so the public diff
+    /// endpoint cannot be used to flood MPEI with requests
+    static ref DIFF_MIN_CACHE_AGE_SECONDS: i64 =
+        env::get_parsed_or("SCHEDULE_DIFF_MIN_CACHE_AGE_SECONDS", 300);
+}
+
+/// How many weeks ahead the nearest exam session is looked for, half a year is enough
+/// to find the session of the current semester from any of its weeks
+const SESSION_LOOKAHEAD_WEEKS: i32 = 26;
+
+/// How the current week schedule is fetched by [GetScheduleUseCase]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScheduleFetchStrategy {
+    /// Read the cache, then request remote if the cache is missing or expired
+    Sequential,
+    /// Read the cache and request remote concurrently,
+    /// expired cache waits for remote not longer than `remote_timeout`
+    Race { remote_timeout: Duration },
+}
+
+impl Default for ScheduleFetchStrategy {
+    fn default() -> Self {
+        if env::get_parsed_or("SCHEDULE_FETCH_RACE", false) {
+            Self::Race {
+                remote_timeout: Duration::from_millis(env::get_parsed_or(
+                    "SCHEDULE_FETCH_RACE_TIMEOUT_MS",
+                    1500,
+                )),
+            }
+        } else {
+            Self::Sequential
+        }
+    }
+}
+
+impl AsRef<str> for ScheduleFetchStrategy {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Sequential => "sequential",
+            Self::Race { .. } => "race",
+        }
+    }
+}
+
+/// Cache entry of the week, read by [ScheduleFetchStrategy::Race]
+enum CacheRead {
+    Fresh(Schedule),
+    Stale(Schedule),
+    Miss,
 }
 
 /// Get [Schedule] model by schedule `name`, `type`, and `offset`.
@@ -49,14 +128,32 @@ lazy_static! {
 /// This UseCase is maximally cache-friendly.
 /// It returns even expired cache entries in cases when remote is unavailable.
 ///
+/// Each request is counted in [SchedulePopularityRepository] for further schedule prefetching.
+/// Each schedule received from remote is validated, warnings are saved to [ScheduleQualityRepository].
+/// Active manual overrides from [ScheduleOverrideRepository] are merged over the schedule
+/// (after the cache, so overrides are applied and expire immediately).
+/// Differences between the fresh remote schedule and the replaced cached one
+/// are saved to [ScheduleChangeRepository].
+///
+/// The current week is fetched with the configured [ScheduleFetchStrategy],
+/// its latency is measured by the source of the response.
+///
+/// Requests of the users and detected changes are published to the [EventBus]
+/// as [ScheduleRequested] and [ScheduleChanged] events, see [ScheduleEventsUseCase].
+///
 /// This UseCase uses injected singleton instances of [ScheduleIdRepository],
-/// [ScheduleRepository] and [ScheduleShiftRepository].
-/// Check [crate::di] module for details.
+/// [ScheduleRepository], [ScheduleShiftRepository], [ScheduleQualityRepository],
+/// [ScheduleOverrideRepository] and [EventBus]. Check [crate::di] module for details.
 pub struct GetScheduleUseCase {
     pub(crate) schedule_id_repository: Arc<ScheduleIdRepository>,
     pub(crate) schedule_repository: Arc<ScheduleRepository>,
     pub(crate) schedule_shift_repository: Arc<ScheduleShiftRepository>,
     pub(crate) schedule_cooldown_repository: Arc<ScheduleCooldownRepository>,
+    pub(crate) schedule_quality_repository: Arc<ScheduleQualityRepository>,
+    pub(crate) schedule_override_repository: Arc<ScheduleOverrideRepository>,
+    pub(crate) event_bus: Arc<EventBus>,
+    pub(crate) fetch_strategy: Reloadable<ScheduleFetchStrategy>,
+    pub(crate) fetch_latency: FetchLatencyStats,
 }
 
 impl GetScheduleUseCase {
@@ -67,46 +164,317 @@ impl GetScheduleUseCase {
         name: String,
         r#type: ScheduleType,
         offset: i32,
+    ) -> anyhow::Result<Schedule> {
+        let mut schedule = self
+            .get_schedule_from_cache_or_remote(name, r#type, offset, true)
+            .await?;
+        self.complete_schedule(&mut schedule).await?;
+        Ok(schedule)
+    }
+
+    /// The same as [GetScheduleUseCase::get_schedule], but the request is not counted
+    /// in the schedule popularity, because it is not requested by the user directly,
+    /// e.g. the schedules of all rooms aggregated by [FindFreeRoomsUseCase].
+    pub async fn get_schedule_for_aggregation(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<Schedule> {
+        let mut schedule = self
+            .get_schedule_from_cache_or_remote(name, r#type, offset, false)
+            .await?;
+        self.complete_schedule(&mut schedule).await?;
+        Ok(schedule)
+    }
+
+    /// The same as [GetScheduleUseCase::get_schedule], but the cache is not read:
+    /// the schedule is always fetched from remote and put into the cache.
+    /// Useful right after MPEI publishes corrections. Remote errors are returned as is.
+    pub async fn get_fresh_schedule(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<Schedule> {
+        info!("Force refresh of schedule (name='{name}', type='{type}', offset={offset})");
+        ensure!(offset < *MAX_OFFSET, CommonError::user("Too large offset"));
+        ensure!(offset > *MIN_OFFSET, CommonError::user("Too small offset"));
+        let name = ScheduleName::new(name, r#type.clone())?;
+        let mut schedule = self.refresh_schedule(name, r#type, offset).await?;
+        self.complete_schedule(&mut schedule).await?;
+        Ok(schedule)
+    }
+
+    /// Get exams and consultations of the nearest exam session,
+    /// or of the current one if the session is in progress.
+    /// Session weeks are found by their period type and requested like the regular weeks,
+    /// so they are cached and counted in the popularity the same way.
+    pub async fn get_session_schedule(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+    ) -> anyhow::Result<SessionSchedule> {
+        let mut offsets = Vec::new();
+        for offset in 0..SESSION_LOOKAHEAD_WEEKS {
+            let week_start = get_week_start(offset)?;
+            match self
+                .schedule_shift_repository
+                .get_period_type(&week_start)
+                .await?
+            {
+                PeriodType::Session => offsets.push(offset),
+                _ if !offsets.is_empty() => break,
+                _ => {}
+            }
+        }
+        debug!("Session of schedule (name='{name}', type='{type}') is at offsets {offsets:?}");
+
+        let mut session_name = name;
+        let mut weeks = Vec::with_capacity(offsets.len());
+        for (index, offset) in offsets.into_iter().enumerate() {
+            // the whole session is requested by the user once
+            let mut schedule = self
+                .get_schedule_from_cache_or_remote(
+                    session_name.clone(),
+                    r#type.clone(),
+                    offset,
+                    index == 0,
+                )
+                .await?;
+            self.complete_schedule(&mut schedule).await?;
+            session_name = schedule.name;
+            weeks.extend(schedule.weeks);
+        }
+        Ok(SessionSchedule::from_weeks(session_name, r#type, weeks))
+    }
+
+    /// Add the data which is not cached to the cached or remote schedule
+    async fn complete_schedule(&self, schedule: &mut Schedule) -> anyhow::Result<()> {
+        // period type is not cached, because it depends on the session config
+        for week in schedule.weeks.iter_mut() {
+            week.period_type = self
+                .schedule_shift_repository
+                .get_period_type(&week.first_day_of_week)
+                .await?;
+            if let Some(first_day) = self
+                .schedule_shift_repository
+                .get_semester_start_within_week(&week.first_day_of_week)
+                .await?
+            {
+                mark_days_before_semester_start(week, first_day);
+            }
+        }
+        // overrides go after the marks, e.g. the orientation day before the first study day
+        self.apply_overrides(schedule).await;
+        Ok(())
+    }
+
+    async fn apply_overrides(&self, schedule: &mut Schedule) {
+        let (Some(first_week), Some(last_week)) = (schedule.weeks.first(), schedule.weeks.last())
+        else {
+            return;
+        };
+        let (from, to) = (
+            first_week.first_day_of_week,
+            last_week.first_day_of_week + ChronoDuration::days(6),
+        );
+        let Ok(name) = ScheduleName::new(schedule.name.clone(), schedule.r#type.clone()) else {
+            return;
+        };
+        // overrides are optional, the schedule from MPEI is better than nothing
+        match self
+            .schedule_override_repository
+            .get_active_overrides(&name, &schedule.r#type, from, to)
+            .await
+        {
+            Ok(overrides) => apply_overrides(schedule, overrides),
+            Err(e) => warn!("Cannot get schedule overrides: {e}"),
+        }
+    }
+
+    async fn get_schedule_from_cache_or_remote(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+        record_popularity: bool,
     ) -> anyhow::Result<Schedule> {
         debug!("GetScheduleUseCase(name='{name}', type='{type}', offset={offset})");
         ensure!(offset < *MAX_OFFSET, CommonError::user("Too large offset"));
         ensure!(offset > *MIN_OFFSET, CommonError::user("Too small offset"));
 
         let name = ScheduleName::new(name, r#type.clone())?;
-        let week_start = Local::now()
-            .with_days_offset(offset * 7)
-            .map(|dt| dt.date_naive())
-            .map(|dt| dt.week(Weekday::Mon).first_day())
-            .ok_or_else(|| anyhow!(CommonError::user("Invalid week offset")))?;
-        let week_of_semester = self
-            .schedule_shift_repository
-            .get_week_of_semester(&week_start)
-            .await?;
+        if record_popularity {
+            self.event_bus.publish(ScheduleRequested {
+                name: name.to_owned(),
+                r#type: r#type.to_owned(),
+            });
+        }
+        let (week_start, week_of_semester) =
+            self.get_week_start_and_week_of_semester(offset).await?;
         // Always ignore expiration policy for past weeks
         // and also in case of active "cooldown"
         let ignore_expiration = week_start.is_past_week()
             || self.schedule_cooldown_repository.is_cooldown_active().await;
 
+        // only the current week is measured, it is the most requested one
+        if offset != 0 {
+            return self
+                .fetch_sequentially(
+                    &name,
+                    &r#type,
+                    week_start,
+                    &week_of_semester,
+                    ignore_expiration,
+                )
+                .await
+                .map(|(schedule, _)| schedule);
+        }
+        let started_at = Instant::now();
+        let result = match **self.fetch_strategy.load() {
+            // there is no remote to race with during the cooldown
+            ScheduleFetchStrategy::Race { remote_timeout } if !ignore_expiration => {
+                self.fetch_racing(
+                    &name,
+                    &r#type,
+                    week_start,
+                    &week_of_semester,
+                    remote_timeout,
+                )
+                .await
+            }
+            _ => {
+                self.fetch_sequentially(
+                    &name,
+                    &r#type,
+                    week_start,
+                    &week_of_semester,
+                    ignore_expiration,
+                )
+                .await
+            }
+        };
+        let source = result.as_ref().map_or(FetchSource::Error, |(_, it)| *it);
+        self.fetch_latency.record(source, started_at.elapsed());
+        result.map(|(schedule, _)| schedule)
+    }
+
+    /// Latency of the current week requests by the source of the response,
+    /// to compare [ScheduleFetchStrategy] variants
+    pub fn fetch_latency(&self) -> ScheduleFetchLatency {
+        let fetch_strategy = **self.fetch_strategy.load();
+        self.fetch_latency.report(fetch_strategy.as_ref())
+    }
+
+    /// Read the cache first, request remote only if the cache is missing or expired
+    async fn fetch_sequentially(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        week_of_semester: &WeekOfSemester,
+        ignore_expiration: bool,
+    ) -> anyhow::Result<(Schedule, FetchSource)> {
         // try to get schedule from cache first
         if let Some(schedule) = self
             .get_schedule_from_cache(
-                &name,
-                &r#type,
+                name,
+                r#type,
                 week_start,
-                &week_of_semester,
+                week_of_semester,
                 ignore_expiration,
             )
             .await?
         {
-            return Ok(schedule);
+            return Ok((schedule, FetchSource::Cache));
         }
 
         // Trying to get schedule id from remote, do not return error in case of error
         // remember error to process it in next steps
         let remote = self
-            .get_schedule_from_remote(&name, &r#type, week_start, &week_of_semester)
+            .get_schedule_from_remote(name, r#type, week_start, week_of_semester)
             .await;
+        self.accept_remote(
+            name,
+            r#type,
+            week_start,
+            week_of_semester,
+            remote,
+            !ignore_expiration,
+        )
+        .await
+    }
+
+    /// Read the cache and request remote concurrently, the first success wins.
+    ///
+    /// Fresh cache cancels the remote request. Expired cache waits for remote
+    /// not longer than `remote_timeout`, then it is returned as is.
+    async fn fetch_racing(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        week_of_semester: &WeekOfSemester,
+        remote_timeout: Duration,
+    ) -> anyhow::Result<(Schedule, FetchSource)> {
+        let remote = self.get_schedule_from_remote(name, r#type, week_start, week_of_semester);
+        let cache = async {
+            if let Some(schedule) = self
+                .get_schedule_from_cache(name, r#type, week_start, week_of_semester, false)
+                .await?
+            {
+                return Ok(CacheRead::Fresh(schedule));
+            }
+            Ok::<_, anyhow::Error>(
+                self.get_schedule_from_cache(name, r#type, week_start, week_of_semester, true)
+                    .await?
+                    .map_or(CacheRead::Miss, CacheRead::Stale),
+            )
+        };
+        tokio::pin!(remote, cache);
+
+        // the cache read is never cancelled: it may write the evicted LRU entries to the disk
+        let mut early_remote = None;
+        let cached = loop {
+            tokio::select! {
+                cached = &mut cache => break cached?,
+                remote = &mut remote, if early_remote.is_none() => early_remote = Some(remote),
+            }
+        };
+        let remote = match (early_remote, cached) {
+            (Some(Ok(schedule)), _) => Ok(schedule),
+            (_, CacheRead::Fresh(schedule)) => {
+                debug!("Fresh cache won the race, remote request is cancelled");
+                return Ok((schedule, FetchSource::Cache));
+            }
+            (Some(Err(e)), _) => Err(e),
+            (None, CacheRead::Stale(schedule)) => {
+                match tokio::time::timeout(remote_timeout, &mut remote).await {
+                    Ok(remote) => remote,
+                    Err(_) => {
+                        warn!("Remote is slower than {remote_timeout:?}, returning expired cache");
+                        return Ok((schedule, FetchSource::StaleCache));
+                    }
+                }
+            }
+            (None, CacheRead::Miss) => remote.await,
+        };
+        self.accept_remote(name, r#type, week_start, week_of_semester, remote, true)
+            .await
+    }
 
+    /// Put the fresh remote schedule into the cache. If there is no fresh schedule,
+    /// fall back to the expired cache entry (if `fallback_to_expired` is `true`).
+    async fn accept_remote(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        week_of_semester: &WeekOfSemester,
+        remote: anyhow::Result<Schedule>,
+        fallback_to_expired: bool,
+    ) -> anyhow::Result<(Schedule, FetchSource)> {
         if let Err(e) = &remote {
             warn!("{e}"); // full error description is in anyhow context
             if let Some(CommonError::GatewayError(_)) = e.as_common_error() {
@@ -120,26 +488,39 @@ impl GetScheduleUseCase {
 
         // if we cannot get value from remote and didn't disable expiration policy at the beginning,
         // try to disable expiration policy and look for cached value again
-        if remote.is_err() && !ignore_expiration || remote_is_empty {
+        if remote.is_err() && fallback_to_expired || remote_is_empty {
             if let Some(schedule) = self
-                .get_schedule_from_cache(&name, &r#type, week_start, &week_of_semester, true)
+                .get_schedule_from_cache(name, r#type, week_start, week_of_semester, true)
                 .await?
             {
-                return Ok(schedule);
+                return Ok((schedule, FetchSource::StaleCache));
+            }
+        }
+
+        // the requested week is not cached at all, but its days may be cached
+        // as a part of the fetch started on another day
+        if remote.is_err() {
+            if let Some(schedule) = self
+                .get_schedule_from_adjacent_cache(name, r#type, week_start, week_of_semester)
+                .await?
+            {
+                return Ok((schedule, FetchSource::StaleCache));
             }
         }
 
         // If we successfully got new value from remote and this value is not empty,
         // put it into the cache
-        if remote.is_ok() {
+        if let Ok(schedule) = &remote {
             if !remote_is_empty {
+                self.detect_changes(name, r#type, week_start, schedule)
+                    .await;
                 // put new remote value into the cache
                 self.schedule_repository
                     .insert_schedule_to_cache(
-                        name,
-                        r#type,
+                        name.to_owned(),
+                        r#type.to_owned(),
                         week_start,
-                        remote.as_ref().unwrap().to_owned(),
+                        schedule.to_owned(),
                     )
                     .await?;
             }
@@ -147,7 +528,179 @@ impl GetScheduleUseCase {
         }
 
         // if we have not even expired cached value, return error about remote request
-        remote
+        remote.map(|schedule| (schedule, FetchSource::Remote))
+    }
+
+    /// Get fresh [Schedule] from remote and put it into the cache, ignoring cached values.
+    /// Used by [PrefetchScheduleUseCase] to warm up the cache before the peak load.
+    pub async fn refresh_schedule(
+        &self,
+        name: ScheduleName,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<Schedule> {
+        let (week_start, week_of_semester) =
+            self.get_week_start_and_week_of_semester(offset).await?;
+        let schedule = self
+            .get_schedule_from_remote(&name, &r#type, week_start, &week_of_semester)
+            .await?;
+        if !self.is_schedule_empty(&schedule) {
+            self.detect_changes(&name, &r#type, week_start, &schedule)
+                .await;
+            self.schedule_repository
+                .insert_schedule_to_cache(name, r#type, week_start, schedule.to_owned())
+                .await?;
+        }
+        Ok(schedule)
+    }
+
+    /// Compare the fresh remote schedule with the cached one, which is about to be replaced,
+    /// and publish the differences. Changes are optional, so errors are only logged.
+    async fn detect_changes(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        remote: &Schedule,
+    ) {
+        let cached = match self
+            .schedule_repository
+            .inspect_cached_schedule(name.to_owned(), r#type.to_owned(), week_start)
+            .await
+        {
+            Ok(Some((cached, _))) => cached,
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Cannot inspect cached schedule for changes detection: {e:#}");
+                return;
+            }
+        };
+        let changes = diff_schedules(&cached, remote);
+        if changes.is_empty() {
+            return;
+        }
+        info!(
+            "Detected {} changes of schedule '{}' ({type})",
+            changes.len(),
+            name.as_ref()
+        );
+        self.event_bus.publish(ScheduleChanged {
+            name: name.to_owned(),
+            r#type: r#type.to_owned(),
+            changes,
+        });
+    }
+
+    /// Get fresh [Schedule] from remote, compare it with the cached one and put it into the cache,
+    /// so the changes are also recorded like after the regular requests.
+    /// MPEI is not requested during the cooldown and if the cached week is fresh enough.
+    /// Used by [DiffScheduleUseCase].
+    pub(crate) async fn diff_remote_with_cache(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<ScheduleCacheDiff> {
+        ensure!(offset < *MAX_OFFSET, CommonError::user("Too large offset"));
+        ensure!(offset > *MIN_OFFSET, CommonError::user("Too small offset"));
+        let name = ScheduleName::new(name, r#type.clone())?;
+        let (week_start, week_of_semester) =
+            self.get_week_start_and_week_of_semester(offset).await?;
+        let cached = self
+            .schedule_repository
+            .inspect_cached_schedule(name.clone(), r#type.to_owned(), week_start)
+            .await?;
+        if let Some((_, metadata)) = &cached {
+            if metadata.age_seconds < *DIFF_MIN_CACHE_AGE_SECONDS {
+                return Ok(ScheduleCacheDiff {
+                    name: name.as_string(),
+                    r#type,
+                    week_start,
+                    cache: Some(metadata.to_owned()),
+                    remote_fetched: false,
+                    changes: Vec::new(),
+                });
+            }
+        }
+        ensure!(
+            !self.schedule_cooldown_repository.is_cooldown_active().await,
+            CommonError::gateway("MPEI is unavailable, try again later")
+        );
+        let remote = self
+            .get_schedule_from_remote(&name, &r#type, week_start, &week_of_semester)
+            .await;
+        let remote = match remote {
+            Ok(remote) => remote,
+            Err(e) => {
+                if let Some(CommonError::GatewayError(_)) = e.as_common_error() {
+                    warn!("Activating cooldown for schedule: {e}");
+                    self.schedule_cooldown_repository.activate().await;
+                }
+                return Err(e);
+            }
+        };
+        if !self.is_schedule_empty(&remote) {
+            self.detect_changes(&name, &r#type, week_start, &remote)
+                .await;
+            self.schedule_repository
+                .insert_schedule_to_cache(name, r#type.to_owned(), week_start, remote.to_owned())
+                .await?;
+        }
+        let (cache, changes) = match cached {
+            Some((cached, metadata)) => (Some(metadata), diff_schedules(&cached, &remote)),
+            None => (None, Vec::new()),
+        };
+        Ok(ScheduleCacheDiff {
+            name: remote.name,
+            r#type,
+            week_start,
+            cache,
+            remote_fetched: true,
+            changes,
+        })
+    }
+
+    /// Get fresh [Schedule] from remote without touching the cache
+    /// and compare it with the cached one. Used by [VerifyScheduleUseCase].
+    pub(crate) async fn verify_schedule(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<ScheduleVerification> {
+        let name = ScheduleName::new(name, r#type.clone())?;
+        let (week_start, week_of_semester) =
+            self.get_week_start_and_week_of_semester(offset).await?;
+        let remote = self
+            .get_schedule_from_remote(&name, &r#type, week_start, &week_of_semester)
+            .await?;
+        let cached = self
+            .schedule_repository
+            .inspect_cached_schedule(name, r#type.to_owned(), week_start)
+            .await?;
+        let (cache, changes) = match cached {
+            Some((cached, metadata)) => (Some(metadata), diff_schedules(&cached, &remote)),
+            None => (None, Vec::new()),
+        };
+        Ok(ScheduleVerification {
+            name: remote.name,
+            r#type,
+            week_start,
+            cache,
+            changes,
+        })
+    }
+
+    async fn get_week_start_and_week_of_semester(
+        &self,
+        offset: i32,
+    ) -> anyhow::Result<(NaiveDate, WeekOfSemester)> {
+        let week_start = get_week_start(offset)?;
+        let week_of_semester = self
+            .schedule_shift_repository
+            .get_week_of_semester(&week_start)
+            .await?;
+        Ok((week_start, week_of_semester))
     }
 
     async fn get_schedule_from_remote(
@@ -165,7 +718,8 @@ impl GetScheduleUseCase {
             .with_context(|| "Error while getting schedule id from remote")?;
 
         // get schedule from remote by its id, if previous step was successful
-        self.schedule_repository
+        let schedule = self
+            .schedule_repository
             .get_schedule_from_remote(
                 schedule_id,
                 name.to_owned(),
@@ -174,7 +728,20 @@ impl GetScheduleUseCase {
                 week_of_semester.to_owned(),
             )
             .await
-            .with_context(|| "Error while getting schedule from remote")
+            .with_context(|| "Error while getting schedule from remote")?;
+
+        let warnings = validate_schedule(&schedule);
+        if !warnings.is_empty() {
+            debug!("Schedule '{name}' has validation warnings: {warnings:?}");
+        }
+        if let Err(e) = self
+            .schedule_quality_repository
+            .save_warnings(name, r#type, week_start, &warnings)
+            .await
+        {
+            warn!("Cannot save schedule quality: {e}");
+        }
+        Ok(schedule)
     }
 
     async fn get_schedule_from_cache(
@@ -207,6 +774,28 @@ impl GetScheduleUseCase {
         Ok(None)
     }
 
+    /// Scan the cached schedules overlapping the week, only the days of the week are kept
+    async fn get_schedule_from_adjacent_cache(
+        &self,
+        name: &ScheduleName,
+        r#type: &ScheduleType,
+        week_start: NaiveDate,
+        week_of_semester: &WeekOfSemester,
+    ) -> anyhow::Result<Option<Schedule>> {
+        for date in week_start.iter_days().take(7) {
+            if let Some(mut schedule) = self
+                .schedule_repository
+                .find_cached_schedule_with_date(name.to_owned(), r#type.to_owned(), date)
+                .await?
+            {
+                debug!("Got schedule from the cached fetch overlapping {date}");
+                keep_week_days(&mut schedule, week_start, week_of_semester);
+                return Ok(Some(schedule));
+            }
+        }
+        Ok(None)
+    }
+
     async fn fix_schedule_shift_if_needed(
         &self,
         schedule: &mut Schedule,
@@ -244,6 +833,48 @@ impl GetScheduleUseCase {
     }
 }
 
+/// Turn the cached fetch overlapping the week into the schedule of this week,
+/// the days out of the week are dropped
+fn keep_week_days(
+    schedule: &mut Schedule,
+    week_start: NaiveDate,
+    week_of_semester: &WeekOfSemester,
+) {
+    let week_end = week_start + ChronoDuration::days(6);
+    let Some(mut week) = schedule.weeks.first().cloned() else {
+        return;
+    };
+    week.days = schedule
+        .weeks
+        .drain(..)
+        .flat_map(|week| week.days)
+        .filter(|day| (week_start..=week_end).contains(&day.date))
+        .collect();
+    week.first_day_of_week = week_start;
+    week.week_of_year = week_start.week_of_year();
+    week.week_of_semester = match week_of_semester {
+        WeekOfSemester::Studying(week_of_semester) => *week_of_semester as i8,
+        WeekOfSemester::NonStudying => -1,
+    };
+    schedule.weeks = vec![week];
+}
+
+/// Monday of the week `offset` weeks away from the current one
+fn get_week_start(offset: i32) -> anyhow::Result<NaiveDate> {
+    Local::now()
+        .with_days_offset(offset * 7)
+        .map(|dt| dt.date_naive())
+        .map(|dt| dt.week(Weekday::Mon).first_day())
+        .ok_or_else(|| anyhow!(CommonError::user("Invalid week offset")))
+}
+
+lazy_static! {
+    static ref SEARCH_REMOTE_TIMEOUT: Duration = Duration::from_millis(env::get_parsed_or(
+        "SCHEDULE_SEARCH_REMOTE_TIMEOUT_MS",
+        5000
+    ));
+}
+
 /// Get [Vec] of [ScheduleSearchResult].
 ///
 /// This use-case is similar to [GetScheduleIdUseCase], but differs from it in that
@@ -257,7 +888,7 @@ impl GetScheduleUseCase {
 /// In this case no requests are made to the database or the MPEI backend. If cache do not have
 /// necessary value, we made request to the MPEI backend and put results of the request to
 /// the database (add new entries, update old entries). Even if the request fails, we do search
-/// in the database and return best mathes.
+/// with the [crate::search::SearchBackend] and return best mathes.
 pub struct SearchScheduleUseCase {
     pub(crate) schedule_search_repository: Arc<ScheduleSearchRepository>,
     pub(crate) schedule_cooldown_repository: Arc<ScheduleCooldownRepository>,
@@ -300,23 +931,16 @@ impl SearchScheduleUseCase {
             }
         }
 
-        let mut db_results = self
+        let results = self
             .schedule_search_repository
-            .get_results_from_db(&query, r#type.to_owned())
+            .get_results_from_search_backend(&query, r#type.to_owned())
             .await?;
 
-        let max_idx = db_results.len();
-        db_results.sort_by(|a, b| {
-            let idx_a = a.name.to_lowercase().find(query.as_ref()).or(Some(max_idx));
-            let idx_b = b.name.to_lowercase().find(query.as_ref()).or(Some(max_idx));
-            idx_a.cmp(&idx_b)
-        });
-
         self.schedule_search_repository
-            .insert_results_to_cache(query, r#type, db_results.clone())
+            .insert_results_to_cache(query, r#type, results.clone())
             .await;
 
-        Ok(db_results)
+        Ok(results)
     }
 
     async fn get_results_from_remote(
@@ -329,35 +953,866 @@ impl SearchScheduleUseCase {
                 .get_results_from_remote(query, r#type)
                 .await
         } else {
+            // lookups are independent, so one slow upstream should not delay the other
+            let (groups, persons) = tokio::join!(
+                self.get_results_from_remote_with_timeout(query, &ScheduleType::Group),
+                self.get_results_from_remote_with_timeout(query, &ScheduleType::Person),
+            );
             let mut output = Vec::<ScheduleSearchResult>::new();
-            let mut groups = self
-                .schedule_search_repository
-                .get_results_from_remote(query, &ScheduleType::Group)
-                .await;
-            let mut persons = self
-                .schedule_search_repository
-                .get_results_from_remote(query, &ScheduleType::Person)
-                .await;
-            if let Ok(groups) = &mut groups {
-                output.append(groups);
-            }
-            if let Ok(persons) = &mut persons {
-                output.append(persons);
+            for results in [groups, persons] {
+                match results {
+                    Ok(mut results) => output.append(&mut results),
+                    Err(e) => warn!("{e}"),
+                }
             }
             Ok(output)
         }
     }
+
+    async fn get_results_from_remote_with_timeout(
+        &self,
+        query: &ScheduleSearchQuery,
+        r#type: &ScheduleType,
+    ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
+        tokio::time::timeout(
+            *SEARCH_REMOTE_TIMEOUT,
+            self.schedule_search_repository
+                .get_results_from_remote(query, r#type),
+        )
+        .await
+        .map_err(|_| anyhow!("Timeout while searching schedules of type '{type}' on remote"))?
+    }
+}
+
+/// Get the rules of the non-standard academic weeks numbering.
+/// Used by clients and admins to find out why the week has a certain number.
+pub struct GetShiftRulesUseCase(pub(crate) Arc<ScheduleShiftRepository>);
+
+impl GetShiftRulesUseCase {
+    pub async fn get_shift_rules(&self, date: Option<NaiveDate>) -> anyhow::Result<ShiftRules> {
+        self.0.get_shift_rules(date).await
+    }
+}
+
+/// Get summary of schedule validation warnings for the last `days` days.
+/// Used by admins to prioritize upstream data fixes.
+pub struct GetScheduleQualityUseCase(pub(crate) Arc<ScheduleQualityRepository>);
+
+impl GetScheduleQualityUseCase {
+    /// Sort is a column name from [QUALITY_SORT_COLUMNS] with optional `-` prefix
+    /// for descending order. By default, the most problematic schedules go first.
+    pub async fn get_quality_report(
+        &self,
+        days: u32,
+        page: Page,
+        sort: Option<&str>,
+    ) -> anyhow::Result<Paged<ScheduleQuality>> {
+        ensure!(
+            (1..=365).contains(&days),
+            CommonError::user("Days should be in range 1..=365")
+        );
+        let sort = match sort {
+            Some(sort) => Sort::parse(sort, QUALITY_SORT_COLUMNS).map_err(CommonError::user)?,
+            None => Sort::desc("total_warnings"),
+        };
+        self.0.get_quality_report(days, page, sort).await
+    }
+}
+
+/// How many days can be overridden with the single request
+const MAX_OVERRIDE_DAYS: usize = 31;
+
+/// Save manual overrides of schedule days, e.g. corrections sent by the dean's office
+/// before MPEI updates their site. Idempotent: the override of the same day is replaced.
+///
+/// Overridden days are merged over the schedules by [GetScheduleUseCase]
+/// until their expiry date (inclusive).
+pub struct SetScheduleOverridesUseCase(pub(crate) Arc<ScheduleOverrideRepository>);
+
+impl SetScheduleOverridesUseCase {
+    pub async fn set_overrides(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        overrides: Vec<DayOverride>,
+    ) -> anyhow::Result<Vec<DayOverride>> {
+        let name = ScheduleName::new(name, r#type.clone())?;
+        ensure!(
+            !overrides.is_empty(),
+            CommonError::user("Overrides should not be empty")
+        );
+        ensure!(
+            overrides.len() <= MAX_OVERRIDE_DAYS,
+            CommonError::user(format!(
+                "No more than {} days can be overridden at once",
+                MAX_OVERRIDE_DAYS
+            ))
+        );
+        for (i, day) in overrides.iter().enumerate() {
+            ensure!(
+                day.expires_at >= day.date,
+                CommonError::user(format!("Override of {} expires before the day", day.date))
+            );
+            ensure!(
+                !overrides[..i].iter().any(|it| it.date == day.date),
+                CommonError::user(format!("Duplicated override of {}", day.date))
+            );
+        }
+        info!(
+            "Saving {} day overrides of {type} '{}'",
+            overrides.len(),
+            name.as_ref()
+        );
+        self.0.save_overrides(&name, &r#type, &overrides).await?;
+        Ok(overrides)
+    }
+}
+
+/// Schedule integrity check for support questions like "is the bot lying or is MPEI lying":
+/// fetch the week from MPEI bypassing the cache and compare it with the cached version.
+///
+/// The cache is not modified, so the check can be repeated to see the same difference.
+pub struct VerifyScheduleUseCase(pub(crate) Arc<GetScheduleUseCase>);
+
+impl VerifyScheduleUseCase {
+    pub async fn verify(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<ScheduleVerification> {
+        self.0.verify_schedule(name, r#type, offset).await
+    }
+}
+
+/// Changes between two weeks of the same schedule, e.g. "что меняется на следующей неделе".
+/// The weeks are taken from the cache or MPEI like the regular schedule requests.
+///
+/// Also compares the cached week with the fresh one from MPEI, see [DiffScheduleUseCase::diff_with_cache].
+pub struct DiffScheduleUseCase(pub(crate) Arc<GetScheduleUseCase>);
+
+impl DiffScheduleUseCase {
+    pub async fn diff(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        from: i32,
+        to: i32,
+    ) -> anyhow::Result<ScheduleDiff> {
+        let (from, to) = tokio::try_join!(
+            self.0.get_schedule(name.clone(), r#type.clone(), from),
+            self.0.get_schedule(name, r#type.clone(), to),
+        )?;
+        let week_start = |schedule: &Schedule| {
+            schedule
+                .weeks
+                .first()
+                .map(|week| week.first_day_of_week)
+                .ok_or_else(|| anyhow!(CommonError::internal("Schedule does not have week")))
+        };
+        Ok(ScheduleDiff {
+            from_week_start: week_start(&from)?,
+            to_week_start: week_start(&to)?,
+            changes: diff_weeks(&from, &to),
+            name: to.name,
+            r#type,
+        })
+    }
+
+    /// Changes of the week with the `offset` fetched from MPEI relative to the cached version.
+    /// The fresh week replaces the cached one, unless the cached one is too fresh to compare.
+    pub async fn diff_with_cache(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        offset: i32,
+    ) -> anyhow::Result<ScheduleCacheDiff> {
+        self.0.diff_remote_with_cache(name, r#type, offset).await
+    }
+}
+
+/// Latency of the current week schedule requests, see [GetScheduleUseCase::fetch_latency].
+/// Used by admins to compare the fetch strategies before switching them.
+pub struct GetScheduleLatencyUseCase(pub(crate) Arc<GetScheduleUseCase>);
+
+impl GetScheduleLatencyUseCase {
+    pub fn get_latency(&self) -> ScheduleFetchLatency {
+        self.0.fetch_latency()
+    }
+}
+
+/// Readiness of the service components for the public status page:
+/// the schedule service itself, the database, MPEI and the services from `STATUS_HEALTH_URLS`.
+///
+/// This UseCase uses injected singleton instances of [ServiceStatusRepository]
+/// and [ScheduleCooldownRepository]. Check [crate::di] module for details.
+pub struct GetServiceStatusUseCase(
+    pub(crate) Arc<ServiceStatusRepository>,
+    pub(crate) Arc<ScheduleCooldownRepository>,
+);
+
+impl GetServiceStatusUseCase {
+    pub async fn get_status(&self) -> ServiceStatus {
+        let mut checks = vec![
+            ("schedule".to_owned(), (ComponentHealth::Ok, None)),
+            ("database".to_owned(), self.0.check_database().await),
+            ("mpei".to_owned(), self.check_mpei().await),
+        ];
+        let health_checks = self.0.health_urls().iter().map(|(name, url)| async move {
+            (name.to_owned(), self.0.check_health_url(url).await)
+        });
+        checks.extend(futures_util::future::join_all(health_checks).await);
+
+        let now = Local::now().naive_local();
+        let components = checks
+            .into_iter()
+            .map(|(name, (status, details))| ComponentStatus {
+                last_change: self.0.record(&name, status, now),
+                name,
+                status,
+                details,
+            })
+            .collect::<Vec<_>>();
+        ServiceStatus {
+            status: components
+                .iter()
+                .map(|it| it.status)
+                .max()
+                .unwrap_or(ComponentHealth::Ok),
+            checked_at: now,
+            components,
+        }
+    }
+
+    /// During the cooldown MPEI is not requested and the schedules are served from the cache
+    async fn check_mpei(&self) -> (ComponentHealth, Option<String>) {
+        if self.1.is_cooldown_active().await {
+            (
+                ComponentHealth::Degraded,
+                Some("MPEI errors, schedules are served from the cache".to_owned()),
+            )
+        } else {
+            (ComponentHealth::Ok, None)
+        }
+    }
+}
+
+/// Hourly stats of the calls to the MPEI backend, recorded by
+/// [crate::upstream_audit::UpstreamAuditInterceptor].
+///
+/// This UseCase uses injected singleton instance of [UpstreamAuditRepository].
+/// Check [crate::di] module for details.
+pub struct UpstreamAuditUseCase(pub(crate) Arc<UpstreamAuditRepository>);
+
+impl UpstreamAuditUseCase {
+    /// Save the stats of the recent calls, should be called periodically
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        self.0.flush().await
+    }
+
+    pub async fn get_hourly_stats(&self, hours: u32) -> anyhow::Result<Vec<UpstreamCallStats>> {
+        ensure!(
+            (1..=24 * 31).contains(&hours),
+            CommonError::user("Hours should be in range 1..=744")
+        );
+        self.0.get_hourly_stats(hours).await
+    }
+}
+
+/// Reload of the config file from `CONFIG_PATH` without restart: use case configs are read
+/// again on their next use, in-memory caches get the new policies and keep their entries,
+/// shift rules are read again right away.
+///
+/// This UseCase uses injected singleton instances of [ScheduleIdRepository],
+/// [ScheduleRepository], [ScheduleSearchRepository] and [ScheduleShiftRepository].
+/// Check [crate::di] module for details.
+pub struct ReloadConfigUseCase(
+    pub(crate) Arc<ScheduleIdRepository>,
+    pub(crate) Arc<ScheduleRepository>,
+    pub(crate) Arc<ScheduleSearchRepository>,
+    pub(crate) Arc<ScheduleShiftRepository>,
+);
+
+impl ReloadConfigUseCase {
+    /// Returns the names of the changed variables
+    pub async fn reload(&self) -> anyhow::Result<Vec<String>> {
+        let changed = env::reload_config_file()?;
+        self.0.reload_config().await;
+        self.1.reload_config().await;
+        self.2.reload_config().await;
+        self.3.reload_config().await;
+        info!("Config is reloaded, changed variables: {changed:?}");
+        Ok(changed)
+    }
+}
+
+/// Changes of the schedule detected during the last `days`, see [GetScheduleUseCase].
+///
+/// This UseCase uses injected singleton instance of [ScheduleChangeRepository].
+/// Check [crate::di] module for details.
+pub struct GetScheduleChangesUseCase(pub(crate) Arc<ScheduleChangeRepository>);
+
+impl GetScheduleChangesUseCase {
+    pub async fn get_changes(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+        days: u32,
+    ) -> anyhow::Result<ScheduleChangelog> {
+        ensure!(
+            (1..=MAX_CHANGELOG_DAYS).contains(&days),
+            CommonError::user(format!("Days should be from 1 to {MAX_CHANGELOG_DAYS}"))
+        );
+        let name = ScheduleName::new(name, r#type.clone())?;
+        let since = Local::now().naive_local() - ChronoDuration::days(days as i64);
+        let changes = self.0.get_changes(&name, &r#type, since).await?;
+        Ok(ScheduleChangelog {
+            name: name.as_string(),
+            r#type,
+            since,
+            changes,
+        })
+    }
+}
+
+/// Changes are kept in the database for a month
+const MAX_CHANGELOG_DAYS: u32 = 30;
+
+/// Most requested schedules over the window of the last days, e.g. `7d`,
+/// for the "popular groups" screen of the mobile app and the bot's start suggestions.
+///
+/// Unlike the [PrefetchScheduleUseCase] popularity, requests are counted by days,
+/// so the schedules popular long ago do not stay on top forever.
+pub struct GetTrendingSchedulesUseCase(pub(crate) Arc<SchedulePopularityRepository>);
+
+const DEFAULT_TRENDING_LIMIT: usize = 10;
+const MAX_TRENDING_LIMIT: usize = 50;
+
+impl GetTrendingSchedulesUseCase {
+    pub async fn get_trending(
+        &self,
+        window: Option<&str>,
+        r#type: Option<ScheduleType>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<TrendingSchedules> {
+        let window_days = match window {
+            Some(window) => parse_trending_window(window).ok_or_else(|| {
+                CommonError::user(format!(
+                    "Window should be from 1d to {KEEP_DAILY_POPULARITY_DAYS}d"
+                ))
+            })?,
+            None => 7,
+        };
+        let limit = limit
+            .unwrap_or(DEFAULT_TRENDING_LIMIT)
+            .clamp(1, MAX_TRENDING_LIMIT);
+        Ok(TrendingSchedules {
+            window_days,
+            items: self.0.get_trending(window_days, r#type, limit).await?,
+        })
+    }
+}
+
+/// Parse the window like `7d` into the number of days, plain `7` is accepted too
+fn parse_trending_window(window: &str) -> Option<u32> {
+    let window = window.trim().to_lowercase();
+    let days = window
+        .strip_suffix('d')
+        .unwrap_or(&window)
+        .parse::<u32>()
+        .ok()?;
+    (1..=KEEP_DAILY_POPULARITY_DAYS)
+        .contains(&days)
+        .then_some(days)
+}
+
+/// Refresh next-week cache entries for the most active schedules before the peak load.
+///
+/// Analytics show that next-week requests spike on Sunday evenings, so the use case
+/// is triggered on configured weekdays after configured hour (once a day), and refreshes
+/// no more than `budget` most popular schedules from [SchedulePopularityRepository].
+pub struct PrefetchScheduleUseCase {
+    pub(crate) get_schedule_use_case: Arc<GetScheduleUseCase>,
+    pub(crate) schedule_popularity_repository: Arc<SchedulePopularityRepository>,
+    pub(crate) schedule_cooldown_repository: Arc<ScheduleCooldownRepository>,
+    pub(crate) config: Reloadable<PrefetchConfig>,
+    pub(crate) last_prefetch_date: Mutex<Option<NaiveDate>>,
+}
+
+/// Configuration of [PrefetchScheduleUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PrefetchConfig {
+    /// Days of week when the prefetch is performed
+    pub weekdays: Vec<Weekday>,
+    /// Prefetch is performed not earlier than this hour
+    pub hour: u32,
+    /// Max number of schedules to refresh, zero disables prefetching
+    pub budget: usize,
+    /// Delay between requests to the MPEI backend
+    pub delay: Duration,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        Self {
+            weekdays: env::get_or("SCHEDULE_PREFETCH_WEEKDAYS", "sun")
+                .split(',')
+                .filter_map(|it| it.trim().parse::<Weekday>().ok())
+                .collect(),
+            hour: env::get_parsed_or("SCHEDULE_PREFETCH_HOUR", 18),
+            budget: env::get_parsed_or("SCHEDULE_PREFETCH_BUDGET", 100),
+            delay: Duration::from_millis(env::get_parsed_or("SCHEDULE_PREFETCH_DELAY_MS", 500)),
+        }
+    }
+}
+
+impl PrefetchConfig {
+    fn should_prefetch(&self, now: NaiveDateTime, last_prefetch_date: Option<NaiveDate>) -> bool {
+        self.budget > 0
+            && self.weekdays.contains(&now.weekday())
+            && now.hour() >= self.hour
+            && last_prefetch_date != Some(now.date())
+    }
+}
+
+impl PrefetchScheduleUseCase {
+    /// Refresh next-week schedules if it is time to do it.
+    /// This method is supposed to be called periodically.
+    pub async fn prefetch_if_needed(&self) -> anyhow::Result<()> {
+        let now = Local::now().naive_local();
+        let config = self.config.load();
+        {
+            let mut last_prefetch_date = self.last_prefetch_date.lock().await;
+            if !config.should_prefetch(now, *last_prefetch_date) {
+                return Ok(());
+            }
+            *last_prefetch_date = Some(now.date());
+        }
+
+        let schedules = self
+            .schedule_popularity_repository
+            .get_most_popular(Page::first(config.budget))
+            .await?;
+        info!("Prefetching next week for {} schedules", schedules.len());
+        let mut refreshed = 0;
+        for (name, r#type) in schedules {
+            if self.schedule_cooldown_repository.is_cooldown_active().await {
+                warn!("Schedule prefetching interrupted due to cooldown");
+                break;
+            }
+            match self
+                .get_schedule_use_case
+                .refresh_schedule(name.to_owned(), r#type, 1)
+                .await
+            {
+                Ok(_) => refreshed += 1,
+                Err(e) => warn!("Cannot prefetch schedule '{name}': {e}"),
+            }
+            tokio::time::sleep(config.delay).await;
+        }
+        info!("Schedule prefetching finished, {refreshed} schedules refreshed");
+        Ok(())
+    }
+}
+
+/// Rooms which are free at the given time, e.g. to find a place to study between the classes.
+///
+/// Schedules of all rooms known from the search are inverted: the room is free, if none
+/// of its classes at the date covers the time. Schedules are taken from the cache or MPEI
+/// like the regular schedule requests, but they do not affect the schedule popularity.
+pub struct FindFreeRoomsUseCase {
+    pub(crate) schedule_search_repository: Arc<ScheduleSearchRepository>,
+    pub(crate) get_schedule_use_case: Arc<GetScheduleUseCase>,
+    pub(crate) config: Reloadable<FindFreeRoomsConfig>,
+}
+
+/// Configuration of [FindFreeRoomsUseCase]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindFreeRoomsConfig {
+    /// Max number of room schedules requested at the same time
+    pub concurrency: usize,
+}
+
+impl Default for FindFreeRoomsConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: env::get_parsed_or("SCHEDULE_FREE_ROOMS_CONCURRENCY", 8),
+        }
+    }
+}
+
+/// Free rooms are searched from the current week up to this number of weeks ahead,
+/// otherwise every request would fetch all room schedules from MPEI
+const FREE_ROOMS_MAX_WEEK_OFFSET: i32 = 2;
+
+impl FindFreeRoomsUseCase {
+    pub async fn find_free_rooms(
+        &self,
+        date: NaiveDate,
+        time: NaiveTime,
+    ) -> anyhow::Result<FreeRooms> {
+        let this_week = Local::now().date_naive().week(Weekday::Mon).first_day();
+        let offset = ((date.week(Weekday::Mon).first_day() - this_week).num_days() / 7) as i32;
+        ensure!(
+            (0..=FREE_ROOMS_MAX_WEEK_OFFSET).contains(&offset),
+            CommonError::user(format!(
+                "Free rooms can be found from this week to {FREE_ROOMS_MAX_WEEK_OFFSET} weeks ahead"
+            ))
+        );
+
+        let rooms = self.schedule_search_repository.get_known_rooms().await?;
+        let schedules = stream::iter(rooms)
+            .map(|name| async move {
+                let schedule = self
+                    .get_schedule_use_case
+                    .get_schedule_for_aggregation(name.clone(), ScheduleType::Room, offset)
+                    .await;
+                (name, schedule)
+            })
+            .buffer_unordered(self.config.load().concurrency.max(1))
+            .collect::<Vec<_>>()
+            .await;
+
+        let mut free_rooms = FreeRooms {
+            date,
+            time,
+            rooms: Vec::new(),
+            unknown: Vec::new(),
+        };
+        for (name, schedule) in schedules {
+            match schedule {
+                Ok(schedule) => free_rooms
+                    .rooms
+                    .extend(find_free_room(name, &schedule, date, time)),
+                Err(e) => {
+                    debug!("Cannot get schedule of room '{name}': {e}");
+                    free_rooms.unknown.push(name);
+                }
+            }
+        }
+        free_rooms.rooms.sort_by(|a, b| a.name.cmp(&b.name));
+        free_rooms.unknown.sort();
+        Ok(free_rooms)
+    }
+}
+
+/// `None` if the room is busy at the time, cancelled classes do not count
+fn find_free_room(
+    name: String,
+    schedule: &Schedule,
+    date: NaiveDate,
+    time: NaiveTime,
+) -> Option<FreeRoom> {
+    let classes = schedule
+        .weeks
+        .iter()
+        .flat_map(|week| week.days.iter())
+        .filter(|day| day.date == date)
+        .flat_map(|day| day.classes.iter())
+        .filter(|cls| !matches!(cls.status, ClassesStatus::Cancelled))
+        .collect::<Vec<_>>();
+    if classes
+        .iter()
+        .any(|cls| cls.time.start <= time && time < cls.time.end)
+    {
+        return None;
+    }
+    Some(FreeRoom {
+        name,
+        free_until: classes
+            .iter()
+            .map(|cls| cls.time.start)
+            .filter(|start| *start > time)
+            .min(),
+    })
+}
+
+/// Default number of the autocomplete suggestions, enough for the suggestion bar of the keyboard
+const AUTOCOMPLETE_DEFAULT_LIMIT: usize = 8;
+const AUTOCOMPLETE_MAX_LIMIT: usize = 20;
+const AUTOCOMPLETE_MAX_QUERY_LENGTH: usize = 50;
+
+/// Suggestions of the schedule names for the mobile keyboards, e.g. `А-08-19` for `а-0`.
+///
+/// Unlike [SearchScheduleUseCase], only the names known from the previous searches are
+/// suggested. They are served from the in-memory prefix index without any requests
+/// to the database or MPEI, so the latency does not depend on them.
+pub struct AutocompleteScheduleUseCase(pub(crate) Arc<ScheduleSearchRepository>);
+
+impl AutocompleteScheduleUseCase {
+    pub fn autocomplete(
+        &self,
+        query: &str,
+        r#type: Option<ScheduleType>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<Vec<ScheduleAutocompleteItem>> {
+        let query = query.trim();
+        ensure!(
+            !query.is_empty(),
+            CommonError::user("The autocomplete query must not be empty")
+        );
+        ensure!(
+            query.chars().count() <= AUTOCOMPLETE_MAX_QUERY_LENGTH,
+            CommonError::user("Too long autocomplete query")
+        );
+        let limit = limit.unwrap_or(AUTOCOMPLETE_DEFAULT_LIMIT);
+        ensure!(
+            (1..=AUTOCOMPLETE_MAX_LIMIT).contains(&limit),
+            CommonError::user(format!("Limit must be from 1 to {AUTOCOMPLETE_MAX_LIMIT}"))
+        );
+        Ok(self.0.get_autocomplete(query, r#type.as_ref(), limit))
+    }
+}
+
+/// Consumers of the schedule events published to the [EventBus]:
+/// requests of the users are counted in the schedule popularity
+/// and detected changes are saved for the changelogs.
+pub struct ScheduleEventsUseCase {
+    pub(crate) event_bus: Arc<EventBus>,
+    pub(crate) schedule_popularity_repository: Arc<SchedulePopularityRepository>,
+    pub(crate) schedule_change_repository: Arc<ScheduleChangeRepository>,
+}
+
+impl ScheduleEventsUseCase {
+    /// Start the consumers in the background, must be called inside the tokio runtime.
+    /// Events published before this call are not consumed.
+    pub fn start_consumers(&self) {
+        let schedule_popularity_repository = self.schedule_popularity_repository.clone();
+        self.event_bus.consume(move |event: ScheduleRequested| {
+            let repository = schedule_popularity_repository.clone();
+            async move {
+                repository
+                    .record_request(&event.name, &event.r#type)
+                    .await
+                    .with_context(|| "Cannot record schedule request")
+            }
+        });
+        let schedule_change_repository = self.schedule_change_repository.clone();
+        self.event_bus.consume(move |event: ScheduleChanged| {
+            let repository = schedule_change_repository.clone();
+            async move {
+                repository
+                    .save_changes(&event.name, &event.r#type, &event.changes)
+                    .await
+                    .with_context(|| "Cannot save schedule changes")
+            }
+        });
+    }
 }
 
 /// Create databases if needed and run migrations.
-/// This use case must be started **STRICTLY** before the server starts.
-pub struct InitDomainScheduleUseCase(pub(crate) Arc<ScheduleSearchRepository>);
+/// This use case must be complete **STRICTLY** before the app becomes ready.
+pub struct InitDomainScheduleUseCase(
+    pub(crate) Arc<ScheduleSearchRepository>,
+    pub(crate) Arc<SchedulePopularityRepository>,
+    pub(crate) Arc<ScheduleQualityRepository>,
+    pub(crate) Arc<ScheduleOverrideRepository>,
+    pub(crate) Arc<ScheduleChangeRepository>,
+    pub(crate) Arc<UpstreamAuditRepository>,
+);
 
 impl InitDomainScheduleUseCase {
     pub async fn init(&self) -> anyhow::Result<()> {
         self.0
             .init_schedule_search_results_db()
             .await
+            .with_context(|| "Database initialization error")?;
+        if let Err(e) = self.0.sync_search_index().await {
+            // search still works with the stale index, which is updated by the new results
+            warn!("Search index sync error: {e:#}");
+        }
+        if let Err(e) = self.0.load_autocomplete_index().await {
+            // autocomplete is filled with the new search results anyway
+            warn!("Autocomplete index loading error: {e:#}");
+        }
+        self.1
+            .init_schedule_popularity_db()
+            .await
+            .with_context(|| "Database initialization error")?;
+        self.2
+            .init_schedule_quality_db()
+            .await
+            .with_context(|| "Database initialization error")?;
+        self.3
+            .init_schedule_override_db()
+            .await
+            .with_context(|| "Database initialization error")?;
+        self.4
+            .init_schedule_change_db()
+            .await
+            .with_context(|| "Database initialization error")?;
+        self.5
+            .init_upstream_audit_db()
+            .await
             .with_context(|| "Database initialization error")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+    use domain_schedule_models::{
+        Classes, ClassesStatus, ClassesTime, ClassesType, Day, Schedule, ScheduleType, Week,
+    };
+
+    use super::{find_free_room, keep_week_days, parse_trending_window, PrefetchConfig};
+    use crate::time::WeekOfSemester;
+
+    fn config() -> PrefetchConfig {
+        PrefetchConfig {
+            weekdays: vec![Weekday::Sun],
+            hour: 18,
+            budget: 10,
+            delay: Duration::ZERO,
+        }
+    }
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn prefetch_on_configured_weekday_and_hour() {
+        // 2023-03-05 is Sunday
+        assert!(config().should_prefetch(datetime("2023-03-05 18:00"), None));
+        assert!(config().should_prefetch(datetime("2023-03-05 23:30"), None));
+        assert!(!config().should_prefetch(datetime("2023-03-05 17:59"), None));
+        assert!(!config().should_prefetch(datetime("2023-03-04 20:00"), None));
+    }
+
+    #[test]
+    fn prefetch_once_a_day() {
+        let sunday = NaiveDate::from_ymd_opt(2023, 3, 5);
+        let prev_sunday = NaiveDate::from_ymd_opt(2023, 2, 26);
+        assert!(!config().should_prefetch(datetime("2023-03-05 20:00"), sunday));
+        assert!(config().should_prefetch(datetime("2023-03-05 20:00"), prev_sunday));
+    }
+
+    #[test]
+    fn zero_budget_disables_prefetch() {
+        let config = PrefetchConfig {
+            budget: 0,
+            ..config()
+        };
+        assert!(!config.should_prefetch(datetime("2023-03-05 20:00"), None));
+    }
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    fn classes(start: &str, end: &str, status: ClassesStatus) -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: "Лекция".to_owned(),
+            place: "Б-114".to_owned(),
+            groups: "А-08-19".to_owned(),
+            person: "Иванов И.И.".to_owned(),
+            time: ClassesTime {
+                start: time(start),
+                end: time(end),
+            },
+            number: 1,
+            status,
+        }
+    }
+
+    fn room_schedule(classes: Vec<Classes>) -> Schedule {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        Schedule {
+            id: "1".to_owned(),
+            name: "Б-114".to_owned(),
+            r#type: ScheduleType::Room,
+            weeks: vec![Week {
+                week_of_year: 11,
+                week_of_semester: 6,
+                first_day_of_week: date,
+                days: vec![Day {
+                    day_of_week: 1,
+                    date,
+                    classes,
+                    source: Default::default(),
+                    expires_at: None,
+                    before_semester_start: false,
+                }],
+                period_type: Default::default(),
+                last_synced_at: None,
+            }],
+        }
+    }
+
+    #[test]
+    fn room_is_free_until_next_classes() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        let schedule = room_schedule(vec![
+            classes("09:20", "10:55", Default::default()),
+            classes("13:45", "15:20", Default::default()),
+        ]);
+
+        let room = find_free_room("Б-114".to_owned(), &schedule, date, time("11:00")).unwrap();
+        assert_eq!(Some(time("13:45")), room.free_until);
+        let room = find_free_room("Б-114".to_owned(), &schedule, date, time("16:00")).unwrap();
+        assert_eq!(None, room.free_until);
+        assert!(find_free_room("Б-114".to_owned(), &schedule, date, time("09:20")).is_none());
+    }
+
+    #[test]
+    fn cancelled_classes_do_not_occupy_room() {
+        let date = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        let schedule = room_schedule(vec![classes("09:20", "10:55", ClassesStatus::Cancelled)]);
+
+        let room = find_free_room("Б-114".to_owned(), &schedule, date, time("10:00")).unwrap();
+        assert_eq!(None, room.free_until);
+    }
+
+    #[test]
+    fn room_is_free_at_other_days() {
+        let other_date = NaiveDate::from_ymd_opt(2023, 3, 14).unwrap();
+        let schedule = room_schedule(vec![classes("09:20", "10:55", Default::default())]);
+
+        assert!(find_free_room("Б-114".to_owned(), &schedule, other_date, time("10:00")).is_some());
+    }
+
+    #[test]
+    fn overlapping_fetch_is_cut_to_requested_week() {
+        // the fetch started on Wednesday 2023-03-08 and contains the days of two weeks
+        let mut schedule = room_schedule(vec![]);
+        let day = schedule.weeks[0].days[0].clone();
+        let fetch_start = NaiveDate::from_ymd_opt(2023, 3, 8).unwrap();
+        schedule.weeks[0].first_day_of_week = fetch_start;
+        schedule.weeks[0].days.insert(
+            0,
+            Day {
+                day_of_week: 3,
+                date: fetch_start,
+                ..day
+            },
+        );
+
+        let week_start = NaiveDate::from_ymd_opt(2023, 3, 13).unwrap();
+        keep_week_days(&mut schedule, week_start, &WeekOfSemester::Studying(7));
+        assert_eq!(1, schedule.weeks.len());
+        let week = &schedule.weeks[0];
+        assert_eq!(week_start, week.first_day_of_week);
+        assert_eq!(7, week.week_of_semester);
+        assert_eq!(
+            vec![week_start],
+            week.days.iter().map(|day| day.date).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn trending_window() {
+        assert_eq!(Some(7), parse_trending_window("7d"));
+        assert_eq!(Some(14), parse_trending_window("14D"));
+        assert_eq!(Some(1), parse_trending_window("1"));
+        assert_eq!(Some(30), parse_trending_window("30d"));
+        assert_eq!(None, parse_trending_window("0d"));
+        assert_eq!(None, parse_trending_window("31d"));
+        assert_eq!(None, parse_trending_window("1w"));
+        assert_eq!(None, parse_trending_window(""));
+    }
+}
@@ -0,0 +1,51 @@
+//! Run with `cargo test -p domain_schedule --features record-replay`.
+//! Fixtures in `tests/fixtures` can be re-recorded from the real MPEI backend
+//! by switching the interceptor to [RecordReplayMode::Record].
+
+use std::sync::Arc;
+
+use domain_schedule::{
+    mpei_api::{MpeiApi, MpeiUpstream},
+    record_replay::{RecordReplayInterceptor, RecordReplayMode},
+};
+use domain_schedule_models::ScheduleType;
+
+fn upstream() -> MpeiUpstream {
+    let interceptor = RecordReplayInterceptor::new(
+        RecordReplayMode::Replay,
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures"),
+    );
+    MpeiUpstream::Remote(
+        MpeiApi::builder()
+            .interceptor(Arc::new(interceptor))
+            .build()
+            .unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn test_search_is_replayed() {
+    let results = upstream()
+        .search("А-08-19", &ScheduleType::Group)
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].id, 1001);
+    assert_eq!(results[0].label, "А-08-19");
+}
+
+#[tokio::test]
+async fn test_schedule_is_replayed() {
+    let classes = upstream()
+        .schedule(&ScheduleType::Group, 1001, "2023.02.06", "2023.02.12", 1)
+        .await
+        .unwrap();
+    let disciplines = classes
+        .iter()
+        .map(|it| it.discipline.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        disciplines,
+        vec!["Математический анализ", "Программирование"]
+    );
+}
@@ -25,7 +25,8 @@ impl Default for ScheduleCooldownRepository {
 impl ScheduleCooldownRepository {
     /// Set cooldown timer active
     pub async fn activate(&self) {
-        *self.last_error_time.lock().await = Some(Local::now())
+        *self.last_error_time.lock().await = Some(Local::now());
+        common_metrics::record_cooldown_activation();
     }
 
     /// Check if cooldown timer still active or not
@@ -0,0 +1,189 @@
+//! Export of the schedule weeks to the iCalendar format (RFC 5545),
+//! so the users can import them into Google Calendar, Apple Calendar, etc.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::{Classes, ClassesStatus, Week};
+
+/// All MPEI classes are in Moscow time, which has no daylight saving time
+const VTIMEZONE: &str = "BEGIN:VTIMEZONE\r\n\
+    TZID:Europe/Moscow\r\n\
+    BEGIN:STANDARD\r\n\
+    DTSTART:19700101T000000\r\n\
+    TZOFFSETFROM:+0300\r\n\
+    TZOFFSETTO:+0300\r\n\
+    TZNAME:MSK\r\n\
+    END:STANDARD\r\n\
+    END:VTIMEZONE\r\n";
+
+/// Lines longer than 75 octets must be folded
+const MAX_LINE_LENGTH: usize = 75;
+
+/// Render all classes of the `week` as the events of the calendar named `calendar_name`.
+///
+/// `stamp` is the UTC time of the export, required by the format for each event.
+pub fn week_to_ics(calendar_name: &str, week: &Week, stamp: NaiveDateTime) -> String {
+    let mut buf = String::with_capacity(4096);
+    push_line(&mut buf, "BEGIN:VCALENDAR");
+    push_line(&mut buf, "VERSION:2.0");
+    push_line(&mut buf, "PRODID:-//mpeix//schedule//RU");
+    push_line(&mut buf, "CALSCALE:GREGORIAN");
+    push_line(&mut buf, "METHOD:PUBLISH");
+    push_line(&mut buf, &format!("X-WR-CALNAME:{}", escape(calendar_name)));
+    push_line(&mut buf, "X-WR-TIMEZONE:Europe/Moscow");
+    buf.push_str(VTIMEZONE);
+    for day in &week.days {
+        for classes in &day.classes {
+            push_event(&mut buf, calendar_name, day.date, classes, stamp);
+        }
+    }
+    push_line(&mut buf, "END:VCALENDAR");
+    buf
+}
+
+fn push_event(
+    buf: &mut String,
+    calendar_name: &str,
+    date: NaiveDate,
+    classes: &Classes,
+    stamp: NaiveDateTime,
+) {
+    let summary = match classes.raw_type.is_empty() {
+        true => classes.name.to_owned(),
+        false => format!("{} ({})", classes.name, classes.raw_type),
+    };
+    let description = [classes.person.as_str(), classes.groups.as_str()]
+        .into_iter()
+        .filter(|it| !it.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    push_line(buf, "BEGIN:VEVENT");
+    // the same classes get the same uid, so the repeated import updates the event
+    push_line(
+        buf,
+        &format!(
+            "UID:{}-{}-{}@mpeix",
+            date.format("%Y%m%d"),
+            classes.time.start.format("%H%M"),
+            escape(calendar_name).replace(' ', "_"),
+        ),
+    );
+    push_line(buf, &format!("DTSTAMP:{}Z", stamp.format("%Y%m%dT%H%M%S")));
+    push_line(
+        buf,
+        &format!(
+            "DTSTART;TZID=Europe/Moscow:{}",
+            format_local(date, classes.time.start)
+        ),
+    );
+    push_line(
+        buf,
+        &format!(
+            "DTEND;TZID=Europe/Moscow:{}",
+            format_local(date, classes.time.end)
+        ),
+    );
+    push_line(buf, &format!("SUMMARY:{}", escape(&summary)));
+    if !classes.place.is_empty() {
+        push_line(buf, &format!("LOCATION:{}", escape(&classes.place)));
+    }
+    if !description.is_empty() {
+        push_line(buf, &format!("DESCRIPTION:{}", escape(&description)));
+    }
+    if classes.status == ClassesStatus::Cancelled {
+        push_line(buf, "STATUS:CANCELLED");
+    }
+    push_line(buf, "END:VEVENT");
+}
+
+fn format_local(date: NaiveDate, time: NaiveTime) -> String {
+    date.and_time(time).format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Escape the special characters of the text values
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Append the content line, folding it without breaking the multibyte characters
+fn push_line(buf: &mut String, line: &str) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            buf.push_str("\r\n ");
+            // the leading space of the continuation line counts too
+            length = 1;
+        }
+        buf.push(c);
+        length += c.len_utf8();
+    }
+    buf.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+
+    use super::week_to_ics;
+    use crate::{Classes, ClassesStatus, ClassesTime, ClassesType, Day, PeriodType, Week};
+
+    fn classes(name: &str, start: (u32, u32), end: (u32, u32)) -> Classes {
+        Classes {
+            name: name.to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: "Лекция".to_owned(),
+            place: "М-611".to_owned(),
+            groups: "А-08-19".to_owned(),
+            person: "Иванов И.И.".to_owned(),
+            time: ClassesTime {
+                start: NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
+            },
+            number: 1,
+            status: ClassesStatus::Normal,
+        }
+    }
+
+    #[test]
+    fn test_week_to_ics() {
+        let date = NaiveDate::from_ymd_opt(2026, 10, 12).unwrap();
+        let mut cancelled = classes("Физика; лабораторная, часть 2", (10, 55), (12, 30));
+        cancelled.status = ClassesStatus::Cancelled;
+        let week = Week {
+            week_of_year: 42,
+            week_of_semester: 7,
+            first_day_of_week: date,
+            days: vec![Day {
+                day_of_week: 1,
+                date,
+                classes: vec![classes("Математика", (9, 20), (10, 55)), cancelled],
+                source: Default::default(),
+                expires_at: None,
+                before_semester_start: false,
+            }],
+            period_type: PeriodType::Study,
+            last_synced_at: None,
+        };
+        let stamp = date.and_hms_opt(6, 0, 0).unwrap();
+        let ics = week_to_ics("А-08-19", &week, stamp);
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(2, ics.matches("BEGIN:VEVENT").count());
+        assert!(ics.contains("UID:20261012-0920-А-08-19@mpeix\r\n"));
+        assert!(ics.contains("DTSTAMP:20261012T060000Z\r\n"));
+        assert!(ics.contains("DTSTART;TZID=Europe/Moscow:20261012T092000\r\n"));
+        assert!(ics.contains("DTEND;TZID=Europe/Moscow:20261012T105500\r\n"));
+        assert!(ics.contains("SUMMARY:Математика (Лекция)\r\n"));
+        assert!(ics.contains("DESCRIPTION:Иванов И.И.\\nА-08-19\r\n"));
+        assert!(ics.contains("STATUS:CANCELLED\r\n"));
+        // escaped and folded line of the cancelled classes
+        assert!(ics.contains("SUMMARY:Физика\\; лабораторная\\,"));
+        assert!(ics.contains("\r\n "));
+        assert!(ics.lines().all(|line| line.len() <= 75));
+    }
+}
@@ -0,0 +1,45 @@
+//! Lenient deserialization mode for the payloads of the old clients and caches:
+//! unknown values of the enums do not fail the whole payload.
+
+use std::cell::Cell;
+
+use serde::{
+    de::{DeserializeOwned, IntoDeserializer},
+    Deserialize, Deserializer,
+};
+
+thread_local! {
+    static LENIENT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Deserialize `T` in the lenient mode: unknown values of the enums are replaced with
+/// [crate::ClassesType::Undefined] or the default values of the other enums instead of failing.
+///
+/// ```ignore
+/// let Lenient(schedule) = serde_json::from_str::<Lenient<Schedule>>(payload)?;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lenient<T>(pub T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Lenient<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let previous = LENIENT.with(|it| it.replace(true));
+        let result = T::deserialize(deserializer);
+        LENIENT.with(|it| it.set(previous));
+        result.map(Lenient)
+    }
+}
+
+/// Deserialize the enum, in the lenient mode unknown values are replaced with the default one
+pub(crate) fn enum_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned + Default,
+{
+    let value = String::deserialize(deserializer)?;
+    match T::deserialize(IntoDeserializer::<D::Error>::into_deserializer(value)) {
+        Ok(value) => Ok(value),
+        Err(_) if LENIENT.with(Cell::get) => Ok(T::default()),
+        Err(e) => Err(e),
+    }
+}
@@ -1,8 +1,13 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
 
-use chrono::{NaiveDate, NaiveTime};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
 use serde::{Deserialize, Serialize};
 
+pub mod ics;
+mod lenient;
+
+pub use lenient::Lenient;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Schedule {
@@ -15,8 +20,12 @@ pub struct Schedule {
 #[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ScheduleType {
+    /// Old clients send the types in lowercase, like in the urls
+    #[serde(alias = "group")]
     Group,
+    #[serde(alias = "person")]
     Person,
+    #[serde(alias = "room")]
     Room,
 }
 
@@ -58,39 +67,196 @@ impl Display for ParseScheduleTypeError {
     }
 }
 
+/// Id of the schedule on the MPEI side, not to be confused with the other numeric ids
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Hash, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct ScheduleId(pub i64);
+
+impl Display for ScheduleId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Week {
     pub week_of_year: u8,
     pub week_of_semester: i8,
+    #[serde(alias = "firstDay")]
     pub first_day_of_week: NaiveDate,
     pub days: Vec<Day>,
+    /// Old cached weeks do not have this field
+    #[serde(default, deserialize_with = "lenient::enum_or_default")]
+    pub period_type: PeriodType,
+    /// When the week was fetched from MPEI for the last time,
+    /// so the clients can tell how fresh the cached week is during MPEI outages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_synced_at: Option<NaiveDateTime>,
+}
+
+/// Schedule requested in the batch, e.g. `{"name": "А-08-19", "type": "GROUP"}`
+#[derive(Debug, Serialize, Deserialize, Clone, Hash, PartialEq, Eq)]
+pub struct ScheduleRef {
+    pub name: String,
+    pub r#type: ScheduleType,
+}
+
+/// Result of the single schedule of the batch: either schedule or error
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchScheduleResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<BatchScheduleError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum BatchScheduleError {
+    InvalidRequest,
+    NotFound,
+    /// MPEI backend is unavailable, the schedule can be requested again later
+    Unavailable,
+    Internal,
+}
+
+/// Rules of the non-standard numbering of academic weeks
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftRules {
+    pub rules: Vec<ShiftRuleDescription>,
+    /// How the week number of the requested date was calculated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<WeekNumberProvenance>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShiftRuleDescription {
+    pub year: i32,
+    /// `spring` or `fall`
+    pub semester: String,
+    pub first_day: NaiveDate,
+    pub week_number: Option<i8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WeekNumberProvenance {
+    pub date: NaiveDate,
+    /// `None` if the date is out of the study weeks
+    pub week_of_semester: Option<u8>,
+    pub source: WeekNumberSource,
+    /// Semester, first study day and its week number which the week number is counted from
+    pub semester: Option<String>,
+    pub first_day: Option<NaiveDate>,
+    pub first_week_number: Option<i8>,
+}
+
+/// Rule (or branch of the default algorithm) which produced the week number
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WeekNumberSource {
+    /// Rule from the schedule shift config
+    ShiftRule,
+    /// Fall semester starts on the 1st of September
+    FirstOfSeptember,
+    /// Fall semester starts on the 2nd of September, because the 1st is Sunday
+    SecondOfSeptember,
+    /// Spring semester starts on the first Monday of February
+    FirstMondayOfFebruary,
+    /// The first week of January is always non-studying
+    JanuaryWeekend,
+    /// The date is out of the study weeks of any semester
+    OutOfSemester,
+}
+
+/// Period of the academic year which the week belongs to
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum PeriodType {
+    #[default]
+    Study,
+    /// Exams period after the last study week of the semester
+    Session,
+    Vacation,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Day {
+    /// From `1` (Monday) to `7` (Sunday), some evening programs have classes on Sundays
     pub day_of_week: u8,
     pub date: NaiveDate,
     pub classes: Vec<Classes>,
+    /// Old cached days do not have this field
+    #[serde(default, deserialize_with = "lenient::enum_or_default")]
+    pub source: DaySource,
+    /// Last day the manual override is applied, only for overridden days
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<NaiveDate>,
+    /// The semester starts later in the week of this day, so the day has no classes yet
+    #[serde(default)]
+    pub before_semester_start: bool,
+}
+
+/// Where the classes of the day come from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DaySource {
+    #[default]
+    Mpei,
+    /// Manual correction, e.g. sent by the dean's office before MPEI updates the site
+    Override,
 }
 
+/// Manual correction of the single day of the schedule,
+/// replaces all classes of the day until `expires_at` (inclusive)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
+pub struct DayOverride {
+    pub date: NaiveDate,
+    pub classes: Vec<Classes>,
+    pub expires_at: NaiveDate,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct Classes {
     pub name: String,
+    #[serde(deserialize_with = "lenient::enum_or_default")]
     pub r#type: ClassesType,
+    #[serde(alias = "typeRaw")]
     pub raw_type: String,
+    #[serde(alias = "room")]
     pub place: String,
     pub groups: String, // TODO: split into separate fields: stream, group, sub_group
+    #[serde(alias = "teacher")]
     pub person: String,
     pub time: ClassesTime,
     pub number: i8,
+    /// Old cached classes do not have this field
+    #[serde(default, deserialize_with = "lenient::enum_or_default")]
+    pub status: ClassesStatus,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// Status of the classes, if MPEI marks them as cancelled or moved
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ClassesStatus {
+    #[default]
+    Normal,
+    #[serde(alias = "CANCELED")]
+    Cancelled,
+    Moved,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ClassesType {
+    #[default]
     Undefined,
     Lecture,
     Practice,
@@ -100,17 +266,424 @@ pub enum ClassesType {
     Exam,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl ClassesType {
+    /// Exams and consultations before them are held during the exam session
+    pub fn is_session(&self) -> bool {
+        matches!(self, Self::Exam | Self::Consultation)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct ClassesTime {
     pub start: NaiveTime,
     pub end: NaiveTime,
 }
 
+/// Lightweight search result for the keyboard suggestions, without the description
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ScheduleAutocompleteItem {
+    pub name: String,
+    pub r#type: ScheduleType,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ScheduleSearchResult {
     pub name: String,
     pub description: String,
     pub id: String,
     pub r#type: ScheduleType,
+    /// Position of the person, e.g. `доцент`, parsed from the description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    /// Department of the person, e.g. `ВМСС`, parsed from the description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub department: Option<String>,
+}
+
+/// Result of the schedule integrity check:
+/// the week fetched from MPEI bypassing the cache compared with the cached one
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleVerification {
+    pub name: String,
+    pub r#type: ScheduleType,
+    pub week_start: NaiveDate,
+    /// `None` if the week is not cached
+    pub cache: Option<CacheMetadata>,
+    /// Changes of the remote week relative to the cached one, empty if the week is not cached
+    pub changes: Vec<ClassesChange>,
+}
+
+/// Changes of the week fetched from MPEI relative to its cached version,
+/// e.g. to show "what changed" instead of the full schedule
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleCacheDiff {
+    pub name: String,
+    pub r#type: ScheduleType,
+    pub week_start: NaiveDate,
+    /// State of the cache entry before the comparison, `None` if the week was not cached
+    pub cache: Option<CacheMetadata>,
+    /// `false` if the cached week is too fresh to request MPEI again, there are no changes then
+    pub remote_fetched: bool,
+    /// Changes of the remote week relative to the cached one, empty if the week was not cached
+    pub changes: Vec<ClassesChange>,
+}
+
+/// State of the cache entry at the moment of the check
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheMetadata {
+    pub created_at: NaiveDateTime,
+    pub age_seconds: i64,
+    /// Zero if the entry is expired
+    pub ttl_seconds: i64,
+    pub expired: bool,
+    pub hits: u32,
+}
+
+/// Rooms without classes at the requested time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeRooms {
+    pub date: NaiveDate,
+    pub time: NaiveTime,
+    /// Sorted by name
+    pub rooms: Vec<FreeRoom>,
+    /// Rooms whose schedules are unavailable, they may be busy
+    pub unknown: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FreeRoom {
+    pub name: String,
+    /// Start of the next classes of the day in the room, `None` if there are no more classes
+    pub free_until: Option<NaiveTime>,
+}
+
+/// Changes of the classes between two weeks of the schedule,
+/// e.g. what changes next week compared to the current one
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleDiff {
+    pub name: String,
+    pub r#type: ScheduleType,
+    pub from_week_start: NaiveDate,
+    pub to_week_start: NaiveDate,
+    /// Changes of the `to` week relative to the `from` week, with the dates of the `to` week
+    pub changes: Vec<ClassesChange>,
+}
+
+/// Changes of the schedule detected during the last days
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleChangelog {
+    pub name: String,
+    pub r#type: ScheduleType,
+    pub since: NaiveDateTime,
+    /// Changes in the order of detection
+    pub changes: Vec<ClassesChange>,
+}
+
+/// Single change of the classes between two versions of the schedule
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassesChange {
+    pub date: NaiveDate,
+    pub kind: ClassesChangeKind,
+    /// `None` for added classes
+    pub before: Option<Classes>,
+    /// `None` for removed classes
+    pub after: Option<Classes>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ClassesChangeKind {
+    Added,
+    Removed,
+    /// Other classes at the same time slot
+    Changed,
+}
+
+/// Latency of the current week schedule requests since the start of the service,
+/// by the source of the response: `cache`, `staleCache`, `remote` or `error`.
+/// Collected to compare the fetch strategies of the schedule.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleFetchLatency {
+    pub strategy: String,
+    pub since: NaiveDateTime,
+    pub sources: BTreeMap<String, LatencySummary>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub count: u64,
+    pub avg_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Stats of the calls to the MPEI backend during the hour, by endpoint and response status.
+/// Status is `0` if the call failed without a response, e.g. on timeout.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamCallStats {
+    pub hour: NaiveDateTime,
+    pub endpoint: String,
+    pub status: u16,
+    pub count: i64,
+    pub avg_latency_ms: i64,
+    pub max_latency_ms: i64,
+    pub total_bytes: i64,
+}
+
+/// State of the service component on the public status page
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentHealth {
+    Ok,
+    /// The component works, but some of its features are unavailable, e.g. stale schedules
+    Degraded,
+    Down,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ComponentStatus {
+    pub name: String,
+    pub status: ComponentHealth,
+    /// When the component got the current status, or when the service started
+    pub last_change: NaiveDateTime,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<String>,
+}
+
+/// Readiness of all components of the service, the overall status is the worst one
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ServiceStatus {
+    pub status: ComponentHealth,
+    pub checked_at: NaiveDateTime,
+    pub components: Vec<ComponentStatus>,
+}
+
+/// Most requested schedules during the last days,
+/// e.g. for the "popular groups" suggestions for the new users
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendingSchedules {
+    pub window_days: u32,
+    /// Sorted by the number of requests in descending order
+    pub items: Vec<TrendingSchedule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TrendingSchedule {
+    pub name: String,
+    pub r#type: ScheduleType,
+    /// Number of requests during the window
+    pub requests: i64,
+}
+
+/// Exams and consultations of the nearest exam session
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSchedule {
+    pub name: String,
+    pub r#type: ScheduleType,
+    /// Only days with exams or consultations, sorted by date.
+    /// Empty if MPEI has not published the session timetable yet.
+    pub days: Vec<Day>,
+}
+
+impl SessionSchedule {
+    /// Collect the exams and consultations from the weeks of the session
+    pub fn from_weeks(name: String, r#type: ScheduleType, weeks: Vec<Week>) -> Self {
+        let days = weeks
+            .into_iter()
+            .flat_map(|week| week.days)
+            .filter_map(|mut day| {
+                day.classes.retain(|classes| classes.r#type.is_session());
+                (!day.classes.is_empty()).then_some(day)
+            })
+            .collect();
+        Self { name, r#type, days }
+    }
+}
+
+/// Summary of validation warnings of the schedule for the last days
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleQuality {
+    pub name: String,
+    pub r#type: ScheduleType,
+    pub overlapping_classes: i64,
+    pub missing_rooms: i64,
+    pub unknown_class_types: i64,
+}
+
+/// Range of days of the week to display, e.g. `mon-sat` for evening students
+/// who do not want to see Sunday at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DaysRange {
+    pub first: Weekday,
+    pub last: Weekday,
+}
+
+#[derive(Debug)]
+pub struct ParseDaysRangeError(String);
+
+impl DaysRange {
+    pub const FULL_WEEK: DaysRange = DaysRange {
+        first: Weekday::Mon,
+        last: Weekday::Sun,
+    };
+
+    pub fn contains(&self, weekday: Weekday) -> bool {
+        (self.first.number_from_monday()..=self.last.number_from_monday())
+            .contains(&weekday.number_from_monday())
+    }
+}
+
+impl Default for DaysRange {
+    fn default() -> Self {
+        Self::FULL_WEEK
+    }
+}
+
+impl Display for DaysRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |it: Weekday| it.to_string().to_lowercase();
+        write!(f, "{}-{}", name(self.first), name(self.last))
+    }
+}
+
+impl FromStr for DaysRange {
+    type Err = ParseDaysRangeError;
+
+    /// Parse strings like `mon-sat` or `tue-fri`, weeks always start on Monday
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseDaysRangeError(s.to_owned());
+        let (first, last) = s.split_once('-').ok_or_else(error)?;
+        let first = first.trim().parse::<Weekday>().map_err(|_| error())?;
+        let last = last.trim().parse::<Weekday>().map_err(|_| error())?;
+        if first.number_from_monday() > last.number_from_monday() {
+            return Err(error());
+        }
+        Ok(Self { first, last })
+    }
+}
+
+impl Display for ParseDaysRangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid days range: {}, expected something like 'mon-sat'",
+            self.0
+        )
+    }
+}
+
+impl Week {
+    /// Remove days outside of the `range` and empty days at the end of the week
+    pub fn retain_days(&mut self, range: &DaysRange) {
+        self.days.retain(|day| range.contains(day.date.weekday()));
+        while self.days.last().is_some_and(|day| day.classes.is_empty()) {
+            self.days.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime, Weekday};
+
+    use crate::{
+        Classes, ClassesStatus, ClassesTime, ClassesType, Day, DaySource, DaysRange, PeriodType,
+        ScheduleId, ScheduleType, SessionSchedule, Week,
+    };
+
+    #[test]
+    fn test_schedule_id_is_plain_number() {
+        assert_eq!("13567", serde_json::to_string(&ScheduleId(13567)).unwrap());
+        assert_eq!(ScheduleId(42), serde_json::from_str("42").unwrap());
+        assert_eq!("13567", ScheduleId(13567).to_string());
+    }
+
+    #[test]
+    fn test_parse_days_range() {
+        let range = "mon-sat".parse::<DaysRange>().unwrap();
+        assert_eq!(
+            DaysRange {
+                first: Weekday::Mon,
+                last: Weekday::Sat
+            },
+            range
+        );
+        assert_eq!("mon-sat", range.to_string());
+        assert!(range.contains(Weekday::Sat));
+        assert!(!range.contains(Weekday::Sun));
+        assert!("sat-mon".parse::<DaysRange>().is_err());
+        assert!("mon".parse::<DaysRange>().is_err());
+        assert!("foo-bar".parse::<DaysRange>().is_err());
+    }
+
+    fn day(date: NaiveDate, types: &[ClassesType]) -> Day {
+        Day {
+            day_of_week: 1,
+            date,
+            classes: types
+                .iter()
+                .map(|r#type| Classes {
+                    name: "Физика".to_string(),
+                    r#type: r#type.clone(),
+                    raw_type: String::new(),
+                    place: "Б-114".to_string(),
+                    groups: String::new(),
+                    person: String::new(),
+                    time: ClassesTime {
+                        start: NaiveTime::from_hms_opt(9, 20, 0).unwrap(),
+                        end: NaiveTime::from_hms_opt(10, 55, 0).unwrap(),
+                    },
+                    number: 1,
+                    status: ClassesStatus::Normal,
+                })
+                .collect(),
+            source: DaySource::Mpei,
+            expires_at: None,
+            before_semester_start: false,
+        }
+    }
+
+    #[test]
+    fn test_session_schedule_from_weeks() {
+        let first = NaiveDate::from_ymd_opt(2023, 1, 9).unwrap();
+        let second = NaiveDate::from_ymd_opt(2023, 1, 12).unwrap();
+        let week = Week {
+            week_of_year: 2,
+            week_of_semester: -1,
+            first_day_of_week: first,
+            days: vec![
+                day(first, &[ClassesType::Lecture, ClassesType::Consultation]),
+                day(first.succ_opt().unwrap(), &[ClassesType::Lab]),
+                day(second, &[ClassesType::Exam]),
+            ],
+            period_type: PeriodType::Session,
+            last_synced_at: None,
+        };
+        let session =
+            SessionSchedule::from_weeks("А-08-19".to_string(), ScheduleType::Group, vec![week]);
+        let days = session
+            .days
+            .iter()
+            .map(|day| (day.date, day.classes.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![(first, 1), (second, 1)], days);
+        assert_eq!(ClassesType::Consultation, session.days[0].classes[0].r#type);
+    }
 }
@@ -0,0 +1,55 @@
+//! Payloads of the old mobile clients, which must still be accepted
+use chrono::NaiveDate;
+use domain_schedule_models::{
+    ClassesStatus, ClassesType, Lenient, ScheduleRef, ScheduleType, Week,
+};
+use serde::Deserialize;
+
+const ANDROID_1_2_WEEK: &str = include_str!("fixtures/android_1.2_week.json");
+const IOS_2_0_BATCH_REQUEST: &str = include_str!("fixtures/ios_2.0_batch_request.json");
+
+#[test]
+fn unknown_classes_type_fails_in_strict_mode() {
+    let error = serde_json::from_str::<Week>(ANDROID_1_2_WEEK).unwrap_err();
+    assert!(error.to_string().contains("SEMINAR"), "{error}");
+}
+
+#[test]
+fn old_field_names_and_unknown_enums_are_accepted_in_lenient_mode() {
+    let Lenient(week) = serde_json::from_str::<Lenient<Week>>(ANDROID_1_2_WEEK).unwrap();
+    assert_eq!(
+        week.first_day_of_week,
+        NaiveDate::from_ymd_opt(2023, 2, 13).unwrap()
+    );
+    let classes = &week.days[0].classes;
+    assert_eq!(classes[0].r#type, ClassesType::Lecture);
+    assert_eq!(classes[0].raw_type, "Лекция");
+    assert_eq!(classes[0].place, "Б-303");
+    assert_eq!(classes[0].person, "Иванов И.И.");
+    assert_eq!(classes[0].status, ClassesStatus::Normal);
+    assert_eq!(classes[1].r#type, ClassesType::Undefined);
+    assert_eq!(classes[1].status, ClassesStatus::Cancelled);
+
+    // the lenient mode is not leaked to the next payloads
+    assert!(serde_json::from_str::<Week>(ANDROID_1_2_WEEK).is_err());
+}
+
+#[test]
+fn lowercase_schedule_types_are_accepted() {
+    #[derive(Deserialize)]
+    struct BatchScheduleRequest {
+        schedules: Vec<ScheduleRef>,
+    }
+    let request = serde_json::from_str::<BatchScheduleRequest>(IOS_2_0_BATCH_REQUEST).unwrap();
+    let types = request
+        .schedules
+        .iter()
+        .map(|it| &it.r#type)
+        .collect::<Vec<_>>();
+    assert_eq!(types, vec![&ScheduleType::Group, &ScheduleType::Person]);
+    // new names are always sent back
+    assert_eq!(
+        serde_json::to_string(&ScheduleType::Group).unwrap(),
+        "\"GROUP\""
+    );
+}
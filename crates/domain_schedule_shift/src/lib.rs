@@ -10,17 +10,21 @@ use toml::Table;
 #[derive(Debug, PartialEq, Eq)]
 pub struct ScheduleShift(HashMap<(Year, ShiftedSemester), ShiftRule>);
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub struct Year(i32);
 
 impl Year {
     pub fn new(year: i32) -> Year {
         Self(year)
     }
+
+    pub fn value(&self) -> i32 {
+        self.0
+    }
 }
 
 /// Type of semester with non-standard study week numbers
-#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 pub enum ShiftedSemester {
     Spring,
     Fall,
@@ -49,6 +53,17 @@ impl ScheduleShift {
     pub fn get(&self, year: Year, semester: ShiftedSemester) -> Option<&ShiftRule> {
         self.0.get(&(year, semester))
     }
+
+    /// Get all shift rules in chronological order
+    pub fn rules(&self) -> Vec<(&Year, &ShiftedSemester, &ShiftRule)> {
+        let mut rules = self
+            .0
+            .iter()
+            .map(|((year, semester), rule)| (year, semester, rule))
+            .collect::<Vec<_>>();
+        rules.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        rules
+    }
 }
 
 impl FromStr for ScheduleShift {
@@ -61,7 +76,7 @@ impl FromStr for ScheduleShift {
         for (year, semester_rule) in shifts_table {
             let year = Year::new(year.parse()?);
             for semester in SEMESTERS {
-                if let Some(rule) = semester_rule.get(&semester.to_string()) {
+                if let Some(rule) = semester_rule.get(semester.to_string()) {
                     let first_day = rule
                         .get("first-day")
                         .and_then(|it| it.as_str())
@@ -145,6 +160,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rules_are_sorted_test() {
+        let toml_content = r#"
+        [2023]
+        fall = { first-day = "2023-09-04" }
+        spring = { first-day = "2023-02-08", week-number = 0 }
+        [2021]
+        spring = { first-day = "2021-02-15" }
+        "#;
+        let shift = ScheduleShift::from_str(toml_content).unwrap();
+        let rules = shift
+            .rules()
+            .into_iter()
+            .map(|(year, semester, _)| (year.value(), semester.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (2021, ShiftedSemester::Spring),
+                (2023, ShiftedSemester::Spring),
+                (2023, ShiftedSemester::Fall),
+            ],
+            rules,
+        );
+    }
+
     #[test]
     fn from_str_invalid_year_test() {
         let toml_content = r#"
@@ -2,7 +2,10 @@ use std::sync::Arc;
 
 use crate::{
     telegram_api::TelegramApi,
-    usecases::{DeleteMessageUseCase, ReplyToTelegramUseCase, SetWebhookUseCase},
+    usecases::{
+        DeleteMessageUseCase, GetChatMemberStatusUseCase, ReplyToTelegramUseCase,
+        SendDocumentUseCase, SetWebhookUseCase,
+    },
 };
 
 impl SetWebhookUseCase {
@@ -17,8 +20,20 @@ impl ReplyToTelegramUseCase {
     }
 }
 
+impl SendDocumentUseCase {
+    pub fn new(telegram_api: Arc<TelegramApi>) -> Self {
+        Self(telegram_api)
+    }
+}
+
 impl DeleteMessageUseCase {
     pub fn new(telegram_api: Arc<TelegramApi>) -> Self {
         Self(telegram_api)
     }
 }
+
+impl GetChatMemberStatusUseCase {
+    pub fn new(telegram_api: Arc<TelegramApi>) -> Self {
+        Self(telegram_api)
+    }
+}
@@ -28,16 +28,43 @@ pub struct Message {
 #[derive(Debug, Deserialize)]
 pub struct CallbackQuery {
     pub id: String,
+    pub from: User,
     pub message: Option<Message>,
     pub data: Option<String>,
 }
 
+/// https://core.telegram.org/bots/api/#getchatmember
+#[derive(Debug, Deserialize)]
+pub struct ChatMemberResponse {
+    pub ok: bool,
+    pub description: Option<String>,
+    pub result: Option<ChatMember>,
+}
+
+/// https://core.telegram.org/bots/api/#chatmember
+#[derive(Debug, Deserialize)]
+pub struct ChatMember {
+    pub status: ChatMemberStatus,
+}
+
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatMemberStatus {
+    Creator,
+    Administrator,
+    Member,
+    Restricted,
+    Left,
+    Kicked,
+}
+
 /// https://core.telegram.org/bots/api/#user
 #[derive(Debug, Deserialize)]
 pub struct User {
     pub id: i64,
     pub is_bot: bool,
     pub first_name: String,
+    pub username: Option<String>,
 }
 
 /// https://core.telegram.org/bots/api/#chat
@@ -49,7 +76,7 @@ pub struct Chat {
     pub title: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum ChatType {
     Private,
@@ -57,15 +84,10 @@ pub enum ChatType {
     #[serde(alias = "supergroup")]
     SuperGroup,
     Channel,
+    #[default]
     Unknown,
 }
 
-impl Default for ChatType {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 /// https://core.telegram.org/bots/api/#inlinekeyboardmarkup
 #[derive(Debug, Serialize, Clone)]
 pub struct InlineKeyboardMarkup {
@@ -84,6 +106,8 @@ pub struct InlineKeyboardButton {
 pub struct ReplyKeyboardMarkup {
     pub keyboard: Vec<Vec<KeyboardButton>>,
     pub one_time_keyboard: bool,
+    /// Fit the keyboard height to the buttons, instead of the height of the system keyboard
+    pub resize_keyboard: bool,
 }
 
 /// https://core.telegram.org/bots/api/#keyboardbutton
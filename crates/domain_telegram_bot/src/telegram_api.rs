@@ -1,7 +1,7 @@
 use common_rust::env;
-use restix::{api, get};
+use restix::{api, get, post, Multipart};
 
-use crate::BaseResponse;
+use crate::{BaseResponse, ChatMemberResponse};
 
 #[api]
 pub trait TelegramApi {
@@ -19,6 +19,26 @@ pub trait TelegramApi {
     #[get("/deleteMessage")]
     async fn delete_message(&self, #[query] chat_id: i64, #[query] message_id: i64)
         -> BaseResponse;
+
+    #[get("/getChatMember")]
+    async fn get_chat_member(
+        &self,
+        #[query] chat_id: i64,
+        #[query] user_id: i64,
+    ) -> ChatMemberResponse;
+
+    #[post("/sendDocument")]
+    async fn send_document(&self, #[multipart] form: Multipart) -> BaseResponse;
+}
+
+/// Boundary of the `multipart/form-data` parts, must not occur in the uploaded files
+const MULTIPART_BOUNDARY: &str = "mpeix-telegram-document-boundary";
+
+/// Form of the `sendDocument` method with the file uploaded to the chat
+pub(crate) fn document_form(chat_id: i64, file_name: &str, content: &[u8]) -> Multipart {
+    Multipart::with_boundary(MULTIPART_BOUNDARY)
+        .text("chat_id", chat_id)
+        .file("document", file_name, "application/octet-stream", content)
 }
 
 impl Default for TelegramApi {
@@ -42,3 +62,25 @@ impl Default for TelegramApi {
             .expect("Error while building TelegramApi")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::document_form;
+
+    #[test]
+    fn test_multipart_document_body() {
+        let form = document_form(-42, "week \"1\".ics", b"BEGIN:VCALENDAR");
+        let body = String::from_utf8(form.into_body()).unwrap();
+        assert_eq!(
+            body,
+            "--mpeix-telegram-document-boundary\r\n\
+            Content-Disposition: form-data; name=\"chat_id\"\r\n\r\n\
+            -42\r\n\
+            --mpeix-telegram-document-boundary\r\n\
+            Content-Disposition: form-data; name=\"document\"; filename=\"week _1_.ics\"\r\n\
+            Content-Type: application/octet-stream\r\n\r\n\
+            BEGIN:VCALENDAR\r\n\
+            --mpeix-telegram-document-boundary--\r\n"
+        );
+    }
+}
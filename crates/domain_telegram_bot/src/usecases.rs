@@ -5,10 +5,13 @@ use common_errors::errors::CommonError;
 use common_restix::ResultExt;
 use log::{error, info};
 
-use crate::{telegram_api::TelegramApi, BaseResponse, CommonKeyboardMarkup};
+use crate::{
+    telegram_api::{document_form, TelegramApi},
+    BaseResponse, ChatMemberResponse, ChatMemberStatus, CommonKeyboardMarkup,
+};
 
 /// Set weebhookfor Telegram Bot API manually.
-/// This use case must be started **STRICTLY** before the server starts.
+/// This use case must be complete **STRICTLY** before the app becomes ready.
 pub struct SetWebhookUseCase(pub(crate) Arc<TelegramApi>);
 
 impl SetWebhookUseCase {
@@ -27,20 +30,34 @@ impl ReplyToTelegramUseCase {
         chat_id: i64,
         keyboard: Option<CommonKeyboardMarkup>,
     ) -> anyhow::Result<()> {
-        let keyboard = if let Some(keyboard) = keyboard {
-            Some(
-                match keyboard {
-                    CommonKeyboardMarkup::Inline(kb) => serde_json::to_string(&kb),
-                    CommonKeyboardMarkup::Reply(kb) => serde_json::to_string(&kb),
-                    CommonKeyboardMarkup::Remove(kb) => serde_json::to_string(&kb),
-                }
-                .with_context(|| {
-                    CommonError::internal("Error while serializing telegram keyboard to JSON")
-                })?,
-            )
-        } else {
-            None
+        let keyboard = Self::serialize_keyboard(keyboard)?;
+        self.reply_with_serialized_keyboard(text, chat_id, keyboard)
+            .await
+    }
+
+    /// Serialize the keyboard for [ReplyToTelegramUseCase::reply_with_serialized_keyboard]
+    pub fn serialize_keyboard(
+        keyboard: Option<CommonKeyboardMarkup>,
+    ) -> anyhow::Result<Option<String>> {
+        let Some(keyboard) = keyboard else {
+            return Ok(None);
         };
+        match keyboard {
+            CommonKeyboardMarkup::Inline(kb) => serde_json::to_string(&kb),
+            CommonKeyboardMarkup::Reply(kb) => serde_json::to_string(&kb),
+            CommonKeyboardMarkup::Remove(kb) => serde_json::to_string(&kb),
+        }
+        .map(Some)
+        .with_context(|| CommonError::internal("Error while serializing telegram keyboard to JSON"))
+    }
+
+    /// The same as [ReplyToTelegramUseCase::reply], e.g. for the repeated attempts
+    pub async fn reply_with_serialized_keyboard(
+        &self,
+        text: &str,
+        chat_id: i64,
+        keyboard: Option<String>,
+    ) -> anyhow::Result<()> {
         self.0
             .send_message(chat_id, text, keyboard)
             .await
@@ -49,6 +66,24 @@ impl ReplyToTelegramUseCase {
     }
 }
 
+/// Send file to Telegram chat, e.g. `.ics` file of the week schedule
+pub struct SendDocumentUseCase(pub(crate) Arc<TelegramApi>);
+
+impl SendDocumentUseCase {
+    pub async fn send_document(
+        &self,
+        chat_id: i64,
+        file_name: &str,
+        content: &[u8],
+    ) -> anyhow::Result<()> {
+        self.0
+            .send_document(document_form(chat_id, file_name, content))
+            .await
+            .with_telegram_error()
+            .with_context(|| "Error while sending Telegram document")
+    }
+}
+
 /// Delete message in Telegram chat
 pub struct DeleteMessageUseCase(pub(crate) Arc<TelegramApi>);
 
@@ -62,6 +97,32 @@ impl DeleteMessageUseCase {
     }
 }
 
+/// Get status of the user in Telegram chat
+pub struct GetChatMemberStatusUseCase(pub(crate) Arc<TelegramApi>);
+
+impl GetChatMemberStatusUseCase {
+    pub async fn get_status(&self, chat_id: i64, user_id: i64) -> anyhow::Result<ChatMemberStatus> {
+        match self
+            .0
+            .get_chat_member(chat_id, user_id)
+            .await
+            .with_common_error()?
+        {
+            ChatMemberResponse {
+                ok: true,
+                result: Some(member),
+                ..
+            } => Ok(member.status),
+            ChatMemberResponse { description, .. } => {
+                let description =
+                    description.unwrap_or_else(|| "Error description was not provided".to_owned());
+                error!("Telegram Api rejected mpeix request with description: {description}");
+                bail!(CommonError::internal(description))
+            }
+        }
+    }
+}
+
 trait BaseResponseExt<T>
 where
     Self: Sized,
@@ -10,6 +10,27 @@ pub struct BaseResponseError {
     pub error_msg: String,
 }
 
+/// https://dev.vk.com/method/messages.getConversationMembers
+#[derive(Debug, Deserialize)]
+pub struct ConversationMembersResponse {
+    pub response: Option<ConversationMembers>,
+    pub error: Option<BaseResponseError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationMembers {
+    pub items: Vec<ConversationMember>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConversationMember {
+    pub member_id: i64,
+    #[serde(default)]
+    pub is_admin: bool,
+    #[serde(default)]
+    pub is_owner: bool,
+}
+
 /// https://dev.vk.com/api/callback/getting-started
 /// https://dev.vk.com/api/community-events/json-schema
 #[derive(Debug, Deserialize)]
@@ -21,20 +42,15 @@ pub struct VkCallbackRequest {
     pub object: Option<NewMessageObject>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum VkCallbackType {
     Confirmation,
     MessageNew,
+    #[default]
     Unknown,
 }
 
-impl Default for VkCallbackType {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 #[derive(Debug, Deserialize)]
 pub struct NewMessageObject {
     pub message: Message,
@@ -79,7 +95,7 @@ pub struct ClientInfo {
     pub carousel: bool,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum ButtonActionType {
     Text,
@@ -92,15 +108,10 @@ pub enum ButtonActionType {
     Callback,
     IntentSubscribe,
     IntentUnsubscribe,
+    #[default]
     Unknown,
 }
 
-impl Default for ButtonActionType {
-    fn default() -> Self {
-        Self::Unknown
-    }
-}
-
 #[derive(Debug, Serialize, Clone)]
 pub struct Keyboard {
     pub buttons: Vec<Vec<KeyboardButton>>,
@@ -5,7 +5,7 @@ use log::{error, info};
 
 use crate::{
     vk_api::{self, VkApi},
-    BaseResponse, BaseResponseError, Keyboard,
+    BaseResponse, BaseResponseError, ConversationMembersResponse, Keyboard,
 };
 
 /// Send message reply to VK
@@ -20,13 +20,27 @@ impl ReplyToVkUseCase {
         peer_id: i64,
         keyboard: Option<Keyboard>,
     ) -> anyhow::Result<()> {
-        let keyboard = if let Some(keyboard) = keyboard {
-            Some(serde_json::to_string(&keyboard).with_context(|| {
-                CommonError::internal("Error while serializing vk keyboard to JSON")
-            })?)
-        } else {
-            None
-        };
+        let keyboard = Self::serialize_keyboard(keyboard)?;
+        self.reply_with_serialized_keyboard(access_token, text, peer_id, keyboard)
+            .await
+    }
+
+    /// Serialize the keyboard for [ReplyToVkUseCase::reply_with_serialized_keyboard]
+    pub fn serialize_keyboard(keyboard: Option<Keyboard>) -> anyhow::Result<Option<String>> {
+        keyboard
+            .map(|it| serde_json::to_string(&it))
+            .transpose()
+            .with_context(|| CommonError::internal("Error while serializing vk keyboard to JSON"))
+    }
+
+    /// The same as [ReplyToVkUseCase::reply], e.g. for the repeated attempts
+    pub async fn reply_with_serialized_keyboard(
+        &self,
+        access_token: &str,
+        text: &str,
+        peer_id: i64,
+        keyboard: Option<String>,
+    ) -> anyhow::Result<()> {
         self.0
             .send_message(
                 vk_api::VK_API_VERSION,
@@ -41,6 +55,43 @@ impl ReplyToVkUseCase {
     }
 }
 
+/// Get admins and the owner of VK conversation.
+/// The bot must be an admin of the conversation itself, otherwise VK rejects the request.
+#[derive(Default)]
+pub struct GetConversationAdminsUseCase(VkApi);
+
+impl GetConversationAdminsUseCase {
+    pub async fn get_admin_ids(
+        &self,
+        access_token: &str,
+        peer_id: i64,
+    ) -> anyhow::Result<Vec<i64>> {
+        let response = self
+            .0
+            .get_conversation_members(vk_api::VK_API_VERSION, access_token, peer_id)
+            .await
+            .with_common_error()?;
+        match response {
+            ConversationMembersResponse {
+                response: Some(members),
+                ..
+            } => Ok(members
+                .items
+                .into_iter()
+                .filter(|it| it.is_admin || it.is_owner)
+                .map(|it| it.member_id)
+                .collect()),
+            ConversationMembersResponse { error, .. } => {
+                let error_msg = error
+                    .map(|it| it.error_msg)
+                    .unwrap_or_else(|| "Error description was not provided".to_owned());
+                error!("Vk Api rejected mpeix request with description: {error_msg}");
+                bail!(CommonError::internal(error_msg))
+            }
+        }
+    }
+}
+
 trait BaseResponseExt<T>
 where
     Self: Sized,
@@ -1,7 +1,7 @@
 use reqwest::{redirect::Policy, ClientBuilder};
 use restix::{api, get};
 
-use crate::BaseResponse;
+use crate::{BaseResponse, ConversationMembersResponse};
 
 pub const VK_API_VERSION: &str = "5.130";
 
@@ -17,6 +17,14 @@ pub trait VkApi {
         #[query] peer_id: i64,
         #[query] keyboard: Option<String>,
     ) -> BaseResponse;
+
+    #[get("/method/messages.getConversationMembers")]
+    async fn get_conversation_members(
+        &self,
+        #[query("v")] api_version: &str,
+        #[query] access_token: &str,
+        #[query] peer_id: i64,
+    ) -> ConversationMembersResponse;
 }
 
 impl Default for VkApi {
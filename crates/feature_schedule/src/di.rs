@@ -1,6 +1,10 @@
 use std::sync::Arc;
 
-use domain_schedule::usecases::{GetScheduleIdUseCase, GetScheduleUseCase, SearchScheduleUseCase};
+use domain_buildings::Buildings;
+use domain_schedule::usecases::{
+    AutocompleteScheduleUseCase, GetScheduleIdUseCase, GetScheduleUseCase, GetShiftRulesUseCase,
+    SearchScheduleUseCase,
+};
 
 use crate::v1::FeatureSchedule;
 
@@ -9,11 +13,17 @@ impl FeatureSchedule {
         get_schedule_id_use_case: Arc<GetScheduleIdUseCase>,
         get_schedule_use_case: Arc<GetScheduleUseCase>,
         search_schedule_use_case: Arc<SearchScheduleUseCase>,
+        buildings: Arc<Buildings>,
+        get_shift_rules_use_case: Arc<GetShiftRulesUseCase>,
+        autocomplete_schedule_use_case: Arc<AutocompleteScheduleUseCase>,
     ) -> Self {
         Self(
             get_schedule_id_use_case,
             get_schedule_use_case,
             search_schedule_use_case,
+            buildings,
+            get_shift_rules_use_case,
+            autocomplete_schedule_use_case,
         )
     }
 }
@@ -1,17 +1,39 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
 
+use anyhow::ensure;
+use chrono::NaiveDate;
+use common_errors::errors::{CommonError, CommonErrorExt};
+use domain_buildings::{Building, Buildings};
 use domain_mobile::AppVersion;
-use domain_schedule::usecases::{GetScheduleIdUseCase, GetScheduleUseCase, SearchScheduleUseCase};
-use domain_schedule_models::{ClassesType, Schedule, ScheduleSearchResult, ScheduleType};
+use domain_schedule::usecases::{
+    AutocompleteScheduleUseCase, GetScheduleIdUseCase, GetScheduleUseCase, GetShiftRulesUseCase,
+    SearchScheduleUseCase,
+};
+use domain_schedule_models::{
+    BatchScheduleError, BatchScheduleResult, ClassesType, DaysRange, Schedule,
+    ScheduleAutocompleteItem, ScheduleId, ScheduleRef, ScheduleSearchResult, ScheduleType,
+    SessionSchedule, ShiftRules,
+};
+use futures_util::future::join_all;
+use log::warn;
+
+/// Max number of schedules in the single batch request
+pub const MAX_BATCH_SIZE: usize = 10;
 
 pub struct FeatureSchedule(
     pub(crate) Arc<GetScheduleIdUseCase>,
     pub(crate) Arc<GetScheduleUseCase>,
     pub(crate) Arc<SearchScheduleUseCase>,
+    pub(crate) Arc<Buildings>,
+    pub(crate) Arc<GetShiftRulesUseCase>,
+    pub(crate) Arc<AutocompleteScheduleUseCase>,
 );
 
 impl FeatureSchedule {
-    pub async fn get_id(&self, name: String, r#type: ScheduleType) -> anyhow::Result<i64> {
+    pub async fn get_id(&self, name: String, r#type: ScheduleType) -> anyhow::Result<ScheduleId> {
         self.0.get_id(name, r#type).await
     }
 
@@ -21,8 +43,20 @@ impl FeatureSchedule {
         r#type: ScheduleType,
         offset: i32,
         app_version: Option<AppVersion>,
+        days_range: Option<DaysRange>,
+        force_refresh: bool,
     ) -> anyhow::Result<Schedule> {
-        let mut schedule = self.1.get_schedule(name, r#type, offset).await?;
+        let mut schedule = match force_refresh {
+            true => self.1.get_fresh_schedule(name, r#type, offset).await?,
+            false => self.1.get_schedule(name, r#type, offset).await?,
+        };
+
+        if let Some(days_range) = days_range {
+            schedule
+                .weeks
+                .iter_mut()
+                .for_each(|week| week.retain_days(&days_range));
+        }
 
         // for backward compatibility with old mpeix apps
         if let Some(mpeix_version) = app_version {
@@ -35,9 +69,7 @@ impl FeatureSchedule {
                     .iter_mut()
                     .flat_map(|week| week.days.iter_mut())
                     .flat_map(|day| day.classes.iter_mut())
-                    .filter(|class| {
-                        matches!(class.r#type, ClassesType::Exam | ClassesType::Consultation)
-                    })
+                    .filter(|class| class.r#type.is_session())
                     .for_each(|class| class.r#type = ClassesType::Undefined);
             }
         }
@@ -45,6 +77,69 @@ impl FeatureSchedule {
         Ok(schedule)
     }
 
+    /// Get exams and consultations of the nearest exam session
+    pub async fn get_session_schedule(
+        &self,
+        name: String,
+        r#type: ScheduleType,
+    ) -> anyhow::Result<SessionSchedule> {
+        self.1.get_session_schedule(name, r#type).await
+    }
+
+    /// Get the same week of several schedules at once, e.g. for dormitory dashboards.
+    /// Schedules are fetched concurrently, failure of one schedule does not fail the others.
+    pub async fn get_schedules_batch(
+        &self,
+        schedules: Vec<ScheduleRef>,
+        offset: i32,
+        app_version: Option<AppVersion>,
+    ) -> anyhow::Result<BTreeMap<String, BatchScheduleResult>> {
+        ensure!(
+            (1..=MAX_BATCH_SIZE).contains(&schedules.len()),
+            CommonError::user(format!(
+                "Batch should contain from 1 to {MAX_BATCH_SIZE} schedules"
+            ))
+        );
+        // the same schedule requested twice is fetched once
+        let mut unique = HashSet::new();
+        let schedules = schedules
+            .into_iter()
+            .filter(|it| unique.insert(it.to_owned()))
+            .collect::<Vec<_>>();
+        let results = join_all(schedules.iter().map(|it| {
+            self.get_schedule(
+                it.name.to_owned(),
+                it.r#type.to_owned(),
+                offset,
+                app_version.to_owned(),
+                None,
+                false,
+            )
+        }))
+        .await;
+
+        Ok(schedules
+            .into_iter()
+            .zip(results)
+            .map(|(schedule_ref, result)| {
+                let result = match result {
+                    Ok(schedule) => BatchScheduleResult {
+                        schedule: Some(schedule),
+                        error: None,
+                    },
+                    Err(e) => {
+                        warn!("Batch entry '{}' failed: {e}", schedule_ref.name);
+                        BatchScheduleResult {
+                            schedule: None,
+                            error: Some(map_batch_error(&e)),
+                        }
+                    }
+                };
+                (schedule_ref.name, result)
+            })
+            .collect())
+    }
+
     pub async fn search_schedule(
         &self,
         query: String,
@@ -52,4 +147,30 @@ impl FeatureSchedule {
     ) -> anyhow::Result<Vec<ScheduleSearchResult>> {
         self.2.search(query, r#type).await
     }
+
+    pub fn autocomplete_schedule(
+        &self,
+        query: &str,
+        r#type: Option<ScheduleType>,
+        limit: Option<usize>,
+    ) -> anyhow::Result<Vec<ScheduleAutocompleteItem>> {
+        self.5.autocomplete(query, r#type, limit)
+    }
+
+    pub fn get_buildings(&self) -> Vec<Building> {
+        self.3.all()
+    }
+
+    pub async fn get_shift_rules(&self, date: Option<NaiveDate>) -> anyhow::Result<ShiftRules> {
+        self.4.get_shift_rules(date).await
+    }
+}
+
+fn map_batch_error(error: &anyhow::Error) -> BatchScheduleError {
+    match error.as_common_error() {
+        Some(CommonError::UserError(_)) => BatchScheduleError::InvalidRequest,
+        Some(CommonError::NotFoundError(_)) => BatchScheduleError::NotFound,
+        Some(CommonError::GatewayError(_)) => BatchScheduleError::Unavailable,
+        _ => BatchScheduleError::Internal,
+    }
 }
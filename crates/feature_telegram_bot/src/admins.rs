@@ -0,0 +1,26 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use domain_bot::{notes::ChatAdminChecker, peer::repository::PlatformId};
+use domain_telegram_bot::{usecases::GetChatMemberStatusUseCase, ChatMemberStatus};
+
+/// Chat admins are the creator and the administrators of Telegram group
+pub struct TelegramChatAdminChecker(pub(crate) Arc<GetChatMemberStatusUseCase>);
+
+#[async_trait]
+impl ChatAdminChecker for TelegramChatAdminChecker {
+    async fn is_chat_admin(
+        &self,
+        platform_id: &PlatformId,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        let PlatformId::Telegram(chat_id) = platform_id else {
+            return Ok(false);
+        };
+        let status = self.0.get_status(chat_id.0, member_id).await?;
+        Ok(matches!(
+            status,
+            ChatMemberStatus::Creator | ChatMemberStatus::Administrator
+        ))
+    }
+}
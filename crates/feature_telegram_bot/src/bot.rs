@@ -1,29 +1,45 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use anyhow::{ensure, Context};
 use common_errors::errors::CommonError;
 use common_rust::env;
 use domain_bot::{
-    models::Reply, peer::repository::PlatformId, renderer::RenderTargetPlatform,
-    usecases::GenerateReplyUseCase,
+    models::{ChatMember, Document, Reply, ReplyContext},
+    peer::repository::{PlatformId, TelegramChatId},
+    renderer::RenderTargetPlatform,
+    theme::ThemeKind,
+    usecases::{
+        AbuseProtectionUseCase, AuditReplyUseCase, GenerateReplyUseCase, PushUseCase,
+        ReplyRetryUseCase,
+    },
 };
 use domain_telegram_bot::{
-    usecases::{DeleteMessageUseCase, ReplyToTelegramUseCase, SetWebhookUseCase},
-    ChatType, CommonKeyboardMarkup, InlineKeyboardButton, InlineKeyboardMarkup, Update,
+    usecases::{
+        DeleteMessageUseCase, ReplyToTelegramUseCase, SendDocumentUseCase, SetWebhookUseCase,
+    },
+    CommonKeyboardMarkup, Update, User,
 };
 use log::error;
 
+use crate::keyboards::render_keyboard;
+
 pub struct FeatureTelegramBot {
     pub(crate) config: Config,
     pub(crate) generate_reply_use_case: Arc<GenerateReplyUseCase>,
     pub(crate) set_webhook_use_case: Arc<SetWebhookUseCase>,
     pub(crate) reply_to_telegram_use_case: Arc<ReplyToTelegramUseCase>,
     pub(crate) delete_message_use_case: Arc<DeleteMessageUseCase>,
+    pub(crate) send_document_use_case: Arc<SendDocumentUseCase>,
+    pub(crate) audit_reply_use_case: Arc<AuditReplyUseCase>,
+    pub(crate) reply_retry_use_case: Arc<ReplyRetryUseCase>,
+    pub(crate) push_use_cases: Vec<Arc<dyn PushUseCase>>,
+    pub(crate) abuse_protection_use_case: Arc<AbuseProtectionUseCase>,
 }
 
 pub(crate) struct Config {
     secret: String,
     webhook_url: String,
+    theme: ThemeKind,
 }
 
 impl Default for Config {
@@ -31,19 +47,11 @@ impl Default for Config {
         Self {
             secret: env::required("TELEGRAM_BOT_SECRET"),
             webhook_url: env::required("TELEGRAM_BOT_WEBHOOK_URL"),
+            theme: env::get_parsed_or("TELEGRAM_BOT_THEME", ThemeKind::Default),
         }
     }
 }
 
-macro_rules! button {
-    ($text:expr, $cq:expr $(,)?) => {
-        InlineKeyboardButton {
-            text: $text.to_owned(),
-            callback_data: $cq.to_owned(),
-        }
-    };
-}
-
 impl FeatureTelegramBot {
     pub async fn set_webhook(&self) -> anyhow::Result<()> {
         self.set_webhook_use_case
@@ -52,38 +60,63 @@ impl FeatureTelegramBot {
     }
 
     pub async fn reply(&self, update: Update, secret: String) -> anyhow::Result<()> {
+        let started_at = Instant::now();
         ensure!(
             secret == self.config.secret,
             CommonError::user("Request has invalid secret key")
         );
-        let (text, message, is_callback) = if let Some(cq) = update.callback_query {
-            (cq.data, cq.message, true)
+        let (text, message, sender, is_callback) = if let Some(cq) = update.callback_query {
+            (cq.data, cq.message, Some(to_chat_member(&cq.from)), true)
         } else {
-            (
-                update.message.as_ref().and_then(|it| it.text.to_owned()),
-                update.message,
-                false,
-            )
+            let text = update.message.as_ref().and_then(|it| it.text.to_owned());
+            let sender = update
+                .message
+                .as_ref()
+                .and_then(|it| it.from.as_ref().map(to_chat_member));
+            (text, update.message, sender, false)
         };
 
         if let Some(message) = message {
-            let reply = if let Some(text) = text {
+            let chat_id = TelegramChatId(message.chat.id);
+            let platform_id = PlatformId::Telegram(chat_id);
+            // blocked peers get no reply, so the spam costs nothing
+            if self.abuse_protection_use_case.should_drop(&platform_id) {
+                return Ok(());
+            }
+            let (reply, context) = if let Some(text) = text {
                 self.generate_reply_use_case
-                    .generate_reply(PlatformId::Telegram(message.chat.id), &text)
+                    .generate_reply(platform_id.clone(), sender, &text)
                     .await
                     .unwrap_or_else(|e| {
                         error!("{e}");
-                        Reply::InternalError
+                        (Reply::InternalError, ReplyContext::default())
                     })
             } else {
-                Reply::UnknownMessageType
+                (Reply::UnknownMessageType, ReplyContext::default())
             };
-            let text = domain_bot::renderer::render_message(&reply, RenderTargetPlatform::Telegram);
-            let keyboard = self.render_keyboard(&reply, &message.chat.r#type);
-            self.reply_to_telegram_use_case
-                .reply(&text, message.chat.id, keyboard)
+            let text = domain_bot::renderer::render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                context.locale,
+                context.theme.unwrap_or(self.config.theme),
+            );
+            let keyboard = render_keyboard(&reply, &message.chat.r#type);
+            self.send_reply(&text, chat_id, keyboard)
                 .await
                 .with_context(|| "Error while sending reply to telegram")?;
+            if let Some(document) = domain_bot::renderer::render_document(&reply, &context) {
+                self.send_document(document, chat_id).await;
+            }
+            self.audit_reply_use_case
+                .audit_reply(
+                    &platform_id,
+                    &context,
+                    &reply,
+                    &text,
+                    None,
+                    started_at.elapsed(),
+                )
+                .await;
 
             if is_callback {
                 self.delete_message_use_case
@@ -98,49 +131,114 @@ impl FeatureTelegramBot {
         Ok(())
     }
 
-    fn render_keyboard(&self, reply: &Reply, chat_type: &ChatType) -> Option<CommonKeyboardMarkup> {
-        match (reply, chat_type) {
-            (
-                Reply::ScheduleSearchResults {
-                    schedule_name: _,
-                    results,
-                    results_contains_person,
-                },
-                _,
-            ) => Some(self.render_search_results_keyboard(results, *results_contains_person)),
-            _ => None,
+    /// Send the pushes, which are due at the moment, to the peers of the platform,
+    /// see [PushUseCase::get_pushes_if_needed]
+    pub async fn send_pushes(&self) {
+        for use_case in &self.push_use_cases {
+            match use_case.get_pushes_if_needed(PlatformId::telegram).await {
+                Ok(pushes) => self.send_prepared_pushes(pushes, use_case.name()).await,
+                Err(e) => error!("Error while preparing {}: {e}", use_case.name()),
+            }
         }
     }
 
-    fn render_search_results_keyboard(
-        &self,
-        results: &[String],
-        results_contains_person: bool,
-    ) -> CommonKeyboardMarkup {
-        if results_contains_person {
-            return CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
-                inline_keyboard: results
-                    .iter()
-                    .map(|text| vec![button!(text, text)])
-                    .collect(),
+    /// Attach the file to the reply, the reply itself is already sent
+    async fn send_document(&self, document: Document, chat_id: TelegramChatId) {
+        self.send_document_use_case
+            .send_document(chat_id.0, &document.file_name, &document.content)
+            .await
+            .unwrap_or_else(|e| {
+                error!(
+                    "Error while sending {} to telegram: {e}",
+                    document.file_name
+                )
             });
+    }
+
+    /// Send the replies queued after the transient errors, if it is time for their next attempt
+    pub async fn retry_failed_replies(&self) -> anyhow::Result<()> {
+        let replies = self
+            .reply_retry_use_case
+            .get_due_replies(PlatformId::telegram)
+            .await?;
+        for reply in replies {
+            let PlatformId::Telegram(chat_id) = reply.platform_id else {
+                continue;
+            };
+            let result = self
+                .reply_to_telegram_use_case
+                .reply_with_serialized_keyboard(&reply.text, chat_id.0, reply.keyboard.to_owned())
+                .await;
+            self.reply_retry_use_case
+                .report_attempt(&reply, result)
+                .await?;
         }
+        Ok(())
+    }
 
-        let mut buttons: Vec<Vec<InlineKeyboardButton>> = vec![];
-        let mut iter = results.iter();
-        let mut i = 0;
+    /// Send the reply, or queue it for the next attempt if the error is transient
+    async fn send_reply(
+        &self,
+        text: &str,
+        chat_id: TelegramChatId,
+        keyboard: Option<CommonKeyboardMarkup>,
+    ) -> anyhow::Result<()> {
+        let keyboard = ReplyToTelegramUseCase::serialize_keyboard(keyboard)?;
+        let Err(e) = self
+            .reply_to_telegram_use_case
+            .reply_with_serialized_keyboard(text, chat_id.0, keyboard.to_owned())
+            .await
+        else {
+            return Ok(());
+        };
+        let platform_id = PlatformId::Telegram(chat_id);
+        match self
+            .reply_retry_use_case
+            .enqueue(&platform_id, text, keyboard.as_deref(), &e)
+            .await
+        {
+            true => Ok(()),
+            false => Err(e),
+        }
+    }
 
-        while i < results.len() - 1 {
-            if let (Some(btn1), Some(btn2)) = (iter.next(), iter.next()) {
-                buttons.push(vec![button!(btn1, btn1), button!(btn2, btn2)]);
+    async fn send_prepared_pushes(
+        &self,
+        pushes: Vec<(PlatformId, Reply, ReplyContext)>,
+        kind: &str,
+    ) {
+        for (platform_id, reply, context) in pushes {
+            let started_at = Instant::now();
+            let PlatformId::Telegram(chat_id) = platform_id else {
+                continue;
+            };
+            let text = domain_bot::renderer::render_message(
+                &reply,
+                RenderTargetPlatform::Telegram,
+                context.locale,
+                context.theme.unwrap_or(self.config.theme),
+            );
+            if let Err(e) = self.send_reply(&text, chat_id, None).await {
+                error!("Error while sending {kind} to telegram: {e}");
+                continue;
             }
-            i += 2;
-        }
-        if let Some(btn) = iter.next() {
-            buttons.push(vec![button!(btn, btn)]);
+            self.audit_reply_use_case
+                .audit_reply(
+                    &platform_id,
+                    &context,
+                    &reply,
+                    &text,
+                    None,
+                    started_at.elapsed(),
+                )
+                .await;
         }
-        CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
-            inline_keyboard: buttons,
-        })
+    }
+}
+
+fn to_chat_member(user: &User) -> ChatMember {
+    ChatMember {
+        id: user.id,
+        username: user.username.to_owned(),
     }
 }
@@ -1,18 +1,40 @@
 use std::sync::Arc;
 
-use domain_bot::usecases::GenerateReplyUseCase;
+use domain_bot::usecases::{
+    AbuseProtectionUseCase, AuditReplyUseCase, EveningSummaryUseCase, GenerateReplyUseCase,
+    ReEngagementUseCase, ReplyRetryUseCase, ScheduleChangeNotificationsUseCase, WeekAheadUseCase,
+    WeeklyChangelogUseCase, WeeklyDigestUseCase,
+};
 use domain_telegram_bot::usecases::{
-    DeleteMessageUseCase, ReplyToTelegramUseCase, SetWebhookUseCase,
+    DeleteMessageUseCase, GetChatMemberStatusUseCase, ReplyToTelegramUseCase, SendDocumentUseCase,
+    SetWebhookUseCase,
 };
 
-use crate::{Config, FeatureTelegramBot};
+use crate::{Config, FeatureTelegramBot, TelegramChatAdminChecker};
+
+impl TelegramChatAdminChecker {
+    pub fn new(get_chat_member_status_use_case: Arc<GetChatMemberStatusUseCase>) -> Self {
+        Self(get_chat_member_status_use_case)
+    }
+}
 
 impl FeatureTelegramBot {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         generate_reply_use_case: Arc<GenerateReplyUseCase>,
         set_webhook_use_case: Arc<SetWebhookUseCase>,
         reply_to_telegram_use_case: Arc<ReplyToTelegramUseCase>,
         delete_message_use_case: Arc<DeleteMessageUseCase>,
+        send_document_use_case: Arc<SendDocumentUseCase>,
+        audit_reply_use_case: Arc<AuditReplyUseCase>,
+        reply_retry_use_case: Arc<ReplyRetryUseCase>,
+        evening_summary_use_case: Arc<EveningSummaryUseCase>,
+        weekly_changelog_use_case: Arc<WeeklyChangelogUseCase>,
+        week_ahead_use_case: Arc<WeekAheadUseCase>,
+        weekly_digest_use_case: Arc<WeeklyDigestUseCase>,
+        schedule_change_notifications_use_case: Arc<ScheduleChangeNotificationsUseCase>,
+        reengagement_use_case: Arc<ReEngagementUseCase>,
+        abuse_protection_use_case: Arc<AbuseProtectionUseCase>,
     ) -> Self {
         Self {
             config: Config::default(),
@@ -20,6 +42,18 @@ impl FeatureTelegramBot {
             set_webhook_use_case,
             reply_to_telegram_use_case,
             delete_message_use_case,
+            send_document_use_case,
+            audit_reply_use_case,
+            reply_retry_use_case,
+            push_use_cases: vec![
+                evening_summary_use_case,
+                weekly_changelog_use_case,
+                week_ahead_use_case,
+                weekly_digest_use_case,
+                schedule_change_notifications_use_case,
+                reengagement_use_case,
+            ],
+            abuse_protection_use_case,
         }
     }
 }
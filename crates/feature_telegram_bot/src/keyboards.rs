@@ -0,0 +1,268 @@
+use domain_bot::{models::Reply, renderer::is_week_summarized};
+use domain_telegram_bot::{
+    ChatType, CommonKeyboardMarkup, InlineKeyboardButton, InlineKeyboardMarkup, KeyboardButton,
+    ReplyKeyboardMarkup,
+};
+
+macro_rules! button {
+    ($text:expr, $cq:expr $(,)?) => {
+        InlineKeyboardButton {
+            text: $text.to_owned(),
+            callback_data: $cq.to_owned(),
+        }
+    };
+}
+
+macro_rules! reply_button {
+    ($text:expr) => {
+        KeyboardButton {
+            text: $text.to_owned(),
+        }
+    };
+}
+
+/// Keyboard under the reply, the same as in the VK bot:
+/// search results are inline buttons, unknown commands get the inline "help" button,
+/// and the private chats have the default command set instead of the keyboard.
+/// Group chats do not get the default command set, so it does not pop up for every member.
+pub(crate) fn render_keyboard(reply: &Reply, chat_type: &ChatType) -> Option<CommonKeyboardMarkup> {
+    match (reply, chat_type) {
+        (Reply::UnknownMessageType | Reply::UnknownCommand, _) => Some(inline_help()),
+        (
+            Reply::ScheduleSearchResults {
+                schedule_name: _,
+                results,
+                results_contains_person,
+                person_details: _,
+            },
+            _,
+        ) => Some(render_search_results_keyboard(
+            results,
+            *results_contains_person,
+        )),
+        (
+            Reply::ScheduleRenamed {
+                schedule_name: _,
+                results,
+            },
+            _,
+        ) if !results.is_empty() => Some(render_search_results_keyboard(results, true)),
+        (Reply::RecentSchedules(results), _) if !results.is_empty() => {
+            Some(render_search_results_keyboard(results, true))
+        }
+        (Reply::StartGreetings { suggestions }, _) if !suggestions.is_empty() => {
+            Some(render_search_results_keyboard(suggestions, false))
+        }
+        (Reply::Week { week_offset, .. }, _) if is_week_summarized(reply) => {
+            Some(inline_full_week(*week_offset))
+        }
+        (_, ChatType::Private) => Some(default_keyboard()),
+        _ => None,
+    }
+}
+
+fn default_keyboard() -> CommonKeyboardMarkup {
+    CommonKeyboardMarkup::Reply(ReplyKeyboardMarkup {
+        keyboard: vec![
+            vec![
+                reply_button!("Ближайшие пары"),
+                reply_button!("Сколько пар осталось"),
+            ],
+            vec![reply_button!("Пары сегодня"), reply_button!("Пары завтра")],
+            vec![
+                reply_button!("Эта неделя"),
+                reply_button!("Следующая неделя"),
+            ],
+            vec![reply_button!("Недавние"), reply_button!("Помощь")],
+        ],
+        one_time_keyboard: false,
+        resize_keyboard: true,
+    })
+}
+
+fn inline_help() -> CommonKeyboardMarkup {
+    CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
+        inline_keyboard: vec![vec![
+            button!("Помощь", "Помощь"),
+            button!("Ближайшие пары", "Ближайшие пары"),
+        ]],
+    })
+}
+
+/// Follow-up of the summarized week, callback data is limited to 64 bytes,
+/// so the short commands are used instead of the text ones
+fn inline_full_week(week_offset: i8) -> CommonKeyboardMarkup {
+    let command = match week_offset {
+        1 => "/nextweek_full",
+        -1 => "/prevweek_full",
+        _ => "/thisweek_full",
+    };
+    CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
+        inline_keyboard: vec![vec![button!("Показать полностью", command)]],
+    })
+}
+
+fn render_search_results_keyboard(
+    results: &[String],
+    results_contains_person: bool,
+) -> CommonKeyboardMarkup {
+    if results_contains_person {
+        return CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
+            inline_keyboard: results
+                .iter()
+                .map(|text| vec![button!(text, text)])
+                .collect(),
+        });
+    }
+
+    let mut buttons: Vec<Vec<InlineKeyboardButton>> = vec![];
+    let mut iter = results.iter();
+    let mut i = 0;
+
+    while i < results.len() - 1 {
+        if let (Some(btn1), Some(btn2)) = (iter.next(), iter.next()) {
+            buttons.push(vec![button!(btn1, btn1), button!(btn2, btn2)]);
+        }
+        i += 2;
+    }
+    if let Some(btn) = iter.next() {
+        buttons.push(vec![button!(btn, btn)]);
+    }
+    CommonKeyboardMarkup::Inline(InlineKeyboardMarkup {
+        inline_keyboard: buttons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveTime};
+    use domain_bot::models::Reply;
+    use domain_schedule_models::{Classes, ClassesTime, ClassesType, Day, ScheduleType, Week};
+    use domain_telegram_bot::{ChatType, CommonKeyboardMarkup};
+
+    use super::render_keyboard;
+
+    fn inline_rows(keyboard: Option<CommonKeyboardMarkup>) -> Vec<Vec<String>> {
+        match keyboard {
+            Some(CommonKeyboardMarkup::Inline(keyboard)) => keyboard
+                .inline_keyboard
+                .into_iter()
+                .map(|row| row.into_iter().map(|it| it.callback_data).collect())
+                .collect(),
+            other => panic!("Expected inline keyboard, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_summarized_week_keyboard() {
+        let reply = |week_offset| Reply::Week {
+            schedule_name: "А-08-19".to_owned(),
+            week_offset,
+            week: Week {
+                week_of_year: 11,
+                week_of_semester: 6,
+                first_day_of_week: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                days: vec![Day {
+                    day_of_week: 1,
+                    date: NaiveDate::from_ymd_opt(2023, 3, 13).unwrap(),
+                    classes: vec![classes(); 41],
+                    source: Default::default(),
+                    expires_at: None,
+                    before_semester_start: false,
+                }],
+                period_type: Default::default(),
+                last_synced_at: None,
+            },
+            schedule_type: ScheduleType::Group,
+            map_provider: None,
+            notes: vec![],
+            full: false,
+            rendered: None,
+        };
+        assert_eq!(
+            vec![vec!["/nextweek_full"]],
+            inline_rows(render_keyboard(&reply(1), &ChatType::Private))
+        );
+        assert_eq!(
+            vec![vec!["/thisweek_full"]],
+            inline_rows(render_keyboard(&reply(0), &ChatType::Group))
+        );
+    }
+
+    fn classes() -> Classes {
+        Classes {
+            name: "Физика".to_owned(),
+            r#type: ClassesType::Lecture,
+            raw_type: String::new(),
+            place: String::new(),
+            groups: String::new(),
+            person: String::new(),
+            time: ClassesTime {
+                start: NaiveTime::from_hms_opt(9, 20, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(10, 55, 0).unwrap(),
+            },
+            number: 1,
+            status: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_search_results_keyboard() {
+        let reply = |results: &[&str], results_contains_person| Reply::ScheduleSearchResults {
+            schedule_name: "А-08".to_owned(),
+            results: results.iter().map(|it| it.to_string()).collect(),
+            results_contains_person,
+            person_details: Vec::new(),
+        };
+        assert_eq!(
+            vec![vec!["А-08-19", "А-08-20"], vec!["А-08-21"]],
+            inline_rows(render_keyboard(
+                &reply(&["А-08-19", "А-08-20", "А-08-21"], false),
+                &ChatType::Group
+            ))
+        );
+        assert_eq!(
+            vec![vec!["Иванов И.И."], vec!["Иванова А.А."]],
+            inline_rows(render_keyboard(
+                &reply(&["Иванов И.И.", "Иванова А.А."], true),
+                &ChatType::Private
+            ))
+        );
+    }
+
+    #[test]
+    fn test_start_suggestions_keyboard() {
+        let reply = |suggestions: &[&str]| Reply::StartGreetings {
+            suggestions: suggestions.iter().map(|it| it.to_string()).collect(),
+        };
+        assert_eq!(
+            vec![vec!["А-08-19", "А-12-19"], vec!["С-12-21"]],
+            inline_rows(render_keyboard(
+                &reply(&["А-08-19", "А-12-19", "С-12-21"]),
+                &ChatType::Private
+            ))
+        );
+        assert!(matches!(
+            render_keyboard(&reply(&[]), &ChatType::Private),
+            Some(CommonKeyboardMarkup::Reply(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_command_keyboard() {
+        assert_eq!(
+            vec![vec!["Помощь", "Ближайшие пары"]],
+            inline_rows(render_keyboard(&Reply::UnknownCommand, &ChatType::Group))
+        );
+    }
+
+    #[test]
+    fn test_default_keyboard_in_private_chats_only() {
+        assert!(matches!(
+            render_keyboard(&Reply::ShowHelp, &ChatType::Private),
+            Some(CommonKeyboardMarkup::Reply(_))
+        ));
+        assert!(render_keyboard(&Reply::ShowHelp, &ChatType::Group).is_none());
+        assert!(render_keyboard(&Reply::ShowHelp, &ChatType::SuperGroup).is_none());
+    }
+}
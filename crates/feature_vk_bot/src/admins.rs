@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use domain_bot::{notes::ChatAdminChecker, peer::repository::PlatformId};
+use domain_vk_bot::usecases::GetConversationAdminsUseCase;
+
+/// Chat admins are the owner and the admins of VK conversation
+pub struct VkChatAdminChecker {
+    pub(crate) access_token: String,
+    pub(crate) get_conversation_admins_use_case: Arc<GetConversationAdminsUseCase>,
+}
+
+#[async_trait]
+impl ChatAdminChecker for VkChatAdminChecker {
+    async fn is_chat_admin(
+        &self,
+        platform_id: &PlatformId,
+        member_id: i64,
+    ) -> anyhow::Result<bool> {
+        let PlatformId::Vk(peer_id) = platform_id else {
+            return Ok(false);
+        };
+        let admin_ids = self
+            .get_conversation_admins_use_case
+            .get_admin_ids(&self.access_token, peer_id.0)
+            .await?;
+        Ok(admin_ids.contains(&member_id))
+    }
+}
@@ -1,23 +1,34 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Instant};
 
 use anyhow::{anyhow, bail, ensure, Context};
 use common_errors::errors::CommonError;
 use common_rust::env;
 use domain_bot::{
-    models::Reply, peer::repository::PlatformId, renderer::RenderTargetPlatform,
-    usecases::GenerateReplyUseCase,
+    models::{ChatMember, Locale, Reply, ReplyContext},
+    peer::repository::{PlatformId, VkPeerId},
+    renderer::{full_week_command, is_week_summarized, RenderTargetPlatform},
+    theme::ThemeKind,
+    usecases::{
+        AbuseProtectionUseCase, AuditReplyUseCase, GenerateReplyUseCase, PushUseCase,
+        ReplyRetryUseCase,
+    },
 };
 use domain_vk_bot::{
     usecases::ReplyToVkUseCase, ButtonActionType, Keyboard, KeyboardButton, KeyboardButtonAction,
     MessagePeerType, NewMessageObject, VkCallbackRequest, VkCallbackType,
 };
 use log::error;
-use once_cell::sync::Lazy;
+
+use crate::keyboards::{button, KeyboardLayout, KeyboardRollout};
 
 pub struct FeatureVkBot {
     pub(crate) config: Config,
     pub(crate) generate_reply_use_case: Arc<GenerateReplyUseCase>,
     pub(crate) reply_to_vk_use_case: Arc<ReplyToVkUseCase>,
+    pub(crate) audit_reply_use_case: Arc<AuditReplyUseCase>,
+    pub(crate) reply_retry_use_case: Arc<ReplyRetryUseCase>,
+    pub(crate) push_use_cases: Vec<Arc<dyn PushUseCase>>,
+    pub(crate) abuse_protection_use_case: Arc<AbuseProtectionUseCase>,
 }
 
 pub(crate) struct Config {
@@ -25,6 +36,8 @@ pub(crate) struct Config {
     secret: Option<String>,
     group_id: Option<i64>,
     access_token: String,
+    keyboard_rollout: KeyboardRollout,
+    theme: ThemeKind,
 }
 
 impl Default for Config {
@@ -33,46 +46,24 @@ impl Default for Config {
         let secret = env::get("VK_BOT_SECRET");
         let group_id = env::get_parsed("VK_BOT_GROUP_ID");
         let access_token = env::required("VK_BOT_ACCESS_TOKEN");
+        let keyboard_rollout =
+            KeyboardRollout::parse(&env::get_or("VK_BOT_KEYBOARD_ROLLOUT", "v1=100"));
+        let theme = env::get_parsed_or("VK_BOT_THEME", ThemeKind::Default);
 
         Self {
             confirmation_code,
             secret,
             group_id,
             access_token,
+            keyboard_rollout,
+            theme,
         }
     }
 }
 
-macro_rules! button {
-    ($label:expr, $color:expr $(,)?) => {
-        KeyboardButton {
-            action: KeyboardButtonAction {
-                r#type: ButtonActionType::Text,
-                label: $label.to_owned(),
-                payload: Some("{}".to_owned()),
-            },
-            color: $color,
-        }
-    };
-}
-
-static KEYBOARD_INLINE_HELP: Lazy<Keyboard> = Lazy::new(|| Keyboard {
-    buttons: vec![vec![button!("Помощь", Some("primary".to_owned()))]],
-    inline: true,
-    one_time: false,
-});
-static KEYBOARD_DEFAULT: Lazy<Keyboard> = Lazy::new(|| Keyboard {
-    buttons: vec![
-        vec![button!("Ближайшие пары", Some("primary".to_owned()))],
-        vec![button!("Пары сегодня", None), button!("Пары завтра", None)],
-        vec![button!("Помощь", None), button!("Сменить расписание", None)],
-    ],
-    inline: false,
-    one_time: false,
-});
-
 impl FeatureVkBot {
     pub async fn reply(&self, callback: VkCallbackRequest) -> anyhow::Result<Option<String>> {
+        let started_at = Instant::now();
         ensure!(
             callback.secret == self.config.secret,
             CommonError::user("Request has invalid secret key")
@@ -94,25 +85,57 @@ impl FeatureVkBot {
                     client_info: _,
                 }) = callback.object
                 {
-                    let reply = if let Some(text) = &message.text {
+                    let peer_id = VkPeerId(message.peer_id);
+                    let platform_id = PlatformId::Vk(peer_id);
+                    // blocked peers get no reply, so the spam costs nothing
+                    if self.abuse_protection_use_case.should_drop(&platform_id) {
+                        return Ok(None);
+                    }
+                    let (reply, context) = if let Some(text) = &message.text {
                         self.generate_reply_use_case
-                            .generate_reply(PlatformId::Vk(message.peer_id), text)
+                            .generate_reply(
+                                platform_id.clone(),
+                                // VK mentions contain ids, so usernames are not needed
+                                Some(ChatMember {
+                                    id: message.from_id,
+                                    username: None,
+                                }),
+                                text,
+                            )
                             .await
                             .unwrap_or_else(|e| {
                                 error!("{e}");
-                                Reply::InternalError
+                                (Reply::InternalError, ReplyContext::default())
                             })
                     } else {
-                        Reply::UnknownMessageType
+                        (Reply::UnknownMessageType, ReplyContext::default())
                     };
 
-                    let text =
-                        domain_bot::renderer::render_message(&reply, RenderTargetPlatform::Vk);
-                    let keyboard = self.render_keyboard(&reply, &message.peer_type());
-                    self.reply_to_vk_use_case
-                        .reply(&self.config.access_token, &text, message.peer_id, keyboard)
+                    let text = domain_bot::renderer::render_message(
+                        &reply,
+                        RenderTargetPlatform::Vk,
+                        context.locale,
+                        context.theme.unwrap_or(self.config.theme),
+                    );
+                    let layout = self
+                        .config
+                        .keyboard_rollout
+                        .layout_for(peer_id, context.privacy_mode);
+                    let (keyboard, keyboard_version) =
+                        self.render_keyboard(&reply, &message.peer_type(), layout);
+                    self.send_reply(&text, peer_id, keyboard)
                         .await
                         .with_context(|| "Error while sending reply to vk")?;
+                    self.audit_reply_use_case
+                        .audit_reply(
+                            &platform_id,
+                            &context,
+                            &reply,
+                            &text,
+                            keyboard_version,
+                            started_at.elapsed(),
+                        )
+                        .await;
 
                     Ok(None)
                 } else {
@@ -127,22 +150,153 @@ impl FeatureVkBot {
         }
     }
 
-    fn render_keyboard(&self, reply: &Reply, peer_type: &MessagePeerType) -> Option<Keyboard> {
-        match (reply, peer_type) {
+    /// Send the pushes, which are due at the moment, to the peers of the platform,
+    /// see [PushUseCase::get_pushes_if_needed]
+    pub async fn send_pushes(&self) {
+        for use_case in &self.push_use_cases {
+            match use_case.get_pushes_if_needed(PlatformId::vk).await {
+                Ok(pushes) => self.send_prepared_pushes(pushes, use_case.name()).await,
+                Err(e) => error!("Error while preparing {}: {e}", use_case.name()),
+            }
+        }
+    }
+
+    /// Send the replies queued after the transient errors, if it is time for their next attempt
+    pub async fn retry_failed_replies(&self) -> anyhow::Result<()> {
+        let replies = self
+            .reply_retry_use_case
+            .get_due_replies(PlatformId::vk)
+            .await?;
+        for reply in replies {
+            let PlatformId::Vk(peer_id) = reply.platform_id else {
+                continue;
+            };
+            let result = self
+                .reply_to_vk_use_case
+                .reply_with_serialized_keyboard(
+                    &self.config.access_token,
+                    &reply.text,
+                    peer_id.0,
+                    reply.keyboard.to_owned(),
+                )
+                .await;
+            self.reply_retry_use_case
+                .report_attempt(&reply, result)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Send the reply, or queue it for the next attempt if the error is transient
+    async fn send_reply(
+        &self,
+        text: &str,
+        peer_id: VkPeerId,
+        keyboard: Option<Keyboard>,
+    ) -> anyhow::Result<()> {
+        let keyboard = ReplyToVkUseCase::serialize_keyboard(keyboard)?;
+        let Err(e) = self
+            .reply_to_vk_use_case
+            .reply_with_serialized_keyboard(
+                &self.config.access_token,
+                text,
+                peer_id.0,
+                keyboard.to_owned(),
+            )
+            .await
+        else {
+            return Ok(());
+        };
+        let platform_id = PlatformId::Vk(peer_id);
+        match self
+            .reply_retry_use_case
+            .enqueue(&platform_id, text, keyboard.as_deref(), &e)
+            .await
+        {
+            true => Ok(()),
+            false => Err(e),
+        }
+    }
+
+    async fn send_prepared_pushes(
+        &self,
+        pushes: Vec<(PlatformId, Reply, ReplyContext)>,
+        kind: &str,
+    ) {
+        for (platform_id, reply, context) in pushes {
+            let started_at = Instant::now();
+            let PlatformId::Vk(peer_id) = platform_id else {
+                continue;
+            };
+            let text = domain_bot::renderer::render_message(
+                &reply,
+                RenderTargetPlatform::Vk,
+                context.locale,
+                context.theme.unwrap_or(self.config.theme),
+            );
+            if let Err(e) = self.send_reply(&text, peer_id, None).await {
+                error!("Error while sending {kind} to vk: {e}");
+                continue;
+            }
+            self.audit_reply_use_case
+                .audit_reply(
+                    &platform_id,
+                    &context,
+                    &reply,
+                    &text,
+                    None,
+                    started_at.elapsed(),
+                )
+                .await;
+        }
+    }
+
+    /// Returns the keyboard and the version of the layout, if the keyboard is taken from it
+    fn render_keyboard(
+        &self,
+        reply: &Reply,
+        peer_type: &MessagePeerType,
+        layout: &KeyboardLayout,
+    ) -> (Option<Keyboard>, Option<&'static str>) {
+        let keyboard = match (reply, peer_type) {
             (Reply::UnknownMessageType | Reply::UnknownCommand, _) => {
-                Some(KEYBOARD_INLINE_HELP.to_owned())
+                return (Some(layout.inline_help.to_owned()), Some(layout.version));
             }
             (
                 Reply::ScheduleSearchResults {
                     schedule_name: _,
                     results,
                     results_contains_person,
+                    person_details: _,
                 },
                 _,
             ) => Some(self.render_search_results_keyboard(results, *results_contains_person)),
+            (
+                Reply::ScheduleRenamed {
+                    schedule_name: _,
+                    results,
+                },
+                _,
+            ) if !results.is_empty() => Some(self.render_search_results_keyboard(results, true)),
+            (Reply::RecentSchedules(results), _) if !results.is_empty() => {
+                Some(self.render_search_results_keyboard(results, true))
+            }
+            (Reply::StartGreetings { suggestions }, _) if !suggestions.is_empty() => {
+                Some(self.render_search_results_keyboard(suggestions, false))
+            }
+            // text buttons send the label, so the label is the text command itself
+            (Reply::Week { week_offset, .. }, _) if is_week_summarized(reply) => Some(Keyboard {
+                buttons: vec![vec![button!(
+                    full_week_command(*week_offset, Locale::Ru),
+                    None
+                )]],
+                inline: true,
+                one_time: false,
+            }),
             (_, MessagePeerType::GroupChat) => None,
-            _ => Some(KEYBOARD_DEFAULT.to_owned()),
-        }
+            _ => return (Some(layout.default.to_owned()), Some(layout.version)),
+        };
+        (keyboard, None)
     }
 
     fn render_search_results_keyboard(
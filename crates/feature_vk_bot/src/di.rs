@@ -1,19 +1,54 @@
 use std::sync::Arc;
 
-use domain_bot::usecases::GenerateReplyUseCase;
-use domain_vk_bot::usecases::ReplyToVkUseCase;
+use common_rust::env;
+use domain_bot::usecases::{
+    AbuseProtectionUseCase, AuditReplyUseCase, EveningSummaryUseCase, GenerateReplyUseCase,
+    ReEngagementUseCase, ReplyRetryUseCase, ScheduleChangeNotificationsUseCase, WeekAheadUseCase,
+    WeeklyChangelogUseCase, WeeklyDigestUseCase,
+};
+use domain_vk_bot::usecases::{GetConversationAdminsUseCase, ReplyToVkUseCase};
 
-use crate::{Config, FeatureVkBot};
+use crate::{Config, FeatureVkBot, VkChatAdminChecker};
+
+impl VkChatAdminChecker {
+    pub fn new(get_conversation_admins_use_case: Arc<GetConversationAdminsUseCase>) -> Self {
+        Self {
+            access_token: env::required("VK_BOT_ACCESS_TOKEN"),
+            get_conversation_admins_use_case,
+        }
+    }
+}
 
 impl FeatureVkBot {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         generate_reply_use_case: Arc<GenerateReplyUseCase>,
         reply_to_vk_use_case: Arc<ReplyToVkUseCase>,
+        audit_reply_use_case: Arc<AuditReplyUseCase>,
+        reply_retry_use_case: Arc<ReplyRetryUseCase>,
+        evening_summary_use_case: Arc<EveningSummaryUseCase>,
+        weekly_changelog_use_case: Arc<WeeklyChangelogUseCase>,
+        week_ahead_use_case: Arc<WeekAheadUseCase>,
+        weekly_digest_use_case: Arc<WeeklyDigestUseCase>,
+        schedule_change_notifications_use_case: Arc<ScheduleChangeNotificationsUseCase>,
+        reengagement_use_case: Arc<ReEngagementUseCase>,
+        abuse_protection_use_case: Arc<AbuseProtectionUseCase>,
     ) -> Self {
         Self {
             config: Config::default(),
             generate_reply_use_case,
             reply_to_vk_use_case,
+            audit_reply_use_case,
+            reply_retry_use_case,
+            push_use_cases: vec![
+                evening_summary_use_case,
+                weekly_changelog_use_case,
+                week_ahead_use_case,
+                weekly_digest_use_case,
+                schedule_change_notifications_use_case,
+                reengagement_use_case,
+            ],
+            abuse_protection_use_case,
         }
     }
 }
@@ -0,0 +1,174 @@
+use domain_bot::peer::repository::VkPeerId;
+use domain_vk_bot::{ButtonActionType, Keyboard, KeyboardButton, KeyboardButtonAction};
+use log::warn;
+use once_cell::sync::Lazy;
+
+macro_rules! button {
+    ($label:expr, $color:expr $(,)?) => {
+        KeyboardButton {
+            action: KeyboardButtonAction {
+                r#type: ButtonActionType::Text,
+                label: $label.to_owned(),
+                payload: Some("{}".to_owned()),
+            },
+            color: $color,
+        }
+    };
+}
+
+pub(crate) use button;
+
+/// Versioned set of keyboards which are shown by default.
+/// New versions are added here to compare them with the current one.
+pub(crate) struct KeyboardLayout {
+    pub(crate) version: &'static str,
+    pub(crate) default: Keyboard,
+    pub(crate) inline_help: Keyboard,
+}
+
+static KEYBOARD_LAYOUTS: Lazy<Vec<KeyboardLayout>> = Lazy::new(|| {
+    vec![
+        KeyboardLayout {
+            version: "v1",
+            default: Keyboard {
+                buttons: vec![
+                    vec![button!("Ближайшие пары", Some("primary".to_owned()))],
+                    vec![button!("Пары сегодня", None), button!("Пары завтра", None)],
+                    vec![button!("Помощь", None), button!("Сменить расписание", None)],
+                ],
+                inline: false,
+                one_time: false,
+            },
+            inline_help: Keyboard {
+                buttons: vec![vec![button!("Помощь", Some("primary".to_owned()))]],
+                inline: true,
+                one_time: false,
+            },
+        },
+        KeyboardLayout {
+            version: "v2",
+            default: Keyboard {
+                buttons: vec![
+                    vec![
+                        button!("Ближайшие пары", Some("primary".to_owned())),
+                        button!("Сколько пар осталось", None),
+                    ],
+                    vec![button!("Пары сегодня", None), button!("Пары завтра", None)],
+                    vec![
+                        button!("Эта неделя", None),
+                        button!("Следующая неделя", None),
+                    ],
+                    vec![button!("Недавние", None), button!("Помощь", None)],
+                ],
+                inline: false,
+                one_time: false,
+            },
+            inline_help: Keyboard {
+                buttons: vec![vec![
+                    button!("Помощь", Some("primary".to_owned())),
+                    button!("Ближайшие пары", None),
+                ]],
+                inline: true,
+                one_time: false,
+            },
+        },
+    ]
+});
+
+/// Share of peers which see each keyboard layout version, e.g. `v1=50,v2=50`.
+///
+/// Peers are split into 100 buckets by their ids, so each peer sees the same layout
+/// until the rollout is changed. Peers out of the listed shares and peers in privacy mode
+/// see the first layout.
+pub(crate) struct KeyboardRollout(Vec<(&'static KeyboardLayout, u32)>);
+
+impl KeyboardRollout {
+    pub(crate) fn parse(value: &str) -> Self {
+        let shares = value
+            .split(',')
+            .filter(|it| !it.trim().is_empty())
+            .filter_map(|share| {
+                let layout = share.split_once('=').and_then(|(version, percent)| {
+                    let layout = KEYBOARD_LAYOUTS
+                        .iter()
+                        .find(|it| it.version == version.trim())?;
+                    Some((layout, percent.trim().parse::<u32>().ok()?))
+                });
+                if layout.is_none() {
+                    warn!("Invalid keyboard rollout share '{share}' is ignored");
+                }
+                layout
+            })
+            .collect();
+        Self(shares)
+    }
+
+    pub(crate) fn layout_for(
+        &self,
+        peer_id: VkPeerId,
+        privacy_mode: bool,
+    ) -> &'static KeyboardLayout {
+        if privacy_mode {
+            return &KEYBOARD_LAYOUTS[0];
+        }
+        let bucket = peer_id.0.rem_euclid(100) as u32;
+        let mut upper_bound = 0;
+        for (layout, percent) in &self.0 {
+            upper_bound += percent;
+            if bucket < upper_bound {
+                return layout;
+            }
+        }
+        &KEYBOARD_LAYOUTS[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use domain_bot::peer::repository::VkPeerId;
+
+    use super::KeyboardRollout;
+
+    #[test]
+    fn test_default_rollout() {
+        let rollout = KeyboardRollout::parse("v1=100");
+        assert!(
+            (0..1000).all(|peer_id| rollout.layout_for(VkPeerId(peer_id), false).version == "v1")
+        );
+        let rollout = KeyboardRollout::parse("");
+        assert_eq!("v1", rollout.layout_for(VkPeerId(42), false).version);
+    }
+
+    #[test]
+    fn test_split_rollout() {
+        let rollout = KeyboardRollout::parse("v1=50, v2=50");
+        assert_eq!(
+            "v1",
+            rollout.layout_for(VkPeerId(2000000049), false).version
+        );
+        assert_eq!(
+            "v2",
+            rollout.layout_for(VkPeerId(2000000050), false).version
+        );
+        assert_eq!("v2", rollout.layout_for(VkPeerId(-1), false).version);
+        // the same peer always sees the same layout
+        assert_eq!(
+            rollout.layout_for(VkPeerId(123456), false).version,
+            rollout.layout_for(VkPeerId(123456), false).version
+        );
+    }
+
+    #[test]
+    fn test_invalid_shares_are_ignored() {
+        let rollout = KeyboardRollout::parse("v2=10,v42=90,v1");
+        assert_eq!("v2", rollout.layout_for(VkPeerId(5), false).version);
+        assert_eq!("v1", rollout.layout_for(VkPeerId(50), false).version);
+    }
+
+    #[test]
+    fn test_privacy_mode_peers_are_excluded() {
+        let rollout = KeyboardRollout::parse("v2=100");
+        assert_eq!("v2", rollout.layout_for(VkPeerId(42), false).version);
+        assert_eq!("v1", rollout.layout_for(VkPeerId(42), true).version);
+    }
+}
@@ -1,3 +1,6 @@
+mod admins;
 mod bot;
+mod keyboards;
+pub use admins::*;
 pub use bot::*;
 pub mod di;
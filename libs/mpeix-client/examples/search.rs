@@ -0,0 +1,23 @@
+//! Find schedules by the part of the name:
+//! `cargo run -p mpeix-client --example search -- https://mpeix.example.com а-08`
+use mpeix_client::MpeixClient;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(base_url), Some(query)) = (args.next(), args.next()) else {
+        eprintln!("Usage: search <base_url> <query>");
+        std::process::exit(1);
+    };
+    let client = MpeixClient::builder()
+        .base_url(&base_url)
+        .user_agent("mpeix-client-example")
+        .build()?;
+    for result in client.search(&query, None).await? {
+        println!(
+            "{} ({}) — {}",
+            result.name, result.r#type, result.description
+        );
+    }
+    Ok(())
+}
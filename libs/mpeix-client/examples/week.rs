@@ -0,0 +1,30 @@
+//! Print the current week of the group schedule:
+//! `cargo run -p mpeix-client --example week -- https://mpeix.example.com А-08-19`
+use mpeix_client::{models::ScheduleType, MpeixClient};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let (Some(base_url), Some(group)) = (args.next(), args.next()) else {
+        eprintln!("Usage: week <base_url> <group>");
+        std::process::exit(1);
+    };
+    let client = MpeixClient::builder()
+        .base_url(&base_url)
+        .user_agent("mpeix-client-example")
+        .build()?;
+    let schedule = client.schedule(&ScheduleType::Group, &group, 0).await?;
+    for day in schedule.weeks.iter().flat_map(|week| &week.days) {
+        println!("{}", day.date.format("%A, %d.%m"));
+        for classes in &day.classes {
+            println!(
+                "  {}-{} {} {}",
+                classes.time.start.format("%H:%M"),
+                classes.time.end.format("%H:%M"),
+                classes.name,
+                classes.place,
+            );
+        }
+    }
+    Ok(())
+}
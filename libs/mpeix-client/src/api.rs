@@ -0,0 +1,86 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use domain_schedule_models::{
+    BatchScheduleResult, Schedule, ScheduleChangelog, ScheduleSearchResult, ScheduleType,
+    ShiftRules,
+};
+use restix::{api, get, post};
+use serde::Deserialize;
+
+/// Public endpoints of the `app_schedule` microservice, as they are declared in its routing.
+/// Prefer [crate::MpeixClient], which adds retries and typed arguments on top of it.
+#[api]
+pub trait MpeixPublicApi {
+    #[get("/v1/{type}/{name}/id")]
+    #[map_response_with(IdResponse::id)]
+    async fn id(&self, #[path] r#type: &ScheduleType, #[path] name: &str) -> i64;
+
+    #[get("/v1/{type}/{name}/schedule/{offset}")]
+    async fn schedule(
+        &self,
+        #[path] r#type: &ScheduleType,
+        #[path] name: &str,
+        #[path] offset: i32,
+        #[query] days: Option<String>,
+    ) -> Schedule;
+
+    #[get("/v1/{type}/{name}/changes")]
+    async fn changes(
+        &self,
+        #[path] r#type: &ScheduleType,
+        #[path] name: &str,
+        #[query] days: u32,
+    ) -> ScheduleChangelog;
+
+    #[post("/v1/schedules/batch")]
+    #[map_response_with(BatchResponse::items)]
+    async fn schedules_batch(
+        &self,
+        #[raw_body] request: String,
+    ) -> BTreeMap<String, BatchScheduleResult>;
+
+    #[get("/v1/search")]
+    #[map_response_with(SearchResponse::items)]
+    async fn search(
+        &self,
+        #[query("q")] query: &str,
+        #[query] r#type: Option<ScheduleType>,
+    ) -> Vec<ScheduleSearchResult>;
+
+    #[get("/v1/calendar/shift-rules")]
+    async fn shift_rules(&self, #[query] date: Option<NaiveDate>) -> ShiftRules;
+}
+
+#[derive(Deserialize)]
+struct IdResponse {
+    id: i64,
+}
+
+impl IdResponse {
+    fn id(self) -> i64 {
+        self.id
+    }
+}
+
+#[derive(Deserialize)]
+struct BatchResponse {
+    items: BTreeMap<String, BatchScheduleResult>,
+}
+
+impl BatchResponse {
+    fn items(self) -> BTreeMap<String, BatchScheduleResult> {
+        self.items
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    items: Vec<ScheduleSearchResult>,
+}
+
+impl SearchResponse {
+    fn items(self) -> Vec<ScheduleSearchResult> {
+        self.items
+    }
+}
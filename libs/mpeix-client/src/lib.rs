@@ -0,0 +1,175 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use chrono::NaiveDate;
+use domain_schedule_models::{
+    BatchScheduleResult, DaysRange, Schedule, ScheduleChangelog, ScheduleRef, ScheduleSearchResult,
+    ScheduleType, ShiftRules,
+};
+use serde::Serialize;
+
+use crate::{
+    api::{MpeixPublicApi, MpeixPublicApiBuilderError},
+    retry::RetryInterceptor,
+};
+
+pub mod api;
+mod retry;
+
+/// Models of the api responses, the same ones which are used by the backend itself
+pub use domain_schedule_models as models;
+
+pub type Result<T> = std::result::Result<T, reqwest::Error>;
+
+/// Typed client of the public mpeix schedule api:
+/// ```ignore
+/// let client = MpeixClient::builder()
+///     .base_url("https://mpeix.example.com")
+///     .build()?;
+/// let schedule = client.schedule(&ScheduleType::Group, "А-08-19", 0).await?;
+/// ```
+pub struct MpeixClient {
+    api: MpeixPublicApi,
+}
+
+/// Builder of [MpeixClient], only `base_url` is required
+pub struct MpeixClientBuilder {
+    base_url: Option<String>,
+    timeout: Duration,
+    max_retries: u32,
+    retry_backoff: Duration,
+    user_agent: String,
+}
+
+impl Default for MpeixClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            timeout: Duration::from_secs(15),
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(500),
+            user_agent: format!("mpeix-client/{}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+}
+
+impl MpeixClientBuilder {
+    /// Base url of the `app_schedule` microservice, without the trailing `/`
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_owned());
+        self
+    }
+
+    /// Total timeout of each request attempt, 15 seconds by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many times the request failed because of the network or the server is repeated,
+    /// 2 by default. The pause between the attempts doubles every time, starting from `backoff`.
+    pub fn retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Please, set the name of your project here, so we know who uses the api
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_owned();
+        self
+    }
+
+    pub fn build(self) -> std::result::Result<MpeixClient, MpeixPublicApiBuilderError> {
+        let mut builder = MpeixPublicApi::builder()
+            .timeout(self.timeout)
+            .default_header("User-Agent", &self.user_agent)
+            .default_header("Content-Type", "application/json")
+            .interceptor(Arc::new(RetryInterceptor {
+                max_retries: self.max_retries,
+                backoff: self.retry_backoff,
+            }));
+        if let Some(base_url) = self.base_url {
+            builder = builder.base_url(base_url);
+        }
+        Ok(MpeixClient {
+            api: builder.build()?,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct BatchScheduleRequest<'a> {
+    schedules: &'a [ScheduleRef],
+    offset: i32,
+}
+
+impl MpeixClient {
+    pub fn builder() -> MpeixClientBuilder {
+        MpeixClientBuilder::default()
+    }
+
+    /// Stable id of the schedule on the MPEI side
+    pub async fn id(&self, r#type: &ScheduleType, name: &str) -> Result<i64> {
+        self.api.id(r#type, name).await
+    }
+
+    /// Week of the schedule: `0` is the current week, `1` is the next one, `-1` is the previous one
+    pub async fn schedule(
+        &self,
+        r#type: &ScheduleType,
+        name: &str,
+        offset: i32,
+    ) -> Result<Schedule> {
+        self.api.schedule(r#type, name, offset, None).await
+    }
+
+    /// The same as [MpeixClient::schedule], but only the `days` of each week are kept
+    pub async fn schedule_of_days(
+        &self,
+        r#type: &ScheduleType,
+        name: &str,
+        offset: i32,
+        days: &DaysRange,
+    ) -> Result<Schedule> {
+        self.api
+            .schedule(r#type, name, offset, Some(days.to_string()))
+            .await
+    }
+
+    /// Changes of the classes detected during the last `days`, at most 30
+    pub async fn changes(
+        &self,
+        r#type: &ScheduleType,
+        name: &str,
+        days: u32,
+    ) -> Result<ScheduleChangelog> {
+        self.api.changes(r#type, name, days).await
+    }
+
+    /// The same week of several schedules at once, keyed by schedule name.
+    /// One failed schedule does not fail the whole request, check [BatchScheduleResult].
+    pub async fn schedules_batch(
+        &self,
+        schedules: &[ScheduleRef],
+        offset: i32,
+    ) -> Result<BTreeMap<String, BatchScheduleResult>> {
+        let request = serde_json::to_string(&BatchScheduleRequest { schedules, offset })
+            .expect("Schedule refs are always serializable");
+        self.api.schedules_batch(request).await
+    }
+
+    /// Find schedules by the part of the name, optionally of the certain type only
+    pub async fn search(
+        &self,
+        query: &str,
+        r#type: Option<ScheduleType>,
+    ) -> Result<Vec<ScheduleSearchResult>> {
+        self.api.search(query, r#type).await
+    }
+
+    /// Rules of the non-standard academic weeks numbering,
+    /// with the explanation of the week number of the `date`, if it is specified
+    pub async fn shift_rules(&self, date: Option<NaiveDate>) -> Result<ShiftRules> {
+        self.api.shift_rules(date).await
+    }
+}
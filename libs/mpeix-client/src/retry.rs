@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use restix::{BoxFuture, Interceptor, Next};
+
+/// Repeat the requests failed because of the network or the server,
+/// with exponentially growing pauses between the attempts: `backoff`, `2 * backoff`, etc.
+pub(crate) struct RetryInterceptor {
+    pub(crate) max_retries: u32,
+    pub(crate) backoff: Duration,
+}
+
+impl Interceptor for RetryInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let mut attempt = 0;
+            loop {
+                // requests with streaming bodies cannot be cloned, they are sent only once
+                let copy = match attempt < self.max_retries {
+                    true => request.try_clone(),
+                    false => None,
+                };
+                let Some(copy) = copy else {
+                    return next.run(request).await;
+                };
+                let result = next.run(copy).await;
+                if !should_retry(&result) {
+                    return result;
+                }
+                tokio::time::sleep(self.backoff * 2u32.pow(attempt)).await;
+                attempt += 1;
+            }
+        })
+    }
+}
+
+/// Only transient failures are retried: client errors will not disappear by themselves
+fn should_retry(result: &reqwest::Result<reqwest::Response>) -> bool {
+    match result {
+        Ok(response) => response.status().is_server_error(),
+        Err(e) => e.is_connect() || e.is_timeout(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::should_retry;
+
+    fn response(status: u16) -> reqwest::Result<reqwest::Response> {
+        Ok(http::Response::builder()
+            .status(status)
+            .body("")
+            .unwrap()
+            .into())
+    }
+
+    #[test]
+    fn only_server_errors_are_retried() {
+        assert!(should_retry(&response(502)));
+        assert!(should_retry(&response(503)));
+        assert!(!should_retry(&response(200)));
+        assert!(!should_retry(&response(404)));
+    }
+}
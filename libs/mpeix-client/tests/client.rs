@@ -0,0 +1,81 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+use mpeix_client::{models::ScheduleType, MpeixClient};
+
+/// Serve the `responses` one per connection, return the base url
+/// and the handle which yields the received request lines
+fn serve(responses: Vec<(u16, &'static str)>) -> (String, thread::JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut request_lines = Vec::new();
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            request_lines.push(line.trim().to_owned());
+            // skip the headers, requests of the test have no body
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            write!(
+                stream,
+                "HTTP/1.1 {status} Status\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        }
+        request_lines
+    });
+    (base_url, handle)
+}
+
+#[tokio::test]
+async fn server_errors_are_retried() {
+    let (base_url, server) = serve(vec![
+        (503, ""),
+        (
+            200,
+            r#"{"items": [{"name": "А-08-19", "description": "ИВТИ", "id": "1", "type": "GROUP"}]}"#,
+        ),
+    ]);
+    let client = MpeixClient::builder()
+        .base_url(&base_url)
+        .retries(1, Duration::from_millis(10))
+        .build()
+        .unwrap();
+    let results = client
+        .search("а-08", Some(ScheduleType::Group))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].name, "А-08-19");
+    let requests = server.join().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[1].starts_with("GET /v1/search?q="));
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let (base_url, server) = serve(vec![(400, r#"{"error": "Invalid schedule type"}"#)]);
+    let client = MpeixClient::builder()
+        .base_url(&base_url)
+        .retries(3, Duration::from_millis(10))
+        .build()
+        .unwrap();
+    assert!(client
+        .schedule(&ScheduleType::Group, "А-08-19", 0)
+        .await
+        .is_err());
+    assert_eq!(server.join().unwrap().len(), 1);
+}
@@ -2,11 +2,15 @@ use proc_macro2::TokenStream;
 use proc_macro_error::{abort, ResultExt};
 use quote::{quote, ToTokens};
 use syn::{
-    parse::Parse, punctuated::Punctuated, token::Async, Block, ExprAssign, Ident, ImplItem,
-    ImplItemMethod, ItemTrait, LitStr, Signature, Token, TraitItem, TraitItemMethod, Visibility,
+    parse::Parse, punctuated::Punctuated, token::Async, Attribute, Block, ExprAssign, ExprParen,
+    FnArg, Ident, ImplItem, ImplItemMethod, ItemTrait, LitStr, Signature, Token, TraitItem,
+    TraitItemMethod, Type, Visibility,
 };
 
-use crate::commons::StringExt;
+use crate::{
+    commons::{emit_violations, StringExt, Violation},
+    method::definition_result_type,
+};
 
 /// Intermediate representation of an Api trait definition.
 /// This structure is generated from the `#[api]` attribute macro.
@@ -19,6 +23,10 @@ struct ApiIR {
 #[derive(Default)]
 struct AttrPropertiesIR {
     base_url: Option<LitStr>,
+    /// Error type of the generated methods, e.g. `"MyError"`
+    error: Option<LitStr>,
+    /// Name of the trait implemented by the generated struct, e.g. `"ExampleClient"`
+    client_trait: Option<LitStr>,
 }
 
 impl Parse for ApiIR {
@@ -51,8 +59,12 @@ impl Parse for AttrPropertiesIR {
             let value: LitStr = syn::parse2(assn.right.to_token_stream())?;
             match ident.to_string().as_str() {
                 "base_url" => props.base_url = Some(value),
+                "error" => props.error = Some(value),
+                "client_trait" => props.client_trait = Some(value),
                 id => {
-                    let message = format!("Unknown identifier `{id}`, expected `base_url`");
+                    let message = format!(
+                        "Unknown identifier `{id}`, expected `base_url`, `error` or `client_trait`"
+                    );
                     return Err(syn::Error::new(ident.span(), message));
                 }
             }
@@ -69,19 +81,25 @@ impl Parse for AttrPropertiesIR {
 ///
 /// For the implementation of REST Api calls, mark methods with attribute macros
 /// `#[get("...")]`, `#[post("...")]`, and others.
+///
+/// With `client_trait = "..."` the struct also implements the generated trait
+/// with the same methods, so the code using the Api can be tested with stubs.
 pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
     // Parsing
     let ir: ApiIR = syn::parse2(item).unwrap_or_abort();
     let attr_props: AttrPropertiesIR = syn::parse2(attr).unwrap_or_abort();
     // Analyzing
     analyze_attr_props(&attr_props);
+    emit_violations(analyze_methods(&ir));
     // Codegen
-    let struct_definition = codegen_struct(&ir);
+    let struct_definition = codegen_struct(&ir, &attr_props);
     let builder_definition = codegen_struct_builder(&ir, &attr_props);
+    let client_trait_definition = codegen_client_trait(&ir, &attr_props);
 
     quote! {
         #struct_definition
         #builder_definition
+        #client_trait_definition
     }
 }
 
@@ -94,15 +112,70 @@ fn analyze_attr_props(attr_props: &AttrPropertiesIR) {
             abort!(base_url, "`base_url` should not end with `/`");
         }
     }
+    if let Some(error) = &attr_props.error {
+        if error.parse::<Type>().is_err() {
+            abort!(error, "`error` should be a type, e.g. `\"MyError\"`");
+        }
+    }
+    if let Some(client_trait) = &attr_props.client_trait {
+        if client_trait.parse::<Ident>().is_err() {
+            abort!(
+                client_trait,
+                "`client_trait` should be a trait name, e.g. `\"ExampleClient\"`"
+            );
+        }
+    }
+}
+
+/// Names of the request method attribute macros
+const METHOD_ATTRS: [&str; 5] = ["get", "post", "put", "delete", "patch"];
+
+/// Find trait methods which are not marked with exactly one request method attribute
+fn analyze_methods(ir: &ApiIR) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for method in &ir.methods {
+        let name = &method.sig.ident;
+        let method_attrs = method
+            .attrs
+            .iter()
+            .filter(|attr| {
+                attr.path
+                    .segments
+                    .last()
+                    .is_some_and(|it| METHOD_ATTRS.iter().any(|method| it.ident == method))
+            })
+            .collect::<Vec<_>>();
+        if method_attrs.is_empty() {
+            violations.push(
+                Violation::new(
+                    name,
+                    format!("Method `{name}` has no request method attribute"),
+                )
+                .with_help("mark the method with `#[get(\"/...\")]` or `#[post(\"/...\")]`"),
+            );
+        }
+        for attr in method_attrs.iter().skip(1) {
+            violations.push(
+                Violation::new(
+                    attr,
+                    format!("Method `{name}` has more than one request method attribute"),
+                )
+                .with_help(
+                    "keep only one of `#[get]`, `#[post]`, `#[put]`, `#[delete]`, `#[patch]`",
+                ),
+            );
+        }
+    }
+    violations
 }
 
 /// Generate the code for the struct definition and implementation
 /// (with `builder()` method and methods copied from source trait)
-fn codegen_struct(ir: &ApiIR) -> TokenStream {
+fn codegen_struct(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStream {
     let vis = &ir.visibility;
     let name = &ir.name;
     let builder_name = format!("{}Builder", &ir.name).as_ident();
-    let methods = codegen_struct_impl_methods(ir);
+    let methods = codegen_struct_impl_methods(ir, attr_props);
     let client_type = codegen_client_type();
 
     quote! {
@@ -110,6 +183,8 @@ fn codegen_struct(ir: &ApiIR) -> TokenStream {
         #vis struct #name {
             client: #client_type,
             base_url: ::std::string::String,
+            interceptor: ::std::option::Option<::std::sync::Arc<dyn ::restix::Interceptor>>,
+            on_timings: ::std::option::Option<::restix::OnTimings>,
         }
 
         impl #name {
@@ -126,8 +201,43 @@ fn codegen_client_type() -> TokenStream {
     quote!(::reqwest::Client)
 }
 
+/// Generate the code which constructs a new client from builder's `timeout`, `proxy`,
+/// `default_headers` and `on_timings` fields, if the client was not passed to the builder explicitly
+#[cfg(feature = "reqwest")]
+fn codegen_client_construction(builder_error_name: &Ident) -> TokenStream {
+    quote! {
+        let mut client_builder = ::reqwest::ClientBuilder::new();
+        if let Some(timeout) = self.timeout {
+            client_builder = client_builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = ::reqwest::Proxy::all(proxy.as_str())
+                .map_err(|e| #builder_error_name(format!("invalid `proxy`: {e}")))?;
+            client_builder = client_builder.proxy(proxy);
+        }
+        if self.on_timings.is_some() {
+            client_builder = client_builder.dns_resolver(::std::sync::Arc::new(::restix::TimingResolver));
+        }
+        if !self.default_headers.is_empty() {
+            let mut headers = ::reqwest::header::HeaderMap::new();
+            for (name, value) in &self.default_headers {
+                let name = ::reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| #builder_error_name(format!("invalid header name `{name}`: {e}")))?;
+                let value = ::reqwest::header::HeaderValue::from_str(value)
+                    .map_err(|e| #builder_error_name(format!("invalid header value `{value}`: {e}")))?;
+                headers.insert(name, value);
+            }
+            client_builder = client_builder.default_headers(headers);
+        }
+        client_builder
+            .build()
+            .map_err(|e| #builder_error_name(format!("cannot build client: {e}")))?
+    }
+}
+
 /// Generate builder for Api struct.
-/// Builder allow us to override `base_url` field.
+/// Builder allow us to override `base_url` field, pass the ready-made client
+/// or configure the client which will be created for this Api instance only.
 fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStream {
     let vis = &ir.visibility;
     let name = &ir.name;
@@ -135,6 +245,7 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
     let builder_error_name = format!("{}BuilderError", &ir.name).as_ident();
     let builder_error_description = format!("Cannot construct {name}: {{}}");
     let client_type = codegen_client_type();
+    let client_construction = codegen_client_construction(&builder_error_name);
     let base_url = if let Some(base_url) = attr_props.base_url.as_ref().map(LitStr::value) {
         quote!(::std::option::Option::Some(#base_url.to_owned()))
     } else {
@@ -145,6 +256,11 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
         #vis struct #builder_name {
             client: ::std::option::Option<#client_type>,
             base_url: ::std::option::Option<::std::string::String>,
+            timeout: ::std::option::Option<::std::time::Duration>,
+            proxy: ::std::option::Option<::std::string::String>,
+            default_headers: ::std::vec::Vec<(::std::string::String, ::std::string::String)>,
+            interceptor: ::std::option::Option<::std::sync::Arc<dyn ::restix::Interceptor>>,
+            on_timings: ::std::option::Option<::restix::OnTimings>,
         }
 
         impl Default for #builder_name {
@@ -152,6 +268,11 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
                 #builder_name {
                     client: ::std::option::Option::None,
                     base_url: #base_url,
+                    timeout: ::std::option::Option::None,
+                    proxy: ::std::option::Option::None,
+                    default_headers: ::std::vec::Vec::new(),
+                    interceptor: ::std::option::Option::None,
+                    on_timings: ::std::option::Option::None,
                 }
             }
         }
@@ -171,6 +292,39 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
                 self
             }
 
+            /// Total request timeout of the client created for this Api instance
+            pub fn timeout(mut self, timeout: ::std::time::Duration) -> #builder_name {
+                self.timeout = ::std::option::Option::Some(timeout);
+                self
+            }
+
+            /// Proxy URL for all requests of the client created for this Api instance
+            pub fn proxy(mut self, proxy: ::std::string::String) -> #builder_name {
+                self.proxy = ::std::option::Option::Some(proxy);
+                self
+            }
+
+            /// Header which is added to every request of the client created for this Api instance
+            pub fn default_header(mut self, name: &str, value: &str) -> #builder_name {
+                self.default_headers.push((name.to_owned(), value.to_owned()));
+                self
+            }
+
+            /// Interceptor which wraps every request of this Api instance,
+            /// can be combined with the explicitly passed `client`
+            pub fn interceptor(mut self, interceptor: ::std::sync::Arc<dyn ::restix::Interceptor>) -> #builder_name {
+                self.interceptor = ::std::option::Option::Some(interceptor);
+                self
+            }
+
+            /// Callback with the DNS lookup and the first byte timings of every call.
+            /// The client created for this Api instance gets the [restix::TimingResolver]
+            /// for the DNS timings, the explicitly passed `client` should have it already
+            pub fn on_timings(mut self, on_timings: ::restix::OnTimings) -> #builder_name {
+                self.on_timings = ::std::option::Option::Some(on_timings);
+                self
+            }
+
             pub fn build(self) -> ::std::result::Result<#name, #builder_error_name> {
                 if let Some(base_url) = &self.base_url {
                     if base_url.is_empty() {
@@ -183,12 +337,23 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
                     return ::std::result::Result::Err(#builder_error_name("`base_url` not specified".to_owned()));
                 }
 
-                if self.client.is_none() {
-                    return ::std::result::Result::Err(#builder_error_name("empty `client`".to_owned()))
-                }
+                let has_client_options = self.timeout.is_some()
+                    || self.proxy.is_some()
+                    || !self.default_headers.is_empty();
+                let client = match self.client {
+                    Some(_) if has_client_options => {
+                        return ::std::result::Result::Err(#builder_error_name(
+                            "`timeout`, `proxy` and `default_header` cannot be applied to the explicitly passed `client`".to_owned()
+                        ));
+                    }
+                    Some(client) => client,
+                    None => { #client_construction }
+                };
                 ::std::result::Result::Ok(#name {
-                    client: self.client.unwrap(),
+                    client,
                     base_url: self.base_url.unwrap(),
+                    interceptor: self.interceptor,
+                    on_timings: self.on_timings,
                 })
             }
         }
@@ -208,17 +373,30 @@ fn codegen_struct_builder(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStr
 
 /// Generate stud struct methods from trait methods.
 /// All methods will be forced to be `pub` and `async`.
-fn codegen_struct_impl_methods(ir: &ApiIR) -> TokenStream {
+///
+/// The error type of the api is passed to the request method attribute macros
+/// with the `#[error(...)]` method attribute, unless the method has its own one.
+fn codegen_struct_impl_methods(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStream {
     let vis: Visibility = syn::parse_quote!(pub);
     let block: Block = syn::parse_quote!({ todo!() });
     let asyncness: Async = syn::parse_quote!(async);
+    let error_attr: Option<Attribute> = attr_props.error.as_ref().map(|error| {
+        let error: Type = error.parse().unwrap_or_abort();
+        syn::parse_quote!(#[error(#error)])
+    });
 
     let methods = ir
         .methods
         .iter()
         .map(|method| {
+            let mut attrs = method.attrs.to_owned();
+            if let Some(error_attr) = &error_attr {
+                if !attrs.iter().any(|attr| attr.path.is_ident("error")) {
+                    attrs.push(error_attr.to_owned());
+                }
+            }
             ImplItem::Method(ImplItemMethod {
-                attrs: method.attrs.to_owned(),
+                attrs,
                 vis: vis.to_owned(),
                 defaultness: None,
                 sig: Signature {
@@ -234,6 +412,72 @@ fn codegen_struct_impl_methods(ir: &ApiIR) -> TokenStream {
     }
 }
 
+/// Generate the trait with the methods of the Api and its implementation for the struct,
+/// which calls the struct methods. Nothing is generated without `client_trait` property.
+///
+/// The trait is object safe (`async_trait`), so it can be used as `Arc<dyn ExampleClient>`.
+fn codegen_client_trait(ir: &ApiIR, attr_props: &AttrPropertiesIR) -> TokenStream {
+    let Some(client_trait) = &attr_props.client_trait else {
+        return TokenStream::new();
+    };
+    let client_trait: Ident = client_trait.parse().unwrap_or_abort();
+    let vis = &ir.visibility;
+    let name = &ir.name;
+    let api_error: Option<Type> = attr_props
+        .error
+        .as_ref()
+        .map(|error| error.parse().unwrap_or_abort());
+
+    let signatures = ir
+        .methods
+        .iter()
+        .map(|method| {
+            let method_error = method.attrs.iter().find_map(|attr| {
+                attr.path
+                    .is_ident("error")
+                    .then(|| syn::parse2::<ExprParen>(attr.tokens.to_owned()).unwrap_or_abort())
+                    .map(|paren| {
+                        syn::parse2::<Type>(paren.expr.to_token_stream()).unwrap_or_abort()
+                    })
+            });
+            let result_type = definition_result_type(
+                &method.sig.output,
+                method_error.as_ref().or(api_error.as_ref()),
+            );
+            let name = &method.sig.ident;
+            // restix attributes of the arguments are not needed outside of the struct methods
+            let inputs = method.sig.inputs.iter().map(|arg| match arg {
+                FnArg::Typed(pat_type) => {
+                    let (pat, ty) = (&pat_type.pat, &pat_type.ty);
+                    quote!(#pat: #ty)
+                }
+                FnArg::Receiver(_) => quote!(&self),
+            });
+            quote!(async fn #name(#( #inputs ),*) -> #result_type)
+        })
+        .collect::<Vec<_>>();
+    let calls = ir.methods.iter().map(|method| {
+        let method_name = &method.sig.ident;
+        let args = method.sig.inputs.iter().filter_map(|arg| match arg {
+            FnArg::Typed(pat_type) => Some(&pat_type.pat),
+            FnArg::Receiver(_) => None,
+        });
+        quote!(#name::#method_name(self, #( #args ),*).await)
+    });
+
+    quote! {
+        #[::restix::async_trait]
+        #vis trait #client_trait: ::std::marker::Send + ::std::marker::Sync {
+            #( #signatures; )*
+        }
+
+        #[::restix::async_trait]
+        impl #client_trait for #name {
+            #( #signatures { #calls } )*
+        }
+    }
+}
+
 #[cfg(feature = "reqwest")]
 #[cfg(test)]
 mod tests {
@@ -270,6 +514,43 @@ mod tests {
         assert_eq!(ir.methods.len(), 2);
     }
 
+    #[test]
+    fn test_analyze_methods() {
+        let trait_definition: ItemTrait = syn::parse_quote! {
+            #[api]
+            pub trait ExampleTrait {
+                #[get("/search")]
+                async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+
+                async fn publish(&self, #[path] id: i32);
+
+                #[get("/user/{id}")]
+                #[post("/user/{id}")]
+                async fn user(&self, #[path] id: i32);
+
+                #[put("/user/{id}")]
+                async fn update_user(&self, #[path] id: i32);
+
+                #[delete("/user/{id}")]
+                #[patch("/user/{id}")]
+                async fn delete_user(&self, #[path] id: i32);
+            }
+        };
+        let ir: ApiIR = syn::parse2(trait_definition.to_token_stream()).unwrap();
+        let messages = analyze_methods(&ir)
+            .into_iter()
+            .map(|it| it.message)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            vec![
+                "Method `publish` has no request method attribute",
+                "Method `user` has more than one request method attribute",
+                "Method `delete_user` has more than one request method attribute",
+            ]
+        );
+    }
+
     #[test]
     fn test_parse_attr_props_ir() {
         let attr_props: AttrPropertiesIR =
@@ -284,5 +565,30 @@ mod tests {
     fn test_parse_attr_props_empty() {
         let attr_props: AttrPropertiesIR = syn::parse2(quote!()).unwrap();
         assert_eq!(attr_props.base_url, None);
+        assert_eq!(attr_props.error, None);
+        assert_eq!(attr_props.client_trait, None);
+    }
+
+    #[test]
+    fn test_parse_attr_props_client_trait() {
+        let attr_props: AttrPropertiesIR = syn::parse2(quote!(
+            base_url = "https://example.com",
+            client_trait = "ExampleClient"
+        ))
+        .unwrap();
+        assert_eq!(
+            attr_props.client_trait.map(|it| it.value()),
+            Some("ExampleClient".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_attr_props_error() {
+        let attr_props: AttrPropertiesIR =
+            syn::parse2(quote!(base_url = "https://example.com", error = "MyError")).unwrap();
+        assert_eq!(
+            attr_props.error.map(|it| it.value()),
+            Some("MyError".to_string())
+        );
     }
 }
@@ -1,5 +1,6 @@
 use proc_macro2::{Ident, Span};
-use proc_macro_error::ResultExt;
+use proc_macro_error::{abort_if_dirty, Diagnostic, DiagnosticExt, Level, ResultExt, SpanRange};
+use quote::ToTokens;
 
 pub trait StringExt {
     fn as_ident(&self) -> Ident;
@@ -22,3 +23,41 @@ impl<S: AsRef<str>> StringExt for S {
         self.as_ref().trim_start_matches("r#")
     }
 }
+
+/// A single misuse of restix attributes found during analysis.
+///
+/// Analysis passes collect all violations first and report them together,
+/// so that the user can fix every problem in one go.
+pub struct Violation {
+    pub span: SpanRange,
+    pub message: String,
+    pub help: Option<String>,
+}
+
+impl Violation {
+    pub fn new<T: ToTokens>(tokens: T, message: impl Into<String>) -> Self {
+        Self {
+            span: SpanRange::from_tokens(&tokens),
+            message: message.into(),
+            help: None,
+        }
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+}
+
+/// Emit all violations as compile errors and abort if there were any
+pub fn emit_violations(violations: Vec<Violation>) {
+    for violation in violations {
+        let mut diagnostic =
+            Diagnostic::spanned_range(violation.span, Level::Error, violation.message);
+        if let Some(help) = violation.help {
+            diagnostic = diagnostic.help(help);
+        }
+        diagnostic.emit();
+    }
+    abort_if_dirty();
+}
@@ -9,4 +9,7 @@ pub use method::*;
 pub enum Method {
     Get,
     Post,
+    Put,
+    Delete,
+    Patch,
 }
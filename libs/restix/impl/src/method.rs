@@ -2,11 +2,18 @@ use proc_macro2::{Ident, TokenStream};
 use proc_macro_error::{abort, ResultExt};
 use quote::{quote, ToTokens};
 use syn::{
-    parse::Parse, spanned::Spanned, Attribute, ExprParen, FnArg, ImplItemMethod, LitStr, PatType,
-    ReturnType, Type, TypePath,
+    parenthesized,
+    parse::{Parse, ParseStream, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Expr, ExprAssign, ExprLit, ExprParen, FnArg, ImplItemMethod, Lit, LitInt, LitStr,
+    PatType, ReturnType, Token, Type, TypePath,
 };
 
-use crate::{commons::StringExt, Method};
+use crate::{
+    commons::{emit_violations, StringExt, Violation},
+    Method,
+};
 
 /// Intermediate representation of an Method definition.
 struct MethodIR {
@@ -18,18 +25,43 @@ struct MethodIR {
 
 enum AttrIR {
     MapResponseWith(AttrMapResponseWithIR),
+    Headers(AttrHeadersIR),
+    /// Error type of the method, passed by `#[api(error = "...")]`
+    Error(Box<Type>),
+    Cache(AttrCacheIR),
 }
 
 struct AttrMapResponseWithIR {
     mapper: TypePath,
 }
 
+/// Names of the response headers captured into `WithHeaders<T>`, e.g. `#[headers("etag")]`
+struct AttrHeadersIR {
+    /// Path of the attribute, kept for the diagnostics
+    path: syn::Path,
+    names: Vec<LitStr>,
+}
+
+/// Response cache of the method, e.g. `#[cache(ttl = "60s", key = "url", max_entries = 100)]`
+struct AttrCacheIR {
+    /// Path of the attribute, kept for the diagnostics
+    path: syn::Path,
+    ttl_ms: u64,
+    max_entries: usize,
+}
+
+/// Default limit of the cached responses of one method
+const DEFAULT_CACHE_MAX_ENTRIES: usize = 1000;
+
 enum ArgIR {
     Receiver,
     Typed {
         name: Ident,
-        r#type: Type,
-        kind: ArgKindIR,
+        r#type: Box<Type>,
+        /// `None` if the argument has no known restix attribute
+        kind: Option<ArgKindIR>,
+        /// All attributes of the argument, kept for the analysis
+        attrs: Vec<Attribute>,
     },
 }
 
@@ -39,14 +71,41 @@ struct ArgsCounter {
 }
 
 enum ArgKindIR {
-    Query(Option<Ident>),
+    Query(Option<Ident>, QueryStyleIR),
     Path(Option<Ident>),
     Body,
+    /// Body sent as is, with the optional `Content-Type` header
+    RawBody(Option<LitStr>),
+    /// Body serialized as `application/x-www-form-urlencoded`
+    Form,
+    /// `restix::Multipart` body sent as `multipart/form-data`
+    Multipart,
+}
+
+/// How collection values of `#[query]` arguments are expanded into the url
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+enum QueryStyleIR {
+    /// `?id=1&id=2&id=3`
+    #[default]
+    Repeat,
+    /// `?id=1,2,3`
+    Comma,
+}
+
+/// Arguments of the argument attribute, e.g. `#[query("ids", style = "comma")]`
+/// or `#[raw_body(content_type = "text/plain")]`
+#[derive(Default)]
+struct ArgAttrArgsIR {
+    alt_name: Option<LitStr>,
+    style: Option<LitStr>,
+    content_type: Option<LitStr>,
 }
 
 enum ReturnTypeIR {
     RawResponse,
-    Typed(Type),
+    Typed(Box<Type>),
+    /// `WithHeaders<T>`, contains the full type and the type of the body `T`
+    WithHeaders(Box<Type>, Box<Type>),
 }
 
 impl Parse for MethodIR {
@@ -60,12 +119,13 @@ impl Parse for MethodIR {
                 .into_iter()
                 .map(parse_attr_ir)
                 .collect::<syn::Result<Vec<_>>>()?,
-            args: method
-                .sig
-                .inputs
-                .into_iter()
-                .map(|fn_arg| parse_arg_ir(fn_arg, &mut args_counter))
-                .collect::<syn::Result<Vec<_>>>()?,
+            args: collect_all(
+                method
+                    .sig
+                    .inputs
+                    .into_iter()
+                    .map(|fn_arg| parse_arg_ir(fn_arg, &mut args_counter)),
+            )?,
             return_type: syn::parse2::<ReturnTypeIR>(method.sig.output.into_token_stream())?,
         })
     }
@@ -75,6 +135,16 @@ fn parse_attr_ir(attr: Attribute) -> syn::Result<AttrIR> {
     Ok(
         match attr.path.get_ident().map(ToString::to_string).as_deref() {
             Some("map_response_with") => AttrIR::MapResponseWith(syn::parse2(attr.tokens)?),
+            Some("headers") => AttrIR::Headers(AttrHeadersIR {
+                path: attr.path,
+                names: parse_headers_attr_args.parse2(attr.tokens)?,
+            }),
+            Some("error") => AttrIR::Error(Box::new(syn::parse2(
+                syn::parse2::<ExprParen>(attr.tokens)?
+                    .expr
+                    .into_token_stream(),
+            )?)),
+            Some("cache") => AttrIR::Cache(parse_cache_attr(attr)?),
             _ => return Err(syn::Error::new(attr.span(), "Unknown attribute")),
         },
     )
@@ -87,6 +157,94 @@ impl Parse for AttrMapResponseWithIR {
     }
 }
 
+/// Parse cache properties like `#[cache(ttl = "60s", key = "url", max_entries = 100)]`
+fn parse_cache_attr(attr: Attribute) -> syn::Result<AttrCacheIR> {
+    let span = attr.span();
+    let exprs = (|input: ParseStream| {
+        let content;
+        parenthesized!(content in input);
+        Punctuated::<ExprAssign, Token![,]>::parse_terminated(&content)
+    })
+    .parse2(attr.tokens)?;
+    let mut ttl = None;
+    let mut max_entries = DEFAULT_CACHE_MAX_ENTRIES;
+    for ExprAssign { left, right, .. } in exprs {
+        let ident: Ident = syn::parse2(left.to_token_stream())?;
+        match ident.to_string().as_str() {
+            "ttl" => ttl = Some(syn::parse2::<LitStr>(right.to_token_stream())?),
+            "key" => {
+                let key: LitStr = syn::parse2(right.to_token_stream())?;
+                if key.value() != "url" {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Unknown cache key. Must be one of: `url`",
+                    ));
+                }
+            }
+            "max_entries" => {
+                max_entries = syn::parse2::<LitInt>(right.to_token_stream())?.base10_parse()?
+            }
+            id => {
+                let message =
+                    format!("Unknown identifier `{id}`, expected `ttl`, `key` or `max_entries`");
+                return Err(syn::Error::new(ident.span(), message));
+            }
+        }
+    }
+    let Some(ttl) = ttl else {
+        return Err(syn::Error::new(span, "Expected `ttl = \"60s\"` property"));
+    };
+    let ttl_ms = parse_ttl_ms(&ttl.value()).ok_or_else(|| {
+        syn::Error::new(
+            ttl.span(),
+            "Invalid ttl, expected e.g. `\"500ms\"`, `\"60s\"`, `\"5m\"` or `\"1h\"`",
+        )
+    })?;
+    Ok(AttrCacheIR {
+        path: attr.path,
+        ttl_ms,
+        max_entries,
+    })
+}
+
+/// Parse durations like `"500ms"`, `"60s"`, `"5m"` or `"1h"` into milliseconds
+fn parse_ttl_ms(ttl: &str) -> Option<u64> {
+    let unit_start = ttl.find(|c: char| !c.is_ascii_digit())?;
+    let value: u64 = ttl[..unit_start].parse().ok()?;
+    let unit_ms = match &ttl[unit_start..] {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        _ => return None,
+    };
+    value.checked_mul(unit_ms).filter(|it| *it > 0)
+}
+
+/// Parse header names like `("etag", "x-ratelimit-remaining")`
+fn parse_headers_attr_args(input: ParseStream) -> syn::Result<Vec<LitStr>> {
+    let content;
+    parenthesized!(content in input);
+    let names: Punctuated<LitStr, Token![,]> = Punctuated::parse_terminated(&content)?;
+    if names.is_empty() {
+        return Err(syn::Error::new(
+            content.span(),
+            "Expected at least one header name, remove the attribute to capture all headers",
+        ));
+    }
+    for name in &names {
+        let is_valid = !name.value().is_empty()
+            && name
+                .value()
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c));
+        if !is_valid {
+            return Err(syn::Error::new(name.span(), "Invalid header name"));
+        }
+    }
+    Ok(names.into_iter().collect())
+}
+
 fn parse_arg_ir(fn_arg: FnArg, counter: &mut ArgsCounter) -> syn::Result<ArgIR> {
     counter.common += 1;
     match &fn_arg {
@@ -102,60 +260,157 @@ fn parse_arg_ir(fn_arg: FnArg, counter: &mut ArgsCounter) -> syn::Result<ArgIR>
             }
             Ok(ArgIR::Typed {
                 name: syn::parse2(pat_type.pat.to_token_stream())?,
-                r#type: syn::parse2(pat_type.ty.to_token_stream())?,
+                r#type: Box::new(syn::parse2(pat_type.ty.to_token_stream())?),
                 kind: parse_arg_kind_ir(pat_type)?,
+                attrs: pat_type.attrs.to_owned(),
             })
         }
     }
 }
 
-fn parse_arg_kind_ir(pat_type: &PatType) -> syn::Result<ArgKindIR> {
-    let mut iter = pat_type.attrs.iter();
-    let arg_kind = if let Some(attr) = iter.next() {
-        let alt_name = if attr.tokens.is_empty() {
-            None
-        } else {
-            let expr_paren = syn::parse2::<ExprParen>(attr.tokens.to_owned())?;
-            Some(
-                syn::parse2::<LitStr>(expr_paren.expr.into_token_stream())?
-                    .value()
-                    .as_ident(),
-            )
-        };
-        match attr.path.get_ident().map(ToString::to_string).as_deref() {
-            Some("path") => ArgKindIR::Path(alt_name),
-            Some("query") => ArgKindIR::Query(alt_name),
-            Some("body") => ArgKindIR::Body,
-            _ => {
-                return Err(syn::Error::new(
-                    attr.path.span(),
-                    "Unsupported attribute. Must be one of: `path`, `query`, `body`",
-                ))
-            }
-        }
+/// Parse the kind of the argument from its first known attribute.
+/// Missing, unknown and repeated attributes are reported by [`analyze_method_ir`].
+fn parse_arg_kind_ir(pat_type: &PatType) -> syn::Result<Option<ArgKindIR>> {
+    let Some((kind, attr)) = pat_type
+        .attrs
+        .iter()
+        .find_map(|attr| arg_attr_kind(attr).map(|kind| (kind, attr)))
+    else {
+        return Ok(None);
+    };
+    let attr_args = if attr.tokens.is_empty() {
+        ArgAttrArgsIR::default()
     } else {
+        parse_arg_attr_args.parse2(attr.tokens.to_owned())?
+    };
+    if let Some(style) = attr_args.style.as_ref().filter(|_| kind != "query") {
         return Err(syn::Error::new(
-            pat_type.span(),
-            "Each argument must have attribute `#[path]`, `#[query]`, or #[body]",
+            style.span(),
+            "Property `style` is allowed only for `#[query]` arguments",
         ));
-    };
-    if let Some(attr) = iter.next() {
+    }
+    if let Some(content_type) = attr_args
+        .content_type
+        .as_ref()
+        .filter(|_| kind != "raw_body")
+    {
+        return Err(syn::Error::new(
+            content_type.span(),
+            "Property `content_type` is allowed only for `#[raw_body]` arguments",
+        ));
+    }
+    if let Some(alt_name) = attr_args.alt_name.as_ref().filter(|_| is_body_kind(kind)) {
         return Err(syn::Error::new(
-            attr.span(),
-            "An argument cannot have more than one attribute",
+            alt_name.span(),
+            "Body arguments do not have names, remove the string literal",
         ));
     }
-    Ok(arg_kind)
+    let alt_name = attr_args.alt_name.as_ref().map(|it| it.value().as_ident());
+    Ok(Some(match kind {
+        "path" => ArgKindIR::Path(alt_name),
+        "query" => ArgKindIR::Query(alt_name, parse_query_style(&attr_args)?),
+        "raw_body" => ArgKindIR::RawBody(attr_args.content_type),
+        "form" => ArgKindIR::Form,
+        "multipart" => ArgKindIR::Multipart,
+        _ => ArgKindIR::Body,
+    }))
+}
+
+/// Name of the argument attribute if it is one of `path`, `query`, `body`, `raw_body`,
+/// `form`, `multipart`
+fn arg_attr_kind(attr: &Attribute) -> Option<&'static str> {
+    match attr.path.get_ident().map(ToString::to_string).as_deref() {
+        Some("path") => Some("path"),
+        Some("query") => Some("query"),
+        Some("body") => Some("body"),
+        Some("raw_body") => Some("raw_body"),
+        Some("form") => Some("form"),
+        Some("multipart") => Some("multipart"),
+        _ => None,
+    }
+}
+
+/// Whether the argument of this kind is sent as the request body, a method can have only one
+fn is_body_kind(kind: &str) -> bool {
+    matches!(kind, "body" | "raw_body" | "form" | "multipart")
+}
+
+/// Collect all results, combining all errors into one instead of stopping at the first
+fn collect_all<T>(results: impl Iterator<Item = syn::Result<T>>) -> syn::Result<Vec<T>> {
+    let mut items = Vec::new();
+    let mut error: Option<syn::Error> = None;
+    for result in results {
+        match (result, &mut error) {
+            (Ok(item), _) => items.push(item),
+            (Err(e), Some(error)) => error.combine(e),
+            (Err(e), None) => error = Some(e),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(items),
+    }
+}
+
+/// Parse argument attribute tokens like `("q")`, `(style = "comma")`, `("ids", style = "comma")`
+/// or `(content_type = "text/plain")`
+fn parse_arg_attr_args(input: ParseStream) -> syn::Result<ArgAttrArgsIR> {
+    let content;
+    parenthesized!(content in input);
+    let exprs: Punctuated<Expr, Token![,]> = Punctuated::parse_terminated(&content)?;
+    let mut args = ArgAttrArgsIR::default();
+    for (i, expr) in exprs.into_iter().enumerate() {
+        match expr {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) if i == 0 => args.alt_name = Some(lit),
+            Expr::Assign(ExprAssign { left, right, .. }) => {
+                let ident: Ident = syn::parse2(left.to_token_stream())?;
+                let value: LitStr = syn::parse2(right.to_token_stream())?;
+                match ident.to_string().as_str() {
+                    "style" => args.style = Some(value),
+                    "content_type" => args.content_type = Some(value),
+                    id => {
+                        let message = format!(
+                            "Unknown identifier `{id}`, expected `style` or `content_type`"
+                        );
+                        return Err(syn::Error::new(ident.span(), message));
+                    }
+                }
+            }
+            expr => {
+                return Err(syn::Error::new(
+                    expr.span(),
+                    "Expected string literal name as the first item, `style = \"...\"` or `content_type = \"...\"`",
+                ))
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn parse_query_style(attr_args: &ArgAttrArgsIR) -> syn::Result<QueryStyleIR> {
+    match attr_args.style.as_ref() {
+        None => Ok(QueryStyleIR::default()),
+        Some(style) => match style.value().as_str() {
+            "repeat" => Ok(QueryStyleIR::Repeat),
+            "comma" => Ok(QueryStyleIR::Comma),
+            _ => Err(syn::Error::new(
+                style.span(),
+                "Unknown query style. Must be one of: `repeat`, `comma`",
+            )),
+        },
+    }
 }
 
 impl ArgIR {
-    fn as_query(&self) -> Option<(&Ident, &Ident)> {
+    fn as_query(&self) -> Option<(&Ident, &Ident, QueryStyleIR)> {
         match self {
             Self::Typed {
                 name,
-                kind: ArgKindIR::Query(alt_name),
+                kind: Some(ArgKindIR::Query(alt_name, style)),
                 ..
-            } => Some((name, alt_name.as_ref().unwrap_or(name))),
+            } => Some((name, alt_name.as_ref().unwrap_or(name), *style)),
             _ => None,
         }
     }
@@ -164,20 +419,26 @@ impl ArgIR {
         match self {
             Self::Typed {
                 name,
-                kind: ArgKindIR::Path(alt_name),
+                kind: Some(ArgKindIR::Path(alt_name)),
                 ..
             } => Some((name, alt_name.as_ref().unwrap_or(name))),
             _ => None,
         }
     }
 
-    fn as_body(&self) -> Option<&Ident> {
+    fn as_body(&self) -> Option<(&Ident, &ArgKindIR)> {
         match self {
             Self::Typed {
                 name,
-                kind: ArgKindIR::Body,
+                kind:
+                    Some(
+                        kind @ (ArgKindIR::Body
+                        | ArgKindIR::RawBody(_)
+                        | ArgKindIR::Form
+                        | ArgKindIR::Multipart),
+                    ),
                 ..
-            } => Some(name),
+            } => Some((name, kind)),
             _ => None,
         }
     }
@@ -188,64 +449,266 @@ impl Parse for ReturnTypeIR {
         let return_type: ReturnType = input.parse()?;
         Ok(match return_type {
             ReturnType::Default => ReturnTypeIR::RawResponse,
-            ReturnType::Type(_, t) => ReturnTypeIR::Typed(*t),
+            ReturnType::Type(_, t) => match with_headers_body_type(&t) {
+                Some(body) => ReturnTypeIR::WithHeaders(t.clone(), Box::new(body.to_owned())),
+                None => ReturnTypeIR::Typed(t),
+            },
         })
     }
 }
 
+/// Type `T` if the type is `WithHeaders<T>` (or `restix::WithHeaders<T>`)
+fn with_headers_body_type(t: &Type) -> Option<&Type> {
+    let Type::Path(TypePath { qself: None, path }) = t else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "WithHeaders" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.iter().collect::<Vec<_>>().as_slice() {
+        [syn::GenericArgument::Type(body)] => Some(body),
+        _ => None,
+    }
+}
+
 /// Parse and validate endroint url arg of attribute macro
-fn parse_attr_endpoint_url(attr: TokenStream) -> String {
+fn parse_attr_endpoint_url(attr: TokenStream) -> LitStr {
     let attr_arg = syn::parse2::<LitStr>(attr).expect_or_abort("Expected string endpoint url");
-    let endpoint_url = attr_arg.value();
-    if !endpoint_url.starts_with('/') {
+    if !attr_arg.value().starts_with('/') {
         abort!(attr_arg, "Endpoint url should start with a '/'")
     }
-    endpoint_url
+    attr_arg
 }
 
 pub fn method(method: Method, attr: TokenStream, item: TokenStream) -> TokenStream {
     // Parsing
     let ir: MethodIR = syn::parse2(item).unwrap_or_abort();
     let endpoint_url = parse_attr_endpoint_url(attr);
-    analyze_method_ir(&ir);
+    // Analyzing
+    emit_violations(analyze_method_ir(&ir, &method, &endpoint_url));
     // Codegen
-    codegen_fn_impl(ir, &endpoint_url, method)
+    codegen_fn_impl(ir, &endpoint_url.value(), method)
 }
 
-fn analyze_method_ir(ir: &MethodIR) {
-    let body_args = ir
+/// Find all misuses of argument attributes in the method
+fn analyze_method_ir(ir: &MethodIR, method: &Method, endpoint_url: &LitStr) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut body_attrs = Vec::new();
+    for arg in &ir.args {
+        let ArgIR::Typed { name, attrs, .. } = arg else {
+            continue;
+        };
+        if attrs.is_empty() {
+            violations.push(
+                Violation::new(name, format!("Argument `{name}` has no restix attribute"))
+                    .with_help(
+                        "mark the argument with `#[path]`, `#[query]`, `#[body]`, `#[raw_body]`, `#[form]`, or `#[multipart]`",
+                    ),
+            );
+        }
+        let mut first_kind: Option<&str> = None;
+        for attr in attrs {
+            let Some(kind) = arg_attr_kind(attr) else {
+                let path = attr.path.to_token_stream().to_string().replace(' ', "");
+                violations.push(
+                    Violation::new(attr, format!("Unsupported argument attribute `#[{path}]`"))
+                        .with_help(
+                            "must be one of: `#[path]`, `#[query]`, `#[body]`, `#[raw_body]`, `#[form]`, `#[multipart]`",
+                        ),
+                );
+                continue;
+            };
+            match first_kind {
+                None => {
+                    first_kind = Some(kind);
+                    if is_body_kind(kind) {
+                        body_attrs.push((kind, attr));
+                    }
+                }
+                Some(first) if first == kind => violations.push(
+                    Violation::new(attr, format!("Duplicate attribute `#[{kind}]`"))
+                        .with_help("remove the repeated attribute"),
+                ),
+                Some(first) => violations.push(
+                    Violation::new(
+                        attr,
+                        format!("Attribute `#[{kind}]` conflicts with `#[{first}]`"),
+                    )
+                    .with_help(
+                        "an argument must have exactly one of `#[path]`, `#[query]`, `#[body]`, `#[raw_body]`, `#[form]`, `#[multipart]`",
+                    ),
+                ),
+            }
+        }
+    }
+
+    if let Method::Get = method {
+        for (kind, attr) in &body_attrs {
+            violations.push(
+                Violation::new(
+                    attr,
+                    format!("`#[{kind}]` arguments are not allowed in `#[get]` methods"),
+                )
+                .with_help(
+                    "use `#[post]`, `#[put]` or `#[patch]` to send a request body, or pass the value with `#[query]`",
+                ),
+            );
+        }
+    }
+    for (kind, attr) in body_attrs.iter().skip(1) {
+        let violation = match (body_attrs[0].0, *kind) {
+            ("body", "body") => Violation::new(attr, "Only one `#[body]` argument is allowed")
+                .with_help("combine the values into a single serializable struct"),
+            (first, kind) => Violation::new(
+                attr,
+                format!("Only one `#[{first}]` or `#[{kind}]` argument is allowed"),
+            )
+            .with_help("combine the values into a single body"),
+        };
+        violations.push(violation);
+    }
+
+    for attr in &ir.attrs {
+        if let AttrIR::Headers(AttrHeadersIR { path, .. }) = attr {
+            if !matches!(ir.return_type, ReturnTypeIR::WithHeaders(..)) {
+                violations.push(
+                    Violation::new(
+                        path,
+                        "`#[headers]` attribute requires `WithHeaders<T>` return type",
+                    )
+                    .with_help("change the return type to `restix::WithHeaders<...>`"),
+                );
+            }
+        }
+    }
+
+    for attr in &ir.attrs {
+        if let AttrIR::Cache(AttrCacheIR { path, .. }) = attr {
+            if !matches!(method, Method::Get) {
+                violations.push(
+                    Violation::new(
+                        path,
+                        "`#[cache]` attribute is allowed only in `#[get]` methods",
+                    )
+                    .with_help("remove the attribute, only the idempotent requests can be cached"),
+                );
+            }
+        }
+    }
+
+    let placeholders = url_placeholders(&endpoint_url.value());
+    let path_args = ir
         .args
         .iter()
-        .filter_map(ArgIR::as_body)
+        .filter_map(ArgIR::as_path)
         .collect::<Vec<_>>();
-    if body_args.len() > 1 {
-        abort!(body_args[1], "Only one body argument is allowed");
+    for placeholder in &placeholders {
+        if !path_args
+            .iter()
+            .any(|(_, alt_name)| alt_name.to_string().unraw() == placeholder)
+        {
+            violations.push(
+                Violation::new(
+                    endpoint_url,
+                    format!("Endpoint url placeholder `{{{placeholder}}}` has no matching `#[path]` argument"),
+                )
+                .with_help(format!(
+                    "add an argument `#[path] {placeholder}: ...` or `#[path(\"{placeholder}\")] ...`"
+                )),
+            );
+        }
+    }
+    for (name, alt_name) in path_args {
+        let key = alt_name.to_string();
+        if !placeholders.iter().any(|it| it == key.unraw()) {
+            violations.push(
+                Violation::new(
+                    name,
+                    format!("`#[path]` argument `{name}` is not used in the endpoint url"),
+                )
+                .with_help(format!(
+                    "add `{{{}}}` to the endpoint url or mark the argument with `#[query]`",
+                    key.unraw()
+                )),
+            );
+        }
+    }
+    violations
+}
+
+/// Names of the format blocks in the endpoint url, e.g. `id` for `"/user/{id}"`
+fn url_placeholders(endpoint_url: &str) -> Vec<String> {
+    let mut placeholders = Vec::new();
+    let mut rest = endpoint_url;
+    while let Some(start) = rest.find('{') {
+        if rest[start + 1..].starts_with('{') {
+            rest = &rest[start + 2..];
+            continue;
+        }
+        let Some(len) = rest[start + 1..].find('}') else {
+            break;
+        };
+        placeholders.push(rest[start + 1..start + 1 + len].to_owned());
+        rest = &rest[start + 1 + len + 1..];
     }
+    placeholders
 }
 
 /// Generate impelmentation for the method from its IR
 fn codegen_fn_impl(ir: MethodIR, endpoint_url: &str, method: Method) -> TokenStream {
     let name = &ir.name;
     let args = codegen_fn_args(&ir);
-    let method_return_type = method_return_type(&ir);
     let fn_code_block = codegen_client_execution(&ir, endpoint_url, method);
-    let client_result_type = client_result_type();
+    let result_type = result_type(method_return_type(&ir), error_type(&ir));
 
     quote! {
-        pub async fn #name ( #args ) -> #client_result_type<#method_return_type>
+        pub async fn #name ( #args ) -> #result_type
         {
             #fn_code_block
         }
     }
 }
 
+/// Custom error type of the method, converted from the client errors with `?`
+fn error_type(ir: &MethodIR) -> Option<&Type> {
+    ir.attrs.iter().find_map(|attr| match attr {
+        AttrIR::Error(error) => Some(error.as_ref()),
+        _ => None,
+    })
+}
+
 fn method_return_type(ir: &MethodIR) -> TokenStream {
     match &ir.return_type {
-        ReturnTypeIR::Typed(t) => quote!(#t),
+        ReturnTypeIR::Typed(t) | ReturnTypeIR::WithHeaders(t, _) => quote!(#t),
         ReturnTypeIR::RawResponse => client_response_type(),
     }
 }
 
+/// Result type of the generated method, `reqwest::Result<T>` if there is no custom error
+fn result_type(method_return_type: TokenStream, error: Option<&Type>) -> TokenStream {
+    match error {
+        Some(error) => quote!(::std::result::Result<#method_return_type, #error>),
+        None => {
+            let client_result_type = client_result_type();
+            quote!(#client_result_type<#method_return_type>)
+        }
+    }
+}
+
+/// The same result type as the method generated from the definition with the `output`,
+/// used by the client trait of the api
+pub(crate) fn definition_result_type(output: &ReturnType, error: Option<&Type>) -> TokenStream {
+    let method_return_type = match output {
+        ReturnType::Type(_, t) => quote!(#t),
+        ReturnType::Default => client_response_type(),
+    };
+    result_type(method_return_type, error)
+}
+
 #[cfg(feature = "reqwest")]
 fn client_result_type() -> TokenStream {
     quote!(::reqwest::Result)
@@ -272,29 +735,94 @@ fn codegen_client_execution(ir: &MethodIR, endpoint_url: &str, method: Method) -
     let method_call: Ident = match method {
         Method::Get => syn::parse_quote!(get),
         Method::Post => syn::parse_quote!(post),
+        Method::Put => syn::parse_quote!(put),
+        Method::Delete => syn::parse_quote!(delete),
+        Method::Patch => syn::parse_quote!(patch),
     };
     let queries = codegen_queries(ir);
-    let body_call = if let Some(body) = ir.args.iter().find_map(ArgIR::as_body) {
-        quote!(.body(#body))
-    } else {
-        quote!()
+    let body_call = match ir.args.iter().find_map(ArgIR::as_body) {
+        Some((body, ArgKindIR::RawBody(Some(content_type)))) => quote! {
+            .header(::reqwest::header::CONTENT_TYPE, #content_type)
+            .body(#body)
+        },
+        Some((body, ArgKindIR::RawBody(None))) => quote!(.body(#body)),
+        Some((body, ArgKindIR::Form)) => quote!(.form(&#body)),
+        Some((body, ArgKindIR::Multipart)) => quote! {
+            .header(::reqwest::header::CONTENT_TYPE, ::restix::Multipart::content_type(&#body))
+            .body(::restix::Multipart::into_body(#body))
+        },
+        Some((body, _)) => codegen_body_call(body),
+        None => quote!(),
     };
     let deserialize_and_return = codegen_deserialize_and_return(ir);
+    let execute = codegen_execute(ir);
 
     quote! {
         #format_url
         #queries
 
-        let response = self.client
+        let request = self.client
             .#method_call(&full_url)
             .query(&queries)
             #body_call
-            .send()
-            .await?;
+            .build()?;
+        let response = #execute;
         #deserialize_and_return
     }
 }
 
+/// Generate the request execution, through the method's cache if it has `#[cache]`
+#[cfg(feature = "reqwest")]
+fn codegen_execute(ir: &MethodIR) -> TokenStream {
+    let cache = ir.attrs.iter().find_map(|attr| match attr {
+        AttrIR::Cache(cache) => Some(cache),
+        _ => None,
+    });
+    match cache {
+        Some(AttrCacheIR {
+            ttl_ms,
+            max_entries,
+            ..
+        }) => quote! {{
+            static CACHE: ::std::sync::OnceLock<::restix::ResponseCache> =
+                ::std::sync::OnceLock::new();
+            let cache = CACHE.get_or_init(|| {
+                ::restix::ResponseCache::new(
+                    ::std::time::Duration::from_millis(#ttl_ms),
+                    #max_entries,
+                )
+            });
+            ::restix::__execute_cached(
+                cache,
+                &self.client,
+                self.interceptor.as_ref(),
+                self.on_timings.as_ref(),
+                request,
+            ).await?
+        }},
+        None => quote! {
+            ::restix::__execute(
+                &self.client,
+                self.interceptor.as_ref(),
+                self.on_timings.as_ref(),
+                request,
+            ).await?
+        },
+    }
+}
+
+/// Serialize `#[body]` argument to JSON
+#[cfg(all(feature = "reqwest", feature = "json"))]
+fn codegen_body_call(body: &Ident) -> TokenStream {
+    quote!(.json(&#body))
+}
+
+/// Without `"json"` feature `#[body]` argument is sent as is, like `#[raw_body]`
+#[cfg(all(feature = "reqwest", not(feature = "json")))]
+fn codegen_body_call(body: &Ident) -> TokenStream {
+    quote!(.body(#body))
+}
+
 /// Generate `let full_url = format!(...)` statement
 fn codegen_format_url(ir: &MethodIR, endpoint_url: &str) -> TokenStream {
     let paths = &ir
@@ -321,11 +849,16 @@ fn codegen_queries(ir: &MethodIR) -> TokenStream {
         .args
         .iter()
         .filter_map(ArgIR::as_query)
-        .map(|(name, alt_name)| {
+        .map(|(name, alt_name, style)| {
             let key = alt_name.to_string();
             let key = key.unraw();
-            quote! {
-                #name.push_to_vec(#key, &mut queries);
+            match style {
+                QueryStyleIR::Repeat => quote! {
+                    #name.push_to_vec(#key, &mut queries);
+                },
+                QueryStyleIR::Comma => quote! {
+                    #name.push_to_vec_joined(#key, ",", &mut queries);
+                },
             }
         })
         .collect::<Vec<_>>();
@@ -338,15 +871,55 @@ fn codegen_queries(ir: &MethodIR) -> TokenStream {
     }
 }
 
+fn codegen_mapper(ir: &MethodIR) -> Option<TokenStream> {
+    ir.attrs.iter().find_map(|attr| match attr {
+        AttrIR::MapResponseWith(AttrMapResponseWithIR { mapper }) => Some(quote!(#mapper)),
+        _ => None,
+    })
+}
+
+/// Generate expression with the `HeaderMap` of all response headers,
+/// or only headers listed in the `#[headers(...)]` attribute
+#[cfg(feature = "reqwest")]
+fn codegen_capture_headers(ir: &MethodIR) -> TokenStream {
+    let names = ir.attrs.iter().find_map(|attr| match attr {
+        AttrIR::Headers(AttrHeadersIR { names, .. }) => Some(
+            names
+                .iter()
+                .map(|it| it.value().to_lowercase())
+                .collect::<Vec<_>>(),
+        ),
+        _ => None,
+    });
+    match names {
+        None => quote!(response.headers().clone()),
+        Some(names) => quote! {{
+            let mut headers = ::reqwest::header::HeaderMap::new();
+            for name in [ #( #names ),* ] {
+                for value in response.headers().get_all(name) {
+                    headers.append(::reqwest::header::HeaderName::from_static(name), value.clone());
+                }
+            }
+            headers
+        }},
+    }
+}
+
 #[cfg(all(feature = "reqwest", feature = "json"))]
 fn codegen_deserialize_and_return(ir: &MethodIR) -> TokenStream {
-    let mapper = ir
-        .attrs
-        .iter()
-        .map(|attr| match attr {
-            AttrIR::MapResponseWith(AttrMapResponseWithIR { mapper }) => Some(quote!(#mapper)),
-        })
-        .next();
+    let mapper = codegen_mapper(ir);
+    if let ReturnTypeIR::WithHeaders(_, body) = &ir.return_type {
+        let capture_headers = codegen_capture_headers(ir);
+        let body = match mapper {
+            Some(mapper) => quote!(#mapper(response.json().await?)),
+            None => quote!(response.json::<#body>().await?),
+        };
+        return quote! {
+            let headers = #capture_headers;
+            let body = #body;
+            ::std::result::Result::Ok(::restix::WithHeaders { body, headers })
+        };
+    }
     match (mapper, &ir.return_type) {
         (Some(mapper), ReturnTypeIR::RawResponse) => {
             quote!(::std::result::Result::Ok(#mapper(response)))
@@ -355,23 +928,366 @@ fn codegen_deserialize_and_return(ir: &MethodIR) -> TokenStream {
         (None, ReturnTypeIR::RawResponse) => quote!(::std::result::Result::Ok(response)),
         (None, _) => {
             let return_type = method_return_type(ir);
-            quote!(response.json::<#return_type>().await)
+            match error_type(ir) {
+                Some(_) => {
+                    quote!(::std::result::Result::Ok(response.json::<#return_type>().await?))
+                }
+                None => quote!(response.json::<#return_type>().await),
+            }
         }
     }
 }
 
 #[cfg(all(feature = "reqwest", not(feature = "json")))]
 fn codegen_deserialize_and_return(ir: &MethodIR) -> TokenStream {
-    let mapper = ir
-        .attrs
-        .iter()
-        .map(|attr| match attr {
-            AttrIR::MapResponseWith(AttrMapResponseWithIR { mapper }) => Some(quote!(#mapper)),
-        })
-        .next();
+    if let ReturnTypeIR::WithHeaders(..) = &ir.return_type {
+        return quote!(::std::compile_error!(
+            "`WithHeaders<T>` return type requires the \"json\" feature"
+        ));
+    }
+    let mapper = codegen_mapper(ir);
     if let Some(mapper) = mapper {
         quote!(Ok(#mapper(response)))
     } else {
         quote!(response)
     }
 }
+
+#[cfg(feature = "reqwest")]
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse_method(method: ImplItemMethod) -> MethodIR {
+        syn::parse2(method.to_token_stream()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_query_default_style() {
+        let ir = parse_method(syn::parse_quote! {
+            async fn users(&self, #[query("id")] ids: &[i64]);
+        });
+        let (name, alt_name, style) = ir.args.iter().find_map(ArgIR::as_query).unwrap();
+        assert_eq!(name, "ids");
+        assert_eq!(alt_name, "id");
+        assert_eq!(style, QueryStyleIR::Repeat);
+    }
+
+    #[test]
+    fn test_parse_query_comma_style() {
+        let ir = parse_method(syn::parse_quote! {
+            async fn users(&self, #[query("id", style = "comma")] ids: Vec<i64>);
+        });
+        let (_, alt_name, style) = ir.args.iter().find_map(ArgIR::as_query).unwrap();
+        assert_eq!(alt_name, "id");
+        assert_eq!(style, QueryStyleIR::Comma);
+    }
+
+    #[test]
+    fn test_parse_query_style_without_alt_name() {
+        let ir = parse_method(syn::parse_quote! {
+            async fn users(&self, #[query(style = "repeat")] ids: Vec<i64>);
+        });
+        let (name, alt_name, style) = ir.args.iter().find_map(ArgIR::as_query).unwrap();
+        assert_eq!(name, alt_name);
+        assert_eq!(style, QueryStyleIR::Repeat);
+    }
+
+    #[test]
+    fn test_parse_query_unknown_style() {
+        let method: ImplItemMethod = syn::parse_quote! {
+            async fn users(&self, #[query(style = "pipe")] ids: Vec<i64>);
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+    }
+
+    fn analyze(method: Method, endpoint_url: &str, item: ImplItemMethod) -> Vec<String> {
+        let endpoint_url = LitStr::new(endpoint_url, proc_macro2::Span::call_site());
+        analyze_method_ir(&parse_method(item), &method, &endpoint_url)
+            .into_iter()
+            .map(|it| it.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_valid_method() {
+        let messages = analyze(
+            Method::Post,
+            "/user/{id}/publish",
+            syn::parse_quote! {
+                async fn publish(&self, #[path] id: i64, #[query] tag: &str, #[body] post: &Post);
+            },
+        );
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_reports_all_violations() {
+        let messages = analyze(
+            Method::Get,
+            "/user/{id}",
+            syn::parse_quote! {
+                async fn user(&self, #[query] #[query] tag: &str, #[body] user: &User, name: &str);
+            },
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "Duplicate attribute `#[query]`",
+                "Argument `name` has no restix attribute",
+                "`#[body]` arguments are not allowed in `#[get]` methods",
+                "Endpoint url placeholder `{id}` has no matching `#[path]` argument",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_path_args() {
+        let messages = analyze(
+            Method::Get,
+            "/user/{user_id}",
+            syn::parse_quote! {
+                async fn user(&self, #[path("user_id")] id: i64, #[path] group: &str);
+            },
+        );
+        assert_eq!(
+            messages,
+            vec!["`#[path]` argument `group` is not used in the endpoint url"]
+        );
+    }
+
+    #[test]
+    fn test_analyze_multiple_bodies() {
+        let messages = analyze(
+            Method::Post,
+            "/send",
+            syn::parse_quote! {
+                async fn send(&self, #[body] a: &A, #[body] b: &B, #[header] c: &str);
+            },
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "Unsupported argument attribute `#[header]`",
+                "Only one `#[body]` argument is allowed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_url_placeholders() {
+        assert_eq!(
+            url_placeholders("/bot{token}/send/{id}"),
+            vec!["token", "id"]
+        );
+        assert_eq!(url_placeholders("/{{escaped}}/{id}"), vec!["id"]);
+        assert!(url_placeholders("/search").is_empty());
+    }
+
+    #[test]
+    fn test_parse_with_headers_return_type() {
+        let ir = parse_method(syn::parse_quote! {
+            #[headers("ETag", "x-ratelimit-remaining")]
+            async fn users(&self) -> restix::WithHeaders<Vec<User>>;
+        });
+        let ReturnTypeIR::WithHeaders(_, body) = &ir.return_type else {
+            panic!("Expected WithHeaders return type");
+        };
+        assert_eq!(body.to_token_stream().to_string(), "Vec < User >");
+        assert!(matches!(&ir.attrs[..], [AttrIR::Headers(it)] if it.names.len() == 2));
+    }
+
+    #[test]
+    fn test_parse_invalid_headers() {
+        let method: ImplItemMethod = syn::parse_quote! {
+            #[headers("rate limit")]
+            async fn users(&self) -> WithHeaders<Vec<User>>;
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+        let method: ImplItemMethod = syn::parse_quote! {
+            #[headers()]
+            async fn users(&self) -> WithHeaders<Vec<User>>;
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_headers_without_with_headers() {
+        let messages = analyze(
+            Method::Get,
+            "/users",
+            syn::parse_quote! {
+                #[headers("etag")]
+                async fn users(&self) -> Vec<User>;
+            },
+        );
+        assert_eq!(
+            messages,
+            vec!["`#[headers]` attribute requires `WithHeaders<T>` return type"]
+        );
+    }
+
+    #[test]
+    fn test_parse_raw_body() {
+        let ir = parse_method(syn::parse_quote! {
+            async fn upload(&self, #[raw_body(content_type = "text/xml")] xml: String);
+        });
+        let Some((name, ArgKindIR::RawBody(Some(content_type)))) =
+            ir.args.iter().find_map(ArgIR::as_body)
+        else {
+            panic!("Expected raw body with content type");
+        };
+        assert_eq!(name, "xml");
+        assert_eq!(content_type.value(), "text/xml");
+
+        let ir = parse_method(syn::parse_quote! {
+            async fn upload(&self, #[raw_body] bytes: Vec<u8>);
+        });
+        assert!(matches!(
+            ir.args.iter().find_map(ArgIR::as_body),
+            Some((_, ArgKindIR::RawBody(None)))
+        ));
+    }
+
+    #[test]
+    fn test_parse_content_type_of_json_body() {
+        let method: ImplItemMethod = syn::parse_quote! {
+            async fn send(&self, #[body(content_type = "text/plain")] message: &Message);
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_body_and_raw_body() {
+        let messages = analyze(
+            Method::Get,
+            "/send",
+            syn::parse_quote! {
+                async fn send(&self, #[body] a: &A, #[raw_body] b: String);
+            },
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "`#[body]` arguments are not allowed in `#[get]` methods",
+                "`#[raw_body]` arguments are not allowed in `#[get]` methods",
+                "Only one `#[body]` or `#[raw_body]` argument is allowed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_form_and_multipart() {
+        let ir = parse_method(syn::parse_quote! {
+            async fn login(&self, #[form] credentials: &Credentials);
+        });
+        assert!(matches!(
+            ir.args.iter().find_map(ArgIR::as_body),
+            Some((_, ArgKindIR::Form))
+        ));
+        let ir = parse_method(syn::parse_quote! {
+            async fn upload(&self, #[path] id: i64, #[multipart] form: restix::Multipart);
+        });
+        assert!(matches!(
+            ir.args.iter().find_map(ArgIR::as_body),
+            Some((name, ArgKindIR::Multipart)) if name == "form"
+        ));
+        let method: ImplItemMethod = syn::parse_quote! {
+            async fn login(&self, #[form("credentials")] credentials: &Credentials);
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+    }
+
+    #[test]
+    fn test_analyze_form_and_multipart() {
+        let messages = analyze(
+            Method::Get,
+            "/upload",
+            syn::parse_quote! {
+                async fn upload(&self, #[multipart] form: Multipart, #[form] params: &Params);
+            },
+        );
+        assert_eq!(
+            messages,
+            vec![
+                "`#[multipart]` arguments are not allowed in `#[get]` methods",
+                "`#[form]` arguments are not allowed in `#[get]` methods",
+                "Only one `#[multipart]` or `#[form]` argument is allowed",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_path_with_style() {
+        let method: ImplItemMethod = syn::parse_quote! {
+            async fn user(&self, #[path(style = "comma")] id: i64);
+        };
+        assert!(syn::parse2::<MethodIR>(method.to_token_stream()).is_err());
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let ir = parse_method(syn::parse_quote! {
+            #[cache(ttl = "60s", key = "url")]
+            async fn search(&self, #[query] q: &str) -> Vec<String>;
+        });
+        assert!(matches!(
+            &ir.attrs[..],
+            [AttrIR::Cache(it)] if it.ttl_ms == 60_000 && it.max_entries == DEFAULT_CACHE_MAX_ENTRIES
+        ));
+
+        let ir = parse_method(syn::parse_quote! {
+            #[cache(ttl = "500ms", max_entries = 10)]
+            async fn search(&self, #[query] q: &str) -> Vec<String>;
+        });
+        assert!(matches!(
+            &ir.attrs[..],
+            [AttrIR::Cache(it)] if it.ttl_ms == 500 && it.max_entries == 10
+        ));
+    }
+
+    #[test]
+    fn test_parse_invalid_cache() {
+        for method in [
+            quote! {
+                #[cache(key = "url")]
+                async fn search(&self) -> Vec<String>;
+            },
+            quote! {
+                #[cache(ttl = "60")]
+                async fn search(&self) -> Vec<String>;
+            },
+            quote! {
+                #[cache(ttl = "0s")]
+                async fn search(&self) -> Vec<String>;
+            },
+            quote! {
+                #[cache(ttl = "1d")]
+                async fn search(&self) -> Vec<String>;
+            },
+            quote! {
+                #[cache(ttl = "60s", key = "body")]
+                async fn search(&self) -> Vec<String>;
+            },
+        ] {
+            assert!(syn::parse2::<MethodIR>(method).is_err());
+        }
+    }
+
+    #[test]
+    fn test_analyze_cache_on_post() {
+        let messages = analyze(
+            Method::Post,
+            "/users",
+            syn::parse_quote! {
+                #[cache(ttl = "60s")]
+                async fn users(&self) -> Vec<User>;
+            },
+        );
+        assert_eq!(
+            messages,
+            vec!["`#[cache]` attribute is allowed only in `#[get]` methods"]
+        );
+    }
+}
@@ -68,6 +68,32 @@ use proc_macro_error::proc_macro_error;
 ///     fn set_webhook(&self, #[path] access_token: &str, #[query] url: &str);
 /// }
 /// ```
+///
+/// ## `error` field of `#[api]` macro
+///
+/// Generated methods return `reqwest::Result<T>` by default. Specify the error type
+/// to return `Result<T, MyError>`, if `MyError` implements `From<restix::Error>`:
+/// ```no_run
+/// #[api(base_url = "https://api.telegram.org", error = "CommonError")]
+/// pub trait TelegramApi {
+///     #[get("/bot{access_token}/setWebhook")]
+///     fn set_webhook(&self, #[path] access_token: &str, #[query] url: &str);
+/// }
+/// ```
+///
+/// ## `client_trait` field of `#[api]` macro
+///
+/// Generate the trait with the same methods, implemented by the struct, so the code
+/// using the Api can be tested with stubs. The trait is object safe:
+/// ```no_run
+/// #[api(base_url = "https://api.telegram.org", client_trait = "TelegramClient")]
+/// pub trait TelegramApi {
+///     #[get("/bot{access_token}/setWebhook")]
+///     fn set_webhook(&self, #[path] access_token: &str, #[query] url: &str);
+/// }
+///
+/// let client: Arc<dyn TelegramClient> = Arc::new(TelegramApi::builder().build()?);
+/// ```
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -79,7 +105,8 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// A method marked with this attribute will send a `GET` request to the specified endpoint.
 ///
 /// ## Kinds of arguments
-/// Each argument must have exactly one attribute from the list: `#[path]`, `#[query]`, `#[body]`
+/// Each argument must have exactly one attribute from the list: `#[path]`, `#[query]`, `#[body]`, `#[raw_body]`,
+/// `#[form]`, `#[multipart]`
 ///
 /// ### Attribute `#[path]`
 /// The path part of the endpoint to send the request to.
@@ -115,6 +142,17 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// #[get("/search")]
 /// async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
 /// ```
+/// #### Collections:
+/// Arguments of types `Vec<T>` and `&[T]` are expanded according to the `style` property.
+/// With default style `"repeat"` the request is sent to `https://.../users?id=1&id=2`,
+/// with style `"comma"` the request is sent to `https://.../users?id=1,2`.
+/// ```no_run
+/// #[get("/users")]
+/// async fn users(&self, #[query("id")] ids: &[i64]) -> Vec<User>;
+///
+/// #[get("/users")]
+/// async fn users_joined(&self, #[query("id", style = "comma")] ids: &[i64]) -> Vec<User>;
+/// ```
 ///
 /// ### Attribute `#[body]`
 /// There can be only one argument with this attribute, and it cannot be optional.
@@ -125,6 +163,34 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// async fn send_message(&self, #[body] message: &Message) -> Update;
 /// ```
 ///
+/// ### Attribute `#[raw_body]`
+/// The argument value is sent as is, without serialization, e.g. plain text or pre-serialized XML.
+/// The argument type must be convertible into the request body: `String`, `Vec<u8>`, `Bytes`, etc.
+/// Optional property `content_type` sets the `Content-Type` header of the request.
+/// There can be only one body argument: `#[body]`, `#[raw_body]`, `#[form]` or `#[multipart]`.
+/// #### Example:
+/// ```no_run
+/// #[post("/import")]
+/// async fn import(&self, #[raw_body(content_type = "text/xml")] xml: String);
+/// ```
+///
+/// ### Attribute `#[form]`
+/// The argument value is sent as `application/x-www-form-urlencoded` body.
+/// The argument type must implement `serde::Serialize`.
+/// #### Example:
+/// ```no_run
+/// #[post("/login")]
+/// async fn login(&self, #[form] credentials: &Credentials) -> Session;
+/// ```
+///
+/// ### Attribute `#[multipart]`
+/// The argument of type `restix::Multipart` is sent as `multipart/form-data` body, e.g. for file uploads.
+/// #### Example:
+/// ```no_run
+/// #[post("/sendDocument")]
+/// async fn send_document(&self, #[multipart] form: Multipart) -> Message;
+/// ```
+///
 /// ### Return type
 /// Leave the return type of the method empty so that in the generated implementation the return type
 /// is `Result<Response>` from the Http client being used. For example, if the `"reqwest"` feature is enabled,
@@ -134,6 +200,19 @@ pub fn api(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// if you want to get `Result<T>` as return type in generated implementation. Feature `"json"` should be
 /// enabled to make this work.
 ///
+/// Use `restix::WithHeaders<T>` as return type to get the response headers along with the body,
+/// e.g. for `ETag` or rate-limit info. All headers are captured by default, the method attribute
+/// `#[headers("etag", ...)]` captures only the listed ones.
+///
+/// ### Response cache
+/// The method attribute `#[cache(ttl = "60s", key = "url", max_entries = 100)]` caches the successful
+/// responses in memory by the request url. Only `ttl` is required, `max_entries` is 1000 by default.
+/// ```no_run
+/// #[get("/search")]
+/// #[cache(ttl = "60s")]
+/// async fn search(&self, #[query] q: &str) -> Vec<String>;
+/// ```
+///
 /// #### Example
 /// ```no_run
 /// #[api]
@@ -168,7 +247,8 @@ pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// A method marked with this attribute will send a `POST` request to the specified endpoint.
 ///
 /// ## Kinds of arguments
-/// Each argument must have exactly one attribute from the list: `#[path]`, `#[query]`, `#[body]`
+/// Each argument must have exactly one attribute from the list: `#[path]`, `#[query]`, `#[body]`, `#[raw_body]`,
+/// `#[form]`, `#[multipart]`
 ///
 /// ### Attribute `#[path]`
 /// The path part of the endpoint to send the request to.
@@ -204,6 +284,17 @@ pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// #[get("/search")]
 /// async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
 /// ```
+/// #### Collections:
+/// Arguments of types `Vec<T>` and `&[T]` are expanded according to the `style` property.
+/// With default style `"repeat"` the request is sent to `https://.../users?id=1&id=2`,
+/// with style `"comma"` the request is sent to `https://.../users?id=1,2`.
+/// ```no_run
+/// #[get("/users")]
+/// async fn users(&self, #[query("id")] ids: &[i64]) -> Vec<User>;
+///
+/// #[get("/users")]
+/// async fn users_joined(&self, #[query("id", style = "comma")] ids: &[i64]) -> Vec<User>;
+/// ```
 ///
 /// ### Attribute `#[body]`
 /// There can be only one argument with this attribute, and it cannot be optional.
@@ -214,6 +305,34 @@ pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// async fn send_message(&self, #[body] message: &Message) -> Update;
 /// ```
 ///
+/// ### Attribute `#[raw_body]`
+/// The argument value is sent as is, without serialization, e.g. plain text or pre-serialized XML.
+/// The argument type must be convertible into the request body: `String`, `Vec<u8>`, `Bytes`, etc.
+/// Optional property `content_type` sets the `Content-Type` header of the request.
+/// There can be only one body argument: `#[body]`, `#[raw_body]`, `#[form]` or `#[multipart]`.
+/// #### Example:
+/// ```no_run
+/// #[post("/import")]
+/// async fn import(&self, #[raw_body(content_type = "text/xml")] xml: String);
+/// ```
+///
+/// ### Attribute `#[form]`
+/// The argument value is sent as `application/x-www-form-urlencoded` body.
+/// The argument type must implement `serde::Serialize`.
+/// #### Example:
+/// ```no_run
+/// #[post("/login")]
+/// async fn login(&self, #[form] credentials: &Credentials) -> Session;
+/// ```
+///
+/// ### Attribute `#[multipart]`
+/// The argument of type `restix::Multipart` is sent as `multipart/form-data` body, e.g. for file uploads.
+/// #### Example:
+/// ```no_run
+/// #[post("/sendDocument")]
+/// async fn send_document(&self, #[multipart] form: Multipart) -> Message;
+/// ```
+///
 /// ### Return type
 /// Leave the return type of the method empty so that in the generated implementation the return type
 /// is `Result<Response>` from the Http client being used. For example, if the `"reqwest"` feature is enabled,
@@ -223,6 +342,10 @@ pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// if you want to get `Result<T>` as return type in generated implementation. Feature `"json"` should be
 /// enabled to make this work.
 ///
+/// Use `restix::WithHeaders<T>` as return type to get the response headers along with the body,
+/// e.g. for `ETag` or rate-limit info. All headers are captured by default, the method attribute
+/// `#[headers("etag", ...)]` captures only the listed ones.
+///
 /// #### Example
 /// ```no_run
 /// #[api]
@@ -251,3 +374,51 @@ pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
 pub fn post(attr: TokenStream, item: TokenStream) -> TokenStream {
     restix_impl::method(restix_impl::Method::Post, attr.into(), item.into()).into()
 }
+
+/// # Restix `put` attribute macro
+///
+/// A method marked with this attribute will send a `PUT` request to the specified endpoint.
+///
+/// Arguments and return type are the same as for the `#[post]` attribute macro.
+/// #### Example:
+/// ```no_run
+/// #[put("/user/{id}")]
+/// async fn replace_user(&self, #[path] id: i64, #[body] user: &User) -> User;
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn put(attr: TokenStream, item: TokenStream) -> TokenStream {
+    restix_impl::method(restix_impl::Method::Put, attr.into(), item.into()).into()
+}
+
+/// # Restix `delete` attribute macro
+///
+/// A method marked with this attribute will send a `DELETE` request to the specified endpoint.
+///
+/// Arguments and return type are the same as for the `#[post]` attribute macro.
+/// #### Example:
+/// ```no_run
+/// #[delete("/user/{id}")]
+/// async fn delete_user(&self, #[path] id: i64);
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn delete(attr: TokenStream, item: TokenStream) -> TokenStream {
+    restix_impl::method(restix_impl::Method::Delete, attr.into(), item.into()).into()
+}
+
+/// # Restix `patch` attribute macro
+///
+/// A method marked with this attribute will send a `PATCH` request to the specified endpoint.
+///
+/// Arguments and return type are the same as for the `#[post]` attribute macro.
+/// #### Example:
+/// ```no_run
+/// #[patch("/user/{id}")]
+/// async fn rename_user(&self, #[path] id: i64, #[query] name: &str) -> User;
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn patch(attr: TokenStream, item: TokenStream) -> TokenStream {
+    restix_impl::method(restix_impl::Method::Patch, attr.into(), item.into()).into()
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{Interceptor, OnTimings};
+
+/// In-memory cache of the successful responses of the Api method marked with
+/// `#[cache(ttl = "60s")]`. Responses are cached by the full request url with the query.
+///
+/// Each method has its own cache, shared by all instances of the Api.
+pub struct ResponseCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<HashMap<String, CachedResponse>>,
+    /// Order of the insertions, the oldest entry is evicted first
+    insertions: AtomicU64,
+}
+
+struct CachedResponse {
+    status: reqwest::StatusCode,
+    headers: reqwest::header::HeaderMap,
+    body: Vec<u8>,
+    stored_at: Instant,
+    insertion: u64,
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> reqwest::Response {
+        let mut response = http::Response::new(self.body.to_owned());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.to_owned();
+        reqwest::Response::from(response)
+    }
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            entries: Mutex::new(HashMap::new()),
+            insertions: AtomicU64::new(0),
+        }
+    }
+
+    /// Fresh cached response for the key, if any
+    pub fn get(&self, key: &str) -> Option<reqwest::Response> {
+        let entries = self.entries.lock().unwrap();
+        entries
+            .get(key)
+            .filter(|entry| entry.stored_at.elapsed() < self.ttl)
+            .map(CachedResponse::to_response)
+    }
+
+    /// Read the body of the successful response and remember it. The response is rebuilt
+    /// from the read body, so it can be returned to the caller as is.
+    pub async fn store(
+        &self,
+        key: String,
+        response: reqwest::Response,
+    ) -> reqwest::Result<reqwest::Response> {
+        if !response.status().is_success() || self.max_entries == 0 {
+            return Ok(response);
+        }
+        let entry = CachedResponse {
+            status: response.status(),
+            headers: response.headers().to_owned(),
+            body: response.bytes().await?.to_vec(),
+            stored_at: Instant::now(),
+            insertion: self.insertions.fetch_add(1, Ordering::Relaxed),
+        };
+        let response = entry.to_response();
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, entry| entry.stored_at.elapsed() < self.ttl);
+        }
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.insertion)
+                .map(|(key, _)| key.to_owned());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, entry);
+        Ok(response)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The same as [crate::__execute], but the fresh cached response is returned without the call.
+/// Used by the generated Api methods marked with `#[cache(...)]`.
+#[doc(hidden)]
+pub async fn __execute_cached(
+    cache: &ResponseCache,
+    client: &reqwest::Client,
+    interceptor: Option<&Arc<dyn Interceptor>>,
+    on_timings: Option<&OnTimings>,
+    request: reqwest::Request,
+) -> reqwest::Result<reqwest::Response> {
+    let key = request.url().to_string();
+    if let Some(response) = cache.get(&key) {
+        return Ok(response);
+    }
+    let response = crate::__execute(client, interceptor, on_timings, request).await?;
+    cache.store(key, response).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ResponseCache;
+
+    fn response(body: &'static str) -> reqwest::Response {
+        reqwest::Response::from(http::Response::new(body))
+    }
+
+    #[tokio::test]
+    async fn fresh_response_is_returned_from_cache() {
+        let cache = ResponseCache::new(Duration::from_secs(60), 10);
+        assert!(cache.get("/search?q=ap").is_none());
+        let stored = cache
+            .store("/search?q=ap".to_owned(), response("apple"))
+            .await
+            .unwrap();
+        assert_eq!("apple", stored.text().await.unwrap());
+        let cached = cache.get("/search?q=ap").unwrap();
+        assert_eq!("apple", cached.text().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn expired_response_is_not_returned() {
+        let cache = ResponseCache::new(Duration::ZERO, 10);
+        cache
+            .store("/search?q=ap".to_owned(), response("apple"))
+            .await
+            .unwrap();
+        assert!(cache.get("/search?q=ap").is_none());
+    }
+
+    #[tokio::test]
+    async fn oldest_response_is_evicted() {
+        let cache = ResponseCache::new(Duration::from_secs(60), 2);
+        for key in ["a", "b", "c"] {
+            cache.store(key.to_owned(), response(key)).await.unwrap();
+        }
+        assert_eq!(2, cache.len());
+        assert!(cache.get("a").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let cache = ResponseCache::new(Duration::from_secs(60), 10);
+        let mut error = http::Response::new("oops");
+        *error.status_mut() = http::StatusCode::BAD_GATEWAY;
+        cache
+            .store("a".to_owned(), reqwest::Response::from(error))
+            .await
+            .unwrap();
+        assert!(cache.is_empty());
+    }
+}
@@ -3,6 +3,10 @@
 pub use restix_impl::*;
 pub use restix_macro::*;
 
+/// Used by the client traits generated with `#[api(client_trait = "...")]`,
+/// stub implementations of these traits should be marked with it too
+pub use async_trait::async_trait;
+
 #[cfg(all(not(feature = "reqwest"), feature = "json"))]
 compile_error!(r#"The "reqwest" feature must be enabled if the "json" feature is enabled"#);
 
@@ -11,8 +15,105 @@ compile_error!(
     r#"At least one "reqwest" feature must be enabled in order to use the restix library"#
 );
 
+/// Deserialized response body along with the response headers.
+///
+/// Use it as the method return type to get headers like `ETag` or rate-limit info.
+/// All response headers are captured by default, the method attribute
+/// `#[headers("etag", ...)]` limits them to the listed ones.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct WithHeaders<T> {
+    pub body: T,
+    pub headers: reqwest::header::HeaderMap,
+}
+
+#[cfg(feature = "reqwest")]
+impl<T> WithHeaders<T> {
+    /// Value of the header, `None` if it is missing or is not a valid string
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|it| it.to_str().ok())
+    }
+}
+
+/// Error of the generated Api methods.
+/// Use `#[api(error = "MyError")]` to return `Result<T, MyError>` instead,
+/// where `MyError` implements `From<restix::Error>`.
+#[cfg(feature = "reqwest")]
+pub type Error = reqwest::Error;
+
+#[cfg(feature = "reqwest")]
+mod cache;
+#[cfg(feature = "reqwest")]
+mod multipart;
+#[cfg(feature = "reqwest")]
+mod timings;
+
+#[cfg(feature = "reqwest")]
+pub use cache::{__execute_cached, ResponseCache};
+#[cfg(feature = "reqwest")]
+pub use multipart::Multipart;
+
+#[cfg(feature = "reqwest")]
+pub use timings::{__execute, CallTimings, OnTimings, TimingResolver};
+
+/// Boxed future returned by the [Interceptor]
+#[cfg(feature = "reqwest")]
+pub type BoxFuture<'a, T> = std::pin::Pin<Box<dyn std::future::Future<Output = T> + Send + 'a>>;
+
+/// Hook around every request of the Api instance, e.g. for logging or for recording
+/// and replaying responses in tests. Set it with the `interceptor(...)` method of the builder.
+///
+/// The interceptor either passes the request further with [Next::run],
+/// or answers it by itself without any network calls.
+#[cfg(feature = "reqwest")]
+pub trait Interceptor: Send + Sync {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>>;
+}
+
+/// The rest of the request execution chain, see [Interceptor].
+/// It is `Copy`, so the interceptor can run the same chain several times, e.g. for retries.
+#[cfg(feature = "reqwest")]
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    client: &'a reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl<'a> Next<'a> {
+    #[doc(hidden)]
+    pub fn new(client: &'a reqwest::Client) -> Self {
+        Self { client }
+    }
+
+    /// Send the request with the client of the Api instance
+    pub async fn run(self, request: reqwest::Request) -> reqwest::Result<reqwest::Response> {
+        self.client.execute(request).await
+    }
+}
+
+/// Conversion of method arguments marked with `#[query]` into url query pairs.
+///
+/// Implemented for every `T: Display`, for `Option<T>` (nothing is pushed for `None`),
+/// and for collections `Vec<T>` and `&[T]`.
 pub trait AsQuery<T> {
+    /// Push query pairs for this value. Collections push one pair per element,
+    /// e.g. `?id=1&id=2&id=3`.
     fn push_to_vec<'a>(&self, key: &'a str, vec: &mut std::vec::Vec<(&'a str, String)>);
+
+    /// Push query pairs for this value. Collections push one pair with elements
+    /// joined by `separator`, e.g. `?id=1,2,3`. Empty collections push nothing.
+    fn push_to_vec_joined<'a>(
+        &self,
+        key: &'a str,
+        _separator: &str,
+        vec: &mut std::vec::Vec<(&'a str, String)>,
+    ) {
+        self.push_to_vec(key, vec)
+    }
 }
 
 impl<T> AsQuery<T> for T
@@ -34,3 +135,95 @@ where
         }
     }
 }
+
+impl<T> AsQuery<T> for Vec<T>
+where
+    T: std::fmt::Display,
+{
+    fn push_to_vec<'a>(&self, key: &'a str, vec: &mut std::vec::Vec<(&'a str, String)>) {
+        self.as_slice().push_to_vec(key, vec);
+    }
+
+    fn push_to_vec_joined<'a>(
+        &self,
+        key: &'a str,
+        separator: &str,
+        vec: &mut std::vec::Vec<(&'a str, String)>,
+    ) {
+        self.as_slice().push_to_vec_joined(key, separator, vec);
+    }
+}
+
+impl<T> AsQuery<T> for &[T]
+where
+    T: std::fmt::Display,
+{
+    fn push_to_vec<'a>(&self, key: &'a str, vec: &mut std::vec::Vec<(&'a str, String)>) {
+        for value in self.iter() {
+            value.push_to_vec(key, vec);
+        }
+    }
+
+    fn push_to_vec_joined<'a>(
+        &self,
+        key: &'a str,
+        separator: &str,
+        vec: &mut std::vec::Vec<(&'a str, String)>,
+    ) {
+        if self.is_empty() {
+            return;
+        }
+        let joined = self
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(separator);
+        vec.push((key, joined));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AsQuery;
+
+    #[test]
+    fn test_vec_repeat() {
+        let mut queries = vec![];
+        vec![1, 2, 3].push_to_vec("id", &mut queries);
+        assert_eq!(
+            queries,
+            vec![
+                ("id", "1".to_owned()),
+                ("id", "2".to_owned()),
+                ("id", "3".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slice_comma() {
+        let mut queries = vec![];
+        let ids: &[i64] = &[1, 2, 3];
+        ids.push_to_vec_joined("ids", ",", &mut queries);
+        assert_eq!(queries, vec![("ids", "1,2,3".to_owned())]);
+    }
+
+    #[test]
+    fn test_empty_collection() {
+        let mut queries = vec![];
+        Vec::<i64>::new().push_to_vec("id", &mut queries);
+        Vec::<i64>::new().push_to_vec_joined("ids", ",", &mut queries);
+        assert!(queries.is_empty());
+    }
+
+    #[test]
+    fn test_scalar_ignores_separator() {
+        let mut queries = vec![];
+        "apple".push_to_vec_joined("q", ",", &mut queries);
+        Some(5).push_to_vec_joined("limit", ",", &mut queries);
+        assert_eq!(
+            queries,
+            vec![("q", "apple".to_owned()), ("limit", "5".to_owned())]
+        );
+    }
+}
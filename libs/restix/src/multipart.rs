@@ -0,0 +1,123 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::Display,
+    hash::{BuildHasher, Hasher},
+};
+
+/// Body of the `multipart/form-data` request, pass it to the `#[multipart]` argument.
+///
+/// Parts are written in the order they are added:
+/// ```no_run
+/// let form = Multipart::new()
+///     .text("chat_id", 42)
+///     .file("document", "week.ics", "text/calendar", b"BEGIN:VCALENDAR");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Multipart {
+    boundary: String,
+    body: Vec<u8>,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multipart {
+    /// Form with the random boundary
+    pub fn new() -> Self {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(0);
+        Self::with_boundary(format!("restix-boundary-{:016x}", hasher.finish()))
+    }
+
+    /// Form with the fixed boundary, e.g. for the tests.
+    /// The boundary must not occur in the content of the parts.
+    pub fn with_boundary(boundary: impl Into<String>) -> Self {
+        Self {
+            boundary: boundary.into(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Add the text field
+    pub fn text(mut self, name: &str, value: impl Display) -> Self {
+        self.write_headers(&format!(
+            "Content-Disposition: form-data; name=\"{}\"",
+            escape(name)
+        ));
+        self.body.extend_from_slice(value.to_string().as_bytes());
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// Add the file field, quotes and line breaks in the file name are replaced with `_`
+    pub fn file(mut self, name: &str, file_name: &str, content_type: &str, content: &[u8]) -> Self {
+        self.write_headers(&format!(
+            "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+            Content-Type: {content_type}",
+            escape(name),
+            escape(file_name),
+        ));
+        self.body.extend_from_slice(content);
+        self.body.extend_from_slice(b"\r\n");
+        self
+    }
+
+    /// Value of the `Content-Type` header of the request
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Body of the request with the closing boundary
+    pub fn into_body(mut self) -> Vec<u8> {
+        self.body
+            .extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        self.body
+    }
+
+    fn write_headers(&mut self, headers: &str) {
+        self.body
+            .extend_from_slice(format!("--{}\r\n{headers}\r\n\r\n", self.boundary).as_bytes());
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace(['"', '\r', '\n'], "_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Multipart;
+
+    #[test]
+    fn test_multipart_body() {
+        let form = Multipart::with_boundary("b").text("chat_id", -42).file(
+            "document",
+            "week \"1\".ics",
+            "text/calendar",
+            b"BEGIN:VCALENDAR",
+        );
+        assert_eq!(form.content_type(), "multipart/form-data; boundary=b");
+        assert_eq!(
+            String::from_utf8(form.into_body()).unwrap(),
+            "--b\r\n\
+            Content-Disposition: form-data; name=\"chat_id\"\r\n\r\n\
+            -42\r\n\
+            --b\r\n\
+            Content-Disposition: form-data; name=\"document\"; filename=\"week _1_.ics\"\r\n\
+            Content-Type: text/calendar\r\n\r\n\
+            BEGIN:VCALENDAR\r\n\
+            --b--\r\n"
+        );
+    }
+
+    #[test]
+    fn test_random_boundaries() {
+        assert_ne!(
+            Multipart::new().content_type(),
+            Multipart::new().content_type()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+
+use crate::{Interceptor, Next};
+
+/// Callback with the timings of every call, see the `on_timings(...)` method of the builder
+pub type OnTimings = Arc<dyn Fn(&CallTimings) + Send + Sync>;
+
+/// Timings of the single call of the Api method, including the interceptor.
+///
+/// Reqwest does not expose the connect and TLS handshake timings separately, so they are
+/// included in `first_byte`: compare the calls with the new and the reused connections
+/// to tell the network issues from the slow server.
+#[derive(Debug, Clone)]
+pub struct CallTimings {
+    pub method: reqwest::Method,
+    pub url: reqwest::Url,
+    /// `None` if the call failed without a response
+    pub status: Option<reqwest::StatusCode>,
+    /// Total time of the DNS lookups, `None` if there were no lookups:
+    /// the connection from the pool was reused, or the client has no [TimingResolver]
+    pub dns: Option<Duration>,
+    /// Time from the start of the call to the response headers (or to the failure):
+    /// connect and TLS handshake of the new connection, sending the request, server processing
+    pub first_byte: Duration,
+}
+
+impl CallTimings {
+    /// New connections start with the DNS lookup, if the client has the [TimingResolver]
+    pub fn is_new_connection(&self) -> bool {
+        self.dns.is_some()
+    }
+}
+
+tokio::task_local! {
+    static DNS_LOOKUPS: Arc<Mutex<Option<Duration>>>;
+}
+
+/// DNS resolver, which attributes the lookups to the calls for [CallTimings].
+///
+/// It is installed automatically into the client created by the builder with `on_timings(...)`,
+/// explicitly passed clients need it in [reqwest::ClientBuilder::dns_resolver].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimingResolver;
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        // lookups of the new connections run in the task of the call
+        let lookups = DNS_LOOKUPS.try_with(Arc::clone).ok();
+        Box::pin(async move {
+            let started_at = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .collect::<Vec<_>>();
+            if let Some(lookups) = lookups {
+                *lookups.lock().unwrap().get_or_insert(Duration::ZERO) += started_at.elapsed();
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Send the request through the interceptor, if any, reporting the timings if requested.
+/// Used by the generated Api methods.
+#[doc(hidden)]
+pub async fn __execute(
+    client: &reqwest::Client,
+    interceptor: Option<&Arc<dyn Interceptor>>,
+    on_timings: Option<&OnTimings>,
+    request: reqwest::Request,
+) -> reqwest::Result<reqwest::Response> {
+    let Some(on_timings) = on_timings else {
+        return run(client, interceptor, request).await;
+    };
+    let method = request.method().to_owned();
+    let url = request.url().to_owned();
+    let lookups = Arc::new(Mutex::new(None));
+    let started_at = Instant::now();
+    let response = DNS_LOOKUPS
+        .scope(lookups.clone(), run(client, interceptor, request))
+        .await;
+    let dns = *lookups.lock().unwrap();
+    on_timings(&CallTimings {
+        method,
+        url,
+        status: response.as_ref().ok().map(reqwest::Response::status),
+        dns,
+        first_byte: started_at.elapsed(),
+    });
+    response
+}
+
+async fn run(
+    client: &reqwest::Client,
+    interceptor: Option<&Arc<dyn Interceptor>>,
+    request: reqwest::Request,
+) -> reqwest::Result<reqwest::Response> {
+    match interceptor {
+        Some(interceptor) => interceptor.intercept(request, Next::new(client)).await,
+        None => client.execute(request).await,
+    }
+}
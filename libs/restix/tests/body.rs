@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use restix::{api, post, BoxFuture, Interceptor, Multipart, Next};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct Message {
+    text: String,
+}
+
+#[api(base_url = "http://localhost:1")]
+pub trait ExampleApi {
+    #[post("/send")]
+    async fn send(&self, #[body] message: &Message);
+
+    #[post("/import")]
+    async fn import(&self, #[raw_body(content_type = "text/csv")] csv: String);
+
+    #[post("/upload")]
+    async fn upload(&self, #[raw_body] bytes: Vec<u8>);
+
+    #[post("/login")]
+    async fn login(&self, #[form] message: &Message);
+
+    #[post("/document")]
+    async fn document(&self, #[multipart] form: Multipart);
+}
+
+/// Answers every request without network, remembering the content types and the bodies
+#[derive(Default)]
+struct CapturingInterceptor {
+    requests: Mutex<Vec<(Option<String>, Vec<u8>)>>,
+}
+
+impl Interceptor for CapturingInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let content_type = request
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .map(|it| it.to_str().unwrap().to_owned());
+            let body = request
+                .body()
+                .and_then(|it| it.as_bytes())
+                .unwrap_or_default()
+                .to_vec();
+            self.requests.lock().unwrap().push((content_type, body));
+            Ok(http::Response::builder()
+                .status(200)
+                .body("")
+                .unwrap()
+                .into())
+        })
+    }
+}
+
+#[tokio::test]
+async fn body_is_serialized_and_raw_body_is_sent_as_is() {
+    let interceptor = Arc::new(CapturingInterceptor::default());
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(interceptor.clone())
+        .build()
+        .unwrap();
+    let message = Message {
+        text: "hello".to_owned(),
+    };
+    api.send(&message).await.unwrap();
+    api.import("id,name\n1,mpeix".to_owned()).await.unwrap();
+    api.upload(vec![0, 1, 2]).await.unwrap();
+    assert_eq!(
+        *interceptor.requests.lock().unwrap(),
+        vec![
+            (
+                Some("application/json".to_owned()),
+                br#"{"text":"hello"}"#.to_vec()
+            ),
+            (Some("text/csv".to_owned()), b"id,name\n1,mpeix".to_vec()),
+            (None, vec![0, 1, 2]),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn form_is_urlencoded_and_multipart_has_boundary() {
+    let interceptor = Arc::new(CapturingInterceptor::default());
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(interceptor.clone())
+        .build()
+        .unwrap();
+    let message = Message {
+        text: "hello world".to_owned(),
+    };
+    api.login(&message).await.unwrap();
+    let form = Multipart::with_boundary("b").text("text", "hello");
+    api.document(form).await.unwrap();
+    assert_eq!(
+        *interceptor.requests.lock().unwrap(),
+        vec![
+            (
+                Some("application/x-www-form-urlencoded".to_owned()),
+                b"text=hello+world".to_vec()
+            ),
+            (
+                Some("multipart/form-data; boundary=b".to_owned()),
+                b"--b\r\nContent-Disposition: form-data; name=\"text\"\r\n\r\nhello\r\n--b--\r\n"
+                    .to_vec()
+            ),
+        ]
+    );
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use restix::{api, get};
+
+#[api(base_url = "https://api.example.com")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+}
+
+#[test]
+fn build_with_client_options() {
+    let api = ExampleApi::builder()
+        .timeout(Duration::from_secs(60))
+        .proxy("http://localhost:3128".to_owned())
+        .default_header("User-Agent", "restix")
+        .build();
+    assert!(api.is_ok());
+}
+
+#[test]
+fn build_without_client() {
+    assert!(ExampleApi::builder().build().is_ok());
+}
+
+#[test]
+fn build_with_invalid_header() {
+    let error = ExampleApi::builder()
+        .default_header("User Agent", "restix")
+        .build()
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("invalid header name"));
+}
+
+#[test]
+fn build_with_client_and_client_options() {
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .timeout(Duration::from_secs(60))
+        .build();
+    assert!(api.is_err());
+}
@@ -0,0 +1,75 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use restix::{api, get, BoxFuture, Interceptor, Next};
+
+#[api(base_url = "http://localhost:1")]
+pub trait CachedApi {
+    #[get("/search")]
+    #[cache(ttl = "60s", key = "url", max_entries = 10)]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+
+    #[get("/expired")]
+    #[cache(ttl = "1ms")]
+    async fn expired(&self) -> Vec<String>;
+
+    #[get("/uncached")]
+    async fn uncached(&self) -> Vec<String>;
+}
+
+/// Counts the calls and answers every request with its url without network
+#[derive(Default)]
+struct CountingInterceptor(AtomicUsize);
+
+impl Interceptor for CountingInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            let body = format!(r#"["{}"]"#, request.url());
+            let response = http::Response::builder().status(200).body(body).unwrap();
+            Ok(reqwest::Response::from(response))
+        })
+    }
+}
+
+fn cached_api(interceptor: Arc<CountingInterceptor>) -> CachedApi {
+    CachedApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(interceptor)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn responses_are_cached_by_url() {
+    let interceptor = Arc::new(CountingInterceptor::default());
+    let api = cached_api(interceptor.clone());
+
+    let first = api.search("apple").await.unwrap();
+    assert_eq!(first, api.search("apple").await.unwrap());
+    assert_eq!(1, interceptor.0.load(Ordering::SeqCst));
+
+    api.search("banana").await.unwrap();
+    assert_eq!(2, interceptor.0.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn expired_and_uncached_responses_are_requested_again() {
+    let interceptor = Arc::new(CountingInterceptor::default());
+    let api = cached_api(interceptor.clone());
+
+    api.expired().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+    api.expired().await.unwrap();
+    assert_eq!(2, interceptor.0.load(Ordering::SeqCst));
+
+    api.uncached().await.unwrap();
+    api.uncached().await.unwrap();
+    assert_eq!(4, interceptor.0.load(Ordering::SeqCst));
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use restix::{api, get, post, BoxFuture, Interceptor, Next};
+
+#[api(base_url = "http://localhost:1", client_trait = "ExampleClient")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+
+    #[post("/user/{id}/publish")]
+    async fn publish(&self, #[path] id: i32);
+}
+
+/// Code under test, which does not know if the api is real
+async fn first_result(client: &dyn ExampleClient, query: &str) -> Option<String> {
+    client.search(query).await.ok()?.into_iter().next()
+}
+
+struct StubClient;
+
+#[restix::async_trait]
+impl ExampleClient for StubClient {
+    async fn search(&self, query: &str) -> reqwest::Result<Vec<String>> {
+        Ok(vec![format!("{query}ple")])
+    }
+
+    async fn publish(&self, _id: i32) -> reqwest::Result<reqwest::Response> {
+        Ok(reqwest::Response::from(http::Response::new("")))
+    }
+}
+
+/// Answers every request with the same body without network
+struct CannedInterceptor(&'static str);
+
+impl Interceptor for CannedInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        _request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let response = http::Response::builder().status(200).body(self.0).unwrap();
+            Ok(reqwest::Response::from(response))
+        })
+    }
+}
+
+#[tokio::test]
+async fn stub_implements_client_trait() {
+    assert_eq!(
+        Some("apple".to_owned()),
+        first_result(&StubClient, "ap").await
+    );
+}
+
+#[tokio::test]
+async fn api_implements_client_trait() {
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(Arc::new(CannedInterceptor(r#"["apricot"]"#)))
+        .build()
+        .unwrap();
+    assert_eq!(Some("apricot".to_owned()), first_result(&api, "ap").await);
+
+    let client: Arc<dyn ExampleClient> = Arc::new(api);
+    assert!(client.publish(1).await.is_ok());
+}
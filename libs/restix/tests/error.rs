@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use restix::{api, get, BoxFuture, Interceptor, Next};
+
+#[derive(Debug)]
+enum ExampleError {
+    Decode,
+    Other,
+}
+
+impl From<restix::Error> for ExampleError {
+    fn from(value: restix::Error) -> Self {
+        if value.is_decode() {
+            Self::Decode
+        } else {
+            Self::Other
+        }
+    }
+}
+
+#[api(base_url = "http://localhost:1", error = "ExampleError")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+
+    #[get("/health")]
+    async fn health(&self);
+}
+
+/// Answers every request with the same body without network
+struct CannedInterceptor(&'static str);
+
+impl Interceptor for CannedInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        _request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let response = http::Response::builder().status(200).body(self.0).unwrap();
+            Ok(reqwest::Response::from(response))
+        })
+    }
+}
+
+fn example_api(body: &'static str) -> ExampleApi {
+    ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(Arc::new(CannedInterceptor(body)))
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn methods_return_custom_error() {
+    let results: Result<Vec<String>, ExampleError> = example_api(r#"["apple"]"#).search("ap").await;
+    assert_eq!(results.unwrap(), vec!["apple"]);
+
+    let results = example_api("not a json").search("ap").await;
+    assert!(matches!(results, Err(ExampleError::Decode)));
+
+    let response: Result<reqwest::Response, ExampleError> = example_api("").health().await;
+    assert!(response.is_ok());
+}
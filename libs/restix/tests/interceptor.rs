@@ -0,0 +1,48 @@
+use std::sync::{Arc, Mutex};
+
+use restix::{api, get, BoxFuture, Interceptor, Next};
+
+#[api(base_url = "http://localhost:1")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+}
+
+/// Answers every request without network, remembering the requested urls
+#[derive(Default)]
+struct CannedInterceptor {
+    urls: Mutex<Vec<String>>,
+}
+
+impl Interceptor for CannedInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            self.urls.lock().unwrap().push(request.url().to_string());
+            let response = http::Response::builder()
+                .status(200)
+                .body(r#"["apple", "apricot"]"#)
+                .unwrap();
+            Ok(reqwest::Response::from(response))
+        })
+    }
+}
+
+#[tokio::test]
+async fn interceptor_answers_request() {
+    let interceptor = Arc::new(CannedInterceptor::default());
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(interceptor.clone())
+        .build()
+        .unwrap();
+    let results = api.search("ap").await.unwrap();
+    assert_eq!(results, vec!["apple", "apricot"]);
+    assert_eq!(
+        *interceptor.urls.lock().unwrap(),
+        vec!["http://localhost:1/search?q=ap"]
+    );
+}
@@ -0,0 +1,96 @@
+use std::sync::{Arc, Mutex};
+
+use restix::{api, delete, patch, put, BoxFuture, Interceptor, Next};
+use serde::Serialize;
+
+#[derive(Serialize)]
+pub struct User {
+    name: String,
+}
+
+#[api(base_url = "http://localhost:1")]
+pub trait UsersApi {
+    #[put("/user/{id}")]
+    async fn replace(&self, #[path] id: i64, #[body] user: &User);
+
+    #[delete("/user/{id}")]
+    async fn delete(&self, #[path] id: i64, #[query] soft: bool);
+
+    #[patch("/user/{id}")]
+    async fn rename(&self, #[path] id: i64, #[raw_body] name: String);
+}
+
+/// Answers every request without network, remembering the methods, the urls and the bodies
+#[derive(Default)]
+struct CapturingInterceptor {
+    requests: Mutex<Vec<(String, String, Vec<u8>)>>,
+}
+
+impl Interceptor for CapturingInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let body = request
+                .body()
+                .and_then(|it| it.as_bytes())
+                .unwrap_or_default()
+                .to_vec();
+            self.requests.lock().unwrap().push((
+                request.method().to_string(),
+                request.url().to_string(),
+                body,
+            ));
+            Ok(http::Response::builder()
+                .status(200)
+                .body("")
+                .unwrap()
+                .into())
+        })
+    }
+}
+
+#[tokio::test]
+async fn put_delete_and_patch_requests_are_sent() {
+    let interceptor = Arc::new(CapturingInterceptor::default());
+    let api = UsersApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(interceptor.clone())
+        .build()
+        .unwrap();
+
+    api.replace(
+        1,
+        &User {
+            name: "Ivan".to_owned(),
+        },
+    )
+    .await
+    .unwrap();
+    api.delete(2, true).await.unwrap();
+    api.rename(3, "Petr".to_owned()).await.unwrap();
+
+    let requests = interceptor.requests.lock().unwrap();
+    assert_eq!(
+        *requests,
+        vec![
+            (
+                "PUT".to_owned(),
+                "http://localhost:1/user/1".to_owned(),
+                br#"{"name":"Ivan"}"#.to_vec(),
+            ),
+            (
+                "DELETE".to_owned(),
+                "http://localhost:1/user/2?soft=true".to_owned(),
+                vec![],
+            ),
+            (
+                "PATCH".to_owned(),
+                "http://localhost:1/user/3".to_owned(),
+                b"Petr".to_vec(),
+            ),
+        ]
+    );
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+use restix::{api, get, BoxFuture, CallTimings, Interceptor, Next};
+
+#[api(base_url = "http://localhost:1")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+}
+
+/// Answers every request without network
+struct CannedInterceptor;
+
+impl Interceptor for CannedInterceptor {
+    fn intercept<'a>(
+        &'a self,
+        _request: reqwest::Request,
+        _next: Next<'a>,
+    ) -> BoxFuture<'a, reqwest::Result<reqwest::Response>> {
+        Box::pin(async move {
+            let response = http::Response::builder()
+                .status(200)
+                .body(r#"["apple"]"#)
+                .unwrap();
+            Ok(reqwest::Response::from(response))
+        })
+    }
+}
+
+fn collect_timings() -> (Arc<Mutex<Vec<CallTimings>>>, restix::OnTimings) {
+    let timings = Arc::new(Mutex::new(Vec::new()));
+    let collected = timings.clone();
+    let on_timings = Arc::new(move |it: &CallTimings| collected.lock().unwrap().push(it.clone()));
+    (timings, on_timings)
+}
+
+#[tokio::test]
+async fn timings_are_reported_for_intercepted_calls() {
+    let (timings, on_timings) = collect_timings();
+    let api = ExampleApi::builder()
+        .client(reqwest::Client::new())
+        .interceptor(Arc::new(CannedInterceptor))
+        .on_timings(on_timings)
+        .build()
+        .unwrap();
+    api.search("ap").await.unwrap();
+
+    let timings = timings.lock().unwrap();
+    assert_eq!(1, timings.len());
+    assert_eq!("http://localhost:1/search?q=ap", timings[0].url.as_str());
+    assert_eq!(Some(reqwest::StatusCode::OK), timings[0].status);
+    assert!(!timings[0].is_new_connection());
+}
+
+#[tokio::test]
+async fn dns_lookup_of_new_connection_is_reported() {
+    let (timings, on_timings) = collect_timings();
+    let api = ExampleApi::builder()
+        .on_timings(on_timings)
+        .build()
+        .unwrap();
+    // nothing listens on the port, but the host is resolved anyway
+    assert!(api.search("ap").await.is_err());
+
+    let timings = timings.lock().unwrap();
+    assert_eq!(1, timings.len());
+    assert_eq!(None, timings[0].status);
+    assert!(timings[0].is_new_connection());
+}
@@ -0,0 +1,9 @@
+use restix::{api, get};
+
+#[api]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[body] query: String) -> Vec<String>;
+}
+
+fn main() {}
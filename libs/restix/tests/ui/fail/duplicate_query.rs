@@ -0,0 +1,9 @@
+use restix::{api, get};
+
+#[api]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] #[query] query: &str) -> Vec<String>;
+}
+
+fn main() {}
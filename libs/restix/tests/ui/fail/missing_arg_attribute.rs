@@ -0,0 +1,9 @@
+use restix::{api, get};
+
+#[api]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, q: &str, #[header] token: &str) -> Vec<String>;
+}
+
+fn main() {}
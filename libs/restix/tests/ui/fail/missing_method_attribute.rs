@@ -0,0 +1,11 @@
+use restix::api;
+
+#[api]
+pub trait ExampleApi {
+    #[restix::get("/search")]
+    async fn search(&self, #[query] q: &str) -> Vec<String>;
+
+    async fn health(&self);
+}
+
+fn main() {}
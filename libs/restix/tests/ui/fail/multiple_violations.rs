@@ -0,0 +1,9 @@
+use restix::{api, post};
+
+#[api]
+pub trait ExampleApi {
+    #[post("/user/{id}/publish")]
+    async fn publish(&self, #[path] #[query] id: i64, #[body] title: String, #[body] text: String);
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use restix::{api, get};
+
+#[api]
+pub trait ExampleApi {
+    #[get("/user/{user_id}")]
+    async fn user(&self, #[path] id: i64);
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use restix::{api, get, post, WithHeaders};
+
+#[api(base_url = "https://api.example.com")]
+pub trait ExampleApi {
+    #[get("/search")]
+    async fn search(&self, #[query("q")] query: &str) -> Vec<String>;
+
+    #[get("/user/{id}/posts")]
+    async fn posts(&self, #[path] id: i64, #[query("tag", style = "comma")] tags: &[String]);
+
+    #[post("/user/{user_id}/publish")]
+    async fn publish(&self, #[path("user_id")] id: i64, #[body] text: String);
+
+    #[post("/user/{user_id}/import")]
+    async fn import(&self, #[path] user_id: i64, #[raw_body(content_type = "text/csv")] csv: String);
+
+    #[get("/users")]
+    #[headers("etag", "X-RateLimit-Remaining")]
+    async fn users(&self) -> WithHeaders<Vec<String>>;
+
+    #[get("/groups")]
+    async fn groups(&self) -> restix::WithHeaders<Vec<String>>;
+}
+
+fn main() {}